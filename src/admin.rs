@@ -0,0 +1,307 @@
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{header, HeaderMap, Method, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::RwLock;
+
+use crate::error::error_response;
+use crate::handler::AppState;
+use crate::reload::reload_config;
+use crate::router::has_bearer_token;
+
+/// Build the admin router. It is served on its own port (`--admin-port`)
+/// so admin endpoints never share a listener with proxied traffic.
+pub fn build_admin_router(state: Arc<RwLock<AppState>>, config_path: PathBuf) -> Router {
+    Router::new()
+        .route("/admin/reload", post(reload_handler))
+        .route("/admin/cache", get(cache_handler))
+        .route("/admin/routes", get(routes_handler))
+        .route("/admin/openapi/coverage", get(coverage_handler))
+        .with_state(AdminState {
+            app: state,
+            config_path: Arc::new(config_path),
+        })
+}
+
+#[derive(Clone)]
+struct AdminState {
+    app: Arc<RwLock<AppState>>,
+    config_path: Arc<PathBuf>,
+}
+
+/// What `POST /admin/reload` reloads
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReloadTarget {
+    /// Re-read and validate the config file, then reset both schema caches
+    #[default]
+    Config,
+    /// Drop compiled JSON schemas so they're read again on next use
+    Schemas,
+    /// Drop parsed OpenAPI specs and operation plans
+    Openapi,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ReloadRequest {
+    #[serde(default)]
+    target: ReloadTarget,
+}
+
+async fn reload_handler(
+    State(admin): State<AdminState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    if let Err(response) = authorize(&admin.app, &headers).await {
+        return response;
+    }
+
+    // An empty body reloads the config, like the file watcher does
+    let request = if body.iter().all(u8::is_ascii_whitespace) {
+        ReloadRequest::default()
+    } else {
+        match serde_json::from_slice::<ReloadRequest>(&body) {
+            Ok(request) => request,
+            Err(e) => {
+                return error_response(
+                    StatusCode::BAD_REQUEST,
+                    &format!("Invalid reload request: {}", e),
+                )
+            }
+        }
+    };
+
+    let result = match request.target {
+        ReloadTarget::Config => reload_config(&admin.app, &admin.config_path).await,
+        ReloadTarget::Schemas | ReloadTarget::Openapi => {
+            let state_guard = admin.app.read().await;
+            if request.target == ReloadTarget::Schemas {
+                state_guard.reset_schema_cache();
+            } else {
+                state_guard.reset_openapi_cache();
+            }
+            Ok(state_guard.routing().config.routes.len())
+        }
+    };
+
+    match result {
+        Ok(routes_loaded) => {
+            tracing::info!(
+                target = ?request.target,
+                routes = routes_loaded,
+                "Reloaded via admin endpoint"
+            );
+            Json(serde_json::json!({
+                "status": "ok",
+                "routes_loaded": routes_loaded,
+            }))
+            .into_response()
+        }
+        Err(e) => {
+            tracing::warn!(
+                target = ?request.target,
+                error = %e,
+                "Admin reload failed; keeping previous config"
+            );
+            error_response(StatusCode::UNPROCESSABLE_ENTITY, &e)
+        }
+    }
+}
+
+/// `GET /admin/cache`: what each cache holds, when it was loaded, and
+/// whether the file has changed since
+async fn cache_handler(State(admin): State<AdminState>, headers: HeaderMap) -> Response {
+    if let Err(response) = authorize(&admin.app, &headers).await {
+        return response;
+    }
+
+    let (schemas, specs, hits, misses) = {
+        let state_guard = admin.app.read().await;
+        let schemas = state_guard.schema_cache.read().snapshot();
+        let specs = state_guard.openapi_cache.read().snapshot();
+        (
+            schemas,
+            specs,
+            state_guard.metrics.schema_cache_hits_total.get(),
+            state_guard.metrics.schema_cache_misses_total.get(),
+        )
+    };
+
+    let schema_entries: Vec<_> = schemas
+        .iter()
+        .map(|entry| {
+            serde_json::json!({
+                "path": entry.path.display().to_string(),
+                "strict_body": entry.strict_body,
+                "draft": entry.draft.map(|draft| draft.name()),
+                "loaded_at": unix_seconds(entry.loaded_at),
+                "current": entry.current,
+            })
+        })
+        .collect();
+    let spec_entries: Vec<_> = specs
+        .iter()
+        .map(|entry| {
+            let operations: Vec<_> = entry
+                .operations
+                .iter()
+                .map(|(method, path)| serde_json::json!({ "method": method, "path": path }))
+                .collect();
+            serde_json::json!({
+                "path": entry.path.display().to_string(),
+                "loaded_at": unix_seconds(entry.loaded_at),
+                "current": entry.current,
+                "version": entry.version,
+                "operations": operations,
+            })
+        })
+        .collect();
+
+    Json(serde_json::json!({
+        "schema_cache": {
+            "entries": schema_entries,
+            "hits": hits as u64,
+            "misses": misses as u64,
+        },
+        "openapi_cache": {
+            "entries": spec_entries,
+        },
+    }))
+    .into_response()
+}
+
+/// `GET /admin/openapi/coverage`: request validation counts for every
+/// operation in the specs the routes use
+async fn coverage_handler(State(admin): State<AdminState>, headers: HeaderMap) -> Response {
+    if let Err(response) = authorize(&admin.app, &headers).await {
+        return response;
+    }
+    Json(coverage_report(&admin.app).await).into_response()
+}
+
+/// The operation coverage report for the specs the live config's routes
+/// and default route use. Operations nothing was validated against are
+/// listed with zero counts.
+pub async fn coverage_report(state: &Arc<RwLock<AppState>>) -> serde_json::Value {
+    let state_guard = state.read().await;
+    let specs: BTreeSet<PathBuf> = state_guard
+        .routing()
+        .config
+        .all_routes()
+        .filter_map(|route| route.openapi_options().map(|openapi| openapi.spec))
+        .collect();
+    let openapi_cache = state_guard.openapi_cache.read();
+    state_guard
+        .metrics
+        .openapi_coverage
+        .report(&specs, &openapi_cache)
+}
+
+async fn routes_handler(State(admin): State<AdminState>, headers: HeaderMap) -> Response {
+    if let Err(response) = authorize(&admin.app, &headers).await {
+        return response;
+    }
+    Json(routes_report(&admin.app).await).into_response()
+}
+
+/// The live config's routes, then the default route marked with
+/// `default_route: true`. OpenAPI routes with a `:param` path also show
+/// the operation they resolve to and which spec parameter each route
+/// parameter binds to; the operation is compiled if it isn't cached yet.
+pub async fn routes_report(state: &Arc<RwLock<AppState>>) -> serde_json::Value {
+    let state_guard = state.read().await;
+    let openapi_cache = state_guard.openapi_cache.read();
+    let routes: Vec<_> = state_guard
+        .routing()
+        .config
+        .all_routes()
+        .map(|route| {
+            let mut entry = serde_json::json!({
+                "method": route.method.to_uppercase(),
+                "path": route.pattern(),
+                "upstream": route.upstream,
+                "tags": route.tags,
+                "description": route.description(),
+            });
+            if route.method.is_empty() {
+                entry["default_route"] = serde_json::json!(true);
+            }
+            if let Some(template) = &route.upstream_path_template {
+                entry["upstream_path_template"] = serde_json::json!(template);
+            }
+            if let Some(schema) = &route.schema {
+                entry["schema"] = serde_json::json!(schema.display().to_string());
+            }
+            if let Some(openapi) = route.openapi_options() {
+                let mut details = serde_json::json!({ "spec": openapi.spec.display().to_string() });
+                let plan = (route.path_regex.is_none() && !route.path.is_empty())
+                    .then(|| {
+                        let template = openapi_cache.operation_path(
+                            &openapi.spec,
+                            openapi.base_path.as_deref(),
+                            &route.path,
+                        )?;
+                        openapi_cache.load_route_operation(
+                            &openapi.spec,
+                            template,
+                            template,
+                            &Method::from_bytes(route.method.to_uppercase().as_bytes())
+                                .unwrap_or_default(),
+                            openapi.operation_id.as_deref(),
+                            openapi.strict_body,
+                        )
+                    })
+                    .transpose();
+                match plan {
+                    Ok(Some(plan)) => {
+                        details["operation"] = serde_json::json!(plan.label());
+                        let params: serde_json::Map<_, _> = plan
+                            .path_param_names
+                            .iter()
+                            .map(|(route_name, spec_name)| {
+                                (route_name.clone(), serde_json::json!(spec_name))
+                            })
+                            .collect();
+                        details["path_params"] = serde_json::Value::Object(params);
+                    }
+                    Ok(None) => {}
+                    Err(e) => details["error"] = serde_json::json!(e.to_string()),
+                }
+                entry["openapi"] = details;
+            }
+            entry
+        })
+        .collect();
+    serde_json::json!({ "routes": routes })
+}
+
+/// Wall-clock time of `instant` as seconds since the Unix epoch
+fn unix_seconds(instant: Instant) -> u64 {
+    SystemTime::now()
+        .checked_sub(instant.elapsed())
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since| since.as_secs())
+}
+
+/// Check the live config's `admin_token`, if one is set
+async fn authorize(state: &Arc<RwLock<AppState>>, headers: &HeaderMap) -> Result<(), Response> {
+    let routing = state.read().await.routing();
+    match &routing.config.global.admin_token {
+        Some(token) if !has_bearer_token(headers, token) => Err((
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            "Unauthorized",
+        )
+            .into_response()),
+        _ => Ok(()),
+    }
+}
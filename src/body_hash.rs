@@ -0,0 +1,76 @@
+use md5::Md5;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
+use std::fmt;
+
+/// Header carrying a body digest as `<algorithm>:<hex>`, both on requests
+/// the gateway forwards and on upstream responses it verifies
+pub const BODY_HASH_HEADER: &str = "x-body-hash";
+
+/// Digest used for `X-Body-Hash`
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum BodyHashAlgorithm {
+    Sha256,
+    Sha512,
+    Md5,
+}
+
+impl BodyHashAlgorithm {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BodyHashAlgorithm::Sha256 => "sha256",
+            BodyHashAlgorithm::Sha512 => "sha512",
+            BodyHashAlgorithm::Md5 => "md5",
+        }
+    }
+
+    /// Lowercase hex digest of `body`
+    pub fn hex_digest(self, body: &[u8]) -> String {
+        match self {
+            BodyHashAlgorithm::Sha256 => hex::encode(Sha256::digest(body)),
+            BodyHashAlgorithm::Sha512 => hex::encode(Sha512::digest(body)),
+            BodyHashAlgorithm::Md5 => hex::encode(Md5::digest(body)),
+        }
+    }
+
+    /// `X-Body-Hash` value for `body`, e.g. `sha256:9f86d0...`
+    pub fn header_value(self, body: &[u8]) -> String {
+        format!("{}:{}", self.as_str(), self.hex_digest(body))
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "sha256" => Some(BodyHashAlgorithm::Sha256),
+            "sha512" => Some(BodyHashAlgorithm::Sha512),
+            "md5" => Some(BodyHashAlgorithm::Md5),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for BodyHashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Check `body` against an `X-Body-Hash` value. The algorithm is taken from
+/// the header, so it doesn't have to match the one used for requests.
+pub fn verify(header_value: &str, body: &[u8]) -> Result<(), String> {
+    let (name, expected) = header_value
+        .trim()
+        .split_once(':')
+        .ok_or_else(|| format!("Malformed X-Body-Hash '{}'", header_value))?;
+    let algorithm = BodyHashAlgorithm::parse(name)
+        .ok_or_else(|| format!("Unsupported X-Body-Hash algorithm '{}'", name))?;
+    let actual = algorithm.hex_digest(body);
+    if actual.eq_ignore_ascii_case(expected) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Upstream response body does not match X-Body-Hash ({} {}, got {})",
+            algorithm, expected, actual
+        ))
+    }
+}
@@ -0,0 +1,194 @@
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Method};
+use jsonschema::JSONSchema;
+use serde_json::Value;
+use std::fs;
+
+use crate::cli::CheckArgs;
+use crate::config::Config;
+use crate::handler::{build_header_object, describe_header_error};
+use crate::openapi::OpenApiCache;
+use crate::schema::SchemaCache;
+
+/// A request to validate offline, as parsed from `check` arguments
+#[derive(Debug, Clone)]
+pub struct CheckRequest {
+    pub method: Method,
+    pub path: String,
+    pub query: Option<String>,
+    pub headers: HeaderMap,
+    pub payload: Vec<u8>,
+}
+
+impl CheckRequest {
+    pub fn from_args(args: &CheckArgs) -> Result<Self, String> {
+        let (method, target) = args
+            .route
+            .trim()
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| format!("Route must look like \"METHOD /path\": '{}'", args.route))?;
+        let method = Method::from_bytes(method.to_uppercase().as_bytes())
+            .map_err(|_| format!("Invalid HTTP method '{}'", method))?;
+
+        // Accept a query string on the route itself; --query wins if both are given
+        let target = target.trim();
+        let (path, inline_query) = match target.split_once('?') {
+            Some((path, query)) => (path, Some(query.to_string())),
+            None => (target, None),
+        };
+
+        let mut headers = HeaderMap::new();
+        for header in &args.headers {
+            let (name, value) = header
+                .split_once(':')
+                .ok_or_else(|| format!("Header must look like \"Name: value\": '{}'", header))?;
+            let name = HeaderName::from_bytes(name.trim().as_bytes())
+                .map_err(|_| format!("Invalid header name '{}'", name.trim()))?;
+            let value = HeaderValue::from_str(value.trim())
+                .map_err(|_| format!("Invalid value for header '{}'", name))?;
+            headers.append(name, value);
+        }
+
+        let payload = match &args.payload {
+            Some(path) => fs::read(path)
+                .map_err(|e| format!("Failed to read payload '{}': {}", path.display(), e))?,
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            method,
+            path: path.to_string(),
+            query: args.query.clone().or(inline_query),
+            headers,
+            payload,
+        })
+    }
+}
+
+/// Validate `request` against the route it resolves to in `config`, the same
+/// way the gateway would.
+///
+/// Returns the validation errors (empty when the request is valid), or `Err`
+/// when the check itself can't run: no matching route, or a schema that
+/// fails to load.
+pub fn check_request(config: &Config, request: &CheckRequest) -> Result<Vec<String>, String> {
+    let route = config
+        .find_route(&request.path, request.method.as_str())
+        .or(config.default_route.as_ref())
+        .ok_or_else(|| format!("No route matches {} {}", request.method, request.path))?;
+
+    let mut errors = Vec::new();
+
+    if let Some(headers_schema) = &route.headers_schema {
+        let schema = SchemaCache::new()
+            .load(headers_schema)
+            .map_err(|e| e.to_string())?;
+        let header_object = build_header_object(&request.headers);
+        let header_errors: Vec<String> = match schema.validate(&header_object) {
+            Ok(()) => Vec::new(),
+            Err(validation_errors) => validation_errors
+                .map(|e| describe_header_error(&e))
+                .collect(),
+        };
+        errors.extend(header_errors);
+    }
+
+    if let Some(openapi) = route.openapi_options() {
+        let cache =
+            OpenApiCache::new().with_case_insensitive_paths(config.global.case_insensitive_paths);
+        let operation_path = cache
+            .operation_path(&openapi.spec, openapi.base_path.as_deref(), &request.path)
+            .map_err(|e| e.to_string())?;
+        let plan = if openapi.strict_body {
+            cache.load_strict_operation(
+                &openapi.spec,
+                operation_path,
+                &request.method,
+                openapi.operation_id.as_deref(),
+            )
+        } else {
+            cache.load_operation(
+                &openapi.spec,
+                operation_path,
+                &request.method,
+                openapi.operation_id.as_deref(),
+            )
+        }
+        .map_err(|e| e.to_string())?;
+
+        let captures = route.path_captures(&request.path, None);
+        if let Err(message) = plan.check_parameters(
+            operation_path,
+            &captures,
+            request.query.as_deref(),
+            &request.headers,
+        ) {
+            errors.push(message);
+        }
+
+        match &plan.schema {
+            Some(_) if request.payload.is_empty() && plan.body_required => errors.push(format!(
+                "OpenAPI request body required for {} {}",
+                plan.method, plan.path_template
+            )),
+            Some(schema) if !request.payload.is_empty() => {
+                errors.extend(validate_payload(schema, &request.payload));
+            }
+            _ => {}
+        }
+    } else if let Some(schema_path) = &route.schema {
+        let cache = SchemaCache::new();
+        let schema = cache
+            .load_with(schema_path, route.schema_options())
+            .map_err(|e| e.to_string())?;
+        if !request.payload.is_empty() {
+            errors.extend(validate_payload(&schema, &request.payload));
+        }
+    }
+
+    Ok(errors)
+}
+
+/// Run the `check` subcommand and return the process exit code
+pub fn run(config: &Config, args: &CheckArgs) -> i32 {
+    let result = CheckRequest::from_args(args).and_then(|request| check_request(config, &request));
+
+    match result {
+        Ok(errors) if errors.is_empty() => {
+            println!("OK {}", args.route);
+            0
+        }
+        Ok(errors) => {
+            println!("FAIL {}", args.route);
+            for error in &errors {
+                println!("  {}", error);
+            }
+            1
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            2
+        }
+    }
+}
+
+/// Validate a JSON payload, reporting each error with its instance path
+fn validate_payload(schema: &JSONSchema, payload: &[u8]) -> Vec<String> {
+    let value: Value = match serde_json::from_slice(payload) {
+        Ok(value) => value,
+        Err(e) => return vec![format!("Invalid JSON: {}", e)],
+    };
+
+    let mut messages = Vec::new();
+    if let Err(errors) = schema.validate(&value) {
+        for error in errors {
+            let instance_path = error.instance_path.to_string();
+            let location = if instance_path.is_empty() {
+                "/"
+            } else {
+                instance_path.as_str()
+            };
+            messages.push(format!("{}: {}", location, error));
+        }
+    }
+    messages
+}
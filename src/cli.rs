@@ -1,5 +1,5 @@
-use clap::Parser;
-use std::path::PathBuf;
+use clap::{Args, Parser, Subcommand};
+use std::path::{Path, PathBuf};
 
 #[derive(Parser, Debug, Clone)]
 #[command(name = "schema-gateway")]
@@ -7,7 +7,13 @@ use std::path::PathBuf;
 #[command(about = "A lightweight schema validation proxy", long_about = None)]
 pub struct Cli {
     /// Path to config file
-    #[arg(short, long, value_name = "FILE", default_value = "config.yml")]
+    #[arg(
+        short,
+        long,
+        value_name = "FILE",
+        default_value = "config.yml",
+        global = true
+    )]
     pub config: PathBuf,
 
     /// Port to listen on
@@ -17,12 +23,90 @@ pub struct Cli {
     /// Validate config and exit
     #[arg(long)]
     pub validate_config: bool,
+
+    /// Poll the config file for changes and reload it automatically
+    #[arg(long)]
+    pub watch: bool,
+
+    /// PEM certificate chain for serving HTTPS (requires --tls-key)
+    #[arg(long, value_name = "FILE")]
+    pub tls_cert: Option<PathBuf>,
+
+    /// PEM private key for --tls-cert
+    #[arg(long, value_name = "FILE")]
+    pub tls_key: Option<PathBuf>,
+
+    /// PEM CA bundle; when set, clients must present a certificate it signed
+    #[arg(long, value_name = "FILE")]
+    pub tls_ca: Option<PathBuf>,
+
+    /// Serve admin endpoints (POST /admin/reload) on this port, bound to
+    /// 127.0.0.1. Disabled when unset.
+    #[arg(long, value_name = "PORT")]
+    pub admin_port: Option<u16>,
+
+    /// Development mode: pretty-print JSON responses and errors, and add
+    /// schema paths and values to validation errors. Not for production.
+    #[arg(long)]
+    pub dev: bool,
+
+    /// Write the process ID to this file once listening, and remove it on
+    /// shutdown. Refuses to start if it names a running process.
+    #[arg(long, value_name = "FILE")]
+    pub pid_file: Option<PathBuf>,
+
+    /// Write the OpenAPI operation coverage report (as served by
+    /// `GET /admin/openapi/coverage`) to this file on shutdown
+    #[arg(long, value_name = "FILE")]
+    pub coverage_report: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Validate a payload against a route's schema without starting the server
+    Check(CheckArgs),
+}
+
+#[derive(Args, Debug, Clone, PartialEq, Eq)]
+pub struct CheckArgs {
+    /// Route to check, as "METHOD /path" (e.g. "POST /api/users")
+    #[arg(long, value_name = "METHOD PATH")]
+    pub route: String,
+
+    /// JSON payload file to validate as the request body
+    #[arg(long, value_name = "FILE")]
+    pub payload: Option<PathBuf>,
+
+    /// Query string for OpenAPI parameter validation (e.g. "limit=10&sort=asc")
+    #[arg(long, value_name = "QUERY")]
+    pub query: Option<String>,
+
+    /// Request header as "Name: value"; repeat for multiple headers
+    #[arg(long = "headers", value_name = "NAME: VALUE")]
+    pub headers: Vec<String>,
 }
 
 impl Cli {
     pub fn parse_args() -> Self {
         Self::parse()
     }
+
+    /// The certificate and key to serve HTTPS with, or `None` for plain HTTP.
+    /// Errors if only one of the pair is given, or `--tls-ca` is given without them.
+    pub fn tls_files(&self) -> Result<Option<(&Path, &Path)>, String> {
+        match (&self.tls_cert, &self.tls_key) {
+            (Some(cert), Some(key)) => Ok(Some((cert, key))),
+            (Some(_), None) => Err("--tls-cert requires --tls-key".to_string()),
+            (None, Some(_)) => Err("--tls-key requires --tls-cert".to_string()),
+            (None, None) if self.tls_ca.is_some() => {
+                Err("--tls-ca requires --tls-cert and --tls-key".to_string())
+            }
+            (None, None) => Ok(None),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -36,6 +120,15 @@ mod tests {
             config: PathBuf::from("config.yml"),
             port: 8080,
             validate_config: false,
+            watch: false,
+            tls_cert: None,
+            tls_key: None,
+            tls_ca: None,
+            admin_port: None,
+            dev: false,
+            pid_file: None,
+            coverage_report: None,
+            command: None,
         };
 
         assert_eq!(cli.config, PathBuf::from("config.yml"));
@@ -1,12 +1,36 @@
-use serde::Deserialize;
+use crate::body_hash::BodyHashAlgorithm;
+use crate::openapi::SpecOperation;
+use crate::rate_limiter::RateLimitConfig;
+use crate::schema::{SchemaDraft, SchemaOptions};
+use axum::http::{HeaderName, HeaderValue, Method};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
 
-#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
 pub struct Config {
+    #[serde(default)]
     pub routes: Vec<Route>,
     #[serde(default)]
     pub global: GlobalConfig,
+    /// OpenAPI specs served under a path prefix, expanded into one route
+    /// per operation by `expand_openapi_mounts`
+    #[serde(default)]
+    pub openapi_mounts: Vec<OpenApiMount>,
+    /// Files whose routes are appended to `routes`, as paths or glob
+    /// patterns relative to the config file. Each holds a YAML list of
+    /// routes.
+    #[serde(default)]
+    pub includes: Vec<String>,
+    /// Where requests no route matches are sent instead of getting a 404.
+    /// Its `path` and `method` are left unset: it matches everything.
+    #[serde(default)]
+    pub default_route: Option<Route>,
 }
 
 impl Config {
@@ -19,7 +43,7 @@ impl Config {
             .map_err(|e| format!("Failed to read config file '{}': {}", path_ref.display(), e))?;
 
         // Parse YAML
-        let config: Config = serde_yaml::from_str(&contents).map_err(|e| {
+        let mut config: Config = serde_yaml::from_str(&contents).map_err(|e| {
             format!(
                 "Failed to parse config file '{}': {}",
                 path_ref.display(),
@@ -27,15 +51,226 @@ impl Config {
             )
         })?;
 
+        let base_dir = path_ref.parent().unwrap_or_else(|| Path::new(""));
+        let base_dir = std::path::absolute(base_dir).map_err(|e| {
+            format!(
+                "Failed to resolve config directory '{}': {}",
+                base_dir.display(),
+                e
+            )
+        })?;
+        config.resolve_paths(&base_dir);
+        config.finish_loading(Some(&base_dir), &path_ref.display().to_string())?;
+
         Ok(config)
     }
 
+    /// Steps shared by `from_file` and `from_str` once the YAML is parsed.
+    /// `base_dir` is the config file's directory, if it came from one.
+    fn finish_loading(&mut self, base_dir: Option<&Path>, source: &str) -> Result<(), String> {
+        self.load_includes(base_dir, source)?;
+        for warning in self.expand_openapi_mounts()? {
+            tracing::warn!("{}", warning);
+        }
+        Ok(())
+    }
+
+    /// The config as YAML that loads back into an equal `Config`. Routes
+    /// from `includes` and `openapi_mounts` are written out with the others,
+    /// so those two lists are left out rather than loaded twice.
+    pub fn to_yaml(&self) -> Result<String, String> {
+        let mut config = self.clone();
+        config.includes.clear();
+        config.openapi_mounts.clear();
+        serde_yaml::to_string(&config).map_err(|e| format!("Failed to serialize config: {}", e))
+    }
+
+    /// Append the routes of every file `includes` names. Relative paths in
+    /// an included file are resolved against that file's directory when
+    /// the config came from a file, and kept as written otherwise. The same
+    /// path and method may not be routed by two different files.
+    fn load_includes(&mut self, base_dir: Option<&Path>, source: &str) -> Result<(), String> {
+        if self.includes.is_empty() {
+            return Ok(());
+        }
+
+        let ignore_case = self.global.case_insensitive_paths;
+        let mut defined_in: HashMap<(String, String), String> = self
+            .routes
+            .iter()
+            .map(|route| (route.index_key(ignore_case), source.to_string()))
+            .collect();
+        let mut loaded = HashSet::new();
+        for pattern in &self.includes {
+            for file in expand_include(base_dir.unwrap_or_else(|| Path::new("")), pattern)? {
+                if !loaded.insert(file.clone()) {
+                    continue;
+                }
+                let file_name = file.display().to_string();
+                for mut route in load_routes_file(&file)? {
+                    if base_dir.is_some() {
+                        route.resolve_paths(file.parent().unwrap_or_else(|| Path::new("")));
+                    }
+                    let key = route.index_key(ignore_case);
+                    if let Some(other) = defined_in.get(&key) {
+                        if *other != file_name {
+                            return Err(format!(
+                                "Duplicate route {} {}: defined in both '{}' and '{}'",
+                                route.method.to_uppercase(),
+                                route.pattern(),
+                                other,
+                                file_name
+                            ));
+                        }
+                    }
+                    defined_in.insert(key, file_name.clone());
+                    self.routes.push(route);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Add a route for every operation of each `openapi_mounts` spec. Routes
+    /// already configured for the same path and method take precedence;
+    /// each skipped operation is reported in the returned warnings.
+    pub fn expand_openapi_mounts(&mut self) -> Result<Vec<String>, String> {
+        let mut warnings = Vec::new();
+        if self.openapi_mounts.is_empty() {
+            return Ok(warnings);
+        }
+
+        let cache = crate::openapi::OpenApiCache::new();
+        let ignore_case = self.global.case_insensitive_paths;
+        let mut taken: HashMap<(String, String), usize> = self
+            .routes
+            .iter()
+            .enumerate()
+            .map(|(idx, route)| (route.index_key(ignore_case), idx))
+            .collect();
+
+        for (mount_idx, mount) in self.openapi_mounts.iter().enumerate() {
+            mount
+                .validate()
+                .map_err(|e| format!("openapi_mounts[{}]: {}", mount_idx, e))?;
+            let operations = cache
+                .list_operations(&mount.spec)
+                .map_err(|e| format!("openapi_mounts[{}]: {}", mount_idx, e))?;
+
+            for operation in operations {
+                let route = mount.route_for(&operation);
+                let key = route.index_key(ignore_case);
+                if let Some(existing) = taken.get(&key) {
+                    warnings.push(format!(
+                        "openapi_mounts[{}]: {} {} is already handled by route {}; skipping the spec operation",
+                        mount_idx, route.method, route.path, existing
+                    ));
+                    continue;
+                }
+                taken.insert(key, self.routes.len());
+                self.routes.push(route);
+            }
+        }
+
+        Ok(warnings)
+    }
+
+    /// Resolve relative schema and OpenAPI paths against `base_dir` (the
+    /// config file's directory) so they don't depend on the working directory.
+    /// Absolute paths are left untouched.
+    pub fn resolve_paths(&mut self, base_dir: &Path) {
+        for route in &mut self.routes {
+            route.resolve_paths(base_dir);
+        }
+        if let Some(route) = self.default_route.as_mut() {
+            route.resolve_paths(base_dir);
+        }
+        for mount in &mut self.openapi_mounts {
+            mount.spec = resolve_relative(base_dir, &mount.spec);
+        }
+        if let Some(tls) = self.global.upstream_tls.as_mut() {
+            tls.resolve_paths(base_dir);
+        }
+    }
+
     pub fn validate(&self) -> Result<(), String> {
         // Check for empty routes
         if self.routes.is_empty() {
             return Err("Config must have at least one route".to_string());
         }
 
+        if self.global.schema_cache_max_entries == Some(0) {
+            return Err("global.schema_cache_max_entries must be greater than 0".to_string());
+        }
+
+        validate_response_header_rules(
+            &self.global.strip_response_headers,
+            &self.global.rename_response_headers,
+        )
+        .map_err(|e| format!("global: {}", e))?;
+
+        validate_header_names(&self.global.remove_request_headers)
+            .map_err(|e| format!("global: {}", e))?;
+        if let Some(auth) = &self.global.upstream_auth {
+            auth.validate().map_err(|e| format!("global: {}", e))?;
+        }
+
+        if self.global.metrics_enabled {
+            validate_metrics_path(&self.global.metrics_path)?;
+        }
+
+        if let Some(tls) = &self.global.upstream_tls {
+            tls.validate().map_err(|e| format!("global: {}", e))?;
+        }
+
+        if let Some(limit) = &self.global.rate_limit {
+            limit.validate().map_err(|e| format!("global: {}", e))?;
+        }
+
+        if self.global.max_request_body_bytes == Some(0) {
+            return Err("global.max_request_body_bytes must be greater than 0".to_string());
+        }
+
+        if self.global.max_response_body_bytes == Some(0) {
+            return Err("global.max_response_body_bytes must be greater than 0".to_string());
+        }
+
+        if self.global.max_response_bytes == Some(0) {
+            return Err("global.max_response_bytes must be greater than 0".to_string());
+        }
+
+        if self.global.max_redirects == 0 {
+            return Err("global.max_redirects must be greater than 0".to_string());
+        }
+
+        if self.global.max_validation_errors == 0 {
+            return Err("global.max_validation_errors must be greater than 0".to_string());
+        }
+
+        if self.global.log_request_body_max_bytes == 0 {
+            return Err("global.log_request_body_max_bytes must be greater than 0".to_string());
+        }
+
+        if self.global.liveness_check_timeout_ms == 0 {
+            return Err("global.liveness_check_timeout_ms must be greater than 0".to_string());
+        }
+
+        if HeaderName::from_bytes(self.global.request_id_header.as_bytes()).is_err() {
+            return Err(format!(
+                "global.request_id_header is not a valid header name: '{}'",
+                self.global.request_id_header
+            ));
+        }
+
+        if let Some(retry) = &self.global.retry {
+            retry.validate().map_err(|e| format!("global: {}", e))?;
+        }
+
+        self.global
+            .http_client
+            .validate()
+            .map_err(|e| format!("global: {}", e))?;
+
         // Validate each route
         for (idx, route) in self.routes.iter().enumerate() {
             if let Err(e) = route.validate() {
@@ -43,48 +278,203 @@ impl Config {
             }
         }
 
+        if let Some(route) = &self.default_route {
+            self.validate_default_route(route)
+                .map_err(|e| format!("default_route: {}", e))?;
+        }
+
+        // Routes are looked up by (path, method); a second route for the same
+        // pair would never be reached
+        let mut seen: HashMap<(String, String), usize> = HashMap::new();
+        for (idx, route) in self.routes.iter().enumerate() {
+            let key = route.index_key(self.global.case_insensitive_paths);
+            if let Some(first) = seen.insert(key, idx) {
+                return Err(format!(
+                    "Route {}: duplicate route {} {} (already defined by route {})",
+                    idx,
+                    route.method.to_uppercase(),
+                    route.pattern(),
+                    first
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_default_route(&self, route: &Route) -> Result<(), String> {
+        if !route.path.is_empty() || route.path_regex.is_some() || !route.method.is_empty() {
+            return Err(
+                "'path', 'path_regex' and 'method' cannot be set: it matches every request"
+                    .to_string(),
+            );
+        }
+        route.validate_handling()?;
+
+        // Rejecting everything no route matches would hide the fallback
+        // behind validation errors for unknown paths
+        let validated = route.schema.is_some() || route.openapi.is_some();
+        if validated && !self.get_effective_config(route).forward_on_error {
+            return Err(
+                "'schema' and 'openapi' require forward_on_error: true on the default route"
+                    .to_string(),
+            );
+        }
         Ok(())
     }
 
+    /// `routes` followed by `default_route`, if set
+    pub fn all_routes(&self) -> impl Iterator<Item = &Route> {
+        self.routes.iter().chain(self.default_route.iter())
+    }
+
     pub fn find_route(&self, path: &str, method: &str) -> Option<&Route> {
-        self.routes.iter().find(|route| route.matches(path, method))
+        self.find_route_cached(path, method, &RouteRegexCache::default())
     }
 
-    pub fn get_effective_config(&self, route: &Route) -> GlobalConfig {
-        GlobalConfig {
-            forward_on_error: route
-                .config
-                .forward_on_error
-                .unwrap_or(self.global.forward_on_error),
-            add_error_header: route
-                .config
-                .add_error_header
-                .unwrap_or(self.global.add_error_header),
-            add_validation_header: route
-                .config
-                .add_validation_header
-                .unwrap_or(self.global.add_validation_header),
+    /// Like `find_route`, but uses pre-compiled `path_regex` patterns
+    /// instead of compiling them on every lookup.
+    pub fn find_route_cached(
+        &self,
+        path: &str,
+        method: &str,
+        regexes: &RouteRegexCache,
+    ) -> Option<&Route> {
+        let ignore_case = self.global.case_insensitive_paths;
+        self.routes
+            .iter()
+            .find(|route| route.matches_as(path, method, regexes.get_for(route), ignore_case))
+    }
+
+    /// Uppercase methods of the routes matching `path`, in config order
+    /// without repeats. Empty when no route serves the path at all.
+    pub fn find_allowed_methods(&self, path: &str) -> Vec<String> {
+        self.find_allowed_methods_cached(path, &RouteRegexCache::default())
+    }
+
+    /// Like `find_allowed_methods`, but uses pre-compiled `path_regex`
+    /// patterns where `regexes` has them.
+    pub fn find_allowed_methods_cached(
+        &self,
+        path: &str,
+        regexes: &RouteRegexCache,
+    ) -> Vec<String> {
+        let mut methods: Vec<String> = Vec::new();
+        for route in &self.routes {
+            let method = route.method.to_uppercase();
+            if !methods.contains(&method)
+                && route.matches_path_as(
+                    path,
+                    regexes.get_for(route),
+                    self.global.case_insensitive_paths,
+                )
+            {
+                methods.push(method);
+            }
         }
+        methods
+    }
+
+    pub fn get_effective_config(&self, route: &Route) -> GlobalConfig {
+        self.global.clone().with_overrides(&route.config)
     }
 }
 
-#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+/// Most tags a route may have
+pub const MAX_ROUTE_TAGS: usize = 10;
+/// Longest tag allowed, in characters
+pub const MAX_TAG_LENGTH: usize = 64;
+/// Tags that make it into the `route_tags` metrics label
+pub const MAX_LABELLED_TAGS: usize = 3;
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
 pub struct Route {
+    #[serde(default)]
     pub path: String,
+    #[serde(default)]
+    pub path_regex: Option<String>,
+    #[serde(default)]
     pub method: String,
     pub schema: Option<PathBuf>,
+    /// Reject request body fields `schema` doesn't declare, as if every
+    /// object in it set `additionalProperties: false`
+    #[serde(default)]
+    pub strict_body: bool,
+    /// Compile `schema` against this draft (`draft4`, `draft6`, `draft7`,
+    /// `draft2019_09` or `draft2020_12`) whatever its `$schema` says
+    #[serde(default)]
+    pub schema_draft: Option<String>,
+    #[serde(default)]
+    pub headers_schema: Option<PathBuf>,
     #[serde(default)]
     pub openapi: Option<OpenApiSource>,
+    #[serde(default)]
     pub upstream: String,
+    /// Path sent upstream instead of the request's, with each `{name}`
+    /// replaced by the `:name` segment (or `path_regex` capture) it names
+    #[serde(default)]
+    pub upstream_path_template: Option<String>,
+    /// Answer requests with this response instead of forwarding them
+    #[serde(default)]
+    pub mock_response: Option<MockResponse>,
+    /// Answer requests that pass validation with a response made up from
+    /// the `openapi` spec instead of forwarding them
+    #[serde(default)]
+    pub mock: bool,
+    /// Free-form labels (team, service, API version) added to the route's
+    /// metrics and admin output
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// What the route is for, shown in logs and the admin API
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Tunnel `Connection: Upgrade` requests (e.g. WebSocket) to the
+    /// upstream, unvalidated, instead of refusing them with 501
+    #[serde(default)]
+    pub allow_upgrade: bool,
     #[serde(default)]
     pub config: RouteConfig,
 }
 
 impl Route {
+    /// Resolve the route's relative file paths against `base_dir`
+    fn resolve_paths(&mut self, base_dir: &Path) {
+        if let Some(schema) = self.schema.as_mut() {
+            *schema = resolve_relative(base_dir, schema);
+        }
+        if let Some(headers_schema) = self.headers_schema.as_mut() {
+            *headers_schema = resolve_relative(base_dir, headers_schema);
+        }
+        match self.openapi.as_mut() {
+            Some(OpenApiSource::Spec(spec)) => *spec = resolve_relative(base_dir, spec),
+            Some(OpenApiSource::Detailed(cfg)) => cfg.spec = resolve_relative(base_dir, &cfg.spec),
+            None => {}
+        }
+        if let Some(tls) = self.config.upstream_tls.as_mut() {
+            tls.resolve_paths(base_dir);
+        }
+        if let Some(MockBody::File { file }) =
+            self.mock_response.as_mut().and_then(|m| m.body.as_mut())
+        {
+            *file = resolve_relative(base_dir, file);
+        }
+    }
+
     fn validate(&self) -> Result<(), String> {
-        // Check for empty upstream
-        if self.upstream.is_empty() {
-            return Err("upstream cannot be empty".to_string());
+        // Check path / path_regex
+        match (self.path.is_empty(), self.path_regex.as_ref()) {
+            (false, Some(_)) => {
+                return Err("Cannot specify both 'path' and 'path_regex' on a route".to_string());
+            }
+            (true, None) => {
+                return Err("Route must specify either 'path' or 'path_regex'".to_string());
+            }
+            (true, Some(pattern)) => {
+                if let Err(e) = Regex::new(pattern) {
+                    return Err(format!("Invalid path_regex '{}': {}", pattern, e));
+                }
+            }
+            (false, None) => {}
         }
 
         // Check for valid HTTP method
@@ -96,10 +486,207 @@ impl Route {
             return Err(format!("Invalid HTTP method: {}", self.method));
         }
 
+        if let Some(template) = &self.upstream_path_template {
+            if !template.starts_with('/') {
+                return Err(format!(
+                    "upstream_path_template must start with '/': '{}'",
+                    template
+                ));
+            }
+            let params = self.path_param_names();
+            for name in template_params(template)? {
+                if !params.iter().any(|param| param == name) {
+                    return Err(format!(
+                        "upstream_path_template uses '{{{}}}', but '{}' has no ':{}' parameter",
+                        name,
+                        self.pattern(),
+                        name
+                    ));
+                }
+            }
+        }
+
+        self.validate_handling()
+    }
+
+    /// Names of the `:param` segments in `path`, or of the named capture
+    /// groups in `path_regex`
+    fn path_param_names(&self) -> Vec<String> {
+        match &self.path_regex {
+            Some(pattern) => Regex::new(pattern)
+                .map(|regex| regex.capture_names().flatten().map(String::from).collect())
+                .unwrap_or_default(),
+            None => self
+                .path
+                .split('/')
+                .filter_map(|seg| seg.strip_prefix(':'))
+                .map(String::from)
+                .collect(),
+        }
+    }
+
+    /// The path to forward `path` to: `upstream_path_template` filled in
+    /// from its `:param` segments and `captures` (see `path_captures`), or
+    /// `None` without a template
+    pub fn upstream_path(&self, path: &str, captures: &HashMap<String, String>) -> Option<String> {
+        let template = self.upstream_path_template.as_ref()?;
+        let mut params = captures.clone();
+        if self.path_regex.is_none() {
+            for (route_seg, path_seg) in self.path.split('/').zip(path.split('/')) {
+                if let Some(name) = route_seg.strip_prefix(':') {
+                    params.insert(name.to_string(), path_seg.to_string());
+                }
+            }
+        }
+
+        // One pass, so a value that looks like a placeholder stays as it is
+        let mut forward = String::with_capacity(template.len());
+        let mut rest = template.as_str();
+        while let Some((start, len)) = rest
+            .find('{')
+            .and_then(|start| Some((start, rest[start..].find('}')?)))
+        {
+            forward.push_str(&rest[..start]);
+            let placeholder = &rest[start..=start + len];
+            match params.get(&placeholder[1..len]) {
+                Some(value) => forward.push_str(value),
+                None => forward.push_str(placeholder),
+            }
+            rest = &rest[start + len + 1..];
+        }
+        forward.push_str(rest);
+        Some(forward)
+    }
+
+    /// Checks of everything but the path and method: where requests go and
+    /// how they're validated
+    fn validate_handling(&self) -> Result<(), String> {
+        if self.mock {
+            if self.openapi.is_none() {
+                return Err("'mock' requires 'openapi'".to_string());
+            }
+            if !self.upstream.is_empty() {
+                return Err("Cannot specify both 'upstream' and 'mock' on a route".to_string());
+            }
+            if self.mock_response.is_some() {
+                return Err("Cannot specify both 'mock' and 'mock_response' on a route".to_string());
+            }
+        }
+
+        // Check upstream / mock_response
+        match (&self.mock_response, self.upstream.is_empty()) {
+            (Some(_), false) => {
+                return Err(
+                    "Cannot specify both 'upstream' and 'mock_response' on a route".to_string(),
+                );
+            }
+            (Some(mock), true) => mock.validate()?,
+            (None, true) if !self.mock => return Err("upstream cannot be empty".to_string()),
+            (None, true) => {}
+            (None, false) => validate_upstream_url(&self.upstream)?,
+        }
+
+        if self.tags.len() > MAX_ROUTE_TAGS {
+            return Err(format!(
+                "A route can have at most {} tags, found {}",
+                MAX_ROUTE_TAGS,
+                self.tags.len()
+            ));
+        }
+        for tag in &self.tags {
+            if tag.is_empty() || tag.chars().count() > MAX_TAG_LENGTH {
+                return Err(format!(
+                    "Tag '{}' must be 1 to {} characters long",
+                    tag, MAX_TAG_LENGTH
+                ));
+            }
+            // Commas separate tags in the metrics label
+            if tag.contains(',') {
+                return Err(format!("Tag '{}' cannot contain ','", tag));
+            }
+        }
+
+        // Tunnels are plain TCP to the upstream
+        if self.allow_upgrade && !self.upstream.starts_with("http://") {
+            return Err("'allow_upgrade' requires an http:// upstream".to_string());
+        }
+
+        let no_renames = HashMap::new();
+        validate_response_header_rules(
+            self.config
+                .strip_response_headers
+                .as_deref()
+                .unwrap_or_default(),
+            self.config
+                .rename_response_headers
+                .as_ref()
+                .unwrap_or(&no_renames),
+        )?;
+        validate_header_names(
+            self.config
+                .remove_request_headers
+                .as_deref()
+                .unwrap_or_default(),
+        )?;
+        if let Some(auth) = &self.config.upstream_auth {
+            auth.validate()?;
+        }
+
+        if let Some(tls) = &self.config.upstream_tls {
+            tls.validate()?;
+        }
+
+        if let Some(limit) = &self.config.rate_limit {
+            limit.validate()?;
+        }
+
+        if self.config.max_request_body_bytes == Some(0) {
+            return Err("max_request_body_bytes must be greater than 0".to_string());
+        }
+        if self.config.max_response_body_bytes == Some(0) {
+            return Err("max_response_body_bytes must be greater than 0".to_string());
+        }
+        if self.config.max_response_bytes == Some(0) {
+            return Err("max_response_bytes must be greater than 0".to_string());
+        }
+        if self.config.max_redirects == Some(0) {
+            return Err("max_redirects must be greater than 0".to_string());
+        }
+
+        if let Some(retry) = &self.config.retry {
+            retry.validate()?;
+        }
+
         if self.schema.is_some() && self.openapi.is_some() {
             return Err("Cannot specify both 'schema' and 'openapi' on a route".to_string());
         }
 
+        if self.strict_body && self.schema.is_none() {
+            return Err(
+                "'strict_body' requires 'schema' (use openapi.strict_body on OpenAPI routes)"
+                    .to_string(),
+            );
+        }
+
+        if let Some(draft) = &self.schema_draft {
+            if self.schema.is_none() {
+                return Err("'schema_draft' requires 'schema'".to_string());
+            }
+            if SchemaDraft::from_name(draft).is_none() {
+                return Err(format!(
+                    "Unknown schema_draft '{}' (expected one of: {})",
+                    draft,
+                    SchemaDraft::NAMES.join(", ")
+                ));
+            }
+        }
+
+        if self.headers_schema.is_some() && self.openapi.is_some() {
+            return Err(
+                "Cannot specify both 'headers_schema' and 'openapi' on a route".to_string(),
+            );
+        }
+
         if let Some(openapi) = self.openapi.as_ref().map(OpenApiSource::to_options) {
             if openapi.spec.as_os_str().is_empty() {
                 return Err("OpenAPI spec path cannot be empty".to_string());
@@ -120,15 +707,114 @@ impl Route {
         Ok(())
     }
 
+    /// The `route_tags` metrics label: the first `MAX_LABELLED_TAGS` tags,
+    /// comma-separated, so a long list can't multiply the label's values
+    pub fn tags_label(&self) -> String {
+        self.tags
+            .iter()
+            .take(MAX_LABELLED_TAGS)
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// `description`, or `"<METHOD> <path>"` when there isn't one
+    pub fn description(&self) -> String {
+        self.description
+            .clone()
+            .unwrap_or_else(|| format!("{} {}", self.method.to_uppercase(), self.pattern()))
+    }
+
+    /// How `schema` is compiled for this route
+    pub fn schema_options(&self) -> SchemaOptions {
+        SchemaOptions {
+            strict_body: self.strict_body,
+            draft: self
+                .schema_draft
+                .as_deref()
+                .and_then(SchemaDraft::from_name),
+        }
+    }
+
+    /// The configured path pattern, used as the route label in metrics and logs.
+    pub fn pattern(&self) -> &str {
+        self.path_regex.as_deref().unwrap_or(&self.path)
+    }
+
+    /// Key identifying the requests this route can match: the uppercase
+    /// method and the pattern, with `:param` names dropped so `/users/:id`
+    /// and `/users/:name` compare equal. Static segments are lowercased when
+    /// `ignore_case` is set (`case_insensitive_paths`), as `/users` and
+    /// `/Users` then match the same requests.
+    fn index_key(&self, ignore_case: bool) -> (String, String) {
+        let pattern = match &self.path_regex {
+            Some(regex) => format!("regex:{}", regex),
+            None => trim_trailing_slash(&self.path)
+                .split('/')
+                .map(|seg| match seg {
+                    seg if seg.starts_with(':') => ":".to_string(),
+                    seg if ignore_case => seg.to_ascii_lowercase(),
+                    seg => seg.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join("/"),
+        };
+        (pattern, self.method.to_uppercase())
+    }
+
+    /// True when the path has no `:param` segments and no `path_regex`, so
+    /// it only matches requests for exactly `path`.
+    fn is_literal(&self) -> bool {
+        self.path_regex.is_none() && !self.path.split('/').any(|seg| seg.starts_with(':'))
+    }
+
     pub fn matches(&self, path: &str, method: &str) -> bool {
+        self.matches_with_regex(path, method, None)
+    }
+
+    /// Match against the request, using `regex` as the compiled form of
+    /// `path_regex` when provided. Without it the pattern is compiled on the fly.
+    pub fn matches_with_regex(&self, path: &str, method: &str, regex: Option<&Regex>) -> bool {
+        self.matches_as(path, method, regex, false)
+    }
+
+    /// Like `matches_with_regex`, comparing static segments without regard
+    /// to ASCII case when `ignore_case` is set (`case_insensitive_paths`)
+    fn matches_as(
+        &self,
+        path: &str,
+        method: &str,
+        regex: Option<&Regex>,
+        ignore_case: bool,
+    ) -> bool {
         // Case-insensitive method matching
-        if self.method.to_uppercase() != method.to_uppercase() {
-            return false;
+        self.method.to_uppercase() == method.to_uppercase()
+            && self.matches_path_as(path, regex, ignore_case)
+    }
+
+    /// Whether the route's path or `path_regex` matches `path`, whatever
+    /// the method
+    pub fn matches_path_with_regex(&self, path: &str, regex: Option<&Regex>) -> bool {
+        self.matches_path_as(path, regex, false)
+    }
+
+    /// Like `matches_path_with_regex`, optionally ignoring ASCII case in
+    /// static segments. `path_regex` patterns are matched as written; use
+    /// `(?i)` in them instead.
+    fn matches_path_as(&self, path: &str, regex: Option<&Regex>, ignore_case: bool) -> bool {
+        if let Some(pattern) = self.path_regex.as_ref() {
+            return match regex {
+                Some(compiled) => compiled.is_match(path),
+                None => Regex::new(pattern)
+                    .map(|compiled| compiled.is_match(path))
+                    .unwrap_or(false),
+            };
         }
 
-        // Split paths by '/' and compare segments
-        let route_segments: Vec<&str> = self.path.split('/').collect();
-        let path_segments: Vec<&str> = path.split('/').collect();
+        // Split paths by '/' and compare segments; a trailing slash on
+        // either side doesn't count
+        let route_segments: Vec<&str> = trim_trailing_slash(&self.path).split('/').collect();
+        let path_segments: Vec<&str> = trim_trailing_slash(path).split('/').collect();
 
         // Must have same number of segments
         if route_segments.len() != path_segments.len() {
@@ -142,8 +828,13 @@ impl Route {
                 continue;
             }
 
-            // Static segments must match exactly
-            if route_seg != path_seg {
+            // Static segments must match exactly, or up to case
+            let same = if ignore_case {
+                route_seg.eq_ignore_ascii_case(path_seg)
+            } else {
+                route_seg == path_seg
+            };
+            if !same {
                 return false;
             }
         }
@@ -152,7 +843,55 @@ impl Route {
     }
 }
 
-#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+/// `path` without trailing slashes, keeping the root `/` as it is
+fn trim_trailing_slash(path: &str) -> &str {
+    match path.trim_end_matches('/') {
+        "" if path.starts_with('/') => "/",
+        trimmed => trimmed,
+    }
+}
+
+/// The `{name}` placeholders in an `upstream_path_template`
+fn template_params(template: &str) -> Result<Vec<&str>, String> {
+    let mut params = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        let Some(len) = rest[start..]
+            .strip_prefix('{')
+            .and_then(|open| open.find('}'))
+        else {
+            return Err(format!(
+                "upstream_path_template has an unmatched brace: '{}'",
+                template
+            ));
+        };
+        let name = &rest[start + 1..start + 1 + len];
+        if name.is_empty() || name.contains('{') {
+            return Err(format!(
+                "upstream_path_template has an invalid placeholder: '{}'",
+                template
+            ));
+        }
+        params.push(name);
+        rest = &rest[start + len + 2..];
+    }
+    Ok(params)
+}
+
+impl FromStr for Config {
+    type Err = String;
+
+    /// Load configuration from a YAML string. Relative paths are kept as
+    /// written, i.e. relative to the working directory.
+    fn from_str(yaml: &str) -> Result<Self, String> {
+        let mut config: Config =
+            serde_yaml::from_str(yaml).map_err(|e| format!("Failed to parse config: {}", e))?;
+        config.finish_loading(None, "config")?;
+        Ok(config)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
 pub struct GlobalConfig {
     #[serde(default = "default_true")]
     pub forward_on_error: bool,
@@ -160,6 +899,221 @@ pub struct GlobalConfig {
     pub add_error_header: bool,
     #[serde(default = "default_true")]
     pub add_validation_header: bool,
+    /// Maximum number of compiled schemas / parsed OpenAPI specs kept in
+    /// each cache. Unbounded when unset.
+    #[serde(default)]
+    pub schema_cache_max_entries: Option<usize>,
+    /// How long a schema or spec load failure is remembered before the file
+    /// is read again. `0` disables negative caching.
+    #[serde(default = "default_negative_cache_ttl_secs")]
+    pub negative_cache_ttl_secs: u64,
+    /// Upstream response headers (case-insensitive) removed before the
+    /// response is returned to the client.
+    #[serde(default)]
+    pub strip_response_headers: Vec<String>,
+    /// Upstream response headers renamed before the response is returned
+    /// to the client, keyed by the original header name.
+    #[serde(default)]
+    pub rename_response_headers: HashMap<String, String>,
+    /// Client request headers left out when forwarding upstream
+    #[serde(default)]
+    pub remove_request_headers: Vec<String>,
+    /// Credential sent upstream as `Authorization` in place of the client's
+    #[serde(default)]
+    pub upstream_auth: Option<UpstreamAuthConfig>,
+    /// Serve Prometheus metrics from the gateway itself
+    #[serde(default = "default_true")]
+    pub metrics_enabled: bool,
+    /// Path the metrics endpoint is served on. Read at startup only.
+    #[serde(default = "default_metrics_path")]
+    pub metrics_path: String,
+    /// When set, the metrics endpoint requires `Authorization: Bearer <token>`
+    #[serde(default)]
+    pub metrics_token: Option<String>,
+    /// TLS settings for upstream connections. Uses the system roots with
+    /// full verification when unset.
+    #[serde(default)]
+    pub upstream_tls: Option<UpstreamTlsConfig>,
+    /// Speak HTTP/2 to `http://` upstreams without negotiating it first
+    /// (h2c with prior knowledge). `https://` upstreams pick HTTP/2 through
+    /// ALPN either way.
+    #[serde(default)]
+    pub upstream_http2: bool,
+    /// Connection pool and socket settings for upstream clients
+    #[serde(default)]
+    pub http_client: HttpClientConfig,
+    /// Pipe request bodies straight to the upstream on routes with nothing
+    /// to validate, instead of reading them into memory first
+    #[serde(default = "default_true")]
+    pub stream_unvalidated: bool,
+    /// When set, admin endpoints require `Authorization: Bearer <token>`
+    #[serde(default)]
+    pub admin_token: Option<String>,
+    /// Number of leading path segments kept in the `field_path` label of
+    /// `validation_field_errors_total`
+    #[serde(default = "default_max_field_label_depth")]
+    pub max_field_label_depth: usize,
+    /// Export per-operation OpenAPI validation counts as
+    /// `openapi_operation_validations_total`, labelled by operation
+    #[serde(default)]
+    pub openapi_operation_metrics: bool,
+    /// Reject requests missing the credentials an OpenAPI operation's
+    /// `security` requirements call for
+    #[serde(default = "default_true")]
+    pub enforce_security: bool,
+    /// Send the digest of the request body upstream as `X-Body-Hash`
+    #[serde(default)]
+    pub inject_body_hash: Option<BodyHashAlgorithm>,
+    /// Reject upstream responses whose body doesn't match their `X-Body-Hash`
+    #[serde(default)]
+    pub verify_response_hash: bool,
+    /// Indent the JSON error bodies the gateway returns. Always on with
+    /// `--dev`.
+    #[serde(default)]
+    pub pretty_print_errors: bool,
+    /// Add `X-Gateway-Request-Duration-Ms` to every proxied response
+    #[serde(default)]
+    pub report_duration_header: bool,
+    /// Add `X-Upstream-Duration-Ms` to every forwarded response
+    #[serde(default)]
+    pub timing_headers: bool,
+    /// Compare the static segments of route paths and OpenAPI path
+    /// templates without regard to ASCII case
+    #[serde(default)]
+    pub case_insensitive_paths: bool,
+    /// Add `X-Gateway-Route` and `X-Gateway-Validation` to responses of
+    /// matched routes. Leave off in production: they reveal the routing.
+    #[serde(default)]
+    pub debug_headers: bool,
+    /// Reject upstream responses whose `Content-Type` the OpenAPI spec
+    /// doesn't declare for their status
+    #[serde(default)]
+    pub reject_undeclared_response_types: bool,
+    /// Forward the client's `traceparent`, `tracestate` and `baggage`
+    /// headers to the upstream unchanged
+    #[serde(default = "default_true")]
+    pub propagate_trace_context: bool,
+    /// Add `Deprecation` (and `Sunset`, from `x-sunset`) to responses for
+    /// OpenAPI operations or parameters marked `deprecated`
+    #[serde(default = "default_true")]
+    pub deprecation_headers: bool,
+    /// Token-bucket limit applied to each route separately. Unlimited when
+    /// unset.
+    #[serde(default)]
+    pub rate_limit: Option<RateLimitConfig>,
+    /// Largest request body the gateway reads into memory for validation,
+    /// in bytes. Larger bodies get 413. Unlimited when unset.
+    #[serde(default)]
+    pub max_request_body_bytes: Option<usize>,
+    /// Largest upstream response body read into memory for response
+    /// validation, in bytes. Larger bodies are streamed to the client
+    /// unvalidated. Unlimited when unset.
+    #[serde(default)]
+    pub max_response_body_bytes: Option<usize>,
+    /// Largest upstream response body passed on at all, in bytes. Larger
+    /// bodies get 502, or are cut off if already streaming. Unlimited when
+    /// unset.
+    #[serde(default)]
+    pub max_response_bytes: Option<usize>,
+    /// Send the upstream a gzip or deflate request body decoded (without
+    /// `Content-Encoding`) instead of as the client compressed it
+    #[serde(default)]
+    pub forward_decompressed_body: bool,
+    /// Retry upstream requests that fail. No retries when unset.
+    #[serde(default)]
+    pub retry: Option<RetryConfig>,
+    /// Follow upstream redirects instead of passing 3xx responses to the
+    /// client
+    #[serde(default)]
+    pub follow_redirects: bool,
+    /// Most redirects followed for one request with `follow_redirects`
+    #[serde(default = "default_max_redirects")]
+    pub max_redirects: usize,
+    /// Most schema validation errors reported for one request; the rest are
+    /// only counted
+    #[serde(default = "default_max_validation_errors")]
+    pub max_validation_errors: usize,
+    /// Log JSON request bodies at DEBUG before validating them. The bodies
+    /// may hold personal data, so leave off unless debugging.
+    #[serde(default)]
+    pub log_request_body: bool,
+    /// Most bytes of each body `log_request_body` logs
+    #[serde(default = "default_log_request_body_max_bytes")]
+    pub log_request_body_max_bytes: usize,
+    /// How long the health probes wait for the gateway state lock before
+    /// answering 503. Read at startup only.
+    #[serde(default = "default_liveness_check_timeout_ms")]
+    pub liveness_check_timeout_ms: u64,
+    /// Header carrying the request ID: reused from the client when present,
+    /// sent to the upstream and echoed on the response
+    #[serde(default = "default_request_id_header")]
+    pub request_id_header: String,
+    /// Leave the upstream host out of 502/504 bodies for failed upstream
+    /// requests; it is still logged
+    #[serde(default)]
+    pub redact_upstream_in_errors: bool,
+}
+
+impl GlobalConfig {
+    pub fn negative_cache_ttl(&self) -> Duration {
+        Duration::from_secs(self.negative_cache_ttl_secs)
+    }
+
+    pub fn liveness_check_timeout(&self) -> Duration {
+        Duration::from_millis(self.liveness_check_timeout_ms)
+    }
+
+    /// These settings with every field `overrides` sets replaced
+    pub fn with_overrides(self, overrides: &RouteConfig) -> Self {
+        let overrides = overrides.clone();
+        Self {
+            forward_on_error: overrides.forward_on_error.unwrap_or(self.forward_on_error),
+            add_error_header: overrides.add_error_header.unwrap_or(self.add_error_header),
+            add_validation_header: overrides
+                .add_validation_header
+                .unwrap_or(self.add_validation_header),
+            strip_response_headers: overrides
+                .strip_response_headers
+                .unwrap_or(self.strip_response_headers),
+            rename_response_headers: overrides
+                .rename_response_headers
+                .unwrap_or(self.rename_response_headers),
+            remove_request_headers: overrides
+                .remove_request_headers
+                .unwrap_or(self.remove_request_headers),
+            upstream_auth: overrides.upstream_auth.or(self.upstream_auth),
+            upstream_tls: overrides.upstream_tls.or(self.upstream_tls),
+            enforce_security: overrides.enforce_security.unwrap_or(self.enforce_security),
+            inject_body_hash: overrides.inject_body_hash.or(self.inject_body_hash),
+            verify_response_hash: overrides
+                .verify_response_hash
+                .unwrap_or(self.verify_response_hash),
+            reject_undeclared_response_types: overrides
+                .reject_undeclared_response_types
+                .unwrap_or(self.reject_undeclared_response_types),
+            propagate_trace_context: overrides
+                .propagate_trace_context
+                .unwrap_or(self.propagate_trace_context),
+            deprecation_headers: overrides
+                .deprecation_headers
+                .unwrap_or(self.deprecation_headers),
+            rate_limit: overrides.rate_limit.or(self.rate_limit),
+            max_request_body_bytes: overrides
+                .max_request_body_bytes
+                .or(self.max_request_body_bytes),
+            max_response_body_bytes: overrides
+                .max_response_body_bytes
+                .or(self.max_response_body_bytes),
+            max_response_bytes: overrides.max_response_bytes.or(self.max_response_bytes),
+            forward_decompressed_body: overrides
+                .forward_decompressed_body
+                .unwrap_or(self.forward_decompressed_body),
+            retry: overrides.retry.or(self.retry),
+            follow_redirects: overrides.follow_redirects.unwrap_or(self.follow_redirects),
+            max_redirects: overrides.max_redirects.unwrap_or(self.max_redirects),
+            ..self
+        }
+    }
 }
 
 impl Default for GlobalConfig {
@@ -168,22 +1122,792 @@ impl Default for GlobalConfig {
             forward_on_error: true,
             add_error_header: true,
             add_validation_header: true,
+            schema_cache_max_entries: None,
+            negative_cache_ttl_secs: default_negative_cache_ttl_secs(),
+            strip_response_headers: Vec::new(),
+            remove_request_headers: Vec::new(),
+            upstream_auth: None,
+            rename_response_headers: HashMap::new(),
+            metrics_enabled: true,
+            metrics_path: default_metrics_path(),
+            metrics_token: None,
+            upstream_tls: None,
+            upstream_http2: false,
+            http_client: HttpClientConfig::default(),
+            stream_unvalidated: true,
+            admin_token: None,
+            max_field_label_depth: default_max_field_label_depth(),
+            openapi_operation_metrics: false,
+            enforce_security: true,
+            inject_body_hash: None,
+            verify_response_hash: false,
+            pretty_print_errors: false,
+            report_duration_header: false,
+            timing_headers: false,
+            case_insensitive_paths: false,
+            debug_headers: false,
+            reject_undeclared_response_types: false,
+            propagate_trace_context: true,
+            deprecation_headers: true,
+            rate_limit: None,
+            max_request_body_bytes: None,
+            max_response_body_bytes: None,
+            max_response_bytes: None,
+            forward_decompressed_body: false,
+            retry: None,
+            follow_redirects: false,
+            max_redirects: default_max_redirects(),
+            max_validation_errors: default_max_validation_errors(),
+            log_request_body: false,
+            log_request_body_max_bytes: default_log_request_body_max_bytes(),
+            liveness_check_timeout_ms: default_liveness_check_timeout_ms(),
+            request_id_header: default_request_id_header(),
+            redact_upstream_in_errors: false,
         }
     }
 }
 
-#[derive(Debug, Deserialize, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Default)]
 pub struct RouteConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub forward_on_error: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub add_error_header: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub add_validation_header: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strip_response_headers: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rename_response_headers: Option<HashMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remove_request_headers: Option<Vec<String>>,
+    /// Replaces the global `upstream_auth` block for this route
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream_auth: Option<UpstreamAuthConfig>,
+    /// Replaces the global `upstream_tls` block for this route
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream_tls: Option<UpstreamTlsConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enforce_security: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inject_body_hash: Option<BodyHashAlgorithm>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verify_response_hash: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reject_undeclared_response_types: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub propagate_trace_context: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecation_headers: Option<bool>,
+    /// Replaces the global `rate_limit` for this route
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimitConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_request_body_bytes: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_response_body_bytes: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_response_bytes: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forward_decompressed_body: Option<bool>,
+    /// Replaces the global `retry` block for this route
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub follow_redirects: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_redirects: Option<usize>,
+}
+
+impl RouteConfig {
+    /// These overrides, taking each one left unset from `fallback`
+    pub fn or(self, fallback: RouteConfig) -> Self {
+        Self {
+            forward_on_error: self.forward_on_error.or(fallback.forward_on_error),
+            add_error_header: self.add_error_header.or(fallback.add_error_header),
+            add_validation_header: self
+                .add_validation_header
+                .or(fallback.add_validation_header),
+            strip_response_headers: self
+                .strip_response_headers
+                .or(fallback.strip_response_headers),
+            rename_response_headers: self
+                .rename_response_headers
+                .or(fallback.rename_response_headers),
+            remove_request_headers: self
+                .remove_request_headers
+                .or(fallback.remove_request_headers),
+            upstream_auth: self.upstream_auth.or(fallback.upstream_auth),
+            upstream_tls: self.upstream_tls.or(fallback.upstream_tls),
+            enforce_security: self.enforce_security.or(fallback.enforce_security),
+            inject_body_hash: self.inject_body_hash.or(fallback.inject_body_hash),
+            verify_response_hash: self.verify_response_hash.or(fallback.verify_response_hash),
+            reject_undeclared_response_types: self
+                .reject_undeclared_response_types
+                .or(fallback.reject_undeclared_response_types),
+            propagate_trace_context: self
+                .propagate_trace_context
+                .or(fallback.propagate_trace_context),
+            deprecation_headers: self.deprecation_headers.or(fallback.deprecation_headers),
+            rate_limit: self.rate_limit.or(fallback.rate_limit),
+            max_request_body_bytes: self
+                .max_request_body_bytes
+                .or(fallback.max_request_body_bytes),
+            max_response_body_bytes: self
+                .max_response_body_bytes
+                .or(fallback.max_response_body_bytes),
+            max_response_bytes: self.max_response_bytes.or(fallback.max_response_bytes),
+            forward_decompressed_body: self
+                .forward_decompressed_body
+                .or(fallback.forward_decompressed_body),
+            retry: self.retry.or(fallback.retry),
+            follow_redirects: self.follow_redirects.or(fallback.follow_redirects),
+            max_redirects: self.max_redirects.or(fallback.max_redirects),
+        }
+    }
+}
+
+/// A credential for the upstream, replacing whatever `Authorization` the
+/// client sent. Values may reference environment variables as `${NAME}`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Hash)]
+pub struct UpstreamAuthConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub basic: Option<BasicAuthConfig>,
+    /// Sent as `Bearer <token>`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bearer: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Hash)]
+pub struct BasicAuthConfig {
+    pub username: String,
+    pub password: String,
+}
+
+impl UpstreamAuthConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.basic.is_some() && self.bearer.is_some() {
+            return Err("upstream_auth can't set both basic and bearer".to_string());
+        }
+        self.header_value().map(|_| ())
+    }
+
+    /// The `Authorization` value to send, environment variables filled in
+    pub fn header_value(&self) -> Result<HeaderValue, String> {
+        let expand = |value: &str| expand_env(value).map_err(|e| format!("upstream_auth: {}", e));
+        let value = match (&self.basic, &self.bearer) {
+            (None, Some(token)) => format!("Bearer {}", expand(token)?),
+            (Some(basic), None) => {
+                let credentials =
+                    format!("{}:{}", expand(&basic.username)?, expand(&basic.password)?);
+                format!("Basic {}", BASE64_STANDARD.encode(credentials))
+            }
+            _ => return Err("upstream_auth needs one of basic or bearer".to_string()),
+        };
+        let mut value = HeaderValue::from_str(&value)
+            .map_err(|_| "upstream_auth: credential is not a valid header value".to_string())?;
+        value.set_sensitive(true);
+        Ok(value)
+    }
+}
+
+/// TLS settings for connections to an upstream
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Hash)]
+#[serde(try_from = "UpstreamTlsFile")]
+pub struct UpstreamTlsConfig {
+    /// Verify the upstream certificate chain and hostname
+    pub verify: bool,
+    /// Required acknowledgement when `verify` is false
+    pub insecure: bool,
+    /// PEM bundle of CAs to trust instead of the system roots
+    pub ca_bundle: Option<PathBuf>,
+    /// PEM client certificate chain for mutual TLS (requires `client_key`)
+    pub client_cert: Option<PathBuf>,
+    /// PEM private key for `client_cert`
+    pub client_key: Option<PathBuf>,
+    /// Hex SHA-256 of the upstream's DER-encoded leaf certificate; the
+    /// connection is refused unless it matches
+    pub pin_cert_hash: Option<String>,
+}
+
+/// `upstream_tls` as written. `ca_cert` and `danger_accept_invalid_certs`
+/// are accepted for `ca_bundle` and `verify: false, insecure: true`.
+#[derive(Deserialize)]
+struct UpstreamTlsFile {
+    #[serde(default)]
+    verify: Option<bool>,
+    #[serde(default)]
+    insecure: bool,
+    #[serde(default)]
+    danger_accept_invalid_certs: bool,
+    #[serde(default, alias = "ca_cert")]
+    ca_bundle: Option<PathBuf>,
+    #[serde(default)]
+    client_cert: Option<PathBuf>,
+    #[serde(default)]
+    client_key: Option<PathBuf>,
+    #[serde(default)]
+    pin_cert_hash: Option<String>,
+}
+
+impl TryFrom<UpstreamTlsFile> for UpstreamTlsConfig {
+    type Error = String;
+
+    fn try_from(file: UpstreamTlsFile) -> Result<Self, Self::Error> {
+        if file.danger_accept_invalid_certs && file.verify == Some(true) {
+            return Err(
+                "upstream_tls: danger_accept_invalid_certs: true contradicts verify: true"
+                    .to_string(),
+            );
+        }
+        Ok(Self {
+            verify: file.verify.unwrap_or(!file.danger_accept_invalid_certs),
+            insecure: file.insecure || file.danger_accept_invalid_certs,
+            ca_bundle: file.ca_bundle,
+            client_cert: file.client_cert,
+            client_key: file.client_key,
+            pin_cert_hash: file.pin_cert_hash,
+        })
+    }
+}
+
+impl Default for UpstreamTlsConfig {
+    fn default() -> Self {
+        Self {
+            verify: true,
+            insecure: false,
+            ca_bundle: None,
+            client_cert: None,
+            client_key: None,
+            pin_cert_hash: None,
+        }
+    }
+}
+
+impl UpstreamTlsConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.verify && !self.insecure {
+            return Err(
+                "upstream_tls.verify: false requires 'insecure: true' to acknowledge the risk"
+                    .to_string(),
+            );
+        }
+
+        match (&self.client_cert, &self.client_key) {
+            (Some(_), None) => {
+                return Err("upstream_tls.client_cert requires client_key".to_string())
+            }
+            (None, Some(_)) => {
+                return Err("upstream_tls.client_key requires client_cert".to_string())
+            }
+            _ => {}
+        }
+
+        let files = [&self.ca_bundle, &self.client_cert, &self.client_key];
+        for path in files.into_iter().flatten() {
+            if !path.exists() {
+                return Err(format!(
+                    "upstream_tls file does not exist: {}",
+                    path.display()
+                ));
+            }
+        }
+        for path in [&self.ca_bundle, &self.client_cert].into_iter().flatten() {
+            crate::tls::load_certs(path).map_err(|e| format!("upstream_tls: {}", e))?;
+        }
+        if let Some(path) = &self.client_key {
+            crate::tls::load_private_key(path).map_err(|e| format!("upstream_tls: {}", e))?;
+        }
+
+        if let Some(pin) = &self.pin_cert_hash {
+            if self.pin_bytes().is_none() {
+                return Err(format!(
+                    "upstream_tls.pin_cert_hash must be a hex SHA-256 digest: '{}'",
+                    pin
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn resolve_paths(&mut self, base_dir: &Path) {
+        for path in [
+            &mut self.ca_bundle,
+            &mut self.client_cert,
+            &mut self.client_key,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *path = resolve_relative(base_dir, path);
+        }
+    }
+
+    /// The decoded `pin_cert_hash`. Colons between byte pairs are allowed.
+    pub fn pin_bytes(&self) -> Option<[u8; 32]> {
+        let pin = self.pin_cert_hash.as_ref()?.replace(':', "");
+        let mut bytes = [0u8; 32];
+        hex::decode_to_slice(pin.trim(), &mut bytes).ok()?;
+        Some(bytes)
+    }
+}
+
+/// Tuning for the connection pools of upstream clients. Unset fields keep
+/// reqwest's defaults.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Default)]
+pub struct HttpClientConfig {
+    /// Most idle connections kept open to each upstream host. `0` turns
+    /// pooling off.
+    #[serde(default)]
+    pub pool_max_idle_per_host: Option<usize>,
+    /// How long an idle pooled connection is kept before it's closed
+    #[serde(default)]
+    pub pool_idle_timeout_ms: Option<u64>,
+    /// How long to wait for a connection to an upstream to open
+    #[serde(default)]
+    pub connect_timeout_ms: Option<u64>,
+    /// Interval of TCP keepalive probes on upstream connections
+    #[serde(default)]
+    pub tcp_keepalive_ms: Option<u64>,
+    /// Speak HTTP/2 to every upstream without negotiating it first
+    #[serde(default)]
+    pub http2_prior_knowledge: bool,
+}
+
+impl HttpClientConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.connect_timeout_ms == Some(0) {
+            return Err("http_client.connect_timeout_ms must be greater than 0".to_string());
+        }
+        if self.tcp_keepalive_ms == Some(0) {
+            return Err("http_client.tcp_keepalive_ms must be greater than 0".to_string());
+        }
+        if self.pool_idle_timeout_ms == Some(0) && self.pool_max_idle_per_host != Some(0) {
+            return Err(
+                "http_client.pool_idle_timeout_ms must be greater than 0 (set pool_max_idle_per_host: 0 to turn pooling off)"
+                    .to_string(),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Retrying requests the upstream failed to answer
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct RetryConfig {
+    /// Attempts in total, including the first
+    pub max_attempts: u32,
+    /// Wait before the first retry, doubled for each one after
+    #[serde(default = "default_retry_backoff_ms")]
+    pub backoff_ms: u64,
+    /// Which failures are retried
+    #[serde(default = "default_retry_on")]
+    pub retry_on: Vec<RetryOn>,
+    /// Also retry POST and PATCH, for upstreams known to handle replays
+    #[serde(default)]
+    pub retry_non_idempotent: bool,
+}
+
+/// A failure `retry_on` can name
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RetryOn {
+    /// The connection to the upstream couldn't be made
+    #[serde(rename = "connect")]
+    Connect,
+    /// The upstream didn't answer in time
+    #[serde(rename = "timeout")]
+    Timeout,
+    /// The upstream answered with a 5xx status
+    #[serde(rename = "5xx")]
+    ServerError,
+}
+
+impl RetryOn {
+    /// Value of the `reason` label of `upstream_retries_total`
+    pub fn label(self) -> &'static str {
+        match self {
+            RetryOn::Connect => "connect",
+            RetryOn::Timeout => "timeout",
+            RetryOn::ServerError => "5xx",
+        }
+    }
+}
+
+impl RetryConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_attempts == 0 {
+            return Err("retry.max_attempts must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+
+    /// Whether requests with `method` may be retried: idempotent methods
+    /// always, others only with `retry_non_idempotent`
+    pub fn allows(&self, method: &Method) -> bool {
+        self.retry_non_idempotent
+            || matches!(
+                *method,
+                Method::GET
+                    | Method::HEAD
+                    | Method::PUT
+                    | Method::DELETE
+                    | Method::OPTIONS
+                    | Method::TRACE
+            )
+    }
+
+    /// Wait before retry number `retry` (starting at 1)
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 1u64 << retry.saturating_sub(1).min(16);
+        Duration::from_millis(self.backoff_ms.saturating_mul(factor))
+    }
+}
+
+fn default_retry_backoff_ms() -> u64 {
+    100
+}
+
+fn default_retry_on() -> Vec<RetryOn> {
+    vec![RetryOn::Connect, RetryOn::Timeout]
+}
+
+fn validate_response_header_rules(
+    strip: &[String],
+    rename: &HashMap<String, String>,
+) -> Result<(), String> {
+    let names = strip.iter().chain(rename.keys()).chain(rename.values());
+    validate_header_names(names)
+}
+
+fn validate_header_names<'a>(names: impl IntoIterator<Item = &'a String>) -> Result<(), String> {
+    for name in names {
+        if axum::http::HeaderName::from_bytes(name.as_bytes()).is_err() {
+            return Err(format!("Invalid header name: '{}'", name));
+        }
+    }
+    Ok(())
+}
+
+/// `value` with each `${NAME}` replaced by the environment variable `NAME`.
+/// Errors never repeat `value`, which may hold a secret.
+fn expand_env(value: &str) -> Result<String, String> {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start + 2..].find('}') else {
+            return Err("unterminated '${' in a value".to_string());
+        };
+        let name = &rest[start + 2..start + 2 + len];
+        let var = std::env::var(name)
+            .map_err(|_| format!("environment variable '{}' is not set", name))?;
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(&var);
+        rest = &rest[start + 3 + len..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// The files an `includes` entry names, sorted. A plain path must exist; a
+/// glob pattern may match nothing.
+fn expand_include(base_dir: &Path, pattern: &str) -> Result<Vec<PathBuf>, String> {
+    let full_pattern = if Path::new(pattern).is_absolute() {
+        pattern.to_string()
+    } else {
+        let base = glob::Pattern::escape(&base_dir.to_string_lossy());
+        Path::new(&base)
+            .join(pattern)
+            .to_string_lossy()
+            .into_owned()
+    };
+    let paths = glob::glob(&full_pattern)
+        .map_err(|e| format!("Invalid include pattern '{}': {}", pattern, e))?;
+
+    let mut files = Vec::new();
+    for path in paths {
+        let path = path.map_err(|e| {
+            format!(
+                "Failed to read included path '{}': {}",
+                e.path().display(),
+                e.error()
+            )
+        })?;
+        if path.is_file() {
+            files.push(path);
+        }
+    }
+
+    if files.is_empty() {
+        if !pattern.contains(['*', '?', '[']) {
+            return Err(format!(
+                "Included routes file '{}' not found",
+                resolve_relative(base_dir, Path::new(pattern)).display()
+            ));
+        }
+        tracing::warn!(pattern = %pattern, "Include pattern matched no files");
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Parse an included file: a YAML list of routes
+fn load_routes_file(path: &Path) -> Result<Vec<Route>, String> {
+    let contents = fs::read_to_string(path).map_err(|e| {
+        format!(
+            "Failed to read included routes file '{}': {}",
+            path.display(),
+            e
+        )
+    })?;
+    serde_yaml::from_str(&contents).map_err(|e| {
+        format!(
+            "Failed to parse included routes file '{}': {}",
+            path.display(),
+            e
+        )
+    })
+}
+
+/// Join `path` onto `base_dir` unless it's already absolute, dropping `.`
+/// and `..` components lexically so the result reads cleanly in logs.
+pub(crate) fn resolve_relative(base_dir: &Path, path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+
+    let mut resolved = PathBuf::new();
+    for component in base_dir.join(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                // `..` above the root stays at the root
+                if !resolved.pop() && !resolved.has_root() {
+                    resolved.push(component);
+                }
+            }
+            other => resolved.push(other),
+        }
+    }
+    resolved
+}
+
+/// Check that `upstream` is an absolute `http`/`https` URL. Request paths
+/// are appended to it as-is, so a base path is allowed but logged.
+fn validate_upstream_url(upstream: &str) -> Result<(), String> {
+    let invalid = |reason: String| {
+        format!(
+            "Invalid upstream '{}': {} (upstream must be an absolute http:// or https:// URL, or a unix:// socket path)",
+            upstream, reason
+        )
+    };
+    let url = url::Url::parse(upstream).map_err(|e| invalid(e.to_string()))?;
+    if url.scheme() == "unix" {
+        if cfg!(not(unix)) {
+            return Err(invalid(
+                "unix sockets are not supported on this platform".to_string(),
+            ));
+        }
+        if url.path().len() < 2 {
+            return Err(invalid("missing socket path".to_string()));
+        }
+        return Ok(());
+    }
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(invalid(format!("unsupported scheme '{}'", url.scheme())));
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err(invalid("missing host".to_string()));
+    }
+    if url.path() != "/" {
+        tracing::warn!(
+            "upstream '{}' has a base path; request paths will be appended to '{}'",
+            upstream,
+            url.path()
+        );
+    }
+    Ok(())
+}
+
+fn validate_metrics_path(path: &str) -> Result<(), String> {
+    if !path.starts_with('/') || path.len() < 2 {
+        return Err(format!(
+            "global.metrics_path must start with '/' and not be the root: '{}'",
+            path
+        ));
+    }
+    if path.contains(':') || path.contains('*') {
+        return Err(format!(
+            "global.metrics_path must be a literal path without ':' or '*': '{}'",
+            path
+        ));
+    }
+    if path == "/health" || path.starts_with("/health/") {
+        return Err(format!(
+            "global.metrics_path conflicts with the health endpoints: '{}'",
+            path
+        ));
+    }
+    Ok(())
 }
 
 fn default_true() -> bool {
     true
 }
 
-#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+fn default_negative_cache_ttl_secs() -> u64 {
+    5
+}
+
+fn default_metrics_path() -> String {
+    "/metrics".to_string()
+}
+
+fn default_max_redirects() -> usize {
+    10
+}
+
+fn default_max_validation_errors() -> usize {
+    20
+}
+
+fn default_log_request_body_max_bytes() -> usize {
+    4096
+}
+
+fn default_liveness_check_timeout_ms() -> u64 {
+    100
+}
+
+fn default_request_id_header() -> String {
+    "x-request-id".to_string()
+}
+
+fn default_max_field_label_depth() -> usize {
+    3
+}
+
+/// A canned response returned by a route in place of an upstream
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct MockResponse {
+    #[serde(default = "default_mock_status")]
+    pub status: u16,
+    #[serde(default)]
+    pub body: Option<MockBody>,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+impl MockResponse {
+    fn validate(&self) -> Result<(), String> {
+        if !(100..=999).contains(&self.status) {
+            return Err(format!("Invalid mock_response status: {}", self.status));
+        }
+        for (name, value) in &self.headers {
+            if HeaderName::from_bytes(name.as_bytes()).is_err() {
+                return Err(format!("Invalid mock_response header name: {}", name));
+            }
+            if HeaderValue::from_str(value).is_err() {
+                return Err(format!("Invalid mock_response value for header '{}'", name));
+            }
+        }
+        if let Some(MockBody::File { file }) = &self.body {
+            if !file.exists() {
+                return Err(format!(
+                    "mock_response body file does not exist: {}",
+                    file.display()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Body of a mock response: a file sent as-is, or inline JSON
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum MockBody {
+    File {
+        file: PathBuf,
+    },
+    Json(serde_json::Value),
+    /// Sent as-is; only built from OpenAPI examples, never configured
+    #[serde(skip_deserializing)]
+    Text(String),
+}
+
+fn default_mock_status() -> u16 {
+    200
+}
+
+/// An OpenAPI spec whose operations are all routed under `prefix`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct OpenApiMount {
+    pub prefix: String,
+    pub spec: PathBuf,
+    pub upstream: String,
+}
+
+impl OpenApiMount {
+    fn validate(&self) -> Result<(), String> {
+        if !self.prefix.starts_with('/') {
+            return Err(format!("prefix '{}' must start with '/'", self.prefix));
+        }
+        if self.upstream.is_empty() {
+            return Err("upstream cannot be empty".to_string());
+        }
+        validate_upstream_url(&self.upstream)
+    }
+
+    /// The route serving `operation`. Spec paths are matched with the
+    /// prefix stripped, so the spec's `servers` entry is not used.
+    fn route_for(&self, operation: &SpecOperation) -> Route {
+        let prefix = self.prefix.trim_end_matches('/');
+        let template: Vec<String> = operation
+            .path
+            .split('/')
+            .map(
+                |seg| match seg.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+                    Some(name) => format!(":{}", name),
+                    None => seg.to_string(),
+                },
+            )
+            .collect();
+        let template = template.join("/");
+        let path = match template.as_str() {
+            "" | "/" if !prefix.is_empty() => prefix.to_string(),
+            _ => format!("{}{}", prefix, template),
+        };
+
+        Route {
+            path,
+            path_regex: None,
+            method: operation.method.clone(),
+            schema: None,
+            strict_body: false,
+            schema_draft: None,
+            headers_schema: None,
+            openapi: Some(OpenApiSource::Detailed(OpenApiRouteConfig {
+                spec: self.spec.clone(),
+                operation_id: operation.operation_id.clone(),
+                base_path: Some(prefix.to_string()),
+                apply_defaults: false,
+                strict_body: false,
+                enforce_content_type: false,
+            })),
+            upstream: self.upstream.clone(),
+            upstream_path_template: None,
+            mock_response: None,
+            mock: false,
+            allow_upgrade: false,
+            tags: Vec::new(),
+            description: None,
+            config: RouteConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
 #[serde(untagged)]
 pub enum OpenApiSource {
     Spec(PathBuf),
@@ -196,30 +1920,196 @@ impl OpenApiSource {
             OpenApiSource::Spec(path) => OpenApiOptions {
                 spec: path.clone(),
                 operation_id: None,
+                base_path: None,
+                apply_defaults: false,
+                strict_body: false,
+                enforce_content_type: false,
             },
             OpenApiSource::Detailed(cfg) => OpenApiOptions {
                 spec: cfg.spec.clone(),
                 operation_id: cfg.operation_id.clone().filter(|s| !s.trim().is_empty()),
+                base_path: cfg.base_path.clone(),
+                apply_defaults: cfg.apply_defaults,
+                strict_body: cfg.strict_body,
+                enforce_content_type: cfg.enforce_content_type,
             },
         }
     }
 }
 
-#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
 pub struct OpenApiRouteConfig {
     pub spec: PathBuf,
     #[serde(default)]
     pub operation_id: Option<String>,
+    /// Prefix removed from request paths before they are matched against
+    /// the spec. Defaults to the path of the spec's first `servers` URL;
+    /// `""` matches request paths as-is.
+    #[serde(default)]
+    pub base_path: Option<String>,
+    /// Add the spec's `default` for optional query and header parameters
+    /// the client left out before forwarding
+    #[serde(default)]
+    pub apply_defaults: bool,
+    /// Reject request body fields the spec doesn't declare, as if every
+    /// object in the body schema set `additionalProperties: false`
+    #[serde(default)]
+    pub strict_body: bool,
+    /// Reject request bodies whose `Content-Type` isn't one the operation's
+    /// `requestBody` declares
+    #[serde(default)]
+    pub enforce_content_type: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct OpenApiOptions {
     pub spec: PathBuf,
     pub operation_id: Option<String>,
+    pub base_path: Option<String>,
+    pub apply_defaults: bool,
+    pub strict_body: bool,
+    pub enforce_content_type: bool,
 }
 
 impl Route {
     pub fn openapi_options(&self) -> Option<OpenApiOptions> {
         self.openapi.as_ref().map(OpenApiSource::to_options)
     }
+
+    /// Extract named capture groups from `path_regex` as path parameters.
+    /// Returns an empty map for routes using `path`.
+    pub fn path_captures(&self, path: &str, regex: Option<&Regex>) -> HashMap<String, String> {
+        let mut params = HashMap::new();
+        let Some(pattern) = self.path_regex.as_ref() else {
+            return params;
+        };
+
+        let compiled;
+        let regex = match regex {
+            Some(regex) => regex,
+            None => match Regex::new(pattern) {
+                Ok(regex) => {
+                    compiled = regex;
+                    &compiled
+                }
+                Err(_) => return params,
+            },
+        };
+
+        if let Some(captures) = regex.captures(path) {
+            for name in regex.capture_names().flatten() {
+                if let Some(value) = captures.name(name) {
+                    params.insert(name.to_string(), value.as_str().to_string());
+                }
+            }
+        }
+        params
+    }
+}
+
+/// Compiled `path_regex` patterns, built once per config so requests
+/// don't recompile them.
+#[derive(Debug, Clone, Default)]
+pub struct RouteRegexCache {
+    patterns: HashMap<String, Regex>,
+}
+
+impl RouteRegexCache {
+    /// Compile every `path_regex` in the config. Invalid patterns are skipped;
+    /// `Config::validate` reports them.
+    pub fn from_config(config: &Config) -> Self {
+        let mut patterns = HashMap::new();
+        for pattern in config.routes.iter().filter_map(|r| r.path_regex.as_ref()) {
+            if patterns.contains_key(pattern) {
+                continue;
+            }
+            if let Ok(regex) = Regex::new(pattern) {
+                patterns.insert(pattern.clone(), regex);
+            }
+        }
+        Self { patterns }
+    }
+
+    pub fn get(&self, pattern: &str) -> Option<&Regex> {
+        self.patterns.get(pattern)
+    }
+
+    /// Compiled regex for `route`, if it uses `path_regex`.
+    pub fn get_for(&self, route: &Route) -> Option<&Regex> {
+        route.path_regex.as_deref().and_then(|p| self.get(p))
+    }
+}
+
+/// Route lookup table, built once per config. Literal paths are found with
+/// a single hash lookup; only `:param` and `path_regex` routes are scanned.
+/// Config order still decides between a literal route and a pattern route
+/// that both match.
+#[derive(Debug, Clone, Default)]
+pub struct RouteIndex {
+    /// Literal routes keyed by (path, uppercase method)
+    literal: HashMap<(String, String), usize>,
+    /// Indices of pattern routes, in config order
+    patterns: Vec<usize>,
+    /// `case_insensitive_paths`, applied to literal keys and pattern routes
+    ignore_case: bool,
+}
+
+impl RouteIndex {
+    pub fn from_config(config: &Config) -> Self {
+        let ignore_case = config.global.case_insensitive_paths;
+        let mut literal = HashMap::new();
+        let mut patterns = Vec::new();
+        for (idx, route) in config.routes.iter().enumerate() {
+            if route.is_literal() {
+                literal
+                    .entry(Self::literal_key(&route.path, &route.method, ignore_case))
+                    .or_insert(idx);
+            } else {
+                patterns.push(idx);
+            }
+        }
+        Self {
+            literal,
+            patterns,
+            ignore_case,
+        }
+    }
+
+    fn literal_key(path: &str, method: &str, ignore_case: bool) -> (String, String) {
+        let path = trim_trailing_slash(path);
+        let path = if ignore_case {
+            path.to_ascii_lowercase()
+        } else {
+            path.to_string()
+        };
+        (path, method.to_uppercase())
+    }
+
+    /// Find the route for a request. `config` must be the config the index
+    /// was built from.
+    pub fn find<'a>(
+        &self,
+        config: &'a Config,
+        path: &str,
+        method: &str,
+        regexes: &RouteRegexCache,
+    ) -> Option<&'a Route> {
+        let literal = self
+            .literal
+            .get(&Self::literal_key(path, method, self.ignore_case))
+            .copied();
+
+        // A pattern route listed before the literal match still wins
+        let pattern = self
+            .patterns
+            .iter()
+            .copied()
+            .take_while(|&idx| literal.is_none_or(|lit| idx < lit))
+            .find(|&idx| {
+                let route = &config.routes[idx];
+                route.matches_as(path, method, regexes.get_for(route), self.ignore_case)
+            });
+
+        pattern.or(literal).and_then(|idx| config.routes.get(idx))
+    }
 }
@@ -0,0 +1,246 @@
+use axum::http::header::CONTENT_ENCODING;
+use axum::http::HeaderMap;
+use std::fmt;
+use std::io::{self, Read};
+
+/// Cap on a decoded body when no `max_request_body_bytes` is configured, so
+/// a small compressed body can't expand without bound
+pub const DEFAULT_DECODED_LIMIT: usize = 16 * 1024 * 1024;
+
+/// A `Content-Encoding` the gateway can undo to validate a request body
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentEncoding {
+    Identity,
+    Gzip,
+    /// zlib-wrapped DEFLATE, as HTTP's `deflate` is defined. Raw DEFLATE
+    /// streams, which some clients send instead, are accepted too.
+    Deflate,
+    Brotli,
+}
+
+/// Why a body couldn't be decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The decoded body would be larger than the limit
+    TooLarge,
+    /// The body isn't valid data for its encoding
+    Invalid(String),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::TooLarge => write!(f, "decoded body is over the size limit"),
+            DecodeError::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl ContentEncoding {
+    /// The encoding `headers` declare, or the name of the unsupported
+    /// coding(s). `identity` entries are ignored; stacked codings such as
+    /// `gzip, gzip` aren't supported.
+    pub fn from_headers(headers: &HeaderMap) -> Result<Self, String> {
+        let codings: Vec<String> = headers
+            .get_all(CONTENT_ENCODING)
+            .iter()
+            .flat_map(|value| {
+                String::from_utf8_lossy(value.as_bytes())
+                    .split(',')
+                    .map(|coding| coding.trim().to_ascii_lowercase())
+                    .collect::<Vec<_>>()
+            })
+            .filter(|coding| !coding.is_empty() && coding != "identity")
+            .collect();
+        match codings.as_slice() {
+            [] => Ok(ContentEncoding::Identity),
+            [coding] if coding == "gzip" || coding == "x-gzip" => Ok(ContentEncoding::Gzip),
+            [coding] if coding == "deflate" => Ok(ContentEncoding::Deflate),
+            [coding] if coding == "br" => Ok(ContentEncoding::Brotli),
+            _ => Err(codings.join(", ")),
+        }
+    }
+
+    /// `body` decoded, failing once the result is over `limit` bytes, or
+    /// over [`DEFAULT_DECODED_LIMIT`] when there's no limit
+    pub fn decode(self, body: &[u8], limit: Option<usize>) -> Result<Vec<u8>, DecodeError> {
+        let limit = limit.unwrap_or(DEFAULT_DECODED_LIMIT);
+        match self {
+            ContentEncoding::Identity if body.len() > limit => Err(DecodeError::TooLarge),
+            ContentEncoding::Identity => Ok(body.to_vec()),
+            ContentEncoding::Gzip => read_limited(flate2::read::MultiGzDecoder::new(body), limit),
+            ContentEncoding::Deflate if is_zlib(body) => {
+                read_limited(flate2::read::ZlibDecoder::new(body), limit)
+            }
+            ContentEncoding::Deflate => {
+                read_limited(flate2::read::DeflateDecoder::new(body), limit)
+            }
+            ContentEncoding::Brotli => read_limited(brotli::Decompressor::new(body, 4096), limit),
+        }
+    }
+}
+
+/// Whether `data` starts with a zlib header (RFC 1950): DEFLATE with a
+/// window of at most 32K, and a header check that's a multiple of 31
+fn is_zlib(data: &[u8]) -> bool {
+    match data {
+        [cmf, flg, ..] => {
+            cmf & 0x0f == 8 && cmf >> 4 <= 7 && (u16::from(*cmf) << 8 | u16::from(*flg)) % 31 == 0
+        }
+        _ => false,
+    }
+}
+
+/// Everything `decoder` produces, reading at most one byte past `limit` so
+/// an oversized body is noticed without decoding the rest of it
+fn read_limited(decoder: impl Read, limit: usize) -> Result<Vec<u8>, DecodeError> {
+    let mut out = Vec::new();
+    decoder
+        .take(limit as u64 + 1)
+        .read_to_end(&mut out)
+        .map_err(|e| DecodeError::Invalid(describe(&e)))?;
+    if out.len() > limit {
+        return Err(DecodeError::TooLarge);
+    }
+    Ok(out)
+}
+
+fn describe(error: &io::Error) -> String {
+    match error.kind() {
+        io::ErrorKind::UnexpectedEof => "truncated compressed data".to_string(),
+        _ => format!("invalid compressed data: {}", error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const BODY: &[u8] = br#"{"name": "Ada", "tags": ["a", "b", "c"]}"#;
+
+    fn compress(encoding: ContentEncoding, data: &[u8]) -> Vec<u8> {
+        let level = flate2::Compression::best();
+        match encoding {
+            ContentEncoding::Identity => data.to_vec(),
+            ContentEncoding::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), level);
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            }
+            ContentEncoding::Deflate => {
+                let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), level);
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            }
+            ContentEncoding::Brotli => {
+                let mut out = Vec::new();
+                let mut encoder = brotli::CompressorWriter::new(&mut out, 4096, 9, 22);
+                encoder.write_all(data).unwrap();
+                drop(encoder);
+                out
+            }
+        }
+    }
+
+    const COMPRESSED: [ContentEncoding; 3] = [
+        ContentEncoding::Gzip,
+        ContentEncoding::Deflate,
+        ContentEncoding::Brotli,
+    ];
+
+    #[test]
+    fn test_round_trip() {
+        for encoding in COMPRESSED {
+            let compressed = compress(encoding, BODY);
+            assert_eq!(encoding.decode(&compressed, None).unwrap(), BODY);
+        }
+    }
+
+    #[test]
+    fn test_corrupt_input_is_invalid() {
+        // gzip and zlib carry checksums, so any flipped byte is caught
+        for encoding in [ContentEncoding::Gzip, ContentEncoding::Deflate] {
+            let mut corrupt = compress(encoding, BODY);
+            let middle = corrupt.len() / 2;
+            corrupt[middle] ^= 0xff;
+            assert!(
+                matches!(
+                    encoding.decode(&corrupt, None),
+                    Err(DecodeError::Invalid(_))
+                ),
+                "{:?}",
+                encoding
+            );
+        }
+        // Brotli has no checksum; a malformed stream is still rejected
+        for encoding in COMPRESSED {
+            assert!(
+                matches!(
+                    encoding.decode(b"not compressed at all", None),
+                    Err(DecodeError::Invalid(_))
+                ),
+                "{:?}",
+                encoding
+            );
+        }
+    }
+
+    #[test]
+    fn test_truncated_input_is_invalid() {
+        for encoding in COMPRESSED {
+            let compressed = compress(encoding, BODY);
+            for len in [1, compressed.len() / 2, compressed.len() - 1] {
+                assert!(
+                    matches!(
+                        encoding.decode(&compressed[..len], None),
+                        Err(DecodeError::Invalid(_))
+                    ),
+                    "{:?} cut to {} bytes",
+                    encoding,
+                    len
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_ratio_bombs_stop_at_the_limit() {
+        let zeros = vec![0u8; DEFAULT_DECODED_LIMIT + 1];
+        for encoding in COMPRESSED {
+            let bomb = compress(encoding, &zeros);
+            assert!(
+                bomb.len() < 64 * 1024,
+                "{:?} bomb is {} bytes",
+                encoding,
+                bomb.len()
+            );
+            assert_eq!(encoding.decode(&bomb, None), Err(DecodeError::TooLarge));
+            assert_eq!(
+                encoding.decode(&bomb, Some(1024)),
+                Err(DecodeError::TooLarge)
+            );
+        }
+    }
+
+    #[test]
+    fn test_limit_is_inclusive() {
+        for encoding in COMPRESSED {
+            let compressed = compress(encoding, BODY);
+            assert!(encoding.decode(&compressed, Some(BODY.len())).is_ok());
+            assert_eq!(
+                encoding.decode(&compressed, Some(BODY.len() - 1)),
+                Err(DecodeError::TooLarge)
+            );
+        }
+    }
+
+    #[test]
+    fn test_zlib_header_detection() {
+        assert!(is_zlib(&compress(ContentEncoding::Deflate, BODY)));
+        assert!(!is_zlib(&[0x01, 0x0f]));
+        assert!(!is_zlib(&[0x78]));
+    }
+}
@@ -0,0 +1,112 @@
+use parking_lot::Mutex;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::openapi::OpenApiCache;
+
+/// Request validation counts for one OpenAPI operation
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CoverageCounts {
+    pub validated: u64,
+    pub passed: u64,
+    pub failed: u64,
+}
+
+/// Counts of OpenAPI request validations per operation, keyed by spec
+/// path and operation label (`operationId`, or `METHOD /path`), for
+/// finding operations that never see traffic
+#[derive(Debug, Default)]
+pub struct OperationCoverage {
+    counts: Mutex<HashMap<(PathBuf, String), CoverageCounts>>,
+}
+
+impl OperationCoverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, spec: &Path, operation: &str, passed: bool) {
+        let mut counts = self.counts.lock();
+        let entry = counts
+            .entry((spec.to_path_buf(), operation.to_string()))
+            .or_default();
+        entry.validated += 1;
+        if passed {
+            entry.passed += 1;
+        } else {
+            entry.failed += 1;
+        }
+    }
+
+    pub fn get(&self, spec: &Path, operation: &str) -> CoverageCounts {
+        self.counts
+            .lock()
+            .get(&(spec.to_path_buf(), operation.to_string()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Every operation declared in `specs` with its counts, including
+    /// operations nothing was validated against. A spec that can't be
+    /// loaded reports the error and whatever was counted for it.
+    pub fn report(&self, specs: &BTreeSet<PathBuf>, cache: &OpenApiCache) -> Value {
+        let mut counts = self.counts.lock().clone();
+
+        let entries: Vec<Value> = specs
+            .iter()
+            .map(|spec| {
+                let mut operations = Map::new();
+                let mut covered = 0;
+                let listed = cache.list_operations(spec);
+                if let Ok(declared) = &listed {
+                    for operation in declared {
+                        let label = operation.label();
+                        let operation_counts = counts
+                            .remove(&(spec.clone(), label.clone()))
+                            .unwrap_or_default();
+                        if operation_counts.validated > 0 {
+                            covered += 1;
+                        }
+                        let mut entry = counts_json(operation_counts);
+                        entry.insert("method".to_string(), json!(operation.method));
+                        entry.insert("path".to_string(), json!(operation.path));
+                        operations.insert(label, Value::Object(entry));
+                    }
+                }
+                // Operations the spec no longer declares, or all of them
+                // when it didn't load
+                counts.retain(|(counted_spec, label), operation_counts| {
+                    if counted_spec != spec {
+                        return true;
+                    }
+                    operations.insert(label.clone(), Value::Object(counts_json(*operation_counts)));
+                    false
+                });
+
+                let mut entry = json!({
+                    "spec": spec.display().to_string(),
+                    "operations": operations,
+                });
+                match listed {
+                    Ok(declared) => {
+                        entry["covered"] = json!(covered);
+                        entry["total"] = json!(declared.len());
+                    }
+                    Err(e) => entry["error"] = json!(e.to_string()),
+                }
+                entry
+            })
+            .collect();
+
+        json!({ "specs": entries })
+    }
+}
+
+fn counts_json(counts: CoverageCounts) -> Map<String, Value> {
+    let mut entry = Map::new();
+    entry.insert("validated".to_string(), json!(counts.validated));
+    entry.insert("passed".to_string(), json!(counts.passed));
+    entry.insert("failed".to_string(), json!(counts.failed));
+    entry
+}
@@ -1,3 +1,6 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use std::fmt;
 use std::io;
 use std::path::PathBuf;
 
@@ -17,17 +20,95 @@ pub enum Error {
     #[error("Invalid schema syntax in {path}: {message}")]
     InvalidSchemaSyntax { path: PathBuf, message: String },
 
+    #[error("Invalid schema bundle {path}: {message}")]
+    InvalidSchemaBundle { path: PathBuf, message: String },
+
+    #[error(
+        "Schema '{name}' not found in bundle {path}; available: {}",
+        .available.join(", ")
+    )]
+    SchemaNotInBundle {
+        path: PathBuf,
+        name: String,
+        available: Vec<String>,
+    },
+
     #[error("OpenAPI spec not found: {path}")]
     OpenApiNotFound { path: PathBuf },
 
-    #[error("Invalid OpenAPI spec in {path}: {message}")]
-    InvalidOpenApi { path: PathBuf, message: String },
+    #[error("OpenAPI {stage} error in {path}: {message}")]
+    OpenApi {
+        path: PathBuf,
+        stage: OpenApiErrorStage,
+        message: String,
+    },
 
-    #[error("OpenAPI error in {path}: {message}")]
-    OpenApi { path: PathBuf, message: String },
+    /// A recent load failure replayed from the negative cache
+    #[error("{message}")]
+    Cached { path: PathBuf, message: String },
 
     #[error(transparent)]
     Io(#[from] io::Error),
 }
 
+/// Where loading an OpenAPI operation failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OpenApiErrorStage {
+    /// The document isn't valid YAML/JSON or isn't shaped like a spec
+    Parse,
+    /// A schema in the spec doesn't compile
+    Compile,
+    /// No operation matches the route's path, method or `operation_id`
+    OperationNotFound,
+    /// A `$ref` can't be resolved
+    ReferenceNotFound,
+}
+
+impl fmt::Display for OpenApiErrorStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            OpenApiErrorStage::Parse => "parse",
+            OpenApiErrorStage::Compile => "compile",
+            OpenApiErrorStage::OperationNotFound => "operation lookup",
+            OpenApiErrorStage::ReferenceNotFound => "reference",
+        };
+        f.write_str(label)
+    }
+}
+
 pub type Result<T> = std::result::Result<T, Error>;
+
+/// The gateway's JSON error body: `{"error": "<message>"}`
+pub fn error_response(status: StatusCode, message: &str) -> Response {
+    (status, serde_json::json!({ "error": message }).to_string()).into_response()
+}
+
+/// `error_response` with the body indented, for `pretty_print_errors`
+pub fn pretty_error_response(status: StatusCode, message: &str) -> Response {
+    let body = serde_json::json!({ "error": message });
+    let text = serde_json::to_string_pretty(&body).unwrap_or_else(|_| body.to_string());
+    (status, text).into_response()
+}
+
+/// The error body for a failed validation, listing the errors:
+/// `{"error": "<message>", "errors": [...], "error_count": N}`. `error_count`
+/// includes errors cut from a truncated `errors` list.
+pub fn validation_error_response(
+    status: StatusCode,
+    message: &str,
+    errors: &[String],
+    error_count: usize,
+    pretty: bool,
+) -> Response {
+    let body = serde_json::json!({
+        "error": message,
+        "errors": errors,
+        "error_count": error_count,
+    });
+    let text = if pretty {
+        serde_json::to_string_pretty(&body).unwrap_or_else(|_| body.to_string())
+    } else {
+        body.to_string()
+    };
+    (status, text).into_response()
+}
@@ -0,0 +1,200 @@
+use serde_json::{Map, Value};
+
+/// How deep `generate` follows nested schemas before giving up with `null`,
+/// so recursive schemas terminate
+const MAX_DEPTH: usize = 16;
+
+/// A small instance of `schema`, for answering requests without an
+/// upstream. Uses the schema's own `example`, `default`, `const` or first
+/// `enum` value where it has one; otherwise objects get their `required`
+/// properties, arrays `minItems` items, and scalars the smallest value
+/// their keywords allow. Local `$ref`s (as left by inlining recursive
+/// schemas) are followed.
+pub fn generate(schema: &Value) -> Value {
+    generate_at(schema, schema, 0)
+}
+
+fn generate_at(schema: &Value, root: &Value, depth: usize) -> Value {
+    let Some(object) = schema.as_object() else {
+        // `true` or `{}`-like schemas accept anything
+        return Value::Null;
+    };
+    if depth > MAX_DEPTH {
+        return Value::Null;
+    }
+
+    if let Some(reference) = object.get("$ref").and_then(Value::as_str) {
+        return match reference
+            .strip_prefix('#')
+            .and_then(|pointer| root.pointer(pointer))
+        {
+            Some(target) => generate_at(target, root, depth + 1),
+            None => Value::Null,
+        };
+    }
+
+    for keyword in ["example", "default", "const"] {
+        if let Some(value) = object.get(keyword) {
+            return value.clone();
+        }
+    }
+    if let Some(value) = object
+        .get("examples")
+        .and_then(Value::as_array)
+        .and_then(|v| v.first())
+    {
+        return value.clone();
+    }
+    if let Some(value) = object
+        .get("enum")
+        .and_then(Value::as_array)
+        .and_then(|v| v.first())
+    {
+        return value.clone();
+    }
+
+    if let Some(parts) = object.get("allOf").and_then(Value::as_array) {
+        let mut merged = Map::new();
+        for part in parts {
+            match generate_at(part, root, depth + 1) {
+                Value::Object(properties) => merged.extend(properties),
+                // A non-object part decides the value on its own
+                other if !other.is_null() => return other,
+                _ => {}
+            }
+        }
+        return Value::Object(merged);
+    }
+    for keyword in ["oneOf", "anyOf"] {
+        if let Some(first) = object
+            .get(keyword)
+            .and_then(Value::as_array)
+            .and_then(|v| v.first())
+        {
+            return generate_at(first, root, depth + 1);
+        }
+    }
+
+    match schema_type(object) {
+        Some("object") => {
+            let properties = object.get("properties").and_then(Value::as_object);
+            let required = object.get("required").and_then(Value::as_array);
+            let instance = required
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .map(|name| {
+                    let value = properties
+                        .and_then(|properties| properties.get(name))
+                        .map(|property| generate_at(property, root, depth + 1))
+                        .unwrap_or(Value::Null);
+                    (name.to_string(), value)
+                })
+                .collect();
+            Value::Object(instance)
+        }
+        Some("array") => {
+            let count = object.get("minItems").and_then(Value::as_u64).unwrap_or(0);
+            let item = object
+                .get("items")
+                .map(|items| generate_at(items, root, depth + 1))
+                .unwrap_or(Value::Null);
+            Value::Array(vec![item; count as usize])
+        }
+        Some("string") => Value::String(string_instance(object)),
+        Some("integer") => number_instance(object, true),
+        Some("number") => number_instance(object, false),
+        Some("boolean") => Value::Bool(false),
+        _ => Value::Null,
+    }
+}
+
+/// The declared type, taking the first non-`null` one from a list. Inferred
+/// from `properties` or `items` when `type` is missing.
+fn schema_type(object: &Map<String, Value>) -> Option<&str> {
+    match object.get("type") {
+        Some(Value::String(name)) => Some(name),
+        Some(Value::Array(names)) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|name| *name != "null"),
+        _ if object.contains_key("properties") => Some("object"),
+        _ if object.contains_key("items") => Some("array"),
+        _ => None,
+    }
+}
+
+fn string_instance(object: &Map<String, Value>) -> String {
+    let formatted = match object.get("format").and_then(Value::as_str) {
+        Some("date-time") => Some("1970-01-01T00:00:00Z"),
+        Some("date") => Some("1970-01-01"),
+        Some("time") => Some("00:00:00Z"),
+        Some("email") => Some("user@example.com"),
+        Some("uuid") => Some("00000000-0000-0000-0000-000000000000"),
+        Some("uri") | Some("url") => Some("https://example.com"),
+        Some("hostname") => Some("example.com"),
+        Some("ipv4") => Some("127.0.0.1"),
+        Some("ipv6") => Some("::1"),
+        _ => None,
+    };
+    if let Some(value) = formatted {
+        return value.to_string();
+    }
+
+    let min = object.get("minLength").and_then(Value::as_u64).unwrap_or(0) as usize;
+    let max = object
+        .get("maxLength")
+        .and_then(Value::as_u64)
+        .map(|max| max as usize);
+    let mut value = "string".to_string();
+    if value.len() < min {
+        value.push_str(&"x".repeat(min - value.len()));
+    }
+    if let Some(max) = max {
+        value.truncate(max);
+    }
+    value
+}
+
+fn number_instance(object: &Map<String, Value>, integer: bool) -> Value {
+    let bound = |key: &str| object.get(key).and_then(Value::as_f64);
+    // OpenAPI 3.0 spells exclusive bounds as booleans next to the bound
+    let exclusive = |key: &str| object.get(key).and_then(Value::as_bool).unwrap_or(false);
+
+    let mut value = 0.0;
+    // Integers round away from the bound the value came from
+    let mut below_maximum = false;
+    if let Some(minimum) = bound("minimum") {
+        value = if exclusive("exclusiveMinimum") {
+            minimum + 1.0
+        } else {
+            minimum
+        };
+    } else if let Some(minimum) = bound("exclusiveMinimum") {
+        value = minimum + 1.0;
+    } else if let Some(maximum) = bound("maximum") {
+        if exclusive("exclusiveMaximum") && maximum <= 0.0 {
+            value = maximum - 1.0;
+            below_maximum = true;
+        } else if maximum < 0.0 {
+            value = maximum;
+            below_maximum = true;
+        }
+    } else if let Some(maximum) = bound("exclusiveMaximum") {
+        if maximum <= 0.0 {
+            value = maximum - 1.0;
+            below_maximum = true;
+        }
+    }
+    if integer {
+        value = if below_maximum {
+            value.floor()
+        } else {
+            value.ceil()
+        };
+        return Value::from(value as i64);
+    }
+    serde_json::Number::from_f64(value)
+        .map(Value::Number)
+        .unwrap_or(Value::Null)
+}
@@ -1,35 +1,313 @@
-use axum::body::Body;
+use arc_swap::ArcSwap;
+use axum::body::{Body, Bytes, HttpBody};
 use axum::extract::State;
-use axum::http::{header::CONTENT_TYPE, HeaderMap, HeaderValue, Method, StatusCode};
+use axum::http::{
+    header::{ALLOW, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, RETRY_AFTER},
+    HeaderMap, HeaderName, HeaderValue, Method, StatusCode,
+};
 use axum::response::{IntoResponse, Response};
-use jsonschema::JSONSchema;
+use hyper::body::Frame;
+use hyper::upgrade::OnUpgrade;
+use jsonschema::{error::ValidationErrorKind, ValidationError};
+use parking_lot::RwLock;
 use serde_json::Value;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use std::sync::Arc;
-use std::time::Instant;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tracing::Instrument;
 use url::form_urlencoded;
 
-use crate::config::{Config, GlobalConfig, OpenApiOptions};
+use crate::body_hash::{self, BodyHashAlgorithm, BODY_HASH_HEADER};
+use crate::config::{
+    Config, GlobalConfig, HttpClientConfig, MockResponse, OpenApiOptions, RetryConfig, RetryOn,
+    RouteConfig, RouteIndex, RouteRegexCache, UpstreamAuthConfig, UpstreamTlsConfig,
+};
+use crate::content_encoding::{ContentEncoding, DecodeError};
+use crate::error::{error_response, pretty_error_response, validation_error_response, Error};
 use crate::metrics::Metrics;
-use crate::openapi::{OpenApiCache, OperationValidationPlan, ParameterLocation, ResponseKey};
-use crate::proxy::forward_request;
-use crate::schema::SchemaCache;
-use crate::validation::validate;
+use crate::openapi::{
+    build_header_lookup, parameter_location_label, parse_cookie_header, parse_query_params,
+    FormPart, MultipartForm, OpenApiCache, OperationValidationPlan, ParameterLocation, ResponseKey,
+    SecurityRequirement, SecurityScheme,
+};
+use crate::proxy::{
+    cap_response_body, is_upgrade_request, mock_response, strip_trace_context, try_forward_request,
+    tunnel_upgrade, ForwardOptions, ResponseTooLarge, UpstreamBody, UpstreamErrorKind,
+    UpstreamFailure,
+};
+use crate::rate_limiter::{RateLimiter, RateLimiters};
+use crate::schema::{SchemaCache, SchemaOptions};
+use crate::tls::upstream_tls_config;
+use crate::validation::{validate_with_limit, ValidationResult};
+
+/// Immutable routing configuration. Replaced as a whole on reload so a
+/// request always sees a consistent config and compiled route patterns.
+pub struct RoutingConfig {
+    pub config: Config,
+    pub route_regexes: RouteRegexCache,
+    pub route_index: RouteIndex,
+    pub upstream_clients: UpstreamClients,
+    pub upstream_auth: UpstreamAuths,
+    /// Token buckets live with the config they were built from, so every
+    /// reload starts all rate limits over with full buckets
+    pub rate_limiters: RateLimiters,
+}
+
+impl RoutingConfig {
+    pub fn new(config: Config) -> Self {
+        Self {
+            route_regexes: RouteRegexCache::from_config(&config),
+            route_index: RouteIndex::from_config(&config),
+            upstream_clients: UpstreamClients::from_config(&config),
+            upstream_auth: UpstreamAuths::from_config(&config),
+            rate_limiters: RateLimiters::from_config(&config),
+            config,
+        }
+    }
+}
+
+/// The settings an upstream client is built with beyond `http_client`.
+/// The default value stands for the shared `AppState::http_client`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct UpstreamClientKey {
+    pub tls: Option<UpstreamTlsConfig>,
+    /// Speak HTTP/2 from the first byte (`upstream_http2`)
+    pub h2c: bool,
+    /// `max_redirects`, for routes with `follow_redirects`
+    pub max_redirects: Option<usize>,
+}
+
+impl UpstreamClientKey {
+    /// The client settings for requests to `upstream` under `config`
+    pub fn for_upstream(upstream: &str, config: &GlobalConfig) -> Self {
+        // TLS settings don't apply to cleartext upstreams, so h2c wins
+        let h2c = config.upstream_http2 && upstream.starts_with("http://");
+        Self {
+            tls: config.upstream_tls.clone().filter(|_| !h2c),
+            h2c,
+            max_redirects: config.follow_redirects.then_some(config.max_redirects),
+        }
+    }
+
+    /// Build a client with these settings on top of `http`.
+    ///
+    /// Certificate and key files are read here, so a client should be
+    /// built per distinct key rather than per request.
+    pub fn build(&self, http: &HttpClientConfig) -> Result<reqwest::Client, String> {
+        let mut builder = upstream_client_builder(http);
+        if self.h2c {
+            builder = builder.http2_prior_knowledge();
+        }
+        if let Some(max) = self.max_redirects {
+            // reqwest counts the original URL as a hop
+            builder = builder.redirect(reqwest::redirect::Policy::limited(max + 1));
+        }
+        if let Some(tls) = &self.tls {
+            builder = builder.use_preconfigured_tls(upstream_tls_config(tls)?);
+        }
+        builder
+            .build()
+            .map_err(|e| format!("Failed to build upstream client: {}", e))
+    }
+}
+
+/// HTTP clients for upstreams needing other settings than the shared
+/// client (`upstream_tls`, `upstream_http2` or `follow_redirects`), one per
+/// distinct combination. Built with the config so certificates are read at
+/// startup and reload, not per request.
+pub struct UpstreamClients {
+    clients: HashMap<UpstreamClientKey, Result<reqwest::Client, String>>,
+}
+
+impl UpstreamClients {
+    pub fn from_config(config: &Config) -> Self {
+        let keys = config.all_routes().map(|route| {
+            UpstreamClientKey::for_upstream(&route.upstream, &config.get_effective_config(route))
+        });
 
+        let mut clients = HashMap::new();
+        for key in keys {
+            if key == UpstreamClientKey::default() || clients.contains_key(&key) {
+                continue;
+            }
+            let client = key.build(&config.global.http_client);
+            if let Err(e) = &client {
+                tracing::error!(error = %e, "Failed to build upstream client");
+            }
+            clients.insert(key, client);
+        }
+        Self { clients }
+    }
+
+    /// The client for `key`, or why it couldn't be built
+    pub fn get(&self, key: &UpstreamClientKey) -> Result<&reqwest::Client, String> {
+        match self.clients.get(key) {
+            Some(Ok(client)) => Ok(client),
+            Some(Err(e)) => Err(e.clone()),
+            None => Err("No upstream client for this configuration".to_string()),
+        }
+    }
+
+    /// The first client that failed to build, if any
+    pub fn first_error(&self) -> Option<&str> {
+        self.clients
+            .values()
+            .find_map(|client| client.as_ref().err())
+            .map(String::as_str)
+    }
+}
+
+/// The `Authorization` value for every `upstream_auth` in the config,
+/// resolved when it's loaded so environment variables are read and
+/// credentials encoded once rather than per request
+pub struct UpstreamAuths {
+    values: HashMap<UpstreamAuthConfig, HeaderValue>,
+}
+
+impl UpstreamAuths {
+    pub fn from_config(config: &Config) -> Self {
+        let mut values = HashMap::new();
+        for route in config.all_routes() {
+            let Some(auth) = config.get_effective_config(route).upstream_auth else {
+                continue;
+            };
+            if values.contains_key(&auth) {
+                continue;
+            }
+            match auth.header_value() {
+                Ok(value) => {
+                    values.insert(auth, value);
+                }
+                Err(e) => tracing::error!(
+                    route = %route.pattern(),
+                    error = %e,
+                    "Not sending upstream_auth"
+                ),
+            }
+        }
+        Self { values }
+    }
+
+    /// The value `auth` resolved to, or `None` if it couldn't be built
+    pub fn get(&self, auth: &UpstreamAuthConfig) -> Option<HeaderValue> {
+        self.values.get(auth).cloned()
+    }
+}
+
+/// Shared gateway state. Each part is independently shareable so routing
+/// lookups are a single atomic load and cache access never blocks on config.
 pub struct AppState {
-    pub config: Config,
-    pub schema_cache: SchemaCache,
-    pub openapi_cache: OpenApiCache,
+    pub routing: Arc<ArcSwap<RoutingConfig>>,
+    pub schema_cache: Arc<RwLock<SchemaCache>>,
+    pub openapi_cache: Arc<RwLock<OpenApiCache>>,
     pub http_client: reqwest::Client,
     pub metrics: Arc<Metrics>,
+    /// Set by `--dev`: pretty-print JSON responses and errors, and include
+    /// schema paths and values in validation errors
+    pub dev_mode: bool,
+}
+
+impl AppState {
+    /// Build state for `config`, sizing the schema caches from its global settings.
+    pub fn new(config: Config, http_client: reqwest::Client, metrics: Arc<Metrics>) -> Self {
+        Self {
+            schema_cache: Arc::new(RwLock::new(build_schema_cache(&config))),
+            openapi_cache: Arc::new(RwLock::new(build_openapi_cache(&config, &metrics))),
+            routing: Arc::new(ArcSwap::from_pointee(RoutingConfig::new(config))),
+            http_client,
+            metrics,
+            dev_mode: false,
+        }
+    }
+
+    /// Turn development mode on or off
+    pub fn with_dev_mode(mut self, dev_mode: bool) -> Self {
+        self.dev_mode = dev_mode;
+        self
+    }
+
+    /// Use `schema_cache`, e.g. one preloaded with the config's schemas
+    pub fn with_schema_cache(self, schema_cache: SchemaCache) -> Self {
+        *self.schema_cache.write() = schema_cache;
+        self
+    }
+
+    /// Snapshot of the current routing config
+    pub fn routing(&self) -> Arc<RoutingConfig> {
+        self.routing.load_full()
+    }
+
+    /// Swap in a new config and start from empty schema caches, since schema
+    /// files may have changed alongside it. The running config is kept if an
+    /// upstream TLS client can't be built for the new one.
+    pub fn replace_config(&self, config: Config) -> Result<(), String> {
+        let routing = RoutingConfig::new(config);
+        if let Some(e) = routing.upstream_clients.first_error() {
+            return Err(e.to_string());
+        }
+        let schema_cache = build_schema_cache(&routing.config);
+        let openapi_cache = build_openapi_cache(&routing.config, &self.metrics);
+        self.routing.store(Arc::new(routing));
+        *self.schema_cache.write() = schema_cache;
+        *self.openapi_cache.write() = openapi_cache;
+        Ok(())
+    }
+
+    /// Start from an empty JSON schema cache, so schema files are read again
+    pub fn reset_schema_cache(&self) {
+        *self.schema_cache.write() = build_schema_cache(&self.routing().config);
+    }
+
+    /// Start from an empty OpenAPI cache, so specs are parsed again
+    pub fn reset_openapi_cache(&self) {
+        *self.openapi_cache.write() = build_openapi_cache(&self.routing().config, &self.metrics);
+    }
+}
+
+/// An empty schema cache sized and configured from `config`'s global settings
+pub fn build_schema_cache(config: &Config) -> SchemaCache {
+    SchemaCache::with_max_entries(config.global.schema_cache_max_entries)
+        .with_negative_ttl(config.global.negative_cache_ttl())
+}
+
+fn build_openapi_cache(config: &Config, metrics: &Metrics) -> OpenApiCache {
+    OpenApiCache::with_max_entries(config.global.schema_cache_max_entries)
+        .with_negative_ttl(config.global.negative_cache_ttl())
+        .with_case_insensitive_paths(config.global.case_insensitive_paths)
+        .with_metrics(metrics)
 }
 
-/// Build a reqwest client suitable for the gateway.
+/// A reqwest client builder for upstream connections, with the pool and
+/// socket settings from `config` applied.
 /// We disable system proxy lookups to avoid platform-specific panics in tests.
-pub fn build_http_client() -> reqwest::Client {
-    reqwest::Client::builder()
-        .no_proxy()
+pub fn upstream_client_builder(config: &HttpClientConfig) -> reqwest::ClientBuilder {
+    let mut builder = reqwest::Client::builder().no_proxy();
+    if let Some(max_idle) = config.pool_max_idle_per_host {
+        builder = builder.pool_max_idle_per_host(max_idle);
+    }
+    if let Some(idle_timeout) = config.pool_idle_timeout_ms {
+        builder = builder.pool_idle_timeout(Duration::from_millis(idle_timeout));
+    }
+    if let Some(connect_timeout) = config.connect_timeout_ms {
+        builder = builder.connect_timeout(Duration::from_millis(connect_timeout));
+    }
+    if let Some(keepalive) = config.tcp_keepalive_ms {
+        builder = builder.tcp_keepalive(Duration::from_millis(keepalive));
+    }
+    if config.http2_prior_knowledge {
+        builder = builder.http2_prior_knowledge();
+    }
+    // Redirects are passed to the client unless a route follows them
+    builder.redirect(reqwest::redirect::Policy::none())
+}
+
+/// Build a reqwest client suitable for the gateway
+pub fn build_http_client(config: &HttpClientConfig) -> reqwest::Client {
+    upstream_client_builder(config)
         .build()
         .expect("Failed to build HTTP client")
 }
@@ -39,55 +317,455 @@ struct RequestContext {
     method: Method,
     upstream_url: String,
     path: String,
+    /// `path`, or the route's `upstream_path_template` filled in
+    upstream_path: String,
     path_and_query: String,
     query: Option<String>,
     headers: HeaderMap,
+    /// The request body, decoded if the client compressed it
     body_bytes: Vec<u8>,
+    /// The body as the client compressed it, forwarded in place of a
+    /// buffered `body_bytes`
+    encoded_body: Option<Vec<u8>>,
     route_pattern: String,
+    /// Value of the `route_tags` metrics label
+    route_tags: String,
+    /// The route's `description`, logged with its warnings
+    route_description: String,
+    /// Named captures from the route's `path_regex`, if any.
+    path_params: HashMap<String, String>,
+    forward_options: ForwardOptions,
+    /// Client for this route's upstream, honouring its `upstream_tls`
+    http_client: reqwest::Client,
+    /// Answer with this instead of forwarding, if set
+    mock_response: Option<MockResponse>,
+    /// Digest sent upstream as `X-Body-Hash`, if any
+    body_hash: Option<BodyHashAlgorithm>,
+    verify_response_hash: bool,
+    /// Pass `traceparent`, `tracestate` and `baggage` on to the upstream
+    propagate_trace_context: bool,
+    /// Pretty-print JSON responses before returning them
+    dev_mode: bool,
+    /// How failed upstream requests are retried, if at all
+    retry: Option<RetryConfig>,
+    /// Largest upstream response body passed on, if limited
+    max_response_bytes: Option<usize>,
+    /// Leave the upstream host out of upstream failure responses
+    redact_upstream_in_errors: bool,
+    /// Add `X-Upstream-Duration-Ms` to forwarded responses
+    timing_headers: bool,
+    metrics: Arc<Metrics>,
+    /// When `handle_request` started; request durations are measured from it
+    start_time: Instant,
+}
+
+impl RequestContext {
+    /// Send the request upstream, or answer it from the route's mock response
+    async fn send(&self, mut headers: HeaderMap, body: impl Into<UpstreamBody>) -> Response {
+        let body = match (body.into(), &self.encoded_body) {
+            (UpstreamBody::Buffered(_), Some(encoded)) => UpstreamBody::Buffered(encoded.clone()),
+            (body, _) => body,
+        };
+        if let (Some(algorithm), UpstreamBody::Buffered(bytes)) = (self.body_hash, &body) {
+            if let Ok(value) = HeaderValue::from_str(&algorithm.header_value(bytes)) {
+                headers.insert(BODY_HASH_HEADER, value);
+            }
+        }
+        if !self.propagate_trace_context {
+            strip_trace_context(&mut headers);
+        }
+
+        let mut upstream_duration = None;
+        let response = match &self.mock_response {
+            Some(mock) => mock_response(mock, &self.method).await,
+            None => {
+                // Until the upstream's headers arrive, retries included
+                let upstream_start = Instant::now();
+                let mut response = self.forward(headers, body).await;
+                let elapsed = upstream_start.elapsed();
+                self.metrics
+                    .upstream_request_duration_seconds
+                    .with_label_values(&[&self.route_pattern, &self.route_tags])
+                    .observe(elapsed.as_secs_f64());
+                upstream_duration = Some(elapsed);
+                if let Some(limit) = self.max_response_bytes {
+                    response = cap_response_body(response, limit);
+                }
+                if self.verify_response_hash && self.method != Method::HEAD {
+                    check_response_hash(response, self).await
+                } else {
+                    response
+                }
+            }
+        };
+        let mut response = if self.dev_mode && has_json_content_type(response.headers()) {
+            pretty_print_json(response).await
+        } else {
+            response
+        };
+        if let (true, Some(elapsed)) = (self.timing_headers, upstream_duration) {
+            response.headers_mut().insert(
+                UPSTREAM_DURATION_HEADER,
+                HeaderValue::from(elapsed.as_millis() as u64),
+            );
+        }
+        response
+    }
+
+    /// Forward the request, retrying failures as the route's `retry`
+    /// settings allow. Only buffered bodies can be sent again.
+    async fn forward(&self, headers: HeaderMap, body: UpstreamBody) -> Response {
+        // A known-empty stream (e.g. a plain GET) has nothing to replay
+        let body = match body {
+            UpstreamBody::Streaming(stream) if stream.size_hint().exact() == Some(0) => {
+                UpstreamBody::Buffered(Vec::new())
+            }
+            body => body,
+        };
+        let (retry, bytes) = match (&self.retry, body) {
+            (Some(retry), UpstreamBody::Buffered(bytes)) if retry.allows(&self.method) => {
+                (retry, bytes)
+            }
+            (_, body) => {
+                return try_forward_request(
+                    &self.http_client,
+                    self.method.clone(),
+                    &self.upstream_url,
+                    &self.path_and_query,
+                    headers,
+                    body,
+                    &self.forward_options,
+                )
+                .await
+                .unwrap_or_else(|failure| self.upstream_failure(failure))
+            }
+        };
+
+        let mut attempt = 1;
+        loop {
+            let result = try_forward_request(
+                &self.http_client,
+                self.method.clone(),
+                &self.upstream_url,
+                &self.path_and_query,
+                headers.clone(),
+                bytes.clone(),
+                &self.forward_options,
+            )
+            .await;
+            let failure = match &result {
+                Err(failure) => match failure.kind {
+                    UpstreamErrorKind::Connect
+                    | UpstreamErrorKind::Dns
+                    | UpstreamErrorKind::Tls => Some(RetryOn::Connect),
+                    UpstreamErrorKind::Timeout => Some(RetryOn::Timeout),
+                    UpstreamErrorKind::Other => None,
+                },
+                Ok(response) if response.status().is_server_error() => Some(RetryOn::ServerError),
+                Ok(_) => None,
+            };
+            match failure {
+                Some(reason)
+                    if attempt < retry.max_attempts && retry.retry_on.contains(&reason) =>
+                {
+                    let delay = retry.backoff(attempt);
+                    tracing::debug!(
+                        method = %self.method,
+                        path = %self.path,
+                        upstream = %self.upstream_url,
+                        reason = reason.label(),
+                        attempt,
+                        delay_ms = delay.as_millis() as u64,
+                        "Retrying upstream request"
+                    );
+                    self.metrics
+                        .upstream_retries_total
+                        .with_label_values(&[&self.route_pattern, &self.route_tags, reason.label()])
+                        .inc();
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                _ => return result.unwrap_or_else(|failure| self.upstream_failure(failure)),
+            }
+        }
+    }
+
+    fn upstream_failure(&self, failure: UpstreamFailure) -> Response {
+        upstream_failure_response(
+            failure,
+            &self.method,
+            &self.path,
+            &self.route_description,
+            self.redact_upstream_in_errors,
+            &self.metrics,
+        )
+    }
+}
+
+/// Log and count an upstream request that got no response, with its full
+/// error, and build the client's 502 or 504 for it
+fn upstream_failure_response(
+    failure: UpstreamFailure,
+    method: &Method,
+    path: &str,
+    route_description: &str,
+    redact_upstream: bool,
+    metrics: &Metrics,
+) -> Response {
+    metrics
+        .upstream_errors_total
+        .with_label_values(&[failure.kind.label()])
+        .inc();
+    tracing::error!(
+        method = %method,
+        path = %path,
+        route_description = %route_description,
+        upstream_url = %failure.url,
+        kind = failure.kind.label(),
+        error = %failure.detail,
+        "Upstream request failed"
+    );
+    failure.error_response(!redact_upstream)
+}
+
+/// Why an upstream response body couldn't be read, for the 502 sent instead
+fn unreadable_body_message(error: axum::Error) -> String {
+    // `to_bytes` wraps the body's error once more, so walk the whole chain
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(&error);
+    while let Some(error) = source {
+        if let Some(too_large) = error.downcast_ref::<ResponseTooLarge>() {
+            return too_large.to_string();
+        }
+        source = error.source();
+    }
+    "Failed to read upstream response body".to_string()
+}
+
+/// Re-indent a JSON response body. Bodies that don't parse are returned
+/// unchanged.
+async fn pretty_print_json(response: Response) -> Response {
+    let (mut parts, body) = response.into_parts();
+    let body_bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => return error_response(StatusCode::BAD_GATEWAY, &unreadable_body_message(e)),
+    };
+    let pretty = serde_json::from_slice::<Value>(&body_bytes)
+        .ok()
+        .and_then(|json| serde_json::to_vec_pretty(&json).ok());
+    match pretty {
+        Some(pretty) => {
+            parts.headers.remove(CONTENT_LENGTH);
+            Response::from_parts(parts, Body::from(pretty))
+        }
+        None => Response::from_parts(parts, Body::from(body_bytes)),
+    }
+}
+
+/// Check the upstream response body against its `X-Body-Hash`, if it sent
+/// one. A mismatch means the body was altered on the way, so it's never
+/// passed on.
+async fn check_response_hash(response: Response, ctx: &RequestContext) -> Response {
+    let Some(expected) = response
+        .headers()
+        .get(BODY_HASH_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+    else {
+        return response;
+    };
+
+    let (parts, body) = response.into_parts();
+    let body_bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => return error_response(StatusCode::BAD_GATEWAY, &unreadable_body_message(e)),
+    };
+
+    match body_hash::verify(&expected, &body_bytes) {
+        Ok(()) => Response::from_parts(parts, Body::from(body_bytes)),
+        Err(error_msg) => {
+            tracing::warn!(
+                method = %ctx.method,
+                path = %ctx.path,
+                route_description = %ctx.route_description,
+                upstream = %ctx.upstream_url,
+                error = %error_msg,
+                "Upstream response failed body hash check"
+            );
+            error_response(StatusCode::BAD_GATEWAY, &error_msg)
+        }
+    }
 }
 
 /// Main request handler for the gateway
 pub async fn handle_request(
+    state: State<Arc<tokio::sync::RwLock<AppState>>>,
+    method: Method,
+    uri: axum::http::Uri,
+    headers: HeaderMap,
+    body: Body,
+) -> Response {
+    handle_request_from(state, None, method, uri, headers, body).await
+}
+
+/// `handle_request` for a client connected from `peer`, which rate limits
+/// keyed by IP count against
+pub async fn handle_request_from(
+    state: State<Arc<tokio::sync::RwLock<AppState>>>,
+    peer: Option<SocketAddr>,
+    method: Method,
+    uri: axum::http::Uri,
+    headers: HeaderMap,
+    body: Body,
+) -> Response {
+    handle_upgradable_request(state, peer, None, method, uri, headers, body).await
+}
+
+/// `handle_request_from`, with the connection's pending upgrade so routes
+/// with `allow_upgrade` can tunnel it
+pub async fn handle_upgradable_request(
     State(state): State<Arc<tokio::sync::RwLock<AppState>>>,
+    peer: Option<SocketAddr>,
+    upgrade: Option<OnUpgrade>,
     method: Method,
     uri: axum::http::Uri,
     headers: HeaderMap,
     body: Body,
 ) -> Response {
     let start_time = Instant::now();
+    let (report_duration, request_id_header) = {
+        let state_guard = state.read().await;
+        let global = &state_guard.routing().config.global;
+        (
+            global.report_duration_header,
+            HeaderName::from_bytes(global.request_id_header.as_bytes())
+                .unwrap_or(HeaderName::from_static(DEFAULT_REQUEST_ID_HEADER)),
+        )
+    };
+
+    let mut headers = headers;
+    let request_id = request_id(&headers, &request_id_header);
+    headers.insert(request_id_header.clone(), request_id.clone());
+    let span = tracing::info_span!(
+        "request",
+        request_id = %String::from_utf8_lossy(request_id.as_bytes())
+    );
+
+    let mut response = route_request(state, peer, upgrade, method, uri, headers, body, start_time)
+        .instrument(span)
+        .await;
+    response.headers_mut().insert(request_id_header, request_id);
+    if report_duration {
+        let millis = start_time.elapsed().as_millis();
+        response
+            .headers_mut()
+            .insert(DURATION_HEADER, HeaderValue::from(millis as u64));
+    }
+    response
+}
+
+/// The client's request ID from `header`, or a new UUID when it didn't send
+/// a usable one
+fn request_id(headers: &HeaderMap, header: &HeaderName) -> HeaderValue {
+    headers
+        .get(header)
+        .filter(|value| {
+            let len = value.as_bytes().len();
+            (1..=MAX_REQUEST_ID_LENGTH).contains(&len) && value.to_str().is_ok()
+        })
+        .cloned()
+        .unwrap_or_else(|| {
+            HeaderValue::from_str(&uuid::Uuid::new_v4().to_string())
+                .expect("UUIDs are valid header values")
+        })
+}
+
+/// `openapi_spec_version` label for OpenAPI validations whose spec couldn't
+/// be loaded
+const UNKNOWN_SPEC_VERSION: &str = "unknown";
+
+/// Route label of requests handled by `default_route`
+pub const DEFAULT_ROUTE_LABEL: &str = "default_route";
+
+/// Pattern of the route that handled a request, with `debug_headers`
+pub const ROUTE_HEADER: &str = "x-gateway-route";
+/// Which validation ran for a request (`json_schema`, `openapi` or
+/// `none`), with `debug_headers`
+pub const VALIDATION_MODE_HEADER: &str = "x-gateway-validation";
+
+/// `request_id_header` unless configured otherwise
+pub const DEFAULT_REQUEST_ID_HEADER: &str = "x-request-id";
+/// Longest client-sent request ID that is reused; longer ones are replaced
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+/// Total time the gateway spent on a request, in milliseconds
+pub const DURATION_HEADER: &str = "x-gateway-request-duration-ms";
+/// Time until the upstream's response headers arrived, in milliseconds,
+/// with `timing_headers`
+pub const UPSTREAM_DURATION_HEADER: &str = "x-upstream-duration-ms";
+/// Set on responses to deprecated OpenAPI operations or parameters
+pub const DEPRECATION_HEADER: &str = "deprecation";
+/// When a deprecated operation goes away, from its `x-sunset`
+pub const SUNSET_HEADER: &str = "sunset";
+
+#[allow(clippy::too_many_arguments)]
+async fn route_request(
+    state: Arc<tokio::sync::RwLock<AppState>>,
+    peer: Option<SocketAddr>,
+    upgrade: Option<OnUpgrade>,
+    method: Method,
+    uri: axum::http::Uri,
+    headers: HeaderMap,
+    body: Body,
+    start_time: Instant,
+) -> Response {
     let path = uri.path().to_string();
-    let path_with_query = build_forward_path(&path, uri.query());
     let method_str = method.as_str().to_uppercase();
 
-    // Read body
-    let body_bytes = match axum::body::to_bytes(body, usize::MAX).await {
-        Ok(bytes) => bytes.to_vec(),
-        Err(_) => {
-            let status = StatusCode::BAD_REQUEST;
-            let route_label = "unknown";
-            let state_guard = state.read().await;
-            state_guard
-                .metrics
-                .http_requests_total
-                .with_label_values(&[&method_str, route_label, &status.as_u16().to_string()])
-                .inc();
-            state_guard
-                .metrics
-                .http_request_duration_seconds
-                .with_label_values(&[&method_str, route_label])
-                .observe(start_time.elapsed().as_secs_f64());
-            drop(state_guard);
-            return (status, "Failed to read request body").into_response();
-        }
-    };
-
     // Lock state for reading
     let state_guard = state.read().await;
-
-    // Find matching route
-    let route = match state_guard.config.find_route(&path, method.as_str()) {
+    let routing = state_guard.routing();
+
+    // Find matching route, falling back to the default route
+    let matched = routing.route_index.find(
+        &routing.config,
+        &path,
+        method.as_str(),
+        &routing.route_regexes,
+    );
+    let route = match matched.or(routing.config.default_route.as_ref()) {
         Some(r) => r,
         None => {
+            let allowed = routing
+                .config
+                .find_allowed_methods_cached(&path, &routing.route_regexes);
+            if !allowed.is_empty() {
+                tracing::debug!(method = %method, path = %path, "Method not allowed");
+                let status = StatusCode::METHOD_NOT_ALLOWED;
+                state_guard
+                    .metrics
+                    .method_not_allowed_total
+                    .with_label_values(&[&method_str])
+                    .inc();
+                state_guard
+                    .metrics
+                    .http_requests_total
+                    .with_label_values(&[
+                        &method_str,
+                        "method_not_allowed",
+                        "",
+                        &status.as_u16().to_string(),
+                    ])
+                    .inc();
+                state_guard
+                    .metrics
+                    .http_request_duration_seconds
+                    .with_label_values(&[&method_str, "method_not_allowed", ""])
+                    .observe(start_time.elapsed().as_secs_f64());
+                drop(state_guard);
+                return (status, [(ALLOW, allowed.join(", "))], "Method not allowed")
+                    .into_response();
+            }
+
             tracing::debug!(method = %method, path = %path, "Route not found");
             let status = StatusCode::NOT_FOUND;
             state_guard
@@ -98,12 +776,12 @@ pub async fn handle_request(
             state_guard
                 .metrics
                 .http_requests_total
-                .with_label_values(&[&method_str, "not_found", &status.as_u16().to_string()])
+                .with_label_values(&[&method_str, "not_found", "", &status.as_u16().to_string()])
                 .inc();
             state_guard
                 .metrics
                 .http_request_duration_seconds
-                .with_label_values(&[&method_str, "not_found"])
+                .with_label_values(&[&method_str, "not_found", ""])
                 .observe(start_time.elapsed().as_secs_f64());
             drop(state_guard);
             return (status, "Route not found").into_response();
@@ -111,63 +789,605 @@ pub async fn handle_request(
     };
 
     // Get effective config for this route
-    let effective_config = state_guard.config.get_effective_config(route);
+    let mut effective_config = routing.config.get_effective_config(route);
+    let dev_mode = state_guard.dev_mode;
+    if dev_mode {
+        effective_config.pretty_print_errors = true;
+    }
     let upstream_url = route.upstream.clone();
+    let mock = route.mock_response.clone();
     let schema_path = route.schema.clone();
-    let openapi_options = route.openapi_options();
-    let route_pattern = route.path.clone();
+    let schema_options = route.schema_options();
+    let headers_schema = route.headers_schema.clone();
+    let openapi_options = route.openapi_options().map(|options| OpenApiRoute {
+        options,
+        template: (matched.is_some() && route.path_regex.is_none()).then(|| route.path.clone()),
+        config: route.config.clone(),
+        mock: route.mock,
+    });
+    let mocked = route.mock || route.mock_response.is_some();
+    let route_tags = route.tags_label();
+    let route_description = route.description();
+    let allow_upgrade = route.allow_upgrade;
+    let route_pattern = match matched {
+        Some(route) => route.pattern().to_string(),
+        None => {
+            tracing::debug!(method = %method, path = %path, "Using default route");
+            DEFAULT_ROUTE_LABEL.to_string()
+        }
+    };
+    let path_params = route.path_captures(&path, routing.route_regexes.get_for(route));
+    let upstream_path = route
+        .upstream_path(&path, &path_params)
+        .unwrap_or_else(|| path.clone());
+    let path_with_query = build_forward_path(&upstream_path, uri.query());
+    let client_key = UpstreamClientKey::for_upstream(&upstream_url, &effective_config);
+    let http_client = if client_key == UpstreamClientKey::default() {
+        Ok(state_guard.http_client.clone())
+    } else {
+        routing.upstream_clients.get(&client_key).cloned()
+    };
+    let authorization = effective_config
+        .upstream_auth
+        .as_ref()
+        .and_then(|auth| routing.upstream_auth.get(auth));
+    let forward_options = ForwardOptions::from_config(&effective_config, authorization);
+    let default_client = state_guard.http_client.clone();
+    let metrics = state_guard.metrics.clone();
 
     drop(state_guard);
 
     let validation_target = if let Some(openapi) = openapi_options {
-        Some(ValidationTarget::OpenApi(openapi))
+        Some(ValidationTarget::OpenApi(Box::new(openapi)))
     } else {
-        schema_path.map(ValidationTarget::JsonSchema)
+        schema_path.map(|path| ValidationTarget::JsonSchema {
+            path,
+            options: schema_options,
+        })
+    };
+    let debug_headers = effective_config.debug_headers.then(|| DebugHeaders {
+        route: route_pattern.clone(),
+        validation: match &validation_target {
+            Some(ValidationTarget::OpenApi(_)) => "openapi",
+            Some(ValidationTarget::JsonSchema { .. }) => "json_schema",
+            None => "none",
+        },
+    });
+    let finish = |response: Response| match &debug_headers {
+        Some(debug_headers) => debug_headers.apply(response),
+        None => response,
     };
 
+    // Checked before the body is read, so rejected requests cost little
+    if let Some(limiter) = routing.rate_limiters.get(&route.method, route.pattern()) {
+        let key = limiter.key(&headers, peer);
+        if let Err(retry_after) = limiter.check(&key) {
+            tracing::debug!(
+                method = %method,
+                path = %path,
+                key = %RateLimiter::loggable_key(&key),
+                "Rate limit exceeded"
+            );
+            return finish(
+                rate_limited(
+                    &state,
+                    &method_str,
+                    &route_pattern,
+                    &route_tags,
+                    retry_after,
+                    start_time,
+                )
+                .await,
+            );
+        }
+    }
+
+    // After the handshake the connection carries another protocol, so
+    // there's nothing to validate
+    if is_upgrade_request(&headers) {
+        let response = match (allow_upgrade, upgrade) {
+            (false, _) => error_response(
+                StatusCode::NOT_IMPLEMENTED,
+                "Upgrade requests are not enabled for this route",
+            ),
+            (true, None) => error_response(
+                StatusCode::NOT_IMPLEMENTED,
+                "Upgrade requests need an HTTP/1.1 connection",
+            ),
+            (true, Some(upgrade)) => tunnel_upgrade(
+                upgrade,
+                method.clone(),
+                &upstream_url,
+                &path_with_query,
+                &headers,
+                &forward_options,
+            )
+            .await
+            .unwrap_or_else(|failure| {
+                upstream_failure_response(
+                    failure,
+                    &method,
+                    &path,
+                    &route_description,
+                    effective_config.redact_upstream_in_errors,
+                    &metrics,
+                )
+            }),
+        };
+        record_request(
+            &state,
+            &method_str,
+            &route_pattern,
+            &route_tags,
+            response.status(),
+            start_time,
+        )
+        .await;
+        return finish(response);
+    }
+
+    // With nothing to validate, the body can go upstream as it arrives.
+    // Header validation still needs it buffered (a failure may forward it),
+    // and so does `inject_body_hash`.
+    let stream_body = effective_config.stream_unvalidated
+        && validation_target.is_none()
+        && headers_schema.is_none()
+        && effective_config.inject_body_hash.is_none();
+    let (mut body_bytes, streamed_body) = if stream_body {
+        (Vec::new(), Some(body))
+    } else {
+        match read_request_body(body, &headers, effective_config.max_request_body_bytes).await {
+            Ok(bytes) => (bytes, None),
+            Err(status) => {
+                let message = if status == StatusCode::PAYLOAD_TOO_LARGE {
+                    "Request body exceeds max_request_body_bytes"
+                } else {
+                    "Failed to read request body"
+                };
+                return finish(
+                    body_rejected(
+                        &state,
+                        &method_str,
+                        &route_pattern,
+                        &route_tags,
+                        status,
+                        message,
+                        start_time,
+                    )
+                    .await,
+                );
+            }
+        }
+    };
+
+    // Compressed bodies are validated decoded, and by default still sent
+    // upstream as the client compressed them
+    let mut headers = headers;
+    let mut encoded_body = None;
+    if validation_target.is_some() && !body_bytes.is_empty() {
+        let body = std::mem::take(&mut body_bytes);
+        match decode_request_body(body, &headers, &effective_config).await {
+            Ok((body, None)) => body_bytes = body,
+            Ok((encoded, Some(decoded))) => {
+                body_bytes = decoded;
+                if effective_config.forward_decompressed_body {
+                    headers.remove(CONTENT_ENCODING);
+                    headers.remove(CONTENT_LENGTH);
+                } else {
+                    encoded_body = Some(encoded);
+                }
+            }
+            Err((status, message)) => {
+                tracing::debug!(method = %method, path = %path, error = %message, "Rejected request body");
+                return finish(
+                    body_rejected(
+                        &state,
+                        &method_str,
+                        &route_pattern,
+                        &route_tags,
+                        status,
+                        &message,
+                        start_time,
+                    )
+                    .await,
+                );
+            }
+        }
+    }
+
     let ctx = RequestContext {
         method,
         upstream_url,
         path,
+        upstream_path,
         path_and_query: path_with_query,
         query: uri.query().map(|q| q.to_string()),
         headers,
         body_bytes,
+        encoded_body,
         route_pattern,
+        route_tags,
+        route_description,
+        path_params,
+        forward_options,
+        http_client: default_client,
+        mock_response: mock,
+        body_hash: effective_config.inject_body_hash,
+        verify_response_hash: effective_config.verify_response_hash,
+        propagate_trace_context: effective_config.propagate_trace_context,
+        dev_mode,
+        retry: effective_config.retry.clone(),
+        max_response_bytes: effective_config.max_response_bytes,
+        redact_upstream_in_errors: effective_config.redact_upstream_in_errors,
+        timing_headers: effective_config.timing_headers,
+        metrics,
+        start_time,
     };
 
-    match validation_target {
-        None => forward_without_validation(ctx, state).await,
-        Some(ValidationTarget::JsonSchema(schema_path)) => {
-            handle_json_schema_validation(ctx, schema_path, state, effective_config).await
+    let ctx = match http_client {
+        Ok(http_client) => RequestContext { http_client, ..ctx },
+        // Mocked routes never connect upstream
+        Err(_) if mocked => ctx,
+        Err(e) => {
+            // Never fall back to the default client: that would skip the
+            // route's pinning and verification settings
+            let error_msg = format!("Upstream client configuration error: {}", e);
+            let reject = GlobalConfig {
+                forward_on_error: false,
+                ..effective_config
+            };
+            return finish(
+                handle_error(&error_msg, &reject, ctx, state, StatusCode::BAD_GATEWAY).await,
+            );
+        }
+    };
+
+    if let Some(headers_schema) = headers_schema {
+        if let Err(response) =
+            validate_request_headers(&ctx, &headers_schema, &effective_config, state.clone()).await
+        {
+            return finish(response);
+        }
+    }
+
+    let response = match validation_target {
+        None => match streamed_body {
+            Some(body) => forward_without_validation(ctx, body.into(), state).await,
+            None => forward_buffered(ctx, state).await,
+        },
+        Some(ValidationTarget::JsonSchema { path, options }) => {
+            handle_json_schema_validation(ctx, path, options, state, effective_config).await
         }
         Some(ValidationTarget::OpenApi(openapi)) => {
-            handle_openapi_validation(ctx, openapi, state, effective_config).await
+            handle_openapi_validation(ctx, *openapi, state, effective_config).await
+        }
+    };
+    finish(response)
+}
+
+/// 429 for a request over its route's rate limit. `Retry-After` is rounded
+/// up to whole seconds.
+async fn rate_limited(
+    state: &Arc<tokio::sync::RwLock<AppState>>,
+    method_str: &str,
+    route_pattern: &str,
+    route_tags: &str,
+    retry_after: Duration,
+    start_time: Instant,
+) -> Response {
+    let status = StatusCode::TOO_MANY_REQUESTS;
+    {
+        let state_guard = state.read().await;
+        state_guard
+            .metrics
+            .rate_limit_rejections_total
+            .with_label_values(&[route_pattern, route_tags])
+            .inc();
+        state_guard
+            .metrics
+            .http_requests_total
+            .with_label_values(&[
+                method_str,
+                route_pattern,
+                route_tags,
+                &status.as_u16().to_string(),
+            ])
+            .inc();
+        state_guard
+            .metrics
+            .http_request_duration_seconds
+            .with_label_values(&[method_str, route_pattern, route_tags])
+            .observe(start_time.elapsed().as_secs_f64());
+        drop(state_guard);
+    }
+
+    let seconds = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    let mut response = error_response(status, "Rate limit exceeded");
+    response
+        .headers_mut()
+        .insert(RETRY_AFTER, HeaderValue::from(seconds));
+    response
+}
+
+/// Record a request whose body was refused before validation, and answer it
+/// with `status`
+async fn body_rejected(
+    state: &Arc<tokio::sync::RwLock<AppState>>,
+    method_str: &str,
+    route_pattern: &str,
+    route_tags: &str,
+    status: StatusCode,
+    message: &str,
+    start_time: Instant,
+) -> Response {
+    record_request(
+        state,
+        method_str,
+        route_pattern,
+        route_tags,
+        status,
+        start_time,
+    )
+    .await;
+    error_response(status, message)
+}
+
+/// Count a request answered with `status` in the HTTP metrics
+async fn record_request(
+    state: &Arc<tokio::sync::RwLock<AppState>>,
+    method_str: &str,
+    route_pattern: &str,
+    route_tags: &str,
+    status: StatusCode,
+    start_time: Instant,
+) {
+    let state_guard = state.read().await;
+    state_guard
+        .metrics
+        .http_requests_total
+        .with_label_values(&[
+            method_str,
+            route_pattern,
+            route_tags,
+            &status.as_u16().to_string(),
+        ])
+        .inc();
+    state_guard
+        .metrics
+        .http_request_duration_seconds
+        .with_label_values(&[method_str, route_pattern, route_tags])
+        .observe(start_time.elapsed().as_secs_f64());
+}
+
+/// `body` back along with its decoded form when `Content-Encoding` says
+/// it's compressed. Unsupported encodings get 415, and decoded bodies over
+/// `max_request_body_bytes` (or the default decoded-size cap) 413. Decoding
+/// runs on the blocking pool so large bodies don't stall the executor.
+async fn decode_request_body(
+    body: Vec<u8>,
+    headers: &HeaderMap,
+    config: &GlobalConfig,
+) -> Result<(Vec<u8>, Option<Vec<u8>>), (StatusCode, String)> {
+    let encoding = ContentEncoding::from_headers(headers).map_err(|coding| {
+        (
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            format!("Unsupported Content-Encoding '{}'", coding),
+        )
+    })?;
+    if encoding == ContentEncoding::Identity {
+        return Ok((body, None));
+    }
+    let limit = config.max_request_body_bytes;
+    let decoded = tokio::task::spawn_blocking(move || {
+        let decoded = encoding.decode(&body, limit);
+        (body, decoded)
+    })
+    .await;
+    match decoded {
+        Ok((body, Ok(decoded))) => Ok((body, Some(decoded))),
+        Ok((_, Err(DecodeError::TooLarge))) => Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            match limit {
+                Some(_) => "Decompressed request body exceeds max_request_body_bytes",
+                None => "Decompressed request body exceeds the default decoded size limit",
+            }
+            .to_string(),
+        )),
+        Ok((_, Err(DecodeError::Invalid(e)))) => Err((
+            StatusCode::BAD_REQUEST,
+            format!("Failed to decompress request body: {}", e),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to decompress request body: {}", e),
+        )),
+    }
+}
+
+/// Read the request body into memory, stopping with 413 once it's over
+/// `limit` (or as soon as `Content-Length` says it will be), and 400 if
+/// the client's body can't be read
+async fn read_request_body(
+    mut body: Body,
+    headers: &HeaderMap,
+    limit: Option<usize>,
+) -> Result<Vec<u8>, StatusCode> {
+    let over_limit = |len: usize| limit.is_some_and(|limit| len > limit);
+    let declared = headers
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    if declared.is_some_and(over_limit) {
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    let mut bytes = Vec::new();
+    while let Some(frame) = std::future::poll_fn(|cx| Pin::new(&mut body).poll_frame(cx)).await {
+        let frame = frame.map_err(|_| StatusCode::BAD_REQUEST)?;
+        if let Ok(data) = frame.into_data() {
+            bytes.extend_from_slice(&data);
+            if over_limit(bytes.len()) {
+                return Err(StatusCode::PAYLOAD_TOO_LARGE);
+            }
         }
     }
+    Ok(bytes)
+}
+
+/// An upstream response body read for validation
+enum ResponseBody {
+    /// The whole body, within the limit
+    Complete(Bytes),
+    /// The body is over the limit: what was read of it, then the rest as it
+    /// arrives
+    TooLarge(Body),
+}
+
+/// Read an upstream response body for validation, stopping once it's over
+/// `limit` (or as soon as `Content-Length` says it will be)
+async fn read_response_body(
+    mut body: Body,
+    headers: &HeaderMap,
+    limit: Option<usize>,
+) -> Result<ResponseBody, axum::Error> {
+    let Some(limit) = limit else {
+        return axum::body::to_bytes(body, usize::MAX)
+            .await
+            .map(ResponseBody::Complete);
+    };
+    let declared = headers
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    if declared.is_some_and(|len| len > limit) {
+        return Ok(ResponseBody::TooLarge(body));
+    }
+
+    let mut chunks = VecDeque::new();
+    let mut len = 0;
+    while let Some(frame) = std::future::poll_fn(|cx| Pin::new(&mut body).poll_frame(cx)).await {
+        if let Ok(data) = frame?.into_data() {
+            len += data.len();
+            chunks.push_back(data);
+            if len > limit {
+                return Ok(ResponseBody::TooLarge(Body::new(PrefixedBody {
+                    prefix: chunks,
+                    rest: body,
+                })));
+            }
+        }
+    }
+    Ok(ResponseBody::Complete(
+        chunks.into_iter().flatten().collect(),
+    ))
+}
+
+/// `rest`, preceded by the chunks already read from it
+struct PrefixedBody {
+    prefix: VecDeque<Bytes>,
+    rest: Body,
+}
+
+impl HttpBody for PrefixedBody {
+    type Data = Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, axum::Error>>> {
+        match self.prefix.pop_front() {
+            Some(chunk) => Poll::Ready(Some(Ok(Frame::data(chunk)))),
+            None => Pin::new(&mut self.rest).poll_frame(cx),
+        }
+    }
+}
+
+/// What `debug_headers` reports about the route that handled a request
+struct DebugHeaders {
+    route: String,
+    validation: &'static str,
+}
+
+impl DebugHeaders {
+    fn apply(&self, mut response: Response) -> Response {
+        let headers = response.headers_mut();
+        if let Ok(route) = HeaderValue::from_str(&self.route) {
+            headers.insert(ROUTE_HEADER, route);
+        }
+        headers.insert(
+            VALIDATION_MODE_HEADER,
+            HeaderValue::from_static(self.validation),
+        );
+        response
+    }
+}
+
+/// Layer the operation's `x-schema-gateway` settings under the route's own
+/// config, refreshing what the request context took from the route
+fn apply_operation_overrides(
+    ctx: &mut RequestContext,
+    plan: &OperationValidationPlan,
+    route_config: RouteConfig,
+    effective_config: GlobalConfig,
+) -> GlobalConfig {
+    if plan.overrides == RouteConfig::default() {
+        return effective_config;
+    }
+    let config = effective_config.with_overrides(&route_config.or(plan.overrides.clone()));
+    // `upstream_auth` is route-only, so the resolved credential carries over
+    let authorization = ctx.forward_options.request_headers.authorization.take();
+    ctx.forward_options = ForwardOptions::from_config(&config, authorization);
+    ctx.body_hash = config.inject_body_hash;
+    ctx.verify_response_hash = config.verify_response_hash;
+    ctx.propagate_trace_context = config.propagate_trace_context;
+    ctx.retry = config.retry.clone();
+    config
+}
+
+fn is_bodyless_method(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD)
 }
 
 enum ValidationTarget {
-    JsonSchema(PathBuf),
-    OpenApi(OpenApiOptions),
+    JsonSchema {
+        path: PathBuf,
+        options: SchemaOptions,
+    },
+    OpenApi(Box<OpenApiRoute>),
+}
+
+/// What `handle_openapi_validation` needs to know about the route
+struct OpenApiRoute {
+    options: OpenApiOptions,
+    /// The route's `:param` path; `None` for `path_regex` routes
+    template: Option<String>,
+    /// Kept to rank the route's own settings above the operation's
+    /// `x-schema-gateway` ones
+    config: RouteConfig,
+    /// Answer with the operation's example response instead of forwarding
+    mock: bool,
+}
+
+/// Forward `ctx` with the body that was read into it
+async fn forward_buffered(
+    mut ctx: RequestContext,
+    state: Arc<tokio::sync::RwLock<AppState>>,
+) -> Response {
+    let body = UpstreamBody::Buffered(std::mem::take(&mut ctx.body_bytes));
+    forward_without_validation(ctx, body, state).await
 }
 
 async fn forward_without_validation(
     ctx: RequestContext,
+    body: UpstreamBody,
     state: Arc<tokio::sync::RwLock<AppState>>,
 ) -> Response {
-    let start_time = Instant::now();
-    let RequestContext {
-        method,
-        upstream_url,
-        path_and_query,
-        headers,
-        body_bytes,
-        route_pattern,
-        ..
-    } = ctx;
-    let method_str = method.as_str().to_uppercase();
-    let route_label = &route_pattern;
+    let start_time = ctx.start_time;
+    let mut ctx = ctx;
+    let headers = std::mem::take(&mut ctx.headers);
+    let method_str = ctx.method.as_str().to_uppercase();
+    let route_label = &ctx.route_pattern;
 
     // Record validation attempt (none)
     {
@@ -175,27 +1395,15 @@ async fn forward_without_validation(
         state_guard
             .metrics
             .validation_attempts_total
-            .with_label_values(&["none"])
+            .with_label_values(&["none", ""])
             .inc();
         drop(state_guard);
     }
 
     // Forward request and record upstream metrics
-    let upstream_start = Instant::now();
-    let state_guard = state.read().await;
-    let response = forward_request(
-        &state_guard.http_client,
-        method.clone(),
-        &upstream_url,
-        &path_and_query,
-        headers,
-        body_bytes,
-    )
-    .await;
-    let upstream_duration = upstream_start.elapsed().as_secs_f64();
+    let response = ctx.send(headers, body).await;
     let status = response.status();
     let status_code = status.as_u16().to_string();
-    drop(state_guard);
 
     // Record upstream metrics
     {
@@ -205,11 +1413,6 @@ async fn forward_without_validation(
             .upstream_requests_total
             .with_label_values(&[&status_code])
             .inc();
-        state_guard
-            .metrics
-            .upstream_request_duration_seconds
-            .with_label_values(&[])
-            .observe(upstream_duration);
         drop(state_guard);
     }
 
@@ -219,12 +1422,12 @@ async fn forward_without_validation(
         state_guard
             .metrics
             .http_requests_total
-            .with_label_values(&[&method_str, route_label, &status_code])
+            .with_label_values(&[&method_str, route_label, &ctx.route_tags, &status_code])
             .inc();
         state_guard
             .metrics
             .http_request_duration_seconds
-            .with_label_values(&[&method_str, route_label])
+            .with_label_values(&[&method_str, route_label, &ctx.route_tags])
             .observe(start_time.elapsed().as_secs_f64());
         drop(state_guard);
     }
@@ -233,12 +1436,13 @@ async fn forward_without_validation(
 }
 
 async fn handle_json_schema_validation(
-    ctx: RequestContext,
+    mut ctx: RequestContext,
     schema_path: PathBuf,
+    options: SchemaOptions,
     state: Arc<tokio::sync::RwLock<AppState>>,
     effective_config: GlobalConfig,
 ) -> Response {
-    let start_time = Instant::now();
+    let start_time = ctx.start_time;
     let method_str = ctx.method.as_str().to_uppercase();
     let route_label = &ctx.route_pattern;
 
@@ -248,13 +1452,13 @@ async fn handle_json_schema_validation(
         state_guard
             .metrics
             .validation_attempts_total
-            .with_label_values(&["json_schema"])
+            .with_label_values(&["json_schema", ""])
             .inc();
         drop(state_guard);
     }
 
     if ctx.body_bytes.is_empty() {
-        return forward_without_validation(ctx, state).await;
+        return forward_buffered(ctx, state).await;
     }
 
     let ctx_for_parse = ctx.clone();
@@ -272,7 +1476,7 @@ async fn handle_json_schema_validation(
             state_guard
                 .metrics
                 .validation_failures_total
-                .with_label_values(&["json_schema", "invalid_json"])
+                .with_label_values(&["json_schema", "invalid_json", ""])
                 .inc();
             drop(state_guard);
             return response;
@@ -280,9 +1484,18 @@ async fn handle_json_schema_validation(
     };
 
     let schema = {
-        let mut state_guard = state.write().await;
-        let was_cached = state_guard.schema_cache.cache.contains_key(&schema_path);
-        let schema_result = state_guard.schema_cache.load(&schema_path);
+        let state_guard = state.read().await;
+        let (was_cached, schema_result, cache_len) = {
+            let schema_cache = state_guard.schema_cache.read();
+            let was_cached = schema_cache.contains_with(&schema_path, options);
+            let result = schema_cache.load_with(&schema_path, options);
+            (was_cached, result, schema_cache.len())
+        };
+        state_guard
+            .metrics
+            .schema_cache_entries
+            .with_label_values(&["schema"])
+            .set(cache_len as i64);
         drop(state_guard);
 
         // Record cache hit/miss
@@ -290,7 +1503,7 @@ async fn handle_json_schema_validation(
             let state_guard = state.read().await;
             if was_cached {
                 state_guard.metrics.schema_cache_hits_total.inc();
-            } else {
+            } else if !is_cached_failure(&schema_result) {
                 state_guard.metrics.schema_cache_misses_total.inc();
             }
             drop(state_guard);
@@ -300,20 +1513,31 @@ async fn handle_json_schema_validation(
             Ok(schema) => schema,
             Err(e) => {
                 let error_msg = format!("{}", e);
-                tracing::warn!(
-                    method = %ctx.method,
-                    path = %ctx.path,
-                    schema_path = %schema_path.display(),
-                    error = %e,
-                    "Failed to load schema"
-                );
+                if matches!(e, Error::Cached { .. }) {
+                    tracing::debug!(
+                        method = %ctx.method,
+                        path = %ctx.path,
+                        schema_path = %schema_path.display(),
+                        error = %e,
+                        "Failed to load schema (cached failure)"
+                    );
+                } else {
+                    tracing::warn!(
+                        method = %ctx.method,
+                        path = %ctx.path,
+                        route_description = %ctx.route_description,
+                        schema_path = %schema_path.display(),
+                        error = %e,
+                        "Failed to load schema"
+                    );
+                }
                 // Record validation failure
                 {
                     let state_guard = state.read().await;
                     state_guard
                         .metrics
                         .validation_failures_total
-                        .with_label_values(&["json_schema", "schema_load_error"])
+                        .with_label_values(&["json_schema", "schema_load_error", ""])
                         .inc();
                     drop(state_guard);
                 }
@@ -329,7 +1553,11 @@ async fn handle_json_schema_validation(
         }
     };
 
-    let validation_result = validate(&schema, &json_value);
+    let validation_result = validate_with_limit(
+        &schema,
+        &json_value,
+        Some(effective_config.max_validation_errors),
+    );
 
     if validation_result.valid {
         // Record validation success
@@ -338,7 +1566,7 @@ async fn handle_json_schema_validation(
             state_guard
                 .metrics
                 .validation_success_total
-                .with_label_values(&["json_schema"])
+                .with_label_values(&["json_schema", ""])
                 .inc();
             drop(state_guard);
         }
@@ -350,7 +1578,7 @@ async fn handle_json_schema_validation(
             "Request validated successfully"
         );
 
-        let mut forwarding_headers = ctx.headers;
+        let mut forwarding_headers = std::mem::take(&mut ctx.headers);
         if effective_config.add_validation_header {
             if let Ok(header_value) = "true".parse() {
                 forwarding_headers.insert("X-Schema-Validated", header_value);
@@ -358,21 +1586,9 @@ async fn handle_json_schema_validation(
         }
 
         // Forward request and record upstream metrics
-        let upstream_start = Instant::now();
-        let state_guard = state.read().await;
-        let response = forward_request(
-            &state_guard.http_client,
-            ctx.method.clone(),
-            &ctx.upstream_url,
-            &ctx.path_and_query,
-            forwarding_headers,
-            ctx.body_bytes.clone(),
-        )
-        .await;
-        let upstream_duration = upstream_start.elapsed().as_secs_f64();
+        let response = ctx.send(forwarding_headers, ctx.body_bytes.clone()).await;
         let status = response.status();
         let status_code = status.as_u16().to_string();
-        drop(state_guard);
 
         // Record upstream metrics
         {
@@ -382,11 +1598,6 @@ async fn handle_json_schema_validation(
                 .upstream_requests_total
                 .with_label_values(&[&status_code])
                 .inc();
-            state_guard
-                .metrics
-                .upstream_request_duration_seconds
-                .with_label_values(&[])
-                .observe(upstream_duration);
             drop(state_guard);
         }
 
@@ -396,12 +1607,12 @@ async fn handle_json_schema_validation(
             state_guard
                 .metrics
                 .http_requests_total
-                .with_label_values(&[&method_str, route_label, &status_code])
+                .with_label_values(&[&method_str, route_label, &ctx.route_tags, &status_code])
                 .inc();
             state_guard
                 .metrics
                 .http_request_duration_seconds
-                .with_label_values(&[&method_str, route_label])
+                .with_label_values(&[&method_str, route_label, &ctx.route_tags])
                 .observe(start_time.elapsed().as_secs_f64());
             drop(state_guard);
         }
@@ -414,77 +1625,119 @@ async fn handle_json_schema_validation(
             state_guard
                 .metrics
                 .validation_failures_total
-                .with_label_values(&["json_schema", "validation_failed"])
+                .with_label_values(&["json_schema", "validation_failed", ""])
                 .inc();
+            state_guard.metrics.record_field_errors(
+                &validation_result.errors,
+                effective_config.max_field_label_depth,
+            );
             drop(state_guard);
         }
 
-        let error_msg = format!("Validation failed: {}", validation_result.errors.join(", "));
         tracing::warn!(
             method = %ctx.method,
             path = %ctx.path,
-            errors = ?validation_result.errors,
+            route_description = %ctx.route_description,
+            errors = ?validation_result.messages(),
+            error_count = validation_result.error_count,
             "Validation failed"
         );
-        handle_error(
-            &error_msg,
-            &effective_config,
-            ctx,
-            state,
-            StatusCode::BAD_REQUEST,
-        )
-        .await
+        handle_validation_failure(&validation_result, &effective_config, ctx, state).await
     }
 }
 
 async fn handle_openapi_validation(
-    ctx: RequestContext,
-    openapi: OpenApiOptions,
+    mut ctx: RequestContext,
+    route: OpenApiRoute,
     state: Arc<tokio::sync::RwLock<AppState>>,
     effective_config: GlobalConfig,
 ) -> Response {
-    let start_time = Instant::now();
-    let method_str = ctx.method.as_str().to_uppercase();
-    let route_label = &ctx.route_pattern;
+    let openapi = route.options;
 
-    // Record validation attempt
-    {
+    // The spec's paths are relative to its base path; the client path is
+    // still what gets forwarded
+    let (plan, operation_path) = {
         let state_guard = state.read().await;
+        let (result, cache_len) = {
+            let openapi_cache = state_guard.openapi_cache.read();
+            let result = openapi_cache
+                .operation_path(&openapi.spec, openapi.base_path.as_deref(), &ctx.path)
+                .and_then(|operation_path| {
+                    if let Some(template) = &route.template {
+                        let route_template = openapi_cache.operation_path(
+                            &openapi.spec,
+                            openapi.base_path.as_deref(),
+                            template,
+                        )?;
+                        let plan = openapi_cache.load_route_operation(
+                            &openapi.spec,
+                            route_template,
+                            operation_path,
+                            &ctx.method,
+                            openapi.operation_id.as_deref(),
+                            openapi.strict_body,
+                        )?;
+                        return Ok((plan, operation_path.to_string()));
+                    }
+                    let plan = if openapi.strict_body {
+                        openapi_cache.load_strict_operation(
+                            &openapi.spec,
+                            operation_path,
+                            &ctx.method,
+                            openapi.operation_id.as_deref(),
+                        )?
+                    } else {
+                        openapi_cache.load_operation(
+                            &openapi.spec,
+                            operation_path,
+                            &ctx.method,
+                            openapi.operation_id.as_deref(),
+                        )?
+                    };
+                    Ok((plan, operation_path.to_string()))
+                });
+            (result, openapi_cache.len())
+        };
         state_guard
             .metrics
-            .validation_attempts_total
+            .schema_cache_entries
             .with_label_values(&["openapi"])
-            .inc();
-        drop(state_guard);
-    }
-
-    let plan = {
-        let mut state_guard = state.write().await;
-        let result = state_guard.openapi_cache.load_operation(
-            &openapi.spec,
-            &ctx.path,
-            &ctx.method,
-            openapi.operation_id.as_deref(),
-        );
+            .set(cache_len as i64);
         drop(state_guard);
         match result {
-            Ok(plan) => plan,
+            Ok(loaded) => loaded,
             Err(e) => {
                 let error_msg = format!("{}", e);
-                tracing::warn!(
-                    method = %ctx.method,
-                    path = %ctx.path,
-                    spec = %openapi.spec.display(),
-                    error = %e,
-                    "Failed to load OpenAPI schema"
-                );
-                // Record validation failure
+                if matches!(e, Error::Cached { .. }) {
+                    tracing::debug!(
+                        method = %ctx.method,
+                        path = %ctx.path,
+                        spec = %openapi.spec.display(),
+                        error = %e,
+                        "Failed to load OpenAPI schema (cached failure)"
+                    );
+                } else {
+                    tracing::warn!(
+                        method = %ctx.method,
+                        path = %ctx.path,
+                        route_description = %ctx.route_description,
+                        spec = %openapi.spec.display(),
+                        error = %e,
+                        "Failed to load OpenAPI schema"
+                    );
+                }
+                // Record validation failure; the spec version isn't known
                 {
                     let state_guard = state.read().await;
+                    state_guard
+                        .metrics
+                        .validation_attempts_total
+                        .with_label_values(&["openapi", UNKNOWN_SPEC_VERSION])
+                        .inc();
                     state_guard
                         .metrics
                         .validation_failures_total
-                        .with_label_values(&["openapi", "schema_load_error"])
+                        .with_label_values(&["openapi", "schema_load_error", UNKNOWN_SPEC_VERSION])
                         .inc();
                     drop(state_guard);
                 }
@@ -499,9 +1752,65 @@ async fn handle_openapi_validation(
             }
         }
     };
+    if route.mock {
+        ctx.mock_response = Some(plan.example_response.to_mock_response());
+    }
+    let spec_version = plan.spec_version.label();
+    // Record validation attempt
+    {
+        let state_guard = state.read().await;
+        state_guard
+            .metrics
+            .validation_attempts_total
+            .with_label_values(&["openapi", spec_version])
+            .inc();
+        drop(state_guard);
+    }
+    let effective_config =
+        apply_operation_overrides(&mut ctx, &plan, route.config, effective_config);
+
+    if effective_config.enforce_security {
+        if let Err(error_msg) =
+            check_openapi_security(&plan.security, ctx.query.as_deref(), &ctx.headers)
+        {
+            {
+                let state_guard = state.read().await;
+                state_guard
+                    .metrics
+                    .validation_failures_total
+                    .with_label_values(&["openapi", "security_requirements_not_met", spec_version])
+                    .inc();
+                drop(state_guard);
+            }
+            record_coverage(&state, &openapi.spec, &plan, false, &effective_config).await;
+            // Never forward a request without credentials
+            let reject = GlobalConfig {
+                forward_on_error: false,
+                ..effective_config
+            };
+            return handle_error(&error_msg, &reject, ctx, state, StatusCode::UNAUTHORIZED).await;
+        }
+    }
+
+    // Before defaults are added: only what the client sent counts
+    let deprecated = plan.deprecated
+        || !plan
+            .deprecated_parameters(ctx.query.as_deref(), &ctx.headers)
+            .is_empty();
+
+    // Defaults are validated like any other value the client could have sent
+    if openapi.apply_defaults {
+        apply_parameter_defaults(&plan, &mut ctx);
+    }
 
-    if let Err(response) =
-        validate_openapi_parameters(&plan, &ctx, &effective_config, state.clone()).await
+    if let Err(response) = validate_openapi_parameters(
+        &plan,
+        &operation_path,
+        &ctx,
+        &effective_config,
+        state.clone(),
+    )
+    .await
     {
         // Record validation failure for parameter validation
         {
@@ -509,15 +1818,20 @@ async fn handle_openapi_validation(
             state_guard
                 .metrics
                 .validation_failures_total
-                .with_label_values(&["openapi", "parameter_validation_failed"])
+                .with_label_values(&["openapi", "parameter_validation_failed", spec_version])
                 .inc();
             drop(state_guard);
         }
+        record_coverage(&state, &openapi.spec, &plan, false, &effective_config).await;
         return response;
     }
 
-    if plan.schema.is_none() && ctx.body_bytes.is_empty() {
-        return forward_without_validation(ctx, state).await;
+    // Nothing to validate without a body; GET and HEAD never need one. The
+    // response is still checked against the spec.
+    if ctx.body_bytes.is_empty() && (plan.schema.is_none() || is_bodyless_method(&ctx.method)) {
+        record_coverage(&state, &openapi.spec, &plan, true, &effective_config).await;
+        let headers = ctx.headers.clone();
+        return forward_openapi(ctx, headers, &plan, deprecated, &effective_config, state).await;
     }
 
     if plan.body_required && ctx.body_bytes.is_empty() {
@@ -531,10 +1845,11 @@ async fn handle_openapi_validation(
             state_guard
                 .metrics
                 .validation_failures_total
-                .with_label_values(&["openapi", "missing_body"])
+                .with_label_values(&["openapi", "missing_body", spec_version])
                 .inc();
             drop(state_guard);
         }
+        record_coverage(&state, &openapi.spec, &plan, false, &effective_config).await;
         return handle_error(
             &error_msg,
             &effective_config,
@@ -545,38 +1860,94 @@ async fn handle_openapi_validation(
         .await;
     }
 
-    let schema = match plan.schema.clone() {
-        Some(schema) => schema,
-        None => {
-            return forward_without_validation(ctx, state).await;
-        }
-    };
-
-    let ctx_for_parse = ctx.clone();
-    let json_value = match parse_json_body_or_handle_error(
-        ctx_for_parse,
-        &effective_config,
-        state.clone(),
-    )
-    .await
+    if openapi.enforce_content_type && !ctx.body_bytes.is_empty() && !plan.content_types.is_empty()
     {
-        Ok(value) => value,
-        Err(response) => {
-            // Record validation failure for JSON parse error
+        let content_type = ctx
+            .headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok());
+        if !content_type.is_some_and(|content_type| plan.accepts_content_type(content_type)) {
+            let error_msg = format!(
+                "Unsupported Content-Type {} for {} {}; expected one of: {}",
+                content_type.map_or("(none)".to_string(), |ct| format!("'{}'", ct)),
+                plan.method,
+                plan.path_template,
+                plan.content_types.join(", ")
+            );
             {
                 let state_guard = state.read().await;
                 state_guard
                     .metrics
                     .validation_failures_total
-                    .with_label_values(&["openapi", "invalid_json"])
+                    .with_label_values(&["openapi", "unsupported_media_type", spec_version])
                     .inc();
                 drop(state_guard);
             }
-            return response;
+            record_coverage(&state, &openapi.spec, &plan, false, &effective_config).await;
+            return handle_error(
+                &error_msg,
+                &effective_config,
+                ctx,
+                state,
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            )
+            .await;
+        }
+    }
+
+    let schema = match plan.schema.clone() {
+        Some(schema) => schema,
+        None => {
+            record_coverage(&state, &openapi.spec, &plan, true, &effective_config).await;
+            let headers = ctx.headers.clone();
+            return forward_openapi(ctx, headers, &plan, deprecated, &effective_config, state)
+                .await;
+        }
+    };
+
+    let ctx_for_parse = ctx.clone();
+    let json_value = if let Some(form) = &plan.multipart {
+        match read_multipart_or_handle_error(
+            form,
+            spec_version,
+            ctx_for_parse,
+            &effective_config,
+            state.clone(),
+        )
+        .await
+        {
+            Ok(value) => value,
+            Err(response) => {
+                record_coverage(&state, &openapi.spec, &plan, false, &effective_config).await;
+                return response;
+            }
+        }
+    } else {
+        match parse_json_body_or_handle_error(ctx_for_parse, &effective_config, state.clone()).await
+        {
+            Ok(value) => value,
+            Err(response) => {
+                // Record validation failure for JSON parse error
+                {
+                    let state_guard = state.read().await;
+                    state_guard
+                        .metrics
+                        .validation_failures_total
+                        .with_label_values(&["openapi", "invalid_json", spec_version])
+                        .inc();
+                    drop(state_guard);
+                }
+                record_coverage(&state, &openapi.spec, &plan, false, &effective_config).await;
+                return response;
+            }
         }
     };
 
-    let validation_result = validate(&schema, &json_value);
+    let validation_result = validate_with_limit(
+        &schema,
+        &json_value,
+        Some(effective_config.max_validation_errors),
+    );
 
     if validation_result.valid {
         // Record validation success
@@ -585,10 +1956,11 @@ async fn handle_openapi_validation(
             state_guard
                 .metrics
                 .validation_success_total
-                .with_label_values(&["openapi"])
+                .with_label_values(&["openapi", spec_version])
                 .inc();
             drop(state_guard);
         }
+        record_coverage(&state, &openapi.spec, &plan, true, &effective_config).await;
 
         tracing::debug!(
             method = %ctx.method,
@@ -605,95 +1977,220 @@ async fn handle_openapi_validation(
             }
         }
 
-        // Forward request and record upstream metrics
-        let upstream_start = Instant::now();
-        let state_guard = state.read().await;
-        let response = forward_request(
-            &state_guard.http_client,
-            ctx.method.clone(),
-            &ctx.upstream_url,
-            &ctx.path_and_query,
+        forward_openapi(
+            ctx,
             forwarding_headers,
-            ctx.body_bytes.clone(),
+            &plan,
+            deprecated,
+            &effective_config,
+            state,
         )
-        .await;
-        let upstream_duration = upstream_start.elapsed().as_secs_f64();
-        drop(state_guard);
-
-        // Record upstream metrics before response validation
+        .await
+    } else {
+        // Record validation failure
         {
             let state_guard = state.read().await;
-            let status = response.status();
-            let status_code = status.as_u16().to_string();
             state_guard
                 .metrics
-                .upstream_requests_total
-                .with_label_values(&[&status_code])
+                .validation_failures_total
+                .with_label_values(&["openapi", "validation_failed", spec_version])
                 .inc();
-            state_guard
-                .metrics
-                .upstream_request_duration_seconds
-                .with_label_values(&[])
-                .observe(upstream_duration);
+            state_guard.metrics.record_field_errors(
+                &validation_result.errors,
+                effective_config.max_field_label_depth,
+            );
             drop(state_guard);
         }
+        record_coverage(&state, &openapi.spec, &plan, false, &effective_config).await;
 
-        let response = validate_openapi_response(response, &plan, &ctx, &effective_config).await;
+        tracing::warn!(
+            method = %ctx.method,
+            path = %ctx.path,
+            route_description = %ctx.route_description,
+            errors = ?validation_result.messages(),
+            error_count = validation_result.error_count,
+            "OpenAPI validation failed"
+        );
+        handle_validation_failure(&validation_result, &effective_config, ctx, state).await
+    }
+}
 
-        // Record final request metrics
-        {
-            let state_guard = state.read().await;
-            let status = response.status();
-            let status_code = status.as_u16().to_string();
-            state_guard
-                .metrics
-                .http_requests_total
-                .with_label_values(&[&method_str, route_label, &status_code])
-                .inc();
-            state_guard
-                .metrics
-                .http_request_duration_seconds
-                .with_label_values(&[&method_str, route_label])
-                .observe(start_time.elapsed().as_secs_f64());
-            drop(state_guard);
+/// Count a request validated against `plan` in the operation coverage report
+async fn record_coverage(
+    state: &Arc<tokio::sync::RwLock<AppState>>,
+    spec: &Path,
+    plan: &OperationValidationPlan,
+    passed: bool,
+    effective_config: &GlobalConfig,
+) {
+    let state_guard = state.read().await;
+    state_guard.metrics.record_operation_validation(
+        spec,
+        &plan.label(),
+        passed,
+        effective_config.openapi_operation_metrics,
+    );
+    drop(state_guard);
+}
+
+/// Forward a request that passed OpenAPI validation, then check the
+/// upstream response against the operation's declared headers and schema.
+/// `deprecated` requests get a `Deprecation` header on the response.
+async fn forward_openapi(
+    mut ctx: RequestContext,
+    headers: HeaderMap,
+    plan: &OperationValidationPlan,
+    deprecated: bool,
+    effective_config: &GlobalConfig,
+    state: Arc<tokio::sync::RwLock<AppState>>,
+) -> Response {
+    let start_time = ctx.start_time;
+    let body = std::mem::take(&mut ctx.body_bytes);
+    let method_str = ctx.method.as_str().to_uppercase();
+    let route_label = &ctx.route_pattern;
+
+    // Forward request and record upstream metrics
+    let response = ctx.send(headers, body).await;
+
+    // Record upstream metrics before response validation
+    {
+        let state_guard = state.read().await;
+        let status = response.status();
+        let status_code = status.as_u16().to_string();
+        state_guard
+            .metrics
+            .upstream_requests_total
+            .with_label_values(&[&status_code])
+            .inc();
+        drop(state_guard);
+    }
+
+    let response = match check_openapi_response_headers(plan, response.status(), response.headers())
+    {
+        Ok(()) => response,
+        Err(error_msg) => {
+            {
+                let state_guard = state.read().await;
+                state_guard
+                    .metrics
+                    .validation_failures_total
+                    .with_label_values(&[
+                        "openapi",
+                        "response_header_validation_failed",
+                        plan.spec_version.label(),
+                    ])
+                    .inc();
+                drop(state_guard);
+            }
+            tracing::warn!(
+                method = %ctx.method,
+                path = %ctx.path,
+                route_description = %ctx.route_description,
+                error = %error_msg,
+                "Response header validation failed"
+            );
+            if effective_config.forward_on_error {
+                let mut response = response;
+                add_error_header(&mut response, effective_config, &error_msg);
+                response
+            } else {
+                error_response(StatusCode::BAD_GATEWAY, &error_msg)
+            }
         }
+    };
 
-        response
-    } else {
-        // Record validation failure
+    let mut response = validate_openapi_response(response, plan, &ctx, effective_config).await;
+
+    if deprecated {
         {
             let state_guard = state.read().await;
             state_guard
                 .metrics
-                .validation_failures_total
-                .with_label_values(&["openapi", "validation_failed"])
+                .deprecated_requests_total
+                .with_label_values(&[&plan.label()])
                 .inc();
             drop(state_guard);
         }
+        if effective_config.deprecation_headers {
+            add_deprecation_headers(&mut response, plan, &ctx.route_description);
+        }
+    }
 
-        let error_msg = format!("Validation failed: {}", validation_result.errors.join(", "));
-        tracing::warn!(
-            method = %ctx.method,
-            path = %ctx.path,
-            errors = ?validation_result.errors,
-            "OpenAPI validation failed"
-        );
-        handle_error(
-            &error_msg,
-            &effective_config,
-            ctx,
-            state,
-            StatusCode::BAD_REQUEST,
-        )
-        .await
+    // Record final request metrics
+    {
+        let state_guard = state.read().await;
+        let status = response.status();
+        let status_code = status.as_u16().to_string();
+        state_guard
+            .metrics
+            .http_requests_total
+            .with_label_values(&[&method_str, route_label, &ctx.route_tags, &status_code])
+            .inc();
+        state_guard
+            .metrics
+            .http_request_duration_seconds
+            .with_label_values(&[&method_str, route_label, &ctx.route_tags])
+            .observe(start_time.elapsed().as_secs_f64());
+        drop(state_guard);
     }
+
+    response
+}
+
+/// Handle errors according to forward_on_error configuration
+async fn handle_error(
+    error_msg: &str,
+    effective_config: &GlobalConfig,
+    ctx: RequestContext,
+    state: Arc<tokio::sync::RwLock<AppState>>,
+    error_status: StatusCode,
+) -> Response {
+    handle_error_listing(error_msg, None, effective_config, ctx, state, error_status).await
+}
+
+/// The individual errors behind a validation failure, for the error body
+struct ErrorList {
+    errors: Vec<String>,
+    /// Includes errors cut from `errors`
+    error_count: usize,
+}
+
+/// A failed schema validation, handled like any other error but with the
+/// errors listed in the body of a rejection
+async fn handle_validation_failure(
+    result: &ValidationResult,
+    effective_config: &GlobalConfig,
+    ctx: RequestContext,
+    state: Arc<tokio::sync::RwLock<AppState>>,
+) -> Response {
+    let messages = if ctx.dev_mode {
+        result.details.clone()
+    } else {
+        result.messages()
+    };
+    let error_msg = format!("Validation failed: {}", messages.join(", "));
+    let errors = ErrorList {
+        errors: messages,
+        error_count: result.error_count,
+    };
+    handle_error_listing(
+        &error_msg,
+        Some(errors),
+        effective_config,
+        ctx,
+        state,
+        StatusCode::BAD_REQUEST,
+    )
+    .await
 }
 
-/// Handle errors according to forward_on_error configuration
-async fn handle_error(
+/// `handle_error`, listing `errors` in the body when the request is
+/// rejected
+async fn handle_error_listing(
     error_msg: &str,
+    errors: Option<ErrorList>,
     effective_config: &GlobalConfig,
-    ctx: RequestContext,
+    mut ctx: RequestContext,
     state: Arc<tokio::sync::RwLock<AppState>>,
     error_status: StatusCode,
 ) -> Response {
@@ -706,13 +2203,14 @@ async fn handle_error(
         tracing::warn!(
             method = %ctx.method,
             path = %ctx.path,
+            route_description = %ctx.route_description,
             upstream = %ctx.upstream_url,
             error = %error_msg,
             "Forwarding request to upstream despite error (forward_on_error: true)"
         );
 
         // Add error header to request if configured
-        let mut forwarding_headers = ctx.headers;
+        let mut forwarding_headers = std::mem::take(&mut ctx.headers);
         if effective_config.add_error_header {
             if let Ok(header_value) = error_msg.parse() {
                 forwarding_headers.insert("X-Gateway-Error", header_value);
@@ -720,21 +2218,9 @@ async fn handle_error(
         }
 
         // Forward request and record upstream metrics
-        let upstream_start = Instant::now();
-        let state_guard = state.read().await;
-        let response = forward_request(
-            &state_guard.http_client,
-            ctx.method.clone(),
-            &ctx.upstream_url,
-            &ctx.path_and_query,
-            forwarding_headers,
-            ctx.body_bytes.clone(),
-        )
-        .await;
-        let upstream_duration = upstream_start.elapsed().as_secs_f64();
+        let response = ctx.send(forwarding_headers, ctx.body_bytes.clone()).await;
         let response_status = response.status();
         let response_status_code = response_status.as_u16().to_string();
-        drop(state_guard);
 
         // Record upstream metrics
         {
@@ -744,13 +2230,13 @@ async fn handle_error(
                 .upstream_requests_total
                 .with_label_values(&[&response_status_code])
                 .inc();
-            state_guard
-                .metrics
-                .upstream_request_duration_seconds
-                .with_label_values(&[])
-                .observe(upstream_duration);
             // Record upstream errors if status indicates error
-            if response_status.is_server_error() || response_status.is_client_error() {
+            // Requests that got no response were counted by their failure's
+            // kind already
+            let no_response = response.extensions().get::<UpstreamErrorKind>().is_some();
+            if !no_response
+                && (response_status.is_server_error() || response_status.is_client_error())
+            {
                 let error_type = if response_status.is_server_error() {
                     "server_error"
                 } else {
@@ -771,8 +2257,18 @@ async fn handle_error(
             state_guard
                 .metrics
                 .http_requests_total
-                .with_label_values(&[&method_str, route_label, &response_status_code])
+                .with_label_values(&[
+                    &method_str,
+                    route_label,
+                    &ctx.route_tags,
+                    &response_status_code,
+                ])
                 .inc();
+            state_guard
+                .metrics
+                .http_request_duration_seconds
+                .with_label_values(&[&method_str, route_label, &ctx.route_tags])
+                .observe(ctx.start_time.elapsed().as_secs_f64());
             drop(state_guard);
         }
 
@@ -782,6 +2278,7 @@ async fn handle_error(
         tracing::warn!(
             method = %ctx.method,
             path = %ctx.path,
+            route_description = %ctx.route_description,
             error = %error_msg,
             status = %error_status,
             "Rejecting request due to error (forward_on_error: false)"
@@ -793,17 +2290,29 @@ async fn handle_error(
             state_guard
                 .metrics
                 .http_requests_total
-                .with_label_values(&[&method_str, route_label, &status_code])
+                .with_label_values(&[&method_str, route_label, &ctx.route_tags, &status_code])
                 .inc();
+            state_guard
+                .metrics
+                .http_request_duration_seconds
+                .with_label_values(&[&method_str, route_label, &ctx.route_tags])
+                .observe(ctx.start_time.elapsed().as_secs_f64());
             drop(state_guard);
         }
 
-        let error_body = serde_json::json!({
-            "error": error_msg
-        });
-        let body_str = serde_json::to_string(&error_body)
-            .unwrap_or_else(|_| format!(r#"{{"error":"{}"}}"#, error_msg));
-        (error_status, body_str).into_response()
+        match errors {
+            Some(errors) => validation_error_response(
+                error_status,
+                error_msg,
+                &errors.errors,
+                errors.error_count,
+                effective_config.pretty_print_errors,
+            ),
+            None if effective_config.pretty_print_errors => {
+                pretty_error_response(error_status, error_msg)
+            }
+            None => error_response(error_status, error_msg),
+        }
     }
 }
 
@@ -813,12 +2322,18 @@ async fn parse_json_body_or_handle_error(
     state: Arc<tokio::sync::RwLock<AppState>>,
 ) -> Result<Value, Response> {
     match serde_json::from_slice::<Value>(&ctx.body_bytes) {
-        Ok(v) => Ok(v),
+        Ok(v) => {
+            if effective_config.log_request_body {
+                log_request_body(&ctx, effective_config.log_request_body_max_bytes);
+            }
+            Ok(v)
+        }
         Err(e) => {
             let error_msg = format!("Invalid JSON: {}", e);
             tracing::warn!(
                 method = %ctx.method,
                 path = %ctx.path,
+                route_description = %ctx.route_description,
                 error = %e,
                 "Failed to parse JSON body"
             );
@@ -834,174 +2349,399 @@ async fn parse_json_body_or_handle_error(
     }
 }
 
-async fn validate_openapi_parameters(
-    plan: &OperationValidationPlan,
-    ctx: &RequestContext,
+/// Log up to `max_bytes` of the request body at DEBUG, flagged `pii_risk`
+/// so log pipelines can drop it
+fn log_request_body(ctx: &RequestContext, max_bytes: usize) {
+    let body = String::from_utf8_lossy(&ctx.body_bytes);
+    let excerpt = if body.len() > max_bytes {
+        let mut end = max_bytes;
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("{}… (truncated)", &body[..end])
+    } else {
+        body.into_owned()
+    };
+    tracing::debug!(
+        method = %ctx.method,
+        path = %ctx.path,
+        pii_risk = true,
+        body = %excerpt,
+        "Request body"
+    );
+}
+
+/// Read a `multipart/form-data` body into the object its schema describes.
+/// Other content types are rejected with 415. The body forwarded upstream
+/// is the original bytes.
+async fn read_multipart_or_handle_error(
+    form: &MultipartForm,
+    spec_version: &str,
+    mut ctx: RequestContext,
     effective_config: &GlobalConfig,
     state: Arc<tokio::sync::RwLock<AppState>>,
-) -> Result<(), Response> {
-    let path_params = extract_path_params(&ctx.path, &plan.path_template);
-    let path_params = match path_params {
-        Some(params) => params,
-        None => {
-            let error_msg = format!(
-                "Route '{}' no longer matches OpenAPI template '{}'",
-                ctx.path, plan.path_template
-            );
-            return Err(handle_error(
-                &error_msg,
-                effective_config,
-                ctx.clone(),
-                state,
-                StatusCode::BAD_REQUEST,
-            )
-            .await);
+) -> Result<Value, Response> {
+    let boundary = ctx
+        .headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|content_type| multer::parse_boundary(content_type).ok());
+
+    let (result, error_status, error_type) = match boundary {
+        Some(boundary) => {
+            let body = Bytes::from(std::mem::take(&mut ctx.body_bytes));
+            let result = read_multipart(form, body.clone(), boundary).await;
+            ctx.body_bytes = Vec::from(body);
+            (result, StatusCode::BAD_REQUEST, "invalid_multipart")
         }
+        None => (
+            Err("Expected a multipart/form-data body".to_string()),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "unsupported_media_type",
+        ),
     };
 
-    let query_params = parse_query_params(ctx.query.as_deref());
-    let header_params = build_header_lookup(&ctx.headers);
-    let cookie_params = parse_cookie_header(&ctx.headers);
-
-    for param in &plan.parameters {
-        let raw_value = match param.location {
-            ParameterLocation::Path => path_params.get(&param.name).cloned(),
-            ParameterLocation::Query => query_params
-                .get(&param.name)
-                .and_then(|vals| vals.first().cloned()),
-            ParameterLocation::Header => {
-                header_params.get(&param.name.to_ascii_lowercase()).cloned()
+    match result {
+        Ok(value) => Ok(value),
+        Err(error_msg) => {
+            {
+                let state_guard = state.read().await;
+                state_guard
+                    .metrics
+                    .validation_failures_total
+                    .with_label_values(&["openapi", error_type, spec_version])
+                    .inc();
+                drop(state_guard);
             }
-            ParameterLocation::Cookie => cookie_params.get(&param.name).cloned(),
-        };
+            tracing::warn!(
+                method = %ctx.method,
+                path = %ctx.path,
+                route_description = %ctx.route_description,
+                error = %error_msg,
+                "Failed to read multipart body"
+            );
+            Err(handle_error(&error_msg, effective_config, ctx, state, error_status).await)
+        }
+    }
+}
 
-        let Some(raw_value) = raw_value else {
-            if param.required {
-                let error_msg = format!(
-                    "Missing required {} parameter '{}'",
-                    parameter_location_label(param.location),
-                    param.name
-                );
-                return Err(handle_error(
-                    &error_msg,
-                    effective_config,
-                    ctx.clone(),
-                    state,
-                    StatusCode::BAD_REQUEST,
-                )
-                .await);
-            }
-            continue;
+async fn read_multipart(
+    form: &MultipartForm,
+    body: Bytes,
+    boundary: String,
+) -> Result<Value, String> {
+    let invalid = |e: multer::Error| format!("Invalid multipart body: {}", e);
+    let mut multipart = multer::Multipart::new(Body::from(body).into_data_stream(), boundary);
+    let mut parts = Vec::new();
+    while let Some(mut field) = multipart.next_field().await.map_err(invalid)? {
+        let name = field.name().unwrap_or_default().to_string();
+        let file_name = field.file_name().map(str::to_string);
+        let text = if form.is_file(&name, file_name.as_deref()) {
+            // Skip over the content without collecting it
+            while field.chunk().await.map_err(invalid)?.is_some() {}
+            None
+        } else {
+            Some(field.text().await.map_err(invalid)?)
         };
+        parts.push(FormPart {
+            name,
+            file_name,
+            text,
+        });
+    }
+    form.assemble(parts)
+}
 
-        let Some(schema) = &param.schema else {
-            continue;
-        };
+/// Validate incoming headers against the route's `headers_schema`.
+/// Runs before body validation so header problems fail fast.
+async fn validate_request_headers(
+    ctx: &RequestContext,
+    schema_path: &Path,
+    effective_config: &GlobalConfig,
+    state: Arc<tokio::sync::RwLock<AppState>>,
+) -> Result<(), Response> {
+    // Record validation attempt
+    {
+        let state_guard = state.read().await;
+        state_guard
+            .metrics
+            .validation_attempts_total
+            .with_label_values(&["headers", ""])
+            .inc();
+        drop(state_guard);
+    }
 
-        let coerced_value = match param.coerce_value(&raw_value) {
-            Ok(value) => value,
-            Err(message) => {
-                return Err(handle_error(
-                    &message,
-                    effective_config,
-                    ctx.clone(),
-                    state,
-                    StatusCode::BAD_REQUEST,
-                )
-                .await);
-            }
+    let schema_result = {
+        let state_guard = state.read().await;
+        let (was_cached, result, cache_len) = {
+            let schema_cache = state_guard.schema_cache.read();
+            let was_cached = schema_cache.contains(schema_path);
+            let result = schema_cache.load(schema_path);
+            (was_cached, result, schema_cache.len())
         };
+        state_guard
+            .metrics
+            .schema_cache_entries
+            .with_label_values(&["schema"])
+            .set(cache_len as i64);
+        if was_cached {
+            state_guard.metrics.schema_cache_hits_total.inc();
+        } else if !is_cached_failure(&result) {
+            state_guard.metrics.schema_cache_misses_total.inc();
+        }
+        drop(state_guard);
+        result
+    };
 
-        let validation_error = schema.validate(&coerced_value).err();
-        if let Some(mut errors) = validation_error {
-            let first_error = errors
-                .next()
-                .map(|e| e.to_string())
-                .unwrap_or_else(|| "Parameter validation failed".to_string());
-            drop(errors);
-            let error_msg = format!("Parameter '{}' invalid: {}", param.name, first_error);
+    let schema = match schema_result {
+        Ok(schema) => schema,
+        Err(e) => {
+            let error_msg = format!("{}", e);
+            if matches!(e, Error::Cached { .. }) {
+                tracing::debug!(
+                    method = %ctx.method,
+                    path = %ctx.path,
+                    schema_path = %schema_path.display(),
+                    error = %e,
+                    "Failed to load headers schema (cached failure)"
+                );
+            } else {
+                tracing::warn!(
+                    method = %ctx.method,
+                    path = %ctx.path,
+                    route_description = %ctx.route_description,
+                    schema_path = %schema_path.display(),
+                    error = %e,
+                    "Failed to load headers schema"
+                );
+            }
+            {
+                let state_guard = state.read().await;
+                state_guard
+                    .metrics
+                    .validation_failures_total
+                    .with_label_values(&["headers", "schema_load_error", ""])
+                    .inc();
+                drop(state_guard);
+            }
             return Err(handle_error(
                 &error_msg,
                 effective_config,
                 ctx.clone(),
                 state,
-                StatusCode::BAD_REQUEST,
+                StatusCode::INTERNAL_SERVER_ERROR,
             )
             .await);
         }
-    }
-
-    Ok(())
-}
+    };
 
-fn extract_path_params(path: &str, template: &str) -> Option<HashMap<String, String>> {
-    let actual_segments: Vec<&str> = path.trim_matches('/').split('/').collect();
-    let template_segments: Vec<&str> = template.trim_matches('/').split('/').collect();
+    let headers_value = build_header_object(&ctx.headers);
+    let messages: Vec<String> = match schema.validate(&headers_value) {
+        Ok(_) => Vec::new(),
+        Err(errors) => errors.map(|e| describe_header_error(&e)).collect(),
+    };
 
-    if actual_segments.len() != template_segments.len() {
-        return None;
+    if messages.is_empty() {
+        let state_guard = state.read().await;
+        state_guard
+            .metrics
+            .validation_success_total
+            .with_label_values(&["headers", ""])
+            .inc();
+        drop(state_guard);
+        return Ok(());
     }
 
-    let mut params = HashMap::new();
-    for (actual, pattern) in actual_segments.iter().zip(template_segments.iter()) {
-        if pattern.starts_with('{') && pattern.ends_with('}') {
-            let name = pattern.trim_start_matches('{').trim_end_matches('}');
-            params.insert(name.to_string(), (*actual).to_string());
-        } else if pattern != actual {
-            return None;
-        }
+    {
+        let state_guard = state.read().await;
+        state_guard
+            .metrics
+            .validation_failures_total
+            .with_label_values(&["headers", "validation_failed", ""])
+            .inc();
+        drop(state_guard);
     }
 
-    Some(params)
+    let error_msg = format!("Header validation failed: {}", messages.join(", "));
+    tracing::warn!(
+        method = %ctx.method,
+        path = %ctx.path,
+        route_description = %ctx.route_description,
+        errors = ?messages,
+        "Header validation failed"
+    );
+    Err(handle_error(
+        &error_msg,
+        effective_config,
+        ctx.clone(),
+        state,
+        StatusCode::BAD_REQUEST,
+    )
+    .await)
 }
 
-fn parse_query_params(query: Option<&str>) -> HashMap<String, Vec<String>> {
-    let mut map = HashMap::new();
-    if let Some(q) = query {
-        for (key, value) in form_urlencoded::parse(q.as_bytes()) {
-            map.entry(key.into_owned())
-                .or_insert_with(Vec::new)
-                .push(value.into_owned());
+pub(crate) fn describe_header_error(error: &ValidationError) -> String {
+    if let ValidationErrorKind::Required { property } = &error.kind {
+        if let Some(name) = property.as_str() {
+            return format!("Missing required header '{}'", name);
         }
     }
-    map
+
+    let instance_path = error.instance_path.to_string();
+    let header_name = instance_path.trim_start_matches('/');
+    if header_name.is_empty() {
+        error.to_string()
+    } else {
+        format!("Header '{}' invalid: {}", header_name, error)
+    }
 }
 
-fn build_header_lookup(headers: &HeaderMap) -> HashMap<String, String> {
-    let mut map = HashMap::new();
-    for (name, value) in headers.iter() {
-        if let Ok(val_str) = value.to_str() {
-            map.entry(name.as_str().to_ascii_lowercase())
-                .or_insert_with(|| val_str.to_string());
-        }
+async fn validate_openapi_parameters(
+    plan: &OperationValidationPlan,
+    operation_path: &str,
+    ctx: &RequestContext,
+    effective_config: &GlobalConfig,
+    state: Arc<tokio::sync::RwLock<AppState>>,
+) -> Result<(), Response> {
+    match plan.check_parameters(
+        operation_path,
+        &ctx.path_params,
+        ctx.query.as_deref(),
+        &ctx.headers,
+    ) {
+        Ok(()) => Ok(()),
+        Err(error_msg) => Err(handle_error(
+            &error_msg,
+            effective_config,
+            ctx.clone(),
+            state,
+            StatusCode::BAD_REQUEST,
+        )
+        .await),
     }
-    map
 }
 
-fn parse_cookie_header(headers: &HeaderMap) -> HashMap<String, String> {
-    let mut map = HashMap::new();
-    if let Some(cookie_header) = headers.get("cookie") {
-        if let Ok(cookie_str) = cookie_header.to_str() {
-            for pair in cookie_str.split(';') {
-                let mut parts = pair.trim().splitn(2, '=');
-                if let Some(name) = parts.next() {
-                    if let Some(value) = parts.next() {
-                        map.insert(name.trim().to_string(), value.trim().to_string());
-                    }
+/// Add the spec's `default` for each optional query or header parameter the
+/// request doesn't have, so the upstream sees it
+fn apply_parameter_defaults(plan: &OperationValidationPlan, ctx: &mut RequestContext) {
+    let query_params = parse_query_params(ctx.query.as_deref());
+    let mut query = form_urlencoded::Serializer::new(ctx.query.clone().unwrap_or_default());
+    let mut query_changed = false;
+
+    for param in plan.parameters.iter().filter(|param| !param.required) {
+        match param.location {
+            ParameterLocation::Query if param.query_value(&query_params).is_none() => {
+                for value in param.default_query_values() {
+                    query.append_pair(&param.name, &value);
+                    query_changed = true;
+                }
+            }
+            ParameterLocation::Header => {
+                let Ok(name) = HeaderName::from_bytes(param.name.as_bytes()) else {
+                    continue;
+                };
+                if ctx.headers.contains_key(&name) {
+                    continue;
+                }
+                let value = param
+                    .default_header_value()
+                    .and_then(|value| HeaderValue::from_str(&value).ok());
+                if let Some(value) = value {
+                    ctx.headers.insert(name, value);
                 }
             }
+            _ => {}
+        }
+    }
+
+    if query_changed {
+        let query = query.finish();
+        ctx.path_and_query = build_forward_path(&ctx.upstream_path, Some(&query));
+        ctx.query = Some(query);
+    }
+}
+
+/// Check that the request carries the credentials at least one of the
+/// operation's security requirements asks for. Only presence and shape are
+/// checked; the credentials themselves are left to the upstream.
+pub(crate) fn check_openapi_security(
+    requirements: &[SecurityRequirement],
+    query: Option<&str>,
+    headers: &HeaderMap,
+) -> Result<(), String> {
+    if requirements.is_empty() {
+        return Ok(());
+    }
+
+    let query_params = parse_query_params(query);
+    let header_values = build_header_lookup(headers);
+    let cookies = parse_cookie_header(headers);
+    let present = |scheme: &SecurityScheme| match scheme {
+        SecurityScheme::ApiKey { name, location } => match location {
+            ParameterLocation::Query => query_params.contains_key(name),
+            ParameterLocation::Cookie => cookies.contains_key(name),
+            _ => header_values.contains_key(&name.to_ascii_lowercase()),
+        },
+        SecurityScheme::Http { scheme } => header_values
+            .get("authorization")
+            .and_then(|value| value.split_once(' '))
+            .is_some_and(|(prefix, credentials)| {
+                prefix.eq_ignore_ascii_case(scheme) && !credentials.trim().is_empty()
+            }),
+    };
+
+    if requirements
+        .iter()
+        .any(|requirement| requirement.schemes.iter().all(present))
+    {
+        return Ok(());
+    }
+
+    let expected: Vec<String> = requirements
+        .iter()
+        .map(|requirement| {
+            requirement
+                .schemes
+                .iter()
+                .map(describe_security_scheme)
+                .collect::<Vec<_>>()
+                .join(" and ")
+        })
+        .collect();
+    Err(format!(
+        "Missing credentials: expected {}",
+        expected.join(" or ")
+    ))
+}
+
+fn describe_security_scheme(scheme: &SecurityScheme) -> String {
+    match scheme {
+        SecurityScheme::ApiKey { name, location } => {
+            format!("{} '{}'", parameter_location_label(*location), name)
         }
+        SecurityScheme::Http { scheme } => format!("'{}' Authorization header", scheme),
     }
-    map
 }
 
-fn parameter_location_label(location: ParameterLocation) -> &'static str {
-    match location {
-        ParameterLocation::Path => "path",
-        ParameterLocation::Query => "query",
-        ParameterLocation::Header => "header",
-        ParameterLocation::Cookie => "cookie",
+/// Build a JSON object of lowercase header names to values.
+/// Headers that appear more than once become arrays of strings.
+pub(crate) fn build_header_object(headers: &HeaderMap) -> Value {
+    let mut map = serde_json::Map::new();
+    for name in headers.keys() {
+        let values: Vec<Value> = headers
+            .get_all(name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .map(|value| Value::String(value.to_string()))
+            .collect();
+        let entry = match values.len() {
+            0 => continue,
+            1 => values.into_iter().next().unwrap_or(Value::Null),
+            _ => Value::Array(values),
+        };
+        map.insert(name.as_str().to_ascii_lowercase(), entry);
     }
+    Value::Object(map)
 }
 
 async fn validate_openapi_response(
@@ -1010,35 +2750,64 @@ async fn validate_openapi_response(
     ctx: &RequestContext,
     effective_config: &GlobalConfig,
 ) -> Response {
-    if plan.response_schemas.is_empty() {
+    // Informational responses have no body to validate
+    if response.status().is_informational() {
         return response;
     }
 
-    let schema = match select_response_schema(&plan.response_schemas, response.status()) {
-        Some(schema) => schema,
-        None => return response,
-    };
+    if matches!(
+        response.status(),
+        StatusCode::NO_CONTENT | StatusCode::NOT_MODIFIED
+    ) {
+        return check_bodiless_response(response, ctx, effective_config).await;
+    }
 
-    if !has_json_content_type(response.headers()) {
+    let Some(content) = ResponseKey::lookup(&plan.response_schemas, response.status().as_u16())
+    else {
         return response;
-    }
+    };
+    let Some(content_type) = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+    else {
+        return response;
+    };
+    let schema = match content.lookup(&content_type) {
+        Some(Some(schema)) => Some(schema.clone()),
+        None if effective_config.reject_undeclared_response_types => None,
+        _ => return response,
+    };
 
     let (parts, body) = response.into_parts();
-    let body_bytes = match axum::body::to_bytes(body, usize::MAX).await {
-        Ok(bytes) => bytes,
-        Err(_) => {
-            let error_msg = "Failed to read upstream response body".to_string();
+    let limit = effective_config.max_response_body_bytes;
+    let body_bytes = match read_response_body(body, &parts.headers, limit).await {
+        Ok(ResponseBody::Complete(bytes)) => bytes,
+        Ok(ResponseBody::TooLarge(body)) => {
+            let response = Response::from_parts(parts, body);
+            if schema.is_none() {
+                return reject_undeclared_response(response, &content_type, ctx, effective_config);
+            }
+            tracing::warn!(
+                method = %ctx.method,
+                path = %ctx.path,
+                route_description = %ctx.route_description,
+                limit = limit.unwrap_or_default(),
+                "Response body exceeds max_response_body_bytes; passing it on unvalidated"
+            );
+            return response;
+        }
+        Err(e) => {
+            let error_msg = unreadable_body_message(e);
             tracing::warn!(
                 method = %ctx.method,
                 path = %ctx.path,
+                route_description = %ctx.route_description,
                 error = %error_msg,
                 "Unable to validate OpenAPI response"
             );
-            return (
-                StatusCode::BAD_GATEWAY,
-                serde_json::json!({ "error": error_msg }).to_string(),
-            )
-                .into_response();
+            return error_response(StatusCode::BAD_GATEWAY, &error_msg);
         }
     };
 
@@ -1048,6 +2817,10 @@ async fn validate_openapi_response(
 
     let mut rebuilt = Response::from_parts(parts, Body::from(body_bytes.clone()));
 
+    let Some(schema) = schema else {
+        return reject_undeclared_response(rebuilt, &content_type, ctx, effective_config);
+    };
+
     match serde_json::from_slice::<Value>(&body_bytes) {
         Ok(json) => match schema.validate(&json) {
             Ok(_) => rebuilt,
@@ -1060,6 +2833,7 @@ async fn validate_openapi_response(
                 tracing::warn!(
                     method = %ctx.method,
                     path = %ctx.path,
+                    route_description = %ctx.route_description,
                     error = %error_msg,
                     "Response validation failed"
                 );
@@ -1067,11 +2841,7 @@ async fn validate_openapi_response(
                     add_error_header(&mut rebuilt, effective_config, &error_msg);
                     rebuilt
                 } else {
-                    (
-                        StatusCode::BAD_GATEWAY,
-                        serde_json::json!({ "error": error_msg }).to_string(),
-                    )
-                        .into_response()
+                    error_response(StatusCode::BAD_GATEWAY, &error_msg)
                 }
             }
         },
@@ -1080,6 +2850,7 @@ async fn validate_openapi_response(
             tracing::warn!(
                 method = %ctx.method,
                 path = %ctx.path,
+                route_description = %ctx.route_description,
                 error = %error_msg,
                 "Response JSON parse failed"
             );
@@ -1087,23 +2858,120 @@ async fn validate_openapi_response(
                 add_error_header(&mut rebuilt, effective_config, &error_msg);
                 rebuilt
             } else {
-                (
-                    StatusCode::BAD_GATEWAY,
-                    serde_json::json!({ "error": error_msg }).to_string(),
-                )
-                    .into_response()
+                error_response(StatusCode::BAD_GATEWAY, &error_msg)
             }
         }
     }
 }
 
-fn select_response_schema(
-    map: &HashMap<ResponseKey, Arc<JSONSchema>>,
+/// Answer for a non-empty response whose `Content-Type` the spec doesn't
+/// declare, under `reject_undeclared_response_types`
+fn reject_undeclared_response(
+    mut response: Response,
+    content_type: &str,
+    ctx: &RequestContext,
+    effective_config: &GlobalConfig,
+) -> Response {
+    let error_msg = format!(
+        "Upstream response Content-Type '{}' is not declared for status {}",
+        content_type,
+        response.status().as_u16()
+    );
+    tracing::warn!(
+        method = %ctx.method,
+        path = %ctx.path,
+        route_description = %ctx.route_description,
+        error = %error_msg,
+        "Response validation failed"
+    );
+    if effective_config.forward_on_error {
+        add_error_header(&mut response, effective_config, &error_msg);
+        return response;
+    }
+    error_response(StatusCode::BAD_GATEWAY, &error_msg)
+}
+
+/// 204 and 304 responses must not have a body, whatever the spec declares
+/// for them
+async fn check_bodiless_response(
+    response: Response,
+    ctx: &RequestContext,
+    effective_config: &GlobalConfig,
+) -> Response {
+    let (parts, body) = response.into_parts();
+    let body_bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => return error_response(StatusCode::BAD_GATEWAY, &unreadable_body_message(e)),
+    };
+    if body_bytes.is_empty() {
+        return Response::from_parts(parts, Body::empty());
+    }
+
+    let error_msg = format!(
+        "Upstream {} response must not have a body",
+        parts.status.as_u16()
+    );
+    tracing::warn!(
+        method = %ctx.method,
+        path = %ctx.path,
+        route_description = %ctx.route_description,
+        error = %error_msg,
+        "Response validation failed"
+    );
+    if effective_config.forward_on_error {
+        let mut response = Response::from_parts(parts, Body::from(body_bytes));
+        add_error_header(&mut response, effective_config, &error_msg);
+        response
+    } else {
+        error_response(StatusCode::BAD_GATEWAY, &error_msg)
+    }
+}
+
+/// Check the upstream response against the headers its status declares.
+/// Missing optional headers are fine.
+pub(crate) fn check_openapi_response_headers(
+    plan: &OperationValidationPlan,
     status: StatusCode,
-) -> Option<Arc<JSONSchema>> {
-    map.get(&ResponseKey::Status(status.as_u16()))
-        .cloned()
-        .or_else(|| map.get(&ResponseKey::Default).cloned())
+    headers: &HeaderMap,
+) -> Result<(), String> {
+    let declared = ResponseKey::lookup(&plan.response_headers, status.as_u16());
+    let Some(declared) = declared else {
+        return Ok(());
+    };
+
+    let header_values = build_header_lookup(headers);
+    for header in declared {
+        let Some(raw) = header_values.get(&header.name.to_ascii_lowercase()) else {
+            if header.required {
+                return Err(format!(
+                    "Missing required response header '{}'",
+                    header.name
+                ));
+            }
+            continue;
+        };
+
+        let Some(schema) = &header.schema else {
+            continue;
+        };
+
+        let value = header
+            .parse_value(raw)
+            .map_err(|e| format!("Invalid response header: {}", e))?;
+        let validation_error = schema.validate(&value).err();
+        if let Some(mut errors) = validation_error {
+            let (name, first_error) = errors
+                .next()
+                .map(|e| (header.name_at(&e.instance_path.to_string()), e.to_string()))
+                .unwrap_or_else(|| (header.name.clone(), "Header validation failed".to_string()));
+            return Err(format!(
+                "Response header '{}' invalid: {}",
+                name, first_error
+            ));
+        }
+    }
+
+    Ok(())
 }
 
 fn has_json_content_type(headers: &HeaderMap) -> bool {
@@ -1114,6 +2982,30 @@ fn has_json_content_type(headers: &HeaderMap) -> bool {
         .unwrap_or(false)
 }
 
+/// Mark a response to a deprecated operation or parameter with
+/// `Deprecation: true`, and `Sunset` when the operation has an `x-sunset`
+fn add_deprecation_headers(
+    response: &mut Response,
+    plan: &OperationValidationPlan,
+    route_description: &str,
+) {
+    let headers = response.headers_mut();
+    headers.insert(DEPRECATION_HEADER, HeaderValue::from_static("true"));
+    if let Some(sunset) = &plan.sunset {
+        match HeaderValue::from_str(sunset) {
+            Ok(value) => {
+                headers.insert(SUNSET_HEADER, value);
+            }
+            Err(_) => tracing::warn!(
+                operation = %plan.label(),
+                route_description = %route_description,
+                "Ignoring x-sunset '{}' that is not a valid header value",
+                sunset
+            ),
+        }
+    }
+}
+
 fn add_error_header(response: &mut Response, config: &GlobalConfig, message: &str) {
     if !config.add_error_header {
         return;
@@ -1123,6 +3015,12 @@ fn add_error_header(response: &mut Response, config: &GlobalConfig, message: &st
     }
 }
 
+/// Whether a load result is a failure replayed from the negative cache
+/// rather than a real attempt to read the file.
+fn is_cached_failure<T>(result: &crate::error::Result<T>) -> bool {
+    matches!(result, Err(Error::Cached { .. }))
+}
+
 fn build_forward_path(path: &str, query: Option<&str>) -> String {
     match query {
         Some(q) if !q.is_empty() => format!("{}?{}", path, q),
@@ -2,6 +2,7 @@ use axum::extract::State;
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 
 use crate::handler::AppState;
@@ -13,21 +14,34 @@ pub async fn health() -> Response {
 }
 
 /// Liveness probe endpoint
-/// Returns 200 OK if the server process is alive
-pub async fn liveness() -> Response {
-    (StatusCode::OK, "Alive").into_response()
+/// Returns 200 OK if the gateway state can be locked within `timeout`, and
+/// 503 if something has held it for longer, e.g. a stuck reload. The lock
+/// can't be poisoned: a request that panics releases it on unwind.
+pub async fn liveness(State(state): State<Arc<RwLock<AppState>>>, timeout: Duration) -> Response {
+    match tokio::time::timeout(timeout, state.read()).await {
+        Ok(_) => (StatusCode::OK, "Alive").into_response(),
+        Err(_) => lock_contention(),
+    }
 }
 
 /// Readiness probe endpoint
 /// Returns 200 OK if the server is ready to accept requests
-/// Checks that config is loaded and routes are available
-pub async fn readiness(State(state): State<Arc<RwLock<AppState>>>) -> Response {
-    let state_guard = state.read().await;
+/// Checks that the state can be locked within `timeout`, config is loaded
+/// and routes are available
+pub async fn readiness(State(state): State<Arc<RwLock<AppState>>>, timeout: Duration) -> Response {
+    let Ok(state_guard) = tokio::time::timeout(timeout, state.read()).await else {
+        return lock_contention();
+    };
 
     // Check if we have at least one route configured
-    if state_guard.config.routes.is_empty() {
+    if state_guard.routing().config.routes.is_empty() {
         return (StatusCode::SERVICE_UNAVAILABLE, "No routes configured").into_response();
     }
 
     (StatusCode::OK, "Ready").into_response()
 }
+
+fn lock_contention() -> Response {
+    tracing::warn!("Health check timed out waiting for the gateway state lock");
+    (StatusCode::SERVICE_UNAVAILABLE, "Lock contention").into_response()
+}
@@ -1,10 +1,21 @@
+pub mod admin;
+pub mod body_hash;
+pub mod check;
 pub mod cli;
 pub mod config;
+pub mod content_encoding;
+pub mod coverage;
 pub mod error;
+pub mod example;
 pub mod handler;
 pub mod health;
 pub mod metrics;
 pub mod openapi;
+pub mod pid_file;
 pub mod proxy;
+pub mod rate_limiter;
+pub mod reload;
+pub mod router;
 pub mod schema;
+pub mod tls;
 pub mod validation;
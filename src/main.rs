@@ -1,18 +1,22 @@
-use axum::body::Body;
-use axum::extract::State;
-use axum::http::{HeaderMap, Method, StatusCode, Uri};
-use axum::response::{IntoResponse, Response};
-use axum::routing::{any, get};
-use axum::Router;
-use schema_gateway::cli::Cli;
-use schema_gateway::config::Config;
-use schema_gateway::handler::{build_http_client, handle_request, AppState};
-use schema_gateway::health;
+use axum_server::tls_rustls::RustlsConfig;
+use schema_gateway::admin::{build_admin_router, coverage_report};
+use schema_gateway::check;
+use schema_gateway::cli::{Cli, Command};
+use schema_gateway::config::{Config, MockBody};
+use schema_gateway::handler::{build_http_client, build_schema_cache, AppState, UpstreamClients};
 use schema_gateway::metrics::Metrics;
 use schema_gateway::openapi::OpenApiCache;
-use schema_gateway::schema::SchemaCache;
+use schema_gateway::pid_file::PidFile;
+use schema_gateway::reload;
+use schema_gateway::router::build_router;
+use schema_gateway::schema::{PreloadReport, SchemaCache, SchemaOptions};
+use schema_gateway::tls;
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::{Notify, RwLock};
 
 #[tokio::main]
 async fn main() {
@@ -26,6 +30,13 @@ async fn main() {
 
     // Parse CLI arguments
     let cli = Cli::parse_args();
+    let tls_files = match cli.tls_files() {
+        Ok(files) => files,
+        Err(e) => {
+            eprintln!("Invalid TLS options: {}", e);
+            std::process::exit(1);
+        }
+    };
 
     // Load config from file
     let config = match Config::from_file(&cli.config) {
@@ -42,9 +53,68 @@ async fn main() {
         std::process::exit(1);
     }
 
+    // Certificates for upstream TLS are loaded now so problems fail fast
+    if let Some(e) = UpstreamClients::from_config(&config).first_error() {
+        eprintln!("Invalid upstream TLS config: {}", e);
+        std::process::exit(1);
+    }
+
+    // Offline payload check: validate and exit without starting the server
+    if let Some(Command::Check(args)) = &cli.command {
+        std::process::exit(check::run(&config, args));
+    }
+
     // If validate-config mode, exit after validation
     if cli.validate_config {
+        // Specs are otherwise only checked one operation at a time, as
+        // requests arrive
+        let specs: BTreeSet<PathBuf> = config
+            .all_routes()
+            .filter_map(|route| route.openapi_options().map(|o| o.spec))
+            .collect();
+        let cache = OpenApiCache::new();
+        let mut spec_errors = 0;
+        for spec in &specs {
+            let problems = cache.check_spec(spec);
+            if problems.is_empty() {
+                continue;
+            }
+            eprintln!("Invalid OpenAPI spec {}:", spec.display());
+            for problem in &problems {
+                eprintln!("  {}", problem);
+            }
+            spec_errors += problems.len();
+        }
+        if spec_errors > 0 {
+            eprintln!("Found {} problem(s) in OpenAPI specs", spec_errors);
+            std::process::exit(1);
+        }
+
         println!("Config valid: {}", cli.config.display());
+        for route in config.all_routes() {
+            let label = if route.method.is_empty() {
+                "default_route".to_string()
+            } else {
+                format!("{} {}", route.method, route.pattern())
+            };
+            let spec = route.openapi_options().map(|o| o.spec);
+            let mock_file = match route.mock_response.as_ref().and_then(|m| m.body.as_ref()) {
+                Some(MockBody::File { file }) => Some(file),
+                _ => None,
+            };
+            let files = route
+                .schema
+                .iter()
+                .chain(route.headers_schema.iter())
+                .chain(spec.iter())
+                .chain(mock_file);
+            if !route.tags.is_empty() {
+                println!("  {}: tags {}", label, route.tags.join(", "));
+            }
+            for file in files {
+                println!("  {}: {}", label, file.display());
+            }
+        }
         std::process::exit(0);
     }
 
@@ -60,24 +130,64 @@ async fn main() {
         std::process::exit(1);
     }));
 
-    let app_state = AppState {
-        config,
-        schema_cache: SchemaCache::new(),
-        openapi_cache: OpenApiCache::new(),
-        http_client: build_http_client(),
-        metrics: metrics.clone(),
-    };
+    let global = config.global.clone();
+    if cli.dev {
+        tracing::warn!(
+            "Development mode (--dev) is on: responses are re-encoded and errors expose \
+             schema internals and request values. Do not use it in production."
+        );
+    }
+    let schema_cache = build_schema_cache(&config);
+    preload_schemas(&config, &schema_cache).await;
+    let http_client = build_http_client(&config.global.http_client);
+    let app_state = AppState::new(config, http_client, metrics.clone())
+        .with_dev_mode(cli.dev)
+        .with_schema_cache(schema_cache);
 
     let shared_state = Arc::new(RwLock::new(app_state));
 
+    if cli.watch {
+        tracing::info!(
+            "Watching {} for changes every {}ms",
+            cli.config.display(),
+            reload::WATCH_INTERVAL.as_millis()
+        );
+        reload::spawn_config_watcher(
+            shared_state.clone(),
+            cli.config.clone(),
+            reload::WATCH_INTERVAL,
+        );
+    }
+
+    if let Some(admin_port) = cli.admin_port {
+        let admin_addr = format!("127.0.0.1:{}", admin_port);
+        let admin_listener = tokio::net::TcpListener::bind(&admin_addr)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("Failed to bind admin port {}: {}", admin_addr, e);
+                std::process::exit(1);
+            });
+        let admin_app = build_admin_router(shared_state.clone(), cli.config.clone());
+        tracing::info!("Admin endpoints listening on http://{}", admin_addr);
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(admin_listener, admin_app).await {
+                tracing::error!(error = %e, "Admin server error");
+            }
+        });
+    }
+
+    let report_state = cli.coverage_report.as_ref().map(|_| shared_state.clone());
+
     // Create axum router with metrics, health, and main handler routes
-    let app = Router::new()
-        .route("/metrics", get(metrics_handler))
-        .route("/health", get(health::health))
-        .route("/health/ready", get(health::readiness))
-        .route("/health/live", get(health::liveness))
-        .route("/*path", any(handler))
-        .with_state(shared_state);
+    let app = build_router(shared_state, &global);
+
+    // Load certificates up front so a bad pair fails before we bind
+    let tls_config = tls_files.map(|(cert, key)| {
+        tls::load_server_config(cert, key, cli.tls_ca.as_deref()).unwrap_or_else(|e| {
+            eprintln!("Failed to load TLS config: {}", e);
+            std::process::exit(1);
+        })
+    });
 
     let addr = format!("127.0.0.1:{}", cli.port);
     let listener = tokio::net::TcpListener::bind(&addr)
@@ -87,52 +197,186 @@ async fn main() {
             std::process::exit(1);
         });
 
-    tracing::info!("Schema Gateway listening on http://{}", addr);
-    println!("Schema Gateway listening on http://{}", addr);
+    let scheme = if tls_config.is_some() {
+        "https"
+    } else {
+        "http"
+    };
+    // Written once the port is ours; dropped (and removed) on clean exit
+    let pid_file = cli.pid_file.as_deref().map(|path| {
+        PidFile::create(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        })
+    });
 
-    axum::serve(listener, app).await.unwrap_or_else(|e| {
+    match &pid_file {
+        Some(pid_file) => tracing::info!(
+            "Schema Gateway listening on {}://{} (PID {} written to {})",
+            scheme,
+            addr,
+            std::process::id(),
+            pid_file.path().display()
+        ),
+        None => tracing::info!("Schema Gateway listening on {}://{}", scheme, addr),
+    }
+    println!("Schema Gateway listening on {}://{}", scheme, addr);
+
+    let result = match tls_config {
+        Some(tls_config) => {
+            let listener = listener.into_std().unwrap_or_else(|e| {
+                eprintln!("Failed to prepare listener on {}: {}", addr, e);
+                std::process::exit(1);
+            });
+            let handle = axum_server::Handle::new();
+            let shutdown_handle = handle.clone();
+            tokio::spawn(async move {
+                shutdown_signal().await;
+                shutdown_handle.graceful_shutdown(Some(SHUTDOWN_GRACE_PERIOD));
+            });
+            axum_server::from_tcp_rustls(listener, RustlsConfig::from_config(Arc::new(tls_config)))
+                .handle(handle)
+                .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+                .await
+        }
+        None => {
+            let signalled = Arc::new(Notify::new());
+            let notify = signalled.clone();
+            let server = axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .with_graceful_shutdown(async move {
+                shutdown_signal().await;
+                notify.notify_one();
+            });
+            tokio::select! {
+                result = server => result,
+                _ = async {
+                    signalled.notified().await;
+                    tokio::time::sleep(SHUTDOWN_GRACE_PERIOD).await;
+                } => {
+                    tracing::warn!(
+                        "Requests still in flight after {:?}; stopping anyway",
+                        SHUTDOWN_GRACE_PERIOD
+                    );
+                    Ok(())
+                }
+            }
+        }
+    };
+
+    result.unwrap_or_else(|e| {
         eprintln!("Server error: {}", e);
         std::process::exit(1);
     });
+    if let (Some(path), Some(state)) = (&cli.coverage_report, &report_state) {
+        write_coverage_report(path, state).await;
+    }
+    tracing::info!("Schema Gateway stopped");
+    drop(pid_file);
 }
 
-async fn handler(
-    State(state): State<Arc<RwLock<AppState>>>,
-    method: Method,
-    uri: Uri,
-    headers: HeaderMap,
-    body: Body,
-) -> axum::response::Response {
-    handle_request(State(state), method, uri, headers, body).await
+/// Write the coverage report for `--coverage-report`. Failing to write it
+/// is logged; the gateway is already stopping.
+async fn write_coverage_report(path: &Path, state: &Arc<RwLock<AppState>>) {
+    let report = coverage_report(state).await;
+    let written = serde_json::to_string_pretty(&report)
+        .map_err(|e| e.to_string())
+        .and_then(|json| std::fs::write(path, json + "\n").map_err(|e| e.to_string()));
+    match written {
+        Ok(()) => tracing::info!(path = %path.display(), "Wrote OpenAPI coverage report"),
+        Err(e) => tracing::error!(
+            path = %path.display(),
+            error = %e,
+            "Failed to write OpenAPI coverage report"
+        ),
+    }
 }
 
-async fn metrics_handler(State(state): State<Arc<RwLock<AppState>>>) -> Response {
-    let state_guard = state.read().await;
-    match state_guard.metrics.gather() {
-        Ok(output) => {
-            match Response::builder()
-                .status(StatusCode::OK)
-                .header("Content-Type", "text/plain; version=0.0.4")
-                .body(axum::body::Body::from(output))
-            {
-                Ok(response) => response,
-                Err(e) => {
-                    tracing::error!("Failed to build metrics response: {}", e);
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        "Failed to build response",
-                    )
-                        .into_response()
-                }
+/// How long in-flight requests get to finish after a shutdown signal
+const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+/// Resolves on Ctrl+C, or SIGTERM on Unix
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!(error = %e, "Failed to listen for Ctrl+C");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
             }
         }
-        Err(e) => {
-            tracing::error!("Failed to gather metrics: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Error gathering metrics: {}", e),
-            )
-                .into_response()
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    tracing::info!("Shutting down");
+}
+
+/// Compile every route's JSON schemas before accepting connections. A
+/// schema that fails is logged; if a route rejecting invalid requests
+/// (`forward_on_error: false`) depends on it, the gateway exits instead.
+async fn preload_schemas(config: &Config, cache: &SchemaCache) {
+    // Grouped by how each schema is compiled; headers schemas as written
+    let mut groups: BTreeMap<SchemaOptions, Vec<PathBuf>> = BTreeMap::new();
+    for route in config.all_routes() {
+        if let Some(schema) = &route.schema {
+            groups
+                .entry(route.schema_options())
+                .or_default()
+                .push(schema.clone());
+        }
+        groups
+            .entry(SchemaOptions::default())
+            .or_default()
+            .extend(route.headers_schema.iter().cloned());
+    }
+
+    let mut report = PreloadReport::default();
+    for (options, paths) in &groups {
+        let group = cache.preload_with(paths, *options).await;
+        report.loaded += group.loaded;
+        report.failed.extend(group.failed);
+    }
+
+    let mut fatal = false;
+    for (path, error) in &report.failed {
+        let rejecting = config
+            .all_routes()
+            .filter(|route| {
+                route.schema.as_ref() == Some(path) || route.headers_schema.as_ref() == Some(path)
+            })
+            .any(|route| !config.get_effective_config(route).forward_on_error);
+        if rejecting {
+            tracing::error!(schema_path = %path.display(), error = %error, "Failed to preload schema");
+            fatal = true;
+        } else {
+            tracing::warn!(schema_path = %path.display(), error = %error, "Failed to preload schema");
         }
     }
+    tracing::info!(
+        loaded = report.loaded,
+        failed = report.failed.len(),
+        "Preloaded schemas"
+    );
+
+    if fatal {
+        eprintln!("A schema used by a route with forward_on_error: false failed to load");
+        std::process::exit(1);
+    }
 }
@@ -1,7 +1,13 @@
 use prometheus::{
-    Counter, CounterVec, Encoder, HistogramOpts, HistogramVec, Opts, Registry, TextEncoder,
+    Counter, CounterVec, Encoder, HistogramOpts, HistogramVec, IntGaugeVec, Opts, Registry,
+    TextEncoder,
 };
 
+use std::path::Path;
+
+use crate::coverage::OperationCoverage;
+use crate::validation::{error_labels, ValidationError};
+
 /// Metrics collection for the schema gateway
 pub struct Metrics {
     pub http_requests_total: CounterVec,
@@ -9,12 +15,26 @@ pub struct Metrics {
     pub validation_attempts_total: CounterVec,
     pub validation_success_total: CounterVec,
     pub validation_failures_total: CounterVec,
+    pub validation_field_errors_total: CounterVec,
+    pub openapi_operation_validations_total: CounterVec,
+    /// Per-operation counts behind `GET /admin/openapi/coverage`
+    pub openapi_coverage: OperationCoverage,
+    pub deprecated_requests_total: CounterVec,
     pub upstream_requests_total: CounterVec,
     pub upstream_request_duration_seconds: HistogramVec,
     pub upstream_errors_total: CounterVec,
     pub schema_cache_hits_total: Counter,
     pub schema_cache_misses_total: Counter,
+    pub schema_cache_entries: IntGaugeVec,
+    pub openapi_cache_hits_total: Counter,
+    pub openapi_cache_misses_total: Counter,
+    pub openapi_spec_loads_total: Counter,
     pub routes_not_found_total: CounterVec,
+    pub method_not_allowed_total: CounterVec,
+    pub rate_limit_rejections_total: CounterVec,
+    pub upstream_retries_total: CounterVec,
+    pub config_reloads_total: Counter,
+    pub config_reload_failures_total: Counter,
     registry: Registry,
 }
 
@@ -26,7 +46,7 @@ impl Metrics {
         // HTTP request metrics
         let http_requests_total = CounterVec::new(
             Opts::new("http_requests_total", "Total number of HTTP requests"),
-            &["method", "route", "status"],
+            &["method", "route", "route_tags", "status"],
         )?;
         registry.register(Box::new(http_requests_total.clone()))?;
 
@@ -38,7 +58,7 @@ impl Metrics {
             .buckets(vec![
                 0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
             ]),
-            &["method", "route"],
+            &["method", "route", "route_tags"],
         )?;
         registry.register(Box::new(http_request_duration_seconds.clone()))?;
 
@@ -48,7 +68,7 @@ impl Metrics {
                 "validation_attempts_total",
                 "Total number of validation attempts",
             ),
-            &["validation_type"],
+            &["validation_type", "openapi_spec_version"],
         )?;
         registry.register(Box::new(validation_attempts_total.clone()))?;
 
@@ -57,7 +77,7 @@ impl Metrics {
                 "validation_success_total",
                 "Total number of successful validations",
             ),
-            &["validation_type"],
+            &["validation_type", "openapi_spec_version"],
         )?;
         registry.register(Box::new(validation_success_total.clone()))?;
 
@@ -66,10 +86,37 @@ impl Metrics {
                 "validation_failures_total",
                 "Total number of validation failures",
             ),
-            &["validation_type", "error_type"],
+            &["validation_type", "error_type", "openapi_spec_version"],
         )?;
         registry.register(Box::new(validation_failures_total.clone()))?;
 
+        let validation_field_errors_total = CounterVec::new(
+            Opts::new(
+                "validation_field_errors_total",
+                "Total number of body validation errors by field and error kind",
+            ),
+            &["field_path", "error_kind"],
+        )?;
+        registry.register(Box::new(validation_field_errors_total.clone()))?;
+
+        let openapi_operation_validations_total = CounterVec::new(
+            Opts::new(
+                "openapi_operation_validations_total",
+                "Total number of OpenAPI request validations by operation and result",
+            ),
+            &["spec", "operation", "result"],
+        )?;
+        registry.register(Box::new(openapi_operation_validations_total.clone()))?;
+
+        let deprecated_requests_total = CounterVec::new(
+            Opts::new(
+                "deprecated_requests_total",
+                "Total number of requests to deprecated OpenAPI operations or parameters",
+            ),
+            &["operation"],
+        )?;
+        registry.register(Box::new(deprecated_requests_total.clone()))?;
+
         // Upstream metrics
         let upstream_requests_total = CounterVec::new(
             Opts::new(
@@ -88,7 +135,7 @@ impl Metrics {
             .buckets(vec![
                 0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
             ]),
-            &[],
+            &["route", "route_tags"],
         )?;
         registry.register(Box::new(upstream_request_duration_seconds.clone()))?;
 
@@ -111,6 +158,33 @@ impl Metrics {
         ))?;
         registry.register(Box::new(schema_cache_misses_total.clone()))?;
 
+        let openapi_cache_hits_total = Counter::with_opts(Opts::new(
+            "openapi_cache_hits_total",
+            "Total number of OpenAPI operation plan cache hits",
+        ))?;
+        registry.register(Box::new(openapi_cache_hits_total.clone()))?;
+
+        let openapi_cache_misses_total = Counter::with_opts(Opts::new(
+            "openapi_cache_misses_total",
+            "Total number of OpenAPI operation plan cache misses",
+        ))?;
+        registry.register(Box::new(openapi_cache_misses_total.clone()))?;
+
+        let openapi_spec_loads_total = Counter::with_opts(Opts::new(
+            "openapi_spec_loads_total",
+            "Total number of OpenAPI spec files read from disk",
+        ))?;
+        registry.register(Box::new(openapi_spec_loads_total.clone()))?;
+
+        let schema_cache_entries = IntGaugeVec::new(
+            Opts::new(
+                "schema_cache_entries",
+                "Current number of entries in the schema caches",
+            ),
+            &["cache"],
+        )?;
+        registry.register(Box::new(schema_cache_entries.clone()))?;
+
         // Route metrics
         let routes_not_found_total = CounterVec::new(
             Opts::new("routes_not_found_total", "Total number of 404 responses"),
@@ -118,22 +192,101 @@ impl Metrics {
         )?;
         registry.register(Box::new(routes_not_found_total.clone()))?;
 
+        let method_not_allowed_total = CounterVec::new(
+            Opts::new("method_not_allowed_total", "Total number of 405 responses"),
+            &["method"],
+        )?;
+        registry.register(Box::new(method_not_allowed_total.clone()))?;
+
+        let rate_limit_rejections_total = CounterVec::new(
+            Opts::new(
+                "rate_limit_rejections_total",
+                "Total number of requests rejected by a route's rate limit",
+            ),
+            &["route", "route_tags"],
+        )?;
+        registry.register(Box::new(rate_limit_rejections_total.clone()))?;
+
+        let upstream_retries_total = CounterVec::new(
+            Opts::new(
+                "upstream_retries_total",
+                "Total number of upstream requests retried, by route and failure",
+            ),
+            &["route", "route_tags", "reason"],
+        )?;
+        registry.register(Box::new(upstream_retries_total.clone()))?;
+
+        // Config reload metrics
+        let config_reloads_total = Counter::with_opts(Opts::new(
+            "config_reloads_total",
+            "Total number of successful config reloads",
+        ))?;
+        registry.register(Box::new(config_reloads_total.clone()))?;
+
+        let config_reload_failures_total = Counter::with_opts(Opts::new(
+            "config_reload_failures_total",
+            "Total number of failed config reloads",
+        ))?;
+        registry.register(Box::new(config_reload_failures_total.clone()))?;
+
         Ok(Metrics {
             http_requests_total,
             http_request_duration_seconds,
             validation_attempts_total,
             validation_success_total,
             validation_failures_total,
+            validation_field_errors_total,
+            openapi_operation_validations_total,
+            openapi_coverage: OperationCoverage::new(),
+            deprecated_requests_total,
             upstream_requests_total,
             upstream_request_duration_seconds,
             upstream_errors_total,
             schema_cache_hits_total,
             schema_cache_misses_total,
+            schema_cache_entries,
+            openapi_cache_hits_total,
+            openapi_cache_misses_total,
+            openapi_spec_loads_total,
             routes_not_found_total,
+            method_not_allowed_total,
+            rate_limit_rejections_total,
+            upstream_retries_total,
+            config_reloads_total,
+            config_reload_failures_total,
             registry,
         })
     }
 
+    /// Count each error from `validate` under its field path (truncated
+    /// to `max_depth` segments) and error kind
+    pub fn record_field_errors(&self, errors: &[ValidationError], max_depth: usize) {
+        for error in errors {
+            let (field_path, error_kind) = error_labels(error, max_depth);
+            self.validation_field_errors_total
+                .with_label_values(&[&field_path, error_kind])
+                .inc();
+        }
+    }
+
+    /// Count an OpenAPI request validation against `operation`, also in
+    /// `openapi_operation_validations_total` when `export` is set
+    pub fn record_operation_validation(
+        &self,
+        spec: &Path,
+        operation: &str,
+        passed: bool,
+        export: bool,
+    ) {
+        self.openapi_coverage.record(spec, operation, passed);
+        if export {
+            let result = if passed { "passed" } else { "failed" };
+            self.openapi_operation_validations_total
+                .with_label_values(&[&spec.display().to_string(), operation, result])
+                .inc();
+        }
+    }
+
     /// Gather all metrics and encode them in Prometheus format
     pub fn gather(&self) -> Result<String, prometheus::Error> {
         let encoder = TextEncoder::new();
@@ -1,21 +1,475 @@
-use axum::http::Method;
-use jsonschema::JSONSchema;
-use serde_json::{Map, Value};
-use std::collections::HashMap;
+use axum::http::{HeaderMap, Method};
+use jsonschema::{Draft, JSONSchema};
+use lru::LruCache;
+use once_cell::sync::OnceCell;
+use prometheus::Counter;
+use serde_json::{json, Map, Value};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use url::form_urlencoded;
 
-use crate::error::{Error, Result};
+use crate::config::{resolve_relative, MockBody, MockResponse, RouteConfig};
+use crate::error::{Error, OpenApiErrorStage, Result};
+use crate::metrics::Metrics;
+use crate::schema::{forbid_additional_properties, unchanged_since, NegativeCache};
+use crate::validation::{validate, ValidationError, ValidationErrorKind, ValidationResult};
 
 #[derive(Clone)]
 pub struct OperationValidationPlan {
     pub schema: Option<Arc<JSONSchema>>,
     pub body_required: bool,
+    /// Media types the `requestBody` declares under `content`, lowercased.
+    /// Empty when the operation has no `requestBody` or `content`.
+    pub content_types: Vec<String>,
+    /// Set when the body is `multipart/form-data`; `schema` then validates
+    /// the parts assembled into an object
+    pub multipart: Option<MultipartForm>,
     pub method: String,
     pub path_template: String,
+    pub operation_id: Option<String>,
+    /// Version of the spec the operation comes from
+    pub spec_version: SpecVersion,
+    /// The operation is marked `deprecated`
+    pub deprecated: bool,
+    /// The operation's `x-sunset` date, sent as the `Sunset` header
+    pub sunset: Option<String>,
+    /// `(route name, spec name)` for each path parameter of the route
+    /// template the plan was loaded for, in path order. Empty for plans
+    /// loaded without one.
+    pub path_param_names: Vec<(String, String)>,
     pub parameters: Vec<ParameterValidator>,
-    pub response_schemas: HashMap<ResponseKey, Arc<JSONSchema>>,
+    /// What each response declares under `content`. Every declared
+    /// response has an entry, even without `content`.
+    pub response_schemas: HashMap<ResponseKey, ResponseContent>,
+    /// Headers each response declares, checked like header parameters
+    pub response_headers: HashMap<ResponseKey, Vec<ParameterValidator>>,
+    /// What a mocked route answers the operation with
+    pub example_response: ExampleResponse,
+    /// Alternative ways to authenticate; a request needs to satisfy one.
+    /// Empty when the operation has no security requirements.
+    pub security: Vec<SecurityRequirement>,
+    /// Route settings from the operation's `x-schema-gateway` extension,
+    /// falling back to the path item's. The route's own `config` wins.
+    pub overrides: RouteConfig,
+    /// Compare request paths with `path_template` ignoring ASCII case
+    pub case_insensitive_paths: bool,
+}
+
+/// A response made up from an operation's lowest declared `2xx` response
+/// (or its `2XX` or `default` one, as `200`): the media type's example, or
+/// an instance generated from its schema
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExampleResponse {
+    pub status: u16,
+    /// The declared media type, preferring JSON ones. `None` when the
+    /// response has no `content`.
+    pub content_type: Option<String>,
+    /// `None` for non-JSON media types without a string example
+    pub body: Option<Value>,
+}
+
+impl ExampleResponse {
+    /// The response as a route's canned response. Non-JSON bodies are sent
+    /// as the raw string.
+    pub fn to_mock_response(&self) -> MockResponse {
+        let json = self
+            .content_type
+            .as_deref()
+            .is_some_and(|content_type| is_json_media_type(&media_type_essence(content_type)));
+        let body = self.body.clone().map(|body| match body {
+            Value::String(text) if !json => MockBody::Text(text),
+            body => MockBody::Json(body),
+        });
+        let headers = self
+            .content_type
+            .iter()
+            .map(|content_type| ("content-type".to_string(), content_type.clone()))
+            .collect();
+        MockResponse {
+            status: self.status,
+            body,
+            headers,
+        }
+    }
+}
+
+/// One entry of an operation's `security` list: every scheme in it must be
+/// present on the request. An empty requirement allows anonymous access.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SecurityRequirement {
+    pub schemes: Vec<SecurityScheme>,
+}
+
+/// The part of a security scheme the gateway can check: that credentials
+/// are present and shaped right, not that they're valid
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SecurityScheme {
+    /// `apiKey`: a named header, query parameter or cookie
+    ApiKey {
+        name: String,
+        location: ParameterLocation,
+    },
+    /// `http`: an `Authorization` header using `scheme`, e.g. `bearer`
+    Http { scheme: String },
+}
+
+impl OperationValidationPlan {
+    /// Whether a request `Content-Type` falls under one of the declared
+    /// media types, matched like response media types: exact, then
+    /// `type/*+json`, `type/*` and `*/*`, with JSON types also accepted by
+    /// `application/json`.
+    pub fn accepts_content_type(&self, content_type: &str) -> bool {
+        media_type_candidates(content_type)
+            .iter()
+            .any(|candidate| self.content_types.contains(candidate))
+    }
+
+    /// The operation's `operationId`, or `METHOD /path` without one
+    pub fn label(&self) -> String {
+        operation_label(
+            self.operation_id.as_deref(),
+            &self.method,
+            &self.path_template,
+        )
+    }
+
+    /// Validate a request against the operation without going through the
+    /// proxy: parameters first, then `body` against the request body schema.
+    ///
+    /// `path` is the request path relative to the spec's base path, and a
+    /// `Value::Null` body counts as no body. For `multipart/form-data`
+    /// operations, pass the parts assembled by [`MultipartForm::assemble`].
+    pub fn validate_request(
+        &self,
+        body: &Value,
+        query: Option<&str>,
+        path: &str,
+        headers: &HeaderMap,
+    ) -> ValidationResult {
+        if let Err(message) = self.check_parameters(path, &HashMap::new(), query, headers) {
+            return ValidationResult {
+                valid: false,
+                details: vec![message.clone()],
+                errors: vec![ValidationError::new(
+                    ValidationErrorKind::Other("parameter".to_string()),
+                    message,
+                )],
+                error_count: 1,
+            };
+        }
+
+        match &self.schema {
+            Some(_) if body.is_null() && self.body_required => {
+                let message = format!(
+                    "OpenAPI request body required for {} {}",
+                    self.method, self.path_template
+                );
+                ValidationResult {
+                    valid: false,
+                    details: vec![message.clone()],
+                    errors: vec![ValidationError::new(ValidationErrorKind::Required, message)],
+                    error_count: 1,
+                }
+            }
+            Some(schema) if !body.is_null() => validate(schema, body),
+            _ => ValidationResult {
+                valid: true,
+                errors: vec![],
+                details: vec![],
+                error_count: 0,
+            },
+        }
+    }
+
+    /// Check the request against the operation's declared parameters and return
+    /// the first problem found. `captured_params` (named `path_regex` captures)
+    /// take precedence over values extracted from the OpenAPI path template.
+    pub fn check_parameters(
+        &self,
+        path: &str,
+        captured_params: &HashMap<String, String>,
+        query: Option<&str>,
+        headers: &HeaderMap,
+    ) -> std::result::Result<(), String> {
+        let Some(mut path_params) =
+            extract_path_params(path, &self.path_template, self.case_insensitive_paths)
+        else {
+            return Err(format!(
+                "Route '{}' no longer matches OpenAPI template '{}'",
+                path, self.path_template
+            ));
+        };
+        path_params.extend(captured_params.clone());
+
+        let query_params = parse_query_params(query);
+        let header_params = build_header_lookup(headers);
+        let cookie_params = parse_cookie_header(headers);
+
+        for param in &self.parameters {
+            let value = match param.location {
+                ParameterLocation::Path => path_params
+                    .get(&param.name)
+                    .map(|raw| param.parse_value(raw)),
+                ParameterLocation::Query => param.query_value(&query_params),
+                ParameterLocation::Header => header_params
+                    .get(&param.name.to_ascii_lowercase())
+                    .map(|raw| param.parse_value(raw)),
+                ParameterLocation::Cookie => cookie_params
+                    .get(&param.name)
+                    .map(|raw| param.parse_value(raw)),
+            };
+
+            let Some(value) = value else {
+                if param.required {
+                    return Err(format!(
+                        "Missing required {} parameter '{}'",
+                        parameter_location_label(param.location),
+                        param.name
+                    ));
+                }
+                continue;
+            };
+
+            let Some(schema) = &param.schema else {
+                continue;
+            };
+
+            let coerced_value = value?;
+
+            let validation_error = schema.validate(&coerced_value).err();
+            if let Some(mut errors) = validation_error {
+                // Point at the failing array item or object property, if any
+                let (name, first_error) = errors
+                    .next()
+                    .map(|e| (param.name_at(&e.instance_path.to_string()), e.to_string()))
+                    .unwrap_or_else(|| {
+                        (
+                            param.name.clone(),
+                            "Parameter validation failed".to_string(),
+                        )
+                    });
+                return Err(format!("Parameter '{}' invalid: {}", name, first_error));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Names of the deprecated parameters the request supplies. Path
+    /// parameters are always supplied.
+    pub fn deprecated_parameters(&self, query: Option<&str>, headers: &HeaderMap) -> Vec<&str> {
+        let query_params = parse_query_params(query);
+        let header_params = build_header_lookup(headers);
+        let cookie_params = parse_cookie_header(headers);
+
+        self.parameters
+            .iter()
+            .filter(|param| param.deprecated)
+            .filter(|param| match param.location {
+                ParameterLocation::Path => true,
+                ParameterLocation::Query => param.query_value(&query_params).is_some(),
+                ParameterLocation::Header => {
+                    header_params.contains_key(&param.name.to_ascii_lowercase())
+                }
+                ParameterLocation::Cookie => cookie_params.contains_key(&param.name),
+            })
+            .map(|param| param.name.as_str())
+            .collect()
+    }
+}
+
+/// How the parts of a `multipart/form-data` body map onto the properties of
+/// its schema
+#[derive(Clone, Debug)]
+pub struct MultipartForm {
+    fields: HashMap<String, FormField>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct FormField {
+    kind: PartKind,
+    /// An array property, sent as one part per item
+    repeated: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PartKind {
+    /// Binary content: only its presence is checked
+    File,
+    Text(Option<PrimitiveType>),
+    Json,
+}
+
+/// One part of a multipart body
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FormPart {
+    pub name: String,
+    pub file_name: Option<String>,
+    /// Content of a text part; `None` for files, which aren't read
+    pub text: Option<String>,
+}
+
+impl MultipartForm {
+    fn from_schema(schema: &Value, encoding: &Map<String, Value>) -> Self {
+        let mut fields = HashMap::new();
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for (name, property) in properties.into_iter().flatten() {
+            let json_encoded = encoding
+                .get(name)
+                .and_then(|e| e.get("contentType"))
+                .and_then(Value::as_str)
+                .is_some_and(|content_type| content_type.to_ascii_lowercase().contains("json"));
+            let field = if json_encoded {
+                FormField {
+                    kind: PartKind::Json,
+                    repeated: false,
+                }
+            } else if schema_type(property) == Some("array") && !is_file_schema(property) {
+                FormField {
+                    kind: property
+                        .get("items")
+                        .map_or(PartKind::Text(None), part_kind),
+                    repeated: true,
+                }
+            } else {
+                FormField {
+                    kind: part_kind(property),
+                    repeated: false,
+                }
+            };
+            fields.insert(name.clone(), field);
+        }
+        Self { fields }
+    }
+
+    /// Whether the part is a file, whose content isn't read: a declared
+    /// binary property, or an undeclared part with a filename
+    pub fn is_file(&self, name: &str, file_name: Option<&str>) -> bool {
+        match self.fields.get(name) {
+            Some(field) => field.kind == PartKind::File,
+            None => file_name.is_some(),
+        }
+    }
+
+    /// Assemble the parts into the object the body schema describes. Text
+    /// parts are coerced to their property's type, JSON parts parsed, and
+    /// files stand in as their filename.
+    pub fn assemble(&self, parts: Vec<FormPart>) -> std::result::Result<Value, String> {
+        let mut grouped: Vec<(String, Vec<Value>)> = Vec::new();
+        for part in parts {
+            let field = self.fields.get(&part.name);
+            let value = match (part.text, field.map(|f| f.kind)) {
+                (None, _) => Value::String(part.file_name.unwrap_or_default()),
+                (Some(text), Some(PartKind::Json)) => serde_json::from_str(&text).map_err(|e| {
+                    format!("Multipart part '{}' is not valid JSON: {}", part.name, e)
+                })?,
+                (Some(text), Some(PartKind::Text(kind))) => {
+                    coerce_primitive(&part.name, kind, &text).map_err(|_| {
+                        format!(
+                            "Multipart part '{}' is not a valid {}",
+                            part.name,
+                            kind.map_or("string", PrimitiveType::as_str)
+                        )
+                    })?
+                }
+                (Some(text), _) => Value::String(text),
+            };
+            match grouped.iter_mut().find(|(name, _)| *name == part.name) {
+                Some((_, values)) => values.push(value),
+                None => grouped.push((part.name, vec![value])),
+            }
+        }
+
+        let mut object = Map::new();
+        for (name, mut values) in grouped {
+            let repeated = self.fields.get(&name).is_some_and(|field| field.repeated);
+            // A part sent more than once for a scalar property becomes an
+            // array, which its schema then rejects
+            let value = if repeated || values.len() > 1 {
+                Value::Array(values)
+            } else {
+                values.remove(0)
+            };
+            object.insert(name, value);
+        }
+        Ok(Value::Object(object))
+    }
+}
+
+fn part_kind(schema: &Value) -> PartKind {
+    if is_file_schema(schema) {
+        return PartKind::File;
+    }
+    match schema_type(schema) {
+        Some("object") | Some("array") => PartKind::Json,
+        _ => PartKind::Text(detect_primitive_type(schema)),
+    }
+}
+
+/// `format: binary` in 3.0, or a `contentMediaType` without an encoding in 3.1
+fn is_file_schema(schema: &Value) -> bool {
+    schema.get("format").and_then(Value::as_str) == Some("binary")
+        || (schema.get("contentMediaType").is_some() && schema.get("contentEncoding").is_none())
+}
+
+/// Relax the schemas of file properties (and file array items) so the
+/// filename standing in for their content passes
+fn accept_any_file(schema: &mut Value) {
+    let Some(properties) = schema.get_mut("properties").and_then(Value::as_object_mut) else {
+        return;
+    };
+    for property in properties.values_mut() {
+        if is_file_schema(property) {
+            *property = json!({});
+        } else if let Some(items) = property.get_mut("items") {
+            if is_file_schema(items) {
+                *items = json!({});
+            }
+        }
+    }
+}
+
+/// A problem found by `OpenApiCache::check_spec`, located by a JSON pointer
+/// into the spec (`#/paths/~1users/get/requestBody`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecProblem {
+    pub pointer: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for SpecProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.pointer, self.message)
+    }
+}
+
+/// An operation as declared in a spec's `paths`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecOperation {
+    /// Path template, e.g. `/users/{id}`
+    pub path: String,
+    /// Uppercase HTTP method
+    pub method: String,
+    pub operation_id: Option<String>,
+}
+
+impl SpecOperation {
+    /// The `operationId`, or `METHOD /path` without one
+    pub fn label(&self) -> String {
+        operation_label(self.operation_id.as_deref(), &self.method, &self.path)
+    }
+}
+
+fn operation_label(operation_id: Option<&str>, method: &str, path: &str) -> String {
+    match operation_id {
+        Some(operation_id) => operation_id.to_string(),
+        None => format!("{} {}", method, path),
+    }
 }
 
 #[derive(Clone)]
@@ -24,34 +478,297 @@ pub struct ParameterValidator {
     pub location: ParameterLocation,
     pub required: bool,
     pub schema: Option<Arc<JSONSchema>>,
+    pub style: ParameterStyle,
+    pub explode: bool,
+    /// The schema's `default`, if it declares one
+    pub default: Option<Value>,
+    /// The parameter is marked `deprecated`
+    pub deprecated: bool,
+    /// Scalar type, or the item type of an array parameter
     value_type: Option<PrimitiveType>,
+    shape: ValueShape,
 }
 
 impl ParameterValidator {
     pub fn coerce_value(&self, raw: &str) -> std::result::Result<Value, String> {
-        match self.value_type {
-            Some(PrimitiveType::Integer) => raw
-                .parse::<i64>()
-                .map(|v| Value::Number(v.into()))
-                .map_err(|_| format!("Failed to parse integer for parameter '{}'", self.name)),
-            Some(PrimitiveType::Number) => match raw.parse::<f64>() {
-                Ok(v) => match serde_json::Number::from_f64(v) {
-                    Some(num) => Ok(Value::Number(num)),
-                    None => Err(format!(
-                        "Invalid number value for parameter '{}' (NaN, Infinity, or out of range)",
-                        self.name
-                    )),
-                },
-                Err(_) => Err(format!(
-                    "Failed to parse number for parameter '{}'",
-                    self.name
+        coerce_primitive(&self.name, self.value_type, raw)
+    }
+
+    /// Assemble the value of a path, header or cookie parameter from its raw
+    /// string. Arrays are comma-separated; objects are `k,v` pairs, or `k=v`
+    /// pairs when exploded.
+    pub fn parse_value(&self, raw: &str) -> std::result::Result<Value, String> {
+        match &self.shape {
+            ValueShape::Scalar => self.coerce_value(raw),
+            ValueShape::Json => self.json_value(raw),
+            ValueShape::Array => self.array_value(split_values(raw, ',')),
+            ValueShape::Object(properties) => {
+                let parts = split_values(raw, ',');
+                let pairs = if self.explode {
+                    parts
+                        .into_iter()
+                        .map(|part| match part.split_once('=') {
+                            Some((key, value)) => (key, value),
+                            None => (part, ""),
+                        })
+                        .collect()
+                } else {
+                    pair_up(&parts)
+                };
+                self.object_value(properties, pairs)
+            }
+        }
+    }
+
+    /// `default` as raw query values: one value, or one per item of an
+    /// exploded array. Empty when there is no default, or it isn't a scalar
+    /// or an array of scalars.
+    pub fn default_query_values(&self) -> Vec<String> {
+        let Some(items) = self.default.as_ref().and_then(default_items) else {
+            return Vec::new();
+        };
+        match self.default {
+            Some(Value::Array(_)) if !self.explode => {
+                let delimiter = match self.style {
+                    ParameterStyle::SpaceDelimited => " ",
+                    ParameterStyle::PipeDelimited => "|",
+                    _ => ",",
+                };
+                vec![items.join(delimiter)]
+            }
+            _ => items,
+        }
+    }
+
+    /// `default` as a raw header value, with array items comma-separated
+    pub fn default_header_value(&self) -> Option<String> {
+        self.default
+            .as_ref()
+            .and_then(default_items)
+            .map(|items| items.join(","))
+    }
+
+    /// Assemble the value of a query parameter according to its style.
+    /// `None` when the parameter is absent.
+    pub fn query_value(
+        &self,
+        query: &HashMap<String, Vec<String>>,
+    ) -> Option<std::result::Result<Value, String>> {
+        match &self.shape {
+            ValueShape::Scalar => query
+                .get(&self.name)
+                .and_then(|values| values.first())
+                .map(|raw| self.coerce_value(raw)),
+            ValueShape::Json => query
+                .get(&self.name)
+                .and_then(|values| values.first())
+                .map(|raw| self.json_value(raw)),
+            ValueShape::Array => {
+                let values = query.get(&self.name)?;
+                let items = if self.explode {
+                    values.iter().map(String::as_str).collect()
+                } else {
+                    let delimiter = match self.style {
+                        ParameterStyle::SpaceDelimited => ' ',
+                        ParameterStyle::PipeDelimited => '|',
+                        _ => ',',
+                    };
+                    split_values(values.first()?, delimiter)
+                };
+                Some(self.array_value(items))
+            }
+            ValueShape::Object(properties) if self.style == ParameterStyle::DeepObject => {
+                let pairs: Vec<(&str, &str)> = query
+                    .iter()
+                    .filter_map(|(key, values)| {
+                        let property = key
+                            .strip_prefix(self.name.as_str())?
+                            .strip_prefix('[')?
+                            .strip_suffix(']')?;
+                        Some((property, values.first()?.as_str()))
+                    })
+                    .collect();
+                if pairs.is_empty() {
+                    return None;
+                }
+                Some(self.object_value(properties, pairs))
+            }
+            ValueShape::Object(properties) if self.explode => {
+                // Each property is a query key of its own
+                let pairs: Vec<(&str, &str)> = properties
+                    .keys()
+                    .filter_map(|property| {
+                        let value = query.get(property)?.first()?;
+                        Some((property.as_str(), value.as_str()))
+                    })
+                    .collect();
+                if pairs.is_empty() {
+                    return None;
+                }
+                Some(self.object_value(properties, pairs))
+            }
+            ValueShape::Object(properties) => {
+                let raw = query.get(&self.name)?.first()?;
+                Some(self.object_value(properties, pair_up(&split_values(raw, ','))))
+            }
+        }
+    }
+
+    fn json_value(&self, raw: &str) -> std::result::Result<Value, String> {
+        serde_json::from_str(raw)
+            .map_err(|e| format!("Parameter '{}' is not valid JSON: {}", self.name, e))
+    }
+
+    /// Coerce each item to the declared `items` type. A single occurrence
+    /// still becomes a one-element array.
+    fn array_value(&self, items: Vec<&str>) -> std::result::Result<Value, String> {
+        items
+            .into_iter()
+            .enumerate()
+            .map(|(idx, item)| {
+                coerce_primitive(&format!("{}[{}]", self.name, idx), self.value_type, item)
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map(Value::Array)
+    }
+
+    /// Name of the array item or object property at `instance_path` (a
+    /// JSON pointer into the assembled value), e.g. `ids[2]` for `/2`
+    pub fn name_at(&self, instance_path: &str) -> String {
+        let mut name = self.name.clone();
+        for segment in instance_path.split('/').filter(|seg| !seg.is_empty()) {
+            name.push_str(&format!("[{}]", segment));
+        }
+        name
+    }
+
+    fn object_value(
+        &self,
+        properties: &HashMap<String, Option<PrimitiveType>>,
+        pairs: Vec<(&str, &str)>,
+    ) -> std::result::Result<Value, String> {
+        let mut object = Map::new();
+        for (key, raw) in pairs {
+            let kind = properties.get(key).copied().flatten();
+            let value = coerce_primitive(&format!("{}[{}]", self.name, key), kind, raw)?;
+            object.insert(key.to_string(), value);
+        }
+        Ok(Value::Object(object))
+    }
+}
+
+fn coerce_primitive(
+    name: &str,
+    kind: Option<PrimitiveType>,
+    raw: &str,
+) -> std::result::Result<Value, String> {
+    match kind {
+        Some(PrimitiveType::Integer) => raw
+            .parse::<i64>()
+            .map(|v| Value::Number(v.into()))
+            .map_err(|_| format!("Failed to parse integer for parameter '{}'", name)),
+        Some(PrimitiveType::Number) => match raw.parse::<f64>() {
+            Ok(v) => match serde_json::Number::from_f64(v) {
+                Some(num) => Ok(Value::Number(num)),
+                None => Err(format!(
+                    "Invalid number value for parameter '{}' (NaN, Infinity, or out of range)",
+                    name
                 )),
             },
-            Some(PrimitiveType::Boolean) => raw
-                .parse::<bool>()
-                .map(Value::Bool)
-                .map_err(|_| format!("Failed to parse boolean for parameter '{}'", self.name)),
-            None => Ok(Value::String(raw.to_string())),
+            Err(_) => Err(format!("Failed to parse number for parameter '{}'", name)),
+        },
+        Some(PrimitiveType::Boolean) => raw
+            .parse::<bool>()
+            .map(Value::Bool)
+            .map_err(|_| format!("Failed to parse boolean for parameter '{}'", name)),
+        None => Ok(Value::String(raw.to_string())),
+    }
+}
+
+/// Split a non-exploded value; an empty value is an empty list
+fn split_values(raw: &str, delimiter: char) -> Vec<&str> {
+    if raw.is_empty() {
+        Vec::new()
+    } else {
+        raw.split(delimiter).collect()
+    }
+}
+
+/// `[k1, v1, k2, v2]` into `[(k1, v1), (k2, v2)]`; a trailing key gets ""
+fn pair_up<'a>(parts: &[&'a str]) -> Vec<(&'a str, &'a str)> {
+    parts
+        .chunks(2)
+        .map(|pair| (pair[0], pair.get(1).copied().unwrap_or("")))
+        .collect()
+}
+
+/// How a parameter value is serialized (`style` in the spec)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParameterStyle {
+    Form,
+    Simple,
+    SpaceDelimited,
+    PipeDelimited,
+    DeepObject,
+}
+
+impl ParameterStyle {
+    /// Style used when the spec doesn't set one
+    fn default_for(location: ParameterLocation) -> Self {
+        match location {
+            ParameterLocation::Query | ParameterLocation::Cookie => ParameterStyle::Form,
+            ParameterLocation::Path | ParameterLocation::Header => ParameterStyle::Simple,
+        }
+    }
+
+    /// The style named `name`, if it is supported for `location`
+    fn parse(name: &str, location: ParameterLocation) -> Option<Self> {
+        let style = match name {
+            "form" => ParameterStyle::Form,
+            "simple" => ParameterStyle::Simple,
+            "spaceDelimited" => ParameterStyle::SpaceDelimited,
+            "pipeDelimited" => ParameterStyle::PipeDelimited,
+            "deepObject" => ParameterStyle::DeepObject,
+            _ => return None,
+        };
+        let allowed = match location {
+            ParameterLocation::Query => style != ParameterStyle::Simple,
+            ParameterLocation::Cookie => style == ParameterStyle::Form,
+            ParameterLocation::Path | ParameterLocation::Header => style == ParameterStyle::Simple,
+        };
+        allowed.then_some(style)
+    }
+}
+
+/// The JSON type a parameter value is assembled into
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ValueShape {
+    Scalar,
+    /// Serialized as JSON (`content: application/json`)
+    Json,
+    Array,
+    /// Property names with their primitive types
+    Object(HashMap<String, Option<PrimitiveType>>),
+}
+
+impl ValueShape {
+    fn of(schema: &Value) -> Self {
+        match schema_type(schema) {
+            Some("array") => ValueShape::Array,
+            Some("object") => {
+                let properties = schema
+                    .get("properties")
+                    .and_then(Value::as_object)
+                    .map(|properties| {
+                        properties
+                            .iter()
+                            .map(|(name, schema)| (name.clone(), detect_primitive_type(schema)))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                ValueShape::Object(properties)
+            }
+            _ => ValueShape::Scalar,
         }
     }
 }
@@ -64,12 +781,105 @@ pub enum ParameterLocation {
     Cookie,
 }
 
+/// A response's media types with their inlined schemas (JSON types only), before compiling
+type ResponseMediaTypes = Vec<(String, Option<Value>)>;
+
+/// The media types one response declares
+#[derive(Clone, Default)]
+pub struct ResponseContent {
+    /// Media type (lowercased, without parameters) → compiled schema, for
+    /// JSON media types that have one
+    pub media_types: HashMap<String, Option<Arc<JSONSchema>>>,
+}
+
+impl ResponseContent {
+    /// The declared entry an upstream `Content-Type` falls under: the exact
+    /// type, then `type/*+json` for `+json` types, then `type/*` and `*/*`.
+    /// JSON types not declared themselves fall back to `application/json`.
+    ///
+    /// `None` when the type isn't declared; `Some(None)` when it is but has
+    /// no schema to validate against.
+    pub fn lookup(&self, content_type: &str) -> Option<Option<&Arc<JSONSchema>>> {
+        media_type_candidates(content_type)
+            .iter()
+            .find_map(|candidate| self.media_types.get(candidate))
+            .map(Option::as_ref)
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum ResponseKey {
     Status(u16),
+    /// A status class such as `2XX`, by its hundreds digit
+    Class(u8),
     Default,
 }
 
+impl ResponseKey {
+    /// The entry in `map` that applies to `status`: the exact status, then
+    /// its class, then `default`
+    pub fn lookup<T>(map: &HashMap<ResponseKey, T>, status: u16) -> Option<&T> {
+        map.get(&ResponseKey::Status(status))
+            .or_else(|| map.get(&ResponseKey::Class((status / 100) as u8)))
+            .or_else(|| map.get(&ResponseKey::Default))
+    }
+}
+
+/// A spec's version, from its `openapi` (or `swagger`) field, which also
+/// decides how its schemas are compiled
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpecVersion {
+    /// Swagger 2.0: schemas are compiled like OpenAPI 3.0 ones
+    Swagger2,
+    /// OpenAPI 3.0: schemas use the 3.0 dialect and are translated to JSON
+    /// Schema before compiling
+    V3_0,
+    /// OpenAPI 3.1: schemas are JSON Schema in the given draft
+    /// (2020-12 unless `jsonSchemaDialect` says otherwise)
+    V3_1(Draft),
+}
+
+impl SpecVersion {
+    /// Value of the `openapi_spec_version` metric label
+    pub fn label(&self) -> &'static str {
+        match self {
+            SpecVersion::Swagger2 => "oas2",
+            SpecVersion::V3_0 => "oas3.0",
+            SpecVersion::V3_1(_) => "oas3.1",
+        }
+    }
+}
+
+/// A parsed spec document. Files loaded through `$ref`s have no `openapi`
+/// field and are compiled with the version of the spec referencing them.
+struct ParsedSpec {
+    document: Value,
+    /// operationId → (path template, method key). Operations behind a ref
+    /// to another file aren't indexed. The first operation wins on
+    /// duplicates.
+    operation_ids: HashMap<String, (String, &'static str)>,
+    version: SpecVersion,
+    /// The document's `openapi` (or `swagger`) value, e.g. `3.0.3`
+    declared_version: Option<String>,
+    /// Path of the first `servers` URL, without a trailing slash
+    base_path: String,
+    loaded_at: Instant,
+}
+
+/// A parsed spec as reported by `GET /admin/cache`
+#[derive(Debug, Clone)]
+pub struct SpecCacheEntry {
+    pub path: PathBuf,
+    pub loaded_at: Instant,
+    /// False once the file on disk has changed (or vanished) since loading
+    pub current: bool,
+    /// `(method, path_template)` of each operation plan compiled from the spec
+    pub operations: Vec<(String, String)>,
+    /// The spec's `openapi` (or `swagger`) value; `None` for files only
+    /// loaded through `$ref`s
+    pub version: Option<String>,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum PrimitiveType {
     Integer,
@@ -77,111 +887,890 @@ enum PrimitiveType {
     Boolean,
 }
 
-#[derive(Default)]
+impl PrimitiveType {
+    fn as_str(self) -> &'static str {
+        match self {
+            PrimitiveType::Integer => "integer",
+            PrimitiveType::Number => "number",
+            PrimitiveType::Boolean => "boolean",
+        }
+    }
+}
+
+/// Cache slots: concurrent misses on the same key wait on the cell so each
+/// spec is parsed, and each operation plan compiled, only once.
+type SpecSlot = Arc<OnceCell<Arc<ParsedSpec>>>;
+type PlanSlot = Arc<OnceCell<OperationValidationPlan>>;
+
 pub struct OpenApiCache {
-    specs: HashMap<PathBuf, Arc<Value>>,
-    operations: HashMap<OperationCacheKey, OperationValidationPlan>,
+    inner: Mutex<OpenApiCacheInner>,
+    failures: NegativeCache,
+    counters: Option<CacheCounters>,
+    case_insensitive_paths: bool,
+}
+
+/// Prometheus counters the cache reports operation plan lookups and spec
+/// file reads to
+struct CacheCounters {
+    hits: Counter,
+    misses: Counter,
+    spec_loads: Counter,
+}
+
+struct OpenApiCacheInner {
+    specs: LruCache<PathBuf, SpecSlot>,
+    operations: HashMap<OperationCacheKey, PlanSlot>,
+}
+
+impl Default for OpenApiCache {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl OpenApiCache {
     pub fn new() -> Self {
+        Self::with_max_entries(None)
+    }
+
+    /// Create a cache holding at most `max_entries` parsed specs, evicting the
+    /// least recently used spec (and every operation plan derived from it)
+    /// when full. `None` means unbounded.
+    pub fn with_max_entries(max_entries: Option<usize>) -> Self {
+        let specs = match max_entries.and_then(NonZeroUsize::new) {
+            Some(cap) => LruCache::new(cap),
+            None => LruCache::unbounded(),
+        };
         Self {
-            specs: HashMap::new(),
-            operations: HashMap::new(),
+            inner: Mutex::new(OpenApiCacheInner {
+                specs,
+                operations: HashMap::new(),
+            }),
+            failures: NegativeCache::default(),
+            counters: None,
+            case_insensitive_paths: false,
+        }
+    }
+
+    /// Count operation plan cache hits and misses, and spec file reads, in
+    /// `metrics`
+    pub fn with_metrics(mut self, metrics: &Metrics) -> Self {
+        self.counters = Some(CacheCounters {
+            hits: metrics.openapi_cache_hits_total.clone(),
+            misses: metrics.openapi_cache_misses_total.clone(),
+            spec_loads: metrics.openapi_spec_loads_total.clone(),
+        });
+        self
+    }
+
+    /// Match request paths to spec path templates without regard to ASCII
+    /// case in static segments
+    pub fn with_case_insensitive_paths(mut self, case_insensitive_paths: bool) -> Self {
+        self.case_insensitive_paths = case_insensitive_paths;
+        self
+    }
+
+    /// Remember spec load failures for `ttl`, replaying them as
+    /// `Error::Cached` instead of re-reading the file. A zero `ttl` disables
+    /// negative caching.
+    pub fn with_negative_ttl(mut self, ttl: Duration) -> Self {
+        self.failures = NegativeCache::new(ttl);
+        self
+    }
+
+    /// Number of parsed specs currently cached
+    pub fn len(&self) -> usize {
+        self.lock()
+            .specs
+            .iter()
+            .filter(|(_, slot)| slot.get().is_some())
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Every parsed spec with its compiled operations, sorted by path (does
+    /// not affect recency)
+    pub fn snapshot(&self) -> Vec<SpecCacheEntry> {
+        let mut entries: Vec<SpecCacheEntry> = {
+            let inner = self.lock();
+            inner
+                .specs
+                .iter()
+                .filter_map(|(path, slot)| {
+                    let spec = slot.get()?;
+                    let mut operations: Vec<(String, String)> = inner
+                        .operations
+                        .iter()
+                        .filter(|(key, plan)| key.spec_path == *path && plan.get().is_some())
+                        .map(|(key, _)| (key.method.to_uppercase(), key.path_template.clone()))
+                        .collect();
+                    operations.sort();
+                    operations.dedup();
+                    Some(SpecCacheEntry {
+                        path: path.clone(),
+                        loaded_at: spec.loaded_at,
+                        current: true,
+                        operations,
+                        version: spec.declared_version.clone(),
+                    })
+                })
+                .collect()
+        };
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+        // Stat the files after releasing the lock
+        for entry in &mut entries {
+            entry.current = unchanged_since(&entry.path, entry.loaded_at);
         }
+        entries
+    }
+
+    /// Whether the spec at `path` is cached (does not affect recency)
+    pub fn contains_spec(&self, path: impl AsRef<Path>) -> bool {
+        self.lock()
+            .specs
+            .peek(path.as_ref())
+            .is_some_and(|slot| slot.get().is_some())
     }
 
     pub fn load_operation(
-        &mut self,
+        &self,
         spec_path: impl AsRef<Path>,
         route_path: &str,
         method: &Method,
         operation_id: Option<&str>,
     ) -> Result<OperationValidationPlan> {
-        let path_buf = spec_path.as_ref().to_path_buf();
-        let spec = self.load_spec(&path_buf)?;
-        let method_key = method.as_str().to_lowercase();
+        self.load_operation_as(
+            spec_path.as_ref(),
+            None,
+            route_path,
+            method,
+            operation_id,
+            false,
+        )
+    }
+
+    /// Like `load_operation`, with `additionalProperties: false` forced onto
+    /// the request body schema (see [`forbid_additional_properties`]).
+    /// Parameter and response schemas are left as written.
+    pub fn load_strict_operation(
+        &self,
+        spec_path: impl AsRef<Path>,
+        route_path: &str,
+        method: &Method,
+        operation_id: Option<&str>,
+    ) -> Result<OperationValidationPlan> {
+        self.load_operation_as(
+            spec_path.as_ref(),
+            None,
+            route_path,
+            method,
+            operation_id,
+            true,
+        )
+    }
 
-        let operation = find_operation(
-            spec.as_ref(),
+    /// Like `load_operation` (or `load_strict_operation` with
+    /// `strict_body`), for a request routed by `route_template`: the
+    /// route's `:param` path relative to the spec's base path. The plan's
+    /// `path_param_names` pairs the route's parameter names with the
+    /// spec's, and names that don't line up are logged when the plan is
+    /// built.
+    pub fn load_route_operation(
+        &self,
+        spec_path: impl AsRef<Path>,
+        route_template: &str,
+        route_path: &str,
+        method: &Method,
+        operation_id: Option<&str>,
+        strict_body: bool,
+    ) -> Result<OperationValidationPlan> {
+        self.load_operation_as(
+            spec_path.as_ref(),
+            Some(route_template),
             route_path,
-            &method_key,
+            method,
             operation_id,
-            &path_buf,
-        )?;
+            strict_body,
+        )
+    }
+
+    fn load_operation_as(
+        &self,
+        spec_path: &Path,
+        route_template: Option<&str>,
+        route_path: &str,
+        method: &Method,
+        operation_id: Option<&str>,
+        strict_body: bool,
+    ) -> Result<OperationValidationPlan> {
+        let path_buf = spec_path.to_path_buf();
+        let spec = self.load_spec(&path_buf)?;
+        let method_key = method.as_str().to_lowercase();
+        let refs = RefResolver::new(self, &spec, &path_buf);
+
+        let operation = find_operation(&refs, route_path, &method_key, operation_id)?;
 
         let cache_key = OperationCacheKey {
             spec_path: path_buf.clone(),
             method: method_key.clone(),
             path_template: operation.path.clone(),
+            route_template: route_template.map(str::to_string),
+            strict_body,
         };
 
-        if let Some(plan) = self.operations.get(&cache_key) {
-            return Ok(plan.clone());
+        let slot = {
+            let mut inner = self.lock();
+            Arc::clone(inner.operations.entry(cache_key.clone()).or_default())
+        };
+        if let Some(counters) = &self.counters {
+            if slot.get().is_some() {
+                counters.hits.inc();
+            } else {
+                counters.misses.inc();
+            }
         }
 
-        let schema_arc = if let Some(schema_value) = operation.schema {
-            let resolved_schema = resolve_schema_value(&schema_value, spec.as_ref(), &path_buf)?;
-            let compiled =
-                JSONSchema::compile(&resolved_schema).map_err(|e| Error::InvalidOpenApi {
-                    path: path_buf.clone(),
-                    message: e.to_string(),
-                })?;
-            Some(Arc::new(compiled))
-        } else {
-            None
-        };
+        let result = slot
+            .get_or_try_init(|| {
+                let mut plan = build_plan(operation, &method_key, &refs, strict_body)?;
+                if let Some(route_template) = route_template {
+                    plan.path_param_names =
+                        bind_path_params(route_template, &plan.path_template, spec_path);
+                }
+                Ok(plan)
+            })
+            .cloned();
+
+        if result.is_err() {
+            let mut inner = self.lock();
+            if inner
+                .operations
+                .get(&cache_key)
+                .is_some_and(|current| Arc::ptr_eq(current, &slot))
+            {
+                inner.operations.remove(&cache_key);
+            }
+        }
 
-        let parameter_validators = compile_parameter_validators(operation.parameters, &path_buf)?;
-        let response_schemas = compile_response_schemas(operation.responses, &path_buf)?;
+        result
+    }
 
-        let plan = OperationValidationPlan {
-            schema: schema_arc,
-            body_required: operation.body_required,
-            method: method_key.to_uppercase(),
-            path_template: operation.path,
-            parameters: parameter_validators,
-            response_schemas,
+    /// Every operation declared in the spec at `spec_path`, ordered by path
+    /// template and then by method.
+    pub fn list_operations(&self, spec_path: impl AsRef<Path>) -> Result<Vec<SpecOperation>> {
+        let spec_path = spec_path.as_ref();
+        let spec = self.load_spec(spec_path)?;
+        let refs = RefResolver::new(self, &spec, spec_path);
+        let paths = refs
+            .spec
+            .get("paths")
+            .and_then(Value::as_object)
+            .ok_or_else(|| Error::OpenApi {
+                path: spec_path.to_path_buf(),
+                stage: OpenApiErrorStage::Parse,
+                message: "OpenAPI document missing 'paths' section".to_string(),
+            })?;
+
+        let mut operations = Vec::new();
+        for (path_template, path_value) in paths {
+            let path_item = refs.resolve(path_value)?;
+            let Some(path_obj) = path_item.as_object() else {
+                continue;
+            };
+            for method in METHOD_KEYS {
+                let Some(operation_value) = path_obj.get(*method) else {
+                    continue;
+                };
+                let operation = refs.resolve(operation_value)?;
+                operations.push(SpecOperation {
+                    path: path_template.clone(),
+                    method: method.to_uppercase(),
+                    operation_id: operation
+                        .get("operationId")
+                        .and_then(Value::as_str)
+                        .map(str::to_string),
+                });
+            }
+        }
+        Ok(operations)
+    }
+
+    /// Check the whole spec at `spec_path` rather than the operations that
+    /// happen to be requested: its top-level structure, every `$ref`, and
+    /// every request body, parameter, response and response header schema
+    /// reachable from `paths`. Returns every problem found; empty when the
+    /// spec is fine.
+    pub fn check_spec(&self, spec_path: impl AsRef<Path>) -> Vec<SpecProblem> {
+        let spec_path = spec_path.as_ref();
+        let document = match read_document(spec_path) {
+            Ok(document) => document,
+            Err(e) => return vec![spec_problem("#", e)],
         };
 
-        self.operations.insert(cache_key, plan.clone());
-        Ok(plan)
+        let mut problems = Vec::new();
+        let version = document.get("openapi").and_then(Value::as_str);
+        if !version.is_some_and(|version| version.starts_with("3.")) {
+            problems.push(SpecProblem {
+                pointer: "#/openapi".to_string(),
+                message: "expected an OpenAPI 3.x version string".to_string(),
+            });
+        }
+        if !document.get("info").is_some_and(Value::is_object) {
+            problems.push(SpecProblem {
+                pointer: "#/info".to_string(),
+                message: "missing 'info' object".to_string(),
+            });
+        }
+        if !document.get("paths").is_some_and(Value::is_object) {
+            problems.push(SpecProblem {
+                pointer: "#/paths".to_string(),
+                message: "missing 'paths' object".to_string(),
+            });
+        }
+        let structural = problems.len();
+        collect_ref_problems(&document, &document, "#", &mut problems);
+        let broken_refs: Vec<String> = problems[structural..]
+            .iter()
+            .map(|problem| problem.message.clone())
+            .collect();
+
+        // Built here rather than loaded, since loading stops at broken refs
+        let spec = ParsedSpec {
+            operation_ids: index_operation_ids(&document),
+            version: detect_version(&document, spec_path),
+            declared_version: declared_version(&document),
+            base_path: server_base_path(&document),
+            document,
+            loaded_at: Instant::now(),
+        };
+        let refs = RefResolver::new(self, &spec, spec_path);
+        let mut checker = SpecChecker {
+            refs: &refs,
+            problems,
+            broken_refs: &broken_refs,
+        };
+        let paths = refs.spec.get("paths").and_then(Value::as_object);
+        for (path_template, path_value) in paths.into_iter().flatten() {
+            let pointer = format!("#/paths/{}", escape_pointer(path_template));
+            let Some(path_item) = checker.resolve(path_value, &pointer) else {
+                continue;
+            };
+            let params = path_item.get("parameters").and_then(Value::as_array);
+            for (index, param) in params.into_iter().flatten().enumerate() {
+                checker.check_parameter(param, &format!("{}/parameters/{}", pointer, index));
+            }
+            for method in METHOD_KEYS {
+                let Some(operation) = path_item.get(*method) else {
+                    continue;
+                };
+                let operation_pointer = format!("{}/{}", pointer, method);
+                let before = checker.problems.len();
+                checker.check_operation(operation, &operation_pointer);
+
+                // Anything the schema checks can't see, such as undefined
+                // security schemes, surfaces when the plan is built
+                if checker.problems.len() == before && broken_refs.is_empty() {
+                    let method =
+                        Method::from_bytes(method.to_uppercase().as_bytes()).unwrap_or(Method::GET);
+                    if let Err(e) = self.load_operation(spec_path, path_template, &method, None) {
+                        checker.report(&operation_pointer, e);
+                    }
+                }
+            }
+        }
+        checker.problems
+    }
+
+    /// The part of `request_path` that is matched against the spec's
+    /// `paths`. `base_path` overrides the path of the spec's first `servers`
+    /// entry; an empty string disables stripping.
+    pub fn operation_path<'a>(
+        &self,
+        spec_path: impl AsRef<Path>,
+        base_path: Option<&str>,
+        request_path: &'a str,
+    ) -> Result<&'a str> {
+        let base_path = match base_path {
+            Some(configured) => normalize_base_path(configured),
+            None => self.load_spec(spec_path.as_ref())?.base_path.clone(),
+        };
+        Ok(strip_base_path(request_path, &base_path))
     }
 
-    fn load_spec(&mut self, path: &Path) -> Result<Arc<Value>> {
-        if let Some(spec) = self.specs.get(path) {
-            return Ok(Arc::clone(spec));
+    fn load_spec(&self, path: &Path) -> Result<Arc<ParsedSpec>> {
+        if let Some(error) = self.failures.get(path) {
+            return Err(error);
         }
 
-        let contents = match fs::read_to_string(path) {
-            Ok(data) => data,
+        let slot = {
+            let mut inner = self.lock();
+            if let Some(slot) = inner.specs.get(path) {
+                Arc::clone(slot)
+            } else {
+                let slot = SpecSlot::default();
+                if let Some((evicted, _)) = inner.specs.push(path.to_path_buf(), Arc::clone(&slot))
+                {
+                    inner.operations.retain(|key, _| key.spec_path != evicted);
+                    tracing::debug!(spec = %evicted.display(), "Evicted OpenAPI spec from cache");
+                }
+                slot
+            }
+        };
+
+        let result = slot
+            .get_or_try_init(|| {
+                if let Some(counters) = &self.counters {
+                    counters.spec_loads.inc();
+                }
+                parse_spec(path).map(Arc::new)
+            })
+            .cloned();
+
+        match &result {
+            Ok(_) => self.failures.clear(path),
             Err(e) => {
-                if e.kind() == std::io::ErrorKind::NotFound {
-                    return Err(Error::OpenApiNotFound {
-                        path: path.to_path_buf(),
+                self.failures.record(path, e);
+                // Don't keep an empty slot around for a spec that failed to load
+                let mut inner = self.lock();
+                if inner
+                    .specs
+                    .peek(path)
+                    .is_some_and(|current| Arc::ptr_eq(current, &slot))
+                {
+                    inner.specs.pop(path);
+                }
+            }
+        }
+
+        result
+    }
+
+    fn lock(&self) -> MutexGuard<'_, OpenApiCacheInner> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn parse_spec(path: &Path) -> Result<ParsedSpec> {
+    let document = read_document(path)?;
+    validate_refs(&document, path)?;
+    let version = detect_version(&document, path);
+    let base_path = server_base_path(&document);
+
+    Ok(ParsedSpec {
+        operation_ids: index_operation_ids(&document),
+        declared_version: declared_version(&document),
+        document,
+        version,
+        base_path,
+        loaded_at: Instant::now(),
+    })
+}
+
+/// Read and parse the document at `path`: as JSON when its extension is
+/// `.json`, as YAML otherwise
+fn read_document(path: &Path) -> Result<Value> {
+    let contents = match fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(e) => {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                return Err(Error::OpenApiNotFound {
+                    path: path.to_path_buf(),
+                });
+            }
+            return Err(Error::Io(e));
+        }
+    };
+
+    let is_json = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let parsed = if is_json {
+        serde_json::from_str(&contents).map_err(|e| format!("Invalid JSON OpenAPI spec: {}", e))
+    } else {
+        serde_yaml::from_str(&contents).map_err(|e| format!("Invalid YAML OpenAPI spec: {}", e))
+    };
+    parsed.map_err(|message| Error::OpenApi {
+        path: path.to_path_buf(),
+        stage: OpenApiErrorStage::Parse,
+        message,
+    })
+}
+
+/// Check that every local `$ref` (`#/...`) in `document` resolves, so a
+/// broken spec fails when it's loaded rather than when the one operation
+/// using the ref is first hit. Refs into other files are checked when those
+/// files are loaded.
+fn validate_refs(document: &Value, path: &Path) -> Result<()> {
+    let mut broken: Vec<&str> = Vec::new();
+    collect_broken_refs(document, document, &mut broken);
+    if broken.is_empty() {
+        return Ok(());
+    }
+    let listed: Vec<String> = broken.iter().map(|r| format!("'{}'", r)).collect();
+    Err(Error::OpenApi {
+        path: path.to_path_buf(),
+        stage: OpenApiErrorStage::ReferenceNotFound,
+        message: format!("unresolved references: {}", listed.join(", ")),
+    })
+}
+
+fn collect_broken_refs<'v>(document: &Value, value: &'v Value, broken: &mut Vec<&'v str>) {
+    match value {
+        Value::Object(map) => {
+            if let Some(Value::String(reference)) = map.get("$ref") {
+                if let Some(pointer) = reference.strip_prefix('#') {
+                    if document.pointer(pointer).is_none() && !broken.contains(&reference.as_str())
+                    {
+                        broken.push(reference);
+                    }
+                }
+            }
+            for child in map.values() {
+                collect_broken_refs(document, child, broken);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_broken_refs(document, item, broken);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Like `collect_broken_refs`, but reporting where each broken ref is used
+fn collect_ref_problems(
+    document: &Value,
+    value: &Value,
+    pointer: &str,
+    problems: &mut Vec<SpecProblem>,
+) {
+    match value {
+        Value::Object(map) => {
+            if let Some(Value::String(reference)) = map.get("$ref") {
+                let target = reference.strip_prefix('#');
+                if target.is_some_and(|target| document.pointer(target).is_none()) {
+                    problems.push(SpecProblem {
+                        pointer: pointer.to_string(),
+                        message: format!("reference '{}' not found", reference),
                     });
                 }
-                return Err(Error::Io(e));
             }
+            for (key, child) in map {
+                let child_pointer = format!("{}/{}", pointer, escape_pointer(key));
+                collect_ref_problems(document, child, &child_pointer, problems);
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                let child_pointer = format!("{}/{}", pointer, index);
+                collect_ref_problems(document, item, &child_pointer, problems);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Escape a JSON pointer segment (`~` and `/`)
+fn escape_pointer(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+/// A load error as a problem at `pointer`, without the spec path that
+/// `Error` repeats
+fn spec_problem(pointer: &str, error: Error) -> SpecProblem {
+    let message = match error {
+        Error::OpenApi { message, .. } => message,
+        other => other.to_string(),
+    };
+    SpecProblem {
+        pointer: pointer.to_string(),
+        message,
+    }
+}
+
+/// Walks the operations of a loaded spec for `OpenApiCache::check_spec`,
+/// resolving refs and compiling schemas the way `build_plan` would
+struct SpecChecker<'r, 'a> {
+    refs: &'r RefResolver<'a>,
+    problems: Vec<SpecProblem>,
+    /// Local refs already reported where they're used
+    broken_refs: &'r [String],
+}
+
+impl SpecChecker<'_, '_> {
+    fn report(&mut self, pointer: &str, error: Error) {
+        let problem = spec_problem(pointer, error);
+        if !self.broken_refs.contains(&problem.message) {
+            self.problems.push(problem);
+        }
+    }
+
+    fn resolve(&mut self, value: &Value, pointer: &str) -> Option<Value> {
+        match self.refs.resolve(value) {
+            Ok(resolved) => Some(resolved.into_owned()),
+            Err(e) => {
+                self.report(pointer, e);
+                None
+            }
+        }
+    }
+
+    fn check_operation(&mut self, operation: &Value, pointer: &str) {
+        let Some(operation) = self.resolve(operation, pointer) else {
+            return;
         };
 
-        let spec: Value = serde_yaml::from_str(&contents).map_err(|e| Error::InvalidOpenApi {
-            path: path.to_path_buf(),
-            message: e.to_string(),
-        })?;
+        let params = operation.get("parameters").and_then(Value::as_array);
+        for (index, param) in params.into_iter().flatten().enumerate() {
+            self.check_parameter(param, &format!("{}/parameters/{}", pointer, index));
+        }
+
+        if let Some(body) = operation.get("requestBody") {
+            let body_pointer = format!("{}/requestBody", pointer);
+            if let Some(body) = self.resolve(body, &body_pointer) {
+                self.check_content(&body, &body_pointer);
+            }
+        }
 
-        let arc = Arc::new(spec);
-        self.specs.insert(path.to_path_buf(), Arc::clone(&arc));
-        Ok(arc)
+        let responses = operation.get("responses").and_then(Value::as_object);
+        for (status, response) in responses.into_iter().flatten() {
+            let response_pointer = format!("{}/responses/{}", pointer, escape_pointer(status));
+            let Some(response) = self.resolve(response, &response_pointer) else {
+                continue;
+            };
+            self.check_content(&response, &response_pointer);
+            let headers = response.get("headers").and_then(Value::as_object);
+            for (name, header) in headers.into_iter().flatten() {
+                let header_pointer =
+                    format!("{}/headers/{}", response_pointer, escape_pointer(name));
+                self.check_parameter(header, &header_pointer);
+            }
+        }
     }
+
+    /// A parameter or header object: its `schema`, or the schemas in its
+    /// `content`
+    fn check_parameter(&mut self, param: &Value, pointer: &str) {
+        let Some(param) = self.resolve(param, pointer) else {
+            return;
+        };
+        if let Some(schema) = param.get("schema") {
+            self.check_schema(schema, &format!("{}/schema", pointer));
+        }
+        self.check_content(&param, pointer);
+    }
+
+    fn check_content(&mut self, value: &Value, pointer: &str) {
+        let content = value.get("content").and_then(Value::as_object);
+        for (media_type, media) in content.into_iter().flatten() {
+            if let Some(schema) = media.get("schema") {
+                let schema_pointer =
+                    format!("{}/content/{}/schema", pointer, escape_pointer(media_type));
+                self.check_schema(schema, &schema_pointer);
+            }
+        }
+    }
+
+    fn check_schema(&mut self, schema: &Value, pointer: &str) {
+        let result = self
+            .refs
+            .inline_schema(schema)
+            .and_then(|resolved| compile_schema(resolved, self.refs.version, self.refs.spec_path));
+        if let Err(e) = result {
+            self.report(pointer, e);
+        }
+    }
+}
+
+/// The path component of the first `servers` entry, with server variables
+/// replaced by their defaults. Empty when there are no servers or the URL
+/// has no path.
+fn server_base_path(document: &Value) -> String {
+    let Some(server) = document
+        .get("servers")
+        .and_then(Value::as_array)
+        .and_then(|servers| servers.first())
+    else {
+        return String::new();
+    };
+    let Some(url) = server.get("url").and_then(Value::as_str) else {
+        return String::new();
+    };
+
+    let mut url = url.to_string();
+    if let Some(variables) = server.get("variables").and_then(Value::as_object) {
+        for (name, variable) in variables {
+            if let Some(default) = variable.get("default").and_then(Value::as_str) {
+                url = url.replace(&format!("{{{}}}", name), default);
+            }
+        }
+    }
+
+    // Drop the scheme and authority of absolute URLs
+    let path = match url.split_once("://") {
+        Some((_, rest)) => rest.find('/').map_or("", |idx| &rest[idx..]),
+        None => url.as_str(),
+    };
+    normalize_base_path(path)
+}
+
+fn normalize_base_path(path: &str) -> String {
+    let trimmed = path.trim_end_matches('/');
+    if trimmed.is_empty() {
+        String::new()
+    } else if trimmed.starts_with('/') {
+        trimmed.to_string()
+    } else {
+        format!("/{}", trimmed)
+    }
+}
+
+/// Remove `base_path` from the front of `path`. Paths outside the base
+/// path are returned unchanged.
+pub fn strip_base_path<'a>(path: &'a str, base_path: &str) -> &'a str {
+    let base_path = base_path.trim_end_matches('/');
+    if base_path.is_empty() {
+        return path;
+    }
+    match path.strip_prefix(base_path) {
+        Some("") => "/",
+        Some(rest) if rest.starts_with('/') => rest,
+        _ => path,
+    }
+}
+
+/// The document's `openapi` or `swagger` field
+fn declared_version(document: &Value) -> Option<String> {
+    ["openapi", "swagger"]
+        .into_iter()
+        .find_map(|key| document.get(key).and_then(Value::as_str))
+        .map(str::to_string)
+}
+
+fn detect_version(document: &Value, path: &Path) -> SpecVersion {
+    if document.get("openapi").is_none()
+        && document
+            .get("swagger")
+            .and_then(Value::as_str)
+            .is_some_and(|version| version.starts_with("2."))
+    {
+        return SpecVersion::Swagger2;
+    }
+    let is_3_0 = match document.get("openapi").and_then(Value::as_str) {
+        Some(version) => version.starts_with("3.0"),
+        // Not a root document (e.g. a file of shared components)
+        None => true,
+    };
+    if is_3_0 {
+        return SpecVersion::V3_0;
+    }
+
+    let draft = match document.get("jsonSchemaDialect").and_then(Value::as_str) {
+        Some(dialect) => dialect_draft(dialect).unwrap_or_else(|| {
+            tracing::warn!(
+                spec = %path.display(),
+                "Unknown jsonSchemaDialect '{}', compiling schemas as JSON Schema 2020-12",
+                dialect
+            );
+            Draft::Draft202012
+        }),
+        None => Draft::Draft202012,
+    };
+    SpecVersion::V3_1(draft)
+}
+
+fn dialect_draft(dialect: &str) -> Option<Draft> {
+    match dialect.trim_end_matches('#') {
+        "https://spec.openapis.org/oas/3.1/dialect/base"
+        | "https://json-schema.org/draft/2020-12/schema" => Some(Draft::Draft202012),
+        "https://json-schema.org/draft/2019-09/schema" => Some(Draft::Draft201909),
+        "http://json-schema.org/draft-07/schema" => Some(Draft::Draft7),
+        "http://json-schema.org/draft-06/schema" => Some(Draft::Draft6),
+        "http://json-schema.org/draft-04/schema" => Some(Draft::Draft4),
+        _ => None,
+    }
+}
+
+fn build_plan(
+    operation: OperationMatch,
+    method_key: &str,
+    refs: &RefResolver,
+    strict_body: bool,
+) -> Result<OperationValidationPlan> {
+    let spec_path = refs.spec_path;
+    let mut multipart = None;
+    let schema_arc = if let Some(schema_value) = operation.schema {
+        let mut resolved_schema = refs.inline_schema(&schema_value)?;
+        if let Some(encoding) = &operation.multipart_encoding {
+            multipart = Some(MultipartForm::from_schema(&resolved_schema, encoding));
+            // File contents aren't read, only checked for presence
+            accept_any_file(&mut resolved_schema);
+        }
+        if strict_body {
+            forbid_additional_properties(&mut resolved_schema, spec_path);
+        }
+        Some(Arc::new(compile_schema(
+            resolved_schema,
+            refs.version,
+            spec_path,
+        )?))
+    } else {
+        None
+    };
+
+    let parameter_validators =
+        compile_parameter_validators(operation.parameters, refs.version, spec_path)?;
+    let response_schemas = compile_response_schemas(operation.responses, refs.version, spec_path)?;
+    let mut response_headers = HashMap::new();
+    for (key, headers) in operation.response_headers {
+        let validators = compile_parameter_validators(headers, refs.version, spec_path)?;
+        response_headers.insert(key, validators);
+    }
+
+    Ok(OperationValidationPlan {
+        schema: schema_arc,
+        body_required: operation.body_required,
+        content_types: operation.content_types,
+        multipart,
+        method: method_key.to_uppercase(),
+        path_template: operation.path,
+        operation_id: operation.operation_id,
+        spec_version: refs.version,
+        deprecated: operation.deprecated,
+        sunset: operation.sunset,
+        path_param_names: Vec::new(),
+        parameters: parameter_validators,
+        response_schemas,
+        response_headers,
+        example_response: operation.example_response,
+        security: operation.security,
+        overrides: operation.overrides,
+        case_insensitive_paths: refs.cache.case_insensitive_paths,
+    })
 }
 
 struct OperationMatch {
     path: String,
+    operation_id: Option<String>,
+    deprecated: bool,
+    sunset: Option<String>,
     body_required: bool,
+    content_types: Vec<String>,
     schema: Option<Value>,
+    multipart_encoding: Option<Map<String, Value>>,
     parameters: Vec<ParameterSpec>,
-    responses: HashMap<ResponseKey, Value>,
+    responses: HashMap<ResponseKey, ResponseMediaTypes>,
+    response_headers: HashMap<ResponseKey, Vec<ParameterSpec>>,
+    example_response: ExampleResponse,
+    security: Vec<SecurityRequirement>,
+    overrides: RouteConfig,
 }
 
 struct ParameterSpec {
@@ -189,28 +1778,139 @@ struct ParameterSpec {
     location: ParameterLocation,
     required: bool,
     schema: Option<Value>,
+    style: ParameterStyle,
+    explode: bool,
+    deprecated: bool,
+    /// Schema taken from a JSON media type in `content`
+    json_content: bool,
+}
+
+/// The raw strings for a scalar `default`, or for each item of an array one
+fn default_items(default: &Value) -> Option<Vec<String>> {
+    let scalar = |value: &Value| match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(_) | Value::Bool(_) => Some(value.to_string()),
+        _ => None,
+    };
+    match default {
+        Value::Array(items) => items.iter().map(scalar).collect(),
+        other => scalar(other).map(|value| vec![value]),
+    }
 }
 
 fn find_operation(
-    spec: &Value,
+    refs: &RefResolver,
     route_path: &str,
     method: &str,
     operation_id: Option<&str>,
-    spec_path: &Path,
 ) -> Result<OperationMatch> {
-    let paths = spec
+    let paths = refs
+        .spec
         .get("paths")
         .and_then(Value::as_object)
         .ok_or_else(|| Error::OpenApi {
-            path: spec_path.to_path_buf(),
+            path: refs.spec_path.to_path_buf(),
+            stage: OpenApiErrorStage::Parse,
             message: "OpenAPI document missing 'paths' section".to_string(),
         })?;
 
     if let Some(op_id) = operation_id {
-        return find_by_operation_id(paths, op_id, method, route_path, spec, spec_path);
+        if let Some((path_template, operation_key)) = refs.operation_ids.get(op_id) {
+            return find_indexed_operation(
+                op_id,
+                path_template,
+                operation_key,
+                method,
+                route_path,
+                refs,
+            );
+        }
+        // Not indexed: missing, or under a path item that doesn't resolve.
+        // The scan reports which.
+        return find_by_operation_id(paths, op_id, method, route_path, refs);
+    }
+
+    find_by_path(paths, route_path, method, refs)
+}
+
+/// Map each operationId in `document` to where it's declared
+fn index_operation_ids(document: &Value) -> HashMap<String, (String, &'static str)> {
+    let mut index = HashMap::new();
+    let paths = document.get("paths").and_then(Value::as_object);
+    for (path_template, path_value) in paths.into_iter().flatten() {
+        let Some(path_item) = resolve_local(document, path_value) else {
+            continue;
+        };
+        for operation_key in METHOD_KEYS {
+            let operation_id = path_item
+                .get(*operation_key)
+                .and_then(|operation| resolve_local(document, operation))
+                .and_then(|operation| operation.get("operationId"))
+                .and_then(Value::as_str);
+            if let Some(operation_id) = operation_id {
+                index
+                    .entry(operation_id.to_string())
+                    .or_insert_with(|| (path_template.clone(), *operation_key));
+            }
+        }
+    }
+    index
+}
+
+/// Follow `value`'s chain of local `$ref`s. `None` if it leads out of the
+/// document, nowhere, or around in a loop.
+fn resolve_local<'v>(document: &'v Value, mut value: &'v Value) -> Option<&'v Value> {
+    const MAX_HOPS: usize = 32;
+    for _ in 0..MAX_HOPS {
+        match ref_of(value) {
+            Some(reference) => value = document.pointer(reference.strip_prefix('#')?)?,
+            None => return Some(value),
+        }
+    }
+    None
+}
+
+/// The operation `target_operation_id` names, found through the spec's
+/// operationId index
+fn find_indexed_operation(
+    target_operation_id: &str,
+    path_template: &str,
+    operation_key: &str,
+    method: &str,
+    route_path: &str,
+    refs: &RefResolver,
+) -> Result<OperationMatch> {
+    if operation_key != method {
+        return Err(Error::OpenApi {
+            path: refs.spec_path.to_path_buf(),
+            stage: OpenApiErrorStage::OperationNotFound,
+            message: format!(
+                "operation '{}' uses HTTP method '{}', not '{}'",
+                target_operation_id, operation_key, method
+            ),
+        });
     }
 
-    find_by_path(paths, route_path, method, spec, spec_path)
+    if !paths_match(route_path, path_template, refs.cache.case_insensitive_paths) {
+        return Err(Error::OpenApi {
+            path: refs.spec_path.to_path_buf(),
+            stage: OpenApiErrorStage::OperationNotFound,
+            message: format!(
+                "operation '{}' is defined at '{}' which does not match route '{}'",
+                target_operation_id, path_template, route_path
+            ),
+        });
+    }
+
+    let path_value = refs
+        .spec
+        .get("paths")
+        .and_then(|paths| paths.get(path_template))
+        .unwrap_or(&Value::Null);
+    let path_item = refs.resolve(path_value)?.into_owned();
+    let operation = path_item.get(operation_key).unwrap_or(&Value::Null);
+    let operation = refs.resolve(operation)?.into_owned();
+    build_operation_match(path_template, path_item, operation, refs)
 }
 
 fn find_by_operation_id(
@@ -218,22 +1918,21 @@ fn find_by_operation_id(
     target_operation_id: &str,
     method: &str,
     route_path: &str,
-    spec: &Value,
-    spec_path: &Path,
+    refs: &RefResolver,
 ) -> Result<OperationMatch> {
     for (path_template, path_value) in paths {
-        let resolved_path_item = resolve_reference(path_value, spec, spec_path)?;
+        let resolved_path_item = refs.resolve(path_value)?;
         let path_obj = resolved_path_item
             .as_object()
             .ok_or_else(|| Error::OpenApi {
-                path: spec_path.to_path_buf(),
+                path: refs.spec_path.to_path_buf(),
+                stage: OpenApiErrorStage::Parse,
                 message: format!("Path item '{}' is not an object", path_template),
             })?;
 
         for operation_key in METHOD_KEYS {
             if let Some(operation_value) = path_obj.get(*operation_key) {
-                let resolved_operation =
-                    resolve_reference(operation_value, spec, spec_path)?.clone();
+                let resolved_operation = refs.resolve(operation_value)?.into_owned();
                 if let Some(op_id) = resolved_operation
                     .get("operationId")
                     .and_then(Value::as_str)
@@ -241,7 +1940,8 @@ fn find_by_operation_id(
                     if op_id == target_operation_id {
                         if *operation_key != method {
                             return Err(Error::OpenApi {
-                                path: spec_path.to_path_buf(),
+                                path: refs.spec_path.to_path_buf(),
+                                stage: OpenApiErrorStage::OperationNotFound,
                                 message: format!(
                                     "operation '{}' uses HTTP method '{}', not '{}'",
                                     target_operation_id, operation_key, method
@@ -249,9 +1949,14 @@ fn find_by_operation_id(
                             });
                         }
 
-                        if !paths_match(route_path, path_template) {
+                        if !paths_match(
+                            route_path,
+                            path_template,
+                            refs.cache.case_insensitive_paths,
+                        ) {
                             return Err(Error::OpenApi {
-                                path: spec_path.to_path_buf(),
+                                path: refs.spec_path.to_path_buf(),
+                                stage: OpenApiErrorStage::OperationNotFound,
                                 message: format!(
                                     "operation '{}' is defined at '{}' which does not match route '{}'",
                                     target_operation_id, path_template, route_path
@@ -261,10 +1966,9 @@ fn find_by_operation_id(
 
                         return build_operation_match(
                             path_template,
-                            resolved_path_item.clone(),
+                            resolved_path_item.into_owned(),
                             resolved_operation,
-                            spec,
-                            spec_path,
+                            refs,
                         );
                     }
                 }
@@ -273,7 +1977,8 @@ fn find_by_operation_id(
     }
 
     Err(Error::OpenApi {
-        path: spec_path.to_path_buf(),
+        path: refs.spec_path.to_path_buf(),
+        stage: OpenApiErrorStage::OperationNotFound,
         message: format!("operation '{}' was not found", target_operation_id),
     })
 }
@@ -282,34 +1987,34 @@ fn find_by_path(
     paths: &Map<String, Value>,
     route_path: &str,
     method: &str,
-    spec: &Value,
-    spec_path: &Path,
+    refs: &RefResolver,
 ) -> Result<OperationMatch> {
     for (path_template, path_value) in paths {
-        if !paths_match(route_path, path_template) {
+        if !paths_match(route_path, path_template, refs.cache.case_insensitive_paths) {
             continue;
         }
 
-        let resolved_path = resolve_reference(path_value, spec, spec_path)?;
+        let resolved_path = refs.resolve(path_value)?;
         let path_obj = resolved_path.as_object().ok_or_else(|| Error::OpenApi {
-            path: spec_path.to_path_buf(),
+            path: refs.spec_path.to_path_buf(),
+            stage: OpenApiErrorStage::Parse,
             message: format!("Path item '{}' is not an object", path_template),
         })?;
 
         if let Some(operation_value) = path_obj.get(method) {
-            let resolved_operation = resolve_reference(operation_value, spec, spec_path)?;
+            let resolved_operation = refs.resolve(operation_value)?.into_owned();
             return build_operation_match(
                 path_template,
-                resolved_path.clone(),
-                resolved_operation.clone(),
-                spec,
-                spec_path,
+                resolved_path.into_owned(),
+                resolved_operation,
+                refs,
             );
         }
     }
 
     Err(Error::OpenApi {
-        path: spec_path.to_path_buf(),
+        path: refs.spec_path.to_path_buf(),
+        stage: OpenApiErrorStage::OperationNotFound,
         message: format!(
             "no OpenAPI operation for '{}' {}",
             method.to_uppercase(),
@@ -322,48 +2027,142 @@ fn build_operation_match(
     path_template: &str,
     path_item: Value,
     operation_value: Value,
-    spec: &Value,
-    spec_path: &Path,
+    refs: &RefResolver,
 ) -> Result<OperationMatch> {
-    let info = extract_request_body_info(&operation_value, spec, spec_path)?;
-    let parameters = collect_parameters(&path_item, &operation_value, spec, spec_path)?;
-    let responses = extract_response_schemas(&operation_value, spec, spec_path)?;
+    let info = extract_request_body_info(&operation_value, refs)?;
+    let parameters = collect_parameters(&path_item, &operation_value, refs)?;
+    let responses = extract_response_schemas(&operation_value, refs)?;
+    let response_headers = extract_response_headers(&operation_value, refs)?;
+    let example_response = extract_example_response(&operation_value, refs)?;
+    let security = extract_security(&operation_value, refs)?;
+    let overrides = gateway_extension(&operation_value, path_template, refs.spec_path)
+        .or(gateway_extension(&path_item, path_template, refs.spec_path));
     Ok(OperationMatch {
         path: path_template.to_string(),
+        operation_id: operation_value
+            .get("operationId")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        deprecated: operation_value
+            .get("deprecated")
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+        sunset: operation_value
+            .get("x-sunset")
+            .and_then(Value::as_str)
+            .map(str::to_string),
         body_required: info.body_required,
+        content_types: info.content_types,
         schema: info.schema,
+        multipart_encoding: info.multipart_encoding,
         parameters,
         responses,
+        response_headers,
+        example_response,
+        security,
+        overrides,
     })
 }
 
+/// Route settings a spec can't set: those choosing the upstream client,
+/// and credentials
+const ROUTE_ONLY_SETTINGS: &[&str] = &[
+    "upstream_tls",
+    "follow_redirects",
+    "max_redirects",
+    "upstream_auth",
+];
+
+/// Route settings from an `x-schema-gateway` object. Keys that aren't
+/// route settings, or have invalid values, are skipped and logged.
+/// `upstream_tls` and the redirect settings are skipped too, since the
+/// upstream client is picked before the operation is known, and so is
+/// `upstream_auth`, which belongs with the gateway's own config.
+fn gateway_extension(object: &Value, path_template: &str, spec_path: &Path) -> RouteConfig {
+    let Some(extension) = object.get(GATEWAY_EXTENSION) else {
+        return RouteConfig::default();
+    };
+    let Some(entries) = extension.as_object() else {
+        tracing::warn!(
+            spec = %spec_path.display(),
+            path = %path_template,
+            "Ignoring {} that is not an object",
+            GATEWAY_EXTENSION
+        );
+        return RouteConfig::default();
+    };
+
+    let mut overrides = RouteConfig::default();
+    for (key, value) in entries {
+        if ROUTE_ONLY_SETTINGS.contains(&key.as_str()) {
+            tracing::debug!(
+                spec = %spec_path.display(),
+                path = %path_template,
+                "Ignoring {}.{}: it can only be set on the route",
+                GATEWAY_EXTENSION,
+                key
+            );
+            continue;
+        }
+        // One key at a time, so a bad entry doesn't drop the others
+        let single = Value::Object(Map::from_iter([(key.clone(), value.clone())]));
+        match serde_json::from_value::<RouteConfig>(single) {
+            Ok(setting) if setting == RouteConfig::default() => {
+                tracing::debug!(
+                    spec = %spec_path.display(),
+                    path = %path_template,
+                    key = %key,
+                    "Ignoring unknown {} key",
+                    GATEWAY_EXTENSION
+                );
+            }
+            Ok(setting) => overrides = overrides.or(setting),
+            Err(e) => {
+                tracing::warn!(
+                    spec = %spec_path.display(),
+                    path = %path_template,
+                    key = %key,
+                    error = %e,
+                    "Ignoring invalid {} setting",
+                    GATEWAY_EXTENSION
+                );
+            }
+        }
+    }
+    overrides
+}
+
 struct RequestBodyInfo {
     schema: Option<Value>,
     body_required: bool,
+    content_types: Vec<String>,
+    /// `encoding` of a `multipart/form-data` body, when that's the media
+    /// type the schema came from
+    multipart_encoding: Option<Map<String, Value>>,
 }
 
-fn extract_request_body_info(
-    operation: &Value,
-    spec: &Value,
-    spec_path: &Path,
-) -> Result<RequestBodyInfo> {
+fn extract_request_body_info(operation: &Value, refs: &RefResolver) -> Result<RequestBodyInfo> {
     let op_obj = operation.as_object().ok_or_else(|| Error::OpenApi {
-        path: spec_path.to_path_buf(),
+        path: refs.spec_path.to_path_buf(),
+        stage: OpenApiErrorStage::Parse,
         message: "operation is not an object".to_string(),
     })?;
 
     let request_body = match op_obj.get("requestBody") {
-        Some(body) => resolve_reference(body, spec, spec_path)?,
+        Some(body) => refs.resolve(body)?,
         None => {
             return Ok(RequestBodyInfo {
                 schema: None,
                 body_required: false,
+                content_types: Vec::new(),
+                multipart_encoding: None,
             })
         }
     };
 
     let body_obj = request_body.as_object().ok_or_else(|| Error::OpenApi {
-        path: spec_path.to_path_buf(),
+        path: refs.spec_path.to_path_buf(),
+        stage: OpenApiErrorStage::Parse,
         message: "requestBody must be an object".to_string(),
     })?;
 
@@ -378,52 +2177,73 @@ fn extract_request_body_info(
             return Ok(RequestBodyInfo {
                 schema: None,
                 body_required,
+                content_types: Vec::new(),
+                multipart_encoding: None,
             })
         }
     };
+    let content_types: Vec<String> = content.keys().map(|key| media_type_essence(key)).collect();
 
-    let media_type = match select_json_media_type(content) {
+    // Forms are only validated when there's no JSON alternative
+    let multipart = content
+        .get("multipart/form-data")
+        .filter(|_| select_json_media_type(content).is_none());
+    let media_type = match select_json_media_type(content).or(multipart) {
         Some(media) => media,
         None => {
             return Ok(RequestBodyInfo {
                 schema: None,
                 body_required,
+                content_types,
+                multipart_encoding: None,
             })
         }
     };
 
     let media_obj = media_type.as_object().ok_or_else(|| Error::OpenApi {
-        path: spec_path.to_path_buf(),
+        path: refs.spec_path.to_path_buf(),
+        stage: OpenApiErrorStage::Parse,
         message: "media type must be an object".to_string(),
     })?;
 
     let schema = match media_obj.get("schema") {
-        Some(schema_val) => resolve_reference(schema_val, spec, spec_path)?,
+        Some(schema_val) => refs.resolve(schema_val)?,
         None => {
             return Ok(RequestBodyInfo {
                 schema: None,
                 body_required,
+                content_types,
+                multipart_encoding: None,
             })
         }
     };
 
+    let multipart_encoding = multipart.map(|_| {
+        media_obj
+            .get("encoding")
+            .and_then(Value::as_object)
+            .cloned()
+            .unwrap_or_default()
+    });
+
     Ok(RequestBodyInfo {
-        schema: Some(schema.clone()),
+        schema: Some(schema.into_owned()),
         body_required,
+        content_types,
+        multipart_encoding,
     })
 }
 
 fn collect_parameters(
     path_item: &Value,
     operation: &Value,
-    spec: &Value,
-    spec_path: &Path,
+    refs: &RefResolver,
 ) -> Result<Vec<ParameterSpec>> {
     let mut params = Vec::new();
 
     if let Some(list) = path_item.get("parameters").and_then(Value::as_array) {
         for param in list {
-            if let Some(parsed) = parse_parameter(param, spec, spec_path)? {
+            if let Some(parsed) = parse_parameter(param, refs)? {
                 upsert_parameter(&mut params, parsed);
             }
         }
@@ -431,7 +2251,7 @@ fn collect_parameters(
 
     if let Some(list) = operation.get("parameters").and_then(Value::as_array) {
         for param in list {
-            if let Some(parsed) = parse_parameter(param, spec, spec_path)? {
+            if let Some(parsed) = parse_parameter(param, refs)? {
                 upsert_parameter(&mut params, parsed);
             }
         }
@@ -440,10 +2260,11 @@ fn collect_parameters(
     Ok(params)
 }
 
-fn parse_parameter(value: &Value, spec: &Value, spec_path: &Path) -> Result<Option<ParameterSpec>> {
-    let resolved = resolve_reference(value, spec, spec_path)?;
+fn parse_parameter(value: &Value, refs: &RefResolver) -> Result<Option<ParameterSpec>> {
+    let resolved = refs.resolve(value)?;
     let obj = resolved.as_object().ok_or_else(|| Error::OpenApi {
-        path: spec_path.to_path_buf(),
+        path: refs.spec_path.to_path_buf(),
+        stage: OpenApiErrorStage::Parse,
         message: "parameter must be an object".to_string(),
     })?;
 
@@ -451,7 +2272,8 @@ fn parse_parameter(value: &Value, spec: &Value, spec_path: &Path) -> Result<Opti
         .get("name")
         .and_then(Value::as_str)
         .ok_or_else(|| Error::OpenApi {
-            path: spec_path.to_path_buf(),
+            path: refs.spec_path.to_path_buf(),
+            stage: OpenApiErrorStage::Parse,
             message: "parameter missing 'name'".to_string(),
         })?
         .to_string();
@@ -467,7 +2289,8 @@ fn parse_parameter(value: &Value, spec: &Value, spec_path: &Path) -> Result<Opti
         }
         None => {
             return Err(Error::OpenApi {
-                path: spec_path.to_path_buf(),
+                path: refs.spec_path.to_path_buf(),
+                stage: OpenApiErrorStage::Parse,
                 message: format!("parameter '{}' missing 'in'", name),
             })
         }
@@ -481,16 +2304,71 @@ fn parse_parameter(value: &Value, spec: &Value, spec_path: &Path) -> Result<Opti
         required = true;
     }
 
-    let schema = match obj.get("schema") {
-        Some(schema_value) => Some(resolve_schema_value(schema_value, spec, spec_path)?),
+    let mut schema = match obj.get("schema") {
+        Some(schema_value) => Some(refs.inline_schema(schema_value)?),
         None => None,
     };
 
+    // Complex values can be described by a media type instead of a schema
+    let json_content = schema.is_none() && obj.contains_key("content");
+    if json_content {
+        let media = obj
+            .get("content")
+            .and_then(Value::as_object)
+            .and_then(select_json_media_type);
+        match media.and_then(|media| media.get("schema")) {
+            Some(schema_value) => schema = Some(refs.inline_schema(schema_value)?),
+            None => tracing::warn!(
+                spec = %refs.spec_path.display(),
+                "Parameter '{}' has no JSON schema in 'content'; its value will not be validated",
+                name
+            ),
+        }
+    }
+
+    // `style` doesn't apply to content parameters
+    let style_name = obj
+        .get("style")
+        .and_then(Value::as_str)
+        .filter(|_| !json_content);
+    let style = match style_name {
+        Some(name) => ParameterStyle::parse(name, location).filter(|style| {
+            *style != ParameterStyle::DeepObject
+                || schema.as_ref().and_then(schema_type) == Some("object")
+        }),
+        None => Some(ParameterStyle::default_for(location)),
+    };
+    let style = match style {
+        Some(style) => style,
+        None => {
+            // Presence is still checked, but the value can't be assembled
+            tracing::warn!(
+                spec = %refs.spec_path.display(),
+                "Unsupported style '{}' for parameter '{}'; its value will not be validated",
+                style_name.unwrap_or_default(),
+                name
+            );
+            schema = None;
+            ParameterStyle::default_for(location)
+        }
+    };
+    let explode = obj
+        .get("explode")
+        .and_then(Value::as_bool)
+        .unwrap_or(style == ParameterStyle::Form);
+
     Ok(Some(ParameterSpec {
         name,
         location,
         required,
         schema,
+        style,
+        explode,
+        deprecated: obj
+            .get("deprecated")
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+        json_content,
     }))
 }
 
@@ -507,9 +2385,8 @@ fn upsert_parameter(params: &mut Vec<ParameterSpec>, spec: ParameterSpec) {
 
 fn extract_response_schemas(
     operation: &Value,
-    spec: &Value,
-    spec_path: &Path,
-) -> Result<HashMap<ResponseKey, Value>> {
+    refs: &RefResolver,
+) -> Result<HashMap<ResponseKey, ResponseMediaTypes>> {
     let mut map = HashMap::new();
     let responses = match operation.get("responses").and_then(Value::as_object) {
         Some(map) => map,
@@ -517,56 +2394,286 @@ fn extract_response_schemas(
     };
 
     for (status_key, response_value) in responses {
-        let resolved_response = resolve_reference(response_value, spec, spec_path)?;
-        let content = match resolved_response.get("content").and_then(Value::as_object) {
-            Some(content) => content,
-            None => continue,
+        let Some(key) = parse_response_key(status_key) else {
+            continue;
         };
+        let resolved_response = refs.resolve(response_value)?;
+        let content = resolved_response.get("content").and_then(Value::as_object);
+
+        let mut media_types = Vec::new();
+        for (media_type, media) in content.into_iter().flatten() {
+            let media_type = media_type_essence(media_type);
+            if !is_json_media_type(&media_type) {
+                media_types.push((media_type, None));
+                continue;
+            }
 
-        let media = match select_json_media_type(content) {
-            Some(media) => media,
-            None => continue,
-        };
+            let media_obj = media.as_object().ok_or_else(|| Error::OpenApi {
+                path: refs.spec_path.to_path_buf(),
+                stage: OpenApiErrorStage::Parse,
+                message: "response media type must be an object".to_string(),
+            })?;
+            let schema_value = match media_obj.get("schema") {
+                Some(schema) => Some(refs.inline_schema(schema)?),
+                None => None,
+            };
+            media_types.push((media_type, schema_value));
+        }
+        map.insert(key, media_types);
+    }
 
-        let media_obj = media.as_object().ok_or_else(|| Error::OpenApi {
-            path: spec_path.to_path_buf(),
-            message: "response media type must be an object".to_string(),
-        })?;
+    Ok(map)
+}
+
+fn extract_example_response(operation: &Value, refs: &RefResolver) -> Result<ExampleResponse> {
+    let responses = operation.get("responses").and_then(Value::as_object);
+    let keyed: Vec<(ResponseKey, &Value)> = responses
+        .into_iter()
+        .flatten()
+        .filter_map(|(status_key, response)| Some((parse_response_key(status_key)?, response)))
+        .collect();
+    let lowest_success = keyed
+        .iter()
+        .filter_map(|(key, response)| match key {
+            ResponseKey::Status(status @ 200..=299) => Some((*status, *response)),
+            _ => None,
+        })
+        .min_by_key(|(status, _)| *status);
+    let fallback = || {
+        [ResponseKey::Class(2), ResponseKey::Default]
+            .into_iter()
+            .find_map(|wanted| keyed.iter().find(|(key, _)| *key == wanted))
+            .map(|(_, response)| (200, *response))
+    };
+    let Some((status, response)) = lowest_success.or_else(fallback) else {
+        return Ok(ExampleResponse {
+            status: 200,
+            content_type: None,
+            body: None,
+        });
+    };
+
+    let response = refs.resolve(response)?;
+    let content = response.get("content").and_then(Value::as_object);
+    let Some((media_type, media)) = content.and_then(|content| {
+        select_json_media_type(content)
+            .and_then(|media| content.iter().find(|(_, v)| std::ptr::eq(*v, media)))
+            .or_else(|| content.iter().next())
+    }) else {
+        return Ok(ExampleResponse {
+            status,
+            content_type: None,
+            body: None,
+        });
+    };
+
+    let media = refs.resolve(media)?;
+    let example = match media.get("example") {
+        Some(example) => Some(example.clone()),
+        None => match media
+            .get("examples")
+            .and_then(Value::as_object)
+            .and_then(|examples| examples.values().next())
+        {
+            Some(example) => refs.resolve(example)?.get("value").cloned(),
+            None => None,
+        },
+    };
+    let essence = media_type_essence(media_type);
+    let body = if is_json_media_type(&essence) {
+        match (example, media.get("schema")) {
+            (Some(example), _) => Some(example),
+            (None, Some(schema)) => Some(crate::example::generate(&refs.inline_schema(schema)?)),
+            (None, None) => None,
+        }
+    } else {
+        example.filter(Value::is_string)
+    };
+
+    Ok(ExampleResponse {
+        status,
+        content_type: Some(media_type.clone()),
+        body,
+    })
+}
+
+/// Headers declared under each response. They're described like header
+/// parameters, minus `name` and `in`, so they're parsed the same way.
+fn extract_response_headers(
+    operation: &Value,
+    refs: &RefResolver,
+) -> Result<HashMap<ResponseKey, Vec<ParameterSpec>>> {
+    let mut map = HashMap::new();
+    let responses = match operation.get("responses").and_then(Value::as_object) {
+        Some(map) => map,
+        None => return Ok(map),
+    };
 
-        let schema_value = match media_obj.get("schema") {
-            Some(schema) => resolve_schema_value(schema, spec, spec_path)?,
+    for (status_key, response_value) in responses {
+        let Some(key) = parse_response_key(status_key) else {
+            continue;
+        };
+        let resolved_response = refs.resolve(response_value)?;
+        let headers = match resolved_response.get("headers").and_then(Value::as_object) {
+            Some(headers) => headers,
             None => continue,
         };
 
-        if let Some(key) = parse_response_key(status_key) {
-            map.insert(key, schema_value);
+        let mut specs = Vec::new();
+        for (name, header_value) in headers {
+            // The spec says a `Content-Type` entry here is ignored
+            if name.eq_ignore_ascii_case("content-type") {
+                continue;
+            }
+            let mut header = refs.resolve(header_value)?.into_owned();
+            let Some(obj) = header.as_object_mut() else {
+                return Err(Error::OpenApi {
+                    path: refs.spec_path.to_path_buf(),
+                    stage: OpenApiErrorStage::Parse,
+                    message: format!("response header '{}' must be an object", name),
+                });
+            };
+            obj.insert("name".to_string(), Value::String(name.clone()));
+            obj.insert("in".to_string(), Value::String("header".to_string()));
+            if let Some(spec) = parse_parameter(&header, refs)? {
+                specs.push(spec);
+            }
+        }
+        if !specs.is_empty() {
+            map.insert(key, specs);
         }
     }
 
     Ok(map)
 }
 
+/// The operation's `security` list, or the spec's top-level one when the
+/// operation doesn't set its own. `security: []` turns security off.
+fn extract_security(operation: &Value, refs: &RefResolver) -> Result<Vec<SecurityRequirement>> {
+    let Some(list) = operation
+        .get("security")
+        .or_else(|| refs.spec.get("security"))
+    else {
+        return Ok(Vec::new());
+    };
+    let list = list.as_array().ok_or_else(|| Error::OpenApi {
+        path: refs.spec_path.to_path_buf(),
+        stage: OpenApiErrorStage::Parse,
+        message: "'security' must be an array".to_string(),
+    })?;
+
+    let mut requirements = Vec::new();
+    for entry in list {
+        let names = entry.as_object().ok_or_else(|| Error::OpenApi {
+            path: refs.spec_path.to_path_buf(),
+            stage: OpenApiErrorStage::Parse,
+            message: "security requirement must be an object".to_string(),
+        })?;
+        let mut schemes = Vec::new();
+        for name in names.keys() {
+            if let Some(scheme) = parse_security_scheme(name, refs)? {
+                schemes.push(scheme);
+            }
+        }
+        requirements.push(SecurityRequirement { schemes });
+    }
+    Ok(requirements)
+}
+
+/// Look up `name` in `components.securitySchemes`. `None` for scheme types
+/// whose credentials can't be checked for presence, which are then treated
+/// as satisfied.
+fn parse_security_scheme(name: &str, refs: &RefResolver) -> Result<Option<SecurityScheme>> {
+    let parse_error = |message: String| Error::OpenApi {
+        path: refs.spec_path.to_path_buf(),
+        stage: OpenApiErrorStage::Parse,
+        message,
+    };
+
+    let scheme = refs
+        .spec
+        .pointer("/components/securitySchemes")
+        .and_then(|schemes| schemes.get(name))
+        .ok_or_else(|| parse_error(format!("security scheme '{}' is not defined", name)))?;
+    let scheme = refs.resolve(scheme)?;
+    let field = |key: &str| scheme.get(key).and_then(Value::as_str);
+
+    match field("type") {
+        Some("apiKey") => {
+            let key_name = field("name")
+                .ok_or_else(|| parse_error(format!("security scheme '{}' missing 'name'", name)))?;
+            let location = match field("in") {
+                Some("header") => ParameterLocation::Header,
+                Some("query") => ParameterLocation::Query,
+                Some("cookie") => ParameterLocation::Cookie,
+                other => {
+                    return Err(parse_error(format!(
+                        "security scheme '{}' has invalid 'in' {:?}",
+                        name,
+                        other.unwrap_or_default()
+                    )))
+                }
+            };
+            Ok(Some(SecurityScheme::ApiKey {
+                name: key_name.to_string(),
+                location,
+            }))
+        }
+        Some("http") => {
+            let scheme_name = field("scheme").ok_or_else(|| {
+                parse_error(format!("security scheme '{}' missing 'scheme'", name))
+            })?;
+            Ok(Some(SecurityScheme::Http {
+                scheme: scheme_name.to_ascii_lowercase(),
+            }))
+        }
+        other => {
+            tracing::warn!(
+                spec = %refs.spec_path.display(),
+                "Security scheme '{}' of type '{}' is not enforced",
+                name,
+                other.unwrap_or_default()
+            );
+            Ok(None)
+        }
+    }
+}
+
 fn parse_response_key(raw: &str) -> Option<ResponseKey> {
     if raw.eq_ignore_ascii_case("default") {
         return Some(ResponseKey::Default);
     }
-    raw.parse::<u16>().ok().map(ResponseKey::Status)
+    match raw.as_bytes() {
+        [class @ b'1'..=b'5', b'X' | b'x', b'X' | b'x'] => Some(ResponseKey::Class(class - b'0')),
+        _ => raw.parse::<u16>().ok().map(ResponseKey::Status),
+    }
 }
 
 fn compile_parameter_validators(
     specs: Vec<ParameterSpec>,
+    version: SpecVersion,
     spec_path: &Path,
 ) -> Result<Vec<ParameterValidator>> {
     let mut validators = Vec::new();
     for spec in specs {
-        let primitive = spec.schema.as_ref().and_then(detect_primitive_type);
+        let shape = match &spec.schema {
+            Some(_) if spec.json_content => ValueShape::Json,
+            Some(schema) => ValueShape::of(schema),
+            None => ValueShape::Scalar,
+        };
+        let primitive = spec.schema.as_ref().and_then(|schema| match shape {
+            ValueShape::Array => schema.get("items").and_then(detect_primitive_type),
+            ValueShape::Json => None,
+            _ => detect_primitive_type(schema),
+        });
+        let default = spec
+            .schema
+            .as_ref()
+            .filter(|_| !spec.json_content)
+            .and_then(|schema| schema.get("default"))
+            .cloned();
         let schema_arc = match spec.schema {
-            Some(schema_value) => Some(Arc::new(JSONSchema::compile(&schema_value).map_err(
-                |e| Error::InvalidOpenApi {
-                    path: spec_path.to_path_buf(),
-                    message: e.to_string(),
-                },
-            )?)),
+            Some(schema_value) => Some(Arc::new(compile_schema(schema_value, version, spec_path)?)),
             None => None,
         };
 
@@ -575,55 +2682,178 @@ fn compile_parameter_validators(
             location: spec.location,
             required: spec.required,
             schema: schema_arc,
+            style: spec.style,
+            explode: spec.explode,
+            default,
+            deprecated: spec.deprecated,
             value_type: primitive,
+            shape,
         });
     }
     Ok(validators)
 }
 
 fn compile_response_schemas(
-    responses: HashMap<ResponseKey, Value>,
+    responses: HashMap<ResponseKey, ResponseMediaTypes>,
+    version: SpecVersion,
     spec_path: &Path,
-) -> Result<HashMap<ResponseKey, Arc<JSONSchema>>> {
+) -> Result<HashMap<ResponseKey, ResponseContent>> {
     let mut compiled = HashMap::new();
-    for (key, schema_value) in responses {
-        let schema =
-            Arc::new(
-                JSONSchema::compile(&schema_value).map_err(|e| Error::InvalidOpenApi {
-                    path: spec_path.to_path_buf(),
-                    message: e.to_string(),
-                })?,
-            );
-        compiled.insert(key, schema);
+    for (key, media_types) in responses {
+        let mut content = ResponseContent::default();
+        for (media_type, schema_value) in media_types {
+            let schema = match schema_value {
+                Some(schema_value) => {
+                    Some(Arc::new(compile_schema(schema_value, version, spec_path)?))
+                }
+                None => None,
+            };
+            content.media_types.insert(media_type, schema);
+        }
+        compiled.insert(key, content);
     }
     Ok(compiled)
 }
 
+/// Compile a resolved schema the way its spec version expects
+fn compile_schema(schema: Value, version: SpecVersion, spec_path: &Path) -> Result<JSONSchema> {
+    let mut options = JSONSchema::options();
+    let schema = match version {
+        SpecVersion::Swagger2 | SpecVersion::V3_0 => translate_3_0_schema(schema),
+        SpecVersion::V3_1(draft) => {
+            options.with_draft(draft);
+            schema
+        }
+    };
+    options.compile(&schema).map_err(|e| Error::OpenApi {
+        path: spec_path.to_path_buf(),
+        stage: OpenApiErrorStage::Compile,
+        message: e.to_string(),
+    })
+}
+
+/// Rewrite OpenAPI 3.0 schema idioms into their JSON Schema equivalents:
+/// boolean `exclusiveMinimum`/`exclusiveMaximum` modify `minimum`/`maximum`
+/// in 3.0, but are numeric bounds of their own in JSON Schema, and
+/// `nullable: true` becomes an explicit `null` type.
+fn translate_3_0_schema(schema: Value) -> Value {
+    match schema {
+        Value::Object(map) => {
+            let mut map: Map<String, Value> = map
+                .into_iter()
+                .map(|(key, value)| (key, translate_3_0_schema(value)))
+                .collect();
+            for (exclusive, bound) in [
+                ("exclusiveMinimum", "minimum"),
+                ("exclusiveMaximum", "maximum"),
+            ] {
+                if let Some(Value::Bool(is_exclusive)) = map.get(exclusive) {
+                    if *is_exclusive && map.contains_key(bound) {
+                        let limit = map.remove(bound).unwrap_or(Value::Null);
+                        map.insert(exclusive.to_string(), limit);
+                    } else {
+                        map.remove(exclusive);
+                    }
+                }
+            }
+            // A bool here is the keyword; a property named "nullable" would
+            // hold a schema object
+            if let Some(Value::Bool(nullable)) = map.get("nullable") {
+                let nullable = *nullable;
+                map.remove("nullable");
+                if nullable {
+                    return allow_null(map);
+                }
+            }
+            Value::Object(map)
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(translate_3_0_schema).collect()),
+        other => other,
+    }
+}
+
+/// Widen a translated 3.0 schema so it also accepts `null`
+fn allow_null(mut map: Map<String, Value>) -> Value {
+    if let Some(Value::Array(values)) = map.get_mut("enum") {
+        if !values.contains(&Value::Null) {
+            values.push(Value::Null);
+        }
+    }
+
+    match map.get_mut("type") {
+        Some(Value::String(name)) => {
+            let name = std::mem::take(name);
+            map.insert("type".to_string(), json!([name, "null"]));
+            Value::Object(map)
+        }
+        Some(Value::Array(names)) => {
+            if !names.iter().any(|name| name == "null") {
+                names.push(json!("null"));
+            }
+            Value::Object(map)
+        }
+        // Untyped, e.g. only allOf/oneOf: null passes the alternative branch
+        _ => json!({ "anyOf": [Value::Object(map), { "type": "null" }] }),
+    }
+}
+
+/// The schema's `type`, ignoring `null` in 3.1 type arrays
+fn schema_type(schema: &Value) -> Option<&str> {
+    match schema.get("type")? {
+        Value::String(name) => Some(name),
+        Value::Array(names) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|name| *name != "null"),
+        _ => None,
+    }
+}
+
 fn detect_primitive_type(schema: &Value) -> Option<PrimitiveType> {
-    match schema.get("type").and_then(Value::as_str) {
-        Some("integer") => Some(PrimitiveType::Integer),
-        Some("number") => Some(PrimitiveType::Number),
-        Some("boolean") => Some(PrimitiveType::Boolean),
+    let primitive = |name: &str| match name {
+        "integer" => Some(PrimitiveType::Integer),
+        "number" => Some(PrimitiveType::Number),
+        "boolean" => Some(PrimitiveType::Boolean),
+        _ => None,
+    };
+
+    match schema.get("type")? {
+        Value::String(name) => primitive(name),
+        // 3.1 type arrays such as [integer, "null"]
+        Value::Array(names) => names.iter().filter_map(Value::as_str).find_map(primitive),
         _ => None,
     }
 }
 
-fn resolve_reference<'a>(value: &'a Value, spec: &'a Value, spec_path: &Path) -> Result<&'a Value> {
-    if let Some(obj) = value.as_object() {
-        if let Some(reference) = obj.get("$ref").and_then(Value::as_str) {
-            let pointer = reference.strip_prefix('#').ok_or_else(|| Error::OpenApi {
-                path: spec_path.to_path_buf(),
-                message: format!("unsupported external reference '{}'", reference),
-            })?;
+/// A media type without its parameters, lowercased
+fn media_type_essence(media_type: &str) -> String {
+    media_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
 
-            return spec.pointer(pointer).ok_or_else(|| Error::OpenApi {
-                path: spec_path.to_path_buf(),
-                message: format!("reference '{}' not found", reference),
-            });
-        }
+/// The declared media types `content_type` falls under, most specific
+/// first
+fn media_type_candidates(content_type: &str) -> Vec<String> {
+    let essence = media_type_essence(content_type);
+    let (main_type, subtype) = essence.split_once('/').unwrap_or((essence.as_str(), ""));
+    let mut candidates = vec![essence.clone()];
+    if subtype.ends_with("+json") {
+        candidates.push(format!("{}/*+json", main_type));
     }
+    candidates.push(format!("{}/*", main_type));
+    candidates.push("*/*".to_string());
+    if is_json_media_type(&essence) {
+        candidates.push("application/json".to_string());
+    }
+    candidates
+}
 
-    Ok(value)
+fn is_json_media_type(essence: &str) -> bool {
+    essence.contains("json")
 }
 
 fn select_json_media_type(content: &Map<String, Value>) -> Option<&Value> {
@@ -643,7 +2873,7 @@ fn select_json_media_type(content: &Map<String, Value>) -> Option<&Value> {
     })
 }
 
-fn paths_match(route_path: &str, spec_path: &str) -> bool {
+fn paths_match(route_path: &str, spec_path: &str, ignore_case: bool) -> bool {
     let route_segments = split_path(route_path);
     let spec_segments = split_path(spec_path);
 
@@ -652,14 +2882,19 @@ fn paths_match(route_path: &str, spec_path: &str) -> bool {
     }
 
     for (route_seg, spec_seg) in route_segments.iter().zip(spec_segments.iter()) {
-        let route_is_param = is_route_param(route_seg);
-        let spec_is_param = is_spec_param(spec_seg);
+        let route_is_param = is_path_param(route_seg);
+        let spec_is_param = is_path_param(spec_seg);
 
         if route_is_param || spec_is_param {
             continue;
         }
 
-        if route_seg != spec_seg {
+        let same = if ignore_case {
+            route_seg.eq_ignore_ascii_case(spec_seg)
+        } else {
+            route_seg == spec_seg
+        };
+        if !same {
             return false;
         }
     }
@@ -667,6 +2902,53 @@ fn paths_match(route_path: &str, spec_path: &str) -> bool {
     true
 }
 
+/// Pair the parameter names of a route template with those of the spec
+/// template it matched, logging a warning for names that differ and for
+/// parameters facing a literal segment. Templates of different lengths
+/// (an operation picked by `operation_id`) aren't compared.
+fn bind_path_params(
+    route_template: &str,
+    spec_template: &str,
+    spec_path: &Path,
+) -> Vec<(String, String)> {
+    let route_segments = split_path(route_template);
+    let spec_segments = split_path(spec_template);
+    if route_segments.len() != spec_segments.len() {
+        return Vec::new();
+    }
+
+    let mut bindings = Vec::new();
+    let mut mismatches = Vec::new();
+    for (route_seg, spec_seg) in route_segments.iter().zip(&spec_segments) {
+        match (path_param_name(route_seg), path_param_name(spec_seg)) {
+            (Some(route_name), Some(spec_name)) => {
+                if route_name != spec_name {
+                    mismatches.push(format!("'{}' is '{}' in the spec", route_seg, spec_seg));
+                }
+                bindings.push((route_name.to_string(), spec_name.to_string()));
+            }
+            (Some(_), None) | (None, Some(_)) => {
+                mismatches.push(format!(
+                    "'{}' matches '{}' in the spec",
+                    route_seg, spec_seg
+                ));
+            }
+            (None, None) => {}
+        }
+    }
+
+    if !mismatches.is_empty() {
+        tracing::warn!(
+            spec = %spec_path.display(),
+            route = %route_template,
+            operation = %spec_template,
+            "Route path parameters don't line up with the spec: {}",
+            mismatches.join(", ")
+        );
+    }
+    bindings
+}
+
 fn split_path(path: &str) -> Vec<String> {
     let trimmed = path.trim_matches('/');
     if trimmed.is_empty() {
@@ -676,48 +2958,403 @@ fn split_path(path: &str) -> Vec<String> {
     trimmed.split('/').map(|s| s.to_string()).collect()
 }
 
-fn is_route_param(segment: &str) -> bool {
+/// A `:name` or `{name}` segment, in a route path or a spec template
+fn is_path_param(segment: &str) -> bool {
     segment.starts_with(':') || (segment.starts_with('{') && segment.ends_with('}'))
 }
 
-fn is_spec_param(segment: &str) -> bool {
-    segment.starts_with('{') && segment.ends_with('}')
+fn path_param_name(segment: &str) -> Option<&str> {
+    is_path_param(segment).then(|| segment.trim_start_matches(':').trim_matches(['{', '}']))
+}
+
+/// Resolves `$ref`s for one spec.
+///
+/// Local refs (`#/components/...`) point into the spec itself. Anything else
+/// names a file relative to the spec's directory, optionally followed by a
+/// fragment (`./components/user.yaml#/User`); those documents are loaded
+/// through the cache. Fragments taken from another file have their own refs
+/// rewritten to absolute `file#pointer` form, so they keep pointing into the
+/// file they came from once copied into this spec.
+struct RefResolver<'a> {
+    cache: &'a OpenApiCache,
+    spec: &'a Value,
+    spec_path: &'a Path,
+    operation_ids: &'a HashMap<String, (String, &'static str)>,
+    version: SpecVersion,
+    /// Absolute path of the spec, used to recognise refs back into it
+    spec_file: PathBuf,
 }
 
-fn resolve_schema_value(schema: &Value, spec: &Value, spec_path: &Path) -> Result<Value> {
-    if let Some(obj) = schema.as_object() {
-        if obj.contains_key("$ref") {
-            let resolved = resolve_reference(schema, spec, spec_path)?;
-            return resolve_schema_value(resolved, spec, spec_path);
+impl<'a> RefResolver<'a> {
+    fn new(cache: &'a OpenApiCache, spec: &'a ParsedSpec, spec_path: &'a Path) -> Self {
+        let spec_file = std::path::absolute(spec_path).unwrap_or_else(|_| spec_path.to_path_buf());
+        Self {
+            cache,
+            spec: &spec.document,
+            spec_path,
+            operation_ids: &spec.operation_ids,
+            version: spec.version,
+            spec_file,
         }
     }
 
-    match schema {
-        Value::Object(map) => {
-            let mut resolved = Map::new();
-            for (key, value) in map {
-                resolved.insert(key.clone(), resolve_schema_value(value, spec, spec_path)?);
+    /// Follow `value`'s `$ref` chain, if any, to the object it names
+    fn resolve<'v>(&self, value: &'v Value) -> Result<Cow<'v, Value>>
+    where
+        'a: 'v,
+    {
+        let mut current = Cow::Borrowed(value);
+        let mut chain = Vec::new();
+        while let Some(reference) = ref_of(&current).map(str::to_string) {
+            let (file, pointer) = self.locate(&reference)?;
+            self.enter(&mut chain, ref_key(&file, pointer))?;
+            current = self.lookup(&reference, &file, pointer)?;
+        }
+        Ok(current)
+    }
+
+    /// Replace every `$ref` in `schema` with the schema it names.
+    ///
+    /// Recursive schemas can't be inlined, so a schema that refers back to
+    /// itself is moved under the result's `$defs` and its refs point there.
+    fn inline_schema(&self, schema: &Value) -> Result<Value> {
+        let mut state = InlineState::default();
+        if let Some(Value::Object(defs)) = schema.get("$defs") {
+            state.names.extend(defs.keys().cloned());
+        }
+        let mut inlined = self.inline(schema, &mut state)?;
+        if state.defs.is_empty() {
+            return Ok(inlined);
+        }
+
+        // A recursive root is used as is, rather than through a ref
+        if let Some(name) = ref_of(&inlined).and_then(|r| r.strip_prefix(DEFS_PREFIX)) {
+            if let Some(definition) = state.defs.get(name) {
+                inlined = definition.clone();
             }
-            Ok(Value::Object(resolved))
         }
-        Value::Array(items) => {
-            let mut resolved_items = Vec::new();
-            for item in items {
-                resolved_items.push(resolve_schema_value(item, spec, spec_path)?);
+        if let Value::Object(map) = &mut inlined {
+            let defs = map
+                .entry("$defs")
+                .or_insert_with(|| Value::Object(Map::new()));
+            if let Value::Object(defs) = defs {
+                defs.extend(state.defs);
+            }
+        }
+        Ok(inlined)
+    }
+
+    fn inline(&self, schema: &Value, state: &mut InlineState) -> Result<Value> {
+        if let Some(reference) = ref_of(schema) {
+            let (file, pointer) = self.locate(reference)?;
+            let key = ref_key(&file, pointer);
+            if let Some(start) = state.stack.iter().position(|seen| *seen == key) {
+                let mut cycle = state.stack[start..].to_vec();
+                cycle.push(key.clone());
+                return Ok(defs_ref(state.recursive_name(&key, pointer, cycle)));
+            }
+            if let Some((name, _)) = state.recursive.get(&key) {
+                if state.defs.contains_key(name) {
+                    return Ok(defs_ref(name));
+                }
+            }
+
+            state.stack.push(key.clone());
+            let target = self.lookup(reference, &file, pointer)?;
+            let resolved = self.inline(&target, state)?;
+            state.stack.pop();
+
+            let Some((name, cycle)) = state.recursive.get(&key) else {
+                return Ok(resolved);
+            };
+            let self_ref = defs_ref(name);
+            // Refs that only lead back to themselves describe no schema
+            if ref_of(&resolved) == ref_of(&self_ref) {
+                return Err(self.error(format!("circular reference: {}", cycle.join(" -> "))));
+            }
+            state.defs.insert(name.clone(), resolved);
+            return Ok(self_ref);
+        }
+
+        match schema {
+            Value::Object(map) => {
+                let mut resolved = Map::new();
+                for (key, value) in map {
+                    resolved.insert(key.clone(), self.inline(value, state)?);
+                }
+                Ok(Value::Object(resolved))
+            }
+            Value::Array(items) => {
+                let mut resolved_items = Vec::new();
+                for item in items {
+                    resolved_items.push(self.inline(item, state)?);
+                }
+                Ok(Value::Array(resolved_items))
+            }
+            _ => Ok(schema.clone()),
+        }
+    }
+
+    /// Split `reference` into the absolute file it points into and the JSON
+    /// pointer within that file
+    fn locate<'r>(&self, reference: &'r str) -> Result<(PathBuf, &'r str)> {
+        let (file, pointer) = reference.split_once('#').unwrap_or((reference, ""));
+        if file.is_empty() {
+            return Ok((self.spec_file.clone(), pointer));
+        }
+        if file.contains("://") {
+            return Err(self.error(format!("unsupported remote reference '{}'", reference)));
+        }
+
+        let base_dir = self.spec_file.parent().unwrap_or_else(|| Path::new(""));
+        Ok((resolve_relative(base_dir, Path::new(file)), pointer))
+    }
+
+    fn lookup<'v>(&self, reference: &str, file: &Path, pointer: &str) -> Result<Cow<'v, Value>>
+    where
+        'a: 'v,
+    {
+        let not_found = || self.error(format!("reference '{}' not found", reference));
+
+        if file == self.spec_file {
+            return self
+                .spec
+                .pointer(pointer)
+                .map(Cow::Borrowed)
+                .ok_or_else(not_found);
+        }
+
+        let document = self
+            .cache
+            .load_spec(file)
+            .map_err(|e| self.error(format!("failed to load reference '{}': {}", reference, e)))?;
+        let target = document.document.pointer(pointer).ok_or_else(not_found)?;
+        Ok(Cow::Owned(rebase_refs(target, file)))
+    }
+
+    /// Push `key` onto the chain of refs being resolved, failing with the
+    /// cycle if it's already there
+    fn enter(&self, chain: &mut Vec<String>, key: String) -> Result<()> {
+        if let Some(start) = chain.iter().position(|seen| *seen == key) {
+            let mut cycle = chain[start..].to_vec();
+            cycle.push(key);
+            return Err(self.error(format!("circular reference: {}", cycle.join(" -> "))));
+        }
+        chain.push(key);
+        Ok(())
+    }
+
+    fn error(&self, message: String) -> Error {
+        Error::OpenApi {
+            path: self.spec_path.to_path_buf(),
+            stage: OpenApiErrorStage::ReferenceNotFound,
+            message,
+        }
+    }
+}
+
+const DEFS_PREFIX: &str = "#/$defs/";
+
+/// Progress of `RefResolver::inline_schema`
+#[derive(Default)]
+struct InlineState {
+    /// Refs being inlined, outermost first
+    stack: Vec<String>,
+    /// Refs found inside themselves, with their `$defs` name and the cycle
+    /// that revealed them
+    recursive: HashMap<String, (String, Vec<String>)>,
+    /// Inlined recursive schemas by `$defs` name
+    defs: Map<String, Value>,
+    /// `$defs` names in use
+    names: HashSet<String>,
+}
+
+impl InlineState {
+    /// The `$defs` name for the recursive ref `key`, picking one from the
+    /// last segment of its pointer the first time
+    fn recursive_name(&mut self, key: &str, pointer: &str, cycle: Vec<String>) -> &str {
+        if !self.recursive.contains_key(key) {
+            let base: String = pointer
+                .rsplit('/')
+                .next()
+                .filter(|segment| !segment.is_empty())
+                .unwrap_or("schema")
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect();
+            let mut name = base.clone();
+            let mut suffix = 2;
+            while !self.names.insert(name.clone()) {
+                name = format!("{}_{}", base, suffix);
+                suffix += 1;
             }
-            Ok(Value::Array(resolved_items))
+            self.recursive.insert(key.to_string(), (name, cycle));
+        }
+        &self.recursive[key].0
+    }
+}
+
+fn defs_ref(name: &str) -> Value {
+    json!({ "$ref": format!("{}{}", DEFS_PREFIX, name) })
+}
+
+fn ref_of(value: &Value) -> Option<&str> {
+    value.as_object()?.get("$ref")?.as_str()
+}
+
+fn ref_key(file: &Path, pointer: &str) -> String {
+    format!("{}#{}", file.display(), pointer)
+}
+
+/// Copy `value`, taken from the document at `file`, rewriting its refs to
+/// absolute `file#pointer` form
+fn rebase_refs(value: &Value, file: &Path) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, child)| {
+                    let rebased = match (key.as_str(), child.as_str()) {
+                        ("$ref", Some(reference)) => Value::String(rebase_ref(reference, file)),
+                        _ => rebase_refs(child, file),
+                    };
+                    (key.clone(), rebased)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => {
+            Value::Array(items.iter().map(|item| rebase_refs(item, file)).collect())
         }
-        _ => Ok(schema.clone()),
+        _ => value.clone(),
     }
 }
 
+fn rebase_ref(reference: &str, file: &Path) -> String {
+    let (target, pointer) = reference.split_once('#').unwrap_or((reference, ""));
+    if target.contains("://") {
+        return reference.to_string();
+    }
+
+    let target = if target.is_empty() {
+        file.to_path_buf()
+    } else {
+        let base_dir = file.parent().unwrap_or_else(|| Path::new(""));
+        resolve_relative(base_dir, Path::new(target))
+    };
+    ref_key(&target, pointer)
+}
+
 #[derive(Hash, Eq, PartialEq, Clone)]
 struct OperationCacheKey {
     spec_path: PathBuf,
     method: String,
     path_template: String,
+    /// Plans loaded for a route carry its parameter names
+    route_template: Option<String>,
+    strict_body: bool,
 }
 
+/// Operation and path item extension holding route settings for the gateway
+pub const GATEWAY_EXTENSION: &str = "x-schema-gateway";
+
 const METHOD_KEYS: &[&str] = &[
     "get", "put", "post", "delete", "options", "head", "patch", "trace",
 ];
+
+fn extract_path_params(
+    path: &str,
+    template: &str,
+    ignore_case: bool,
+) -> Option<HashMap<String, String>> {
+    let actual_segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let template_segments: Vec<&str> = template.trim_matches('/').split('/').collect();
+
+    if actual_segments.len() != template_segments.len() {
+        return None;
+    }
+
+    let mut params = HashMap::new();
+    for (actual, pattern) in actual_segments.iter().zip(template_segments.iter()) {
+        if let Some(name) = path_param_name(pattern) {
+            params.insert(name.to_string(), (*actual).to_string());
+        } else if !(pattern == actual || ignore_case && pattern.eq_ignore_ascii_case(actual)) {
+            return None;
+        }
+    }
+
+    Some(params)
+}
+
+pub(crate) fn parse_query_params(query: Option<&str>) -> HashMap<String, Vec<String>> {
+    let mut map = HashMap::new();
+    if let Some(q) = query {
+        for (key, value) in form_urlencoded::parse(q.as_bytes()) {
+            map.entry(key.into_owned())
+                .or_insert_with(Vec::new)
+                .push(value.into_owned());
+        }
+    }
+    map
+}
+
+pub(crate) fn build_header_lookup(headers: &HeaderMap) -> HashMap<String, String> {
+    let mut map = HashMap::new();
+    for (name, value) in headers.iter() {
+        if let Ok(val_str) = value.to_str() {
+            map.entry(name.as_str().to_ascii_lowercase())
+                .or_insert_with(|| val_str.to_string());
+        }
+    }
+    map
+}
+
+pub(crate) fn parse_cookie_header(headers: &HeaderMap) -> HashMap<String, String> {
+    let mut map = HashMap::new();
+    if let Some(cookie_header) = headers.get("cookie") {
+        if let Ok(cookie_str) = cookie_header.to_str() {
+            for pair in cookie_str.split(';') {
+                let mut parts = pair.trim().splitn(2, '=');
+                if let Some(name) = parts.next() {
+                    if let Some(value) = parts.next() {
+                        map.insert(name.trim().to_string(), value.trim().to_string());
+                    }
+                }
+            }
+        }
+    }
+    map
+}
+
+pub(crate) fn parameter_location_label(location: ParameterLocation) -> &'static str {
+    match location {
+        ParameterLocation::Path => "path",
+        ParameterLocation::Query => "query",
+        ParameterLocation::Header => "header",
+        ParameterLocation::Cookie => "cookie",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response_key() {
+        assert_eq!(parse_response_key("200"), Some(ResponseKey::Status(200)));
+        assert_eq!(parse_response_key("default"), Some(ResponseKey::Default));
+        assert_eq!(parse_response_key("Default"), Some(ResponseKey::Default));
+        assert_eq!(parse_response_key("1XX"), Some(ResponseKey::Class(1)));
+        assert_eq!(parse_response_key("2XX"), Some(ResponseKey::Class(2)));
+        assert_eq!(parse_response_key("4xx"), Some(ResponseKey::Class(4)));
+        assert_eq!(parse_response_key("5Xx"), Some(ResponseKey::Class(5)));
+
+        for invalid in ["6XX", "0XX", "2X", "20X", "2XXX", "XXX", "ok", ""] {
+            assert_eq!(parse_response_key(invalid), None, "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A file holding the gateway's process ID, for init systems and scripts
+/// that check for a running instance. The file is removed when this is
+/// dropped.
+#[derive(Debug)]
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    /// Write the current process ID to `path`.
+    ///
+    /// Fails if the file already names a process that is still running. A
+    /// file left behind by an instance that's gone is replaced, as is one
+    /// naming this process: a restarted container reuses the same PID
+    /// (often 1) for the new instance.
+    pub fn create(path: &Path) -> Result<Self, String> {
+        let own_pid = std::process::id();
+        if let Ok(contents) = fs::read_to_string(path) {
+            match contents.trim().parse::<u32>() {
+                Ok(pid) if pid != own_pid && process_is_running(pid) => {
+                    return Err(format!(
+                        "PID file {} belongs to running process {}; is another gateway running?",
+                        path.display(),
+                        pid
+                    ));
+                }
+                _ => tracing::warn!(
+                    path = %path.display(),
+                    "Replacing stale PID file"
+                ),
+            }
+        }
+
+        fs::write(path, format!("{}\n", own_pid))
+            .map_err(|e| format!("Failed to write PID file {}: {}", path.display(), e))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            tracing::warn!(
+                path = %self.path.display(),
+                error = %e,
+                "Failed to remove PID file"
+            );
+        }
+    }
+}
+
+/// Whether `pid` names a live process. Signal 0 checks without signalling;
+/// `EPERM` means the process exists but belongs to someone else.
+#[cfg(unix)]
+fn process_is_running(pid: u32) -> bool {
+    // 0 and values past i32::MAX would address process groups
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    if pid <= 0 {
+        return false;
+    }
+    // SAFETY: kill with signal 0 only checks that the process exists
+    let result = unsafe { libc::kill(pid, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Without a portable liveness check, an existing PID file is treated as
+/// stale
+#[cfg(not(unix))]
+fn process_is_running(_pid: u32) -> bool {
+    false
+}
@@ -1,7 +1,436 @@
-use axum::body::Body;
-use axum::http::{HeaderMap, Method, StatusCode};
+use axum::body::{Body, Bytes, HttpBody};
+use axum::http::header::{AUTHORIZATION, CONNECTION, HOST, UPGRADE};
+use axum::http::header::{CONTENT_LENGTH, CONTENT_TYPE};
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Method, Request, StatusCode};
 use axum::response::{IntoResponse, Response};
+use axum::BoxError;
+use hyper::body::{Frame, SizeHint};
+use hyper::upgrade::OnUpgrade;
+use hyper_util::rt::TokioIo;
 use reqwest::Client;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+use crate::config::{GlobalConfig, MockBody, MockResponse};
+use crate::error::error_response;
+
+/// Rewrites applied to upstream response headers before they reach the client
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResponseHeaderRules {
+    /// Header names (case-insensitive) to remove
+    pub strip: Vec<String>,
+    /// Header names to rename, keyed by the upstream name
+    pub rename: HashMap<String, String>,
+}
+
+impl ResponseHeaderRules {
+    pub fn from_config(config: &GlobalConfig) -> Self {
+        Self {
+            strip: config.strip_response_headers.clone(),
+            rename: config.rename_response_headers.clone(),
+        }
+    }
+
+    pub fn apply(&self, headers: &mut HeaderMap) {
+        for name in &self.strip {
+            if let Ok(header_name) = HeaderName::from_bytes(name.as_bytes()) {
+                headers.remove(header_name);
+            }
+        }
+
+        for (from, to) in &self.rename {
+            let (Ok(from_name), Ok(to_name)) = (
+                HeaderName::from_bytes(from.as_bytes()),
+                HeaderName::from_bytes(to.as_bytes()),
+            ) else {
+                continue;
+            };
+            let values: Vec<_> = headers.get_all(&from_name).iter().cloned().collect();
+            headers.remove(&from_name);
+            for value in values {
+                headers.append(to_name.clone(), value);
+            }
+        }
+    }
+}
+
+/// Changes made to the client's request headers before they go upstream
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequestHeaderRules {
+    /// Headers left out
+    pub remove: Vec<HeaderName>,
+    /// Sent as `Authorization` after the client's headers, so a client
+    /// can't replace it
+    pub authorization: Option<HeaderValue>,
+}
+
+impl RequestHeaderRules {
+    /// The rules `config` sets. `authorization` is the value its
+    /// `upstream_auth` resolved to, which is worked out once per config
+    /// load rather than per request.
+    pub fn from_config(config: &GlobalConfig, authorization: Option<HeaderValue>) -> Self {
+        let mut remove: Vec<HeaderName> = config
+            .remove_request_headers
+            .iter()
+            .filter_map(|name| HeaderName::from_bytes(name.as_bytes()).ok())
+            .collect();
+        // The client's credential never goes through, even if the
+        // gateway's couldn't be built
+        if config.upstream_auth.is_some() {
+            remove.push(AUTHORIZATION);
+        }
+        Self {
+            remove,
+            authorization: authorization.filter(|_| config.upstream_auth.is_some()),
+        }
+    }
+
+    /// Apply the rules to `headers` in place, for paths that don't go
+    /// through `upstream_request_headers`
+    pub fn apply(&self, headers: &mut HeaderMap) {
+        for name in &self.remove {
+            headers.remove(name);
+        }
+        if let Some(value) = &self.authorization {
+            headers.insert(AUTHORIZATION, value.clone());
+        }
+    }
+}
+
+/// How a request and its response are rewritten on the way through
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ForwardOptions {
+    pub request_headers: RequestHeaderRules,
+    pub response_headers: ResponseHeaderRules,
+    /// Limit on opening connections the gateway makes itself (Unix sockets
+    /// and upgrade tunnels); `reqwest` clients carry their own
+    pub connect_timeout: Option<Duration>,
+}
+
+impl ForwardOptions {
+    /// The options `config` sets; see `RequestHeaderRules::from_config` for
+    /// `authorization`
+    pub fn from_config(config: &GlobalConfig, authorization: Option<HeaderValue>) -> Self {
+        Self {
+            request_headers: RequestHeaderRules::from_config(config, authorization),
+            response_headers: ResponseHeaderRules::from_config(config),
+            connect_timeout: config
+                .http_client
+                .connect_timeout_ms
+                .map(Duration::from_millis),
+        }
+    }
+}
+
+/// Open a connection with `connect`, giving up with a timeout failure
+/// after `timeout`
+async fn connect_within<T>(
+    timeout: Option<Duration>,
+    upstream_url: &str,
+    connect: impl Future<Output = std::io::Result<T>>,
+) -> Result<T, UpstreamFailure> {
+    let result = match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, connect).await.map_err(|_| {
+            UpstreamFailure::new(
+                UpstreamErrorKind::Timeout,
+                upstream_url,
+                "timed out connecting to the upstream",
+            )
+        })?,
+        None => connect.await,
+    };
+    result.map_err(|e| UpstreamFailure::new(UpstreamErrorKind::Connect, upstream_url, e))
+}
+
+/// W3C Trace Context and Baggage headers, forwarded byte for byte so
+/// upstream spans join the client's trace
+pub const TRACE_CONTEXT_HEADERS: [&str; 3] = ["traceparent", "tracestate", "baggage"];
+
+/// Remove the trace context headers, for routes that shouldn't pass the
+/// client's trace on
+pub fn strip_trace_context(headers: &mut HeaderMap) {
+    for name in TRACE_CONTEXT_HEADERS {
+        headers.remove(name);
+    }
+}
+
+/// Headers that describe one connection rather than the message (RFC 7230
+/// section 6.1), never passed on in either direction
+pub const HOP_BY_HOP_HEADERS: [&str; 8] = [
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Whether `name` (lowercase) is hop-by-hop, either always or because the
+/// message's `Connection` header lists it
+fn is_hop_by_hop(name: &str, connection_listed: &[String]) -> bool {
+    HOP_BY_HOP_HEADERS.contains(&name) || connection_listed.iter().any(|listed| listed == name)
+}
+
+/// The lowercase header names listed in `headers`' `Connection` values
+fn connection_listed(headers: &HeaderMap) -> Vec<String> {
+    headers
+        .get_all(axum::http::header::CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|name| name.trim().to_ascii_lowercase())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Remove hop-by-hop headers, including those named by `Connection`
+pub fn strip_hop_by_hop(headers: &mut HeaderMap) {
+    let listed = connection_listed(headers);
+    let names: Vec<HeaderName> = headers
+        .keys()
+        .filter(|name| is_hop_by_hop(name.as_str(), &listed))
+        .cloned()
+        .collect();
+    for name in names {
+        headers.remove(name);
+    }
+}
+
+/// Request body sent to the upstream
+pub enum UpstreamBody {
+    /// Body already read into memory, e.g. for validation
+    Buffered(Vec<u8>),
+    /// Client body piped through as it arrives
+    Streaming(Body),
+}
+
+impl From<Vec<u8>> for UpstreamBody {
+    fn from(body: Vec<u8>) -> Self {
+        UpstreamBody::Buffered(body)
+    }
+}
+
+impl From<Body> for UpstreamBody {
+    fn from(body: Body) -> Self {
+        UpstreamBody::Streaming(body)
+    }
+}
+
+/// Why the upstream gave no response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpstreamErrorKind {
+    /// The connection was refused, reset or otherwise not made
+    Connect,
+    Timeout,
+    /// The upstream's host name didn't resolve
+    Dns,
+    /// The TLS handshake failed, e.g. on an untrusted or unpinned certificate
+    Tls,
+    Other,
+}
+
+impl UpstreamErrorKind {
+    /// The `kind` in error bodies and the `upstream_errors_total` label
+    pub fn label(self) -> &'static str {
+        match self {
+            UpstreamErrorKind::Connect => "connect",
+            UpstreamErrorKind::Timeout => "timeout",
+            UpstreamErrorKind::Dns => "dns",
+            UpstreamErrorKind::Tls => "tls",
+            UpstreamErrorKind::Other => "other",
+        }
+    }
+
+    /// Classify a failed request by walking its error chain
+    fn of(error: &reqwest::Error) -> Self {
+        if error.is_timeout() {
+            return UpstreamErrorKind::Timeout;
+        }
+        let mut source: Option<&(dyn std::error::Error + 'static)> = Some(error);
+        while let Some(error) = source {
+            // The default client uses native-tls, `upstream_tls` routes rustls
+            if error.is::<native_tls::Error>() || error.is::<upstream_rustls::Error>() {
+                return UpstreamErrorKind::Tls;
+            }
+            // hyper's connector reports resolver failures this way
+            if error.to_string().starts_with("dns error") {
+                return UpstreamErrorKind::Dns;
+            }
+            // `io::Error::source` skips the error it wraps, so step into it
+            source = match error.downcast_ref::<std::io::Error>() {
+                Some(io) => io
+                    .get_ref()
+                    .map(|inner| inner as &(dyn std::error::Error + 'static)),
+                None => error.source(),
+            };
+        }
+        if error.is_connect() {
+            UpstreamErrorKind::Connect
+        } else {
+            UpstreamErrorKind::Other
+        }
+    }
+
+    fn status(self) -> StatusCode {
+        match self {
+            UpstreamErrorKind::Timeout => StatusCode::GATEWAY_TIMEOUT,
+            _ => StatusCode::BAD_GATEWAY,
+        }
+    }
+
+    fn message(self) -> &'static str {
+        match self {
+            UpstreamErrorKind::Connect => "Failed to connect to upstream",
+            UpstreamErrorKind::Timeout => "Upstream request timeout",
+            UpstreamErrorKind::Dns => "Failed to resolve upstream host",
+            UpstreamErrorKind::Tls => "TLS handshake with upstream failed",
+            UpstreamErrorKind::Other => "Upstream request failed",
+        }
+    }
+}
+
+/// An upstream request that got no response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpstreamFailure {
+    pub kind: UpstreamErrorKind,
+    /// The URL the request went to
+    pub url: String,
+    /// The full error, for logs
+    pub detail: String,
+}
+
+impl UpstreamFailure {
+    pub fn new(kind: UpstreamErrorKind, url: &str, error: impl fmt::Display) -> Self {
+        Self {
+            kind,
+            url: url.to_string(),
+            detail: error.to_string(),
+        }
+    }
+
+    /// reqwest's message already includes its sources
+    fn from_reqwest(url: &str, error: &reqwest::Error) -> Self {
+        Self::new(UpstreamErrorKind::of(error), url, error)
+    }
+
+    /// The 502 or 504 for this failure:
+    /// `{"error": ..., "kind": ..., "upstream": "<host>"}`, leaving out
+    /// `upstream` when `show_upstream` is false
+    pub fn error_response(&self, show_upstream: bool) -> Response {
+        let mut body = serde_json::json!({
+            "error": self.kind.message(),
+            "kind": self.kind.label(),
+        });
+        if show_upstream {
+            if let Some(host) = url::Url::parse(&self.url)
+                .ok()
+                .and_then(|url| url.host_str().map(str::to_string))
+            {
+                body["upstream"] = host.into();
+            }
+        }
+        let mut response = (self.kind.status(), body.to_string()).into_response();
+        // Marks the response as the gateway's own, for `upstream_errors_total`
+        response.extensions_mut().insert(self.kind);
+        response
+    }
+}
+
+impl IntoResponse for UpstreamFailure {
+    fn into_response(self) -> Response {
+        self.error_response(true)
+    }
+}
+
+/// An upstream response body over `max_response_bytes`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResponseTooLarge {
+    pub limit: usize,
+}
+
+impl fmt::Display for ResponseTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Upstream response body is larger than max_response_bytes ({} bytes)",
+            self.limit
+        )
+    }
+}
+
+impl std::error::Error for ResponseTooLarge {}
+
+/// Limit `response`'s body to `limit` bytes. A `Content-Length` over the
+/// limit gets 502 straight away; otherwise the body fails with
+/// `ResponseTooLarge` once the limit is passed, which aborts a body already
+/// streaming to the client.
+pub fn cap_response_body(response: Response, limit: usize) -> Response {
+    let declared = response
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    if declared.is_some_and(|len| len > limit) {
+        return error_response(
+            StatusCode::BAD_GATEWAY,
+            &ResponseTooLarge { limit }.to_string(),
+        );
+    }
+    response.map(|inner| {
+        Body::new(CappedBody {
+            inner,
+            remaining: limit,
+            limit,
+        })
+    })
+}
+
+/// A body that fails once more than `limit` bytes have come through
+struct CappedBody {
+    inner: Body,
+    remaining: usize,
+    limit: usize,
+}
+
+impl HttpBody for CappedBody {
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, BoxError>>> {
+        let frame = match Pin::new(&mut self.inner).poll_frame(cx) {
+            Poll::Ready(Some(Ok(frame))) => frame,
+            Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e.into()))),
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => return Poll::Pending,
+        };
+        if let Some(data) = frame.data_ref() {
+            if data.len() > self.remaining {
+                let limit = self.limit;
+                return Poll::Ready(Some(Err(ResponseTooLarge { limit }.into())));
+            }
+            self.remaining -= data.len();
+        }
+        Poll::Ready(Some(Ok(frame)))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
 
 /// Forward a request to the upstream server
 pub async fn forward_request(
@@ -10,8 +439,41 @@ pub async fn forward_request(
     upstream_url: &str,
     path: &str,
     headers: HeaderMap,
-    body: Vec<u8>,
+    body: impl Into<UpstreamBody>,
+    options: &ForwardOptions,
 ) -> Response {
+    try_forward_request(client, method, upstream_url, path, headers, body, options)
+        .await
+        .unwrap_or_else(IntoResponse::into_response)
+}
+
+/// `forward_request`, returning why the upstream couldn't be reached
+/// instead of the error response for it
+pub async fn try_forward_request(
+    client: &Client,
+    method: Method,
+    upstream_url: &str,
+    path: &str,
+    headers: HeaderMap,
+    body: impl Into<UpstreamBody>,
+    options: &ForwardOptions,
+) -> Result<Response, UpstreamFailure> {
+    // HEAD responses must not carry a body, whatever the upstream sends
+    let is_head = method == Method::HEAD;
+
+    #[cfg(unix)]
+    if upstream_url.starts_with("unix://") {
+        return forward_over_unix_socket(
+            method,
+            upstream_url,
+            path,
+            &headers,
+            body.into(),
+            options,
+        )
+        .await;
+    }
+
     // Build the full upstream URL
     let url = format!("{}{}", upstream_url.trim_end_matches('/'), path);
 
@@ -32,29 +494,32 @@ pub async fn forward_request(
             let reqwest_method = match reqwest::Method::from_bytes(method.as_str().as_bytes()) {
                 Ok(m) => m,
                 Err(_) => {
-                    return (StatusCode::METHOD_NOT_ALLOWED, "Unsupported HTTP method")
-                        .into_response();
+                    return Ok(
+                        (StatusCode::METHOD_NOT_ALLOWED, "Unsupported HTTP method").into_response()
+                    );
                 }
             };
             client.request(reqwest_method, &url)
         }
     };
 
-    // Add headers to the request (skip certain headers like Host, Connection)
-    for (name, value) in headers.iter() {
-        let name_str = name.as_str().to_lowercase();
-        // Skip headers that shouldn't be forwarded
-        if name_str == "host" || name_str == "connection" {
-            continue;
-        }
-        if let Ok(value_str) = value.to_str() {
-            request_builder = request_builder.header(name.as_str(), value_str);
-        }
+    // Repeated names come through once per value, so each is kept
+    for (name, value) in upstream_request_headers(&headers, &options.request_headers).iter() {
+        request_builder = request_builder.header(name.as_str(), value.as_bytes());
     }
 
     // Add body if present
-    if !body.is_empty() {
-        request_builder = request_builder.body(body);
+    match body.into() {
+        UpstreamBody::Buffered(body) if !body.is_empty() => {
+            request_builder = request_builder.body(body);
+        }
+        // A known-empty stream (e.g. a plain GET) is sent without a body, so
+        // the upstream doesn't see a chunked request it didn't expect
+        UpstreamBody::Streaming(body) if body.size_hint().exact() != Some(0) => {
+            request_builder =
+                request_builder.body(reqwest::Body::wrap_stream(body.into_data_stream()));
+        }
+        _ => {}
     }
 
     // Send the request
@@ -63,7 +528,9 @@ pub async fn forward_request(
             // Extract status code
             let status = upstream_response.status();
 
-            // Extract headers
+            // Extract headers. Appended rather than inserted: `Set-Cookie`,
+            // `Link`, `Vary` and `Warning` can each be sent several times,
+            // and every value has to reach the client.
             let mut response_headers = HeaderMap::new();
             for (name, value) in upstream_response.headers().iter() {
                 if let Ok(header_name) =
@@ -71,38 +538,290 @@ pub async fn forward_request(
                 {
                     if let Ok(header_value) = axum::http::HeaderValue::from_bytes(value.as_bytes())
                     {
-                        response_headers.insert(header_name, header_value);
+                        response_headers.append(header_name, header_value);
                     }
                 }
             }
 
-            // Extract body
-            match upstream_response.bytes().await {
-                Ok(body_bytes) => {
-                    let mut response = Response::new(Body::from(body_bytes.to_vec()));
-                    // Convert reqwest::StatusCode to axum::http::StatusCode
-                    if let Ok(axum_status) = StatusCode::from_u16(status.as_u16()) {
-                        *response.status_mut() = axum_status;
-                    }
-                    *response.headers_mut() = response_headers;
-                    response
-                }
-                Err(_) => (
-                    StatusCode::BAD_GATEWAY,
-                    "Failed to read upstream response body",
-                )
-                    .into_response(),
+            // Convert reqwest::StatusCode to axum::http::StatusCode
+            let status = StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::OK);
+            let body = Body::from_stream(upstream_response.bytes_stream());
+            Ok(client_response(
+                status,
+                response_headers,
+                body,
+                is_head,
+                &options.response_headers,
+            ))
+        }
+        Err(err) => Err(UpstreamFailure::from_reqwest(&url, &err)),
+    }
+}
+
+/// The client's headers as they go upstream. Trace context comes first and
+/// unchanged; Host and hop-by-hop headers are left out, since the upstream
+/// connection has its own framing and proxy credentials are meant for the
+/// gateway. `rules` are applied on the way.
+fn upstream_request_headers(headers: &HeaderMap, rules: &RequestHeaderRules) -> HeaderMap {
+    let mut forwarded = HeaderMap::new();
+    for name in TRACE_CONTEXT_HEADERS {
+        if rules.remove.iter().any(|removed| removed == name) {
+            continue;
+        }
+        for value in headers.get_all(name) {
+            forwarded.append(name, value.clone());
+        }
+    }
+
+    let connection_listed = connection_listed(headers);
+    for (name, value) in headers.iter() {
+        let name_str = name.as_str();
+        // Skip headers that shouldn't be forwarded, or were already
+        if name_str == "host"
+            || is_hop_by_hop(name_str, &connection_listed)
+            || TRACE_CONTEXT_HEADERS.contains(&name_str)
+            || rules.remove.contains(name)
+        {
+            continue;
+        }
+        forwarded.append(name.clone(), value.clone());
+    }
+
+    if let Some(value) = &rules.authorization {
+        forwarded.insert(AUTHORIZATION, value.clone());
+    }
+    forwarded
+}
+
+/// The client's response for an upstream answer: hop-by-hop headers
+/// dropped and `rules` applied.
+///
+/// The body is streamed through as the upstream sends it; callers that
+/// validate it read it in themselves. Other headers, including
+/// Content-Length, are kept as the upstream sent them.
+fn client_response(
+    status: StatusCode,
+    mut headers: HeaderMap,
+    body: Body,
+    is_head: bool,
+    rules: &ResponseHeaderRules,
+) -> Response {
+    strip_hop_by_hop(&mut headers);
+    rules.apply(&mut headers);
+
+    let body = if is_head { Body::empty() } else { body };
+    let mut response = Response::new(body);
+    *response.status_mut() = status;
+    *response.headers_mut() = headers;
+    response
+}
+
+/// `try_forward_request` for a `unix://` upstream, over a new HTTP/1.1
+/// connection to the socket at the URL's path. The URL's host, if any, is
+/// sent as the Host header (`localhost` otherwise).
+#[cfg(unix)]
+async fn forward_over_unix_socket(
+    method: Method,
+    upstream_url: &str,
+    path: &str,
+    headers: &HeaderMap,
+    body: UpstreamBody,
+    options: &ForwardOptions,
+) -> Result<Response, UpstreamFailure> {
+    let url = url::Url::parse(upstream_url)
+        .map_err(|e| UpstreamFailure::new(UpstreamErrorKind::Other, upstream_url, e))?;
+    let host = url.host_str().filter(|host| !host.is_empty());
+    let stream = connect_within(
+        options.connect_timeout,
+        upstream_url,
+        tokio::net::UnixStream::connect(url.path()),
+    )
+    .await?;
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+        .await
+        .map_err(|e| UpstreamFailure::new(UpstreamErrorKind::Connect, upstream_url, e))?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            tracing::debug!(error = %e, "Upstream socket connection failed");
+        }
+    });
+
+    let is_head = method == Method::HEAD;
+    let mut request_headers = upstream_request_headers(headers, &options.request_headers);
+    let host = HeaderValue::from_str(host.unwrap_or("localhost"))
+        .map_err(|e| UpstreamFailure::new(UpstreamErrorKind::Other, upstream_url, e))?;
+    request_headers.insert(HOST, host);
+    let body = match body {
+        UpstreamBody::Buffered(body) => Body::from(body),
+        UpstreamBody::Streaming(body) => body,
+    };
+    let mut request = Request::builder()
+        .method(method)
+        .uri(path)
+        .body(body)
+        .map_err(|e| UpstreamFailure::new(UpstreamErrorKind::Other, upstream_url, e))?;
+    *request.headers_mut() = request_headers;
+
+    let upstream_response = sender
+        .send_request(request)
+        .await
+        .map_err(|e| UpstreamFailure::new(UpstreamErrorKind::Other, upstream_url, e))?;
+    let (parts, body) = upstream_response.into_parts();
+    Ok(client_response(
+        parts.status,
+        parts.headers,
+        Body::new(body),
+        is_head,
+        &options.response_headers,
+    ))
+}
+
+/// Whether the client asked to switch protocols, e.g. to WebSocket: an
+/// `Upgrade` header that `Connection` lists
+pub fn is_upgrade_request(headers: &HeaderMap) -> bool {
+    headers.contains_key(UPGRADE)
+        && connection_listed(headers)
+            .iter()
+            .any(|name| name == "upgrade")
+}
+
+/// Forward an upgrade handshake to `upstream_url` (an `http://` URL) and,
+/// if the upstream switches protocols, splice the client and upstream
+/// connections together until either side closes.
+///
+/// The upstream's answer is returned as the client's response: `101` with
+/// its handshake headers, or whatever it sent instead of upgrading.
+pub async fn tunnel_upgrade(
+    client_upgrade: OnUpgrade,
+    method: Method,
+    upstream_url: &str,
+    path: &str,
+    headers: &HeaderMap,
+    options: &ForwardOptions,
+) -> Result<Response, UpstreamFailure> {
+    let url = url::Url::parse(upstream_url)
+        .map_err(|e| UpstreamFailure::new(UpstreamErrorKind::Other, upstream_url, e))?;
+    let host = url.host_str().ok_or_else(|| {
+        UpstreamFailure::new(
+            UpstreamErrorKind::Other,
+            upstream_url,
+            "no host in upstream URL",
+        )
+    })?;
+    let port = url.port_or_known_default().unwrap_or(80);
+    let stream = connect_within(
+        options.connect_timeout,
+        upstream_url,
+        TcpStream::connect((host, port)),
+    )
+    .await?;
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+        .await
+        .map_err(|e| UpstreamFailure::new(UpstreamErrorKind::Connect, upstream_url, e))?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.with_upgrades().await {
+            tracing::debug!(error = %e, "Upstream upgrade connection failed");
+        }
+    });
+
+    // The handshake is the one place Connection and Upgrade go through
+    let mut request_headers = headers.clone();
+    strip_hop_by_hop(&mut request_headers);
+    request_headers.remove(HOST);
+    options.request_headers.apply(&mut request_headers);
+    for value in headers.get_all(UPGRADE) {
+        request_headers.append(UPGRADE, value.clone());
+    }
+    request_headers.insert(CONNECTION, HeaderValue::from_static("upgrade"));
+    let authority = match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    };
+    if let Ok(value) = HeaderValue::from_str(&authority) {
+        request_headers.insert(HOST, value);
+    }
+    let base_path = url.path().trim_end_matches('/');
+    let mut request = Request::builder()
+        .method(method)
+        .uri(format!("{}{}", base_path, path))
+        .body(Body::empty())
+        .map_err(|e| UpstreamFailure::new(UpstreamErrorKind::Other, upstream_url, e))?;
+    *request.headers_mut() = request_headers;
+
+    let mut upstream_response = sender
+        .send_request(request)
+        .await
+        .map_err(|e| UpstreamFailure::new(UpstreamErrorKind::Other, upstream_url, e))?;
+    if upstream_response.status() != StatusCode::SWITCHING_PROTOCOLS {
+        let (mut parts, body) = upstream_response.into_parts();
+        strip_hop_by_hop(&mut parts.headers);
+        return Ok(Response::from_parts(parts, Body::new(body)));
+    }
+
+    let upstream_upgrade = hyper::upgrade::on(&mut upstream_response);
+    tokio::spawn(async move {
+        let (client, upstream) = match tokio::try_join!(client_upgrade, upstream_upgrade) {
+            Ok(upgraded) => upgraded,
+            Err(e) => {
+                tracing::debug!(error = %e, "Upgrade failed");
+                return;
             }
+        };
+        let (mut client, mut upstream) = (TokioIo::new(client), TokioIo::new(upstream));
+        if let Err(e) = tokio::io::copy_bidirectional(&mut client, &mut upstream).await {
+            tracing::debug!(error = %e, "Upgraded connection closed with an error");
         }
-        Err(err) => {
-            // Handle connection errors
-            if err.is_timeout() {
-                (StatusCode::GATEWAY_TIMEOUT, "Upstream request timeout").into_response()
-            } else if err.is_connect() {
-                (StatusCode::BAD_GATEWAY, "Failed to connect to upstream").into_response()
-            } else {
-                (StatusCode::BAD_GATEWAY, "Upstream request failed").into_response()
+    });
+
+    let (parts, _) = upstream_response.into_parts();
+    Ok(Response::from_parts(parts, Body::empty()))
+}
+
+/// Build the response for a route's `mock_response`
+pub async fn mock_response(mock: &MockResponse, method: &Method) -> Response {
+    let (body, default_content_type) = match &mock.body {
+        None => (Vec::new(), None),
+        Some(MockBody::Json(value)) => (value.to_string().into_bytes(), Some("application/json")),
+        Some(MockBody::Text(text)) => (text.clone().into_bytes(), None),
+        Some(MockBody::File { file }) => match tokio::fs::read(file).await {
+            Ok(contents) => {
+                let is_json = file.extension().is_some_and(|ext| ext == "json");
+                (contents, is_json.then_some("application/json"))
+            }
+            Err(e) => {
+                return error_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    &format!(
+                        "Failed to read mock response body '{}': {}",
+                        file.display(),
+                        e
+                    ),
+                );
             }
+        },
+    };
+
+    let mut headers = HeaderMap::new();
+    if let Some(content_type) = default_content_type {
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+    }
+    // Validated with the config, so invalid entries can't occur here
+    for (name, value) in &mock.headers {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            headers.insert(name, value);
         }
     }
+
+    let body = if *method == Method::HEAD {
+        Body::empty()
+    } else {
+        Body::from(body)
+    };
+    let mut response = Response::new(body);
+    *response.status_mut() = StatusCode::from_u16(mock.status).unwrap_or(StatusCode::OK);
+    *response.headers_mut() = headers;
+    response
 }
@@ -0,0 +1,193 @@
+use axum::http::{HeaderMap, HeaderName};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Once, Weak};
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+
+/// How often the background task tops up token buckets
+const REFILL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Token-bucket limit on the requests a route accepts
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Hash)]
+pub struct RateLimitConfig {
+    /// Tokens added to each bucket per second
+    pub requests_per_second: u32,
+    /// Bucket capacity: how many requests can arrive at once
+    pub burst: u32,
+    /// What requests are counted against
+    #[serde(default)]
+    pub key_source: RateLimitKeySource,
+    /// Header holding the API key, with `key_source: api_key_header`
+    #[serde(default = "default_api_key_header")]
+    pub api_key_header: String,
+}
+
+fn default_api_key_header() -> String {
+    "x-api-key".to_string()
+}
+
+impl RateLimitConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.requests_per_second == 0 {
+            return Err("rate_limit.requests_per_second must be greater than 0".to_string());
+        }
+        if self.burst == 0 {
+            return Err("rate_limit.burst must be greater than 0".to_string());
+        }
+        if HeaderName::from_bytes(self.api_key_header.as_bytes()).is_err() {
+            return Err(format!(
+                "rate_limit.api_key_header is not a valid header name: '{}'",
+                self.api_key_header
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Which requests share a token bucket
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitKeySource {
+    /// One bucket per client IP address
+    #[default]
+    Ip,
+    /// One bucket per value of `api_key_header`; requests without it are
+    /// limited by client IP
+    ApiKeyHeader,
+    /// One bucket for the whole route
+    Global,
+}
+
+struct TokenBucket {
+    tokens: f64,
+}
+
+/// Token buckets for one route, keyed by what `key_source` resolves to.
+/// Buckets are refilled by a background task rather than on each request;
+/// full buckets are dropped, so idle clients cost nothing.
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Arc<Mutex<HashMap<String, TokenBucket>>>,
+    refill_task: Once,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+            refill_task: Once::new(),
+        }
+    }
+
+    /// The bucket key for a request from `peer` with `headers`
+    pub fn key(&self, headers: &HeaderMap, peer: Option<SocketAddr>) -> String {
+        let ip_key = || match peer {
+            Some(addr) => format!("ip:{}", addr.ip()),
+            None => "ip:unknown".to_string(),
+        };
+        match self.config.key_source {
+            RateLimitKeySource::Global => "global".to_string(),
+            RateLimitKeySource::Ip => ip_key(),
+            RateLimitKeySource::ApiKeyHeader => headers
+                .get(self.config.api_key_header.as_str())
+                .and_then(|value| value.to_str().ok())
+                .map(|value| format!("key:{}", value))
+                .unwrap_or_else(ip_key),
+        }
+    }
+
+    /// `key` as it's safe to log: an API key is replaced by a short hash
+    /// of it, which still tells clients apart
+    pub fn loggable_key(key: &str) -> String {
+        match key.strip_prefix("key:") {
+            Some(api_key) => {
+                let digest = Sha256::digest(api_key.as_bytes());
+                format!("key:sha256:{}", hex::encode(&digest[..4]))
+            }
+            None => key.to_string(),
+        }
+    }
+
+    /// Take a token from `key`'s bucket. When it's empty, returns how long
+    /// until the next token arrives.
+    pub fn check(&self, key: &str) -> Result<(), Duration> {
+        self.refill_task
+            .call_once(|| spawn_refill(Arc::downgrade(&self.buckets), self.config.clone()));
+
+        let mut buckets = self.buckets.lock();
+        let bucket = buckets.entry(key.to_string()).or_insert(TokenBucket {
+            tokens: f64::from(self.config.burst),
+        });
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+        let missing = 1.0 - bucket.tokens;
+        Err(Duration::from_secs_f64(
+            missing / f64::from(self.config.requests_per_second),
+        ))
+    }
+}
+
+/// Top up every bucket each `REFILL_INTERVAL` until the limiter is dropped
+fn spawn_refill(buckets: Weak<Mutex<HashMap<String, TokenBucket>>>, config: RateLimitConfig) {
+    let rate = f64::from(config.requests_per_second);
+    let burst = f64::from(config.burst);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(REFILL_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        interval.tick().await;
+        let mut last = Instant::now();
+        loop {
+            interval.tick().await;
+            let Some(buckets) = buckets.upgrade() else {
+                break;
+            };
+            let now = Instant::now();
+            let added = rate * now.duration_since(last).as_secs_f64();
+            last = now;
+            buckets.lock().retain(|_, bucket| {
+                bucket.tokens += added;
+                bucket.tokens < burst
+            });
+        }
+    });
+}
+
+/// Rate limiters for every route with a `rate_limit`, built with the
+/// config. Bucket state starts over when the config is reloaded.
+#[derive(Default)]
+pub struct RateLimiters {
+    limiters: HashMap<(String, String), RateLimiter>,
+}
+
+impl RateLimiters {
+    pub fn from_config(config: &Config) -> Self {
+        let limiters = config
+            .all_routes()
+            .filter_map(|route| {
+                let limit = config.get_effective_config(route).rate_limit?;
+                Some((
+                    limiter_key(&route.method, route.pattern()),
+                    RateLimiter::new(limit),
+                ))
+            })
+            .collect();
+        Self { limiters }
+    }
+
+    /// The limiter for the route with `method` and `pattern`, if it has one
+    pub fn get(&self, method: &str, pattern: &str) -> Option<&RateLimiter> {
+        self.limiters.get(&limiter_key(method, pattern))
+    }
+}
+
+fn limiter_key(method: &str, pattern: &str) -> (String, String) {
+    (method.to_uppercase(), pattern.to_string())
+}
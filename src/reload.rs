@@ -0,0 +1,104 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+
+use crate::config::Config;
+use crate::handler::AppState;
+
+/// How often `--watch` checks the config file for changes
+pub const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Reload the config file into `state`.
+///
+/// The new config is parsed and validated before anything is swapped in, so a
+/// broken file leaves the running config untouched. Schema caches are reset
+/// because schema files may have changed alongside the config (see
+/// `AppState::replace_config`).
+/// Returns the number of routes in the new config.
+pub async fn reload_config(
+    state: &Arc<RwLock<AppState>>,
+    config_path: &Path,
+) -> Result<usize, String> {
+    let result = Config::from_file(config_path).and_then(|config| {
+        config.validate()?;
+        Ok(config)
+    });
+
+    // Only a read lock is needed: config and caches are swapped through
+    // their own handles, so in-flight requests are never blocked.
+    let state_guard = state.read().await;
+    let config = match result {
+        Ok(config) => config,
+        Err(e) => {
+            state_guard.metrics.config_reload_failures_total.inc();
+            return Err(e);
+        }
+    };
+
+    let route_count = config.routes.len();
+    if let Err(e) = state_guard.replace_config(config) {
+        state_guard.metrics.config_reload_failures_total.inc();
+        return Err(e);
+    }
+    state_guard.metrics.config_reloads_total.inc();
+
+    Ok(route_count)
+}
+
+/// Poll the config file's mtime every `interval` and reload when it changes.
+///
+/// This is a fallback for environments where signal delivery is unreliable.
+/// The task only holds the state lock while swapping in a new config.
+pub fn spawn_config_watcher(
+    state: Arc<RwLock<AppState>>,
+    config_path: PathBuf,
+    interval: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut last_modified = modified_time(&config_path).await;
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+
+            let modified = modified_time(&config_path).await;
+            if modified.is_none() || modified == last_modified {
+                continue;
+            }
+
+            let delta = match (last_modified, modified) {
+                (Some(previous), Some(current)) => current.duration_since(previous).ok(),
+                _ => None,
+            };
+            last_modified = modified;
+
+            match reload_config(&state, &config_path).await {
+                Ok(route_count) => {
+                    tracing::info!(
+                        config = %config_path.display(),
+                        mtime_delta_ms = delta.map(|d| d.as_millis() as u64),
+                        routes = route_count,
+                        "Reloaded config after file change"
+                    );
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        config = %config_path.display(),
+                        error = %e,
+                        "Config changed but reload failed; keeping previous config"
+                    );
+                }
+            }
+        }
+    })
+}
+
+async fn modified_time(path: &Path) -> Option<SystemTime> {
+    tokio::fs::metadata(path)
+        .await
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
@@ -0,0 +1,126 @@
+use axum::body::Body;
+use axum::extract::{ConnectInfo, State};
+use axum::http::{header, HeaderMap, Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{any, get};
+use axum::Extension;
+use axum::Router;
+use hyper::upgrade::OnUpgrade;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::config::GlobalConfig;
+use crate::handler::{handle_upgradable_request, AppState};
+use crate::health;
+
+/// Build the gateway router: built-in endpoints first, then the catch-all
+/// proxy handler.
+///
+/// The metrics route is registered from `global` once, so changing
+/// `metrics_enabled` or `metrics_path` requires a restart. The token is
+/// checked against the live config on every scrape. The same goes for the
+/// health probes' `liveness_check_timeout_ms`.
+pub fn build_router(state: Arc<RwLock<AppState>>, global: &GlobalConfig) -> Router {
+    let mut router = Router::new();
+
+    if global.metrics_enabled {
+        router = router.route(&global.metrics_path, get(metrics_handler));
+    }
+
+    let probe_timeout = global.liveness_check_timeout();
+    router
+        .route("/health", get(health::health))
+        .route(
+            "/health/ready",
+            get(move |state| health::readiness(state, probe_timeout)),
+        )
+        .route(
+            "/health/live",
+            get(move |state| health::liveness(state, probe_timeout)),
+        )
+        .route("/*path", any(handler))
+        .with_state(state)
+}
+
+/// The client address is only known when the router is served with
+/// `into_make_service_with_connect_info`. HTTP/1.1 connections carry a
+/// pending upgrade for requests asking to switch protocols.
+async fn handler(
+    State(state): State<Arc<RwLock<AppState>>>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    upgrade: Option<Extension<OnUpgrade>>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Body,
+) -> Response {
+    let peer = connect_info.map(|ConnectInfo(addr)| addr);
+    let upgrade = upgrade.map(|Extension(upgrade)| upgrade);
+    handle_upgradable_request(State(state), peer, upgrade, method, uri, headers, body).await
+}
+
+async fn metrics_handler(
+    State(state): State<Arc<RwLock<AppState>>>,
+    headers: HeaderMap,
+) -> Response {
+    let state_guard = state.read().await;
+
+    if let Some(token) = &state_guard.routing().config.global.metrics_token {
+        if !has_bearer_token(&headers, token) {
+            return (
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, "Bearer")],
+                "Unauthorized",
+            )
+                .into_response();
+        }
+    }
+
+    match state_guard.metrics.gather() {
+        Ok(output) => {
+            match Response::builder()
+                .status(StatusCode::OK)
+                .header("Content-Type", "text/plain; version=0.0.4")
+                .body(Body::from(output))
+            {
+                Ok(response) => response,
+                Err(e) => {
+                    tracing::error!("Failed to build metrics response: {}", e);
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Failed to build response",
+                    )
+                        .into_response()
+                }
+            }
+        }
+        Err(e) => {
+            tracing::error!("Failed to gather metrics: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Error gathering metrics: {}", e),
+            )
+                .into_response()
+        }
+    }
+}
+
+pub(crate) fn has_bearer_token(headers: &HeaderMap, expected: &str) -> bool {
+    let Some(provided) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+
+    // Compare in constant time so the token can't be guessed byte by byte
+    let (provided, expected) = (provided.trim().as_bytes(), expected.as_bytes());
+    provided.len() == expected.len()
+        && provided
+            .iter()
+            .zip(expected)
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
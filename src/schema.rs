@@ -1,55 +1,412 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime};
 
 use crate::error::{Error, Result};
-use jsonschema::JSONSchema;
-use serde_json::Value;
+use jsonschema::{Draft, JSONSchema};
+use lru::LruCache;
+use once_cell::sync::OnceCell;
+use serde_json::{Map, Value};
 
+/// Cache slot for a single schema path. Concurrent misses on the same path
+/// wait on the cell so the schema is only compiled once.
+type SchemaSlot = Arc<OnceCell<CachedSchema>>;
+
+struct CachedSchema {
+    schema: Arc<JSONSchema>,
+    loaded_at: Instant,
+}
+
+/// JSON Schema draft to compile a schema against, whatever its `$schema`
+/// declares
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SchemaDraft {
+    Draft4,
+    Draft6,
+    Draft7,
+    Draft2019_09,
+    Draft2020_12,
+}
+
+impl SchemaDraft {
+    /// Config names, in draft order
+    pub const NAMES: [&'static str; 5] =
+        ["draft4", "draft6", "draft7", "draft2019_09", "draft2020_12"];
+
+    /// Parse a config name such as `draft7`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "draft4" => Some(Self::Draft4),
+            "draft6" => Some(Self::Draft6),
+            "draft7" => Some(Self::Draft7),
+            "draft2019_09" => Some(Self::Draft2019_09),
+            "draft2020_12" => Some(Self::Draft2020_12),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Draft4 => "draft4",
+            Self::Draft6 => "draft6",
+            Self::Draft7 => "draft7",
+            Self::Draft2019_09 => "draft2019_09",
+            Self::Draft2020_12 => "draft2020_12",
+        }
+    }
+
+    fn to_jsonschema(self) -> Draft {
+        match self {
+            Self::Draft4 => Draft::Draft4,
+            Self::Draft6 => Draft::Draft6,
+            Self::Draft7 => Draft::Draft7,
+            Self::Draft2019_09 => Draft::Draft201909,
+            Self::Draft2020_12 => Draft::Draft202012,
+        }
+    }
+}
+
+/// How a schema is compiled. Each combination is compiled and cached
+/// separately from the schema as written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SchemaOptions {
+    /// Force `additionalProperties: false` on (see [`forbid_additional_properties`])
+    pub strict_body: bool,
+    /// Compile against this draft instead of the one `$schema` names
+    pub draft: Option<SchemaDraft>,
+}
+
+impl SchemaOptions {
+    const STRICT: Self = Self {
+        strict_body: true,
+        draft: None,
+    };
+}
+
+/// Cache key: a path and the options it was compiled with
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct SchemaKey {
+    path: PathBuf,
+    options: SchemaOptions,
+}
+
+/// A compiled schema as reported by `GET /admin/cache`
+#[derive(Debug, Clone)]
+pub struct SchemaCacheEntry {
+    pub path: PathBuf,
+    /// Compiled with `additionalProperties: false` forced on (`strict_body`)
+    pub strict_body: bool,
+    /// Draft forced by the route's `schema_draft`, if any
+    pub draft: Option<SchemaDraft>,
+    pub loaded_at: Instant,
+    /// False once the file on disk has changed (or vanished) since loading
+    pub current: bool,
+}
+
+/// Outcome of [`SchemaCache::preload`]
+#[derive(Debug, Default)]
+pub struct PreloadReport {
+    /// Schemas compiled and added to the cache
+    pub loaded: usize,
+    /// Schemas that couldn't be read or compiled
+    pub failed: Vec<(PathBuf, Error)>,
+}
+
+/// Parsed bundle document: schema name to raw schema
+type Bundle = Arc<Map<String, Value>>;
+
+/// Compiles and caches JSON Schemas by path.
+///
+/// A path of the form `bundle.yml#name` refers to the entry `name` in a
+/// bundle file mapping schema names to schemas. The bundle is parsed once
+/// and each named entry is compiled and cached on its own.
+///
+/// Every method takes `&self`, so callers only ever need shared access. A
+/// hit holds the map lock just long enough to clone the entry's `Arc` (a
+/// `Mutex` rather than an `RwLock`, since a hit also updates the LRU
+/// order), and a miss compiles outside it.
 pub struct SchemaCache {
-    pub cache: HashMap<PathBuf, Arc<JSONSchema>>,
+    cache: Mutex<LruCache<SchemaKey, SchemaSlot>>,
+    bundles: Mutex<HashMap<PathBuf, Bundle>>,
+    failures: NegativeCache,
 }
 
 impl SchemaCache {
     pub fn new() -> Self {
+        Self::with_max_entries(None)
+    }
+
+    /// Create a cache holding at most `max_entries` compiled schemas,
+    /// evicting the least recently used one when full. `None` means unbounded.
+    pub fn with_max_entries(max_entries: Option<usize>) -> Self {
+        let cache = match max_entries.and_then(NonZeroUsize::new) {
+            Some(cap) => LruCache::new(cap),
+            None => LruCache::unbounded(),
+        };
         Self {
-            cache: HashMap::new(),
+            cache: Mutex::new(cache),
+            bundles: Mutex::new(HashMap::new()),
+            failures: NegativeCache::default(),
         }
     }
 
-    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> Result<Arc<JSONSchema>> {
-        let path_ref = path.as_ref();
-        let path_buf = PathBuf::from(path_ref);
+    /// Remember load failures for `ttl`, replaying them as `Error::Cached`
+    /// instead of re-reading the file. A zero `ttl` disables negative caching.
+    pub fn with_negative_ttl(mut self, ttl: Duration) -> Self {
+        self.failures = NegativeCache::new(ttl);
+        self
+    }
+
+    /// Whether a compiled schema for `path` is cached (does not affect recency)
+    pub fn contains<P: AsRef<Path>>(&self, path: P) -> bool {
+        self.contains_with(path, SchemaOptions::default())
+    }
+
+    /// Like `contains`, for the variant `load_strict` compiles
+    pub fn contains_strict<P: AsRef<Path>>(&self, path: P) -> bool {
+        self.contains_with(path, SchemaOptions::STRICT)
+    }
+
+    /// Like `contains`, for the variant `load_with` compiles
+    pub fn contains_with<P: AsRef<Path>>(&self, path: P, options: SchemaOptions) -> bool {
+        let key = SchemaKey {
+            path: path.as_ref().to_path_buf(),
+            options,
+        };
+        self.entries()
+            .peek(&key)
+            .is_some_and(|slot| slot.get().is_some())
+    }
+
+    /// Number of compiled schemas currently cached
+    pub fn len(&self) -> usize {
+        self.entries()
+            .iter()
+            .filter(|(_, slot)| slot.get().is_some())
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Every compiled schema, sorted by path (does not affect recency)
+    pub fn snapshot(&self) -> Vec<SchemaCacheEntry> {
+        let mut loaded: Vec<(SchemaKey, Instant)> = self
+            .entries()
+            .iter()
+            .filter_map(|(key, slot)| slot.get().map(|cached| (key.clone(), cached.loaded_at)))
+            .collect();
+        loaded.sort();
+
+        // Stat the files after releasing the lock
+        loaded
+            .into_iter()
+            .map(|(key, loaded_at)| {
+                let path = key.path;
+                let file = split_bundle_ref(&path).map_or(path.as_path(), |(file, _)| file);
+                let current = unchanged_since(file, loaded_at);
+                SchemaCacheEntry {
+                    path,
+                    strict_body: key.options.strict_body,
+                    draft: key.options.draft,
+                    loaded_at,
+                    current,
+                }
+            })
+            .collect()
+    }
 
-        if let Some(schema) = self.cache.get(&path_buf) {
-            return Ok(Arc::clone(schema));
+    pub fn load<P: AsRef<Path>>(&self, path: P) -> Result<Arc<JSONSchema>> {
+        self.load_with(path, SchemaOptions::default())
+    }
+
+    /// Load the schema with `additionalProperties: false` added to every
+    /// object schema that doesn't set it (see [`forbid_additional_properties`]).
+    /// Cached separately from the schema as written.
+    pub fn load_strict<P: AsRef<Path>>(&self, path: P) -> Result<Arc<JSONSchema>> {
+        self.load_with(path, SchemaOptions::STRICT)
+    }
+
+    /// Load the schema compiled with `options`, cached separately from
+    /// other variants of the same file
+    pub fn load_with<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: SchemaOptions,
+    ) -> Result<Arc<JSONSchema>> {
+        let path_buf = path.as_ref().to_path_buf();
+        let key = SchemaKey {
+            path: path_buf.clone(),
+            options,
+        };
+
+        if let Some(error) = self.failures.get(&path_buf) {
+            return Err(error);
         }
 
-        let contents = match fs::read_to_string(&path_buf) {
-            Ok(s) => s,
+        let slot = {
+            let mut entries = self.entries();
+            if let Some(slot) = entries.get(&key) {
+                Arc::clone(slot)
+            } else {
+                let slot = SchemaSlot::default();
+                if let Some((evicted, _)) = entries.push(key.clone(), Arc::clone(&slot)) {
+                    tracing::debug!(schema_path = %evicted.path.display(), "Evicted schema from cache");
+                }
+                slot
+            }
+        };
+
+        // The map lock is released here; only callers racing on this path wait.
+        let result = slot
+            .get_or_try_init(|| {
+                self.compile(&path_buf, options).map(|schema| CachedSchema {
+                    schema: Arc::new(schema),
+                    loaded_at: Instant::now(),
+                })
+            })
+            .map(|cached| Arc::clone(&cached.schema));
+
+        match &result {
+            Ok(_) => self.failures.clear(&path_buf),
             Err(e) => {
-                if e.kind() == std::io::ErrorKind::NotFound {
-                    return Err(Error::SchemaNotFound { path: path_buf });
+                self.failures.record(&path_buf, e);
+                // Don't keep an empty slot around for a path that failed to load
+                let mut entries = self.entries();
+                if entries
+                    .peek(&key)
+                    .is_some_and(|current| Arc::ptr_eq(current, &slot))
+                {
+                    entries.pop(&key);
                 }
-                return Err(Error::Io(e));
             }
+        }
+
+        result
+    }
+
+    /// Compile the schemas at `paths` ahead of their first request, on
+    /// blocking threads in parallel. Schemas already cached are skipped,
+    /// and failures are remembered like failed `load`s.
+    pub async fn preload(&self, paths: &[PathBuf]) -> PreloadReport {
+        self.preload_with(paths, SchemaOptions::default()).await
+    }
+
+    /// Like `preload`, for the variants `load_strict` compiles
+    pub async fn preload_strict(&self, paths: &[PathBuf]) -> PreloadReport {
+        self.preload_with(paths, SchemaOptions::STRICT).await
+    }
+
+    /// Like `preload`, for the variants `load_with` compiles
+    pub async fn preload_with(&self, paths: &[PathBuf], options: SchemaOptions) -> PreloadReport {
+        let mut report = PreloadReport::default();
+        let mut seen = HashSet::new();
+        let mut tasks = Vec::new();
+        for path in paths {
+            if !seen.insert(path) || self.contains_with(path, options) {
+                continue;
+            }
+            // Reading is quick; compiling is the CPU-bound part
+            match self.read(path) {
+                Ok(schema) => {
+                    let task_path = path.clone();
+                    let task = tokio::task::spawn_blocking(move || {
+                        compile_value(schema, &task_path, options)
+                    });
+                    tasks.push((path.clone(), task));
+                }
+                Err(e) => {
+                    self.failures.record(path, &e);
+                    report.failed.push((path.clone(), e));
+                }
+            }
+        }
+
+        for (path, task) in tasks {
+            let result = task.await.unwrap_or_else(|e| {
+                Err(Error::InvalidSchemaSyntax {
+                    path: path.clone(),
+                    message: format!("compilation did not finish: {}", e),
+                })
+            });
+            match result {
+                Ok(schema) => {
+                    self.insert(&path, options, schema);
+                    report.loaded += 1;
+                }
+                Err(e) => {
+                    self.failures.record(&path, &e);
+                    report.failed.push((path, e));
+                }
+            }
+        }
+        report
+    }
+
+    fn insert(&self, path: &Path, options: SchemaOptions, schema: JSONSchema) {
+        let slot = SchemaSlot::default();
+        let _ = slot.set(CachedSchema {
+            schema: Arc::new(schema),
+            loaded_at: Instant::now(),
+        });
+        let key = SchemaKey {
+            path: path.to_path_buf(),
+            options,
         };
+        if let Some((evicted, _)) = self.entries().push(key, slot) {
+            if evicted.path != path || evicted.options != options {
+                tracing::debug!(schema_path = %evicted.path.display(), "Evicted schema from cache");
+            }
+        }
+        self.failures.clear(path);
+    }
 
-        let value: Value =
-            serde_json::from_str(&contents).map_err(|e| Error::InvalidSchemaJson {
-                path: path_buf.clone(),
-                source: e,
-            })?;
+    fn compile(&self, path: &Path, options: SchemaOptions) -> Result<JSONSchema> {
+        compile_value(self.read(path)?, path, options)
+    }
 
-        let compiled = JSONSchema::compile(&value).map_err(|e| Error::InvalidSchemaSyntax {
-            path: path_buf.clone(),
-            message: e.to_string(),
-        })?;
+    /// The schema document at `path`, or the bundle entry it names
+    fn read(&self, path: &Path) -> Result<Value> {
+        match split_bundle_ref(path) {
+            Some((bundle_path, name)) => {
+                let bundle = self.load_bundle(bundle_path)?;
+                bundle.get(name).cloned().ok_or_else(|| {
+                    let mut available: Vec<String> = bundle.keys().cloned().collect();
+                    available.sort();
+                    Error::SchemaNotInBundle {
+                        path: bundle_path.to_path_buf(),
+                        name: name.to_string(),
+                        available,
+                    }
+                })
+            }
+            None => read_schema(path),
+        }
+    }
 
-        let arc = Arc::new(compiled);
-        self.cache.insert(path_buf, Arc::clone(&arc));
-        Ok(arc)
+    fn load_bundle(&self, path: &Path) -> Result<Bundle> {
+        let mut bundles = self
+            .bundles
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(bundle) = bundles.get(path) {
+            return Ok(Arc::clone(bundle));
+        }
+        let bundle = Arc::new(parse_bundle(path)?);
+        bundles.insert(path.to_path_buf(), Arc::clone(&bundle));
+        Ok(bundle)
+    }
+
+    fn entries(&self) -> MutexGuard<'_, LruCache<SchemaKey, SchemaSlot>> {
+        // A panic while holding the lock can't leave the LRU half-updated in a
+        // way that matters to us, so recover from poisoning.
+        self.cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
     }
 }
 
@@ -58,3 +415,265 @@ impl Default for SchemaCache {
         Self::new()
     }
 }
+
+/// Recent load failures keyed by path, so a missing or broken file isn't
+/// re-read on every request.
+#[derive(Default)]
+pub(crate) struct NegativeCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<PathBuf, NegativeEntry>>,
+}
+
+struct NegativeEntry {
+    message: String,
+    expires_at: Instant,
+}
+
+impl NegativeCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The cached failure for `path`, if it hasn't expired yet
+    pub(crate) fn get(&self, path: &Path) -> Option<Error> {
+        if self.ttl.is_zero() {
+            return None;
+        }
+        let mut entries = self.lock();
+        match entries.get(path) {
+            Some(entry) if entry.expires_at > Instant::now() => Some(Error::Cached {
+                path: path.to_path_buf(),
+                message: entry.message.clone(),
+            }),
+            Some(_) => {
+                entries.remove(path);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub(crate) fn record(&self, path: &Path, error: &Error) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        self.lock().insert(
+            path.to_path_buf(),
+            NegativeEntry {
+                message: error.to_string(),
+                expires_at: Instant::now() + self.ttl,
+            },
+        );
+    }
+
+    pub(crate) fn clear(&self, path: &Path) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        self.lock().remove(path);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<PathBuf, NegativeEntry>> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Whether the file at `path` hasn't been modified since `loaded_at`. A
+/// missing or unreadable file counts as changed.
+pub(crate) fn unchanged_since(path: &Path, loaded_at: Instant) -> bool {
+    let Some(loaded) = SystemTime::now().checked_sub(loaded_at.elapsed()) else {
+        return false;
+    };
+    fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .is_ok_and(|modified| modified <= loaded)
+}
+
+/// Split `bundle.yml#name` into the bundle path and entry name
+fn split_bundle_ref(path: &Path) -> Option<(&Path, &str)> {
+    let (file, name) = path.to_str()?.rsplit_once('#')?;
+    if file.is_empty() || name.is_empty() || name.contains('/') {
+        return None;
+    }
+    Some((Path::new(file), name))
+}
+
+fn parse_bundle(path: &Path) -> Result<Map<String, Value>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(e) => {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                return Err(Error::SchemaNotFound {
+                    path: path.to_path_buf(),
+                });
+            }
+            return Err(Error::Io(e));
+        }
+    };
+
+    // YAML is a superset of JSON, so this handles both bundle formats
+    let value: Value = serde_yaml::from_str(&contents).map_err(|e| Error::InvalidSchemaBundle {
+        path: path.to_path_buf(),
+        message: e.to_string(),
+    })?;
+
+    match value {
+        Value::Object(entries) => Ok(entries),
+        _ => Err(Error::InvalidSchemaBundle {
+            path: path.to_path_buf(),
+            message: "expected a mapping of schema names to schemas".to_string(),
+        }),
+    }
+}
+
+fn compile_value(mut schema: Value, path: &Path, options: SchemaOptions) -> Result<JSONSchema> {
+    if options.strict_body {
+        forbid_additional_properties(&mut schema, path);
+    }
+    let mut compile_options = JSONSchema::options();
+    if let Some(draft) = options.draft {
+        compile_options.with_draft(draft.to_jsonschema());
+    }
+    compile_options
+        .compile(&schema)
+        .map_err(|e| Error::InvalidSchemaSyntax {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })
+}
+
+fn read_schema(path: &Path) -> Result<Value> {
+    let contents = match fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(e) => {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                return Err(Error::SchemaNotFound {
+                    path: path.to_path_buf(),
+                });
+            }
+            return Err(Error::Io(e));
+        }
+    };
+
+    serde_json::from_str(&contents).map_err(|e| Error::InvalidSchemaJson {
+        path: path.to_path_buf(),
+        source: e,
+    })
+}
+
+const COMPOSITION_KEYWORDS: [&str; 3] = ["allOf", "anyOf", "oneOf"];
+
+/// Add `additionalProperties: false` to every object schema in `schema`
+/// that doesn't say whether extra properties are allowed, so unknown fields
+/// are rejected.
+///
+/// Schemas combined with `allOf`, `anyOf` or `oneOf` are left open, with a
+/// warning: closing each branch would reject the properties the other
+/// branches declare. So are definitions those branches `$ref`.
+pub fn forbid_additional_properties(schema: &mut Value, source: &Path) {
+    let mut composed_refs = HashSet::new();
+    collect_composed_refs(schema, false, &mut composed_refs);
+    close_objects(schema, "#", &composed_refs, source);
+}
+
+/// `$ref`s that appear somewhere inside a composition keyword
+fn collect_composed_refs(value: &Value, in_composition: bool, refs: &mut HashSet<String>) {
+    match value {
+        Value::Object(map) => {
+            if in_composition {
+                if let Some(Value::String(reference)) = map.get("$ref") {
+                    refs.insert(reference.clone());
+                }
+            }
+            for (key, child) in map {
+                let inside = in_composition || COMPOSITION_KEYWORDS.contains(&key.as_str());
+                collect_composed_refs(child, inside, refs);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_composed_refs(item, in_composition, refs);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn close_objects(value: &mut Value, pointer: &str, composed_refs: &HashSet<String>, source: &Path) {
+    let Value::Object(map) = value else {
+        return;
+    };
+    if COMPOSITION_KEYWORDS
+        .iter()
+        .any(|keyword| map.contains_key(*keyword))
+    {
+        tracing::warn!(
+            schema = %source.display(),
+            pointer = %pointer,
+            "strict_body leaves schemas using allOf/anyOf/oneOf open"
+        );
+        return;
+    }
+
+    let is_object = map.contains_key("properties")
+        || match map.get("type") {
+            Some(Value::String(kind)) => kind == "object",
+            Some(Value::Array(kinds)) => kinds.iter().any(|kind| kind == "object"),
+            _ => false,
+        };
+    if is_object
+        && !map.contains_key("additionalProperties")
+        && !map.contains_key("unevaluatedProperties")
+    {
+        map.insert("additionalProperties".to_string(), Value::Bool(false));
+    }
+
+    for keyword in ["properties", "patternProperties", "definitions", "$defs"] {
+        let Some(Value::Object(children)) = map.get_mut(keyword) else {
+            continue;
+        };
+        for (name, child) in children.iter_mut() {
+            let child_pointer = format!(
+                "{}/{}/{}",
+                pointer,
+                keyword,
+                name.replace('~', "~0").replace('/', "~1")
+            );
+            if composed_refs.contains(&child_pointer) {
+                tracing::warn!(
+                    schema = %source.display(),
+                    pointer = %child_pointer,
+                    "strict_body leaves definitions used by allOf/anyOf/oneOf open"
+                );
+                continue;
+            }
+            close_objects(child, &child_pointer, composed_refs, source);
+        }
+    }
+
+    for keyword in [
+        "items",
+        "prefixItems",
+        "additionalItems",
+        "additionalProperties",
+    ] {
+        match map.get_mut(keyword) {
+            Some(Value::Array(items)) => {
+                for (index, item) in items.iter_mut().enumerate() {
+                    let item_pointer = format!("{}/{}/{}", pointer, keyword, index);
+                    close_objects(item, &item_pointer, composed_refs, source);
+                }
+            }
+            Some(child) => {
+                let child_pointer = format!("{}/{}", pointer, keyword);
+                close_objects(child, &child_pointer, composed_refs, source);
+            }
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use rustls::crypto::ring::default_provider;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use sha2::{Digest, Sha256};
+use upstream_rustls::client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier};
+
+use crate::config::UpstreamTlsConfig;
+
+/// Build the TLS config for the HTTPS listener.
+///
+/// `cert` is a PEM certificate chain (leaf first) and `key` its PEM private
+/// key. When `client_ca` is given, clients must present a certificate signed
+/// by one of the CAs in that PEM bundle (mutual TLS).
+pub fn load_server_config(
+    cert: &Path,
+    key: &Path,
+    client_ca: Option<&Path>,
+) -> Result<ServerConfig, String> {
+    let provider = Arc::new(default_provider());
+    let cert_chain = load_certs(cert)?;
+    let private_key = load_private_key(key)?;
+
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("Failed to configure TLS: {}", e))?;
+
+    let builder = match client_ca {
+        Some(ca_path) => {
+            let mut roots = RootCertStore::empty();
+            for ca in load_certs(ca_path)? {
+                roots.add(ca).map_err(|e| {
+                    format!("Invalid CA certificate in '{}': {}", ca_path.display(), e)
+                })?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                .build()
+                .map_err(|e| {
+                    format!(
+                        "Failed to use '{}' for client verification: {}",
+                        ca_path.display(),
+                        e
+                    )
+                })?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let mut config = builder
+        .with_single_cert(cert_chain, private_key)
+        .map_err(|e| {
+            format!(
+                "TLS certificate '{}' and key '{}' don't form a valid pair: {}",
+                cert.display(),
+                key.display(),
+                e
+            )
+        })?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    Ok(config)
+}
+
+pub(crate) fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, String> {
+    let file = File::open(path)
+        .map_err(|e| format!("Failed to read certificate '{}': {}", path.display(), e))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Invalid PEM in certificate '{}': {}", path.display(), e))?;
+
+    if certs.is_empty() {
+        return Err(format!("No certificates found in '{}'", path.display()));
+    }
+    Ok(certs)
+}
+
+pub(crate) fn load_private_key(path: &Path) -> Result<PrivateKeyDer<'static>, String> {
+    let file = File::open(path)
+        .map_err(|e| format!("Failed to read private key '{}': {}", path.display(), e))?;
+    rustls_pemfile::private_key(&mut BufReader::new(file))
+        .map_err(|e| format!("Invalid PEM in private key '{}': {}", path.display(), e))?
+        .ok_or_else(|| format!("No private key found in '{}'", path.display()))
+}
+
+/// The rustls settings for `tls`, for `ClientBuilder::use_preconfigured_tls`
+pub(crate) fn upstream_tls_config(
+    tls: &UpstreamTlsConfig,
+) -> Result<upstream_rustls::ClientConfig, String> {
+    let inner = if tls.verify {
+        let mut roots = upstream_rustls::RootCertStore::empty();
+        match &tls.ca_bundle {
+            Some(ca_path) => {
+                for cert in load_certs(ca_path)? {
+                    roots
+                        .add(&upstream_rustls::Certificate(cert.to_vec()))
+                        .map_err(|e| {
+                            format!("Invalid CA certificate in '{}': {}", ca_path.display(), e)
+                        })?;
+                }
+            }
+            None => {
+                let native = rustls_native_certs::load_native_certs()
+                    .map_err(|e| format!("Failed to load system root certificates: {}", e))?;
+                roots.add_parsable_certificates(&native);
+            }
+        }
+        Some(WebPkiVerifier::new(roots, None))
+    } else {
+        None
+    };
+
+    let verifier = Arc::new(UpstreamCertVerifier {
+        inner,
+        pin: tls.pin_bytes(),
+    });
+    let builder = upstream_rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(verifier);
+
+    let config = match (&tls.client_cert, &tls.client_key) {
+        (Some(cert), Some(key)) => {
+            let chain = load_certs(cert)?
+                .into_iter()
+                .map(|c| upstream_rustls::Certificate(c.to_vec()))
+                .collect();
+            let private_key =
+                upstream_rustls::PrivateKey(load_private_key(key)?.secret_der().to_vec());
+            builder
+                .with_client_auth_cert(chain, private_key)
+                .map_err(|e| {
+                    format!(
+                        "Upstream client certificate '{}' and key '{}' don't form a valid pair: {}",
+                        cert.display(),
+                        key.display(),
+                        e
+                    )
+                })?
+        }
+        _ => builder.with_no_client_auth(),
+    };
+    Ok(config)
+}
+
+/// Verifies upstream certificates with webpki (unless verification is
+/// disabled) and then checks the leaf against the configured pin.
+struct UpstreamCertVerifier {
+    inner: Option<WebPkiVerifier>,
+    pin: Option<[u8; 32]>,
+}
+
+impl ServerCertVerifier for UpstreamCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &upstream_rustls::Certificate,
+        intermediates: &[upstream_rustls::Certificate],
+        server_name: &upstream_rustls::ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, upstream_rustls::Error> {
+        if let Some(inner) = &self.inner {
+            inner.verify_server_cert(
+                end_entity,
+                intermediates,
+                server_name,
+                scts,
+                ocsp_response,
+                now,
+            )?;
+        }
+
+        if let Some(pin) = &self.pin {
+            let digest = Sha256::digest(&end_entity.0);
+            if digest.as_slice() != pin {
+                return Err(upstream_rustls::Error::General(format!(
+                    "upstream certificate SHA-256 {} does not match pinned hash",
+                    hex::encode(digest)
+                )));
+            }
+        }
+
+        Ok(ServerCertVerified::assertion())
+    }
+}
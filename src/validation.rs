@@ -1,36 +1,263 @@
+use jsonschema::error::ValidationErrorKind as SchemaErrorKind;
 use jsonschema::JSONSchema;
-use serde_json::Value;
+use serde_json::{json, Value};
+use std::fmt;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ValidationResult {
     pub valid: bool,
-    pub errors: Vec<String>,
+    pub errors: Vec<ValidationError>,
+    /// `errors` with the failing schema keyword's path and the offending
+    /// value appended, for `--dev`
+    pub details: Vec<String>,
+    /// How many errors were found, including any left out of `errors` by
+    /// `validate_with_limit`
+    pub error_count: usize,
+}
+
+impl ValidationResult {
+    /// Each error as a message, prefixed by its instance path when there is
+    /// one, and a final `… and N more errors` when the list was cut short
+    pub fn messages(&self) -> Vec<String> {
+        let mut messages: Vec<String> = self.errors.iter().map(ToString::to_string).collect();
+        messages.extend(self.truncation_note());
+        messages
+    }
+
+    /// `… and N more errors`, if `errors` doesn't hold all of them
+    fn truncation_note(&self) -> Option<String> {
+        let omitted = self.error_count.saturating_sub(self.errors.len());
+        (omitted > 0).then(|| format!("… and {} more errors", omitted))
+    }
+}
+
+/// One failure from validating a document against a schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /// JSON pointer to the failing value; empty for the document itself
+    pub instance_path: String,
+    /// JSON pointer to the schema keyword that failed
+    pub schema_path: String,
+    pub kind: ValidationErrorKind,
+    /// The failure, without the instance path
+    pub message: String,
+}
+
+impl ValidationError {
+    /// An error that didn't come from a schema keyword, such as a
+    /// parameter that couldn't be parsed
+    pub fn new(kind: ValidationErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            instance_path: String::new(),
+            schema_path: String::new(),
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.instance_path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.instance_path, self.message)
+        }
+    }
+}
+
+/// The schema keyword behind a [`ValidationError`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationErrorKind {
+    Required,
+    Type,
+    Format,
+    MinLength,
+    MaxLength,
+    Pattern,
+    Enum,
+    /// A property `additionalProperties` doesn't allow
+    Additional,
+    /// Any other keyword, by its metric label (e.g. `minimum`, `one_of`)
+    Other(String),
+}
+
+impl ValidationErrorKind {
+    /// The `error_kind` metric label
+    pub fn label(&self) -> &str {
+        match self {
+            Self::Required => "required",
+            Self::Type => "type",
+            Self::Format => "format",
+            Self::MinLength => "min_length",
+            Self::MaxLength => "max_length",
+            Self::Pattern => "pattern",
+            Self::Enum => "enum",
+            Self::Additional => "additional_properties",
+            Self::Other(label) => label,
+        }
+    }
+
+    fn from_schema_error(kind: &SchemaErrorKind) -> Self {
+        let label = match kind {
+            SchemaErrorKind::Required { .. } => return Self::Required,
+            SchemaErrorKind::Type { .. } => return Self::Type,
+            SchemaErrorKind::Format { .. } => return Self::Format,
+            SchemaErrorKind::MinLength { .. } => return Self::MinLength,
+            SchemaErrorKind::MaxLength { .. } => return Self::MaxLength,
+            SchemaErrorKind::Pattern { .. } => return Self::Pattern,
+            SchemaErrorKind::Enum { .. } => return Self::Enum,
+            SchemaErrorKind::AdditionalProperties { .. } => return Self::Additional,
+            SchemaErrorKind::AdditionalItems { .. } => "additional_items",
+            SchemaErrorKind::UnevaluatedProperties { .. } => "unevaluated_properties",
+            SchemaErrorKind::AnyOf => "any_of",
+            SchemaErrorKind::OneOfMultipleValid | SchemaErrorKind::OneOfNotValid => "one_of",
+            SchemaErrorKind::Not { .. } => "not",
+            SchemaErrorKind::Constant { .. } => "const",
+            SchemaErrorKind::Contains => "contains",
+            SchemaErrorKind::UniqueItems => "unique_items",
+            SchemaErrorKind::Minimum { .. } | SchemaErrorKind::ExclusiveMinimum { .. } => "minimum",
+            SchemaErrorKind::Maximum { .. } | SchemaErrorKind::ExclusiveMaximum { .. } => "maximum",
+            SchemaErrorKind::MultipleOf { .. } => "multiple_of",
+            SchemaErrorKind::MinItems { .. } => "min_items",
+            SchemaErrorKind::MaxItems { .. } => "max_items",
+            SchemaErrorKind::MinProperties { .. } => "min_properties",
+            SchemaErrorKind::MaxProperties { .. } => "max_properties",
+            SchemaErrorKind::PropertyNames { .. } => "property_names",
+            SchemaErrorKind::FalseSchema => "false_schema",
+            _ => "other",
+        };
+        Self::Other(label.to_string())
+    }
 }
 
 pub fn validate(schema: &JSONSchema, json: &Value) -> ValidationResult {
+    validate_with_limit(schema, json, None)
+}
+
+/// `validate`, keeping at most `limit` errors. The rest are only counted in
+/// `error_count`, so a document with thousands of bad items doesn't turn
+/// into thousands of messages.
+pub fn validate_with_limit(
+    schema: &JSONSchema,
+    json: &Value,
+    limit: Option<usize>,
+) -> ValidationResult {
     match schema.validate(json) {
         Ok(_) => ValidationResult {
             valid: true,
             errors: vec![],
+            details: vec![],
+            error_count: 0,
         },
-        Err(errors) => {
-            let error_messages: Vec<String> = errors
+        Err(mut errors) => {
+            let (errors_kept, mut details): (Vec<ValidationError>, Vec<String>) = errors
+                .by_ref()
+                .take(limit.unwrap_or(usize::MAX))
                 .map(|e| {
-                    let instance_path = e.instance_path.to_string();
-                    let error_description = e.to_string();
-
-                    if instance_path.is_empty() {
-                        error_description
-                    } else {
-                        format!("{}: {}", instance_path, error_description)
-                    }
+                    let error = ValidationError {
+                        instance_path: e.instance_path.to_string(),
+                        schema_path: e.schema_path.to_string(),
+                        kind: ValidationErrorKind::from_schema_error(&e.kind),
+                        message: e.to_string(),
+                    };
+                    let detail = format!(
+                        "{} (schema path: {}, value: {})",
+                        error, error.schema_path, e.instance
+                    );
+                    (error, detail)
                 })
-                .collect();
+                .unzip();
 
-            ValidationResult {
+            let mut result = ValidationResult {
                 valid: false,
-                errors: error_messages,
-            }
+                error_count: errors_kept.len() + errors.count(),
+                errors: errors_kept,
+                details: vec![],
+            };
+            details.extend(result.truncation_note());
+            result.details = details;
+            result
+        }
+    }
+}
+
+/// Where `validate_value_at_path` registers the full schema, so that the
+/// sub-schema's `$ref`s still resolve against it
+const AT_PATH_DOCUMENT_ID: &str = "https://schema-gateway.invalid/validate-value-at-path";
+
+/// Validate `value` against the part of `schema` at the JSON pointer `path`
+/// (e.g. `/properties/user`), such as the fields a `PATCH` changes.
+///
+/// Compiled schemas don't keep their source, so this takes the schema
+/// document and compiles a `$ref` to the sub-schema on each call. References
+/// inside the sub-schema resolve against the whole document, and its
+/// `$schema` picks the draft. Errors if nothing is at `path` or the
+/// sub-schema doesn't compile.
+pub fn validate_value_at_path(
+    schema: &Value,
+    value: &Value,
+    path: &str,
+) -> Result<ValidationResult, String> {
+    if schema.pointer(path).is_none() {
+        return Err(format!("No schema at '{}'", path));
+    }
+    let mut wrapper = json!({
+        "$ref": format!("{}#{}", AT_PATH_DOCUMENT_ID, encode_fragment(path))
+    });
+    if let Some(draft) = schema.get("$schema") {
+        wrapper["$schema"] = draft.clone();
+    }
+    let compiled = JSONSchema::options()
+        .with_document(AT_PATH_DOCUMENT_ID.to_string(), schema.clone())
+        .compile(&wrapper)
+        .map_err(|e| format!("Schema at '{}' doesn't compile: {}", path, e))?;
+    Ok(validate(&compiled, value))
+}
+
+/// Percent-encode a JSON pointer for use as a URI fragment
+fn encode_fragment(pointer: &str) -> String {
+    let mut encoded = String::with_capacity(pointer.len());
+    for byte in pointer.bytes() {
+        if byte.is_ascii_alphanumeric() || b"/~-._!$&'()*+,;=:@".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
         }
     }
+    encoded
+}
+
+/// Metric labels for an error from `validate`: the JSON pointer of the
+/// failing field and the kind of error.
+///
+/// Only object keys the schema declares under `properties` are kept; array
+/// indices and keys the client chose (matched by `additionalProperties` or
+/// `patternProperties`) are replaced by `*`, and the path is cut to
+/// `max_depth` segments, so label cardinality stays bounded whatever the
+/// request contains. Errors on the document itself use `/`.
+pub fn error_labels(error: &ValidationError, max_depth: usize) -> (String, &str) {
+    let schema_segments: Vec<&str> = error.schema_path.split('/').collect();
+    let mut cursor = 0;
+    let segments: Vec<&str> = error
+        .instance_path
+        .split('/')
+        .skip(1)
+        .take(max_depth)
+        .map(|seg| {
+            let declared = schema_segments[cursor..]
+                .windows(2)
+                .position(|pair| pair[0] == "properties" && pair[1] == seg);
+            match declared {
+                Some(offset) => {
+                    cursor += offset + 2;
+                    seg
+                }
+                None => "*",
+            }
+        })
+        .collect();
+    let field_path = format!("/{}", segments.join("/"));
+
+    (field_path, error.kind.label())
 }
@@ -0,0 +1,275 @@
+use axum::http::Method;
+use reqwest::Client;
+use schema_gateway::admin::build_admin_router;
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, AppState};
+use schema_gateway::metrics::Metrics;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::RwLock;
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const ONE_ROUTE: &str = r#"
+routes:
+  - path: /api/users
+    method: POST
+    upstream: http://backend:3000
+"#;
+
+const TWO_ROUTES: &str = r#"
+routes:
+  - path: /api/users
+    method: POST
+    upstream: http://backend:3000
+  - path: /api/posts
+    method: GET
+    upstream: http://backend:3000
+"#;
+
+fn write_temp_config(contents: &str) -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("config.yml");
+    fs::write(&path, contents).expect("write temp config");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// Serve the admin router for `config_path` and return its base URL
+async fn start_admin(config_path: &Path) -> TestResult<(Arc<RwLock<AppState>>, String)> {
+    let config = Config::from_file(config_path)?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+
+    let app = build_admin_router(state.clone(), config_path.to_path_buf());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("http://{}/admin/reload", listener.local_addr()?);
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.expect("serve admin");
+    });
+    Ok((state, url))
+}
+
+async fn route_count(state: &Arc<RwLock<AppState>>) -> usize {
+    state.read().await.routing().config.routes.len()
+}
+
+#[tokio::test]
+async fn test_admin_reload_swaps_config() -> TestResult {
+    let config_path = write_temp_config(ONE_ROUTE);
+    let (state, url) = start_admin(&config_path).await?;
+    fs::write(&config_path, TWO_ROUTES)?;
+
+    let response = Client::new().post(&url).send().await?;
+
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await?;
+    assert_eq!(body, json!({"status": "ok", "routes_loaded": 2}));
+    assert_eq!(route_count(&state).await, 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_admin_reload_rejects_invalid_config() -> TestResult {
+    let config_path = write_temp_config(ONE_ROUTE);
+    let (state, url) = start_admin(&config_path).await?;
+    fs::write(
+        &config_path,
+        "routes:\n  - path: /api/users\n    method: POST\n    upstream: \"\"\n",
+    )?;
+
+    let response = Client::new()
+        .post(&url)
+        .json(&json!({"target": "config"}))
+        .send()
+        .await?;
+
+    assert_eq!(response.status(), 422);
+    let body: Value = serde_json::from_str(&response.text().await?)?;
+    assert!(
+        body["error"]
+            .as_str()
+            .is_some_and(|e| e.contains("upstream cannot be empty")),
+        "unexpected body: {}",
+        body
+    );
+    assert_eq!(route_count(&state).await, 1);
+    let reload_failures = state
+        .read()
+        .await
+        .metrics
+        .config_reload_failures_total
+        .get();
+    assert_eq!(reload_failures, 1.0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_admin_reload_single_cache() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let schema_path = dir.path().join("user.json");
+    fs::write(&schema_path, r#"{"type": "object"}"#)?;
+    let spec_path = dir.path().join("openapi.yaml");
+    fs::write(
+        &spec_path,
+        "openapi: 3.0.3\ninfo: { title: Demo, version: '1' }\npaths:\n  /api/users:\n    post: {}\n",
+    )?;
+
+    let config_path = write_temp_config(ONE_ROUTE);
+    let (state, url) = start_admin(&config_path).await?;
+    {
+        let state_guard = state.read().await;
+        state_guard.schema_cache.read().load(&schema_path)?;
+        state_guard.openapi_cache.read().load_operation(
+            &spec_path,
+            "/api/users",
+            &Method::POST,
+            None,
+        )?;
+    }
+    // The config file changing must not matter for cache-only reloads
+    fs::write(&config_path, TWO_ROUTES)?;
+
+    let response = Client::new()
+        .post(&url)
+        .json(&json!({"target": "schemas"}))
+        .send()
+        .await?;
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.json::<Value>().await?,
+        json!({"status": "ok", "routes_loaded": 1})
+    );
+    {
+        let state_guard = state.read().await;
+        assert!(state_guard.schema_cache.read().is_empty());
+        assert!(state_guard.openapi_cache.read().contains_spec(&spec_path));
+    }
+
+    let response = Client::new()
+        .post(&url)
+        .json(&json!({"target": "openapi"}))
+        .send()
+        .await?;
+    assert_eq!(response.status(), 200);
+    assert!(state.read().await.openapi_cache.read().is_empty());
+    assert_eq!(route_count(&state).await, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_admin_reload_rejects_unknown_target() -> TestResult {
+    let config_path = write_temp_config(ONE_ROUTE);
+    let (_state, url) = start_admin(&config_path).await?;
+
+    let response = Client::new()
+        .post(&url)
+        .json(&json!({"target": "everything"}))
+        .send()
+        .await?;
+
+    assert_eq!(response.status(), 400);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_admin_reload_requires_admin_token() -> TestResult {
+    let config_path = write_temp_config(&format!("global:\n  admin_token: s3cret\n{}", ONE_ROUTE));
+    let (_state, url) = start_admin(&config_path).await?;
+    let client = Client::new();
+
+    let response = client.post(&url).send().await?;
+    assert_eq!(response.status(), 401);
+    assert_eq!(
+        response
+            .headers()
+            .get("www-authenticate")
+            .and_then(|v| v.to_str().ok()),
+        Some("Bearer")
+    );
+
+    let response = client.post(&url).bearer_auth("wrong").send().await?;
+    assert_eq!(response.status(), 401);
+
+    let response = client.post(&url).bearer_auth("s3cret").send().await?;
+    assert_eq!(response.status(), 200);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_admin_cache_lists_entries() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let schema_path = dir.path().join("user.json");
+    fs::write(&schema_path, r#"{"type": "object"}"#)?;
+    let spec_path = dir.path().join("openapi.yaml");
+    fs::write(
+        &spec_path,
+        "openapi: 3.0.3\ninfo: { title: Demo, version: '1' }\npaths:\n  /api/users/{id}:\n    get: {}\n",
+    )?;
+
+    let config_path = write_temp_config(ONE_ROUTE);
+    let (state, url) = start_admin(&config_path).await?;
+    let cache_url = url.replace("/admin/reload", "/admin/cache");
+    {
+        let state_guard = state.read().await;
+        state_guard.schema_cache.read().load(&schema_path)?;
+        state_guard.openapi_cache.read().load_operation(
+            &spec_path,
+            "/api/users/42",
+            &Method::GET,
+            None,
+        )?;
+        state_guard.metrics.schema_cache_misses_total.inc();
+    }
+
+    let body: Value = Client::new().get(&cache_url).send().await?.json().await?;
+    let schema = &body["schema_cache"]["entries"][0];
+    assert_eq!(schema["path"], json!(schema_path.display().to_string()));
+    assert_eq!(schema["current"], json!(true));
+    assert!(schema["loaded_at"].as_u64().is_some_and(|t| t > 0));
+    assert_eq!(body["schema_cache"]["hits"], json!(0));
+    assert_eq!(body["schema_cache"]["misses"], json!(1));
+    assert_eq!(
+        body["openapi_cache"]["entries"],
+        json!([{
+            "path": spec_path.display().to_string(),
+            "loaded_at": body["openapi_cache"]["entries"][0]["loaded_at"],
+            "current": true,
+            "version": "3.0.3",
+            "operations": [{"method": "GET", "path": "/api/users/{id}"}],
+        }])
+    );
+
+    // Editing the file after it was compiled marks the entry stale
+    fs::File::options()
+        .write(true)
+        .open(&schema_path)?
+        .set_modified(SystemTime::now() + Duration::from_secs(60))?;
+    let body: Value = Client::new().get(&cache_url).send().await?.json().await?;
+    assert_eq!(body["schema_cache"]["entries"][0]["current"], json!(false));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_admin_cache_requires_admin_token() -> TestResult {
+    let config_path = write_temp_config(&format!("global:\n  admin_token: s3cret\n{}", ONE_ROUTE));
+    let (_state, url) = start_admin(&config_path).await?;
+    let cache_url = url.replace("/admin/reload", "/admin/cache");
+
+    let response = Client::new().get(&cache_url).send().await?;
+    assert_eq!(response.status(), 401);
+    let response = Client::new()
+        .get(&cache_url)
+        .bearer_auth("s3cret")
+        .send()
+        .await?;
+    assert_eq!(response.status(), 200);
+    Ok(())
+}
@@ -0,0 +1,177 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use schema_gateway::body_hash::{verify, BodyHashAlgorithm};
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const ABC_SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+const ABC_SHA512: &str = "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+                          2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f";
+const ABC_MD5: &str = "900150983cd24fb0d6963f7d28e17f72";
+
+#[test]
+fn test_header_value_for_each_algorithm() {
+    assert_eq!(
+        BodyHashAlgorithm::Sha256.header_value(b"abc"),
+        format!("sha256:{}", ABC_SHA256)
+    );
+    assert_eq!(
+        BodyHashAlgorithm::Sha512.header_value(b"abc"),
+        format!("sha512:{}", ABC_SHA512)
+    );
+    assert_eq!(
+        BodyHashAlgorithm::Md5.header_value(b"abc"),
+        format!("md5:{}", ABC_MD5)
+    );
+}
+
+#[test]
+fn test_verify_each_algorithm() {
+    for value in [
+        format!("sha256:{}", ABC_SHA256),
+        format!("sha512:{}", ABC_SHA512),
+        format!("MD5:{}", ABC_MD5.to_uppercase()),
+    ] {
+        assert_eq!(verify(&value, b"abc"), Ok(()), "{}", value);
+        assert!(verify(&value, b"abd").is_err(), "{}", value);
+    }
+
+    assert!(
+        verify("sha1:a9993e364706816aba3e25717850c26c9cd0d89d", b"abc")
+            .is_err_and(|e| e.contains("Unsupported X-Body-Hash algorithm 'sha1'"))
+    );
+    assert!(verify(ABC_SHA256, b"abc").is_err_and(|e| e.contains("Malformed X-Body-Hash")));
+}
+
+#[test]
+fn test_body_hash_config() -> TestResult {
+    let config = Config::from_str(
+        r#"
+global:
+  inject_body_hash: md5
+
+routes:
+  - path: /api/users
+    method: POST
+    upstream: http://backend:3000
+    config:
+      inject_body_hash: sha512
+      verify_response_hash: true
+  - path: /api/posts
+    method: POST
+    upstream: http://backend:3000
+"#,
+    )?;
+
+    let users = config.get_effective_config(&config.routes[0]);
+    assert_eq!(users.inject_body_hash, Some(BodyHashAlgorithm::Sha512));
+    assert!(users.verify_response_hash);
+    let posts = config.get_effective_config(&config.routes[1]);
+    assert_eq!(posts.inject_body_hash, Some(BodyHashAlgorithm::Md5));
+    assert!(!posts.verify_response_hash);
+
+    assert!(Config::from_str("global:\n  inject_body_hash: crc32\nroutes: []\n").is_err());
+    Ok(())
+}
+
+/// POST through a route with `route_config`, upstream answering with
+/// `upstream`
+async fn send(route_config: &str, upstream: ResponseTemplate) -> TestResult<StatusCode> {
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::method("POST"))
+        .respond_with(upstream)
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let config = Config::from_str(&format!(
+        r#"
+routes:
+  - path: /api/users
+    method: POST
+    upstream: {}
+    config:
+{}
+"#,
+        mock_server.uri(),
+        route_config
+    ))?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/users")
+        .body(Body::empty())?;
+    let (parts, body) = request.into_parts();
+    let response = handle_request(State(state), parts.method, parts.uri, parts.headers, body).await;
+    Ok(response.status())
+}
+
+#[tokio::test]
+async fn test_inject_body_hash_on_unvalidated_route() -> TestResult {
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::method("POST"))
+        .and(matchers::header(
+            "x-body-hash",
+            format!("sha256:{}", ABC_SHA256).as_str(),
+        ))
+        .respond_with(ResponseTemplate::new(201))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let config = Config::from_str(&format!(
+        "routes:\n  - path: /api/users\n    method: POST\n    upstream: {}\n    config:\n      inject_body_hash: sha256\n",
+        mock_server.uri()
+    ))?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/users")
+        .header("X-Body-Hash", "md5:spoofed")
+        .body(Body::from("abc"))?;
+    let (parts, body) = request.into_parts();
+    let response = handle_request(State(state), parts.method, parts.uri, parts.headers, body).await;
+
+    // The client's own X-Body-Hash is replaced, and streaming doesn't skip it
+    assert_eq!(response.status(), StatusCode::CREATED);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_verify_response_hash() -> TestResult {
+    let route_config = "      verify_response_hash: true";
+
+    let matching = ResponseTemplate::new(200)
+        .insert_header("x-body-hash", format!("sha512:{}", ABC_SHA512).as_str())
+        .set_body_string("abc");
+    assert_eq!(send(route_config, matching).await?, StatusCode::OK);
+
+    let tampered = ResponseTemplate::new(200)
+        .insert_header("x-body-hash", format!("md5:{}", ABC_MD5).as_str())
+        .set_body_string("abd");
+    assert_eq!(send(route_config, tampered).await?, StatusCode::BAD_GATEWAY);
+
+    // Responses without a hash are passed through
+    let unhashed = ResponseTemplate::new(200).set_body_string("abd");
+    assert_eq!(send(route_config, unhashed).await?, StatusCode::OK);
+    Ok(())
+}
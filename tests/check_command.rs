@@ -0,0 +1,195 @@
+use axum::http::{HeaderMap, Method};
+use schema_gateway::check::{check_request, CheckRequest};
+use schema_gateway::cli::{CheckArgs, Cli, Command};
+use schema_gateway::config::Config;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn write_temp_file(name: &str, contents: &str) -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join(name);
+    fs::write(&path, contents).expect("write temp file");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+fn request(method: Method, path: &str, payload: &str) -> CheckRequest {
+    CheckRequest {
+        method,
+        path: path.to_string(),
+        query: None,
+        headers: HeaderMap::new(),
+        payload: payload.as_bytes().to_vec(),
+    }
+}
+
+fn schema_config() -> TestResult<Config> {
+    let schema_path = write_temp_file(
+        "user.json",
+        r#"{
+        "type": "object",
+        "required": ["name"],
+        "properties": {
+            "name": {"type": "string"},
+            "age": {"type": "integer"}
+        }
+    }"#,
+    );
+    Ok(Config::from_str(&format!(
+        r#"
+routes:
+  - path: /api/users
+    method: POST
+    schema: {}
+    upstream: http://backend:3000
+"#,
+        schema_path.display()
+    ))?)
+}
+
+#[test]
+fn test_check_valid_payload() -> TestResult {
+    let config = schema_config()?;
+    let errors = check_request(
+        &config,
+        &request(Method::POST, "/api/users", r#"{"name": "Ada"}"#),
+    )?;
+    assert!(errors.is_empty(), "unexpected errors: {:?}", errors);
+    Ok(())
+}
+
+#[test]
+fn test_check_reports_every_error_with_instance_path() -> TestResult {
+    let config = schema_config()?;
+    let errors = check_request(
+        &config,
+        &request(Method::POST, "/api/users", r#"{"name": 1, "age": "x"}"#),
+    )?;
+    assert_eq!(errors.len(), 2, "unexpected errors: {:?}", errors);
+    assert!(errors.iter().any(|e| e.starts_with("/name: ")));
+    assert!(errors.iter().any(|e| e.starts_with("/age: ")));
+
+    let errors = check_request(&config, &request(Method::POST, "/api/users", "{}"))?;
+    assert_eq!(errors.len(), 1);
+    assert!(
+        errors[0].starts_with("/: "),
+        "unexpected error: {}",
+        errors[0]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_check_unknown_route_is_an_error() -> TestResult {
+    let config = schema_config()?;
+    let result = check_request(&config, &request(Method::GET, "/api/users", ""));
+    assert!(result
+        .unwrap_err()
+        .contains("No route matches GET /api/users"));
+    Ok(())
+}
+
+#[test]
+fn test_check_openapi_parameters_and_body() -> TestResult {
+    let spec_path = write_temp_file(
+        "openapi.yaml",
+        r#"
+openapi: 3.0.0
+info:
+  title: Demo
+  version: "1.0.0"
+paths:
+  /api/items/{id}:
+    put:
+      parameters:
+        - name: id
+          in: path
+          required: true
+          schema: {type: integer}
+        - name: dry_run
+          in: query
+          schema: {type: boolean}
+        - name: X-Tenant
+          in: header
+          required: true
+          schema: {type: string}
+      requestBody:
+        required: true
+        content:
+          application/json:
+            schema:
+              type: object
+              required: [title]
+"#,
+    );
+    let config = Config::from_str(&format!(
+        r#"
+routes:
+  - path: /api/items/:id
+    method: PUT
+    openapi: {}
+    upstream: http://backend:3000
+"#,
+        spec_path.display()
+    ))?;
+
+    let args = CheckArgs {
+        route: "PUT /api/items/42?dry_run=true".to_string(),
+        payload: Some(write_temp_file("item.json", r#"{"title": "x"}"#)),
+        query: None,
+        headers: vec!["X-Tenant: acme".to_string()],
+    };
+    let valid = CheckRequest::from_args(&args).map_err(|e| e.to_string())?;
+    assert_eq!(valid.query.as_deref(), Some("dry_run=true"));
+    assert!(check_request(&config, &valid)?.is_empty());
+
+    let mut missing_header = valid.clone();
+    missing_header.headers.clear();
+    let errors = check_request(&config, &missing_header)?;
+    assert_eq!(
+        errors,
+        vec!["Missing required header parameter 'X-Tenant'".to_string()]
+    );
+
+    let mut bad_query = valid.clone();
+    bad_query.query = Some("dry_run=maybe".to_string());
+    bad_query.payload.clear();
+    let errors = check_request(&config, &bad_query)?;
+    assert_eq!(errors.len(), 2, "unexpected errors: {:?}", errors);
+    assert!(errors[0].contains("dry_run"));
+    assert!(errors[1].contains("request body required"));
+    Ok(())
+}
+
+#[test]
+fn test_parse_check_subcommand() {
+    use clap::Parser;
+
+    let cli = Cli::try_parse_from([
+        "schema-gateway",
+        "check",
+        "--config",
+        "gateway.yml",
+        "--route",
+        "POST /api/users",
+        "--payload",
+        "user.json",
+        "--headers",
+        "X-Tenant: acme",
+        "--headers",
+        "X-Trace: 1",
+    ])
+    .expect("parse check subcommand");
+
+    assert_eq!(cli.config, PathBuf::from("gateway.yml"));
+    let Some(Command::Check(args)) = cli.command else {
+        panic!("expected check subcommand");
+    };
+    assert_eq!(args.route, "POST /api/users");
+    assert_eq!(args.payload, Some(PathBuf::from("user.json")));
+    assert_eq!(args.headers, vec!["X-Tenant: acme", "X-Trace: 1"]);
+    assert_eq!(args.query, None);
+}
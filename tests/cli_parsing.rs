@@ -1,5 +1,5 @@
 use schema_gateway::cli::Cli;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[test]
 fn test_default_arguments() {
@@ -11,6 +11,15 @@ fn test_default_arguments() {
         config: PathBuf::from("config.yml"),
         port: 8080,
         validate_config: false,
+        watch: false,
+        tls_cert: None,
+        tls_key: None,
+        tls_ca: None,
+        admin_port: None,
+        dev: false,
+        pid_file: None,
+        coverage_report: None,
+        command: None,
     };
 
     assert_eq!(cli.config, PathBuf::from("config.yml"));
@@ -28,6 +37,15 @@ fn test_custom_config_path() {
         config: PathBuf::from("custom.yml"),
         port: 8080,
         validate_config: false,
+        watch: false,
+        tls_cert: None,
+        tls_key: None,
+        tls_ca: None,
+        admin_port: None,
+        dev: false,
+        pid_file: None,
+        coverage_report: None,
+        command: None,
     };
 
     assert_eq!(cli.config, PathBuf::from("custom.yml"));
@@ -43,6 +61,15 @@ fn test_custom_port() {
         config: PathBuf::from("config.yml"),
         port: 3000,
         validate_config: false,
+        watch: false,
+        tls_cert: None,
+        tls_key: None,
+        tls_ca: None,
+        admin_port: None,
+        dev: false,
+        pid_file: None,
+        coverage_report: None,
+        command: None,
     };
 
     assert_eq!(cli.port, 3000);
@@ -58,7 +85,65 @@ fn test_validate_config_mode() {
         config: PathBuf::from("config.yml"),
         port: 8080,
         validate_config: true,
+        watch: false,
+        tls_cert: None,
+        tls_key: None,
+        tls_ca: None,
+        admin_port: None,
+        dev: false,
+        pid_file: None,
+        coverage_report: None,
+        command: None,
     };
 
     assert!(cli.validate_config);
 }
+
+#[test]
+fn test_watch_flag() {
+    use clap::Parser;
+
+    let cli = Cli::try_parse_from(["schema-gateway", "--watch"]).expect("parse --watch");
+    assert!(cli.watch);
+
+    let cli = Cli::try_parse_from(["schema-gateway"]).expect("parse defaults");
+    assert!(!cli.watch);
+}
+
+#[test]
+fn test_tls_flags_must_be_paired() {
+    use clap::Parser;
+
+    let cli = Cli::try_parse_from([
+        "schema-gateway",
+        "--tls-cert",
+        "cert.pem",
+        "--tls-key",
+        "key.pem",
+    ])
+    .expect("parse tls flags");
+    assert_eq!(
+        cli.tls_files(),
+        Ok(Some((Path::new("cert.pem"), Path::new("key.pem"))))
+    );
+
+    let cli = Cli::try_parse_from(["schema-gateway"]).expect("parse defaults");
+    assert_eq!(cli.tls_files(), Ok(None));
+
+    let cli =
+        Cli::try_parse_from(["schema-gateway", "--tls-cert", "cert.pem"]).expect("parse tls-cert");
+    assert_eq!(
+        cli.tls_files(),
+        Err("--tls-cert requires --tls-key".to_string())
+    );
+
+    let cli =
+        Cli::try_parse_from(["schema-gateway", "--tls-key", "key.pem"]).expect("parse tls-key");
+    assert_eq!(
+        cli.tls_files(),
+        Err("--tls-key requires --tls-cert".to_string())
+    );
+
+    let cli = Cli::try_parse_from(["schema-gateway", "--tls-ca", "ca.pem"]).expect("parse tls-ca");
+    assert!(cli.tls_files().is_err());
+}
@@ -0,0 +1,99 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn write_temp_schema_file(contents: &str) -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("schema.json");
+    fs::write(&path, contents).expect("write temp schema");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_requests_share_schema_cache() -> TestResult {
+    const REQUESTS: usize = 64;
+
+    let schema_path = write_temp_schema_file(
+        r#"{
+        "type": "object",
+        "required": ["name"],
+        "properties": {"name": {"type": "string"}}
+    }"#,
+    );
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/users"))
+        .respond_with(ResponseTemplate::new(201))
+        .expect(REQUESTS as u64)
+        .mount(&mock_server)
+        .await;
+
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: false
+
+routes:
+  - path: /api/users
+    method: POST
+    schema: {}
+    upstream: {}
+"#,
+        schema_path.display(),
+        mock_server.uri()
+    ))?;
+
+    let metrics = Arc::new(Metrics::new()?);
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        metrics.clone(),
+    )));
+
+    let tasks: Vec<_> = (0..REQUESTS)
+        .map(|i| {
+            let state = state.clone();
+            tokio::spawn(async move {
+                let request = Request::builder()
+                    .method(Method::POST)
+                    .uri("/api/users")
+                    .header("content-type", "application/json")
+                    .body(Body::from(format!(r#"{{"name": "user-{}"}}"#, i)))
+                    .expect("build request");
+                let (parts, body) = request.into_parts();
+                handle_request(State(state), parts.method, parts.uri, parts.headers, body)
+                    .await
+                    .status()
+            })
+        })
+        .collect();
+
+    for task in tasks {
+        assert_eq!(task.await?, StatusCode::CREATED);
+    }
+
+    let state_guard = state.read().await;
+    assert_eq!(state_guard.schema_cache.read().len(), 1);
+    assert_eq!(
+        metrics.schema_cache_hits_total.get() + metrics.schema_cache_misses_total.get(),
+        REQUESTS as f64
+    );
+    Ok(())
+}
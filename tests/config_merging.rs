@@ -1,4 +1,5 @@
 use schema_gateway::config::Config;
+use std::str::FromStr;
 
 #[test]
 fn test_route_overrides_global() {
@@ -18,7 +19,7 @@ routes:
       add_error_header: false
 "#;
 
-    let config: Config = serde_yaml::from_str(yaml).expect("parse config");
+    let config = Config::from_str(yaml).expect("parse config");
     let route = &config.routes[0];
 
     // Get the effective config for this route
@@ -56,7 +57,7 @@ routes:
     upstream: http://backend:3000
 "#;
 
-    let config: Config = serde_yaml::from_str(yaml).expect("parse config");
+    let config = Config::from_str(yaml).expect("parse config");
     let route = &config.routes[0];
 
     // Get the effective config for this route
@@ -1,4 +1,5 @@
 use schema_gateway::config::Config;
+use std::str::FromStr;
 use tempfile::NamedTempFile;
 
 #[test]
@@ -11,7 +12,7 @@ routes:
     upstream: http://backend:3000
 "#;
 
-    let config: Config = serde_yaml::from_str(yaml).expect("parse minimal config");
+    let config = Config::from_str(yaml).expect("parse minimal config");
 
     assert_eq!(config.routes.len(), 1);
 
@@ -50,7 +51,7 @@ routes:
     upstream: http://backend:3000
 "#;
 
-    let config: Config = serde_yaml::from_str(yaml).expect("parse full config");
+    let config = Config::from_str(yaml).expect("parse full config");
 
     // Global config
     assert!(!config.global.forward_on_error);
@@ -94,7 +95,7 @@ fn test_reject_empty_routes() {
 routes: []
 "#;
 
-    let result: Result<Config, _> = serde_yaml::from_str(yaml);
+    let result = Config::from_str(yaml);
 
     // Should parse but validation should fail
     if let Ok(config) = result {
@@ -126,7 +127,7 @@ routes:
     upstream: http://backend:3000
 "#;
 
-    let result: Result<Config, _> = serde_yaml::from_str(yaml);
+    let result = Config::from_str(yaml);
 
     if let Ok(config) = result {
         let validation_result = config.validate();
@@ -155,7 +156,7 @@ routes:
     upstream: ""
 "#;
 
-    let result: Result<Config, _> = serde_yaml::from_str(yaml);
+    let result = Config::from_str(yaml);
 
     if let Ok(config) = result {
         let validation_result = config.validate();
@@ -190,7 +191,7 @@ routes:
     upstream: http://backend:3000
 "#;
 
-    let config: Config = serde_yaml::from_str(yaml).expect("parse config");
+    let config = Config::from_str(yaml).expect("parse config");
     assert_eq!(config.routes.len(), 2);
 
     let route1 = &config.routes[0];
@@ -215,7 +216,7 @@ routes:
     upstream: http://backend:3000
 "#;
 
-    let config: Config = serde_yaml::from_str(yaml).expect("parse config");
+    let config = Config::from_str(yaml).expect("parse config");
     let validation_result = config.validate();
     assert!(validation_result.is_err(), "route should be invalid");
     let err_msg = validation_result.unwrap_err();
@@ -236,7 +237,7 @@ routes:
     upstream: http://backend:3000
 "#;
 
-    let config_missing: Config = serde_yaml::from_str(yaml_missing).expect("parse config");
+    let config_missing = Config::from_str(yaml_missing).expect("parse config");
     let validation_err = config_missing.validate().unwrap_err();
     assert!(
         validation_err.contains("OpenAPI spec does not exist"),
@@ -256,9 +257,459 @@ routes:
         tmp_file.path().display()
     );
 
-    let valid_config: Config = serde_yaml::from_str(&valid_yaml).expect("parse config");
+    let valid_config = Config::from_str(&valid_yaml).expect("parse config");
     assert!(
         valid_config.validate().is_ok(),
         "expected config with existing OpenAPI file to validate"
     );
 }
+
+#[test]
+fn test_parse_schema_cache_max_entries() {
+    let yaml = r#"
+global:
+  schema_cache_max_entries: 128
+
+routes:
+  - path: /api/users
+    method: POST
+    upstream: http://backend:3000
+"#;
+
+    let config = Config::from_str(yaml).expect("parse config");
+    assert_eq!(config.global.schema_cache_max_entries, Some(128));
+    assert!(config.validate().is_ok());
+
+    let unbounded = Config::from_str(
+        r#"
+routes:
+  - path: /api/users
+    method: POST
+    upstream: http://backend:3000
+"#,
+    )
+    .expect("parse config");
+    assert_eq!(unbounded.global.schema_cache_max_entries, None);
+
+    let zero = Config::from_str(&yaml.replace("128", "0")).expect("parse config");
+    assert!(
+        zero.validate().is_err(),
+        "expected zero capacity to be rejected"
+    );
+}
+
+#[test]
+fn test_metrics_endpoint_settings() {
+    let defaults = Config::from_str(
+        r#"
+routes:
+  - path: /api/users
+    method: POST
+    upstream: http://backend:3000
+"#,
+    )
+    .expect("parse config");
+    assert!(defaults.global.metrics_enabled);
+    assert_eq!(defaults.global.metrics_path, "/metrics");
+    assert_eq!(defaults.global.metrics_token, None);
+
+    let yaml = r#"
+global:
+  metrics_path: /internal/metrics
+  metrics_token: s3cret
+
+routes:
+  - path: /api/users
+    method: POST
+    upstream: http://backend:3000
+"#;
+    let config = Config::from_str(yaml).expect("parse config");
+    assert_eq!(config.global.metrics_path, "/internal/metrics");
+    assert_eq!(config.global.metrics_token.as_deref(), Some("s3cret"));
+    assert!(config.validate().is_ok());
+
+    for bad in ["metrics", "/", "/metrics/:id", "/health/metrics"] {
+        let config =
+            Config::from_str(&yaml.replace("/internal/metrics", bad)).expect("parse config");
+        assert!(
+            config.validate().is_err(),
+            "expected metrics_path '{}' to be rejected",
+            bad
+        );
+    }
+}
+
+#[test]
+fn test_from_file_resolves_paths_relative_to_config() {
+    let root = tempfile::tempdir().expect("create temp dir");
+    let schemas_dir = root.path().join("schemas");
+    let config_dir = root.path().join("config");
+    std::fs::create_dir_all(&schemas_dir).expect("create schemas dir");
+    std::fs::create_dir_all(&config_dir).expect("create config dir");
+    std::fs::write(schemas_dir.join("user.json"), r#"{"type": "object"}"#).expect("write schema");
+    std::fs::write(schemas_dir.join("api.yaml"), "openapi: 3.0.0\n").expect("write spec");
+
+    let absolute = root.path().join("absolute.json");
+    let config_path = config_dir.join("gateway.yml");
+    std::fs::write(
+        &config_path,
+        format!(
+            r#"
+routes:
+  - path: /api/users
+    method: POST
+    schema: ../schemas/user.json
+    headers_schema: {}
+    upstream: http://backend:3000
+  - path: /api/posts
+    method: POST
+    openapi:
+      spec: ./../schemas/api.yaml
+    upstream: http://backend:3000
+"#,
+            absolute.display()
+        ),
+    )
+    .expect("write config");
+
+    let config = Config::from_file(&config_path).expect("load config");
+
+    let schema = config.routes[0].schema.as_ref().expect("schema path");
+    assert_eq!(schema, &schemas_dir.join("user.json"));
+    assert!(
+        schema.exists(),
+        "resolved schema should exist: {:?}",
+        schema
+    );
+    assert_eq!(config.routes[0].headers_schema.as_ref(), Some(&absolute));
+
+    let spec = config.routes[1].openapi_options().expect("openapi").spec;
+    assert_eq!(spec, schemas_dir.join("api.yaml"));
+    assert!(
+        config.validate().is_ok(),
+        "spec existence check uses the resolved path"
+    );
+}
+
+#[test]
+fn test_config_from_str() {
+    let config = Config::from_str(
+        r#"
+routes:
+  - path: /api/users
+    method: POST
+    schema: ./schemas/user.json
+    upstream: http://backend:3000
+"#,
+    )
+    .expect("parse config");
+    // Without a file there is no directory to resolve against
+    assert_eq!(
+        config.routes[0].schema.as_deref(),
+        Some(std::path::Path::new("./schemas/user.json"))
+    );
+    assert_eq!(
+        "routes: []".parse::<Config>().expect("parse config").routes,
+        vec![]
+    );
+
+    let err = Config::from_str("routes: [unterminated").expect_err("invalid YAML");
+    assert!(
+        err.starts_with("Failed to parse config: "),
+        "unexpected error: {}",
+        err
+    );
+    assert!(!err.contains("file"), "unexpected error: {}", err);
+}
+
+fn upstream_error(upstream: &str) -> Option<String> {
+    let yaml = format!(
+        "routes:\n  - path: /api/users\n    method: POST\n    upstream: \"{}\"\n",
+        upstream
+    );
+    Config::from_str(&yaml)
+        .expect("parse config")
+        .validate()
+        .err()
+}
+
+#[test]
+fn test_upstream_must_be_absolute_http_url() {
+    for upstream in [
+        "http://backend:3000",
+        "https://backend.internal",
+        "http://10.0.0.5:8080/",
+        // A base path is allowed; request paths are appended to it
+        "http://backend:3000/v1",
+    ] {
+        assert_eq!(upstream_error(upstream), None, "{}", upstream);
+    }
+
+    for upstream in [
+        "backend:3000",
+        "backend",
+        "/api",
+        "ftp://files.example.com",
+        "http://",
+        "http://bad host",
+    ] {
+        let err = upstream_error(upstream).unwrap_or_default();
+        assert!(
+            err.contains(&format!("Invalid upstream '{}'", upstream))
+                && err.contains("upstream must be an absolute http:// or https:// URL"),
+            "{}: unexpected error: {}",
+            upstream,
+            err
+        );
+    }
+}
+
+#[test]
+fn test_openapi_mount_upstream_must_be_absolute_http_url() {
+    let err = Config::from_str(
+        r#"
+openapi_mounts:
+  - prefix: /api
+    spec: ./openapi.yaml
+    upstream: backend:3000
+routes: []
+"#,
+    )
+    .and_then(|config| config.validate())
+    .expect_err("invalid mount upstream");
+    assert!(err.contains("Invalid upstream 'backend:3000'"), "{}", err);
+}
+
+/// A config directory with `gateway.yml` holding `config` plus the given
+/// extra files
+fn config_dir(config: &str, files: &[(&str, &str)]) -> tempfile::TempDir {
+    let root = tempfile::tempdir().expect("create temp dir");
+    std::fs::write(root.path().join("gateway.yml"), config).expect("write config");
+    for (name, contents) in files {
+        let path = root.path().join(name);
+        std::fs::create_dir_all(path.parent().expect("parent dir")).expect("create dir");
+        std::fs::write(path, contents).expect("write file");
+    }
+    root
+}
+
+#[test]
+fn test_includes_append_routes_from_matching_files() {
+    let root = config_dir(
+        r#"
+includes:
+  - routes/*.yml
+routes:
+  - path: /health
+    method: GET
+    upstream: http://backend:3000
+"#,
+        &[
+            (
+                "routes/users.yml",
+                r#"
+- path: /api/users
+  method: POST
+  schema: ../schemas/user.json
+  upstream: http://users:3000
+"#,
+            ),
+            (
+                "routes/posts.yml",
+                r#"
+- path: /api/posts
+  method: GET
+  upstream: http://posts:3000
+"#,
+            ),
+            ("routes/notes.txt", "not routes"),
+            ("schemas/user.json", r#"{"type": "object"}"#),
+        ],
+    );
+
+    let config = Config::from_file(root.path().join("gateway.yml")).expect("load config");
+    let paths: Vec<&str> = config.routes.iter().map(|r| r.path.as_str()).collect();
+    assert_eq!(paths, vec!["/health", "/api/posts", "/api/users"]);
+    // Relative paths resolve against the included file's directory
+    assert_eq!(
+        config.routes[2].schema.as_ref(),
+        Some(&root.path().join("schemas/user.json"))
+    );
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn test_missing_include_names_the_file() {
+    let root = config_dir("includes:\n  - extra.yml\nroutes: []\n", &[]);
+    let err = Config::from_file(root.path().join("gateway.yml")).expect_err("missing include");
+    assert!(err.contains("extra.yml"), "{}", err);
+    assert!(err.contains("not found"), "{}", err);
+
+    // A glob that matches nothing is allowed
+    let root = config_dir("includes:\n  - routes/*.yml\nroutes: []\n", &[]);
+    assert!(Config::from_file(root.path().join("gateway.yml")).is_ok());
+}
+
+#[test]
+fn test_unparseable_include_names_the_file() {
+    let root = config_dir(
+        "includes:\n  - extra.yml\nroutes: []\n",
+        &[("extra.yml", "path: /api/users\n")],
+    );
+    let err = Config::from_file(root.path().join("gateway.yml")).expect_err("bad include");
+    assert!(
+        err.contains("Failed to parse included routes file") && err.contains("extra.yml"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn test_include_duplicate_route_across_files_is_rejected() {
+    let root = config_dir(
+        r#"
+includes:
+  - extra.yml
+routes:
+  - path: /api/users
+    method: POST
+    upstream: http://backend:3000
+"#,
+        &[(
+            "extra.yml",
+            "- path: /api/users\n  method: post\n  upstream: http://other:3000\n",
+        )],
+    );
+    let err = Config::from_file(root.path().join("gateway.yml")).expect_err("duplicate route");
+    assert!(err.contains("Duplicate route POST /api/users"), "{}", err);
+    assert!(
+        err.contains("gateway.yml") && err.contains("extra.yml"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn test_config_round_trips_through_yaml() {
+    let yaml = r#"
+global:
+  forward_on_error: false
+  schema_cache_max_entries: 100
+  strip_response_headers: [server]
+  rename_response_headers:
+    x-internal-trace: x-trace-id
+  inject_body_hash: sha256
+  rate_limit:
+    requests_per_second: 10
+    burst: 20
+    key_source: api_key_header
+  retry:
+    max_attempts: 3
+    retry_on: [connect, 5xx]
+  upstream_tls:
+    verify: false
+    insecure: true
+
+routes:
+  - path: /api/users
+    method: POST
+    schema: ./schemas/user.json
+    strict_body: true
+    upstream: http://backend:3000
+    config:
+      forward_on_error: true
+      max_request_body_bytes: 1024
+  - path: /api/users/:id
+    method: GET
+    openapi: ./specs/api.yaml
+    upstream: http://backend:3000
+  - path: /api/orders
+    method: POST
+    openapi:
+      spec: ./specs/api.yaml
+      operation_id: createOrder
+      base_path: /v1
+      apply_defaults: true
+    upstream: http://backend:3000
+  - path_regex: ^/api/items/(?P<id>[0-9]+)$
+    method: GET
+    mock_response:
+      status: 201
+      body: { id: 1 }
+      headers: { x-mock: "yes" }
+
+default_route:
+  upstream: http://fallback:3000
+"#;
+
+    let config = Config::from_str(yaml).expect("parse config");
+    let serialized = config.to_yaml().expect("serialize config");
+    let reparsed = Config::from_str(&serialized).expect("parse serialized config");
+    assert_eq!(config, reparsed, "{}", serialized);
+    // Serializing again gives the same document
+    assert_eq!(serialized, reparsed.to_yaml().expect("serialize config"));
+}
+
+#[test]
+fn test_serialized_openapi_spec_path_is_a_plain_string() {
+    let config = Config::from_str(
+        "routes:\n  - path: /api/users\n    method: GET\n    openapi: ./specs/api.yaml\n    upstream: http://backend:3000\n",
+    )
+    .expect("parse config");
+    let serialized = config.to_yaml().expect("serialize config");
+    assert!(
+        serialized.contains("openapi: ./specs/api.yaml\n"),
+        "{}",
+        serialized
+    );
+    // Unset route overrides are left out rather than written as null
+    assert!(serialized.contains("config: {}"), "{}", serialized);
+}
+
+#[test]
+fn test_serialized_config_inlines_included_routes() {
+    let root = config_dir(
+        r#"
+includes:
+  - extra.yml
+routes:
+  - path: /health
+    method: GET
+    upstream: http://backend:3000
+"#,
+        &[(
+            "extra.yml",
+            "- path: /api/users\n  method: POST\n  upstream: http://backend:3000\n",
+        )],
+    );
+    let config = Config::from_file(root.path().join("gateway.yml")).expect("load config");
+    assert_eq!(config.routes.len(), 2);
+
+    // The included route is written with the others, so loading the output
+    // doesn't find it twice
+    let reparsed = Config::from_str(&config.to_yaml().expect("serialize config"))
+        .expect("parse serialized config");
+    assert!(reparsed.includes.is_empty());
+    assert_eq!(reparsed.routes, config.routes);
+}
+
+#[test]
+fn test_parse_errors_point_at_the_line() {
+    // An unclosed flow sequence on line 5
+    let root = config_dir(
+        "global:\n  forward_on_error: true\nroutes:\n  - path: /api/users\n    method: [POST\n    upstream: http://backend:3000\n",
+        &[],
+    );
+    let err = Config::from_file(root.path().join("gateway.yml")).expect_err("syntax error");
+    assert!(err.starts_with("Failed to parse config file '"), "{}", err);
+    assert!(err.contains("gateway.yml"), "{}", err);
+    assert!(err.contains("line 5"), "{}", err);
+
+    // A value of the wrong type is located too
+    let root = config_dir(
+        "global:\n  forward_on_error: true\n  max_redirects: many\nroutes: []\n",
+        &[],
+    );
+    let err = Config::from_file(root.path().join("gateway.yml")).expect_err("type error");
+    assert!(err.contains("line 3 column 18"), "{}", err);
+}
@@ -0,0 +1,156 @@
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, AppState};
+use schema_gateway::metrics::Metrics;
+use schema_gateway::reload::{reload_config, spawn_config_watcher};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::RwLock;
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const ONE_ROUTE: &str = r#"
+routes:
+  - path: /api/users
+    method: POST
+    upstream: http://backend:3000
+"#;
+
+const TWO_ROUTES: &str = r#"
+routes:
+  - path: /api/users
+    method: POST
+    upstream: http://backend:3000
+  - path: /api/posts
+    method: GET
+    upstream: http://backend:3000
+"#;
+
+fn write_temp_config(contents: &str) -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("config.yml");
+    fs::write(&path, contents).expect("write temp config");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// Rewrite the file and push its mtime forward so coarse filesystem
+/// timestamps can't hide the change.
+fn rewrite_config(path: &Path, contents: &str, bump: Duration) {
+    fs::write(path, contents).expect("rewrite config");
+    let file = fs::File::options()
+        .write(true)
+        .open(path)
+        .expect("open config");
+    file.set_modified(SystemTime::now() + bump)
+        .expect("set mtime");
+}
+
+fn build_state(config_path: &Path) -> TestResult<Arc<RwLock<AppState>>> {
+    let config = Config::from_file(config_path)?;
+    let http_client = build_http_client(&config.global.http_client);
+    Ok(Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    ))))
+}
+
+async fn wait_for<F>(state: &Arc<RwLock<AppState>>, predicate: F) -> bool
+where
+    F: Fn(&AppState) -> bool,
+{
+    for _ in 0..100 {
+        if predicate(&*state.read().await) {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    false
+}
+
+#[tokio::test]
+async fn test_reload_config_swaps_routes() -> TestResult {
+    let config_path = write_temp_config(ONE_ROUTE);
+    let state = build_state(&config_path)?;
+
+    fs::write(&config_path, TWO_ROUTES)?;
+    let routes = reload_config(&state, &config_path).await?;
+
+    assert_eq!(routes, 2);
+    let state_guard = state.read().await;
+    assert!(state_guard
+        .routing()
+        .config
+        .find_route("/api/posts", "GET")
+        .is_some());
+    assert_eq!(state_guard.metrics.config_reloads_total.get(), 1.0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reload_keeps_previous_config_on_error() -> TestResult {
+    let config_path = write_temp_config(ONE_ROUTE);
+    let state = build_state(&config_path)?;
+
+    fs::write(&config_path, "routes: []\n")?;
+    assert!(reload_config(&state, &config_path).await.is_err());
+
+    let state_guard = state.read().await;
+    assert_eq!(state_guard.routing().config.routes.len(), 1);
+    assert_eq!(state_guard.metrics.config_reloads_total.get(), 0.0);
+    assert_eq!(state_guard.metrics.config_reload_failures_total.get(), 1.0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_watcher_reloads_on_mtime_change() -> TestResult {
+    let config_path = write_temp_config(ONE_ROUTE);
+    let state = build_state(&config_path)?;
+
+    let watcher = spawn_config_watcher(
+        state.clone(),
+        config_path.clone(),
+        Duration::from_millis(20),
+    );
+
+    // Give the watcher a chance to record the initial mtime
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    rewrite_config(&config_path, TWO_ROUTES, Duration::from_secs(1));
+    assert!(
+        wait_for(&state, |s| s.routing().config.routes.len() == 2).await,
+        "expected watcher to pick up the new route"
+    );
+
+    // A broken file is counted as a failure and leaves the config in place
+    rewrite_config(&config_path, "not: [valid", Duration::from_secs(2));
+    assert!(
+        wait_for(&state, |s| s.metrics.config_reload_failures_total.get()
+            >= 1.0)
+        .await,
+        "expected watcher to record the failed reload"
+    );
+    assert_eq!(state.read().await.routing().config.routes.len(), 2);
+
+    watcher.abort();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_replace_config_keeps_existing_snapshots() -> TestResult {
+    let config_path = write_temp_config(ONE_ROUTE);
+    let state = build_state(&config_path)?;
+    let state_guard = state.read().await;
+
+    let before = state_guard.routing();
+    state_guard.replace_config(Config::from_str(TWO_ROUTES)?)?;
+
+    // In-flight requests keep the snapshot they started with
+    assert_eq!(before.config.routes.len(), 1);
+    assert_eq!(state_guard.routing().config.routes.len(), 2);
+    assert!(state_guard.schema_cache.read().is_empty());
+    assert!(state_guard.openapi_cache.read().is_empty());
+    Ok(())
+}
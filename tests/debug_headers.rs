@@ -0,0 +1,132 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use axum::response::Response;
+use schema_gateway::config::Config;
+use schema_gateway::handler::{
+    build_http_client, handle_request, AppState, ROUTE_HEADER, VALIDATION_MODE_HEADER,
+};
+use schema_gateway::metrics::Metrics;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn write_schema() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("user.json");
+    fs::write(&path, r#"{"type": "object", "required": ["name"]}"#).expect("write schema");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// Gateway state with an exact and a parameterized route, and `global`
+/// settings
+async fn gateway(global: &str) -> TestResult<(Arc<RwLock<AppState>>, MockServer)> {
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::any())
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&mock_server)
+        .await;
+
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: false
+{}
+
+routes:
+  - path: /api/users
+    method: POST
+    schema: {}
+    upstream: {upstream}
+  - path: /api/users/:id/posts
+    method: GET
+    upstream: {upstream}
+"#,
+        global,
+        write_schema().display(),
+        upstream = mock_server.uri()
+    ))?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+    Ok((state, mock_server))
+}
+
+async fn send(
+    state: &Arc<RwLock<AppState>>,
+    method: Method,
+    uri: &str,
+    body: &str,
+) -> TestResult<Response> {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))?;
+    let (parts, body) = request.into_parts();
+    Ok(handle_request(
+        State(state.clone()),
+        parts.method,
+        parts.uri,
+        parts.headers,
+        body,
+    )
+    .await)
+}
+
+fn debug_headers(response: &Response) -> (Option<&str>, Option<&str>) {
+    let header = |name| response.headers().get(name)?.to_str().ok();
+    (header(ROUTE_HEADER), header(VALIDATION_MODE_HEADER))
+}
+
+#[tokio::test]
+async fn test_debug_headers_name_the_matched_route() -> TestResult {
+    let (state, _upstream) = gateway("  debug_headers: true").await?;
+
+    let response = send(&state, Method::POST, "/api/users", r#"{"name": "a"}"#).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        debug_headers(&response),
+        (Some("/api/users"), Some("json_schema"))
+    );
+
+    let response = send(&state, Method::GET, "/api/users/42/posts", "").await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        debug_headers(&response),
+        (Some("/api/users/:id/posts"), Some("none"))
+    );
+
+    // Rejected requests say which route rejected them
+    let response = send(&state, Method::POST, "/api/users", "{}").await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        debug_headers(&response),
+        (Some("/api/users"), Some("json_schema"))
+    );
+
+    // No route matched, nothing to report
+    let response = send(&state, Method::GET, "/api/missing", "").await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(debug_headers(&response), (None, None));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_debug_headers_are_off_by_default() -> TestResult {
+    let (state, _upstream) = gateway("").await?;
+
+    let response = send(&state, Method::GET, "/api/users/42/posts", "").await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(debug_headers(&response), (None, None));
+    Ok(())
+}
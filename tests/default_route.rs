@@ -0,0 +1,207 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn write_schema() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("user.json");
+    fs::write(&path, r#"{"type": "object", "required": ["name"]}"#).expect("write schema");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// Gateway state with a validated `POST /api/users` route to `validated`
+/// and a default route to `fallback`
+async fn gateway(
+    default_route: &str,
+) -> TestResult<(Arc<RwLock<AppState>>, MockServer, MockServer)> {
+    let validated = MockServer::start().await;
+    Mock::given(matchers::any())
+        .respond_with(ResponseTemplate::new(201))
+        .mount(&validated)
+        .await;
+    let fallback = MockServer::start().await;
+    Mock::given(matchers::any())
+        .respond_with(ResponseTemplate::new(200).set_body_string("fallback"))
+        .mount(&fallback)
+        .await;
+
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: false
+
+routes:
+  - path: /api/users
+    method: POST
+    schema: {}
+    upstream: {}
+
+default_route:
+  upstream: {}
+{}
+"#,
+        write_schema().display(),
+        validated.uri(),
+        fallback.uri(),
+        default_route
+    ))?;
+    config.validate()?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+    Ok((state, validated, fallback))
+}
+
+async fn send(
+    state: &Arc<RwLock<AppState>>,
+    method: Method,
+    uri: &str,
+    body: &str,
+) -> TestResult<StatusCode> {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))?;
+    let (parts, body) = request.into_parts();
+    let response = handle_request(
+        State(state.clone()),
+        parts.method,
+        parts.uri,
+        parts.headers,
+        body,
+    )
+    .await;
+    Ok(response.status())
+}
+
+#[tokio::test]
+async fn test_unmatched_requests_go_to_the_default_route() -> TestResult {
+    let (state, validated, fallback) = gateway("").await?;
+
+    assert_eq!(
+        send(&state, Method::GET, "/unknown/path?page=2", "").await?,
+        StatusCode::OK
+    );
+    // A wrong method on a routed path is unmatched too
+    assert_eq!(
+        send(&state, Method::DELETE, "/api/users", "").await?,
+        StatusCode::OK
+    );
+    // Matched routes are still validated
+    assert_eq!(
+        send(&state, Method::POST, "/api/users", "{}").await?,
+        StatusCode::BAD_REQUEST
+    );
+
+    let received = fallback.received_requests().await.unwrap_or_default();
+    let targets: Vec<String> = received
+        .iter()
+        .map(|request| {
+            let query = request.url.query().map(|q| format!("?{}", q));
+            format!(
+                "{} {}{}",
+                request.method,
+                request.url.path(),
+                query.unwrap_or_default()
+            )
+        })
+        .collect();
+    assert_eq!(
+        targets,
+        vec!["GET /unknown/path?page=2", "DELETE /api/users"]
+    );
+    assert!(validated
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .is_empty());
+
+    let metrics = state.read().await.metrics.gather()?;
+    assert!(
+        metrics
+            .contains(r#"http_requests_total{method="GET",route="default_route",route_tags="",status="200"} 1"#),
+        "{}",
+        metrics
+    );
+    assert!(!metrics.contains("routes_not_found_total{"), "{}", metrics);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_default_route_can_validate_permissively() -> TestResult {
+    let schema = write_schema();
+    let (state, _validated, fallback) = gateway(&format!(
+        "  schema: {}\n  config:\n    forward_on_error: true",
+        schema.display()
+    ))
+    .await?;
+
+    assert_eq!(
+        send(&state, Method::PUT, "/other", "{}").await?,
+        StatusCode::OK
+    );
+    let received = fallback.received_requests().await.unwrap_or_default();
+    let request = received.first().ok_or("default route not called")?;
+    assert!(request.headers.contains_key("x-gateway-error"));
+    Ok(())
+}
+
+#[test]
+fn test_default_route_config_validation() -> TestResult {
+    let schema = write_schema();
+    let cases = [
+        (
+            "  path: /fallback\n  upstream: http://fallback:3000".to_string(),
+            "'path', 'path_regex' and 'method' cannot be set",
+        ),
+        (
+            "  method: GET\n  upstream: http://fallback:3000".to_string(),
+            "'path', 'path_regex' and 'method' cannot be set",
+        ),
+        ("  upstream: fallback:3000".to_string(), "default_route:"),
+        (
+            format!(
+                "  upstream: http://fallback:3000\n  schema: {}",
+                schema.display()
+            ),
+            "require forward_on_error: true",
+        ),
+    ];
+    for (default_route, expected) in cases {
+        let config = Config::from_str(&format!(
+            r#"
+global:
+  forward_on_error: false
+
+routes:
+  - path: /api/users
+    method: POST
+    upstream: http://backend:3000
+
+default_route:
+{}
+"#,
+            default_route
+        ))?;
+        let error = config.validate().expect_err(&default_route);
+        assert!(error.starts_with("default_route: "), "{}", error);
+        assert!(error.contains(expected), "{}", error);
+    }
+    Ok(())
+}
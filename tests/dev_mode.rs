@@ -0,0 +1,153 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use axum::response::Response;
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use schema_gateway::schema::SchemaCache;
+use schema_gateway::validation::validate;
+use serde_json::json;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const SCHEMA: &str = r#"{
+    "type": "object",
+    "required": ["name"],
+    "properties": { "age": { "type": "integer", "minimum": 0 } }
+}"#;
+
+fn write_schema() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("user.json");
+    fs::write(&path, SCHEMA).expect("write schema");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// POST `body` through a validated route, with `global` settings
+async fn send(body: &str, global: &str, dev_mode: bool) -> TestResult<(StatusCode, String)> {
+    let response = respond(body, global, dev_mode).await?;
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    Ok((status, String::from_utf8(bytes.to_vec())?))
+}
+
+/// `send`, returning the whole response
+async fn respond(body: &str, global: &str, dev_mode: bool) -> TestResult<Response> {
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"id": 1, "tags": ["a"]})))
+        .mount(&mock_server)
+        .await;
+
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: false
+{}
+
+routes:
+  - path: /api/users
+    method: POST
+    schema: {}
+    upstream: {}
+"#,
+        global,
+        write_schema().display(),
+        mock_server.uri()
+    ))?;
+    let http_client = build_http_client(&config.global.http_client);
+    let app_state =
+        AppState::new(config, http_client, Arc::new(Metrics::new()?)).with_dev_mode(dev_mode);
+    let state = Arc::new(RwLock::new(app_state));
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/users")
+        .body(Body::from(body.to_string()))?;
+    let (parts, body) = request.into_parts();
+    Ok(handle_request(State(state), parts.method, parts.uri, parts.headers, body).await)
+}
+
+#[test]
+fn test_validation_details_include_schema_path_and_value() -> TestResult {
+    let schema = SchemaCache::new().load(write_schema())?;
+    let result = validate(&schema, &json!({"name": "x", "age": -3}));
+
+    assert_eq!(
+        result.messages(),
+        vec!["/age: -3 is less than the minimum of 0"]
+    );
+    assert_eq!(
+        result.details,
+        vec!["/age: -3 is less than the minimum of 0 (schema path: /properties/age/minimum, value: -3)"]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dev_mode_pretty_prints_responses() -> TestResult {
+    let (status, body) = send(r#"{"name": "x"}"#, "", true).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body,
+        serde_json::to_string_pretty(&json!({"id": 1, "tags": ["a"]}))?
+    );
+
+    let (_, body) = send(r#"{"name": "x"}"#, "", false).await?;
+    assert_eq!(body, r#"{"id":1,"tags":["a"]}"#);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pretty_printed_response_drops_the_upstream_content_length() -> TestResult {
+    // The upstream's length is for the compact body; the re-indented one
+    // is longer and must be framed by the gateway
+    let response = respond(r#"{"name": "x"}"#, "", true).await?;
+    assert!(
+        response.headers().get("content-length").is_none(),
+        "{:?}",
+        response.headers()
+    );
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    assert_eq!(
+        bytes,
+        serde_json::to_string_pretty(&json!({"id": 1, "tags": ["a"]}))?
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dev_mode_errors_are_pretty_and_detailed() -> TestResult {
+    let (status, body) = send(r#"{"age": -1}"#, "", true).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body.starts_with("{\n  \"error\": "), "{}", body);
+    let error: serde_json::Value = serde_json::from_str(&body)?;
+    let message = error["error"].as_str().unwrap_or_default();
+    assert!(
+        message.contains("(schema path: /properties/age/minimum, value: -1)"),
+        "{}",
+        message
+    );
+    assert!(message.contains("(schema path: /required, value: {\"age\":-1})"));
+
+    let (_, body) = send(r#"{"age": -1}"#, "", false).await?;
+    assert!(body.starts_with("{\"error\":"), "{}", body);
+    assert!(!body.contains("schema path"), "{}", body);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pretty_print_errors_without_dev_mode() -> TestResult {
+    let (_, body) = send(r#"{"age": -1}"#, "  pretty_print_errors: true", false).await?;
+    assert!(body.starts_with("{\n  \"error\": "), "{}", body);
+    assert!(!body.contains("schema path"), "{}", body);
+    Ok(())
+}
@@ -0,0 +1,159 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{HeaderMap, HeaderValue, Method, StatusCode};
+use axum::response::Response;
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use serde_json::json;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const SPEC: &str = r#"
+openapi: 3.0.3
+info: { title: Demo, version: "1.0.0" }
+paths:
+  /api/session:
+    post:
+      responses:
+        '200':
+          description: ok
+          content:
+            application/json:
+              schema: { type: object, required: [user] }
+"#;
+
+fn write_spec() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("openapi.yaml");
+    fs::write(&path, SPEC).expect("write spec");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// An upstream setting two cookies on every response
+async fn cookie_upstream() -> MockServer {
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::any())
+        .respond_with(
+            ResponseTemplate::new(200)
+                .append_header("Set-Cookie", "session=abc; HttpOnly")
+                .append_header("Set-Cookie", "theme=dark")
+                .set_body_json(json!({"user": "ada"})),
+        )
+        .mount(&mock_server)
+        .await;
+    mock_server
+}
+
+/// Gateway state with a plain `GET /api/session` route and a
+/// `POST /api/session` whose responses are validated against the spec
+fn gateway(upstream: &str) -> TestResult<Arc<RwLock<AppState>>> {
+    let config = Config::from_str(&format!(
+        r#"
+routes:
+  - path: /api/session
+    method: GET
+    upstream: {upstream}
+  - path: /api/session
+    method: POST
+    openapi: {spec}
+    upstream: {upstream}
+"#,
+        upstream = upstream,
+        spec = write_spec().display()
+    ))?;
+    config.validate()?;
+    let http_client = build_http_client(&config.global.http_client);
+    Ok(Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    ))))
+}
+
+async fn send(
+    state: &Arc<RwLock<AppState>>,
+    method: Method,
+    headers: HeaderMap,
+) -> TestResult<Response> {
+    Ok(handle_request(
+        State(state.clone()),
+        method,
+        "/api/session".parse()?,
+        headers,
+        Body::empty(),
+    )
+    .await)
+}
+
+fn cookies(response: &Response) -> Vec<&str> {
+    response
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_both_cookies_reach_the_client() -> TestResult {
+    let upstream = cookie_upstream().await;
+    let state = gateway(&upstream.uri())?;
+
+    let response = send(&state, Method::GET, HeaderMap::new()).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        cookies(&response),
+        vec!["session=abc; HttpOnly", "theme=dark"]
+    );
+
+    // Rebuilt after OpenAPI response validation, still with both
+    let response = send(&state, Method::POST, HeaderMap::new()).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        cookies(&response),
+        vec!["session=abc; HttpOnly", "theme=dark"]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_repeated_request_headers_reach_the_upstream() -> TestResult {
+    let upstream = cookie_upstream().await;
+    let state = gateway(&upstream.uri())?;
+
+    let mut headers = HeaderMap::new();
+    headers.append("accept", HeaderValue::from_static("application/json"));
+    headers.append("accept", HeaderValue::from_static("text/plain"));
+    headers.append("x-tag", HeaderValue::from_static("one"));
+    headers.append("x-tag", HeaderValue::from_static("two"));
+    headers.append("x-tag", HeaderValue::from_static("three"));
+    // Not valid UTF-8, but a valid header value
+    headers.append("x-raw", HeaderValue::from_bytes(b"caf\xe9")?);
+    let response = send(&state, Method::GET, headers).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let requests = upstream.received_requests().await.unwrap_or_default();
+    assert_eq!(requests.len(), 1);
+    let values = |name: &str| -> Vec<Vec<u8>> {
+        requests[0]
+            .headers
+            .get_all(name)
+            .iter()
+            .map(|value| value.as_bytes().to_vec())
+            .collect()
+    };
+    let accept = values("accept");
+    let accept: Vec<&[u8]> = accept.iter().map(Vec::as_slice).collect();
+    assert_eq!(accept, vec![&b"application/json"[..], b"text/plain"]);
+    assert_eq!(values("x-tag").len(), 3);
+    assert_eq!(values("x-raw"), vec![b"caf\xe9".to_vec()]);
+    Ok(())
+}
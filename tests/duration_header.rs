@@ -0,0 +1,197 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use axum::response::Response;
+use schema_gateway::config::Config;
+use schema_gateway::handler::{
+    build_http_client, handle_request, AppState, DURATION_HEADER, UPSTREAM_DURATION_HEADER,
+};
+use schema_gateway::metrics::Metrics;
+use schema_gateway::router::build_router;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn write_schema() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("user.json");
+    fs::write(&path, r#"{"type": "object", "required": ["name"]}"#).expect("write schema");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// Gateway state with a validated route to an upstream that takes 50ms
+async fn gateway(global: &str) -> TestResult<(Arc<RwLock<AppState>>, MockServer)> {
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::method("POST"))
+        .respond_with(ResponseTemplate::new(201).set_delay(Duration::from_millis(50)))
+        .mount(&mock_server)
+        .await;
+
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: false
+{}
+
+routes:
+  - path: /api/users
+    method: POST
+    schema: {}
+    upstream: {}
+"#,
+        global,
+        write_schema().display(),
+        mock_server.uri()
+    ))?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+    Ok((state, mock_server))
+}
+
+async fn send(state: &Arc<RwLock<AppState>>, uri: &str, body: &str) -> TestResult<Response> {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))?;
+    let (parts, body) = request.into_parts();
+    Ok(handle_request(
+        State(state.clone()),
+        parts.method,
+        parts.uri,
+        parts.headers,
+        body,
+    )
+    .await)
+}
+
+fn duration_ms(response: &Response) -> Option<u64> {
+    header_ms(response, DURATION_HEADER)
+}
+
+fn header_ms(response: &Response, name: &str) -> Option<u64> {
+    response.headers().get(name)?.to_str().ok()?.parse().ok()
+}
+
+#[tokio::test]
+async fn test_duration_header_covers_the_upstream_round_trip() -> TestResult {
+    let (state, _upstream) = gateway("  report_duration_header: true").await?;
+
+    let response = send(&state, "/api/users", r#"{"name": "a"}"#).await?;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let millis = duration_ms(&response).ok_or("missing duration header")?;
+    assert!(millis >= 50, "{}ms", millis);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_duration_header_on_error_responses() -> TestResult {
+    let (state, _upstream) = gateway("  report_duration_header: true").await?;
+
+    let rejected = send(&state, "/api/users", "{}").await?;
+    assert_eq!(rejected.status(), StatusCode::BAD_REQUEST);
+    assert!(duration_ms(&rejected).is_some());
+
+    let not_found = send(&state, "/api/missing", "{}").await?;
+    assert_eq!(not_found.status(), StatusCode::NOT_FOUND);
+    assert!(duration_ms(&not_found).is_some());
+
+    // Rejections are timed in the histogram too
+    let metrics = state.read().await.metrics.gather()?;
+    assert!(
+        metrics
+            .contains(r#"http_request_duration_seconds_count{method="POST",route="/api/users",route_tags=""} 1"#),
+        "{}",
+        metrics
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_duration_header_is_off_by_default() -> TestResult {
+    let (state, _upstream) = gateway("").await?;
+
+    let response = send(&state, "/api/users", r#"{"name": "a"}"#).await?;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert!(!response.headers().contains_key(DURATION_HEADER));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upstream_duration_header() -> TestResult {
+    let (state, _upstream) =
+        gateway("  timing_headers: true\n  report_duration_header: true").await?;
+
+    let response = send(&state, "/api/users", r#"{"name": "a"}"#).await?;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let upstream = header_ms(&response, UPSTREAM_DURATION_HEADER).ok_or("missing header")?;
+    let total = duration_ms(&response).ok_or("missing duration header")?;
+    assert!(upstream >= 50, "{}ms", upstream);
+    assert!(upstream <= total, "{}ms upstream of {}ms", upstream, total);
+
+    // Requests that never reach the upstream don't get one
+    let rejected = send(&state, "/api/users", "{}").await?;
+    assert!(!rejected.headers().contains_key(UPSTREAM_DURATION_HEADER));
+
+    let (state, _upstream) = gateway("").await?;
+    let response = send(&state, "/api/users", r#"{"name": "a"}"#).await?;
+    assert!(!response.headers().contains_key(UPSTREAM_DURATION_HEADER));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upstream_duration_is_recorded_per_route() -> TestResult {
+    let (state, _upstream) = gateway("").await?;
+    let global = state.read().await.routing().config.global.clone();
+    let app = build_router(state, &global);
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let base_url = format!("http://{}", listener.local_addr()?);
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.expect("serve");
+    });
+
+    let client = reqwest::Client::new();
+    for body in [r#"{"name": "a"}"#, "{}"] {
+        client
+            .post(format!("{}/api/users", base_url))
+            .header("content-type", "application/json")
+            .body(body)
+            .send()
+            .await?;
+    }
+
+    // Only the request that was forwarded is sampled
+    let metrics = client
+        .get(format!("{}/metrics", base_url))
+        .send()
+        .await?
+        .text()
+        .await?;
+    assert!(
+        metrics.contains(
+            r#"upstream_request_duration_seconds_count{route="/api/users",route_tags=""} 1"#
+        ),
+        "{}",
+        metrics
+    );
+    assert!(
+        metrics.contains(
+            r#"upstream_request_duration_seconds_bucket{route="/api/users",route_tags="",le="0.025"} 0"#
+        ),
+        "{}",
+        metrics
+    );
+    Ok(())
+}
@@ -1,3 +1,4 @@
+use schema_gateway::error::{Error, OpenApiErrorStage};
 use schema_gateway::schema::SchemaCache;
 use schema_gateway::validation::validate;
 use std::fs;
@@ -33,7 +34,7 @@ fn test_error_includes_field_path() {
     }"#;
 
     let path = write_temp_schema_file(schema_json);
-    let mut cache = SchemaCache::new();
+    let cache = SchemaCache::new();
     let schema = cache.load(&path).expect("load schema");
 
     let invalid_json = serde_json::json!({
@@ -49,7 +50,7 @@ fn test_error_includes_field_path() {
     assert!(!result.valid, "expected validation to fail");
     assert!(!result.errors.is_empty(), "expected at least one error");
 
-    let error_text = result.errors.join(" ");
+    let error_text = result.messages().join(" ");
     // The error should include a path to the field, like "/user/profile/email"
     assert!(
         error_text.contains("user")
@@ -72,7 +73,7 @@ fn test_error_includes_expected_vs_actual() {
     }"#;
 
     let path = write_temp_schema_file(schema_json);
-    let mut cache = SchemaCache::new();
+    let cache = SchemaCache::new();
     let schema = cache.load(&path).expect("load schema");
 
     let invalid_json = serde_json::json!({
@@ -86,7 +87,7 @@ fn test_error_includes_expected_vs_actual() {
     assert!(!result.errors.is_empty(), "expected at least one error");
 
     // Errors should describe the type mismatch
-    let error_text = result.errors.join(" ").to_lowercase();
+    let error_text = result.messages().join(" ").to_lowercase();
     // The jsonschema crate typically includes type information in errors
     assert!(
         error_text.contains("type")
@@ -96,3 +97,16 @@ fn test_error_includes_expected_vs_actual() {
         result.errors
     );
 }
+
+#[test]
+fn test_openapi_error_names_its_stage() {
+    let error = Error::OpenApi {
+        path: PathBuf::from("specs/api.yaml"),
+        stage: OpenApiErrorStage::OperationNotFound,
+        message: "no OpenAPI operation for 'POST' /api/users".to_string(),
+    };
+    assert_eq!(
+        error.to_string(),
+        "OpenAPI operation lookup error in specs/api.yaml: no OpenAPI operation for 'POST' /api/users"
+    );
+}
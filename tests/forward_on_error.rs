@@ -1,8 +1,17 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request};
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
 use serde_json::json;
 use std::fs;
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
 use wiremock::{
-    matchers::{method, path},
+    matchers::{method, path, query_param},
     Mock, MockServer, ResponseTemplate,
 };
 
@@ -199,3 +208,51 @@ async fn test_error_header_content() {
     // All error messages should be human-readable and include context
     // This test documents the expected error header format
 }
+
+#[tokio::test]
+async fn test_forwarding_after_an_error_keeps_the_query_string() {
+    let schema = write_temp_schema_file(r#"{"type": "object", "required": ["email"]}"#);
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/users"))
+        .and(query_param("notify", "true"))
+        .respond_with(ResponseTemplate::new(201))
+        .expect(3)
+        .mount(&mock_server)
+        .await;
+
+    // A failing body, an unloadable schema, and a valid body
+    let routes = [
+        (schema.display().to_string(), "{}"),
+        ("/definitely/does/not/exist.json".to_string(), "{}"),
+        (
+            schema.display().to_string(),
+            r#"{"email": "a@example.com"}"#,
+        ),
+    ];
+    for (schema_path, body) in routes {
+        let config = Config::from_str(&format!(
+            "global:\n  forward_on_error: true\nroutes:\n  - path: /api/users\n    method: POST\n    schema: {}\n    upstream: {}\n",
+            schema_path,
+            mock_server.uri()
+        ))
+        .expect("parse config");
+        let http_client = build_http_client(&config.global.http_client);
+        let state = Arc::new(RwLock::new(AppState::new(
+            config,
+            http_client,
+            Arc::new(Metrics::new().expect("metrics")),
+        )));
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/api/users?notify=true")
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .expect("build request");
+        let (parts, body) = request.into_parts();
+        let response =
+            handle_request(State(state), parts.method, parts.uri, parts.headers, body).await;
+        assert_eq!(response.status(), 201, "schema {}", schema_path);
+    }
+}
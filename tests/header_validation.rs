@@ -0,0 +1,184 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{
+    matchers::{header, method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const TENANT_HEADER_SCHEMA: &str = r#"{
+    "type": "object",
+    "required": ["x-tenant-id"],
+    "properties": {
+        "x-tenant-id": {
+            "type": "string",
+            "pattern": "^[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}$"
+        },
+        "x-feature": {
+            "type": "array",
+            "items": {"type": "string"}
+        }
+    }
+}"#;
+
+fn write_temp_schema_file(contents: &str) -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("headers.json");
+    fs::write(&path, contents).expect("write temp schema");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+fn build_state(config_yaml: &str) -> TestResult<Arc<RwLock<AppState>>> {
+    let config = Config::from_str(config_yaml)?;
+    let http_client = build_http_client(&config.global.http_client);
+    let app_state = AppState::new(config, http_client, Arc::new(Metrics::new()?));
+    Ok(Arc::new(RwLock::new(app_state)))
+}
+
+async fn send(state: Arc<RwLock<AppState>>, request: Request<Body>) -> axum::response::Response {
+    let (parts, body) = request.into_parts();
+    handle_request(State(state), parts.method, parts.uri, parts.headers, body).await
+}
+
+#[tokio::test]
+async fn test_missing_required_header_rejected() -> TestResult {
+    let schema_path = write_temp_schema_file(TENANT_HEADER_SCHEMA);
+    let state = build_state(&format!(
+        r#"
+global:
+  forward_on_error: false
+
+routes:
+  - path: /api/users
+    method: POST
+    headers_schema: {}
+    upstream: http://localhost:9999
+"#,
+        schema_path.display()
+    ))?;
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/users")
+        .body(Body::from(r#"{"name": "Alice"}"#))?;
+
+    let response = send(state, request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    let body = String::from_utf8(body.to_vec())?;
+    assert!(
+        body.contains("Missing required header 'x-tenant-id'"),
+        "unexpected body: {}",
+        body
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_header_pattern_mismatch_rejected_before_body() -> TestResult {
+    let schema_path = write_temp_schema_file(TENANT_HEADER_SCHEMA);
+    let state = build_state(&format!(
+        r#"
+global:
+  forward_on_error: false
+
+routes:
+  - path: /api/users
+    method: POST
+    headers_schema: {}
+    upstream: http://localhost:9999
+"#,
+        schema_path.display()
+    ))?;
+
+    // Body is malformed JSON; the header failure must be reported first
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/users")
+        .header("X-Tenant-Id", "not-a-uuid")
+        .body(Body::from("{ not json"))?;
+
+    let response = send(state, request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    let body = String::from_utf8(body.to_vec())?;
+    assert!(body.contains("x-tenant-id"), "unexpected body: {}", body);
+    assert!(!body.contains("Invalid JSON"), "unexpected body: {}", body);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_valid_headers_forwarded() -> TestResult {
+    let schema_path = write_temp_schema_file(TENANT_HEADER_SCHEMA);
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/users"))
+        .and(header(
+            "x-tenant-id",
+            "123e4567-e89b-12d3-a456-426614174000",
+        ))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let state = build_state(&format!(
+        r#"
+global:
+  forward_on_error: false
+
+routes:
+  - path: /api/users
+    method: GET
+    headers_schema: {}
+    upstream: {}
+"#,
+        schema_path.display(),
+        mock_server.uri()
+    ))?;
+
+    // Multi-valued headers are exposed to the schema as arrays
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri("/api/users")
+        .header("X-Tenant-Id", "123e4567-e89b-12d3-a456-426614174000")
+        .header("X-Feature", "beta")
+        .header("X-Feature", "dark-mode")
+        .body(Body::empty())?;
+
+    let response = send(state, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    Ok(())
+}
+
+#[test]
+fn test_headers_schema_with_openapi_rejected() {
+    let config = Config::from_str(
+        r#"
+routes:
+  - path: /api/users
+    method: POST
+    headers_schema: ./headers.json
+    openapi: ./openapi.yaml
+    upstream: http://backend:3000
+"#,
+    )
+    .expect("parse config");
+
+    let err = config.validate().expect_err("expected validation error");
+    assert!(err.contains("headers_schema"), "unexpected error: {}", err);
+}
@@ -0,0 +1,107 @@
+use schema_gateway::config::{Config, HttpClientConfig};
+use schema_gateway::handler::build_http_client;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// Answer GET requests on `stream` with "ok" until the client closes it
+async fn serve_keep_alive(mut stream: TcpStream) -> TestResult {
+    loop {
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            let mut byte = [0u8; 1];
+            if stream.read(&mut byte).await? == 0 {
+                return Ok(());
+            }
+            head.push(byte[0]);
+        }
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok")
+            .await?;
+    }
+}
+
+/// An HTTP/1.1 upstream that counts the connections it accepts
+async fn counting_upstream() -> TestResult<(String, Arc<AtomicUsize>)> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("http://{}/", listener.local_addr()?);
+    let connections = Arc::new(AtomicUsize::new(0));
+    let counter = connections.clone();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            counter.fetch_add(1, Ordering::SeqCst);
+            tokio::spawn(serve_keep_alive(stream));
+        }
+    });
+    Ok((url, connections))
+}
+
+async fn connections_for_three_requests(config: &HttpClientConfig) -> TestResult<usize> {
+    let (url, connections) = counting_upstream().await?;
+    let client = build_http_client(config);
+    for _ in 0..3 {
+        let response = client.get(&url).send().await?;
+        assert_eq!(response.text().await?, "ok");
+    }
+    Ok(connections.load(Ordering::SeqCst))
+}
+
+#[tokio::test]
+async fn test_pooling_can_be_turned_off() -> TestResult {
+    assert_eq!(
+        connections_for_three_requests(&HttpClientConfig::default()).await?,
+        1
+    );
+
+    let unpooled = HttpClientConfig {
+        pool_max_idle_per_host: Some(0),
+        ..Default::default()
+    };
+    assert_eq!(connections_for_three_requests(&unpooled).await?, 3);
+    Ok(())
+}
+
+#[test]
+fn test_http_client_settings_parse_and_validate() -> TestResult {
+    let parse = |settings: &str| -> TestResult<Config> {
+        Ok(Config::from_str(&format!(
+            "global:\n  http_client:\n{}\nroutes:\n  - path: /api\n    method: GET\n    upstream: http://backend:3000\n",
+            settings
+        ))?)
+    };
+
+    let config = parse(
+        "    pool_max_idle_per_host: 16\n    pool_idle_timeout_ms: 30000\n    connect_timeout_ms: 2000\n    tcp_keepalive_ms: 60000",
+    )?;
+    config.validate()?;
+    assert_eq!(
+        config.global.http_client,
+        HttpClientConfig {
+            pool_max_idle_per_host: Some(16),
+            pool_idle_timeout_ms: Some(30000),
+            connect_timeout_ms: Some(2000),
+            tcp_keepalive_ms: Some(60000),
+            http2_prior_knowledge: false,
+        }
+    );
+
+    let error = parse("    connect_timeout_ms: 0")?.validate().unwrap_err();
+    assert_eq!(
+        error,
+        "global: http_client.connect_timeout_ms must be greater than 0"
+    );
+    let error = parse("    tcp_keepalive_ms: 0")?.validate().unwrap_err();
+    assert!(error.contains("tcp_keepalive_ms"), "{}", error);
+
+    // An idle timeout of zero only makes sense with pooling off
+    let error = parse("    pool_idle_timeout_ms: 0")?
+        .validate()
+        .unwrap_err();
+    assert!(error.contains("set pool_max_idle_per_host: 0"), "{}", error);
+    parse("    pool_idle_timeout_ms: 0\n    pool_max_idle_per_host: 0")?.validate()?;
+    Ok(())
+}
@@ -1,6 +1,15 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
 use serde_json::json;
 use std::fs;
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
 use wiremock::{
     matchers::{method, path},
     Mock, MockServer, ResponseTemplate,
@@ -254,14 +263,38 @@ async fn test_method_not_allowed() {
         .mount(&mock_server)
         .await;
 
-    // This test demonstrates the method-not-allowed flow:
-    // 1. Client sends GET request to /api/users
-    // 2. Gateway finds route for /api/users but method is POST
-    // 3. Method mismatch detected
-    // 4. Gateway returns 405 Method Not Allowed
-    // 5. Upstream is NOT called
-
-    // Since we don't have gateway implementation yet, verify mock expects 0 calls
-    // In real implementation, gateway would return 405 here
-    assert!(mock_server.address().port() > 0);
+    let config = Config::from_str(&format!(
+        r#"
+routes:
+  - path: /api/users
+    method: POST
+    upstream: {}
+"#,
+        mock_server.uri()
+    ))
+    .expect("parse config");
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new().expect("create metrics")),
+    )));
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri("/api/users")
+        .body(Body::empty())
+        .expect("build request");
+    let (parts, body) = request.into_parts();
+    let response = handle_request(State(state), parts.method, parts.uri, parts.headers, body).await;
+
+    // The path exists, so the gateway answers 405 listing the route's method
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(
+        response
+            .headers()
+            .get("allow")
+            .and_then(|value| value.to_str().ok()),
+        Some("POST")
+    );
 }
@@ -1,5 +1,5 @@
 use schema_gateway::schema::SchemaCache;
-use schema_gateway::validation::validate;
+use schema_gateway::validation::{error_labels, validate, ValidationError, ValidationErrorKind};
 use std::fs;
 use std::path::PathBuf;
 
@@ -24,7 +24,7 @@ fn test_valid_json_passes() {
     }"#;
 
     let path = write_temp_schema_file(schema_json);
-    let mut cache = SchemaCache::new();
+    let cache = SchemaCache::new();
     let schema = cache.load(&path).expect("load schema");
 
     let valid_json = serde_json::json!({
@@ -55,7 +55,7 @@ fn test_missing_required_field() {
     }"#;
 
     let path = write_temp_schema_file(schema_json);
-    let mut cache = SchemaCache::new();
+    let cache = SchemaCache::new();
     let schema = cache.load(&path).expect("load schema");
 
     let invalid_json = serde_json::json!({
@@ -67,7 +67,7 @@ fn test_missing_required_field() {
 
     assert!(!result.valid, "expected validation to fail");
     assert!(!result.errors.is_empty(), "expected at least one error");
-    let error_text = result.errors.join(" ");
+    let error_text = result.messages().join(" ");
     assert!(
         error_text.contains("email"),
         "expected error to mention 'email', got: {:?}",
@@ -87,7 +87,7 @@ fn test_wrong_type() {
     }"#;
 
     let path = write_temp_schema_file(schema_json);
-    let mut cache = SchemaCache::new();
+    let cache = SchemaCache::new();
     let schema = cache.load(&path).expect("load schema");
 
     let invalid_json = serde_json::json!({
@@ -98,7 +98,7 @@ fn test_wrong_type() {
 
     assert!(!result.valid, "expected validation to fail");
     assert!(!result.errors.is_empty(), "expected at least one error");
-    let error_text = result.errors.join(" ");
+    let error_text = result.messages().join(" ");
     assert!(
         error_text.contains("age"),
         "expected error to mention 'age', got: {:?}",
@@ -130,7 +130,7 @@ fn test_nested_validation() {
     }"#;
 
     let path = write_temp_schema_file(schema_json);
-    let mut cache = SchemaCache::new();
+    let cache = SchemaCache::new();
     let schema = cache.load(&path).expect("load schema");
 
     let invalid_json = serde_json::json!({
@@ -145,7 +145,7 @@ fn test_nested_validation() {
 
     assert!(!result.valid, "expected validation to fail");
     assert!(!result.errors.is_empty(), "expected at least one error");
-    let error_text = result.errors.join(" ");
+    let error_text = result.messages().join(" ");
     assert!(
         error_text.contains("zipcode"),
         "expected error to mention 'zipcode', got: {:?}",
@@ -170,7 +170,7 @@ fn test_array_validation() {
     }"#;
 
     let path = write_temp_schema_file(schema_json);
-    let mut cache = SchemaCache::new();
+    let cache = SchemaCache::new();
     let schema = cache.load(&path).expect("load schema");
 
     let invalid_json = serde_json::json!({
@@ -181,7 +181,7 @@ fn test_array_validation() {
 
     assert!(!result.valid, "expected validation to fail");
     assert!(!result.errors.is_empty(), "expected at least one error");
-    let error_text = result.errors.join(" ");
+    let error_text = result.messages().join(" ");
     assert!(
         error_text.contains("tags"),
         "expected error to mention 'tags', got: {:?}",
@@ -189,6 +189,95 @@ fn test_array_validation() {
     );
 }
 
+const ROOT_ARRAY_SCHEMA: &str = r#"{
+    "$schema": "https://json-schema.org/draft/2020-12/schema",
+    "type": "array",
+    "items": {
+        "type": "object",
+        "properties": {
+            "id": {"type": "integer"}
+        },
+        "required": ["id"]
+    },
+    "maxItems": 3
+}"#;
+
+#[test]
+fn test_root_array_passes() {
+    let path = write_temp_schema_file(ROOT_ARRAY_SCHEMA);
+    let cache = SchemaCache::new();
+    let schema = cache.load(&path).expect("load schema");
+
+    for valid_json in [
+        serde_json::json!([]),
+        serde_json::json!([{"id": 1}, {"id": 2}]),
+    ] {
+        let result = validate(&schema, &valid_json);
+        assert!(
+            result.valid,
+            "expected {} to pass, got: {:?}",
+            valid_json, result.errors
+        );
+    }
+}
+
+#[test]
+fn test_root_array_rejects_non_array() {
+    let path = write_temp_schema_file(ROOT_ARRAY_SCHEMA);
+    let cache = SchemaCache::new();
+    let schema = cache.load(&path).expect("load schema");
+
+    for invalid_json in [
+        serde_json::json!({"id": 1}),
+        serde_json::json!("[]"),
+        serde_json::json!(null),
+    ] {
+        let result = validate(&schema, &invalid_json);
+        assert!(!result.valid, "expected {} to fail", invalid_json);
+        assert_eq!(result.errors.len(), 1, "{:?}", result.errors);
+        assert_eq!(result.errors[0].kind, ValidationErrorKind::Type);
+        // Errors at the root have an empty pointer
+        assert_eq!(result.errors[0].instance_path, "");
+    }
+}
+
+#[test]
+fn test_root_array_item_errors_name_their_index() {
+    let path = write_temp_schema_file(ROOT_ARRAY_SCHEMA);
+    let cache = SchemaCache::new();
+    let schema = cache.load(&path).expect("load schema");
+
+    let invalid_json = serde_json::json!([{"id": 1}, {"id": "two"}, {}]);
+    let result = validate(&schema, &invalid_json);
+
+    assert!(!result.valid, "expected validation to fail");
+    let mut paths: Vec<&str> = result
+        .errors
+        .iter()
+        .map(|e| e.instance_path.as_str())
+        .collect();
+    paths.sort();
+    assert_eq!(paths, vec!["/1/id", "/2"], "{:?}", result.errors);
+
+    let messages = result.messages();
+    assert!(
+        messages.iter().any(|m| m.starts_with("/1/id: ")),
+        "expected a message for /1/id, got: {:?}",
+        messages
+    );
+    assert!(
+        messages.iter().any(|m| m.starts_with("/2: ")),
+        "expected a message for /2, got: {:?}",
+        messages
+    );
+
+    // Constraints on the array itself report the root
+    let too_long = serde_json::json!([{"id": 1}, {"id": 2}, {"id": 3}, {"id": 4}]);
+    let result = validate(&schema, &too_long);
+    assert_eq!(result.errors.len(), 1, "{:?}", result.errors);
+    assert_eq!(result.errors[0].instance_path, "");
+}
+
 #[test]
 fn test_multiple_validation_errors() {
     let schema_json = r#"{
@@ -203,7 +292,7 @@ fn test_multiple_validation_errors() {
     }"#;
 
     let path = write_temp_schema_file(schema_json);
-    let mut cache = SchemaCache::new();
+    let cache = SchemaCache::new();
     let schema = cache.load(&path).expect("load schema");
 
     let invalid_json = serde_json::json!({
@@ -221,7 +310,7 @@ fn test_multiple_validation_errors() {
         result.errors
     );
 
-    let error_text = result.errors.join(" ");
+    let error_text = result.messages().join(" ");
     // Should report multiple issues: wrong type for name, wrong type for age, missing email
     assert!(
         error_text.contains("name") || error_text.contains("age") || error_text.contains("email"),
@@ -229,3 +318,121 @@ fn test_multiple_validation_errors() {
         result.errors
     );
 }
+
+#[test]
+fn test_error_labels_from_validation_errors() {
+    let schema_json = r#"{
+        "type": "object",
+        "properties": {
+            "email": {"type": "string", "format": "email"},
+            "tags": {"type": "array", "items": {"type": "string", "minLength": 2}},
+            "profile": {
+                "type": "object",
+                "properties": {
+                    "address": {
+                        "type": "object",
+                        "properties": {"zip": {"type": "string", "pattern": "^[0-9]+$"}}
+                    }
+                }
+            }
+        },
+        "required": ["name"]
+    }"#;
+
+    let path = write_temp_schema_file(schema_json);
+    let schema = SchemaCache::new().load(&path).expect("load schema");
+    let result = validate(
+        &schema,
+        &serde_json::json!({
+            "email": "not-an-email",
+            "tags": ["ok", "x"],
+            "profile": {"address": {"zip": "abc"}}
+        }),
+    );
+
+    let mut labels: Vec<(String, &str)> = result
+        .errors
+        .iter()
+        .map(|error| error_labels(error, 3))
+        .collect();
+    labels.sort();
+    assert_eq!(
+        labels,
+        vec![
+            ("/".to_string(), "required"),
+            ("/email".to_string(), "format"),
+            ("/profile/address/zip".to_string(), "pattern"),
+            ("/tags/*".to_string(), "min_length"),
+        ]
+    );
+
+    // Deep paths are cut to the configured depth
+    let zip = result
+        .errors
+        .iter()
+        .find(|error| error.kind == ValidationErrorKind::Pattern)
+        .expect("pattern error");
+    assert_eq!(zip.instance_path, "/profile/address/zip");
+    assert_eq!(
+        zip.schema_path,
+        "/properties/profile/properties/address/properties/zip/pattern"
+    );
+    assert_eq!(
+        error_labels(zip, 2),
+        ("/profile/address".to_string(), "pattern")
+    );
+
+    // Keys the client chose don't become label values
+    let schema = SchemaCache::new()
+        .load(write_temp_schema_file(
+            r#"{
+                "type": "object",
+                "properties": {
+                    "metadata": {"type": "object", "additionalProperties": {"type": "string"}}
+                },
+                "patternProperties": {"^x-": {"type": "integer"}}
+            }"#,
+        ))
+        .expect("load schema");
+    let result = validate(
+        &schema,
+        &serde_json::json!({"metadata": {"a1b2c3": 1}, "x-random-key": "no"}),
+    );
+    let mut labels: Vec<(String, &str)> = result
+        .errors
+        .iter()
+        .map(|error| error_labels(error, 3))
+        .collect();
+    labels.sort();
+    assert_eq!(
+        labels,
+        vec![
+            ("/*".to_string(), "type"),
+            ("/metadata/*".to_string(), "type"),
+        ]
+    );
+
+    // Keywords without their own kind keep a label of their own
+    let schema = SchemaCache::new()
+        .load(write_temp_schema_file(
+            r#"{"type": "object", "minProperties": 1}"#,
+        ))
+        .expect("load schema");
+    let result = validate(&schema, &serde_json::json!({}));
+    assert_eq!(
+        result.errors[0].kind,
+        ValidationErrorKind::Other("min_properties".to_string())
+    );
+    assert_eq!(
+        error_labels(&result.errors[0], 3),
+        ("/".to_string(), "min_properties")
+    );
+
+    // Errors without a schema keyword are labelled by their kind alone
+    let error = ValidationError::new(
+        ValidationErrorKind::Other("other".to_string()),
+        "something else",
+    );
+    assert_eq!(error.to_string(), "something else");
+    assert_eq!(error_labels(&error, 3), ("/".to_string(), "other"));
+}
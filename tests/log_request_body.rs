@@ -0,0 +1,167 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn write_schema() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("note.json");
+    fs::write(&path, r#"{"type": "object", "required": ["text"]}"#).expect("write schema");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// Log output collected in memory
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().expect("log buffer").extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Captured {
+    fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().expect("log buffer")).into_owned()
+    }
+}
+
+/// Send `body` to a validated `POST /api/notes` with `global` settings and
+/// return the status and the DEBUG log lines written meanwhile
+async fn send(global: &str, body: &str) -> TestResult<(StatusCode, String)> {
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::any())
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&mock_server)
+        .await;
+
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: false
+{}
+
+routes:
+  - path: /api/notes
+    method: POST
+    schema: {}
+    upstream: {}
+"#,
+        global,
+        write_schema().display(),
+        mock_server.uri()
+    ))?;
+    config.validate()?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+
+    let captured = Captured::default();
+    let writer = captured.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/notes")
+        .header("content-type", "application/json")
+        .header("x-request-id", "req-42")
+        .body(Body::from(body.to_string()))?;
+    let (parts, body) = request.into_parts();
+    let response = handle_request(
+        State(state.clone()),
+        parts.method,
+        parts.uri,
+        parts.headers,
+        body,
+    )
+    .await;
+    Ok((response.status(), captured.contents()))
+}
+
+fn body_lines(logs: &str) -> Vec<&str> {
+    logs.lines()
+        .filter(|line| line.contains("Request body"))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_bodies_are_logged_when_enabled() -> TestResult {
+    let (status, logs) = send("  log_request_body: true", r#"{"wrong": 1}"#).await?;
+    // Logged before validation, so rejected bodies show up too
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let lines = body_lines(&logs);
+    assert_eq!(lines.len(), 1, "{}", logs);
+    assert!(lines[0].contains("DEBUG"), "{}", lines[0]);
+    assert!(lines[0].contains("request_id=req-42"), "{}", lines[0]);
+    assert!(lines[0].contains("path=/api/notes"), "{}", lines[0]);
+    assert!(lines[0].contains("pii_risk=true"), "{}", lines[0]);
+    assert!(lines[0].contains(r#"body={"wrong": 1}"#), "{}", lines[0]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_long_bodies_are_truncated() -> TestResult {
+    let text = "x".repeat(100);
+    let (status, logs) = send(
+        "  log_request_body: true\n  log_request_body_max_bytes: 20",
+        &format!(r#"{{"text": "{}"}}"#, text),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    let lines = body_lines(&logs);
+    assert_eq!(lines.len(), 1, "{}", logs);
+    assert!(
+        lines[0].contains(r#"body={"text": "xxxxxxxxxx… (truncated)"#),
+        "{}",
+        lines[0]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_bodies_are_not_logged_by_default() -> TestResult {
+    let (status, logs) = send("", r#"{"text": "secret"}"#).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body_lines(&logs).is_empty(), "{}", logs);
+    assert!(!logs.contains("secret"), "{}", logs);
+    Ok(())
+}
+
+#[test]
+fn test_log_request_body_max_bytes_must_be_positive() -> TestResult {
+    let config = Config::from_str(
+        "global:\n  log_request_body_max_bytes: 0\nroutes:\n  - path: /api\n    method: GET\n    upstream: http://backend:3000\n",
+    )?;
+    let error = config.validate().expect_err("zero limit");
+    assert!(
+        error.contains("log_request_body_max_bytes must be greater than 0"),
+        "{}",
+        error
+    );
+    Ok(())
+}
@@ -9,8 +9,7 @@ use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
 
 use schema_gateway::config::Config;
 use schema_gateway::handler::{build_http_client, handle_request, AppState};
-use schema_gateway::openapi::OpenApiCache;
-use schema_gateway::schema::SchemaCache;
+use std::str::FromStr;
 
 type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
@@ -54,7 +53,7 @@ routes:
         mock_server.uri()
     );
 
-    let config: Config = serde_yaml::from_str(&config_yaml)?;
+    let config = Config::from_str(&config_yaml)?;
 
     Mock::given(method("POST"))
         .respond_with(ResponseTemplate::new(200))
@@ -62,13 +61,12 @@ routes:
         .mount(&mock_server)
         .await;
 
-    let app_state = AppState {
+    let http_client = build_http_client(&config.global.http_client);
+    let app_state = AppState::new(
         config,
-        schema_cache: SchemaCache::new(),
-        openapi_cache: OpenApiCache::new(),
-        http_client: build_http_client(),
-        metrics: std::sync::Arc::new(schema_gateway::metrics::Metrics::new().unwrap()),
-    };
+        http_client,
+        std::sync::Arc::new(schema_gateway::metrics::Metrics::new().unwrap()),
+    );
 
     let state = Arc::new(RwLock::new(app_state));
 
@@ -114,7 +112,7 @@ routes:
         mock_server.uri()
     );
 
-    let config: Config = serde_yaml::from_str(&config_yaml)?;
+    let config = Config::from_str(&config_yaml)?;
 
     Mock::given(method("POST"))
         .respond_with(ResponseTemplate::new(200))
@@ -122,13 +120,12 @@ routes:
         .mount(&mock_server)
         .await;
 
-    let app_state = AppState {
+    let http_client = build_http_client(&config.global.http_client);
+    let app_state = AppState::new(
         config,
-        schema_cache: SchemaCache::new(),
-        openapi_cache: OpenApiCache::new(),
-        http_client: build_http_client(),
-        metrics: std::sync::Arc::new(schema_gateway::metrics::Metrics::new().unwrap()),
-    };
+        http_client,
+        std::sync::Arc::new(schema_gateway::metrics::Metrics::new().unwrap()),
+    );
 
     let state = Arc::new(RwLock::new(app_state));
 
@@ -160,15 +157,14 @@ routes:
     upstream: http://localhost:9999
 "#;
 
-    let config: Config = serde_yaml::from_str(config_yaml)?;
+    let config = Config::from_str(config_yaml)?;
 
-    let app_state = AppState {
+    let http_client = build_http_client(&config.global.http_client);
+    let app_state = AppState::new(
         config,
-        schema_cache: SchemaCache::new(),
-        openapi_cache: OpenApiCache::new(),
-        http_client: build_http_client(),
-        metrics: std::sync::Arc::new(schema_gateway::metrics::Metrics::new().unwrap()),
-    };
+        http_client,
+        std::sync::Arc::new(schema_gateway::metrics::Metrics::new().unwrap()),
+    );
 
     let state = Arc::new(RwLock::new(app_state));
 
@@ -224,7 +220,7 @@ routes:
         mock_server.uri()
     );
 
-    let config: Config = serde_yaml::from_str(&config_yaml)?;
+    let config = Config::from_str(&config_yaml)?;
 
     Mock::given(method("POST"))
         .respond_with(ResponseTemplate::new(200))
@@ -232,13 +228,12 @@ routes:
         .mount(&mock_server)
         .await;
 
-    let app_state = AppState {
+    let http_client = build_http_client(&config.global.http_client);
+    let app_state = AppState::new(
         config,
-        schema_cache: SchemaCache::new(),
-        openapi_cache: OpenApiCache::new(),
-        http_client: build_http_client(),
-        metrics: std::sync::Arc::new(schema_gateway::metrics::Metrics::new().unwrap()),
-    };
+        http_client,
+        std::sync::Arc::new(schema_gateway::metrics::Metrics::new().unwrap()),
+    );
 
     let state = Arc::new(RwLock::new(app_state));
 
@@ -1,12 +1,11 @@
-use axum::response::IntoResponse;
 use reqwest::Client;
 use schema_gateway::config::Config;
 use schema_gateway::handler::{build_http_client, AppState};
 use schema_gateway::metrics::Metrics;
-use schema_gateway::openapi::OpenApiCache;
-use schema_gateway::schema::SchemaCache;
+use schema_gateway::router::build_router;
 use std::fs;
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use wiremock::{matchers::path, Mock, MockServer, ResponseTemplate};
@@ -30,25 +29,15 @@ async fn create_test_server(config_content: &str) -> (MockServer, u16) {
     let config_path = write_temp_config(&config_content);
     let config = Config::from_file(&config_path).expect("load config");
 
+    let global = config.global.clone();
     let metrics = Arc::new(Metrics::new().expect("create metrics"));
-    let app_state = AppState {
-        config,
-        schema_cache: SchemaCache::new(),
-        openapi_cache: OpenApiCache::new(),
-        http_client: build_http_client(),
-        metrics: metrics.clone(),
-    };
+    let http_client = build_http_client(&config.global.http_client);
+    let app_state = AppState::new(config, http_client, metrics.clone());
 
     let shared_state = Arc::new(RwLock::new(app_state));
 
     // Start axum server
-    let app = axum::Router::new()
-        .route("/metrics", axum::routing::get(metrics_handler))
-        .route("/health", axum::routing::get(health_handler))
-        .route("/health/ready", axum::routing::get(readiness_handler))
-        .route("/health/live", axum::routing::get(liveness_handler))
-        .route("/*path", axum::routing::any(handler))
-        .with_state(shared_state);
+    let app = build_router(shared_state, &global);
 
     let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
         .await
@@ -66,62 +55,6 @@ async fn create_test_server(config_content: &str) -> (MockServer, u16) {
     (mock_server, port)
 }
 
-async fn metrics_handler(
-    axum::extract::State(state): axum::extract::State<
-        Arc<tokio::sync::RwLock<schema_gateway::handler::AppState>>,
-    >,
-) -> axum::response::Response {
-    let state_guard = state.read().await;
-    match state_guard.metrics.gather() {
-        Ok(output) => axum::response::Response::builder()
-            .status(axum::http::StatusCode::OK)
-            .header("Content-Type", "text/plain; version=0.0.4")
-            .body(axum::body::Body::from(output))
-            .unwrap(),
-        Err(e) => axum::response::Response::builder()
-            .status(axum::http::StatusCode::INTERNAL_SERVER_ERROR)
-            .body(axum::body::Body::from(format!("Error: {}", e)))
-            .unwrap(),
-    }
-}
-
-async fn health_handler() -> &'static str {
-    "OK"
-}
-
-async fn readiness_handler(
-    axum::extract::State(state): axum::extract::State<
-        Arc<tokio::sync::RwLock<schema_gateway::handler::AppState>>,
-    >,
-) -> axum::response::Response {
-    let state_guard = state.read().await;
-    if state_guard.config.routes.is_empty() {
-        return (
-            axum::http::StatusCode::SERVICE_UNAVAILABLE,
-            "No routes configured",
-        )
-            .into_response();
-    }
-    (axum::http::StatusCode::OK, "Ready").into_response()
-}
-
-async fn liveness_handler() -> &'static str {
-    "Alive"
-}
-
-async fn handler(
-    axum::extract::State(state): axum::extract::State<
-        Arc<tokio::sync::RwLock<schema_gateway::handler::AppState>>,
-    >,
-    method: axum::http::Method,
-    uri: axum::http::Uri,
-    headers: axum::http::HeaderMap,
-    body: axum::body::Body,
-) -> axum::response::Response {
-    schema_gateway::handler::handle_request(axum::extract::State(state), method, uri, headers, body)
-        .await
-}
-
 #[tokio::test]
 async fn test_metrics_endpoint_returns_prometheus_format() {
     let config = r#"
@@ -233,6 +166,69 @@ routes:
     assert_eq!(response.text().await.expect("read body"), "Alive");
 }
 
+#[tokio::test]
+async fn test_probes_fail_while_the_state_lock_is_held() {
+    let config = Config::from_str(
+        r#"
+global:
+  liveness_check_timeout_ms: 50
+routes:
+  - path: /api/test
+    method: GET
+    upstream: http://localhost:3000
+"#,
+    )
+    .expect("parse config");
+    let global = config.global.clone();
+    let metrics = Arc::new(Metrics::new().expect("create metrics"));
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(config, http_client, metrics)));
+    let app = build_router(state.clone(), &global);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind to port");
+    let port = listener.local_addr().expect("get local addr").port();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.expect("serve");
+    });
+
+    let client = Client::new();
+    let get = |path: &str| {
+        client
+            .get(format!("http://127.0.0.1:{}{}", port, path))
+            .send()
+    };
+
+    // A writer that never lets go, as a stuck reload would
+    let guard = state.write().await;
+    for probe in ["/health/live", "/health/ready"] {
+        let response = get(probe).await.expect("send request");
+        assert_eq!(response.status(), 503, "{}", probe);
+        assert_eq!(response.text().await.expect("read body"), "Lock contention");
+    }
+    // Plain `/health` doesn't look at the state
+    assert_eq!(get("/health").await.expect("send request").status(), 200);
+
+    drop(guard);
+    let response = get("/health/live").await.expect("send request");
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.text().await.expect("read body"), "Alive");
+}
+
+#[test]
+fn test_liveness_check_timeout_must_be_positive() {
+    let config = Config::from_str(
+        "global:\n  liveness_check_timeout_ms: 0\nroutes:\n  - path: /api\n    method: GET\n    upstream: http://backend:3000\n",
+    )
+    .expect("parse config");
+    let error = config.validate().expect_err("zero timeout");
+    assert!(
+        error.contains("liveness_check_timeout_ms must be greater than 0"),
+        "{}",
+        error
+    );
+}
+
 #[tokio::test]
 async fn test_metrics_increment_on_request() {
     let schema_json = r#"{
@@ -293,3 +289,270 @@ routes:
     assert!(metrics_body.contains("validation_attempts_total"));
     assert!(metrics_body.contains("validation_success_total"));
 }
+
+#[tokio::test]
+async fn test_openapi_cache_metrics() {
+    let spec = r#"
+openapi: 3.0.3
+info: { title: Demo, version: "1.0.0" }
+paths:
+  /api/users:
+    post:
+      requestBody:
+        content:
+          application/json:
+            schema: { type: object, required: [name] }
+"#;
+
+    let spec_dir = tempfile::tempdir().expect("create temp dir");
+    let spec_path = spec_dir.path().join("openapi.yaml");
+    fs::write(&spec_path, spec).expect("write spec");
+
+    let config = format!(
+        r#"
+global:
+  forward_on_error: false
+routes:
+  - path: /api/users
+    method: POST
+    openapi: {}
+    upstream: http://localhost:3000
+"#,
+        spec_path.display()
+    );
+
+    let (mock_server, port) = create_test_server(&config).await;
+    Mock::given(path("/api/users"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&mock_server)
+        .await;
+
+    let client = Client::new();
+    let base_url = format!("http://127.0.0.1:{}", port);
+    for _ in 0..3 {
+        let response = client
+            .post(format!("{}/api/users", base_url))
+            .json(&serde_json::json!({"name": "test"}))
+            .send()
+            .await
+            .expect("send request");
+        assert_eq!(response.status(), 200);
+    }
+
+    let metrics_body = client
+        .get(format!("{}/metrics", base_url))
+        .send()
+        .await
+        .expect("get metrics")
+        .text()
+        .await
+        .expect("read metrics");
+
+    // The first request compiles the plan; the spec is read once
+    for expected in [
+        "openapi_cache_misses_total 1",
+        "openapi_cache_hits_total 2",
+        "openapi_spec_loads_total 1",
+    ] {
+        assert!(
+            metrics_body.contains(expected),
+            "missing '{}' in metrics: {}",
+            expected,
+            metrics_body
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_validation_field_errors_are_labelled() {
+    let schema_json = r#"{
+        "type": "object",
+        "properties": {
+            "user": {
+                "type": "object",
+                "properties": {
+                    "address": {
+                        "type": "object",
+                        "properties": {"zip": {"type": "string"}}
+                    }
+                }
+            }
+        },
+        "required": ["name"]
+    }"#;
+
+    let schema_dir = tempfile::tempdir().expect("create temp dir");
+    let schema_path = schema_dir.path().join("schema.json");
+    fs::write(&schema_path, schema_json).expect("write schema");
+
+    let config = format!(
+        r#"
+global:
+  forward_on_error: false
+  max_field_label_depth: 2
+routes:
+  - path: /api/users
+    method: POST
+    schema: {}
+    upstream: http://localhost:3000
+"#,
+        schema_path.display()
+    );
+
+    let (_mock_server, port) = create_test_server(&config).await;
+    let client = Client::new();
+    let base_url = format!("http://127.0.0.1:{}", port);
+
+    let response = client
+        .post(format!("{}/api/users", base_url))
+        .json(&serde_json::json!({"user": {"address": {"zip": 12345}}}))
+        .send()
+        .await
+        .expect("send request");
+    assert_eq!(response.status(), 400);
+
+    let metrics_body = client
+        .get(format!("{}/metrics", base_url))
+        .send()
+        .await
+        .expect("get metrics")
+        .text()
+        .await
+        .expect("read metrics");
+
+    assert!(
+        metrics_body
+            .contains(r#"validation_field_errors_total{error_kind="required",field_path="/"} 1"#),
+        "metrics: {}",
+        metrics_body
+    );
+    assert!(
+        metrics_body.contains(
+            r#"validation_field_errors_total{error_kind="type",field_path="/user/address"} 1"#
+        ),
+        "metrics: {}",
+        metrics_body
+    );
+}
+
+#[tokio::test]
+async fn test_metrics_path_is_configurable() {
+    let config = r#"
+global:
+  metrics_path: /internal/metrics
+routes:
+  - path: /api/test
+    method: GET
+    upstream: http://localhost:3000
+"#;
+
+    let (_mock_server, port) = create_test_server(config).await;
+    let client = Client::new();
+
+    let response = client
+        .get(format!("http://127.0.0.1:{}/internal/metrics", port))
+        .send()
+        .await
+        .expect("send request");
+    assert_eq!(response.status(), 200);
+    let body = response.text().await.expect("read body");
+    assert!(body.contains("# HELP") || body.contains("# TYPE"));
+}
+
+#[tokio::test]
+async fn test_metrics_path_is_never_proxied() {
+    // A route that would otherwise swallow /metrics
+    let config = r#"
+global:
+  forward_on_error: true
+routes:
+  - path: /:anything
+    method: GET
+    upstream: http://localhost:3000
+"#;
+
+    let (mock_server, port) = create_test_server(config).await;
+    Mock::given(path("/metrics"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("from upstream"))
+        .expect(0)
+        .mount(&mock_server)
+        .await;
+
+    let response = Client::new()
+        .get(format!("http://127.0.0.1:{}/metrics", port))
+        .send()
+        .await
+        .expect("send request");
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.headers().get("Content-Type").unwrap(),
+        "text/plain; version=0.0.4"
+    );
+    assert_ne!(response.text().await.expect("read body"), "from upstream");
+}
+
+#[tokio::test]
+async fn test_metrics_token_requires_bearer() {
+    let config = r#"
+global:
+  metrics_token: s3cret
+routes:
+  - path: /api/test
+    method: GET
+    upstream: http://localhost:3000
+"#;
+
+    let (_mock_server, port) = create_test_server(config).await;
+    let client = Client::new();
+    let url = format!("http://127.0.0.1:{}/metrics", port);
+
+    let response = client.get(&url).send().await.expect("send request");
+    assert_eq!(response.status(), 401);
+    assert_eq!(
+        response.headers().get("WWW-Authenticate").unwrap(),
+        "Bearer"
+    );
+
+    let response = client
+        .get(&url)
+        .bearer_auth("wrong")
+        .send()
+        .await
+        .expect("send request");
+    assert_eq!(response.status(), 401);
+
+    let response = client
+        .get(&url)
+        .bearer_auth("s3cret")
+        .send()
+        .await
+        .expect("send request");
+    assert_eq!(response.status(), 200);
+}
+
+#[tokio::test]
+async fn test_metrics_disabled_falls_through_to_routes() {
+    let config = r#"
+global:
+  metrics_enabled: false
+routes:
+  - path: /metrics
+    method: GET
+    upstream: http://localhost:3000
+"#;
+
+    let (mock_server, port) = create_test_server(config).await;
+    Mock::given(path("/metrics"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("from upstream"))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let response = Client::new()
+        .get(format!("http://127.0.0.1:{}/metrics", port))
+        .send()
+        .await
+        .expect("send request");
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.text().await.expect("read body"), "from upstream");
+}
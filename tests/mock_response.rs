@@ -0,0 +1,228 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use axum::response::Response;
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn write_temp_dir(files: &[(&str, &str)]) -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    for (name, contents) in files {
+        fs::write(dir.path().join(name), contents).expect("write file");
+    }
+    let path = dir.path().to_path_buf();
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// An upstream that fails the test if it receives any request
+async fn unused_upstream() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(matchers::any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+    server
+}
+
+async fn send(config: &str, method: Method, uri: &str, body: &'static str) -> TestResult<Response> {
+    let config_path = write_temp_dir(&[("config.yaml", config)]).join("config.yaml");
+    let config = Config::from_file(&config_path)?;
+    config.validate()?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(body))?;
+    let (parts, body) = request.into_parts();
+    Ok(handle_request(State(state), parts.method, parts.uri, parts.headers, body).await)
+}
+
+async fn body_text(response: Response) -> TestResult<String> {
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+#[tokio::test]
+async fn test_mock_response_is_returned_without_upstream_call() -> TestResult {
+    let upstream = unused_upstream().await;
+    let config = format!(
+        r#"
+routes:
+  - path: /api/users
+    method: POST
+    mock_response:
+      status: 201
+      body: {{ id: 42, name: Alice }}
+      headers:
+        x-mocked: "true"
+
+  - path: /api/other
+    method: GET
+    upstream: {}
+"#,
+        upstream.uri()
+    );
+
+    let response = send(&config, Method::POST, "/api/users", r#"{"name":"Alice"}"#).await?;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_eq!(
+        response
+            .headers()
+            .get("x-mocked")
+            .and_then(|v| v.to_str().ok()),
+        Some("true")
+    );
+    assert_eq!(
+        response
+            .headers()
+            .get("content-type")
+            .and_then(|v| v.to_str().ok()),
+        Some("application/json")
+    );
+    let body: serde_json::Value = serde_json::from_str(&body_text(response).await?)?;
+    assert_eq!(body, serde_json::json!({"id": 42, "name": "Alice"}));
+
+    upstream.verify().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_mock_route_still_validates_requests() -> TestResult {
+    let upstream = unused_upstream().await;
+    let dir = write_temp_dir(&[("user.json", r#"{"type": "object", "required": ["name"]}"#)]);
+    let config = format!(
+        r#"
+global:
+  forward_on_error: false
+
+routes:
+  - path: /api/users
+    method: POST
+    schema: {}
+    mock_response:
+      status: 201
+
+  - path: /api/other
+    method: GET
+    upstream: {}
+"#,
+        dir.join("user.json").display(),
+        upstream.uri()
+    );
+
+    let valid = send(&config, Method::POST, "/api/users", r#"{"name":"Alice"}"#).await?;
+    assert_eq!(valid.status(), StatusCode::CREATED);
+    assert!(body_text(valid).await?.is_empty());
+
+    let invalid = send(&config, Method::POST, "/api/users", r#"{"age":3}"#).await?;
+    assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+
+    upstream.verify().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_mock_body_from_file() -> TestResult {
+    let dir = write_temp_dir(&[("users.json", r#"[{"id": 1}]"#)]);
+    fs::create_dir(dir.join("conf"))?;
+    let config = r#"
+routes:
+  - path: /api/users
+    method: GET
+    mock_response:
+      body:
+        file: ../users.json
+"#;
+    fs::write(dir.join("conf").join("config.yaml"), config)?;
+
+    let config = Config::from_file(dir.join("conf").join("config.yaml"))?;
+    config.validate()?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri("/api/users")
+        .body(Body::empty())?;
+    let (parts, body) = request.into_parts();
+    let response = handle_request(State(state), parts.method, parts.uri, parts.headers, body).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_text(response).await?, r#"[{"id": 1}]"#);
+    Ok(())
+}
+
+#[test]
+fn test_mock_response_validation() {
+    let validate = |route: &str| {
+        let config: Config = format!("routes:\n{}", route).parse().expect("parse config");
+        config.validate()
+    };
+
+    let both = validate(
+        r#"
+  - path: /api/users
+    method: GET
+    upstream: http://backend:3000
+    mock_response:
+      status: 200
+"#,
+    )
+    .expect_err("upstream and mock_response are exclusive");
+    assert!(
+        both.contains("Cannot specify both 'upstream' and 'mock_response'"),
+        "unexpected error: {}",
+        both
+    );
+
+    let neither = validate(
+        r#"
+  - path: /api/users
+    method: GET
+"#,
+    )
+    .expect_err("a route needs an upstream or a mock");
+    assert!(neither.contains("upstream cannot be empty"), "{}", neither);
+
+    let missing_file = validate(
+        r#"
+  - path: /api/users
+    method: GET
+    mock_response:
+      body: { file: /does/not/exist.json }
+"#,
+    )
+    .expect_err("missing body file");
+    assert!(missing_file.contains("does not exist"), "{}", missing_file);
+
+    let bad_status = validate(
+        r#"
+  - path: /api/users
+    method: GET
+    mock_response:
+      status: 42
+"#,
+    )
+    .expect_err("bad status");
+    assert!(bad_status.contains("status"), "{}", bad_status);
+}
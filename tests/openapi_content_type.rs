@@ -0,0 +1,136 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use axum::response::Response;
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const SPEC: &str = r#"
+openapi: 3.0.3
+info: { title: Demo, version: "1.0.0" }
+paths:
+  /api/users:
+    post:
+      requestBody:
+        content:
+          application/json:
+            schema: { type: object, required: [name] }
+          text/*: {}
+      responses:
+        '201': { description: created }
+  /api/ping:
+    post:
+      responses:
+        '200': { description: ok }
+"#;
+
+fn write_spec() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("openapi.yaml");
+    fs::write(&path, SPEC).expect("write spec");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// POST `body` with `content_type` to `uri`, on routes that enforce
+/// content types when `enforce` is set
+async fn send(
+    enforce: bool,
+    uri: &str,
+    content_type: Option<&str>,
+    body: &str,
+) -> TestResult<Response> {
+    let mock_server = MockServer::start().await;
+    Mock::given(wiremock::matchers::any())
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&mock_server)
+        .await;
+
+    let spec = write_spec();
+    let mut routes = String::new();
+    for path in ["/api/users", "/api/ping"] {
+        routes.push_str(&format!(
+            "  - path: {}\n    method: POST\n    openapi:\n      spec: {}\n      enforce_content_type: {}\n    upstream: {}\n",
+            path,
+            spec.display(),
+            enforce,
+            mock_server.uri()
+        ));
+    }
+    let config = Config::from_str(&format!(
+        "global:\n  forward_on_error: false\n\nroutes:\n{}",
+        routes
+    ))?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+
+    let mut request = Request::builder().method(Method::POST).uri(uri);
+    if let Some(content_type) = content_type {
+        request = request.header("content-type", content_type);
+    }
+    let (parts, body) = request.body(Body::from(body.to_string()))?.into_parts();
+    Ok(handle_request(State(state), parts.method, parts.uri, parts.headers, body).await)
+}
+
+#[tokio::test]
+async fn test_undeclared_content_type_rejected_when_enforced() -> TestResult {
+    let valid = r#"{"name": "a"}"#;
+
+    let response = send(false, "/api/users", Some("application/xml"), valid).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = send(true, "/api/users", Some("application/xml"), valid).await?;
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    let body = String::from_utf8_lossy(&body);
+    assert!(
+        body.contains("'application/xml'") && body.contains("application/json, text/*"),
+        "{}",
+        body
+    );
+
+    let response = send(true, "/api/users", None, valid).await?;
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_declared_content_types_accepted() -> TestResult {
+    let valid = r#"{"name": "a"}"#;
+    for content_type in [
+        "application/json",
+        "Application/JSON; charset=utf-8",
+        "application/vnd.api+json",
+    ] {
+        let response = send(true, "/api/users", Some(content_type), valid).await?;
+        assert_eq!(response.status(), StatusCode::OK, "{}", content_type);
+    }
+
+    let response = send(true, "/api/users", Some("text/plain"), valid).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_content_type_not_checked_without_body_or_request_body() -> TestResult {
+    // Still a 400, for the missing JSON body
+    let response = send(true, "/api/users", Some("application/xml"), "").await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = send(true, "/api/ping", Some("application/xml"), "<ping/>").await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    Ok(())
+}
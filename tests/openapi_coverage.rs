@@ -0,0 +1,223 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use schema_gateway::admin::{build_admin_router, coverage_report};
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const SPEC: &str = r#"
+openapi: 3.0.3
+info: { title: Demo, version: "1.0.0" }
+paths:
+  /api/users:
+    post:
+      operationId: createUser
+      requestBody:
+        required: true
+        content:
+          application/json:
+            schema: { type: object, required: [name] }
+      responses:
+        '201': { description: created }
+  /api/users/{id}:
+    get:
+      parameters:
+        - { name: id, in: path, required: true, schema: { type: integer } }
+      responses:
+        '200': { description: ok }
+    delete:
+      operationId: deleteUser
+      responses:
+        '204': { description: deleted }
+"#;
+
+fn write_spec() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("openapi.yaml");
+    fs::write(&path, SPEC).expect("write spec");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// State for a config routing the spec's operations to a mock upstream,
+/// with `global` settings
+async fn gateway(global: &str) -> TestResult<(Arc<RwLock<AppState>>, PathBuf, MockServer)> {
+    let mock_server = MockServer::start().await;
+    Mock::given(wiremock::matchers::any())
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&mock_server)
+        .await;
+
+    let spec = write_spec();
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: false
+{}
+
+routes:
+  - path: /api/users
+    method: POST
+    openapi: {spec}
+    upstream: {upstream}
+  - path: /api/users/:id
+    method: GET
+    openapi: {spec}
+    upstream: {upstream}
+"#,
+        global,
+        spec = spec.display(),
+        upstream = mock_server.uri()
+    ))?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+    Ok((state, spec, mock_server))
+}
+
+async fn send(
+    state: &Arc<RwLock<AppState>>,
+    method: Method,
+    uri: &str,
+    body: &str,
+) -> TestResult<StatusCode> {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))?;
+    let (parts, body) = request.into_parts();
+    let response = handle_request(
+        State(state.clone()),
+        parts.method,
+        parts.uri,
+        parts.headers,
+        body,
+    )
+    .await;
+    Ok(response.status())
+}
+
+async fn exercise(state: &Arc<RwLock<AppState>>) -> TestResult {
+    assert_eq!(
+        send(state, Method::POST, "/api/users", r#"{"name": "a"}"#).await?,
+        StatusCode::OK
+    );
+    assert_eq!(
+        send(state, Method::POST, "/api/users", "{}").await?,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        send(state, Method::POST, "/api/users", "not json").await?,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        send(state, Method::GET, "/api/users/7", "").await?,
+        StatusCode::OK
+    );
+    assert_eq!(
+        send(state, Method::GET, "/api/users/abc", "").await?,
+        StatusCode::BAD_REQUEST
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_coverage_counts_each_operation() -> TestResult {
+    let (state, spec, _upstream) = gateway("").await?;
+    exercise(&state).await?;
+
+    let report = coverage_report(&state).await;
+    assert_eq!(
+        report,
+        json!({
+            "specs": [{
+                "spec": spec.display().to_string(),
+                "covered": 2,
+                "total": 3,
+                "operations": {
+                    "createUser": {
+                        "method": "POST", "path": "/api/users",
+                        "validated": 3, "passed": 1, "failed": 2
+                    },
+                    "GET /api/users/{id}": {
+                        "method": "GET", "path": "/api/users/{id}",
+                        "validated": 2, "passed": 1, "failed": 1
+                    },
+                    "deleteUser": {
+                        "method": "DELETE", "path": "/api/users/{id}",
+                        "validated": 0, "passed": 0, "failed": 0
+                    }
+                }
+            }]
+        })
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_coverage_endpoint() -> TestResult {
+    let (state, _spec, _upstream) = gateway("").await?;
+    exercise(&state).await?;
+
+    let app = build_admin_router(state.clone(), PathBuf::from("config.yml"));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("http://{}/admin/openapi/coverage", listener.local_addr()?);
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.expect("serve admin");
+    });
+
+    let response = reqwest::get(&url).await?;
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await?;
+    assert_eq!(body, coverage_report(&state).await);
+    assert_eq!(
+        body["specs"][0]["operations"]["createUser"]["failed"],
+        json!(2)
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_operation_metrics_are_opt_in() -> TestResult {
+    let (state, _spec, _upstream) = gateway("").await?;
+    exercise(&state).await?;
+    let metrics = state.read().await.metrics.gather()?;
+    assert!(
+        !metrics.contains("openapi_operation_validations_total{"),
+        "{}",
+        metrics
+    );
+
+    let (state, _spec, _upstream) = gateway("  openapi_operation_metrics: true").await?;
+    exercise(&state).await?;
+    let metrics = state.read().await.metrics.gather()?;
+    for (labels, count) in [
+        (r#"operation="createUser",result="failed""#, " 2"),
+        (r#"operation="createUser",result="passed""#, " 1"),
+        (r#"operation="GET /api/users/{id}",result="passed""#, " 1"),
+    ] {
+        assert!(
+            metrics
+                .lines()
+                .any(|line| line.contains(labels) && line.ends_with(count)),
+            "{}\n{}",
+            labels,
+            metrics
+        );
+    }
+    Ok(())
+}
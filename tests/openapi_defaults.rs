@@ -0,0 +1,146 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, Request as ReceivedRequest, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const SPEC: &str = r#"
+openapi: 3.0.3
+info: { title: Demo, version: "1.0.0" }
+paths:
+  /api/items:
+    get:
+      parameters:
+        - { name: limit, in: query, schema: { type: integer, default: 20, maximum: 100 } }
+        - { name: sort, in: query, schema: { type: string, default: name } }
+        - name: fields
+          in: query
+          schema: { type: array, items: { type: string }, default: [id, name] }
+        - name: tags
+          in: query
+          explode: false
+          schema: { type: array, items: { type: string }, default: [a, b] }
+        - { name: X-Page-Size, in: header, schema: { type: integer, default: 50 } }
+        - { name: offset, in: query, schema: { type: integer } }
+  /api/broken:
+    get:
+      parameters:
+        - { name: limit, in: query, schema: { type: integer, default: 20, maximum: 10 } }
+"#;
+
+fn write_spec() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("openapi.yaml");
+    fs::write(&path, SPEC).expect("write spec");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// Send `GET uri` through a route for `path` with `apply_defaults`,
+/// returning the status and the request the upstream received
+async fn send(
+    path: &str,
+    uri: &str,
+    apply_defaults: bool,
+    forward_on_error: bool,
+) -> TestResult<(StatusCode, Option<ReceivedRequest>)> {
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::method("GET"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&mock_server)
+        .await;
+
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: {}
+
+routes:
+  - path: {}
+    method: GET
+    openapi:
+      spec: {}
+      apply_defaults: {}
+    upstream: {}
+"#,
+        forward_on_error,
+        path,
+        write_spec().display(),
+        apply_defaults,
+        mock_server.uri()
+    ))?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(uri)
+        .body(Body::empty())?;
+    let (parts, body) = request.into_parts();
+    let response = handle_request(State(state), parts.method, parts.uri, parts.headers, body).await;
+    let received = mock_server
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .next();
+    Ok((response.status(), received))
+}
+
+#[tokio::test]
+async fn test_defaults_are_added_to_forwarded_request() -> TestResult {
+    let (status, received) = send("/api/items", "/api/items?offset=10", true, false).await?;
+    assert_eq!(status, StatusCode::OK);
+    let received = received.ok_or("upstream not called")?;
+
+    assert_eq!(
+        received.url.query(),
+        Some("offset=10&limit=20&sort=name&fields=id&fields=name&tags=a%2Cb")
+    );
+    assert_eq!(
+        received
+            .headers
+            .get("x-page-size")
+            .and_then(|v| v.to_str().ok()),
+        Some("50")
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_defaults_are_validated() -> TestResult {
+    // The spec's default of 20 breaks its own maximum
+    let (status, received) = send("/api/broken", "/api/broken", true, false).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(received.is_none());
+
+    // Permissive mode still forwards the default
+    let (status, received) = send("/api/broken", "/api/broken", true, true).await?;
+    assert_eq!(status, StatusCode::OK);
+    let received = received.ok_or("upstream not called")?;
+    assert_eq!(received.url.query(), Some("limit=20"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_request_is_untouched_without_apply_defaults() -> TestResult {
+    let (status, received) = send("/api/items", "/api/items?limit=5", false, false).await?;
+    assert_eq!(status, StatusCode::OK);
+    let received = received.ok_or("upstream not called")?;
+    assert_eq!(received.url.query(), Some("limit=5"));
+    assert!(received.headers.get("x-page-size").is_none());
+    Ok(())
+}
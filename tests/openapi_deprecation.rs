@@ -0,0 +1,164 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use axum::response::Response;
+use schema_gateway::config::Config;
+use schema_gateway::handler::{
+    build_http_client, handle_request, AppState, DEPRECATION_HEADER, SUNSET_HEADER,
+};
+use schema_gateway::metrics::Metrics;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const SPEC: &str = r#"
+openapi: 3.0.3
+info: { title: Demo, version: "1.0.0" }
+paths:
+  /api/users:
+    get:
+      operationId: listUsers
+      parameters:
+        - { name: page, in: query, schema: { type: integer } }
+        - { name: offset, in: query, deprecated: true, schema: { type: integer, default: 0 } }
+      responses:
+        '200': { description: ok }
+  /api/legacy:
+    get:
+      operationId: legacyUsers
+      deprecated: true
+      x-sunset: "Sat, 01 Nov 2025 00:00:00 GMT"
+      responses:
+        '200': { description: ok }
+"#;
+
+fn write_spec() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("openapi.yaml");
+    fs::write(&path, SPEC).expect("write spec");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// Gateway state routing both operations of the spec, with `route_config`
+/// added to each route
+async fn gateway(route_config: &str) -> TestResult<(Arc<RwLock<AppState>>, MockServer)> {
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::method("GET"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&mock_server)
+        .await;
+
+    let spec = write_spec();
+    let config = Config::from_str(&format!(
+        r#"
+routes:
+  - path: /api/users
+    method: GET
+    openapi:
+      spec: {spec}
+      apply_defaults: true
+    upstream: {upstream}
+{route_config}
+  - path: /api/legacy
+    method: GET
+    openapi: {spec}
+    upstream: {upstream}
+{route_config}
+"#,
+        spec = spec.display(),
+        upstream = mock_server.uri(),
+        route_config = route_config
+    ))?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+    Ok((state, mock_server))
+}
+
+async fn get(state: &Arc<RwLock<AppState>>, uri: &str) -> TestResult<Response> {
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(uri)
+        .body(Body::empty())?;
+    let (parts, body) = request.into_parts();
+    let response = handle_request(
+        State(state.clone()),
+        parts.method,
+        parts.uri,
+        parts.headers,
+        body,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    Ok(response)
+}
+
+fn header<'a>(response: &'a Response, name: &str) -> Option<&'a str> {
+    response.headers().get(name)?.to_str().ok()
+}
+
+#[tokio::test]
+async fn test_deprecated_operation_gets_deprecation_and_sunset() -> TestResult {
+    let (state, _upstream) = gateway("").await?;
+
+    let response = get(&state, "/api/legacy").await?;
+    assert_eq!(header(&response, DEPRECATION_HEADER), Some("true"));
+    assert_eq!(
+        header(&response, SUNSET_HEADER),
+        Some("Sat, 01 Nov 2025 00:00:00 GMT")
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deprecated_parameter_only_when_supplied() -> TestResult {
+    let (state, _upstream) = gateway("").await?;
+
+    // The default the gateway adds for `offset` doesn't count
+    let response = get(&state, "/api/users?page=2").await?;
+    assert!(!response.headers().contains_key(DEPRECATION_HEADER));
+
+    let response = get(&state, "/api/users?offset=20").await?;
+    assert_eq!(header(&response, DEPRECATION_HEADER), Some("true"));
+    assert!(!response.headers().contains_key(SUNSET_HEADER));
+
+    let metrics = state.read().await.metrics.gather()?;
+    assert!(
+        metrics.contains(r#"deprecated_requests_total{operation="listUsers"} 1"#),
+        "{}",
+        metrics
+    );
+    assert!(
+        !metrics.contains(r#"operation="legacyUsers""#),
+        "{}",
+        metrics
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deprecation_headers_can_be_disabled_per_route() -> TestResult {
+    let (state, _upstream) = gateway("    config:\n      deprecation_headers: false").await?;
+
+    let response = get(&state, "/api/legacy").await?;
+    assert!(!response.headers().contains_key(DEPRECATION_HEADER));
+    assert!(!response.headers().contains_key(SUNSET_HEADER));
+
+    // Still counted
+    let metrics = state.read().await.metrics.gather()?;
+    assert!(
+        metrics.contains(r#"deprecated_requests_total{operation="legacyUsers"} 1"#),
+        "{}",
+        metrics
+    );
+    Ok(())
+}
@@ -0,0 +1,182 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const SPEC: &str = r#"
+openapi: 3.0.3
+info: { title: Demo, version: "1.0.0" }
+paths:
+  /api/users:
+    post:
+      x-schema-gateway:
+        forward_on_error: true
+        add_error_header: false
+        not_a_setting: 1
+      requestBody:
+        required: true
+        content:
+          application/json:
+            schema: { type: object, required: [name] }
+      responses:
+        '201': { description: created }
+    put:
+      requestBody:
+        required: true
+        content:
+          application/json:
+            schema: { type: object, required: [name] }
+      responses:
+        '200': { description: ok }
+  /api/posts:
+    x-schema-gateway:
+      forward_on_error: true
+    post:
+      requestBody:
+        required: true
+        content:
+          application/json:
+            schema: { type: object, required: [title] }
+      responses:
+        '201': { description: created }
+    put:
+      x-schema-gateway:
+        forward_on_error: false
+      requestBody:
+        required: true
+        content:
+          application/json:
+            schema: { type: object, required: [title] }
+      responses:
+        '200': { description: ok }
+"#;
+
+fn write_spec() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("openapi.yaml");
+    fs::write(&path, SPEC).expect("write spec");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// Gateway routing each of the spec's operations upstream with
+/// `forward_on_error: false` globally; `route_config` is added to the
+/// POST /api/users route
+async fn gateway(route_config: &str) -> TestResult<(Arc<RwLock<AppState>>, MockServer)> {
+    let mock_server = MockServer::start().await;
+    Mock::given(wiremock::matchers::any())
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&mock_server)
+        .await;
+
+    let spec = write_spec();
+    let mut routes = String::new();
+    for (path, method) in [
+        ("/api/users", "POST"),
+        ("/api/users", "PUT"),
+        ("/api/posts", "POST"),
+        ("/api/posts", "PUT"),
+    ] {
+        routes.push_str(&format!(
+            "  - path: {}\n    method: {}\n    openapi: {}\n    upstream: {}\n",
+            path,
+            method,
+            spec.display(),
+            mock_server.uri()
+        ));
+        if (path, method) == ("/api/users", "POST") {
+            routes.push_str(route_config);
+        }
+    }
+    let config = Config::from_str(&format!(
+        "global:\n  forward_on_error: false\n\nroutes:\n{}",
+        routes
+    ))?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+    Ok((state, mock_server))
+}
+
+async fn send_invalid(
+    state: &Arc<RwLock<AppState>>,
+    method: Method,
+    uri: &str,
+) -> TestResult<axum::response::Response> {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(Body::from("{}"))?;
+    let (parts, body) = request.into_parts();
+    Ok(handle_request(
+        State(state.clone()),
+        parts.method,
+        parts.uri,
+        parts.headers,
+        body,
+    )
+    .await)
+}
+
+/// The `X-Gateway-Error` header on the last request the upstream received
+async fn last_error_header(upstream: &MockServer) -> TestResult<Option<String>> {
+    let received = upstream.received_requests().await.unwrap_or_default();
+    let request = received.last().ok_or("upstream not called")?;
+    Ok(request
+        .headers
+        .get("x-gateway-error")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string))
+}
+
+#[tokio::test]
+async fn test_operation_extension_overrides_only_that_operation() -> TestResult {
+    let (state, upstream) = gateway("").await?;
+
+    // The extension forwards invalid POSTs, without the error header
+    let response = send_invalid(&state, Method::POST, "/api/users").await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(last_error_header(&upstream).await?, None);
+
+    // The sibling operation keeps the global strict mode
+    let response = send_invalid(&state, Method::PUT, "/api/users").await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_path_item_extension_is_a_fallback() -> TestResult {
+    let (state, upstream) = gateway("").await?;
+
+    let response = send_invalid(&state, Method::POST, "/api/posts").await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(last_error_header(&upstream).await?.is_some());
+
+    // The operation's own extension beats the path item's
+    let response = send_invalid(&state, Method::PUT, "/api/posts").await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_route_config_wins_over_extension() -> TestResult {
+    let (state, _upstream) = gateway("    config:\n      forward_on_error: false\n").await?;
+
+    let response = send_invalid(&state, Method::POST, "/api/users").await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    Ok(())
+}
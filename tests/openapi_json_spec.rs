@@ -0,0 +1,119 @@
+use axum::http::{HeaderMap, Method};
+use schema_gateway::openapi::OpenApiCache;
+use serde_json::json;
+use std::fs;
+use std::path::PathBuf;
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const SPEC: &str = r#"{
+  "openapi": "3.0.3",
+  "info": { "title": "Demo", "version": "1.0.0" },
+  "paths": {
+    "/users": {
+      "post": {
+        "requestBody": {
+          "required": true,
+          "content": {
+            "application/json": {
+              "schema": {
+                "type": "object",
+                "required": ["name"],
+                "properties": { "name": { "type": "string" } }
+              }
+            }
+          }
+        }
+      }
+    }
+  }
+}"#;
+
+fn write_spec(name: &str, contents: &str) -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join(name);
+    fs::write(&path, contents).expect("write spec");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+#[test]
+fn test_json_spec_is_loaded() -> TestResult {
+    let cache = OpenApiCache::new();
+    let plan = cache.load_operation(
+        write_spec("openapi.JSON", SPEC),
+        "/users",
+        &Method::POST,
+        None,
+    )?;
+
+    let headers = HeaderMap::new();
+    let result = plan.validate_request(&json!({"name": "Ada"}), None, "/users", &headers);
+    assert!(result.valid, "{:?}", result.errors);
+    let result = plan.validate_request(&json!({}), None, "/users", &headers);
+    assert!(!result.valid);
+
+    let entries = cache.snapshot();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].version.as_deref(), Some("3.0.3"));
+    Ok(())
+}
+
+#[test]
+fn test_swagger_version_is_recorded() -> TestResult {
+    let cache = OpenApiCache::new();
+    let spec = SPEC.replace(r#""openapi": "3.0.3""#, r#""swagger": "2.0""#);
+    cache.load_operation(
+        write_spec("swagger.json", &spec),
+        "/users",
+        &Method::POST,
+        None,
+    )?;
+    assert_eq!(cache.snapshot()[0].version.as_deref(), Some("2.0"));
+    Ok(())
+}
+
+#[test]
+fn test_parse_errors_name_the_format() {
+    let cache = OpenApiCache::new();
+
+    // Valid YAML, but not JSON: `.json` files aren't parsed as YAML
+    let error = cache
+        .load_operation(
+            write_spec("openapi.json", "openapi: 3.0.3\npaths: {}\n"),
+            "/users",
+            &Method::POST,
+            None,
+        )
+        .err()
+        .expect("YAML in a .json file");
+    assert!(
+        error.to_string().contains("Invalid JSON OpenAPI spec"),
+        "{}",
+        error
+    );
+
+    let error = cache
+        .load_operation(
+            write_spec("openapi.yaml", "paths: [unclosed\n"),
+            "/users",
+            &Method::POST,
+            None,
+        )
+        .err()
+        .expect("broken YAML");
+    assert!(
+        error.to_string().contains("Invalid YAML OpenAPI spec"),
+        "{}",
+        error
+    );
+
+    // Unknown extensions fall back to YAML, which also reads JSON
+    let plan = cache.load_operation(
+        write_spec("openapi.spec", SPEC),
+        "/users",
+        &Method::POST,
+        None,
+    );
+    assert!(plan.is_ok(), "{:?}", plan.err());
+}
@@ -0,0 +1,279 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use schema_gateway::config::Config;
+use schema_gateway::example::generate;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const SPEC: &str = r#"
+openapi: 3.0.3
+info: { title: Demo, version: "1.0.0" }
+paths:
+  /api/users:
+    post:
+      requestBody:
+        required: true
+        content:
+          application/json:
+            schema: { type: object, required: [name] }
+      responses:
+        '400': { description: bad request }
+        '202': { description: accepted }
+        '201':
+          description: created
+          content:
+            application/json:
+              schema: { $ref: '#/components/schemas/User' }
+  /api/users/{id}:
+    get:
+      parameters:
+        - { name: id, in: path, required: true, schema: { type: integer } }
+      responses:
+        '200':
+          description: ok
+          content:
+            application/json:
+              schema: { $ref: '#/components/schemas/User' }
+              examples:
+                alice:
+                  value: { id: 7, name: alice, createdAt: '2024-01-01T00:00:00Z' }
+  /api/status:
+    get:
+      responses:
+        '200':
+          description: ok
+          content:
+            text/plain:
+              schema: { type: string }
+              example: all good
+components:
+  schemas:
+    User:
+      type: object
+      required: [id, name, createdAt]
+      properties:
+        id: { type: integer, minimum: 1 }
+        name: { type: string }
+        email: { type: string, format: email }
+        createdAt: { type: string, format: date-time }
+"#;
+
+fn write_spec() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("openapi.yaml");
+    fs::write(&path, SPEC).expect("write spec");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// Gateway state with mocked routes for each of the spec's operations
+fn gateway(forward_on_error: bool) -> TestResult<Arc<RwLock<AppState>>> {
+    let spec = write_spec();
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: {forward_on_error}
+
+routes:
+  - path: /api/users
+    method: POST
+    openapi: {spec}
+    mock: true
+  - path: /api/users/:id
+    method: GET
+    openapi: {spec}
+    mock: true
+  - path: /api/status
+    method: GET
+    openapi: {spec}
+    mock: true
+"#,
+        forward_on_error = forward_on_error,
+        spec = spec.display()
+    ))?;
+    config.validate()?;
+    let http_client = build_http_client(&config.global.http_client);
+    Ok(Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    ))))
+}
+
+async fn send(
+    state: &Arc<RwLock<AppState>>,
+    method: Method,
+    uri: &str,
+    body: &str,
+) -> TestResult<(StatusCode, Option<String>, Vec<u8>)> {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))?;
+    let (parts, body) = request.into_parts();
+    let response = handle_request(
+        State(state.clone()),
+        parts.method,
+        parts.uri,
+        parts.headers,
+        body,
+    )
+    .await;
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get("content-type")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    Ok((status, content_type, body.to_vec()))
+}
+
+#[tokio::test]
+async fn test_mock_uses_the_declared_example() -> TestResult {
+    let state = gateway(false)?;
+
+    let (status, content_type, body) = send(&state, Method::GET, "/api/users/7", "").await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type.as_deref(), Some("application/json"));
+    assert_eq!(
+        serde_json::from_slice::<Value>(&body)?,
+        json!({ "id": 7, "name": "alice", "createdAt": "2024-01-01T00:00:00Z" })
+    );
+
+    let (status, content_type, body) = send(&state, Method::GET, "/api/status", "").await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type.as_deref(), Some("text/plain"));
+    assert_eq!(body, b"all good");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_mock_generates_from_the_lowest_success_schema() -> TestResult {
+    let state = gateway(false)?;
+
+    let (status, content_type, body) =
+        send(&state, Method::POST, "/api/users", r#"{"name": "a"}"#).await?;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(content_type.as_deref(), Some("application/json"));
+    assert_eq!(
+        serde_json::from_slice::<Value>(&body)?,
+        json!({ "id": 1, "name": "string", "createdAt": "1970-01-01T00:00:00Z" })
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_mock_validation_failures_behave_as_usual() -> TestResult {
+    let state = gateway(false)?;
+    let (status, _, _) = send(&state, Method::POST, "/api/users", "{}").await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _, _) = send(&state, Method::GET, "/api/users/abc", "").await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Permissive mode "forwards" to the mock
+    let state = gateway(true)?;
+    let (status, _, _) = send(&state, Method::POST, "/api/users", "{}").await?;
+    assert_eq!(status, StatusCode::CREATED);
+    Ok(())
+}
+
+#[test]
+fn test_mock_route_config_validation() -> TestResult {
+    let spec = write_spec();
+    let cases = [
+        ("    mock: true\n".to_string(), "'mock' requires 'openapi'"),
+        (
+            format!(
+                "    openapi: {}\n    mock: true\n    upstream: http://backend:3000\n",
+                spec.display()
+            ),
+            "Cannot specify both 'upstream' and 'mock'",
+        ),
+        (
+            format!(
+                "    openapi: {}\n    mock: true\n    mock_response: {{ status: 204 }}\n",
+                spec.display()
+            ),
+            "Cannot specify both 'mock' and 'mock_response'",
+        ),
+    ];
+    for (route, expected) in cases {
+        let config = Config::from_str(&format!(
+            "routes:\n  - path: /api/users\n    method: POST\n{}",
+            route
+        ))?;
+        let error = config.validate().expect_err(&route);
+        assert!(error.contains(expected), "{}", error);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_generate_minimal_instances() {
+    let schema = json!({
+        "type": "object",
+        "required": ["id", "tags", "status", "score", "nickname", "child"],
+        "properties": {
+            "id": { "type": "integer", "minimum": 0, "exclusiveMinimum": true },
+            "tags": { "type": "array", "minItems": 2, "items": { "type": "string", "maxLength": 3 } },
+            "status": { "type": "string", "enum": ["active", "disabled"] },
+            "score": { "type": ["number", "null"], "maximum": -2.5 },
+            "nickname": { "type": "string", "minLength": 8 },
+            "child": { "$ref": "#/$defs/Node" },
+            "optional": { "type": "string" }
+        },
+        "$defs": {
+            "Node": {
+                "allOf": [
+                    { "type": "object", "required": ["name"], "properties": { "name": { "type": "string", "default": "root" } } },
+                    { "type": "object", "required": ["flag"], "properties": { "flag": { "type": "boolean" } } }
+                ]
+            }
+        }
+    });
+    assert_eq!(
+        generate(&schema),
+        json!({
+            "id": 1,
+            "tags": ["str", "str"],
+            "status": "active",
+            "score": -2.5,
+            "nickname": "stringxx",
+            "child": { "name": "root", "flag": false }
+        })
+    );
+}
+
+#[test]
+fn test_generate_stops_on_recursive_required_fields() {
+    let schema = json!({
+        "$ref": "#/$defs/Node",
+        "$defs": {
+            "Node": {
+                "type": "object",
+                "required": ["next"],
+                "properties": { "next": { "$ref": "#/$defs/Node" } }
+            }
+        }
+    });
+    // Terminates, bottoming out in null
+    let mut value = &generate(&schema);
+    let mut depth = 0;
+    while let Some(next) = value.get("next") {
+        value = next;
+        depth += 1;
+    }
+    assert!(value.is_null());
+    assert!(depth > 0);
+}
@@ -0,0 +1,217 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const SPEC: &str = r#"
+openapi: 3.0.3
+info: { title: Demo, version: "1.0.0" }
+servers:
+  - url: https://api.example.com/v1
+paths:
+  /users:
+    get:
+      operationId: listUsers
+    post:
+      operationId: createUser
+      requestBody:
+        required: true
+        content:
+          application/json:
+            schema:
+              type: object
+              required: [email]
+  /users/{id}:
+    get:
+      operationId: getUser
+      parameters:
+        - name: id
+          in: path
+          required: true
+          schema: { type: integer }
+"#;
+
+/// Write `spec.yaml` and `config.yaml` into a temp dir and return the
+/// config path.
+fn write_config(config: &str) -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    fs::write(dir.path().join("spec.yaml"), SPEC).expect("write spec");
+    let config_path = dir.path().join("config.yaml");
+    fs::write(&config_path, config).expect("write config");
+    let _ = Box::leak(Box::new(dir));
+    config_path
+}
+
+#[test]
+fn test_mount_expands_spec_operations() -> TestResult {
+    let config_path = write_config(
+        r#"
+openapi_mounts:
+  - prefix: /api/
+    spec: ./spec.yaml
+    upstream: http://backend:3000
+"#,
+    );
+
+    let config = Config::from_file(&config_path)?;
+    config.validate()?;
+
+    let routes: Vec<(&str, &str)> = config
+        .routes
+        .iter()
+        .map(|route| (route.method.as_str(), route.path.as_str()))
+        .collect();
+    assert_eq!(
+        routes,
+        vec![
+            ("GET", "/api/users"),
+            ("POST", "/api/users"),
+            ("GET", "/api/users/:id"),
+        ]
+    );
+
+    let route = config.find_route("/api/users/7", "GET").expect("route");
+    assert_eq!(route.upstream, "http://backend:3000");
+    let openapi = route.openapi_options().expect("openapi options");
+    assert_eq!(openapi.spec, config_path.with_file_name("spec.yaml"));
+    assert_eq!(openapi.operation_id.as_deref(), Some("getUser"));
+    assert_eq!(openapi.base_path.as_deref(), Some("/api"));
+    Ok(())
+}
+
+#[test]
+fn test_explicit_routes_win_over_mounts() -> TestResult {
+    let config_path = write_config(
+        r#"
+routes:
+  - path: /api/users/:user_id
+    method: get
+    upstream: http://explicit:3000
+
+openapi_mounts:
+  - prefix: /api
+    spec: spec.yaml
+    upstream: http://backend:3000
+"#,
+    );
+
+    let mut config: Config = serde_yaml::from_str(&fs::read_to_string(&config_path)?)?;
+    config.resolve_paths(config_path.parent().expect("config dir"));
+    let warnings = config.expand_openapi_mounts()?;
+
+    assert_eq!(warnings.len(), 1, "warnings: {:?}", warnings);
+    assert!(
+        warnings[0].contains("GET /api/users/:id is already handled by route 0"),
+        "unexpected warning: {}",
+        warnings[0]
+    );
+    config.validate()?;
+    assert_eq!(config.routes.len(), 3);
+    let route = config.find_route("/api/users/7", "GET").expect("route");
+    assert_eq!(route.upstream, "http://explicit:3000");
+    Ok(())
+}
+
+#[test]
+fn test_mount_errors_name_the_mount() {
+    let missing = write_config(
+        r#"
+openapi_mounts:
+  - prefix: /api
+    spec: ./missing.yaml
+    upstream: http://backend:3000
+"#,
+    );
+    let err = Config::from_file(&missing).expect_err("missing spec is rejected");
+    assert!(
+        err.contains("openapi_mounts[0]"),
+        "unexpected error: {}",
+        err
+    );
+
+    let bad_prefix = write_config(
+        r#"
+openapi_mounts:
+  - prefix: api
+    spec: ./spec.yaml
+    upstream: http://backend:3000
+"#,
+    );
+    let err = Config::from_file(&bad_prefix).expect_err("relative prefix is rejected");
+    assert!(
+        err.contains("openapi_mounts[0]: prefix 'api' must start with '/'"),
+        "unexpected error: {}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn test_mounted_routes_validate_requests() -> TestResult {
+    use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/api/users/7"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let config_path = write_config(&format!(
+        r#"
+global:
+  forward_on_error: false
+
+openapi_mounts:
+  - prefix: /api
+    spec: ./spec.yaml
+    upstream: {}
+"#,
+        mock_server.uri()
+    ));
+    let config = Config::from_file(&config_path)?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+
+    let send = |method: Method, uri: &str, body: &'static str| {
+        let state = state.clone();
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body));
+        async move {
+            let (parts, body) = request?.into_parts();
+            let response =
+                handle_request(State(state), parts.method, parts.uri, parts.headers, body).await;
+            TestResult::Ok(response.status())
+        }
+    };
+
+    assert_eq!(send(Method::GET, "/api/users/7", "").await?, StatusCode::OK);
+    assert_eq!(
+        send(Method::GET, "/api/users/abc", "").await?,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        send(Method::POST, "/api/users", "{}").await?,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        send(Method::DELETE, "/api/users/7", "").await?,
+        StatusCode::METHOD_NOT_ALLOWED
+    );
+    Ok(())
+}
@@ -0,0 +1,243 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use axum::response::Response;
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const SPEC: &str = r#"
+openapi: 3.0.3
+info: { title: Demo, version: "1.0.0" }
+paths:
+  /api/uploads:
+    post:
+      requestBody:
+        required: true
+        content:
+          multipart/form-data:
+            schema:
+              type: object
+              required: [file, metadata]
+              properties:
+                file: { type: string, format: binary }
+                metadata:
+                  type: object
+                  required: [title]
+                  properties:
+                    title: { type: string }
+                count: { type: integer, minimum: 1 }
+                tags:
+                  type: array
+                  maxItems: 2
+                  items: { type: string }
+                attachments:
+                  type: array
+                  items: { type: string, format: binary }
+                labels:
+                  type: array
+                  items: { type: string }
+            encoding:
+              labels:
+                contentType: application/json
+"#;
+
+const BOUNDARY: &str = "gateway-test-boundary";
+
+/// A multipart body with one part per `(name, filename, content)`
+fn multipart_body(parts: &[(&str, Option<&str>, &[u8])]) -> Vec<u8> {
+    let mut body = Vec::new();
+    for (name, file_name, content) in parts {
+        body.extend_from_slice(format!("--{}\r\n", BOUNDARY).as_bytes());
+        let disposition = match file_name {
+            Some(file_name) => format!(
+                "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n\
+                 Content-Type: application/octet-stream\r\n\r\n",
+                name, file_name
+            ),
+            None => format!("Content-Disposition: form-data; name=\"{}\"\r\n\r\n", name),
+        };
+        body.extend_from_slice(disposition.as_bytes());
+        body.extend_from_slice(content);
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{}--\r\n", BOUNDARY).as_bytes());
+    body
+}
+
+fn write_spec() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("openapi.yaml");
+    fs::write(&path, SPEC).expect("write spec");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// POST `body` to the upload route and return the response, plus the body
+/// the upstream received, if it was called
+async fn send(
+    content_type: &str,
+    body: Vec<u8>,
+) -> TestResult<(Response, Option<Vec<u8>>, Arc<Metrics>)> {
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::method("POST"))
+        .respond_with(ResponseTemplate::new(201))
+        .mount(&mock_server)
+        .await;
+
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: false
+
+routes:
+  - path: /api/uploads
+    method: POST
+    openapi: {}
+    upstream: {}
+"#,
+        write_spec().display(),
+        mock_server.uri()
+    ))?;
+    let metrics = Arc::new(Metrics::new()?);
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        metrics.clone(),
+    )));
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/uploads")
+        .header("content-type", content_type)
+        .body(Body::from(body))?;
+    let (parts, body) = request.into_parts();
+    let response = handle_request(State(state), parts.method, parts.uri, parts.headers, body).await;
+    let received = mock_server
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .first()
+        .map(|request| request.body.clone());
+    Ok((response, received, metrics))
+}
+
+async fn send_form(parts: &[(&str, Option<&str>, &[u8])]) -> TestResult<(StatusCode, String)> {
+    let content_type = format!("multipart/form-data; boundary={}", BOUNDARY);
+    let (response, _, _) = send(&content_type, multipart_body(parts)).await?;
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    Ok((status, String::from_utf8_lossy(&bytes).to_string()))
+}
+
+const FILE: (&str, Option<&str>, &[u8]) = ("file", Some("photo.png"), b"\x89PNG\xff\x00binary");
+const METADATA: (&str, Option<&str>, &[u8]) = ("metadata", None, br#"{"title": "Holiday"}"#);
+
+#[tokio::test]
+async fn test_valid_multipart_body_is_forwarded_untouched() -> TestResult {
+    let body = multipart_body(&[
+        FILE,
+        METADATA,
+        ("count", None, b"3"),
+        ("tags", None, b"beach"),
+        ("tags", None, b"sun"),
+        ("attachments", Some("a.txt"), b"first"),
+        ("attachments", Some("b.txt"), b"second"),
+        ("labels", None, br#"["x", "y"]"#),
+    ]);
+    let content_type = format!("multipart/form-data; boundary={}", BOUNDARY);
+    let (response, received, _) = send(&content_type, body.clone()).await?;
+
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_eq!(received, Some(body));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_missing_required_part_is_rejected() -> TestResult {
+    let (status, body) = send_form(&[METADATA]).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(
+        body.contains("\\\"file\\\" is a required property"),
+        "{}",
+        body
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_text_parts_are_validated_against_their_schemas() -> TestResult {
+    let (status, body) = send_form(&[FILE, ("metadata", None, br#"{"name": "x"}"#)]).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body.contains("title"), "{}", body);
+
+    let (status, body) = send_form(&[FILE, ("metadata", None, b"{not json")]).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(
+        body.contains("Multipart part 'metadata' is not valid JSON"),
+        "{}",
+        body
+    );
+
+    let (status, body) = send_form(&[FILE, METADATA, ("count", None, b"many")]).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(
+        body.contains("Multipart part 'count' is not a valid integer"),
+        "{}",
+        body
+    );
+
+    let (status, _) = send_form(&[FILE, METADATA, ("count", None, b"0")]).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = send_form(&[
+        FILE,
+        METADATA,
+        ("tags", None, b"a"),
+        ("tags", None, b"b"),
+        ("tags", None, b"c"),
+    ])
+    .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = send_form(&[FILE, METADATA, ("labels", None, b"plain")]).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_non_multipart_body_is_unsupported_media_type() -> TestResult {
+    let (response, received, metrics) = send(
+        "application/json",
+        br#"{"file": "x", "metadata": {}}"#.to_vec(),
+    )
+    .await?;
+
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert!(received.is_none());
+    assert_eq!(
+        metrics
+            .validation_failures_total
+            .with_label_values(&["openapi", "unsupported_media_type", "oas3.0"])
+            .get(),
+        1.0
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_malformed_multipart_body_is_rejected() -> TestResult {
+    let content_type = format!("multipart/form-data; boundary={}", BOUNDARY);
+    let (response, _, _) = send(&content_type, b"no boundary in here".to_vec()).await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use axum::http::Method;
+use schema_gateway::metrics::Metrics;
+use schema_gateway::openapi::OpenApiCache;
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const OPERATIONS: usize = 900;
+
+/// A spec with `OPERATIONS` operations, `op{n}` at `GET /resource{n}/{id}`
+fn write_large_spec() -> TestResult<PathBuf> {
+    let mut spec =
+        String::from("openapi: 3.0.3\ninfo: { title: Demo, version: \"1.0.0\" }\npaths:\n");
+    for n in 0..OPERATIONS {
+        writeln!(spec, "  /resource{}/{{id}}:", n)?;
+        writeln!(spec, "    get:")?;
+        writeln!(spec, "      operationId: op{}", n)?;
+        writeln!(spec, "      parameters:")?;
+        writeln!(
+            spec,
+            "        - {{ name: id, in: path, required: true, schema: {{ type: integer }} }}"
+        )?;
+        writeln!(spec, "      responses: {{ '200': {{ description: ok }} }}")?;
+    }
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("openapi.yaml");
+    fs::write(&path, spec)?;
+    let _ = Box::leak(Box::new(dir));
+    Ok(path)
+}
+
+#[test]
+fn test_operation_id_lookups_use_the_index() -> TestResult {
+    let spec = write_large_spec()?;
+    let metrics = Metrics::new()?;
+    let cache = OpenApiCache::new().with_metrics(&metrics);
+
+    cache.load_operation(&spec, "/resource0/{id}", &Method::GET, Some("op0"))?;
+
+    // Each of these would walk every path before the matching one without
+    // the index
+    let start = Instant::now();
+    for n in 1..OPERATIONS {
+        let path = format!("/resource{}/{{id}}", n);
+        let operation_id = format!("op{}", n);
+        let plan = cache.load_operation(&spec, &path, &Method::GET, Some(&operation_id))?;
+        assert_eq!(plan.path_template, path);
+    }
+    let elapsed = start.elapsed();
+
+    assert_eq!(metrics.openapi_spec_loads_total.get(), 1.0);
+    assert!(
+        elapsed < Duration::from_secs(1),
+        "{} operationId lookups took {:?}",
+        OPERATIONS - 1,
+        elapsed
+    );
+    Ok(())
+}
+
+#[test]
+fn test_operation_id_mismatches_are_still_reported() -> TestResult {
+    let spec = write_large_spec()?;
+    let cache = OpenApiCache::new();
+
+    let error = cache
+        .load_operation(&spec, "/resource7/{id}", &Method::POST, Some("op7"))
+        .err()
+        .map(|e| e.to_string())
+        .unwrap_or_default();
+    assert!(
+        error.contains("operation 'op7' uses HTTP method 'get', not 'post'"),
+        "{}",
+        error
+    );
+
+    let error = cache
+        .load_operation(&spec, "/resource8/{id}", &Method::GET, Some("op7"))
+        .err()
+        .map(|e| e.to_string())
+        .unwrap_or_default();
+    assert!(
+        error.contains("operation 'op7' is defined at '/resource7/{id}' which does not match route '/resource8/{id}'"),
+        "{}",
+        error
+    );
+
+    let error = cache
+        .load_operation(&spec, "/resource7/{id}", &Method::GET, Some("missing"))
+        .err()
+        .map(|e| e.to_string())
+        .unwrap_or_default();
+    assert!(
+        error.contains("operation 'missing' was not found"),
+        "{}",
+        error
+    );
+    Ok(())
+}
@@ -0,0 +1,324 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const SPEC: &str = r#"
+openapi: 3.0.3
+info: { title: Demo, version: "1.0.0" }
+paths:
+  /api/items:
+    get:
+      parameters:
+        - name: tags
+          in: query
+          style: form
+          explode: false
+          schema:
+            type: array
+            items: { type: string, enum: [a, b, c] }
+        - name: ids
+          in: query
+          schema:
+            type: array
+            items: { type: integer, minimum: 1 }
+            maxItems: 3
+        - name: sizes
+          in: query
+          style: pipeDelimited
+          explode: false
+          schema:
+            type: array
+            items: { type: integer, maximum: 10 }
+        - name: filter
+          in: query
+          style: deepObject
+          explode: true
+          schema:
+            type: object
+            properties:
+              category: { type: string, minLength: 3 }
+              max_price: { type: number }
+            additionalProperties: false
+        - name: point
+          in: query
+          style: form
+          explode: false
+          schema:
+            type: object
+            properties:
+              x: { type: integer }
+              y: { type: integer }
+            required: [x, y]
+        - name: X-Versions
+          in: header
+          schema:
+            type: array
+            items: { type: integer }
+        - name: search
+          in: query
+          content:
+            application/json:
+              schema:
+                type: object
+                properties:
+                  term: { type: string }
+                  limit: { type: integer }
+                required: [term]
+        - name: X-Context
+          in: header
+          content:
+            application/json:
+              schema:
+                type: object
+                required: [tenant]
+        - name: legacy
+          in: query
+          style: matrix
+          required: true
+          schema: { type: integer }
+"#;
+
+fn write_spec() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("openapi.yaml");
+    fs::write(&path, SPEC).expect("write spec");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// Send GET `uri` (with optional headers) through a gateway in strict mode
+/// and return the status and body
+async fn response_for(uri: &str, headers: &[(&str, &str)]) -> TestResult<(StatusCode, String)> {
+    let mock_server = MockServer::start().await;
+    Mock::given(wiremock::matchers::any())
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&mock_server)
+        .await;
+
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: false
+
+routes:
+  - path: /api/items
+    method: GET
+    openapi: {}
+    upstream: {}
+"#,
+        write_spec().display(),
+        mock_server.uri()
+    ))?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+
+    let mut request = Request::builder().method(Method::GET).uri(uri);
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let (parts, body) = request.body(Body::empty())?.into_parts();
+    let response = handle_request(State(state), parts.method, parts.uri, parts.headers, body).await;
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    Ok((status, String::from_utf8_lossy(&body).into_owned()))
+}
+
+async fn status_for(uri: &str, headers: &[(&str, &str)]) -> TestResult<StatusCode> {
+    Ok(response_for(uri, headers).await?.0)
+}
+
+async fn query_status(query: &str) -> TestResult<StatusCode> {
+    status_for(&format!("/api/items?legacy=x&{}", query), &[]).await
+}
+
+#[tokio::test]
+async fn test_form_non_exploded_array() -> TestResult {
+    assert_eq!(query_status("tags=a,b,c").await?, StatusCode::OK);
+    assert_eq!(query_status("tags=a,z").await?, StatusCode::BAD_REQUEST);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_form_exploded_array() -> TestResult {
+    assert_eq!(query_status("ids=1&ids=2").await?, StatusCode::OK);
+    assert_eq!(
+        query_status("ids=1&ids=two").await?,
+        StatusCode::BAD_REQUEST
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pipe_delimited_array() -> TestResult {
+    assert_eq!(query_status("sizes=1|2|3").await?, StatusCode::OK);
+    assert_eq!(query_status("sizes=1|20").await?, StatusCode::BAD_REQUEST);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deep_object() -> TestResult {
+    assert_eq!(
+        query_status("filter[category]=books&filter[max_price]=9.5").await?,
+        StatusCode::OK
+    );
+    // Percent-encoded brackets are decoded first
+    assert_eq!(
+        query_status("filter%5Bcategory%5D=books").await?,
+        StatusCode::OK
+    );
+    assert_eq!(
+        query_status("filter[category]=ab").await?,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        query_status("filter[max_price]=cheap").await?,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        query_status("filter[colour]=red").await?,
+        StatusCode::BAD_REQUEST
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_form_non_exploded_object() -> TestResult {
+    assert_eq!(query_status("point=x,1,y,2").await?, StatusCode::OK);
+    assert_eq!(query_status("point=x,1").await?, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        query_status("point=x,1,y,up").await?,
+        StatusCode::BAD_REQUEST
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_simple_header_array() -> TestResult {
+    let uri = "/api/items?legacy=x";
+    assert_eq!(
+        status_for(uri, &[("x-versions", "1,2")]).await?,
+        StatusCode::OK
+    );
+    assert_eq!(
+        status_for(uri, &[("x-versions", "1,beta")]).await?,
+        StatusCode::BAD_REQUEST
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unsupported_style_is_not_validated() -> TestResult {
+    // `legacy` uses matrix style in the query: its value is skipped, but
+    // a required parameter must still be present
+    assert_eq!(
+        status_for("/api/items?legacy=not-a-number", &[]).await?,
+        StatusCode::OK
+    );
+    assert_eq!(
+        status_for("/api/items", &[]).await?,
+        StatusCode::BAD_REQUEST
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_array_items_are_coerced_and_bounded() -> TestResult {
+    // A single occurrence is a one-element array
+    assert_eq!(query_status("ids=7").await?, StatusCode::OK);
+    assert_eq!(query_status("ids=1&ids=2&ids=3").await?, StatusCode::OK);
+
+    let (status, body) = response_for("/api/items?legacy=x&ids=1&ids=2&ids=3&ids=4", &[]).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body.contains("Parameter 'ids' invalid"), "body: {}", body);
+
+    let (status, body) = response_for("/api/items?legacy=x&ids=1&ids=x", &[]).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(
+        body.contains("Failed to parse integer for parameter 'ids[1]'"),
+        "body: {}",
+        body
+    );
+
+    let (status, body) = response_for("/api/items?legacy=x&ids=1&ids=2&ids=0", &[]).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(
+        body.contains("Parameter 'ids[2]' invalid"),
+        "body: {}",
+        body
+    );
+
+    let (_, body) = response_for("/api/items?legacy=x&filter[category]=ab", &[]).await?;
+    assert!(
+        body.contains("Parameter 'filter[category]' invalid"),
+        "body: {}",
+        body
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_json_content_query_parameter() -> TestResult {
+    assert_eq!(
+        query_status("search=%7B%22term%22%3A%22books%22%2C%22limit%22%3A5%7D").await?,
+        StatusCode::OK
+    );
+
+    let (status, body) =
+        response_for("/api/items?legacy=x&search=%7B%22limit%22%3A5%7D", &[]).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(
+        body.contains("Parameter 'search' invalid"),
+        "body: {}",
+        body
+    );
+
+    let (status, body) = response_for("/api/items?legacy=x&search=%7Bterm", &[]).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(
+        body.contains("Parameter 'search' is not valid JSON"),
+        "body: {}",
+        body
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_json_content_header_parameter() -> TestResult {
+    let uri = "/api/items?legacy=x";
+    assert_eq!(
+        status_for(uri, &[("x-context", r#"{"tenant": "acme"}"#)]).await?,
+        StatusCode::OK
+    );
+
+    let (status, body) = response_for(uri, &[("x-context", r#"{"user": "bob"}"#)]).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(
+        body.contains("Parameter 'X-Context' invalid"),
+        "body: {}",
+        body
+    );
+
+    let (status, body) = response_for(uri, &[("x-context", "tenant=acme")]).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(
+        body.contains("Parameter 'X-Context' is not valid JSON"),
+        "body: {}",
+        body
+    );
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use axum::http::{HeaderMap, Method};
+use schema_gateway::openapi::OpenApiCache;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const SPEC: &str = r#"
+openapi: 3.0.3
+info: { title: Demo, version: "1.0.0" }
+paths:
+  /api/Users/{id}:
+    get:
+      operationId: getUser
+      parameters:
+        - { name: id, in: path, required: true, schema: { type: integer } }
+      responses:
+        '200': { description: ok }
+  /api/teams/:teamId:
+    get:
+      operationId: getTeam
+      parameters:
+        - { name: teamId, in: path, required: true, schema: { type: integer } }
+      responses:
+        '200': { description: ok }
+"#;
+
+fn write_spec() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("openapi.yaml");
+    fs::write(&path, SPEC).expect("write spec");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+#[test]
+fn test_trailing_slash_matches_the_template() -> TestResult {
+    let plan =
+        OpenApiCache::new().load_operation(write_spec(), "/api/Users/7/", &Method::GET, None)?;
+    assert_eq!(plan.path_template, "/api/Users/{id}");
+    plan.check_parameters("/api/Users/7/", &HashMap::new(), None, &HeaderMap::new())?;
+    Ok(())
+}
+
+#[test]
+fn test_colon_params_in_the_spec() -> TestResult {
+    let cache = OpenApiCache::new();
+    let plan = cache.load_operation(write_spec(), "/api/teams/3", &Method::GET, None)?;
+    assert_eq!(plan.path_template, "/api/teams/:teamId");
+    plan.check_parameters("/api/teams/3", &HashMap::new(), None, &HeaderMap::new())?;
+    let error = plan
+        .check_parameters("/api/teams/x", &HashMap::new(), None, &HeaderMap::new())
+        .expect_err("teamId is an integer");
+    assert!(error.contains("teamId"), "{}", error);
+
+    // And the route template's names are paired with them
+    let plan = cache.load_route_operation(
+        write_spec(),
+        "/api/teams/{teamId}",
+        "/api/teams/3",
+        &Method::GET,
+        None,
+        false,
+    )?;
+    assert_eq!(
+        plan.path_param_names,
+        vec![("teamId".to_string(), "teamId".to_string())]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_case_insensitive_paths() -> TestResult {
+    let spec = write_spec();
+    let Err(error) = OpenApiCache::new().load_operation(&spec, "/api/users/7", &Method::GET, None)
+    else {
+        return Err("case differs, yet the operation was found".into());
+    };
+    assert!(error.to_string().contains("/api/users/7"), "{}", error);
+
+    let cache = OpenApiCache::new().with_case_insensitive_paths(true);
+    let plan = cache.load_operation(&spec, "/api/users/7", &Method::GET, None)?;
+    assert_eq!(plan.path_template, "/api/Users/{id}");
+    plan.check_parameters("/API/USERS/7", &HashMap::new(), None, &HeaderMap::new())?;
+    Ok(())
+}
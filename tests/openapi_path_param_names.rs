@@ -0,0 +1,121 @@
+use axum::http::Method;
+use schema_gateway::admin::routes_report;
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, AppState};
+use schema_gateway::metrics::Metrics;
+use schema_gateway::openapi::OpenApiCache;
+use serde_json::json;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const SPEC: &str = r#"
+openapi: 3.0.3
+info: { title: Demo, version: "1.0.0" }
+servers:
+  - url: https://api.example.com/api
+paths:
+  /users/{id}/posts/{postId}:
+    get:
+      operationId: getUserPost
+      parameters:
+        - { name: id, in: path, required: true, schema: { type: integer } }
+        - { name: postId, in: path, required: true, schema: { type: integer } }
+      responses:
+        '200': { description: ok }
+"#;
+
+fn write_spec() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("openapi.yaml");
+    fs::write(&path, SPEC).expect("write spec");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+#[test]
+fn test_route_operation_pairs_parameter_names() -> TestResult {
+    let spec = write_spec();
+    let cache = OpenApiCache::new();
+
+    let plan = cache.load_route_operation(
+        &spec,
+        "/users/:uid/posts/:postId",
+        "/users/7/posts/3",
+        &Method::GET,
+        None,
+        false,
+    )?;
+    assert_eq!(
+        plan.path_param_names,
+        vec![
+            ("uid".to_string(), "id".to_string()),
+            ("postId".to_string(), "postId".to_string()),
+        ]
+    );
+
+    // Plans loaded without a route template have no pairing
+    let plan = cache.load_operation(&spec, "/users/7/posts/3", &Method::GET, None)?;
+    assert!(plan.path_param_names.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_routes_report_shows_parameter_binding() -> TestResult {
+    let spec = write_spec();
+    let config = Config::from_str(&format!(
+        r#"
+routes:
+  - path: /api/users/:uid/posts/:postId
+    method: GET
+    openapi: {spec}
+    upstream: http://backend:3000
+  - path_regex: '^/api/users/(?P<id>\d+)/posts/(?P<postId>\d+)$'
+    method: DELETE
+    openapi: {spec}
+    upstream: http://backend:3000
+
+default_route:
+  openapi: {spec}
+  upstream: http://legacy:8080
+  config:
+    forward_on_error: true
+"#,
+        spec = spec.display()
+    ))?;
+    config.validate()?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+
+    let report = routes_report(&state).await;
+    assert_eq!(
+        report["routes"][0]["openapi"],
+        json!({
+            "spec": spec.display().to_string(),
+            "operation": "getUserPost",
+            "path_params": { "uid": "id", "postId": "postId" }
+        })
+    );
+    // Regex routes have no template to compare
+    assert_eq!(
+        report["routes"][1]["openapi"],
+        json!({ "spec": spec.display().to_string() })
+    );
+    assert_eq!(report["routes"][1]["method"], json!("DELETE"));
+    // The default route comes last, with no operation to resolve
+    assert_eq!(report["routes"][2]["default_route"], json!(true));
+    assert_eq!(report["routes"][2]["upstream"], json!("http://legacy:8080"));
+    assert_eq!(
+        report["routes"][2]["openapi"],
+        json!({ "spec": spec.display().to_string() })
+    );
+    Ok(())
+}
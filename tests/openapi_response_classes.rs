@@ -0,0 +1,200 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use schema_gateway::openapi::{OpenApiCache, ResponseKey};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const SPEC: &str = r#"
+openapi: 3.0.3
+info: { title: Demo, version: "1.0.0" }
+paths:
+  /api/users:
+    get:
+      responses:
+        '200':
+          description: ok
+          content:
+            application/json:
+              schema: { type: object, required: [exact] }
+        2XX:
+          description: other success
+          content:
+            application/json:
+              schema: { type: object, required: [id] }
+        3xx:
+          description: redirect
+          headers:
+            Location:
+              required: true
+              schema: { type: string }
+        default:
+          description: error
+          content:
+            application/json:
+              schema: { type: object, required: [error] }
+  /api/orders:
+    get:
+      responses:
+        2XX:
+          description: success
+          content:
+            application/json:
+              schema: { type: object, required: [id] }
+"#;
+
+fn write_spec() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("openapi.yaml");
+    fs::write(&path, SPEC).expect("write spec");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// Proxy `GET path` to an upstream answering with `upstream`
+async fn send_to(path: &str, upstream: ResponseTemplate) -> TestResult<StatusCode> {
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::method("GET"))
+        .respond_with(upstream)
+        .mount(&mock_server)
+        .await;
+
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: false
+
+routes:
+  - path: {}
+    method: GET
+    openapi: {}
+    upstream: {}
+"#,
+        path,
+        write_spec().display(),
+        mock_server.uri()
+    ))?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(path)
+        .body(Body::empty())?;
+    let (parts, body) = request.into_parts();
+    let response = handle_request(State(state), parts.method, parts.uri, parts.headers, body).await;
+    Ok(response.status())
+}
+
+async fn send(upstream: ResponseTemplate) -> TestResult<StatusCode> {
+    send_to("/api/users", upstream).await
+}
+
+fn json(status: u16, body: &str) -> ResponseTemplate {
+    ResponseTemplate::new(status).set_body_raw(body.as_bytes().to_vec(), "application/json")
+}
+
+#[test]
+fn test_class_keys_are_parsed() -> TestResult {
+    let plan =
+        OpenApiCache::new().load_operation(write_spec(), "/api/users", &Method::GET, None)?;
+
+    assert!(plan
+        .response_schemas
+        .contains_key(&ResponseKey::Status(200)));
+    assert!(plan.response_schemas.contains_key(&ResponseKey::Class(2)));
+    assert!(plan.response_schemas.contains_key(&ResponseKey::Default));
+    assert!(plan.response_headers.contains_key(&ResponseKey::Class(3)));
+    Ok(())
+}
+
+#[test]
+fn test_lookup_prefers_status_then_class_then_default() {
+    let map = HashMap::from([
+        (ResponseKey::Status(200), "exact"),
+        (ResponseKey::Class(2), "class"),
+        (ResponseKey::Default, "default"),
+    ]);
+
+    assert_eq!(ResponseKey::lookup(&map, 200), Some(&"exact"));
+    assert_eq!(ResponseKey::lookup(&map, 201), Some(&"class"));
+    assert_eq!(ResponseKey::lookup(&map, 404), Some(&"default"));
+
+    let map = HashMap::from([(ResponseKey::Class(4), "class")]);
+    assert_eq!(ResponseKey::lookup(&map, 500), None);
+}
+
+#[tokio::test]
+async fn test_class_schema_validates_responses_without_an_exact_key() -> TestResult {
+    assert_eq!(send(json(201, r#"{"id": 1}"#)).await?, StatusCode::CREATED);
+    assert_eq!(
+        send(json(201, r#"{"exact": 1}"#)).await?,
+        StatusCode::BAD_GATEWAY
+    );
+
+    // The exact key wins over the class
+    assert_eq!(send(json(200, r#"{"exact": 1}"#)).await?, StatusCode::OK);
+    assert_eq!(
+        send(json(200, r#"{"id": 1}"#)).await?,
+        StatusCode::BAD_GATEWAY
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_range_only_spec_validates_success_responses() -> TestResult {
+    for status in [200, 201] {
+        assert_eq!(
+            send_to("/api/orders", json(status, r#"{"id": 1}"#))
+                .await?
+                .as_u16(),
+            status
+        );
+        assert_eq!(
+            send_to("/api/orders", json(status, "{}")).await?,
+            StatusCode::BAD_GATEWAY
+        );
+    }
+    // Nothing is declared for other classes
+    assert_eq!(
+        send_to("/api/orders", json(404, "{}")).await?,
+        StatusCode::NOT_FOUND
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_redirects_are_checked_against_the_class_headers() -> TestResult {
+    // 300 isn't followed by the HTTP client, so it reaches validation
+    let redirect = ResponseTemplate::new(300).insert_header("location", "/elsewhere");
+    assert_eq!(send(redirect).await?, StatusCode::MULTIPLE_CHOICES);
+    assert_eq!(
+        send(ResponseTemplate::new(300)).await?,
+        StatusCode::BAD_GATEWAY
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_no_content_skips_body_validation() -> TestResult {
+    // 204 falls under 2XX, whose schema an empty body can't satisfy
+    assert_eq!(
+        send(ResponseTemplate::new(204)).await?,
+        StatusCode::NO_CONTENT
+    );
+    Ok(())
+}
@@ -0,0 +1,205 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use axum::response::Response;
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const SPEC: &str = r#"
+openapi: 3.0.3
+info: { title: Demo, version: "1.0.0" }
+components:
+  headers:
+    RateLimit:
+      required: true
+      schema: { type: integer, minimum: 0 }
+paths:
+  /api/users:
+    get:
+      responses:
+        '200':
+          description: ok
+          headers:
+            X-Rate-Limit-Remaining:
+              $ref: '#/components/headers/RateLimit'
+            X-Request-Tags:
+              schema:
+                type: array
+                items: { type: string, enum: [a, b] }
+            Content-Type:
+              required: true
+              schema: { type: string, enum: [never] }
+        default:
+          description: error
+          headers:
+            X-Error-Code:
+              required: true
+              schema: { type: string }
+"#;
+
+fn write_spec() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("openapi.yaml");
+    fs::write(&path, SPEC).expect("write spec");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// Proxy `GET /api/users` to an upstream answering with `upstream`
+async fn send(
+    upstream: ResponseTemplate,
+    forward_on_error: bool,
+) -> TestResult<(Response, Arc<Metrics>)> {
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/api/users"))
+        .respond_with(upstream)
+        .mount(&mock_server)
+        .await;
+
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: {}
+
+routes:
+  - path: /api/users
+    method: GET
+    openapi: {}
+    upstream: {}
+"#,
+        forward_on_error,
+        write_spec().display(),
+        mock_server.uri()
+    ))?;
+    let metrics = Arc::new(Metrics::new()?);
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        metrics.clone(),
+    )));
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri("/api/users")
+        .body(Body::empty())?;
+    let (parts, body) = request.into_parts();
+    let response = handle_request(State(state), parts.method, parts.uri, parts.headers, body).await;
+    Ok((response, metrics))
+}
+
+async fn error_of(response: Response) -> TestResult<String> {
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    let body: serde_json::Value = serde_json::from_slice(&bytes)?;
+    Ok(body["error"].as_str().unwrap_or_default().to_string())
+}
+
+fn header_failures(metrics: &Metrics) -> f64 {
+    metrics
+        .validation_failures_total
+        .with_label_values(&["openapi", "response_header_validation_failed", "oas3.0"])
+        .get()
+}
+
+#[tokio::test]
+async fn test_valid_response_headers_pass() -> TestResult {
+    let upstream = ResponseTemplate::new(200)
+        .insert_header("x-rate-limit-remaining", "42")
+        .insert_header("x-request-tags", "a,b");
+    let (response, metrics) = send(upstream, false).await?;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(header_failures(&metrics), 0.0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_missing_optional_response_header_passes() -> TestResult {
+    let upstream = ResponseTemplate::new(200).insert_header("x-rate-limit-remaining", "0");
+    let (response, _) = send(upstream, false).await?;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_missing_required_response_header_is_bad_gateway() -> TestResult {
+    let (response, metrics) = send(ResponseTemplate::new(200), false).await?;
+
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    assert_eq!(
+        error_of(response).await?,
+        "Missing required response header 'X-Rate-Limit-Remaining'"
+    );
+    assert_eq!(header_failures(&metrics), 1.0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_response_header_values_are_coerced_and_validated() -> TestResult {
+    let upstream = ResponseTemplate::new(200).insert_header("x-rate-limit-remaining", "many");
+    let (response, _) = send(upstream, false).await?;
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    assert!(error_of(response)
+        .await?
+        .contains("Failed to parse integer"));
+
+    let upstream = ResponseTemplate::new(200)
+        .insert_header("x-rate-limit-remaining", "-1")
+        .insert_header("x-request-tags", "a,c");
+    let (response, _) = send(upstream, false).await?;
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    assert!(error_of(response)
+        .await?
+        .starts_with("Response header 'X-Rate-Limit-Remaining' invalid:"));
+
+    let upstream = ResponseTemplate::new(200)
+        .insert_header("x-rate-limit-remaining", "1")
+        .insert_header("x-request-tags", "a,c");
+    let (response, _) = send(upstream, false).await?;
+    assert!(error_of(response)
+        .await?
+        .starts_with("Response header 'X-Request-Tags[1]' invalid:"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_default_response_headers_apply_to_other_statuses() -> TestResult {
+    let (response, _) = send(ResponseTemplate::new(500), false).await?;
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    assert_eq!(
+        error_of(response).await?,
+        "Missing required response header 'X-Error-Code'"
+    );
+
+    let upstream = ResponseTemplate::new(500).insert_header("x-error-code", "E42");
+    let (response, _) = send(upstream, false).await?;
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_response_header_failure_forwarded_on_error() -> TestResult {
+    let (response, metrics) = send(ResponseTemplate::new(200), true).await?;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response
+            .headers()
+            .get("x-gateway-error")
+            .and_then(|v| v.to_str().ok()),
+        Some("Missing required response header 'X-Rate-Limit-Remaining'")
+    );
+    assert_eq!(header_failures(&metrics), 1.0);
+    Ok(())
+}
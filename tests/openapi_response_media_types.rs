@@ -0,0 +1,191 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use axum::response::Response;
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const SPEC: &str = r#"
+openapi: 3.0.3
+info: { title: Demo, version: "1.0.0" }
+paths:
+  /api/reports:
+    get:
+      responses:
+        '200':
+          description: ok
+          content:
+            text/csv:
+              schema: { type: string }
+            application/json:
+              schema: { type: object, required: [rows] }
+            application/problem+json:
+              schema: { type: object, required: [title] }
+  /api/users:
+    get:
+      responses:
+        '200':
+          description: ok
+          content:
+            application/json:
+              schema: { type: object, required: [id] }
+"#;
+
+fn write_spec() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("openapi.yaml");
+    fs::write(&path, SPEC).expect("write spec");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// Proxy `GET path` to an upstream answering `status` with `body` of
+/// `content_type`, under the `global` settings given
+async fn send(
+    path: &str,
+    status: u16,
+    content_type: &str,
+    body: &str,
+    global: &str,
+) -> TestResult<Response> {
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::method("GET"))
+        .respond_with(
+            ResponseTemplate::new(status).set_body_raw(body.as_bytes().to_vec(), content_type),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let config = Config::from_str(&format!(
+        r#"
+global:
+{}
+
+routes:
+  - path: {}
+    method: GET
+    openapi: {}
+    upstream: {}
+"#,
+        global,
+        path,
+        write_spec().display(),
+        mock_server.uri()
+    ))?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(path)
+        .body(Body::empty())?;
+    let (parts, body) = request.into_parts();
+    Ok(handle_request(State(state), parts.method, parts.uri, parts.headers, body).await)
+}
+
+const STRICT: &str = "  forward_on_error: false";
+
+async fn status_of(path: &str, content_type: &str, body: &str) -> TestResult<StatusCode> {
+    Ok(send(path, 200, content_type, body, STRICT).await?.status())
+}
+
+#[tokio::test]
+async fn test_each_media_type_uses_its_own_schema() -> TestResult {
+    let reports = "/api/reports";
+    assert_eq!(
+        status_of(reports, "text/csv", "a,b\n1,2").await?,
+        StatusCode::OK
+    );
+    assert_eq!(
+        status_of(
+            reports,
+            "application/json; charset=utf-8",
+            r#"{"rows": []}"#
+        )
+        .await?,
+        StatusCode::OK
+    );
+    assert_eq!(
+        status_of(reports, "application/json", "{}").await?,
+        StatusCode::BAD_GATEWAY
+    );
+    assert_eq!(
+        status_of(reports, "Application/Problem+JSON", r#"{"title": "x"}"#).await?,
+        StatusCode::OK
+    );
+    assert_eq!(
+        status_of(reports, "application/problem+json", r#"{"rows": []}"#).await?,
+        StatusCode::BAD_GATEWAY
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_undeclared_json_types_fall_back_to_application_json() -> TestResult {
+    assert_eq!(
+        status_of("/api/users", "application/vnd.api+json", r#"{"id": 1}"#).await?,
+        StatusCode::OK
+    );
+    assert_eq!(
+        status_of("/api/users", "application/vnd.api+json", "{}").await?,
+        StatusCode::BAD_GATEWAY
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_undeclared_content_type_is_skipped_by_default() -> TestResult {
+    assert_eq!(
+        status_of("/api/users", "text/html", "<html></html>").await?,
+        StatusCode::OK
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_undeclared_content_type_can_be_rejected() -> TestResult {
+    let reject = "  forward_on_error: false\n  reject_undeclared_response_types: true";
+
+    let response = send("/api/reports", 200, "text/html", "<html></html>", reject).await?;
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    assert!(
+        String::from_utf8_lossy(&bytes)
+            .contains("Upstream response Content-Type 'text/html' is not declared for status 200"),
+        "{}",
+        String::from_utf8_lossy(&bytes)
+    );
+
+    // Declared types and statuses without declared responses pass
+    let response = send("/api/reports", 200, "text/csv", "a,b", reject).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = send("/api/reports", 404, "text/html", "missing", reject).await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // Permissive mode forwards with the error header instead
+    let permissive = "  forward_on_error: true\n  reject_undeclared_response_types: true";
+    let response = send(
+        "/api/reports",
+        200,
+        "text/html",
+        "<html></html>",
+        permissive,
+    )
+    .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().contains_key("x-gateway-error"));
+    Ok(())
+}
@@ -0,0 +1,251 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use schema_gateway::openapi::{
+    OpenApiCache, ParameterLocation, SecurityRequirement, SecurityScheme,
+};
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const SPEC: &str = r#"
+openapi: 3.0.3
+info: { title: Demo, version: "1.0.0" }
+security:
+  - ApiKeyAuth: []
+components:
+  securitySchemes:
+    ApiKeyAuth: { type: apiKey, in: header, name: X-API-Key }
+    QueryKey: { type: apiKey, in: query, name: api_key }
+    BearerAuth: { type: http, scheme: bearer }
+    OAuth:
+      type: oauth2
+      flows:
+        clientCredentials: { tokenUrl: https://auth.example.com/token, scopes: {} }
+paths:
+  /api/users:
+    get: {}
+  /api/tokens:
+    get:
+      security:
+        - BearerAuth: []
+        - QueryKey: []
+          ApiKeyAuth: []
+  /api/health:
+    get:
+      security: []
+  /api/optional:
+    get:
+      security:
+        - BearerAuth: []
+        - {}
+  /api/oauth:
+    get:
+      security:
+        - OAuth: [read]
+"#;
+
+fn write_spec(contents: &str) -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("openapi.yaml");
+    fs::write(&path, contents).expect("write spec");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// Send `GET uri` with `headers` through a route for `path`, returning the
+/// status and whether the upstream was called
+async fn send(
+    path: &str,
+    uri: &str,
+    headers: &[(&str, &str)],
+    route_config: &str,
+) -> TestResult<(StatusCode, bool, Arc<Metrics>)> {
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::method("GET"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&mock_server)
+        .await;
+
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: true
+
+routes:
+  - path: {}
+    method: GET
+    openapi: {}
+    upstream: {}
+{}
+"#,
+        path,
+        write_spec(SPEC).display(),
+        mock_server.uri(),
+        route_config
+    ))?;
+    let metrics = Arc::new(Metrics::new()?);
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        metrics.clone(),
+    )));
+
+    let mut request = Request::builder().method(Method::GET).uri(uri);
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let (parts, body) = request.body(Body::empty())?.into_parts();
+    let response = handle_request(State(state), parts.method, parts.uri, parts.headers, body).await;
+    let forwarded = !mock_server
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .is_empty();
+    Ok((response.status(), forwarded, metrics))
+}
+
+#[test]
+fn test_security_requirements_on_plan() -> TestResult {
+    let spec = write_spec(SPEC);
+    let cache = OpenApiCache::new();
+
+    let plan = cache.load_operation(&spec, "/api/users", &Method::GET, None)?;
+    assert_eq!(
+        plan.security,
+        vec![SecurityRequirement {
+            schemes: vec![SecurityScheme::ApiKey {
+                name: "X-API-Key".to_string(),
+                location: ParameterLocation::Header,
+            }],
+        }]
+    );
+
+    let plan = cache.load_operation(&spec, "/api/health", &Method::GET, None)?;
+    assert!(plan.security.is_empty());
+
+    let plan = cache.load_operation(&spec, "/api/tokens", &Method::GET, None)?;
+    assert_eq!(plan.security.len(), 2);
+    assert_eq!(plan.security[1].schemes.len(), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_missing_api_key_is_rejected_before_upstream() -> TestResult {
+    let (status, forwarded, metrics) = send("/api/users", "/api/users", &[], "").await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert!(
+        !forwarded,
+        "forward_on_error must not forward missing credentials"
+    );
+    assert_eq!(
+        metrics
+            .validation_failures_total
+            .with_label_values(&["openapi", "security_requirements_not_met", "oas3.0"])
+            .get(),
+        1.0
+    );
+
+    let (status, forwarded, _) =
+        send("/api/users", "/api/users", &[("X-API-Key", "k1")], "").await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(forwarded);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_bearer_scheme_needs_bearer_prefix() -> TestResult {
+    for (authorization, expected) in [
+        ("Bearer abc", StatusCode::OK),
+        ("bearer abc", StatusCode::OK),
+        ("Basic dXNlcjpwYXNz", StatusCode::UNAUTHORIZED),
+        ("Bearer ", StatusCode::UNAUTHORIZED),
+    ] {
+        let (status, _, _) = send(
+            "/api/tokens",
+            "/api/tokens",
+            &[("Authorization", authorization)],
+            "",
+        )
+        .await?;
+        assert_eq!(status, expected, "Authorization: {}", authorization);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_any_requirement_satisfies_but_all_its_schemes_are_needed() -> TestResult {
+    let (status, _, _) = send(
+        "/api/tokens",
+        "/api/tokens?api_key=k1",
+        &[("X-API-Key", "k1")],
+        "",
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+
+    // Only half of the second requirement
+    let (status, _, _) = send("/api/tokens", "/api/tokens?api_key=k1", &[], "").await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_empty_and_anonymous_security_allow_requests() -> TestResult {
+    let (status, _, _) = send("/api/health", "/api/health", &[], "").await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _, _) = send("/api/optional", "/api/optional", &[], "").await?;
+    assert_eq!(status, StatusCode::OK);
+    // Schemes without a presence check aren't enforced
+    let (status, _, _) = send("/api/oauth", "/api/oauth", &[], "").await?;
+    assert_eq!(status, StatusCode::OK);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_enforce_security_can_be_disabled_per_route() -> TestResult {
+    let (status, forwarded, _) = send(
+        "/api/users",
+        "/api/users",
+        &[],
+        "    config:\n      enforce_security: false",
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(forwarded);
+    Ok(())
+}
+
+#[test]
+fn test_undefined_security_scheme_is_an_error() {
+    let spec = write_spec(
+        r#"
+openapi: 3.0.3
+info: { title: Demo, version: "1.0.0" }
+paths:
+  /api/users:
+    get:
+      security:
+        - Missing: []
+"#,
+    );
+    let error = OpenApiCache::new()
+        .load_operation(&spec, "/api/users", &Method::GET, None)
+        .err()
+        .map(|e| e.to_string())
+        .unwrap_or_default();
+    assert!(
+        error.contains("security scheme 'Missing' is not defined"),
+        "unexpected error: {}",
+        error
+    );
+}
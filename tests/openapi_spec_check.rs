@@ -0,0 +1,126 @@
+use schema_gateway::openapi::{OpenApiCache, SpecProblem};
+use std::fs;
+use std::path::PathBuf;
+
+fn write_spec(contents: &str) -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("openapi.yaml");
+    fs::write(&path, contents).expect("write spec");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+fn pointers(problems: &[SpecProblem]) -> Vec<&str> {
+    problems.iter().map(|p| p.pointer.as_str()).collect()
+}
+
+#[test]
+fn test_valid_spec_has_no_problems() {
+    let spec = write_spec(
+        r#"
+openapi: 3.0.3
+info: { title: Demo, version: "1.0.0" }
+components:
+  schemas:
+    User: { type: object, required: [name] }
+paths:
+  /users/{id}:
+    parameters:
+      - { name: id, in: path, required: true, schema: { type: integer } }
+    get:
+      responses:
+        '200':
+          description: ok
+          content:
+            application/json:
+              schema: { $ref: '#/components/schemas/User' }
+    put:
+      requestBody:
+        content:
+          application/json:
+            schema: { $ref: '#/components/schemas/User' }
+"#,
+    );
+    assert_eq!(OpenApiCache::new().check_spec(spec), vec![]);
+}
+
+#[test]
+fn test_every_problem_is_reported_with_its_location() {
+    let spec = write_spec(
+        r#"
+openapi: 3.0.3
+paths:
+  /users/{id}:
+    parameters:
+      - { name: id, in: path, required: true, schema: { type: integer, minimum: "x" } }
+    get:
+      parameters:
+        - $ref: '#/components/parameters/Missing'
+      responses:
+        '200':
+          description: ok
+          headers:
+            X-Rate-Limit:
+              schema: { type: integer, maximum: [] }
+          content:
+            application/json:
+              schema: { $ref: './other.yaml#/User' }
+    put:
+      requestBody:
+        content:
+          application/json:
+            schema: { $ref: '#/components/schemas/Missing' }
+"#,
+    );
+    let problems = OpenApiCache::new().check_spec(spec);
+
+    assert_eq!(
+        pointers(&problems),
+        vec![
+            "#/info",
+            "#/paths/~1users~1{id}/get/parameters/0",
+            "#/paths/~1users~1{id}/put/requestBody/content/application~1json/schema",
+            "#/paths/~1users~1{id}/parameters/0/schema",
+            "#/paths/~1users~1{id}/get/responses/200/content/application~1json/schema",
+            "#/paths/~1users~1{id}/get/responses/200/headers/X-Rate-Limit/schema",
+        ],
+        "{:#?}",
+        problems
+    );
+    assert_eq!(
+        problems[1].to_string(),
+        "#/paths/~1users~1{id}/get/parameters/0: reference '#/components/parameters/Missing' not found"
+    );
+    assert!(problems[4]
+        .message
+        .contains("failed to load reference './other.yaml#/User'"));
+}
+
+#[test]
+fn test_problems_found_while_building_plans() {
+    let spec = write_spec(
+        r#"
+openapi: 3.1.0
+info: { title: Demo, version: "1.0.0" }
+paths:
+  /users:
+    get:
+      security:
+        - Missing: []
+"#,
+    );
+    let problems = OpenApiCache::new().check_spec(spec);
+    assert_eq!(pointers(&problems), vec!["#/paths/~1users/get"]);
+    assert!(problems[0]
+        .message
+        .contains("security scheme 'Missing' is not defined"));
+}
+
+#[test]
+fn test_unreadable_spec() {
+    let problems = OpenApiCache::new().check_spec(write_spec("openapi: [unterminated"));
+    assert_eq!(pointers(&problems), vec!["#"]);
+
+    let problems = OpenApiCache::new().check_spec(write_spec("swagger: '2.0'\ninfo: {}\n"));
+    assert_eq!(pointers(&problems), vec!["#/openapi", "#/paths"]);
+}
@@ -0,0 +1,184 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use schema_gateway::openapi::{OpenApiCache, SpecVersion};
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const PATHS: &str = r#"
+info: { title: Demo, version: "1.0.0" }
+paths:
+  /api/users:
+    post:
+      requestBody:
+        required: true
+        content:
+          application/json:
+            schema: { type: object, required: [name] }
+      responses:
+        '201': { description: created }
+"#;
+
+const SWAGGER: &str = r#"
+swagger: "2.0"
+info: { title: Demo, version: "1.0.0" }
+paths:
+  /api/users:
+    get:
+      responses:
+        '200': { description: ok }
+"#;
+
+fn write_spec(contents: &str) -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("openapi.yaml");
+    fs::write(&path, contents).expect("write spec");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// Gateway state where `POST /api/users` is validated against a 3.0 spec
+/// and `PUT /api/users` against a 3.1 one
+async fn gateway() -> TestResult<(Arc<RwLock<AppState>>, MockServer)> {
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::any())
+        .respond_with(ResponseTemplate::new(201))
+        .mount(&mock_server)
+        .await;
+
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: false
+
+routes:
+  - path: /api/users
+    method: POST
+    openapi: {v30}
+    upstream: {upstream}
+  - path: /api/users
+    method: PUT
+    openapi: {v31}
+    upstream: {upstream}
+"#,
+        v30 = write_spec(&format!("openapi: 3.0.3{}", PATHS)).display(),
+        v31 = write_spec(&format!("openapi: 3.1.0{}", PATHS.replace("post:", "put:"))).display(),
+        upstream = mock_server.uri()
+    ))?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+    Ok((state, mock_server))
+}
+
+async fn send(state: &Arc<RwLock<AppState>>, method: Method, body: &str) -> TestResult<StatusCode> {
+    let request = Request::builder()
+        .method(method)
+        .uri("/api/users")
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))?;
+    let (parts, body) = request.into_parts();
+    let response = handle_request(
+        State(state.clone()),
+        parts.method,
+        parts.uri,
+        parts.headers,
+        body,
+    )
+    .await;
+    Ok(response.status())
+}
+
+#[tokio::test]
+async fn test_validation_metrics_are_labelled_with_the_spec_version() -> TestResult {
+    let (state, _upstream) = gateway().await?;
+
+    assert_eq!(
+        send(&state, Method::POST, r#"{"name": "a"}"#).await?,
+        StatusCode::CREATED
+    );
+    assert_eq!(
+        send(&state, Method::PUT, r#"{"name": "a"}"#).await?,
+        StatusCode::CREATED
+    );
+    assert_eq!(
+        send(&state, Method::PUT, "{}").await?,
+        StatusCode::BAD_REQUEST
+    );
+
+    let metrics = state.read().await.metrics.gather()?;
+    for line in [
+        r#"validation_attempts_total{openapi_spec_version="oas3.0",validation_type="openapi"} 1"#,
+        r#"validation_attempts_total{openapi_spec_version="oas3.1",validation_type="openapi"} 2"#,
+        r#"validation_success_total{openapi_spec_version="oas3.0",validation_type="openapi"} 1"#,
+        r#"validation_success_total{openapi_spec_version="oas3.1",validation_type="openapi"} 1"#,
+        r#"validation_failures_total{error_type="validation_failed",openapi_spec_version="oas3.1",validation_type="openapi"} 1"#,
+    ] {
+        assert!(metrics.contains(line), "{}\n{}", line, metrics);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unloadable_spec_is_labelled_unknown() -> TestResult {
+    let config = Config::from_str(
+        r#"
+global:
+  forward_on_error: false
+
+routes:
+  - path: /api/users
+    method: POST
+    openapi: /nonexistent/openapi.yaml
+    upstream: http://127.0.0.1:9
+"#,
+    )?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+
+    send(&state, Method::POST, r#"{"name": "a"}"#).await?;
+    let metrics = state.read().await.metrics.gather()?;
+    assert!(
+        metrics.contains(
+            r#"validation_failures_total{error_type="schema_load_error",openapi_spec_version="unknown",validation_type="openapi"} 1"#
+        ),
+        "{}",
+        metrics
+    );
+    Ok(())
+}
+
+#[test]
+fn test_spec_version_is_detected() -> TestResult {
+    let cache = OpenApiCache::new();
+
+    let v30 = write_spec(&format!("openapi: 3.0.3{}", PATHS));
+    let plan = cache.load_operation(&v30, "/api/users", &Method::POST, None)?;
+    assert_eq!(plan.spec_version, SpecVersion::V3_0);
+
+    let v31 = write_spec(&format!("openapi: 3.1.0{}", PATHS));
+    let plan = cache.load_operation(&v31, "/api/users", &Method::POST, None)?;
+    assert_eq!(plan.spec_version.label(), "oas3.1");
+
+    let swagger = write_spec(SWAGGER);
+    let plan = cache.load_operation(&swagger, "/api/users", &Method::GET, None)?;
+    assert_eq!(plan.spec_version, SpecVersion::Swagger2);
+    assert_eq!(plan.spec_version.label(), "oas2");
+    Ok(())
+}
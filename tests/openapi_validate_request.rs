@@ -0,0 +1,113 @@
+use axum::http::{HeaderMap, HeaderValue, Method};
+use schema_gateway::openapi::OpenApiCache;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::PathBuf;
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const SPEC: &str = r#"
+openapi: 3.0.3
+info: { title: Demo, version: "1.0.0" }
+paths:
+  /users/{id}:
+    put:
+      parameters:
+        - { name: id, in: path, required: true, schema: { type: integer } }
+        - { name: dry_run, in: query, schema: { type: boolean } }
+        - { name: X-Tenant, in: header, required: true, schema: { type: string } }
+      requestBody:
+        required: true
+        content:
+          application/json:
+            schema:
+              type: object
+              required: [name]
+              properties:
+                name: { type: string }
+                age: { type: integer, minimum: 0 }
+    get:
+      parameters:
+        - { name: id, in: path, required: true, schema: { type: integer } }
+"#;
+
+fn write_spec() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("openapi.yaml");
+    fs::write(&path, SPEC).expect("write spec");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+fn tenant() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert("x-tenant", HeaderValue::from_static("acme"));
+    headers
+}
+
+#[test]
+fn test_validate_request_without_the_proxy() -> TestResult {
+    let plan =
+        OpenApiCache::new().load_operation(write_spec(), "/users/{id}", &Method::PUT, None)?;
+
+    let result = plan.validate_request(
+        &json!({"name": "Ada", "age": 36}),
+        Some("dry_run=true"),
+        "/users/42",
+        &tenant(),
+    );
+    assert!(result.valid, "{:?}", result.errors);
+    assert!(result.errors.is_empty());
+
+    let result = plan.validate_request(&json!({"age": -1}), None, "/users/42", &tenant());
+    assert!(!result.valid);
+    assert_eq!(result.errors.len(), 2, "{:?}", result.errors);
+    assert!(result
+        .messages()
+        .contains(&"/age: -1 is less than the minimum of 0".to_string()));
+    Ok(())
+}
+
+#[test]
+fn test_validate_request_checks_parameters_first() -> TestResult {
+    let plan =
+        OpenApiCache::new().load_operation(write_spec(), "/users/{id}", &Method::PUT, None)?;
+    let body = json!({"name": "Ada"});
+
+    let result = plan.validate_request(&body, None, "/users/abc", &tenant());
+    assert!(!result.valid);
+    assert_eq!(
+        result.messages(),
+        vec!["Failed to parse integer for parameter 'id'"]
+    );
+
+    let result = plan.validate_request(&body, None, "/users/42", &HeaderMap::new());
+    assert_eq!(
+        result.messages(),
+        vec!["Missing required header parameter 'X-Tenant'"]
+    );
+
+    let result = plan.validate_request(&body, None, "/accounts/42", &tenant());
+    assert_eq!(
+        result.messages(),
+        vec!["Route '/accounts/42' no longer matches OpenAPI template '/users/{id}'"]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_validate_request_body_presence() -> TestResult {
+    let cache = OpenApiCache::new();
+    let plan = cache.load_operation(write_spec(), "/users/{id}", &Method::PUT, None)?;
+    let result = plan.validate_request(&Value::Null, None, "/users/42", &tenant());
+    assert_eq!(
+        result.messages(),
+        vec!["OpenAPI request body required for PUT /users/{id}"]
+    );
+
+    // Operations without a request body ignore it
+    let plan = cache.load_operation(write_spec(), "/users/{id}", &Method::GET, None)?;
+    let result = plan.validate_request(&json!({"any": 1}), None, "/users/42", &HeaderMap::new());
+    assert!(result.valid);
+    Ok(())
+}
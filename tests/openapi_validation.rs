@@ -2,12 +2,13 @@ use axum::body::Body;
 use axum::extract::State;
 use axum::http::{Method, Request, StatusCode};
 use schema_gateway::config::Config;
+use schema_gateway::error::{Error, OpenApiErrorStage};
 use schema_gateway::handler::{build_http_client, handle_request, AppState};
 use schema_gateway::openapi::{OpenApiCache, ResponseKey};
-use schema_gateway::schema::SchemaCache;
 use serde_json::json;
 use std::fs;
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -44,7 +45,7 @@ paths:
 "#;
 
     let path = write_openapi_spec(spec);
-    let mut cache = OpenApiCache::new();
+    let cache = OpenApiCache::new();
 
     let plan1 = cache
         .load_operation(&path, "/api/users", &Method::POST, None)
@@ -83,7 +84,7 @@ paths:
 "#;
 
     let path = write_openapi_spec(spec);
-    let mut cache = OpenApiCache::new();
+    let cache = OpenApiCache::new();
     let plan = cache
         .load_operation(&path, "/api/users/:id", &Method::GET, Some("getUser"))
         .expect("load operation");
@@ -125,7 +126,7 @@ paths:
 "#;
 
     let path = write_openapi_spec(spec);
-    let mut cache = OpenApiCache::new();
+    let cache = OpenApiCache::new();
     let plan = cache
         .load_operation(&path, "/api/items/:id", &Method::GET, None)
         .expect("load operation");
@@ -176,14 +177,13 @@ routes:
         spec_path.display()
     );
 
-    let config: Config = serde_yaml::from_str(&config_yaml)?;
-    let app_state = AppState {
+    let config = Config::from_str(&config_yaml)?;
+    let http_client = build_http_client(&config.global.http_client);
+    let app_state = AppState::new(
         config,
-        schema_cache: SchemaCache::new(),
-        openapi_cache: OpenApiCache::new(),
-        http_client: build_http_client(),
-        metrics: std::sync::Arc::new(schema_gateway::metrics::Metrics::new().unwrap()),
-    };
+        http_client,
+        std::sync::Arc::new(schema_gateway::metrics::Metrics::new().unwrap()),
+    );
 
     let state = Arc::new(RwLock::new(app_state));
     let request_body = json!({ "name": "Bob" }).to_string();
@@ -245,14 +245,13 @@ routes:
         spec_path.display()
     );
 
-    let config: Config = serde_yaml::from_str(&config_yaml)?;
-    let app_state = AppState {
+    let config = Config::from_str(&config_yaml)?;
+    let http_client = build_http_client(&config.global.http_client);
+    let app_state = AppState::new(
         config,
-        schema_cache: SchemaCache::new(),
-        openapi_cache: OpenApiCache::new(),
-        http_client: build_http_client(),
-        metrics: std::sync::Arc::new(schema_gateway::metrics::Metrics::new().unwrap()),
-    };
+        http_client,
+        std::sync::Arc::new(schema_gateway::metrics::Metrics::new().unwrap()),
+    );
 
     let state = Arc::new(RwLock::new(app_state));
     let request = Request::builder()
@@ -266,3 +265,794 @@ routes:
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
     Ok(())
 }
+
+#[test]
+fn test_openapi_cache_eviction_drops_operations() {
+    let spec = r#"
+openapi: 3.0.0
+info:
+  title: Demo
+  version: "1.0.0"
+paths:
+  /api/users:
+    post:
+      requestBody:
+        content:
+          application/json:
+            schema:
+              type: object
+"#;
+
+    let first_path = write_openapi_spec(spec);
+    let second_path = write_openapi_spec(spec);
+    let cache = OpenApiCache::with_max_entries(Some(1));
+
+    let plan1 = cache
+        .load_operation(&first_path, "/api/users", &Method::POST, None)
+        .expect("load first");
+    cache
+        .load_operation(&second_path, "/api/users", &Method::POST, None)
+        .expect("load second");
+
+    assert_eq!(cache.len(), 1);
+    assert!(!cache.contains_spec(&first_path));
+    assert!(cache.contains_spec(&second_path));
+
+    // Reloading the evicted spec must recompile its operation plan
+    let plan1_again = cache
+        .load_operation(&first_path, "/api/users", &Method::POST, None)
+        .expect("reload first");
+    assert!(!Arc::ptr_eq(
+        &plan1.schema.expect("has schema"),
+        &plan1_again.schema.expect("has schema"),
+    ));
+    assert!(!cache.contains_spec(&second_path));
+}
+
+#[tokio::test]
+async fn test_openapi_head_without_body_skips_body_validation() -> TestResult {
+    use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+    let spec_path = write_openapi_spec(
+        r#"
+openapi: 3.0.0
+info:
+  title: Demo
+  version: "1.0.0"
+paths:
+  /api/users:
+    head:
+      requestBody:
+        required: true
+        content:
+          application/json:
+            schema:
+              type: object
+              required: ["email"]
+"#,
+    );
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::method("HEAD"))
+        .and(matchers::path("/api/users"))
+        .respond_with(ResponseTemplate::new(200).insert_header("x-total-count", "3"))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: false
+
+routes:
+  - path: /api/users
+    method: HEAD
+    openapi: {}
+    upstream: {}
+"#,
+        spec_path.display(),
+        mock_server.uri()
+    ))?;
+    let http_client = build_http_client(&config.global.http_client);
+    let app_state = AppState::new(
+        config,
+        http_client,
+        std::sync::Arc::new(schema_gateway::metrics::Metrics::new().unwrap()),
+    );
+
+    let state = Arc::new(RwLock::new(app_state));
+    let request = Request::builder()
+        .method(Method::HEAD)
+        .uri("/api/users")
+        .body(Body::empty())?;
+
+    let (parts, body) = request.into_parts();
+    let response = handle_request(State(state), parts.method, parts.uri, parts.headers, body).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response
+            .headers()
+            .get("x-total-count")
+            .and_then(|v| v.to_str().ok()),
+        Some("3")
+    );
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    assert!(body.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_openapi_negative_cache_replays_failure_until_ttl() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("openapi.yaml");
+    let metrics = schema_gateway::metrics::Metrics::new()?;
+    let cache = OpenApiCache::new()
+        .with_metrics(&metrics)
+        .with_negative_ttl(std::time::Duration::from_millis(200));
+
+    // The first attempt really reads the filesystem
+    let first = cache
+        .load_operation(&path, "/api/users", &Method::POST, None)
+        .err()
+        .ok_or("spec is missing")?;
+    assert_eq!(metrics.openapi_spec_loads_total.get(), 1.0);
+
+    // The file appears, but the failure is replayed without re-reading it
+    fs::write(
+        &path,
+        "openapi: 3.0.0\ninfo: { title: Demo, version: \"1.0.0\" }\npaths:\n  /api/users:\n    post:\n      responses: { '200': { description: ok } }\n",
+    )?;
+    for _ in 0..3 {
+        let second = cache
+            .load_operation(&path, "/api/users", &Method::POST, None)
+            .err()
+            .ok_or("failure is cached")?;
+        assert!(matches!(second, Error::Cached { .. }));
+        assert_eq!(second.to_string(), first.to_string());
+    }
+    assert_eq!(metrics.openapi_spec_loads_total.get(), 1.0);
+
+    std::thread::sleep(std::time::Duration::from_millis(250));
+    cache.load_operation(&path, "/api/users", &Method::POST, None)?;
+    assert_eq!(metrics.openapi_spec_loads_total.get(), 2.0);
+    assert!(cache.contains_spec(&path));
+    Ok(())
+}
+
+fn write_spec_files(files: &[(&str, &str)]) -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    for (name, contents) in files {
+        let path = dir.path().join(name);
+        fs::create_dir_all(path.parent().expect("parent dir")).expect("create spec dir");
+        fs::write(&path, contents).expect("write spec");
+    }
+    let root = dir.path().to_path_buf();
+    let _ = Box::leak(Box::new(dir));
+    root
+}
+
+#[test]
+fn test_openapi_external_file_refs() -> TestResult {
+    let root = write_spec_files(&[
+        (
+            "openapi.yaml",
+            r#"
+openapi: 3.0.0
+info:
+  title: Demo
+  version: "1.0.0"
+paths:
+  /api/users/{id}:
+    put:
+      parameters:
+        - $ref: ./components/params.yaml#/UserId
+      requestBody:
+        required: true
+        content:
+          application/json:
+            schema:
+              $ref: ./components/user.yaml#/User
+"#,
+        ),
+        (
+            "components/user.yaml",
+            r#"
+User:
+  type: object
+  required: [email, address]
+  properties:
+    email:
+      $ref: ../shared/common.yaml#/Email
+    address:
+      $ref: '#/Address'
+Address:
+  type: object
+  required: [city]
+  properties:
+    city: { type: string }
+"#,
+        ),
+        (
+            "components/params.yaml",
+            r#"
+UserId:
+  $ref: '#/IdParam'
+IdParam:
+  name: id
+  in: path
+  required: true
+  schema:
+    $ref: ../shared/common.yaml#/Id
+"#,
+        ),
+        (
+            "shared/common.yaml",
+            r#"
+Email:
+  type: string
+  pattern: "@"
+Id:
+  type: integer
+"#,
+        ),
+    ]);
+
+    let cache = OpenApiCache::new();
+    let plan = cache.load_operation(
+        root.join("openapi.yaml"),
+        "/api/users/:id",
+        &Method::PUT,
+        None,
+    )?;
+
+    let schema = plan.schema.expect("has schema");
+    assert!(schema.is_valid(&json!({"email": "a@b.c", "address": {"city": "Oslo"}})));
+    assert!(!schema.is_valid(&json!({"email": "nope", "address": {"city": "Oslo"}})));
+    assert!(!schema.is_valid(&json!({"email": "a@b.c", "address": {}})));
+
+    assert_eq!(plan.parameters.len(), 1);
+    assert_eq!(plan.parameters[0].name, "id");
+    assert!(plan.parameters[0].coerce_value("abc").is_err());
+
+    // Referenced files share the spec cache
+    assert!(cache.contains_spec(root.join("components/user.yaml")));
+    assert!(cache.contains_spec(root.join("shared/common.yaml")));
+    Ok(())
+}
+
+#[test]
+fn test_openapi_circular_file_refs_are_reported() {
+    let root = write_spec_files(&[
+        (
+            "openapi.yaml",
+            r#"
+openapi: 3.0.0
+info:
+  title: Demo
+  version: "1.0.0"
+paths:
+  /api/nodes:
+    post:
+      requestBody:
+        content:
+          application/json:
+            schema:
+              $ref: ./a.yaml#/A
+"#,
+        ),
+        ("a.yaml", "A:\n  $ref: ./b.yaml#/B\n"),
+        ("b.yaml", "B:\n  $ref: ./a.yaml#/A\n"),
+    ]);
+
+    let err = OpenApiCache::new()
+        .load_operation(root.join("openapi.yaml"), "/api/nodes", &Method::POST, None)
+        .err()
+        .expect("circular refs must fail");
+
+    match err {
+        Error::OpenApi { message, .. } => {
+            assert!(
+                message.contains("circular reference"),
+                "unexpected message: {}",
+                message
+            );
+            assert!(
+                message.contains("a.yaml#/A -> "),
+                "missing cycle path: {}",
+                message
+            );
+            assert!(
+                message.contains("b.yaml#/B -> "),
+                "missing cycle path: {}",
+                message
+            );
+        }
+        other => panic!("expected Error::OpenApi, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_openapi_missing_ref_file_is_openapi_error() {
+    let root = write_spec_files(&[(
+        "openapi.yaml",
+        r#"
+openapi: 3.0.0
+info:
+  title: Demo
+  version: "1.0.0"
+paths:
+  /api/users:
+    post:
+      requestBody:
+        content:
+          application/json:
+            schema:
+              $ref: ./missing.yaml#/User
+"#,
+    )]);
+
+    let err = OpenApiCache::new()
+        .load_operation(root.join("openapi.yaml"), "/api/users", &Method::POST, None)
+        .err()
+        .expect("missing file must fail");
+
+    match err {
+        Error::OpenApi { message, .. } => assert!(
+            message.contains("failed to load reference './missing.yaml#/User'"),
+            "unexpected message: {}",
+            message
+        ),
+        other => panic!("expected Error::OpenApi, got {:?}", other),
+    }
+}
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/openapi")
+        .join(name)
+}
+
+#[test]
+fn test_openapi_3_0_schema_idioms_are_translated() -> TestResult {
+    let plan = OpenApiCache::new().load_operation(
+        fixture("orders-3.0.yaml"),
+        "/api/orders",
+        &Method::POST,
+        None,
+    )?;
+    let schema = plan.schema.expect("has schema");
+
+    assert!(schema.is_valid(&json!({"quantity": 1})));
+    assert!(!schema.is_valid(&json!({"quantity": 0})));
+    assert!(schema.is_valid(&json!({"quantity": 1, "discount": 1})));
+    assert!(!schema.is_valid(&json!({"quantity": 1, "discount": 1.5})));
+    Ok(())
+}
+
+#[test]
+fn test_openapi_3_1_schemas_compile_as_2020_12() -> TestResult {
+    let plan = OpenApiCache::new().load_operation(
+        fixture("orders-3.1.yaml"),
+        "/api/orders",
+        &Method::POST,
+        None,
+    )?;
+    let schema = plan.schema.expect("has schema");
+
+    assert!(schema.is_valid(&json!({"quantity": 1, "tags": ["a", "b", 3]})));
+    assert!(schema.is_valid(&json!({"quantity": 1, "note": null})));
+    assert!(!schema.is_valid(&json!({"quantity": 0})));
+    assert!(!schema.is_valid(&json!({"quantity": 1, "tags": ["a", 3]})));
+    assert!(!schema.is_valid(&json!({"quantity": 1, "extra": true})));
+
+    // Type arrays still drive query parameter coercion
+    let limit = &plan.parameters[0];
+    assert_eq!(limit.coerce_value("10")?, json!(10));
+    assert!(limit.coerce_value("ten").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_openapi_recursive_schemas_validate() -> TestResult {
+    let plan = OpenApiCache::new().load_operation(
+        fixture("tree.yaml"),
+        "/api/trees",
+        &Method::POST,
+        None,
+    )?;
+    let schema = plan.schema.expect("has schema");
+
+    let tree = json!({"value": 1, "children": [
+        {"value": 2},
+        {"value": 3, "children": [{"value": 4, "children": []}]}
+    ]});
+    assert!(schema.is_valid(&tree));
+    // Errors deep in the tree are still found
+    assert!(!schema.is_valid(&json!({"value": 1, "children": [{"value": 2, "children": [{}]}]})));
+    assert!(!schema.is_valid(&json!({"value": 1, "children": [{"value": "two"}]})));
+
+    // Mutually recursive response schemas
+    let folder = plan
+        .response_schemas
+        .get(&ResponseKey::Status(201))
+        .and_then(|c| c.lookup("application/json").flatten())
+        .expect("has response schema");
+    assert!(folder.is_valid(&json!({"name": "root", "entries": [
+        {"name": "a.txt", "parent": null},
+        {"name": "b.txt", "parent": {"name": "root", "entries": [{"name": "c.txt"}]}}
+    ]})));
+    assert!(!folder.is_valid(&json!({"name": "root", "entries": [
+        {"name": "a.txt", "parent": {"entries": []}}
+    ]})));
+    Ok(())
+}
+
+#[test]
+fn test_openapi_3_1_json_schema_dialect() -> TestResult {
+    let spec = fs::read_to_string(fixture("orders-3.1.yaml"))?.replace(
+        "openapi: 3.1.0\n",
+        "openapi: 3.1.0\njsonSchemaDialect: http://json-schema.org/draft-07/schema#\n",
+    );
+    let path = write_openapi_spec(&spec);
+
+    let plan = OpenApiCache::new().load_operation(&path, "/api/orders", &Method::POST, None)?;
+    let schema = plan.schema.expect("has schema");
+
+    // Draft 7 has no unevaluatedProperties or minContains
+    assert!(schema.is_valid(&json!({"quantity": 1, "extra": true})));
+    assert!(schema.is_valid(&json!({"quantity": 1, "tags": ["a", 3]})));
+    assert!(!schema.is_valid(&json!({"quantity": 0})));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_openapi_3_0_nullable_fields_accept_null() -> TestResult {
+    let spec_path = write_openapi_spec(
+        r##"
+openapi: 3.0.3
+info:
+  title: Demo
+  version: "1.0.0"
+paths:
+  /api/profiles/{id}:
+    put:
+      parameters:
+        - name: id
+          in: path
+          required: true
+          schema:
+            type: string
+      requestBody:
+        required: true
+        content:
+          application/json:
+            schema:
+              $ref: "#/components/schemas/Profile"
+      responses:
+        "200":
+          description: ok
+          content:
+            application/json:
+              schema:
+                $ref: "#/components/schemas/Profile"
+components:
+  schemas:
+    Profile:
+      type: object
+      required: [name]
+      properties:
+        name:
+          type: string
+          nullable: true
+        status:
+          type: string
+          enum: [active, disabled]
+          nullable: true
+        aliases:
+          type: array
+          items:
+            type: string
+            nullable: true
+        manager:
+          nullable: true
+          allOf:
+            - $ref: "#/components/schemas/Person"
+        nickname:
+          type: string
+          nullable: false
+    Person:
+      type: object
+      required: [id]
+      properties:
+        id: { type: integer }
+"##,
+    );
+
+    let plan =
+        OpenApiCache::new().load_operation(&spec_path, "/api/profiles/:id", &Method::PUT, None)?;
+    let schema = plan.schema.clone().expect("has schema");
+
+    assert!(schema.is_valid(&json!({
+        "name": null,
+        "status": null,
+        "aliases": ["a", null],
+        "manager": null
+    })));
+    assert!(schema.is_valid(&json!({"name": "Ada", "manager": {"id": 1}})));
+    assert!(!schema.is_valid(&json!({"name": 7})));
+    assert!(!schema.is_valid(&json!({"name": "Ada", "status": "gone"})));
+    assert!(!schema.is_valid(&json!({"name": "Ada", "manager": {}})));
+    assert!(!schema.is_valid(&json!({"name": "Ada", "nickname": null})));
+
+    let response_schema = plan
+        .response_schemas
+        .get(&ResponseKey::Status(200))
+        .and_then(|content| content.lookup("application/json").flatten())
+        .expect("response schema");
+    assert!(response_schema.is_valid(&json!({"name": null})));
+
+    // End to end: a null field is no longer rejected by the gateway
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: false
+routes:
+  - path: /api/profiles/:id
+    method: PUT
+    openapi: {}
+    upstream: http://127.0.0.1:1
+"#,
+        spec_path.display()
+    ))?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(schema_gateway::metrics::Metrics::new()?),
+    )));
+    let request = Request::builder()
+        .method(Method::PUT)
+        .uri("/api/profiles/42")
+        .header("content-type", "application/json")
+        .body(Body::from(r#"{"name": null}"#))?;
+    let (parts, body) = request.into_parts();
+    let response = handle_request(State(state), parts.method, parts.uri, parts.headers, body).await;
+    // Validation passed, so the gateway tried the (unreachable) upstream
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    Ok(())
+}
+
+#[test]
+fn test_openapi_errors_report_their_stage() {
+    let stage_of = |spec: &str, route: &str| match OpenApiCache::new().load_operation(
+        write_openapi_spec(spec),
+        route,
+        &Method::POST,
+        None,
+    ) {
+        Err(Error::OpenApi { stage, .. }) => stage,
+        Err(other) => panic!("expected Error::OpenApi, got {:?}", other),
+        Ok(_) => panic!("expected {} to fail", route),
+    };
+
+    let body_with_schema = |schema: &str| {
+        format!(
+            r#"
+openapi: 3.0.3
+info: {{ title: Demo, version: "1.0.0" }}
+paths:
+  /api/users:
+    post:
+      requestBody:
+        content:
+          application/json:
+            schema: {}
+"#,
+            schema
+        )
+    };
+
+    assert_eq!(
+        stage_of("openapi: [unterminated", "/api/users"),
+        OpenApiErrorStage::Parse
+    );
+    assert_eq!(
+        stage_of(&body_with_schema("{ type: 7 }"), "/api/users"),
+        OpenApiErrorStage::Compile
+    );
+    assert_eq!(
+        stage_of(&body_with_schema("{ type: object }"), "/api/orders"),
+        OpenApiErrorStage::OperationNotFound
+    );
+    assert_eq!(
+        stage_of(
+            &body_with_schema("{ $ref: '#/components/schemas/Missing' }"),
+            "/api/users"
+        ),
+        OpenApiErrorStage::ReferenceNotFound
+    );
+}
+
+#[test]
+fn test_openapi_broken_refs_fail_at_load() {
+    let spec = write_openapi_spec(
+        r##"
+openapi: 3.0.3
+info: { title: Demo, version: "1.0.0" }
+paths:
+  /api/users:
+    post:
+      requestBody:
+        content:
+          application/json:
+            schema: { type: object }
+  /api/orders:
+    post:
+      requestBody:
+        content:
+          application/json:
+            schema:
+              properties:
+                item: { $ref: '#/components/schemas/Item' }
+                owner: { $ref: '#/components/schemas/Owner' }
+                again: { $ref: '#/components/schemas/Item' }
+components:
+  schemas:
+    Tag: { type: string }
+"##,
+    );
+
+    // The broken refs belong to another operation but still fail the spec
+    match OpenApiCache::new().load_operation(&spec, "/api/users", &Method::POST, None) {
+        Err(Error::OpenApi { stage, message, .. }) => {
+            assert_eq!(stage, OpenApiErrorStage::ReferenceNotFound);
+            assert_eq!(
+                message,
+                "unresolved references: '#/components/schemas/Item', '#/components/schemas/Owner'"
+            );
+        }
+        other => panic!("expected a reference error, got {:?}", other.map(|_| ())),
+    }
+}
+
+const BASE_PATH_SPEC: &str = r#"
+openapi: 3.0.3
+info: { title: Demo, version: "1.0.0" }
+servers:
+  - url: https://api.example.com/v1
+paths:
+  /users/{id}:
+    get:
+      parameters:
+        - name: id
+          in: path
+          required: true
+          schema: { type: integer }
+"#;
+
+async fn send_base_path_request(routes: &str, uri: &str) -> TestResult<StatusCode> {
+    let config = Config::from_str(&format!(
+        "global:\n  forward_on_error: false\n\nroutes:\n{}",
+        routes
+    ))?;
+    let metrics = Arc::new(schema_gateway::metrics::Metrics::new()?);
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(config, http_client, metrics)));
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(uri)
+        .body(Body::empty())?;
+    let (parts, body) = request.into_parts();
+    let response = handle_request(State(state), parts.method, parts.uri, parts.headers, body).await;
+    Ok(response.status())
+}
+
+#[tokio::test]
+async fn test_openapi_base_path_from_servers() -> TestResult {
+    use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+    let spec_path = write_openapi_spec(BASE_PATH_SPEC);
+    let mock_server = MockServer::start().await;
+    // The upstream sees the client's path, base path included
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/v1/users/7"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let routes = format!(
+        r#"
+  - path: /v1/users/:id
+    method: GET
+    openapi: {}
+    upstream: {}
+"#,
+        spec_path.display(),
+        mock_server.uri()
+    );
+
+    assert_eq!(
+        send_base_path_request(&routes, "/v1/users/7").await?,
+        StatusCode::OK
+    );
+    assert_eq!(
+        send_base_path_request(&routes, "/v1/users/abc").await?,
+        StatusCode::BAD_REQUEST
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_openapi_explicit_base_path() -> TestResult {
+    use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+    let spec_path = write_openapi_spec(BASE_PATH_SPEC);
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/gateway/users/7"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let routes = format!(
+        r#"
+  - path: /gateway/users/:id
+    method: GET
+    openapi:
+      spec: {}
+      base_path: /gateway/
+    upstream: {}
+"#,
+        spec_path.display(),
+        mock_server.uri()
+    );
+
+    assert_eq!(
+        send_base_path_request(&routes, "/gateway/users/7").await?,
+        StatusCode::OK
+    );
+    assert_eq!(
+        send_base_path_request(&routes, "/gateway/users/abc").await?,
+        StatusCode::BAD_REQUEST
+    );
+    Ok(())
+}
+
+#[test]
+fn test_openapi_operation_path() -> TestResult {
+    let spec_path = write_openapi_spec(
+        r#"
+openapi: 3.0.3
+info: { title: Demo, version: "1.0.0" }
+servers:
+  - url: https://{region}.example.com/api/{version}/
+    variables:
+      region: { default: eu }
+      version: { default: v2 }
+  - url: /other
+paths: {}
+"#,
+    );
+    let cache = OpenApiCache::new();
+
+    let derived = |path| cache.operation_path(&spec_path, None, path);
+    assert_eq!(derived("/api/v2/users")?, "/users");
+    assert_eq!(derived("/api/v2")?, "/");
+    // Only whole segments are stripped
+    assert_eq!(derived("/api/v2users")?, "/api/v2users");
+    assert_eq!(derived("/users")?, "/users");
+
+    assert_eq!(
+        cache.operation_path(&spec_path, Some("/api"), "/api/v2/users")?,
+        "/v2/users"
+    );
+    assert_eq!(
+        cache.operation_path(&spec_path, Some(""), "/api/v2/users")?,
+        "/api/v2/users"
+    );
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use schema_gateway::pid_file::PidFile;
+use std::fs;
+use std::path::PathBuf;
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn pid_path() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("gateway.pid");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+#[test]
+fn test_pid_file_holds_our_pid_until_dropped() -> TestResult {
+    let path = pid_path();
+    let pid_file = PidFile::create(&path)?;
+
+    assert_eq!(pid_file.path(), path.as_path());
+    assert_eq!(
+        fs::read_to_string(&path)?.trim(),
+        std::process::id().to_string()
+    );
+
+    drop(pid_file);
+    assert!(!path.exists());
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_running_process_blocks_a_second_start() -> TestResult {
+    let mut other = std::process::Command::new("sleep").arg("30").spawn()?;
+    let other_pid = other.id().to_string();
+    let path = pid_path();
+    fs::write(&path, &other_pid)?;
+
+    let result = PidFile::create(&path);
+    other.kill()?;
+    other.wait()?;
+
+    let error = result.expect_err("start must fail while the other process runs");
+    assert!(
+        error.contains(&format!("running process {}", other_pid)),
+        "{}",
+        error
+    );
+    // The other instance's file is left alone
+    assert_eq!(fs::read_to_string(&path)?.trim(), other_pid);
+    Ok(())
+}
+
+#[test]
+fn test_pid_file_naming_this_process_is_stale() -> TestResult {
+    // What a restarted container finds: its old PID file, holding the PID
+    // the new process was given too
+    let path = pid_path();
+    fs::write(&path, format!("{}\n", std::process::id()))?;
+
+    let _pid_file = PidFile::create(&path)?;
+    assert_eq!(
+        fs::read_to_string(&path)?.trim(),
+        std::process::id().to_string()
+    );
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_stale_pid_file_is_replaced() -> TestResult {
+    let mut child = std::process::Command::new("true").spawn()?;
+    let exited_pid = child.id();
+    child.wait()?;
+
+    for stale in [
+        exited_pid.to_string(),
+        "not a pid".to_string(),
+        "0".to_string(),
+    ] {
+        let path = pid_path();
+        fs::write(&path, &stale)?;
+
+        let _pid_file = PidFile::create(&path)?;
+        assert_eq!(
+            fs::read_to_string(&path)?.trim(),
+            std::process::id().to_string(),
+            "{}",
+            stale
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_unwritable_pid_file_is_an_error() {
+    let path = pid_path().join("missing-dir").join("gateway.pid");
+    let error = PidFile::create(&path).expect_err("directory doesn't exist");
+    assert!(error.starts_with("Failed to write PID file"), "{}", error);
+}
@@ -1,4 +1,10 @@
+use axum::http::{HeaderMap, Method};
+use schema_gateway::config::{Config, HttpClientConfig};
+use schema_gateway::handler::build_http_client;
+use schema_gateway::proxy::{forward_request, ForwardOptions, ResponseHeaderRules};
 use serde_json::json;
+use std::collections::HashMap;
+use std::str::FromStr;
 use wiremock::{
     matchers::{body_json, header, method, path},
     Mock, MockServer, ResponseTemplate,
@@ -255,3 +261,658 @@ async fn test_handle_upstream_timeout() {
     // In the gateway implementation, this timeout would be caught
     // and return 504 Gateway Timeout
 }
+
+#[tokio::test]
+async fn test_strip_and_rename_response_headers() {
+    // Given: Upstream leaks infrastructure details in response headers
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/users"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("Server", "nginx/1.25.3")
+                .insert_header("X-Powered-By", "Express")
+                .insert_header("X-Internal-Trace", "abc-123")
+                .insert_header("X-Kept", "yes"),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let rules = ResponseHeaderRules {
+        strip: vec!["server".to_string(), "X-POWERED-BY".to_string()],
+        rename: HashMap::from([("x-internal-trace".to_string(), "x-trace-id".to_string())]),
+    };
+
+    // When: Forwarding through the proxy
+    let response = forward_request(
+        &build_http_client(&HttpClientConfig::default()),
+        Method::GET,
+        &mock_server.uri(),
+        "/api/users",
+        HeaderMap::new(),
+        Vec::new(),
+        &ForwardOptions {
+            response_headers: rules,
+            ..Default::default()
+        },
+    )
+    .await;
+
+    // Then: Stripped headers are gone, renamed ones carry the original value
+    let headers = response.headers();
+    assert!(headers.get("server").is_none());
+    assert!(headers.get("x-powered-by").is_none());
+    assert!(headers.get("x-internal-trace").is_none());
+    assert_eq!(
+        headers.get("x-trace-id").and_then(|v| v.to_str().ok()),
+        Some("abc-123")
+    );
+    assert_eq!(
+        headers.get("x-kept").and_then(|v| v.to_str().ok()),
+        Some("yes")
+    );
+}
+
+#[tokio::test]
+async fn test_repeated_response_headers_are_all_forwarded() {
+    // Given: An upstream setting two cookies and sending other list headers
+    // as separate lines
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/login"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .append_header("Set-Cookie", "session=abc; HttpOnly")
+                .append_header("Set-Cookie", "theme=dark")
+                .append_header("Link", "</a>; rel=next")
+                .append_header("Link", "</b>; rel=prev")
+                .append_header("Vary", "Accept")
+                .append_header("Vary", "Accept-Encoding")
+                .append_header("Warning", "110 - \"Response is Stale\"")
+                .append_header("Warning", "199 - \"Miscellaneous\""),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    // When: Forwarding through the proxy
+    let response = forward_request(
+        &build_http_client(&HttpClientConfig::default()),
+        Method::POST,
+        &mock_server.uri(),
+        "/api/login",
+        HeaderMap::new(),
+        Vec::new(),
+        &ForwardOptions::default(),
+    )
+    .await;
+
+    // Then: Every value reaches the client, in order
+    let values = |name: &str| -> Vec<String> {
+        response
+            .headers()
+            .get_all(name)
+            .iter()
+            .filter_map(|v| v.to_str().ok().map(str::to_string))
+            .collect()
+    };
+    assert_eq!(
+        values("set-cookie"),
+        vec!["session=abc; HttpOnly", "theme=dark"]
+    );
+    assert_eq!(values("link"), vec!["</a>; rel=next", "</b>; rel=prev"]);
+    assert_eq!(values("vary"), vec!["Accept", "Accept-Encoding"]);
+    assert_eq!(values("warning").len(), 2);
+}
+
+#[test]
+fn test_route_response_header_rules_override_global() {
+    let config = Config::from_str(
+        r#"
+global:
+  strip_response_headers: [server, x-powered-by]
+  rename_response_headers:
+    x-internal-trace: x-trace-id
+
+routes:
+  - path: /api/users
+    method: GET
+    upstream: http://backend:3000
+  - path: /api/debug
+    method: GET
+    upstream: http://backend:3000
+    config:
+      strip_response_headers: []
+"#,
+    )
+    .expect("parse config");
+
+    let users = config.get_effective_config(&config.routes[0]);
+    let rules = ResponseHeaderRules::from_config(&users);
+    assert_eq!(rules.strip, vec!["server", "x-powered-by"]);
+    assert_eq!(
+        rules.rename.get("x-internal-trace").map(String::as_str),
+        Some("x-trace-id")
+    );
+
+    let debug = config.get_effective_config(&config.routes[1]);
+    assert!(debug.strip_response_headers.is_empty());
+    assert_eq!(debug.rename_response_headers.len(), 1);
+}
+
+#[tokio::test]
+async fn test_head_response_body_is_stripped() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // Given: An upstream that (incorrectly) sends a body with its HEAD response
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind upstream");
+    let upstream = format!("http://{}", listener.local_addr().expect("local addr"));
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.expect("accept");
+        let mut buf = [0u8; 1024];
+        let _ = socket.read(&mut buf).await;
+        let _ = socket
+            .write_all(
+                b"HTTP/1.1 200 OK\r\nContent-Length: 17\r\nX-Kept: yes\r\n\r\n{\"id\": \"secret\"}\n",
+            )
+            .await;
+    });
+
+    // When: Forwarding a HEAD request
+    let response = forward_request(
+        &build_http_client(&HttpClientConfig::default()),
+        Method::HEAD,
+        &upstream,
+        "/api/users/1",
+        HeaderMap::new(),
+        Vec::new(),
+        &ForwardOptions::default(),
+    )
+    .await;
+
+    // Then: Headers survive untouched but the body is empty
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response
+            .headers()
+            .get("content-length")
+            .and_then(|v| v.to_str().ok()),
+        Some("17")
+    );
+    assert_eq!(
+        response
+            .headers()
+            .get("x-kept")
+            .and_then(|v| v.to_str().ok()),
+        Some("yes")
+    );
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("read body");
+    assert!(body.is_empty(), "expected empty HEAD body, got {:?}", body);
+}
+
+/// Start a raw HTTP upstream that sends each request head (lowercased) and
+/// the first body bytes to `tx`, then replies 200 once `ready` matches what
+/// it has read so far.
+async fn start_recording_upstream(
+    ready: fn(&str) -> bool,
+) -> (String, tokio::sync::mpsc::UnboundedReceiver<String>) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind upstream");
+    let upstream = format!("http://{}", listener.local_addr().expect("local addr"));
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.expect("accept");
+        let mut received = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = socket.read(&mut buf).await.unwrap_or(0);
+            if n == 0 {
+                break;
+            }
+            received.extend_from_slice(&buf[..n]);
+            if ready(&String::from_utf8_lossy(&received)) {
+                break;
+            }
+        }
+        let _ = tx.send(String::from_utf8_lossy(&received).to_lowercase());
+        let _ = socket
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok")
+            .await;
+    });
+    (upstream, rx)
+}
+
+fn streaming_state(
+    upstream: &str,
+    stream_unvalidated: bool,
+) -> std::sync::Arc<tokio::sync::RwLock<schema_gateway::handler::AppState>> {
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  stream_unvalidated: {}
+routes:
+  - path: /upload
+    method: POST
+    upstream: {}
+"#,
+        stream_unvalidated, upstream
+    ))
+    .expect("parse config");
+    let metrics = schema_gateway::metrics::Metrics::new().expect("metrics");
+    let http_client = build_http_client(&config.global.http_client);
+    std::sync::Arc::new(tokio::sync::RwLock::new(
+        schema_gateway::handler::AppState::new(config, http_client, std::sync::Arc::new(metrics)),
+    ))
+}
+
+#[tokio::test]
+async fn test_unvalidated_body_is_streamed_upstream() {
+    use axum::extract::State;
+
+    // Given: An upstream that answers as soon as the first chunk arrives
+    let (upstream, mut received) =
+        start_recording_upstream(|seen| seen.contains("first-chunk")).await;
+    let state = streaming_state(&upstream, true);
+
+    // And: A client body whose second chunk is only sent after the response
+    let (chunks, rx) = tokio::sync::mpsc::channel::<Result<&'static str, std::io::Error>>(1);
+    let stream = futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    });
+    let mut headers = HeaderMap::new();
+    headers.insert("x-custom", "kept".parse().expect("header value"));
+    let request = schema_gateway::handler::handle_request(
+        State(state),
+        Method::POST,
+        "/upload".parse().expect("uri"),
+        headers,
+        axum::body::Body::from_stream(stream),
+    );
+    let request = tokio::spawn(request);
+    chunks.send(Ok("first-chunk")).await.expect("send chunk");
+
+    // Then: The response arrives while the client is still sending, so the
+    // gateway didn't wait for the whole body
+    let response = tokio::time::timeout(std::time::Duration::from_secs(5), request)
+        .await
+        .expect("gateway buffered the body instead of streaming it")
+        .expect("handler task");
+    assert_eq!(response.status(), 200);
+    drop(chunks);
+
+    // And: Headers were forwarded, minus the skipped ones
+    let head = received.recv().await.expect("upstream request");
+    assert!(head.contains("x-custom: kept"), "got {}", head);
+    assert!(head.contains("transfer-encoding: chunked"), "got {}", head);
+}
+
+#[tokio::test]
+async fn test_unvalidated_body_buffered_when_streaming_disabled() {
+    use axum::extract::State;
+
+    // Given: Streaming turned off
+    let (upstream, mut received) = start_recording_upstream(|seen| seen.contains("hello")).await;
+    let state = streaming_state(&upstream, false);
+
+    // When: Forwarding a body without a Content-Length header
+    let body = futures_util::stream::iter([Ok::<_, std::io::Error>("hel"), Ok("lo")]);
+    let response = schema_gateway::handler::handle_request(
+        State(state),
+        Method::POST,
+        "/upload".parse().expect("uri"),
+        HeaderMap::new(),
+        axum::body::Body::from_stream(body),
+    )
+    .await;
+
+    // Then: The upstream gets the whole body with a known length
+    assert_eq!(response.status(), 200);
+    let head = received.recv().await.expect("upstream request");
+    assert!(head.contains("content-length: 5"), "got {}", head);
+    assert!(!head.contains("transfer-encoding"), "got {}", head);
+}
+
+#[tokio::test]
+async fn test_multi_megabyte_upload_is_streamed_through() {
+    use axum::extract::State;
+
+    // Given: A passthrough route
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/upload"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&mock_server)
+        .await;
+    let state = streaming_state(&mock_server.uri(), true);
+
+    // When: Uploading 8 MiB in 64 KiB chunks, with no Content-Length
+    let chunk = vec![b'x'; 64 * 1024];
+    let chunks = (0..128).map(move |_| Ok::<_, std::io::Error>(chunk.clone()));
+    let response = schema_gateway::handler::handle_request(
+        State(state),
+        Method::POST,
+        "/upload".parse().expect("uri"),
+        HeaderMap::new(),
+        axum::body::Body::from_stream(futures_util::stream::iter(chunks)),
+    )
+    .await;
+
+    // Then: The upstream gets every byte
+    assert_eq!(response.status(), 200);
+    let received = mock_server.received_requests().await.unwrap_or_default();
+    let request = received.first().expect("upstream request");
+    assert_eq!(request.body.len(), 8 * 1024 * 1024);
+    assert!(request.body.iter().all(|b| *b == b'x'));
+}
+
+#[tokio::test]
+async fn test_chunked_response_is_streamed_back() {
+    use axum::body::HttpBody;
+    use axum::extract::State;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // Given: An upstream that sends one chunk, then waits before finishing
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind upstream");
+    let upstream = format!("http://{}", listener.local_addr().expect("local addr"));
+    let (finish, finished) = tokio::sync::oneshot::channel::<()>();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.expect("accept");
+        let mut buf = [0u8; 4096];
+        let _ = socket.read(&mut buf).await;
+        let _ = socket
+            .write_all(
+                b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nTransfer-Encoding: chunked\r\n\r\n\
+                  d\r\ndata: first\n\n\r\n",
+            )
+            .await;
+        let _ = finished.await;
+        let _ = socket
+            .write_all(b"e\r\ndata: second\n\n\r\n0\r\n\r\n")
+            .await;
+    });
+    let config = Config::from_str(&format!(
+        "routes:\n  - path: /events\n    method: GET\n    upstream: {}\n",
+        upstream
+    ))
+    .expect("parse config");
+    let metrics = schema_gateway::metrics::Metrics::new().expect("metrics");
+    let http_client = build_http_client(&config.global.http_client);
+    let state = std::sync::Arc::new(tokio::sync::RwLock::new(
+        schema_gateway::handler::AppState::new(config, http_client, std::sync::Arc::new(metrics)),
+    ));
+
+    // When: Requesting the event stream
+    let response = tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        schema_gateway::handler::handle_request(
+            State(state),
+            Method::GET,
+            "/events".parse().expect("uri"),
+            HeaderMap::new(),
+            axum::body::Body::empty(),
+        ),
+    )
+    .await
+    .expect("gateway waited for the whole response");
+
+    // Then: The first event arrives before the upstream has finished
+    assert_eq!(response.status(), 200);
+    let mut body = response.into_body();
+    let first = std::future::poll_fn(|cx| std::pin::Pin::new(&mut body).poll_frame(cx))
+        .await
+        .expect("first frame")
+        .expect("read frame")
+        .into_data()
+        .expect("data frame");
+    assert_eq!(&first[..], b"data: first\n\n");
+
+    // And: The rest follows once the upstream sends it
+    finish.send(()).expect("finish upstream");
+    let rest = axum::body::to_bytes(body, usize::MAX)
+        .await
+        .expect("read rest");
+    assert_eq!(&rest[..], b"data: second\n\n");
+}
+
+#[tokio::test]
+async fn test_validated_body_over_the_limit_is_rejected() {
+    use axum::extract::State;
+
+    // Given: A validated route that buffers at most 1 KiB
+    let dir = tempfile::tempdir().expect("temp dir");
+    let schema = dir.path().join("schema.json");
+    std::fs::write(&schema, r#"{"type": "object"}"#).expect("write schema");
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&mock_server)
+        .await;
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: false
+  max_request_body_bytes: 1024
+routes:
+  - path: /upload
+    method: POST
+    schema: {}
+    upstream: {}
+"#,
+        schema.display(),
+        mock_server.uri()
+    ))
+    .expect("parse config");
+    config.validate().expect("valid config");
+    let metrics = schema_gateway::metrics::Metrics::new().expect("metrics");
+    let http_client = build_http_client(&config.global.http_client);
+    let state = std::sync::Arc::new(tokio::sync::RwLock::new(
+        schema_gateway::handler::AppState::new(config, http_client, std::sync::Arc::new(metrics)),
+    ));
+    let send = |body: axum::body::Body, content_length: Option<usize>| {
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", "application/json".parse().expect("value"));
+        if let Some(len) = content_length {
+            headers.insert("content-length", len.into());
+        }
+        schema_gateway::handler::handle_request(
+            State(state.clone()),
+            Method::POST,
+            "/upload".parse().expect("uri"),
+            headers,
+            body,
+        )
+    };
+
+    // When/Then: A body under the limit is validated and forwarded
+    let response = send(axum::body::Body::from(r#"{"a": 1}"#), None).await;
+    assert_eq!(response.status(), 200);
+
+    // And: One announced as too large is refused before it's read
+    let large = format!(r#"{{"a": "{}"}}"#, "x".repeat(2048));
+    let response = send(axum::body::Body::from(large.clone()), Some(large.len())).await;
+    assert_eq!(response.status(), 413);
+
+    // And: So is one without a Content-Length that turns out too large
+    let chunks = large
+        .into_bytes()
+        .chunks(256)
+        .map(|chunk| Ok::<_, std::io::Error>(chunk.to_vec()))
+        .collect::<Vec<_>>();
+    let response = send(
+        axum::body::Body::from_stream(futures_util::stream::iter(chunks)),
+        None,
+    )
+    .await;
+    assert_eq!(response.status(), 413);
+    assert_eq!(
+        mock_server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .len(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_hop_by_hop_request_headers_are_not_forwarded() {
+    // Given: A client request carrying connection-level headers
+    let (upstream, mut received) = start_recording_upstream(|seen| seen.ends_with("{}")).await;
+    let mut headers = HeaderMap::new();
+    for (name, value) in [
+        ("transfer-encoding", "chunked"),
+        ("connection", "keep-alive, X-Custom"),
+        ("keep-alive", "timeout=5"),
+        ("x-custom", "for-the-gateway"),
+        ("proxy-authorization", "Basic c2VjcmV0"),
+        ("te", "trailers"),
+        ("x-kept", "yes"),
+    ] {
+        headers.insert(name, value.parse().expect("header value"));
+    }
+
+    // When: Forwarding it with the body the gateway read in
+    let response = forward_request(
+        &build_http_client(&HttpClientConfig::default()),
+        Method::POST,
+        &upstream,
+        "/api/users",
+        headers,
+        b"{}".to_vec(),
+        &ForwardOptions::default(),
+    )
+    .await;
+    assert_eq!(response.status(), 200);
+
+    // Then: Only end-to-end headers reach the upstream, and the body is sent
+    // with its real length
+    let head = received.recv().await.expect("upstream request");
+    assert!(head.contains("x-kept: yes"), "got {}", head);
+    assert!(head.contains("content-length: 2"), "got {}", head);
+    for name in [
+        "transfer-encoding",
+        "keep-alive",
+        "x-custom",
+        "proxy-authorization",
+        "te:",
+    ] {
+        assert!(!head.contains(name), "{} forwarded in {}", name, head);
+    }
+}
+
+#[tokio::test]
+async fn test_hop_by_hop_response_headers_are_not_returned() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // Given: An upstream answering with connection-level headers
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind upstream");
+    let upstream = format!("http://{}", listener.local_addr().expect("local addr"));
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.expect("accept");
+        let mut buf = [0u8; 1024];
+        let _ = socket.read(&mut buf).await;
+        let _ = socket
+            .write_all(
+                b"HTTP/1.1 200 OK\r\nConnection: X-Custom, close\r\nX-Custom: upstream-only\r\n\
+                  Keep-Alive: timeout=5\r\nProxy-Authenticate: Basic\r\nTrailer: X-Checksum\r\n\
+                  Transfer-Encoding: chunked\r\nX-Kept: yes\r\n\r\n2\r\nok\r\n0\r\n\r\n",
+            )
+            .await;
+    });
+
+    // When: Forwarding a request to it
+    let response = forward_request(
+        &build_http_client(&HttpClientConfig::default()),
+        Method::GET,
+        &upstream,
+        "/api/users",
+        HeaderMap::new(),
+        Vec::new(),
+        &ForwardOptions::default(),
+    )
+    .await;
+
+    // Then: The client sees the body and end-to-end headers only
+    let headers = response.headers().clone();
+    assert_eq!(
+        headers.get("x-kept").and_then(|v| v.to_str().ok()),
+        Some("yes")
+    );
+    for name in [
+        "connection",
+        "x-custom",
+        "keep-alive",
+        "proxy-authenticate",
+        "trailer",
+        "transfer-encoding",
+    ] {
+        assert!(
+            headers.get(name).is_none(),
+            "{} returned: {:?}",
+            name,
+            headers
+        );
+    }
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("read body");
+    assert_eq!(&body[..], b"ok");
+}
+
+#[tokio::test]
+async fn test_upstream_framing_headers_are_not_returned() {
+    // Given: A wiremock upstream sending a chunked body and connection
+    // options of its own
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("transfer-encoding", "chunked")
+                .insert_header("connection", "keep-alive, x-hop")
+                .insert_header("x-hop", "upstream-only")
+                .set_body_string("chunked body"),
+        )
+        .mount(&mock_server)
+        .await;
+
+    // When: Forwarding a request to it
+    let response = forward_request(
+        &build_http_client(&HttpClientConfig::default()),
+        Method::GET,
+        &mock_server.uri(),
+        "/api/export",
+        HeaderMap::new(),
+        Vec::new(),
+        &ForwardOptions::default(),
+    )
+    .await;
+
+    // Then: The gateway frames the response itself
+    assert_eq!(response.status(), 200);
+    for name in ["transfer-encoding", "connection", "x-hop"] {
+        assert!(
+            response.headers().get(name).is_none(),
+            "{} returned: {:?}",
+            name,
+            response.headers()
+        );
+    }
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("read body");
+    assert_eq!(&body[..], b"chunked body");
+}
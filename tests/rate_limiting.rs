@@ -0,0 +1,227 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use axum::response::Response;
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request_from, AppState};
+use schema_gateway::metrics::Metrics;
+use schema_gateway::rate_limiter::RateLimiter;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// Gateway state with a `/api/limited` route limited by `rate_limit` and an
+/// unlimited `/api/open` route
+async fn gateway(rate_limit: &str) -> TestResult<(Arc<RwLock<AppState>>, MockServer)> {
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::any())
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&mock_server)
+        .await;
+
+    let config = Config::from_str(&format!(
+        r#"
+routes:
+  - path: /api/limited
+    method: GET
+    upstream: {upstream}
+    config:
+      rate_limit:
+{rate_limit}
+  - path: /api/open
+    method: GET
+    upstream: {upstream}
+"#,
+        upstream = mock_server.uri(),
+        rate_limit = rate_limit
+    ))?;
+    config.validate()?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+    Ok((state, mock_server))
+}
+
+async fn get(
+    state: &Arc<RwLock<AppState>>,
+    uri: &str,
+    peer: &str,
+    api_key: Option<&str>,
+) -> TestResult<Response> {
+    let mut request = Request::builder().method(Method::GET).uri(uri);
+    if let Some(key) = api_key {
+        request = request.header("x-api-key", key);
+    }
+    let (parts, body) = request.body(Body::empty())?.into_parts();
+    Ok(handle_request_from(
+        State(state.clone()),
+        Some(peer.parse::<SocketAddr>()?),
+        parts.method,
+        parts.uri,
+        parts.headers,
+        body,
+    )
+    .await)
+}
+
+async fn statuses(
+    state: &Arc<RwLock<AppState>>,
+    count: usize,
+    peer: &str,
+    api_key: Option<&str>,
+) -> TestResult<Vec<u16>> {
+    let mut statuses = Vec::new();
+    for _ in 0..count {
+        let response = get(state, "/api/limited", peer, api_key).await?;
+        statuses.push(response.status().as_u16());
+    }
+    Ok(statuses)
+}
+
+#[tokio::test]
+async fn test_requests_over_the_burst_get_429() -> TestResult {
+    let (state, _upstream) =
+        gateway("        requests_per_second: 1\n        burst: 2\n        key_source: global")
+            .await?;
+
+    assert_eq!(
+        statuses(&state, 2, "10.0.0.1:4000", None).await?,
+        vec![200, 200]
+    );
+    // Global: a different client draws from the same bucket
+    let response = get(&state, "/api/limited", "10.0.0.2:4000", None).await?;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(
+        response
+            .headers()
+            .get("retry-after")
+            .and_then(|v| v.to_str().ok()),
+        Some("1")
+    );
+
+    // Other routes are unaffected
+    let response = get(&state, "/api/open", "10.0.0.1:4000", None).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let metrics = state.read().await.metrics.gather()?;
+    assert!(
+        metrics.contains(r#"rate_limit_rejections_total{route="/api/limited",route_tags=""} 1"#),
+        "{}",
+        metrics
+    );
+    assert!(
+        metrics.contains(
+            r#"http_requests_total{method="GET",route="/api/limited",route_tags="",status="429"} 1"#
+        ),
+        "{}",
+        metrics
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ip_and_api_key_buckets_are_separate() -> TestResult {
+    let (state, _upstream) = gateway("        requests_per_second: 1\n        burst: 1").await?;
+    // `ip` is the default key source
+    assert_eq!(
+        statuses(&state, 2, "10.0.0.1:4000", None).await?,
+        vec![200, 429]
+    );
+    // Another port on the same address is the same client
+    assert_eq!(statuses(&state, 1, "10.0.0.1:5000", None).await?, vec![429]);
+    assert_eq!(statuses(&state, 1, "10.0.0.2:4000", None).await?, vec![200]);
+
+    let (state, _upstream) = gateway(
+        "        requests_per_second: 1\n        burst: 1\n        key_source: api_key_header",
+    )
+    .await?;
+    assert_eq!(
+        statuses(&state, 2, "10.0.0.1:4000", Some("alice")).await?,
+        vec![200, 429]
+    );
+    // Same address, different key
+    assert_eq!(
+        statuses(&state, 1, "10.0.0.1:4000", Some("bob")).await?,
+        vec![200]
+    );
+    // No key: limited by address
+    assert_eq!(
+        statuses(&state, 2, "10.0.0.1:4000", None).await?,
+        vec![200, 429]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_loggable_key_hides_api_keys() {
+    let logged = RateLimiter::loggable_key("key:secret-api-key");
+    assert!(logged.starts_with("key:sha256:"), "{}", logged);
+    assert!(!logged.contains("secret"), "{}", logged);
+    assert_eq!(logged.len(), "key:sha256:".len() + 8);
+    // Stable, so one client's rejections can still be followed
+    assert_eq!(RateLimiter::loggable_key("key:secret-api-key"), logged);
+    assert_ne!(RateLimiter::loggable_key("key:other-api-key"), logged);
+
+    assert_eq!(RateLimiter::loggable_key("ip:10.0.0.1"), "ip:10.0.0.1");
+    assert_eq!(RateLimiter::loggable_key("global"), "global");
+}
+
+#[tokio::test]
+async fn test_buckets_refill_over_time() -> TestResult {
+    let (state, _upstream) =
+        gateway("        requests_per_second: 20\n        burst: 1\n        key_source: global")
+            .await?;
+
+    assert_eq!(
+        statuses(&state, 2, "10.0.0.1:4000", None).await?,
+        vec![200, 429]
+    );
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(statuses(&state, 1, "10.0.0.1:4000", None).await?, vec![200]);
+    Ok(())
+}
+
+#[test]
+fn test_rate_limit_config_validation() -> TestResult {
+    let cases = [
+        (
+            "requests_per_second: 0, burst: 1",
+            "requests_per_second must be greater than 0",
+        ),
+        ("requests_per_second: 1, burst: 0", "burst must be greater than 0"),
+        (
+            "requests_per_second: 1, burst: 1, key_source: api_key_header, api_key_header: 'bad header'",
+            "api_key_header is not a valid header name",
+        ),
+    ];
+    for (rate_limit, expected) in cases {
+        let config = Config::from_str(&format!(
+            "routes:\n  - path: /api\n    method: GET\n    upstream: http://backend:3000\n    config:\n      rate_limit: {{ {} }}\n",
+            rate_limit
+        ))?;
+        let error = config.validate().expect_err(rate_limit);
+        assert!(error.starts_with("Route 0: "), "{}", error);
+        assert!(error.contains(expected), "{}", error);
+
+        let config = Config::from_str(&format!(
+            "global:\n  rate_limit: {{ {} }}\nroutes:\n  - path: /api\n    method: GET\n    upstream: http://backend:3000\n",
+            rate_limit
+        ))?;
+        let error = config.validate().expect_err(rate_limit);
+        assert!(error.starts_with("global: "), "{}", error);
+    }
+
+    let unknown = Config::from_str(
+        "routes:\n  - path: /api\n    method: GET\n    upstream: http://backend:3000\n    config:\n      rate_limit: { requests_per_second: 1, burst: 1, key_source: user }\n",
+    );
+    assert!(unknown.is_err());
+    Ok(())
+}
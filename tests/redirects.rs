@@ -0,0 +1,142 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use axum::response::Response;
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// An upstream redirecting `/old` to `/hop`, `/hop` to `/new`, and
+/// answering `/new` itself
+async fn upstream() -> MockServer {
+    let mock_server = MockServer::start().await;
+    for (from, to) in [("/old", "/hop?from=old"), ("/hop", "/new")] {
+        Mock::given(matchers::path(from))
+            .respond_with(ResponseTemplate::new(302).insert_header("location", to))
+            .mount(&mock_server)
+            .await;
+    }
+    Mock::given(matchers::path("/new"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("moved here"))
+        .mount(&mock_server)
+        .await;
+    mock_server
+}
+
+/// `GET path` through a route for it on `upstream`, with `route` settings
+/// added under the route's `config`
+async fn send(upstream: &MockServer, path: &str, route: &str) -> TestResult<Response> {
+    let config = Config::from_str(&format!(
+        r#"
+routes:
+  - path: {}
+    method: GET
+    upstream: {}
+    config:
+      forward_on_error: true
+{}
+"#,
+        path,
+        upstream.uri(),
+        route
+    ))?;
+    config.validate()?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(path)
+        .body(Body::empty())?;
+    let (parts, body) = request.into_parts();
+    Ok(handle_request(State(state), parts.method, parts.uri, parts.headers, body).await)
+}
+
+async fn body_text(response: Response) -> TestResult<String> {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    Ok(String::from_utf8(body.to_vec())?)
+}
+
+#[tokio::test]
+async fn test_redirects_are_passed_to_the_client() -> TestResult {
+    let upstream = upstream().await;
+    let response = send(&upstream, "/old", "").await?;
+    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(
+        response
+            .headers()
+            .get("location")
+            .and_then(|value| value.to_str().ok()),
+        Some("/hop?from=old")
+    );
+
+    // The upstream was asked once, for the original path only
+    let requests = upstream
+        .received_requests()
+        .await
+        .ok_or("request recording is off")?;
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].url.path(), "/old");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_routes_can_follow_redirects() -> TestResult {
+    let upstream = upstream().await;
+    let response = send(&upstream, "/old", "      follow_redirects: true").await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get("location").is_none());
+    assert_eq!(body_text(response).await?, "moved here");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_max_redirects_limits_the_hops() -> TestResult {
+    let upstream = upstream().await;
+    let route = "      follow_redirects: true\n      max_redirects: 2";
+    let response = send(&upstream, "/old", route).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let route = "      follow_redirects: true\n      max_redirects: 1";
+    let response = send(&upstream, "/old", route).await?;
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    assert_eq!(
+        upstream
+            .received_requests()
+            .await
+            .ok_or("request recording is off")?
+            .len(),
+        5
+    );
+    Ok(())
+}
+
+#[test]
+fn test_max_redirects_must_be_positive() -> TestResult {
+    let config = Config::from_str(
+        "global:\n  max_redirects: 0\nroutes:\n  - path: /api\n    method: GET\n    upstream: http://backend:3000\n",
+    )?;
+    let error = config.validate().expect_err("zero hops");
+    assert_eq!(error, "global.max_redirects must be greater than 0");
+
+    let config = Config::from_str(
+        "routes:\n  - path: /api\n    method: GET\n    upstream: http://backend:3000\n    config:\n      max_redirects: 0\n",
+    )?;
+    let error = config.validate().expect_err("zero route hops");
+    assert!(
+        error.contains("max_redirects must be greater than 0"),
+        "{}",
+        error
+    );
+    Ok(())
+}
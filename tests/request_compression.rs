@@ -0,0 +1,307 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{HeaderMap, HeaderValue, Method, StatusCode};
+use schema_gateway::config::Config;
+use schema_gateway::content_encoding::{ContentEncoding, DecodeError};
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// `{"name": "Ada"}`, gzipped
+const GZIP_NAME: &str = "1f8b0800000000000203ab56ca4bcc4d55b25250724c4954aa050068a20ae40f000000";
+/// `{"name": "Ada"}` as a raw DEFLATE stored block
+const RAW_STORED_NAME: &str = "010f00f0ff7b226e616d65223a2022416461227d";
+/// `{"name": "Ada"}`, brotli-compressed
+const BROTLI_NAME: &str = "0b07807b226e616d65223a2022416461227d03";
+/// `items()` as zlib, compressed with dynamic Huffman codes
+const ZLIB_ITEMS: &str = "78da85d43b6a03411444d1ad0c1dcba057a5afb762148cb1300ae4c4ca84f6eeb170f40a73a3fedee8d07d1f97dbf9fa3d5ea7b7fbb87c2ce37a358daff97a5ea6cfb397f558766ef3e7f3d2987f57efe3f4584d7f41f5a020500f04817b6008363dd840b0edc116825d0f7610ec7bb087e0d0830304c71e1c092ea80bad139bb42bb88bbc2bc08bc42bc88bcc2bd08bd42bd88bdc2be08be42be88bec2bf08bf415fa227d85bef0ade763277d85be485fa12fd257e88bf415fa227d85be485fa12fd257e88bf41dfa267d87be49dfa16ffcebf3b3277d87be49dfa16fd277e89bf41dfa267d87be49dfa1ef7ff44f8f1f38c70ab3";
+
+fn items() -> Value {
+    let items: Vec<Value> = (0..40)
+        .map(|i| json!({"id": i, "name": format!("item-{}", i), "tags": ["a", "b"]}))
+        .collect();
+    json!({ "items": items })
+}
+
+fn encoded(name: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "content-encoding",
+        HeaderValue::from_str(name).expect("value"),
+    );
+    headers
+}
+
+#[test]
+fn test_gzip_deflate_and_brotli_bodies_are_decoded() -> TestResult {
+    let decoded = ContentEncoding::Gzip.decode(&hex::decode(GZIP_NAME)?, None)?;
+    assert_eq!(decoded, br#"{"name": "Ada"}"#);
+
+    let decoded = ContentEncoding::Deflate.decode(&hex::decode(ZLIB_ITEMS)?, None)?;
+    assert_eq!(serde_json::from_slice::<Value>(&decoded)?, items());
+
+    // Raw DEFLATE without the zlib wrapper is accepted as `deflate` too
+    let decoded = ContentEncoding::Deflate.decode(&hex::decode(RAW_STORED_NAME)?, None)?;
+    assert_eq!(decoded, br#"{"name": "Ada"}"#);
+
+    let decoded = ContentEncoding::Brotli.decode(&hex::decode(BROTLI_NAME)?, None)?;
+    assert_eq!(decoded, br#"{"name": "Ada"}"#);
+    Ok(())
+}
+
+#[test]
+fn test_decoding_stops_at_the_limit() -> TestResult {
+    let compressed = hex::decode(ZLIB_ITEMS)?;
+    assert_eq!(
+        ContentEncoding::Deflate.decode(&compressed, Some(1000)),
+        Err(DecodeError::TooLarge)
+    );
+
+    let mut corrupt = hex::decode(GZIP_NAME)?;
+    let crc = corrupt.len() - 8;
+    corrupt[crc] ^= 0xff;
+    assert!(matches!(
+        ContentEncoding::Gzip.decode(&corrupt, None),
+        Err(DecodeError::Invalid(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_content_encoding_header_is_parsed() {
+    assert_eq!(
+        ContentEncoding::from_headers(&HeaderMap::new()),
+        Ok(ContentEncoding::Identity)
+    );
+    assert_eq!(
+        ContentEncoding::from_headers(&encoded("GZIP")),
+        Ok(ContentEncoding::Gzip)
+    );
+    assert_eq!(
+        ContentEncoding::from_headers(&encoded("identity, deflate")),
+        Ok(ContentEncoding::Deflate)
+    );
+    assert_eq!(
+        ContentEncoding::from_headers(&encoded("br")),
+        Ok(ContentEncoding::Brotli)
+    );
+    assert_eq!(
+        ContentEncoding::from_headers(&encoded("zstd")),
+        Err("zstd".to_string())
+    );
+    assert_eq!(
+        ContentEncoding::from_headers(&encoded("gzip, gzip")),
+        Err("gzip, gzip".to_string())
+    );
+}
+
+fn write_schema() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("user.json");
+    fs::write(
+        &path,
+        r#"{"type": "object", "required": ["name"], "properties": {"name": {"type": "string"}}}"#,
+    )
+    .expect("write schema");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// Reject-mode gateway validating `POST /api/users`, with `global` added to
+/// its global settings
+async fn gateway(global: &str) -> TestResult<(Arc<RwLock<AppState>>, MockServer)> {
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::any())
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&mock_server)
+        .await;
+
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: false
+{}
+
+routes:
+  - path: /api/users
+    method: POST
+    schema: {}
+    upstream: {}
+"#,
+        global,
+        write_schema().display(),
+        mock_server.uri()
+    ))?;
+    config.validate()?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+    Ok((state, mock_server))
+}
+
+async fn post(
+    state: &Arc<RwLock<AppState>>,
+    encoding: &str,
+    body: Vec<u8>,
+) -> TestResult<(StatusCode, Value)> {
+    let mut headers = encoded(encoding);
+    headers.insert("content-type", HeaderValue::from_static("application/json"));
+    headers.insert("content-length", HeaderValue::from(body.len()));
+    let response = handle_request(
+        State(state.clone()),
+        Method::POST,
+        "/api/users".parse()?,
+        headers,
+        Body::from(body),
+    )
+    .await;
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    Ok((status, serde_json::from_slice(&body).unwrap_or(Value::Null)))
+}
+
+#[tokio::test]
+async fn test_gzip_body_is_validated_and_forwarded_compressed() -> TestResult {
+    let (state, upstream) = gateway("").await?;
+    let compressed = hex::decode(GZIP_NAME)?;
+
+    let (status, _) = post(&state, "gzip", compressed.clone()).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let requests = upstream.received_requests().await.unwrap_or_default();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].body, compressed);
+    assert_eq!(
+        requests[0]
+            .headers
+            .get("content-encoding")
+            .and_then(|v| v.to_str().ok()),
+        Some("gzip")
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_brotli_body_is_validated() -> TestResult {
+    let (state, upstream) = gateway("").await?;
+    let compressed = hex::decode(BROTLI_NAME)?;
+
+    let (status, _) = post(&state, "br", compressed.clone()).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let requests = upstream.received_requests().await.unwrap_or_default();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].body, compressed);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_decoded_body_is_forwarded_when_configured() -> TestResult {
+    let (state, upstream) = gateway("  forward_decompressed_body: true").await?;
+
+    let (status, _) = post(&state, "deflate", hex::decode(RAW_STORED_NAME)?).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let requests = upstream.received_requests().await.unwrap_or_default();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].body, br#"{"name": "Ada"}"#);
+    assert!(requests[0].headers.get("content-encoding").is_none());
+    assert_eq!(
+        requests[0]
+            .headers
+            .get("content-length")
+            .and_then(|v| v.to_str().ok()),
+        Some("15")
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_compressed_bodies_that_fail_validation_are_rejected() -> TestResult {
+    let (state, upstream) = gateway("").await?;
+
+    // Valid deflate, but the decoded body is missing `name`
+    let (status, body) = post(&state, "deflate", hex::decode(ZLIB_ITEMS)?).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let error = body["error"].as_str().ok_or("error is not a string")?;
+    assert!(
+        error.contains("\"name\" is a required property"),
+        "{}",
+        error
+    );
+
+    // Not gzip at all
+    let (status, body) = post(&state, "gzip", br#"{"name": "Ada"}"#.to_vec()).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let error = body["error"].as_str().ok_or("error is not a string")?;
+    assert!(
+        error.starts_with("Failed to decompress request body"),
+        "{}",
+        error
+    );
+
+    assert!(upstream
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unsupported_encodings_get_415() -> TestResult {
+    let (state, _upstream) = gateway("").await?;
+
+    let (status, body) = post(&state, "zstd", vec![0x28, 0xb5, 0x2f, 0xfd]).await?;
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(body["error"], "Unsupported Content-Encoding 'zstd'");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_decoded_size_counts_against_the_body_limit() -> TestResult {
+    let (state, _upstream) = gateway("  max_request_body_bytes: 1000").await?;
+
+    let (status, body) = post(&state, "deflate", hex::decode(ZLIB_ITEMS)?).await?;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(
+        body["error"],
+        "Decompressed request body exceeds max_request_body_bytes"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_decoded_size_is_capped_without_a_body_limit() -> TestResult {
+    let (state, upstream) = gateway("").await?;
+
+    // Over the default cap once decoded, but small on the wire
+    let zeros = vec![b' '; schema_gateway::content_encoding::DEFAULT_DECODED_LIMIT + 1];
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+    std::io::Write::write_all(&mut encoder, &zeros)?;
+    let bomb = encoder.finish()?;
+
+    let (status, body) = post(&state, "gzip", bomb).await?;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(
+        body["error"],
+        "Decompressed request body exceeds the default decoded size limit"
+    );
+    assert!(upstream
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .is_empty());
+    Ok(())
+}
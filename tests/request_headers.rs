@@ -0,0 +1,215 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn write_schema() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("order.json");
+    fs::write(&path, r#"{"type": "object", "required": ["sku"]}"#).expect("write schema");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+async fn upstream() -> MockServer {
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::any())
+        .respond_with(ResponseTemplate::new(201))
+        .mount(&mock_server)
+        .await;
+    mock_server
+}
+
+/// Gateway state for a validated, permissive `POST /api/orders` route on
+/// `upstream` with `route` settings added under its `config`
+async fn gateway(upstream: &MockServer, route: &str) -> TestResult<Arc<RwLock<AppState>>> {
+    let config = Config::from_str(&format!(
+        r#"
+routes:
+  - path: /api/orders
+    method: POST
+    schema: {}
+    upstream: {}
+    config:
+      forward_on_error: true
+{}
+"#,
+        write_schema().display(),
+        upstream.uri(),
+        route
+    ))?;
+    config.validate()?;
+    let http_client = build_http_client(&config.global.http_client);
+    Ok(Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    ))))
+}
+
+/// `POST /api/orders` with `body` and the client's credentials
+async fn post(state: &Arc<RwLock<AppState>>, body: &str) -> TestResult<StatusCode> {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/orders")
+        .header("content-type", "application/json")
+        .header("authorization", "Bearer client-token")
+        .header("x-internal-user", "alice")
+        .header("x-client", "tests")
+        .body(Body::from(body.to_string()))?;
+    let (parts, body) = request.into_parts();
+    Ok(handle_request(
+        State(Arc::clone(state)),
+        parts.method,
+        parts.uri,
+        parts.headers,
+        body,
+    )
+    .await
+    .status())
+}
+
+/// `post` through a fresh gateway with `route` settings
+async fn send(upstream: &MockServer, route: &str, body: &str) -> TestResult<StatusCode> {
+    post(&gateway(upstream, route).await?, body).await
+}
+
+/// The `name` header of the one request the upstream received
+async fn received(upstream: &MockServer, name: &str) -> TestResult<Option<String>> {
+    let requests = upstream
+        .received_requests()
+        .await
+        .ok_or("request recording is off")?;
+    assert_eq!(requests.len(), 1);
+    Ok(requests[0]
+        .headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string))
+}
+
+#[tokio::test]
+async fn test_listed_headers_are_removed() -> TestResult {
+    let upstream = upstream().await;
+    let route = "      remove_request_headers: [Authorization, x-internal-user]";
+    let status = send(&upstream, route, r#"{"sku": "A1"}"#).await?;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(received(&upstream, "authorization").await?, None);
+    assert_eq!(received(&upstream, "x-internal-user").await?, None);
+    assert_eq!(
+        received(&upstream, "x-client").await?.as_deref(),
+        Some("tests")
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upstream_auth_replaces_the_client_credential() -> TestResult {
+    std::env::set_var("REQUEST_HEADERS_TEST_TOKEN", "partner-secret");
+    let upstream = upstream().await;
+    let route = "      upstream_auth:\n        bearer: ${REQUEST_HEADERS_TEST_TOKEN}";
+    send(&upstream, route, r#"{"sku": "A1"}"#).await?;
+    assert_eq!(
+        received(&upstream, "authorization").await?.as_deref(),
+        Some("Bearer partner-secret")
+    );
+    // Headers that aren't listed still go through
+    assert_eq!(
+        received(&upstream, "x-internal-user").await?.as_deref(),
+        Some("alice")
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upstream_auth_is_resolved_when_the_config_loads() -> TestResult {
+    std::env::set_var("REQUEST_HEADERS_TEST_LOAD_TOKEN", "loaded-secret");
+    let upstream = upstream().await;
+    let route = "      upstream_auth:\n        bearer: ${REQUEST_HEADERS_TEST_LOAD_TOKEN}";
+    let state = gateway(&upstream, route).await?;
+
+    // Requests use the value read at load, not the environment's current one
+    std::env::remove_var("REQUEST_HEADERS_TEST_LOAD_TOKEN");
+    post(&state, r#"{"sku": "A1"}"#).await?;
+    assert_eq!(
+        received(&upstream, "authorization").await?.as_deref(),
+        Some("Bearer loaded-secret")
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_basic_upstream_auth() -> TestResult {
+    std::env::set_var("REQUEST_HEADERS_TEST_PASSWORD", "open sesame");
+    let upstream = upstream().await;
+    let route = "      upstream_auth:\n        basic:\n          username: gateway\n          password: ${REQUEST_HEADERS_TEST_PASSWORD}";
+    send(&upstream, route, r#"{"sku": "A1"}"#).await?;
+    // base64("gateway:open sesame")
+    assert_eq!(
+        received(&upstream, "authorization").await?.as_deref(),
+        Some("Basic Z2F0ZXdheTpvcGVuIHNlc2FtZQ==")
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rules_apply_when_forwarding_invalid_requests() -> TestResult {
+    let upstream = upstream().await;
+    let route = "      remove_request_headers: [x-internal-user]\n      upstream_auth:\n        bearer: gateway-token";
+    let status = send(&upstream, route, r#"{"wrong": 1}"#).await?;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(received(&upstream, "x-internal-user").await?, None);
+    assert_eq!(
+        received(&upstream, "authorization").await?.as_deref(),
+        Some("Bearer gateway-token")
+    );
+    Ok(())
+}
+
+#[test]
+fn test_upstream_auth_validation() -> TestResult {
+    let parse = |auth: &str| -> TestResult<Config> {
+        Ok(Config::from_str(&format!(
+            "routes:\n  - path: /api\n    method: GET\n    upstream: http://backend:3000\n    config:\n      upstream_auth:\n{}\n",
+            auth
+        ))?)
+    };
+
+    let error = parse("        bearer: token\n        basic: {username: a, password: b}")?
+        .validate()
+        .expect_err("both kinds");
+    assert!(
+        error.contains("upstream_auth can't set both basic and bearer"),
+        "{}",
+        error
+    );
+
+    let error = parse("        bearer: ${REQUEST_HEADERS_TEST_UNSET}")?
+        .validate()
+        .expect_err("unset variable");
+    assert!(
+        error.contains("environment variable 'REQUEST_HEADERS_TEST_UNSET' is not set"),
+        "{}",
+        error
+    );
+
+    let error = parse("        {}")?.validate().expect_err("no credential");
+    assert!(error.contains("one of basic or bearer"), "{}", error);
+
+    let config = Config::from_str(
+        "global:\n  remove_request_headers: [\"bad header\"]\nroutes:\n  - path: /api\n    method: GET\n    upstream: http://backend:3000\n",
+    )?;
+    let error = config.validate().expect_err("invalid header name");
+    assert!(error.contains("Invalid header name"), "{}", error);
+    Ok(())
+}
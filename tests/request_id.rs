@@ -0,0 +1,204 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use axum::response::Response;
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn write_schema() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("order.json");
+    fs::write(&path, r#"{"type": "object", "required": ["sku"]}"#).expect("write schema");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// A rejecting, validated `POST /api/orders` on `upstream`, with `global`
+/// settings added
+fn gateway(upstream: &MockServer, global: &str) -> TestResult<Arc<RwLock<AppState>>> {
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: false
+{}
+
+routes:
+  - path: /api/orders
+    method: POST
+    schema: {}
+    upstream: {}
+"#,
+        global,
+        write_schema().display(),
+        upstream.uri()
+    ))?;
+    config.validate()?;
+    let http_client = build_http_client(&config.global.http_client);
+    Ok(Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    ))))
+}
+
+async fn upstream() -> MockServer {
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::any())
+        .respond_with(ResponseTemplate::new(201))
+        .mount(&mock_server)
+        .await;
+    mock_server
+}
+
+async fn send(
+    state: &Arc<RwLock<AppState>>,
+    body: &str,
+    headers: &[(&str, &str)],
+) -> TestResult<Response> {
+    let mut request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/orders")
+        .header("content-type", "application/json");
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let (parts, body) = request.body(Body::from(body.to_string()))?.into_parts();
+    Ok(handle_request(
+        State(state.clone()),
+        parts.method,
+        parts.uri,
+        parts.headers,
+        body,
+    )
+    .await)
+}
+
+fn header(response: &Response, name: &str) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// The `name` header of each request the upstream received
+async fn upstream_ids(upstream: &MockServer, name: &str) -> TestResult<Vec<Option<String>>> {
+    Ok(upstream
+        .received_requests()
+        .await
+        .ok_or("request recording is off")?
+        .iter()
+        .map(|request| {
+            request
+                .headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        })
+        .collect())
+}
+
+#[tokio::test]
+async fn test_generated_id_reaches_upstream_and_client() -> TestResult {
+    let upstream = upstream().await;
+    let state = gateway(&upstream, "")?;
+
+    let response = send(&state, r#"{"sku": "A1"}"#, &[]).await?;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let id = header(&response, "x-request-id").ok_or("no request ID")?;
+    assert!(uuid::Uuid::parse_str(&id).is_ok(), "{}", id);
+    assert_eq!(
+        upstream_ids(&upstream, "x-request-id").await?,
+        vec![Some(id)]
+    );
+
+    // Every request gets its own
+    let response = send(&state, r#"{"sku": "A1"}"#, &[]).await?;
+    let second = header(&response, "x-request-id").ok_or("no request ID")?;
+    assert_eq!(upstream_ids(&upstream, "x-request-id").await?.len(), 2);
+    assert_ne!(
+        upstream_ids(&upstream, "x-request-id").await?[0],
+        Some(second)
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_client_id_is_reused() -> TestResult {
+    let upstream = upstream().await;
+    let state = gateway(&upstream, "")?;
+
+    let response = send(&state, r#"{"sku": "A1"}"#, &[("x-request-id", "client-7")]).await?;
+    assert_eq!(
+        header(&response, "x-request-id").as_deref(),
+        Some("client-7")
+    );
+    assert_eq!(
+        upstream_ids(&upstream, "x-request-id").await?,
+        vec![Some("client-7".to_string())]
+    );
+
+    // An ID too long to log safely is replaced
+    let long = "x".repeat(129);
+    let response = send(&state, r#"{"sku": "A1"}"#, &[("x-request-id", &long)]).await?;
+    let id = header(&response, "x-request-id").ok_or("no request ID")?;
+    assert!(uuid::Uuid::parse_str(&id).is_ok(), "{}", id);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_gateway_errors_carry_the_id() -> TestResult {
+    let upstream = upstream().await;
+    let state = gateway(&upstream, "")?;
+
+    let response = send(&state, "{}", &[("x-request-id", "client-8")]).await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        header(&response, "x-request-id").as_deref(),
+        Some("client-8")
+    );
+    assert!(upstream_ids(&upstream, "x-request-id").await?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_header_name_is_configurable() -> TestResult {
+    let upstream = upstream().await;
+    let state = gateway(&upstream, "  request_id_header: X-Correlation-Id")?;
+
+    let response = send(
+        &state,
+        r#"{"sku": "A1"}"#,
+        &[("x-correlation-id", "corr-1")],
+    )
+    .await?;
+    assert_eq!(
+        header(&response, "x-correlation-id").as_deref(),
+        Some("corr-1")
+    );
+    assert_eq!(header(&response, "x-request-id"), None);
+    assert_eq!(
+        upstream_ids(&upstream, "x-correlation-id").await?,
+        vec![Some("corr-1".to_string())]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_request_id_header_must_be_a_header_name() -> TestResult {
+    let config = Config::from_str(
+        "global:\n  request_id_header: \"bad header\"\nroutes:\n  - path: /api\n    method: GET\n    upstream: http://backend:3000\n",
+    )?;
+    let error = config.validate().expect_err("invalid header name");
+    assert!(error.contains("request_id_header"), "{}", error);
+    Ok(())
+}
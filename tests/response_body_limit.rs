@@ -0,0 +1,255 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use axum::response::Response;
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const SPEC: &str = r#"
+openapi: 3.0.3
+info: { title: Demo, version: "1.0.0" }
+paths:
+  /api/export:
+    get:
+      responses:
+        '200':
+          description: ok
+          content:
+            application/json:
+              schema: { type: object, required: [id] }
+"#;
+
+fn write_spec() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("openapi.yaml");
+    fs::write(&path, SPEC).expect("write spec");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// A JSON body of about `len` bytes that the spec's schema rejects
+fn invalid_body(len: usize) -> String {
+    format!(r#"{{"rows": "{}"}}"#, "x".repeat(len))
+}
+
+/// An upstream answering every request with `body` as
+/// `Transfer-Encoding: chunked`, 100 bytes per chunk
+async fn chunked_upstream(body: String) -> TestResult<String> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("http://{}", listener.local_addr()?);
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let body = body.clone();
+            tokio::spawn(async move {
+                let mut head = Vec::new();
+                while !head.ends_with(b"\r\n\r\n") {
+                    let mut byte = [0u8; 1];
+                    if stream.read(&mut byte).await.unwrap_or(0) == 0 {
+                        return;
+                    }
+                    head.push(byte[0]);
+                }
+                let mut response = String::from(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n",
+                );
+                for chunk in body.as_bytes().chunks(100) {
+                    response.push_str(&format!("{:x}\r\n", chunk.len()));
+                    response.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+                    response.push_str("\r\n");
+                }
+                response.push_str("0\r\n\r\n");
+                let _ = stream.write_all(response.as_bytes()).await;
+            });
+        }
+    });
+    Ok(url)
+}
+
+/// `GET /api/export` through a rejecting OpenAPI route on `upstream`, with
+/// `global` settings added
+async fn send(upstream: &str, global: &str) -> TestResult<Response> {
+    let route = format!("    openapi: {}", write_spec().display());
+    send_to_route(upstream, global, &route).await
+}
+
+/// `GET /api/export` through a route on `upstream` with nothing to
+/// validate
+async fn send_unvalidated(upstream: &str, global: &str) -> TestResult<Response> {
+    send_to_route(upstream, global, "").await
+}
+
+async fn send_to_route(upstream: &str, global: &str, route: &str) -> TestResult<Response> {
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: false
+{}
+
+routes:
+  - path: /api/export
+    method: GET
+    upstream: {}
+{}
+"#,
+        global, upstream, route
+    ))?;
+    config.validate()?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri("/api/export")
+        .body(Body::empty())?;
+    let (parts, body) = request.into_parts();
+    Ok(handle_request(State(state), parts.method, parts.uri, parts.headers, body).await)
+}
+
+async fn body_text(response: Response) -> TestResult<String> {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    Ok(String::from_utf8(body.to_vec())?)
+}
+
+#[tokio::test]
+async fn test_chunked_bodies_over_the_limit_stream_through_unvalidated() -> TestResult {
+    let body = invalid_body(5000);
+    let upstream = chunked_upstream(body.clone()).await?;
+
+    // Within the limit the body is validated and rejected
+    let response = send(&upstream, "  max_response_body_bytes: 10000").await?;
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+
+    // Over it, the chunks already read and the rest reach the client intact
+    let response = send(&upstream, "  max_response_body_bytes: 1000").await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_text(response).await?, body);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_content_length_over_the_limit_skips_validation() -> TestResult {
+    let body = invalid_body(5000);
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::method("GET"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw(body.clone().into_bytes(), "application/json"),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let response = send(&mock_server.uri(), "").await?;
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+
+    let response = send(&mock_server.uri(), "  max_response_body_bytes: 1000").await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_text(response).await?, body);
+    Ok(())
+}
+
+async fn error_message(response: Response) -> TestResult<String> {
+    let body: serde_json::Value = serde_json::from_str(&body_text(response).await?)?;
+    Ok(body["error"].as_str().unwrap_or_default().to_string())
+}
+
+const CAPPED: &str = "  max_response_bytes: 100000";
+const TOO_LARGE: &str = "Upstream response body is larger than max_response_bytes (100000 bytes)";
+
+#[tokio::test]
+async fn test_large_response_is_refused_by_its_content_length() -> TestResult {
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::method("GET"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw(vec![b'x'; 5 * 1024 * 1024], "application/octet-stream"),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let response = send_unvalidated(&mock_server.uri(), CAPPED).await?;
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    assert_eq!(error_message(response).await?, TOO_LARGE);
+
+    // Without a cap the whole body is passed on
+    let response = send_unvalidated(&mock_server.uri(), "").await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_text(response).await?.len(), 5 * 1024 * 1024);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_chunked_response_over_the_cap_fails_validation_read() -> TestResult {
+    let upstream = chunked_upstream(invalid_body(200_000)).await?;
+
+    // The cap wins over streaming past validation
+    let global = format!("{}\n  max_response_body_bytes: 1000", CAPPED);
+    let response = send(&upstream, &global).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(body_text(response).await.is_err());
+
+    let response = send(&upstream, CAPPED).await?;
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    assert_eq!(error_message(response).await?, TOO_LARGE);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_streamed_response_is_cut_off_at_the_cap() -> TestResult {
+    let upstream = chunked_upstream(invalid_body(200_000)).await?;
+    let response = send_unvalidated(&upstream, CAPPED).await?;
+    // Headers were already on their way, so the body is aborted instead
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(body_text(response).await.is_err());
+
+    let upstream = chunked_upstream(invalid_body(50_000)).await?;
+    let response = send_unvalidated(&upstream, CAPPED).await?;
+    assert_eq!(body_text(response).await?, invalid_body(50_000));
+    Ok(())
+}
+
+#[test]
+fn test_max_response_bytes_must_be_positive() -> TestResult {
+    let config = Config::from_str(
+        "global:\n  max_response_bytes: 0\nroutes:\n  - path: /api\n    method: GET\n    upstream: http://backend:3000\n",
+    )?;
+    let error = config.validate().expect_err("zero cap");
+    assert_eq!(error, "global.max_response_bytes must be greater than 0");
+    Ok(())
+}
+
+#[test]
+fn test_max_response_body_bytes_must_be_positive() -> TestResult {
+    let config = Config::from_str(
+        "global:\n  max_response_body_bytes: 0\nroutes:\n  - path: /api\n    method: GET\n    upstream: http://backend:3000\n",
+    )?;
+    let error = config.validate().expect_err("zero limit");
+    assert_eq!(
+        error,
+        "global.max_response_body_bytes must be greater than 0"
+    );
+
+    let config = Config::from_str(
+        "routes:\n  - path: /api\n    method: GET\n    upstream: http://backend:3000\n    config:\n      max_response_body_bytes: 0\n",
+    )?;
+    let error = config.validate().expect_err("zero route limit");
+    assert!(
+        error.contains("max_response_body_bytes must be greater than 0"),
+        "{}",
+        error
+    );
+    Ok(())
+}
@@ -0,0 +1,132 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use schema_gateway::admin::routes_report;
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::RwLock;
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn write_schema() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("note.json");
+    fs::write(&path, r#"{"type": "object", "required": ["text"]}"#).expect("write schema");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// Log output collected in memory
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().expect("log buffer").extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Captured {
+    fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().expect("log buffer")).into_owned()
+    }
+}
+
+/// A described `POST /api/notes` and an undescribed `POST /api/drafts`,
+/// both rejecting invalid bodies
+fn gateway() -> TestResult<Arc<RwLock<AppState>>> {
+    let schema = write_schema();
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: false
+
+routes:
+  - path: /api/notes
+    method: POST
+    schema: {schema}
+    upstream: http://127.0.0.1:9
+    description: Note intake for the mobile app
+  - path: /api/drafts
+    method: post
+    schema: {schema}
+    upstream: http://127.0.0.1:9
+"#,
+        schema = schema.display()
+    ))?;
+    config.validate()?;
+    let http_client = build_http_client(&config.global.http_client);
+    Ok(Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    ))))
+}
+
+#[tokio::test]
+async fn test_warnings_carry_the_route_description() -> TestResult {
+    let state = gateway()?;
+    let captured = Captured::default();
+    let writer = captured.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    for path in ["/api/notes", "/api/drafts"] {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(path)
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"wrong": 1}"#))?;
+        let (parts, body) = request.into_parts();
+        let response = handle_request(
+            State(state.clone()),
+            parts.method,
+            parts.uri,
+            parts.headers,
+            body,
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    // Both the validation failure and the rejection are logged per request
+    let logs = captured.contents();
+    let warnings: Vec<&str> = logs.lines().filter(|line| line.contains("WARN")).collect();
+    assert_eq!(warnings.len(), 4, "{}", logs);
+    for line in warnings {
+        let expected = if line.contains("path=/api/notes") {
+            "route_description=Note intake for the mobile app"
+        } else {
+            "route_description=POST /api/drafts"
+        };
+        assert!(line.contains(expected), "{}", line);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_admin_routes_list_descriptions() -> TestResult {
+    let state = gateway()?;
+    let report = routes_report(&state).await;
+    assert_eq!(
+        report["routes"][0]["description"],
+        "Note intake for the mobile app"
+    );
+    assert_eq!(report["routes"][1]["description"], "POST /api/drafts");
+    Ok(())
+}
@@ -1,4 +1,12 @@
-use schema_gateway::config::{Config, Route};
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use schema_gateway::config::{Config, Route, RouteIndex, RouteRegexCache};
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
 
 fn create_route(path: &str, method: &str) -> Route {
     serde_yaml::from_str(&format!(
@@ -116,7 +124,7 @@ routes:
     upstream: http://backend:3000
 "#;
 
-    let config: Config = serde_yaml::from_str(yaml).expect("parse config");
+    let config = Config::from_str(yaml).expect("parse config");
 
     // No matching path
     assert!(
@@ -179,3 +187,549 @@ fn test_multiple_params() {
         "expected different static segment to not match"
     );
 }
+
+fn create_regex_route(pattern: &str, method: &str) -> Route {
+    serde_yaml::from_str(&format!(
+        r#"
+path_regex: '{}'
+method: {}
+upstream: http://backend:3000
+"#,
+        pattern, method
+    ))
+    .expect("create regex route")
+}
+
+#[test]
+fn test_match_anchored_regex() {
+    let route = create_regex_route(r"^/api/v\d+/users$", "GET");
+
+    assert!(
+        route.matches("/api/v1/users", "GET"),
+        "expected versioned path to match"
+    );
+    assert!(
+        route.matches("/api/v42/users", "GET"),
+        "expected multi-digit version to match"
+    );
+    assert!(
+        !route.matches("/api/vx/users", "GET"),
+        "expected non-numeric version to not match"
+    );
+    assert!(
+        !route.matches("/prefix/api/v1/users", "GET"),
+        "expected anchored pattern to reject prefixed path"
+    );
+    assert!(
+        !route.matches("/api/v1/users/extra", "GET"),
+        "expected anchored pattern to reject suffixed path"
+    );
+    assert!(
+        !route.matches("/api/v1/users", "POST"),
+        "expected different method to not match"
+    );
+}
+
+#[test]
+fn test_regex_named_captures() {
+    let route = create_regex_route(r"^/api/users/(?P<id>\d+)/posts/(?P<slug>[a-z-]+)$", "GET");
+
+    let params = route.path_captures("/api/users/42/posts/hello-world", None);
+    assert_eq!(params.get("id").map(String::as_str), Some("42"));
+    assert_eq!(params.get("slug").map(String::as_str), Some("hello-world"));
+
+    // Plain path routes have no captures
+    let plain = create_route("/api/users/:id", "GET");
+    assert!(plain.path_captures("/api/users/42", None).is_empty());
+}
+
+#[test]
+fn test_find_route_cached_uses_compiled_regex() {
+    let yaml = r#"
+routes:
+  - path: /api/users
+    method: GET
+    upstream: http://backend:3000
+
+  - path_regex: '^/api/items/(?P<id>\d+)$'
+    method: GET
+    upstream: http://backend:3000
+"#;
+
+    let config = Config::from_str(yaml).expect("parse config");
+    config.validate().expect("config is valid");
+    let regexes = RouteRegexCache::from_config(&config);
+
+    let route = config
+        .find_route_cached("/api/items/7", "GET", &regexes)
+        .expect("regex route matches");
+    assert_eq!(route.pattern(), r"^/api/items/(?P<id>\d+)$");
+    assert!(regexes.get_for(route).is_some());
+
+    assert!(config
+        .find_route_cached("/api/items/abc", "GET", &regexes)
+        .is_none());
+    assert!(config
+        .find_route_cached("/api/users", "GET", &regexes)
+        .is_some());
+}
+
+#[test]
+fn test_route_index_selects_by_method() {
+    let yaml = r#"
+routes:
+  - path: /api/users
+    method: GET
+    upstream: http://list:3000
+
+  - path: /api/users
+    method: POST
+    upstream: http://create:3000
+
+  - path: /api/users/:id
+    method: GET
+    upstream: http://show:3000
+"#;
+
+    let config = Config::from_str(yaml).expect("parse config");
+    config.validate().expect("config is valid");
+    let regexes = RouteRegexCache::from_config(&config);
+    let index = RouteIndex::from_config(&config);
+
+    let find = |path: &str, method: &str| {
+        index
+            .find(&config, path, method, &regexes)
+            .map(|route| route.upstream.as_str())
+    };
+    assert_eq!(find("/api/users", "GET"), Some("http://list:3000"));
+    assert_eq!(find("/api/users", "post"), Some("http://create:3000"));
+    assert_eq!(find("/api/users/7", "GET"), Some("http://show:3000"));
+    assert_eq!(find("/api/users", "DELETE"), None);
+    assert_eq!(find("/api/posts", "GET"), None);
+}
+
+#[test]
+fn test_route_index_keeps_config_order() {
+    // A pattern route listed first shadows a later literal one, as with
+    // a linear scan
+    let yaml = r#"
+routes:
+  - path: /api/users/:id
+    method: GET
+    upstream: http://show:3000
+
+  - path: /api/users/me
+    method: GET
+    upstream: http://me:3000
+
+  - path: /api/teams/me
+    method: GET
+    upstream: http://me:3000
+
+  - path: /api/teams/:id
+    method: GET
+    upstream: http://show:3000
+"#;
+
+    let config = Config::from_str(yaml).expect("parse config");
+    let regexes = RouteRegexCache::from_config(&config);
+    let index = RouteIndex::from_config(&config);
+
+    for path in ["/api/users/me", "/api/teams/me", "/api/teams/7"] {
+        let indexed = index.find(&config, path, "GET", &regexes);
+        let scanned = config.find_route(path, "GET");
+        assert_eq!(indexed, scanned, "lookup for {}", path);
+    }
+    assert_eq!(
+        index
+            .find(&config, "/api/users/me", "GET", &regexes)
+            .map(|route| route.upstream.as_str()),
+        Some("http://show:3000")
+    );
+}
+
+#[test]
+fn test_duplicate_routes_rejected() {
+    let config = Config::from_str(
+        r#"
+routes:
+  - path: /api/users
+    method: GET
+    upstream: http://backend:3000
+
+  - path: /api/users
+    method: POST
+    upstream: http://backend:3000
+
+  - path: /api/users
+    method: get
+    upstream: http://other:3000
+"#,
+    )
+    .expect("parse config");
+    let err = config.validate().expect_err("duplicate route is rejected");
+    assert!(err.contains("Route 2"), "unexpected error: {}", err);
+    assert!(
+        err.contains("duplicate route GET /api/users"),
+        "unexpected error: {}",
+        err
+    );
+    assert!(err.contains("route 0"), "unexpected error: {}", err);
+
+    // Parameter names don't make two patterns distinct
+    let params = Config::from_str(
+        r#"
+routes:
+  - path: /api/users/:id
+    method: GET
+    upstream: http://backend:3000
+
+  - path: /api/users/:name
+    method: GET
+    upstream: http://backend:3000
+"#,
+    )
+    .expect("parse config");
+    let err = params.validate().expect_err("same pattern is rejected");
+    assert!(
+        err.contains("duplicate route GET /api/users/:name"),
+        "unexpected error: {}",
+        err
+    );
+
+    // Nor does a trailing slash
+    let slashed = Config::from_str(
+        r#"
+routes:
+  - path: /api/users
+    method: GET
+    upstream: http://backend:3000
+
+  - path: /api/users/
+    method: GET
+    upstream: http://backend:3000
+"#,
+    )
+    .expect("parse config");
+    let err = slashed
+        .validate()
+        .expect_err("trailing slash is a duplicate");
+    assert!(
+        err.contains("duplicate route GET /api/users/"),
+        "unexpected error: {}",
+        err
+    );
+}
+
+#[test]
+fn test_trailing_slash_is_ignored() {
+    let route = create_route("/api/users/:id", "GET");
+    assert!(route.matches("/api/users/7/", "GET"));
+    assert!(!route.matches("/api/users/", "GET"));
+
+    let slashed = create_route("/api/users/", "GET");
+    assert!(slashed.matches("/api/users", "GET"));
+    assert!(slashed.matches("/api/users/", "GET"));
+
+    // The root stays a path of its own
+    let root = create_route("/", "GET");
+    assert!(root.matches("/", "GET"));
+    assert!(!root.matches("/api", "GET"));
+
+    let config = Config::from_str(
+        "routes:\n  - path: /api/users\n    method: GET\n    upstream: http://backend:3000\n",
+    )
+    .expect("parse config");
+    let index = RouteIndex::from_config(&config);
+    let regexes = RouteRegexCache::from_config(&config);
+    assert!(index
+        .find(&config, "/api/users/", "GET", &regexes)
+        .is_some());
+    assert_eq!(config.find_allowed_methods("/api/users/"), vec!["GET"]);
+}
+
+#[test]
+fn test_case_insensitive_paths() {
+    let yaml = |global: &str| {
+        format!(
+            r#"
+{}
+routes:
+  - path: /api/Users
+    method: GET
+    upstream: http://list:3000
+
+  - path: /api/users/:id/Posts
+    method: GET
+    upstream: http://posts:3000
+"#,
+            global
+        )
+    };
+
+    // Off by default
+    let config = Config::from_str(&yaml("")).expect("parse config");
+    let index = RouteIndex::from_config(&config);
+    let regexes = RouteRegexCache::from_config(&config);
+    assert!(index.find(&config, "/api/users", "GET", &regexes).is_none());
+    assert!(config.find_route("/API/users/7/posts", "GET").is_none());
+
+    let config =
+        Config::from_str(&yaml("global:\n  case_insensitive_paths: true")).expect("parse config");
+    config.validate().expect("config is valid");
+    let index = RouteIndex::from_config(&config);
+    let regexes = RouteRegexCache::from_config(&config);
+    let find = |path: &str| {
+        index
+            .find(&config, path, "GET", &regexes)
+            .map(|route| route.upstream.as_str())
+    };
+    assert_eq!(find("/api/users"), Some("http://list:3000"));
+    assert_eq!(find("/API/USERS/"), Some("http://list:3000"));
+    assert_eq!(find("/Api/users/Ab/posts"), Some("http://posts:3000"));
+    assert_eq!(find("/api/customers"), None);
+    assert_eq!(
+        config
+            .find_route("/API/users/7/posts", "GET")
+            .map(|r| r.upstream.as_str()),
+        Some("http://posts:3000")
+    );
+    assert_eq!(config.find_allowed_methods("/API/USERS"), vec!["GET"]);
+}
+
+#[test]
+fn test_case_insensitive_duplicate_routes_rejected() {
+    let yaml = |global: &str| {
+        format!(
+            r#"
+{}
+routes:
+  - path: /api/users/:id
+    method: GET
+    upstream: http://backend:3000
+
+  - path: /API/Users/:name
+    method: GET
+    upstream: http://other:3000
+"#,
+            global
+        )
+    };
+
+    // Distinct routes while paths are case-sensitive
+    let config = Config::from_str(&yaml("")).expect("parse config");
+    config.validate().expect("config is valid");
+
+    // The same route once case is ignored
+    let config =
+        Config::from_str(&yaml("global:\n  case_insensitive_paths: true")).expect("parse config");
+    let err = config.validate().expect_err("duplicate route is rejected");
+    assert!(
+        err.contains("duplicate route GET /API/Users/:name"),
+        "unexpected error: {}",
+        err
+    );
+}
+
+#[test]
+fn test_regex_route_validation() {
+    let both = Config::from_str(
+        r#"
+routes:
+  - path: /api/users
+    path_regex: '^/api/users$'
+    method: GET
+    upstream: http://backend:3000
+"#,
+    )
+    .expect("parse config");
+    let err = both
+        .validate()
+        .expect_err("path and path_regex are exclusive");
+    assert!(err.contains("path_regex"), "unexpected error: {}", err);
+
+    let invalid = Config::from_str(
+        r#"
+routes:
+  - path_regex: '^/api/(unclosed$'
+    method: GET
+    upstream: http://backend:3000
+"#,
+    )
+    .expect("parse config");
+    let err = invalid.validate().expect_err("regex must compile");
+    assert!(
+        err.contains("Invalid path_regex"),
+        "unexpected error: {}",
+        err
+    );
+
+    let neither = Config::from_str(
+        r#"
+routes:
+  - method: GET
+    upstream: http://backend:3000
+"#,
+    )
+    .expect("parse config");
+    assert!(neither.validate().is_err());
+}
+
+#[tokio::test]
+async fn test_regex_captures_feed_openapi_path_parameters() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let spec_path = dir.path().join("openapi.yaml");
+    std::fs::write(
+        &spec_path,
+        r#"
+openapi: 3.0.0
+info:
+  title: Demo
+  version: "1.0.0"
+paths:
+  /api/items/{id}:
+    get:
+      parameters:
+        - name: id
+          in: path
+          required: true
+          schema:
+            type: integer
+            maximum: 100
+"#,
+    )
+    .expect("write spec");
+
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: false
+
+routes:
+  - path_regex: '^/api/items/(?P<id>\d+)$'
+    method: GET
+    openapi: {}
+    upstream: http://localhost:9999
+"#,
+        spec_path.display()
+    ))
+    .expect("parse config");
+
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new().expect("create metrics")),
+    )));
+
+    // The captured id exceeds the spec's maximum and must be rejected
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri("/api/items/500")
+        .body(Body::empty())
+        .expect("build request");
+    let (parts, body) = request.into_parts();
+    let response = handle_request(State(state), parts.method, parts.uri, parts.headers, body).await;
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("read body");
+    let body = String::from_utf8_lossy(&body);
+    assert!(
+        body.contains("Parameter 'id' invalid"),
+        "unexpected body: {}",
+        body
+    );
+}
+
+#[test]
+fn test_find_allowed_methods() {
+    let config = Config::from_str(
+        r#"
+routes:
+  - path: /api/users
+    method: post
+    upstream: http://backend:3000
+  - path: /api/users/:id
+    method: GET
+    upstream: http://backend:3000
+  - path_regex: '^/api/users/\d+$'
+    method: DELETE
+    upstream: http://backend:3000
+  - path: /api/users/:name
+    method: GET
+    upstream: http://backend:3000
+"#,
+    )
+    .expect("parse config");
+
+    assert_eq!(config.find_allowed_methods("/api/users"), vec!["POST"]);
+    assert_eq!(
+        config.find_allowed_methods("/api/users/7"),
+        vec!["GET", "DELETE"]
+    );
+    assert_eq!(config.find_allowed_methods("/api/users/bob"), vec!["GET"]);
+    assert!(config.find_allowed_methods("/api/posts").is_empty());
+}
+
+#[tokio::test]
+async fn test_wrong_method_returns_405_with_allow() {
+    let config = Config::from_str(
+        r#"
+routes:
+  - path: /api/users
+    method: POST
+    upstream: http://localhost:9999
+  - path: /api/users
+    method: PUT
+    upstream: http://localhost:9999
+"#,
+    )
+    .expect("parse config");
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new().expect("create metrics")),
+    )));
+
+    let send = |method: Method, uri: &'static str| {
+        let state = state.clone();
+        async move {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .body(Body::empty())
+                .expect("build request");
+            let (parts, body) = request.into_parts();
+            handle_request(State(state), parts.method, parts.uri, parts.headers, body).await
+        }
+    };
+
+    let response = send(Method::GET, "/api/users").await;
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(
+        response
+            .headers()
+            .get("allow")
+            .and_then(|value| value.to_str().ok()),
+        Some("POST, PUT")
+    );
+
+    let response = send(Method::GET, "/api/posts").await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert!(!response.headers().contains_key("allow"));
+
+    let metrics = state.read().await.metrics.gather().expect("gather metrics");
+    assert!(
+        metrics.contains(r#"method_not_allowed_total{method="GET"} 1"#),
+        "{}",
+        metrics
+    );
+    assert!(
+        metrics.contains(r#"routes_not_found_total{method="GET"} 1"#),
+        "{}",
+        metrics
+    );
+}
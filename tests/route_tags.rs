@@ -0,0 +1,127 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use schema_gateway::admin::routes_report;
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use serde_json::json;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn route_error(tags: &str) -> Option<String> {
+    Config::from_str(&format!(
+        "routes:\n  - path: /api\n    method: GET\n    upstream: http://backend:3000\n    tags: {}\n",
+        tags
+    ))
+    .map_err(|e| e.to_string())
+    .and_then(|config| config.validate())
+    .err()
+}
+
+#[tokio::test]
+async fn test_tags_label_metrics_and_admin_output() -> TestResult {
+    let upstream = MockServer::start().await;
+    Mock::given(matchers::any())
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&upstream)
+        .await;
+
+    let config = Config::from_str(&format!(
+        r#"
+routes:
+  - path: /api/payments
+    method: GET
+    upstream: {upstream}
+    tags: [team-payments, v2, public, beta]
+  - path: /api/health
+    method: GET
+    upstream: {upstream}
+"#,
+        upstream = upstream.uri()
+    ))?;
+    config.validate()?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+
+    for path in ["/api/payments", "/api/health"] {
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(path)
+            .body(Body::empty())?;
+        let (parts, body) = request.into_parts();
+        let response = handle_request(
+            State(state.clone()),
+            parts.method,
+            parts.uri,
+            parts.headers,
+            body,
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    // Only the first three tags make it into the label
+    let metrics = state.read().await.metrics.gather()?;
+    assert!(
+        metrics.contains(r#"http_requests_total{method="GET",route="/api/payments",route_tags="team-payments,v2,public",status="200"} 1"#),
+        "{}",
+        metrics
+    );
+    assert!(
+        metrics.contains(r#"http_request_duration_seconds_count{method="GET",route="/api/payments",route_tags="team-payments,v2,public"} 1"#),
+        "{}",
+        metrics
+    );
+    assert!(
+        metrics.contains(
+            r#"http_requests_total{method="GET",route="/api/health",route_tags="",status="200"} 1"#
+        ),
+        "{}",
+        metrics
+    );
+
+    // The admin API lists all of them
+    let report = routes_report(&state).await;
+    assert_eq!(
+        report["routes"][0]["tags"],
+        json!(["team-payments", "v2", "public", "beta"])
+    );
+    assert_eq!(report["routes"][1]["tags"], json!([]));
+    Ok(())
+}
+
+#[test]
+fn test_tag_validation() {
+    assert_eq!(route_error("[team-a, v1]"), None);
+    assert_eq!(route_error(&format!("[{}]", "t".repeat(64))), None);
+
+    let too_many = format!(
+        "[{}]",
+        (0..11)
+            .map(|i| format!("t{}", i))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let error = route_error(&too_many).unwrap_or_default();
+    assert!(error.starts_with("Route 0: "), "{}", error);
+    assert!(error.contains("at most 10 tags, found 11"), "{}", error);
+
+    let error = route_error(&format!("[{}]", "t".repeat(65))).unwrap_or_default();
+    assert!(
+        error.contains("must be 1 to 64 characters long"),
+        "{}",
+        error
+    );
+
+    let error = route_error("['a,b']").unwrap_or_default();
+    assert!(error.contains("cannot contain ','"), "{}", error);
+}
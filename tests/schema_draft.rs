@@ -0,0 +1,173 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use schema_gateway::schema::{SchemaCache, SchemaDraft, SchemaOptions};
+use serde_json::json;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// Draft-7 style: the contact is a `$ref` into `definitions`, and drafts
+/// before 2019-09 assert `format`
+const CONTACT_SCHEMA: &str = r##"{
+  "type": "object",
+  "required": ["contact"],
+  "properties": { "contact": { "$ref": "#/definitions/email" } },
+  "definitions": { "email": { "type": "string", "format": "email" } }
+}"##;
+
+fn write_schema(contents: &str) -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("contact.json");
+    fs::write(&path, contents).expect("write schema");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+fn with_draft(draft: SchemaDraft) -> SchemaOptions {
+    SchemaOptions {
+        strict_body: false,
+        draft: Some(draft),
+    }
+}
+
+/// POST `body` through a rejecting route for `schema` with `route_config`
+/// added
+async fn send(schema: &str, route_config: &str, body: &str) -> TestResult<StatusCode> {
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::method("POST"))
+        .respond_with(ResponseTemplate::new(201))
+        .mount(&mock_server)
+        .await;
+
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: false
+
+routes:
+  - path: /api/contacts
+    method: POST
+    schema: {}
+    upstream: {}
+{}
+"#,
+        write_schema(schema).display(),
+        mock_server.uri(),
+        route_config
+    ))?;
+    config.validate()?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/contacts")
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))?;
+    let (parts, body) = request.into_parts();
+    let response = handle_request(State(state), parts.method, parts.uri, parts.headers, body).await;
+    Ok(response.status())
+}
+
+#[test]
+fn test_definitions_validate_under_draft7_but_not_draft2020_12() -> TestResult {
+    let path = write_schema(CONTACT_SCHEMA);
+    let cache = SchemaCache::new();
+    let draft7 = cache.load_with(&path, with_draft(SchemaDraft::Draft7))?;
+    let draft2020 = cache.load_with(&path, with_draft(SchemaDraft::Draft2020_12))?;
+
+    let valid = json!({"contact": "ada@example.com"});
+    let not_an_email = json!({"contact": "not an email"});
+    let not_a_string = json!({"contact": 42});
+
+    assert!(draft7.is_valid(&valid));
+    assert!(!draft7.is_valid(&not_an_email));
+    assert!(!draft7.is_valid(&not_a_string));
+
+    // The `$ref` still resolves, but 2020-12 treats `format` as an annotation
+    assert!(draft2020.is_valid(&valid));
+    assert!(draft2020.is_valid(&not_an_email));
+    assert!(!draft2020.is_valid(&not_a_string));
+
+    // Each draft is cached on its own
+    assert!(cache.contains_with(&path, with_draft(SchemaDraft::Draft7)));
+    assert!(!cache.contains(&path));
+    let mut drafts: Vec<_> = cache.snapshot().into_iter().map(|e| e.draft).collect();
+    drafts.sort();
+    assert_eq!(
+        drafts,
+        vec![Some(SchemaDraft::Draft7), Some(SchemaDraft::Draft2020_12)]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_schema_draft_overrides_declared_schema() -> TestResult {
+    let declared_2020 = CONTACT_SCHEMA.replacen(
+        '{',
+        r##"{ "$schema": "https://json-schema.org/draft/2020-12/schema#","##,
+        1,
+    );
+    let body = r#"{"contact": "not an email"}"#;
+
+    assert_eq!(send(&declared_2020, "", body).await?, StatusCode::CREATED);
+    assert_eq!(
+        send(&declared_2020, "    schema_draft: draft7", body).await?,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        send(
+            &declared_2020,
+            "    schema_draft: draft7",
+            r#"{"contact": "ada@example.com"}"#
+        )
+        .await?,
+        StatusCode::CREATED
+    );
+    Ok(())
+}
+
+#[test]
+fn test_schema_draft_names() {
+    for name in SchemaDraft::NAMES {
+        let draft = SchemaDraft::from_name(name).expect(name);
+        assert_eq!(draft.name(), name);
+    }
+    assert_eq!(SchemaDraft::from_name("draft2019-09"), None);
+}
+
+#[test]
+fn test_schema_draft_validation() -> TestResult {
+    let schema = write_schema(CONTACT_SCHEMA);
+    let config = Config::from_str(&format!(
+        "routes:\n  - path: /api\n    method: POST\n    upstream: http://backend:3000\n    schema: {}\n    schema_draft: draft8\n",
+        schema.display()
+    ))?;
+    let error = config.validate().expect_err("unknown draft");
+    assert!(error.contains("Unknown schema_draft 'draft8'"), "{}", error);
+    assert!(error.contains("draft2020_12"), "{}", error);
+
+    let config = Config::from_str(
+        "routes:\n  - path: /api\n    method: POST\n    upstream: http://backend:3000\n    schema_draft: draft7\n",
+    )?;
+    let error = config.validate().expect_err("no schema");
+    assert!(
+        error.contains("'schema_draft' requires 'schema'"),
+        "{}",
+        error
+    );
+    Ok(())
+}
@@ -1,6 +1,7 @@
 use std::fs;
 use std::path::PathBuf;
 
+use schema_gateway::error::Error;
 use schema_gateway::schema::SchemaCache;
 
 fn write_temp_schema_file(contents: &str) -> PathBuf {
@@ -25,7 +26,7 @@ fn test_load_valid_schema() {
 
     let path = write_temp_schema_file(schema_json);
 
-    let mut cache = SchemaCache::new();
+    let cache = SchemaCache::new();
     let compiled = cache.load(&path);
 
     assert!(
@@ -37,7 +38,7 @@ fn test_load_valid_schema() {
 
 #[test]
 fn test_load_missing_schema() {
-    let mut cache = SchemaCache::new();
+    let cache = SchemaCache::new();
     let missing = PathBuf::from("/definitely/does/not/exist.json");
     match cache.load(&missing) {
         Ok(_) => panic!("loading should fail for missing schema"),
@@ -55,7 +56,7 @@ fn test_load_missing_schema() {
 #[test]
 fn test_load_invalid_json() {
     let path = write_temp_schema_file("{ this is not valid json }");
-    let mut cache = SchemaCache::new();
+    let cache = SchemaCache::new();
     match cache.load(&path) {
         Ok(_) => panic!("loading should fail for invalid schema JSON"),
         Err(err) => {
@@ -77,7 +78,7 @@ fn test_load_invalid_schema_syntax() {
         "type": "not-a-valid-type"
     }"#;
     let path = write_temp_schema_file(schema_json);
-    let mut cache = SchemaCache::new();
+    let cache = SchemaCache::new();
     match cache.load(&path) {
         Ok(_) => panic!("loading should fail for invalid schema syntax"),
         Err(err) => {
@@ -99,7 +100,7 @@ fn test_schema_caching() {
     }"#;
     let path = write_temp_schema_file(schema_json);
 
-    let mut cache = SchemaCache::new();
+    let cache = SchemaCache::new();
     let first = cache.load(&path).expect("compile first");
     // Replace file contents to ensure we don't re-read/compile if cached
     fs::write(&path, "{}\n").expect("overwrite schema");
@@ -110,3 +111,232 @@ fn test_schema_caching() {
         "expected same Arc from cache"
     );
 }
+
+#[test]
+fn test_schema_cache_evicts_least_recently_used() {
+    let schema_json = r#"{"type": "object"}"#;
+    let first_path = write_temp_schema_file(schema_json);
+    let second_path = write_temp_schema_file(schema_json);
+    let third_path = write_temp_schema_file(schema_json);
+
+    let cache = SchemaCache::with_max_entries(Some(2));
+    let first = cache.load(&first_path).expect("compile first");
+    cache.load(&second_path).expect("compile second");
+    // Touch the first schema so the second becomes least recently used
+    cache.load(&first_path).expect("first from cache");
+    cache.load(&third_path).expect("compile third");
+
+    assert_eq!(cache.len(), 2);
+    assert!(cache.contains(&first_path), "expected first to stay cached");
+    assert!(
+        !cache.contains(&second_path),
+        "expected second to be evicted"
+    );
+    assert!(cache.contains(&third_path), "expected third to be cached");
+
+    // Re-accessing an evicted schema recompiles it from disk
+    fs::write(&second_path, r#"{"type": "string"}"#).expect("overwrite schema");
+    let reloaded = cache.load(&second_path).expect("recompile second");
+    assert!(reloaded.is_valid(&serde_json::json!("text")));
+    assert!(!cache.contains(&first_path), "expected first to be evicted");
+    assert!(
+        !std::sync::Arc::ptr_eq(&first, &reloaded),
+        "expected a freshly compiled schema"
+    );
+}
+
+#[test]
+fn test_concurrent_loads_compile_once() {
+    let schema_json = r#"{"type": "object", "required": ["id"]}"#;
+    let path = write_temp_schema_file(schema_json);
+    let cache = SchemaCache::new();
+
+    let schemas: Vec<_> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..16)
+            .map(|_| scope.spawn(|| cache.load(&path).expect("load schema")))
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("join loader"))
+            .collect()
+    });
+
+    let first = &schemas[0];
+    assert!(
+        schemas.iter().all(|s| std::sync::Arc::ptr_eq(first, s)),
+        "expected every concurrent load to share one compiled schema"
+    );
+    assert_eq!(cache.len(), 1);
+}
+
+#[test]
+fn test_negative_cache_replays_failure_until_ttl() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("later.json");
+    let cache = SchemaCache::new().with_negative_ttl(std::time::Duration::from_millis(200));
+
+    // First attempt really reads the filesystem
+    let first = cache.load(&path).expect_err("schema is missing");
+    assert!(matches!(first, Error::SchemaNotFound { .. }));
+
+    // The file appears, but the failure is replayed until the TTL expires
+    fs::write(&path, r#"{"type": "object"}"#).expect("write schema");
+    let second = cache.load(&path).expect_err("failure is cached");
+    assert!(matches!(second, Error::Cached { .. }));
+    assert_eq!(second.to_string(), first.to_string());
+
+    std::thread::sleep(std::time::Duration::from_millis(250));
+    cache.load(&path).expect("retry after TTL succeeds");
+    assert!(cache.contains(&path));
+}
+
+#[test]
+fn test_negative_cache_disabled_by_default() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("later.json");
+    let cache = SchemaCache::new();
+
+    assert!(cache.load(&path).is_err());
+    fs::write(&path, r#"{"type": "object"}"#).expect("write schema");
+    cache.load(&path).expect("load without negative caching");
+}
+
+fn write_temp_bundle(contents: &str) -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("schemas.yml");
+    fs::write(&path, contents).expect("write temp bundle");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+const BUNDLE_YAML: &str = r#"
+createUser:
+  type: object
+  required: [name]
+  properties:
+    name: {type: string}
+deleteUser:
+  type: object
+  required: [id]
+"#;
+
+#[test]
+fn test_load_named_schema_from_bundle() {
+    let bundle = write_temp_bundle(BUNDLE_YAML);
+    let cache = SchemaCache::new();
+
+    let create = cache
+        .load(format!("{}#createUser", bundle.display()))
+        .expect("compile createUser");
+    let delete = cache
+        .load(format!("{}#deleteUser", bundle.display()))
+        .expect("compile deleteUser");
+
+    assert!(create.is_valid(&serde_json::json!({"name": "Ada"})));
+    assert!(!create.is_valid(&serde_json::json!({})));
+    assert!(delete.is_valid(&serde_json::json!({"id": 1})));
+    assert_eq!(cache.len(), 2, "each named entry is cached separately");
+}
+
+#[test]
+fn test_bundle_is_parsed_once() {
+    let bundle = write_temp_bundle(BUNDLE_YAML);
+    let cache = SchemaCache::new();
+
+    cache
+        .load(format!("{}#createUser", bundle.display()))
+        .expect("compile createUser");
+    // Later entries come from the cached document, not the file on disk
+    fs::write(&bundle, "{}").expect("overwrite bundle");
+    cache
+        .load(format!("{}#deleteUser", bundle.display()))
+        .expect("compile deleteUser from cached bundle");
+}
+
+#[test]
+fn test_unknown_bundle_name_lists_available() {
+    let bundle = write_temp_bundle(BUNDLE_YAML);
+    let cache = SchemaCache::new();
+
+    let err = cache
+        .load(format!("{}#updateUser", bundle.display()))
+        .expect_err("unknown name should fail");
+    let msg = err.to_string();
+    assert!(
+        matches!(err, Error::SchemaNotInBundle { .. }),
+        "unexpected error: {}",
+        msg
+    );
+    assert!(msg.contains("'updateUser'"), "unexpected error: {}", msg);
+    assert!(
+        msg.contains("available: createUser, deleteUser"),
+        "unexpected error: {}",
+        msg
+    );
+}
+
+#[test]
+fn test_bundle_must_be_a_mapping() {
+    let bundle = write_temp_bundle("- type: object\n");
+    let cache = SchemaCache::new();
+
+    let err = cache
+        .load(format!("{}#createUser", bundle.display()))
+        .expect_err("list bundle should fail");
+    assert!(
+        matches!(err, Error::InvalidSchemaBundle { .. }),
+        "unexpected error: {}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn test_preload_compiles_schemas_up_front() {
+    let valid = write_temp_schema_file(r#"{"type": "object"}"#);
+    let bundle = write_temp_bundle(BUNDLE_YAML);
+    let create_user = PathBuf::from(format!("{}#createUser", bundle.display()));
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let missing = dir.path().join("missing.json");
+    let cache = SchemaCache::new();
+
+    let report = cache
+        .preload(&[
+            valid.clone(),
+            create_user.clone(),
+            missing.clone(),
+            valid.clone(),
+        ])
+        .await;
+    assert_eq!(report.loaded, 2);
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].0, missing);
+    assert!(matches!(report.failed[0].1, Error::SchemaNotFound { .. }));
+
+    assert!(cache.contains(&valid));
+    assert!(cache.contains(&create_user));
+    assert!(!cache.contains_strict(&valid));
+
+    // Cached schemas are skipped on a second pass
+    let report = cache.preload(std::slice::from_ref(&valid)).await;
+    assert_eq!(report.loaded, 0);
+    assert!(report.failed.is_empty());
+
+    let report = cache.preload_strict(std::slice::from_ref(&valid)).await;
+    assert_eq!(report.loaded, 1);
+    assert!(cache.contains_strict(&valid));
+}
+
+#[tokio::test]
+async fn test_preload_reports_invalid_schema() {
+    let invalid = write_temp_schema_file(r#"{"type": "not-a-type"}"#);
+    let cache = SchemaCache::new().with_negative_ttl(std::time::Duration::from_secs(60));
+
+    let report = cache.preload(std::slice::from_ref(&invalid)).await;
+    assert_eq!(report.loaded, 0);
+    assert!(matches!(
+        report.failed[0].1,
+        Error::InvalidSchemaSyntax { .. }
+    ));
+    // The failure is remembered like a failed load
+    assert!(matches!(cache.load(&invalid), Err(Error::Cached { .. })));
+}
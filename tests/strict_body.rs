@@ -0,0 +1,236 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use schema_gateway::schema::forbid_additional_properties;
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const SPEC: &str = r#"
+openapi: 3.0.3
+info: { title: Demo, version: "1.0.0" }
+paths:
+  /api/users:
+    post:
+      requestBody:
+        content:
+          application/json:
+            schema:
+              type: object
+              required: [name]
+              properties:
+                name: { type: string }
+                address:
+                  type: object
+                  properties:
+                    city: { type: string }
+      responses:
+        '201':
+          description: created
+"#;
+
+const SCHEMA: &str = r#"{
+  "type": "object",
+  "required": ["name"],
+  "properties": {
+    "name": { "type": "string" },
+    "tags": { "type": "array", "items": { "type": "object", "properties": { "id": { "type": "integer" } } } }
+  }
+}"#;
+
+fn write_file(name: &str, contents: &str) -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join(name);
+    fs::write(&path, contents).expect("write file");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// POST `body` through a route configured by `route_config`, with
+/// validation failures rejected
+async fn send(route_config: &str, body: &str) -> TestResult<StatusCode> {
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::method("POST"))
+        .respond_with(ResponseTemplate::new(201))
+        .mount(&mock_server)
+        .await;
+
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: false
+
+routes:
+  - path: /api/users
+    method: POST
+    upstream: {}
+{}
+"#,
+        mock_server.uri(),
+        route_config
+    ))?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/users")
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))?;
+    let (parts, body) = request.into_parts();
+    let response = handle_request(State(state), parts.method, parts.uri, parts.headers, body).await;
+    Ok(response.status())
+}
+
+fn openapi_route(strict_body: bool) -> String {
+    format!(
+        "    openapi:\n      spec: {}\n      strict_body: {}",
+        write_file("openapi.yaml", SPEC).display(),
+        strict_body
+    )
+}
+
+fn schema_route(strict_body: bool) -> String {
+    format!(
+        "    schema: {}\n    strict_body: {}",
+        write_file("user.json", SCHEMA).display(),
+        strict_body
+    )
+}
+
+#[tokio::test]
+async fn test_openapi_extra_field_rejected_only_when_strict() -> TestResult {
+    let extra = r#"{"name": "a", "admin": true}"#;
+    let nested = r#"{"name": "a", "address": {"city": "x", "zip": "1"}}"#;
+    let declared = r#"{"name": "a", "address": {"city": "x"}}"#;
+
+    assert_eq!(
+        send(&openapi_route(false), extra).await?,
+        StatusCode::CREATED
+    );
+    assert_eq!(
+        send(&openapi_route(false), nested).await?,
+        StatusCode::CREATED
+    );
+
+    assert_eq!(
+        send(&openapi_route(true), extra).await?,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        send(&openapi_route(true), nested).await?,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        send(&openapi_route(true), declared).await?,
+        StatusCode::CREATED
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_schema_extra_field_rejected_only_when_strict() -> TestResult {
+    let extra = r#"{"name": "a", "tags": [{"id": 1, "label": "x"}]}"#;
+    let declared = r#"{"name": "a", "tags": [{"id": 1}]}"#;
+
+    assert_eq!(
+        send(&schema_route(false), extra).await?,
+        StatusCode::CREATED
+    );
+    assert_eq!(
+        send(&schema_route(true), extra).await?,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        send(&schema_route(true), declared).await?,
+        StatusCode::CREATED
+    );
+    Ok(())
+}
+
+#[test]
+fn test_explicit_additional_properties_is_kept() {
+    let mut schema = json!({
+        "type": "object",
+        "additionalProperties": true,
+        "properties": {
+            "meta": { "type": "object", "additionalProperties": { "type": "string" } },
+            "extra": { "type": "object", "unevaluatedProperties": false }
+        }
+    });
+    forbid_additional_properties(&mut schema, Path::new("schema.json"));
+
+    assert_eq!(schema["additionalProperties"], json!(true));
+    assert_eq!(
+        schema["properties"]["meta"]["additionalProperties"],
+        json!({ "type": "string" })
+    );
+    assert!(schema["properties"]["extra"]
+        .get("additionalProperties")
+        .is_none());
+}
+
+#[test]
+fn test_composed_schemas_are_left_open() {
+    let mut schema = json!({
+        "type": "object",
+        "properties": {
+            "pet": { "allOf": [{ "$ref": "#/definitions/Base" }, { "properties": { "bark": {} } }] },
+            "owner": { "$ref": "#/definitions/Owner" }
+        },
+        "definitions": {
+            "Base": { "type": "object", "properties": { "name": {} } },
+            "Owner": { "type": "object", "properties": { "id": {} } }
+        }
+    });
+    forbid_additional_properties(&mut schema, Path::new("schema.json"));
+
+    assert_eq!(schema["additionalProperties"], json!(false));
+    assert!(schema["properties"]["pet"]
+        .get("additionalProperties")
+        .is_none());
+    assert!(schema["properties"]["pet"]["allOf"][1]
+        .get("additionalProperties")
+        .is_none());
+    // Base is pulled into allOf, so closing it would reject `bark`
+    assert!(schema["definitions"]["Base"]
+        .get("additionalProperties")
+        .is_none());
+    assert_eq!(
+        schema["definitions"]["Owner"]["additionalProperties"],
+        json!(false)
+    );
+}
+
+#[test]
+fn test_strict_body_requires_a_schema() -> TestResult {
+    let config = Config::from_str(
+        r#"
+routes:
+  - path: /api/users
+    method: POST
+    upstream: http://backend:3000
+    strict_body: true
+"#,
+    )?;
+    let error = config.validate().expect_err("strict_body without schema");
+    assert!(
+        error.contains("'strict_body' requires 'schema'"),
+        "{}",
+        error
+    );
+    Ok(())
+}
@@ -0,0 +1,128 @@
+use axum_server::tls_rustls::RustlsConfig;
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, AppState};
+use schema_gateway::metrics::Metrics;
+use schema_gateway::router::build_router;
+use schema_gateway::tls::load_server_config;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+struct TestCert {
+    cert_pem: String,
+    cert_path: PathBuf,
+    key_path: PathBuf,
+}
+
+fn write_temp_file(name: &str, contents: &str) -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join(name);
+    fs::write(&path, contents).expect("write temp file");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+fn self_signed_cert() -> TestResult<TestCert> {
+    let generated = rcgen::generate_simple_self_signed(vec!["localhost".to_string()])?;
+    let cert_pem = generated.cert.pem();
+    Ok(TestCert {
+        cert_path: write_temp_file("cert.pem", &cert_pem),
+        key_path: write_temp_file("key.pem", &generated.signing_key.serialize_pem()),
+        cert_pem,
+    })
+}
+
+#[test]
+fn test_load_server_config_with_matching_pair() -> TestResult {
+    let cert = self_signed_cert()?;
+    let config = load_server_config(&cert.cert_path, &cert.key_path, None)?;
+    assert_eq!(
+        config.alpn_protocols,
+        vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_load_server_config_with_client_ca() -> TestResult {
+    let cert = self_signed_cert()?;
+    let ca = self_signed_cert()?;
+    load_server_config(&cert.cert_path, &cert.key_path, Some(&ca.cert_path))?;
+    Ok(())
+}
+
+#[test]
+fn test_load_server_config_rejects_mismatched_key() -> TestResult {
+    let cert = self_signed_cert()?;
+    let other = self_signed_cert()?;
+    let err = load_server_config(&cert.cert_path, &other.key_path, None).unwrap_err();
+    assert!(
+        err.contains("don't form a valid pair"),
+        "unexpected error: {}",
+        err
+    );
+    Ok(())
+}
+
+#[test]
+fn test_load_server_config_reports_unreadable_files() -> TestResult {
+    let cert = self_signed_cert()?;
+    let missing = PathBuf::from("/definitely/does/not/exist.pem");
+
+    let err = load_server_config(&missing, &cert.key_path, None).unwrap_err();
+    assert!(err.contains("Failed to read certificate"), "{}", err);
+
+    let err = load_server_config(&cert.cert_path, &missing, None).unwrap_err();
+    assert!(err.contains("Failed to read private key"), "{}", err);
+
+    let not_pem = write_temp_file("empty.pem", "not a certificate\n");
+    let err = load_server_config(&not_pem, &cert.key_path, None).unwrap_err();
+    assert!(err.contains("No certificates found"), "{}", err);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_https_listener_serves_requests() -> TestResult {
+    let cert = self_signed_cert()?;
+    let tls_config = load_server_config(&cert.cert_path, &cert.key_path, None)?;
+
+    let config = Config::from_str(
+        r#"
+routes:
+  - path: /api/users
+    method: GET
+    upstream: http://backend:3000
+"#,
+    )?;
+    let global = config.global.clone();
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+    let app = build_router(state, &global);
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let port = listener.local_addr()?.port();
+    tokio::spawn(
+        axum_server::from_tcp_rustls(listener, RustlsConfig::from_config(Arc::new(tls_config)))
+            .serve(app.into_make_service()),
+    );
+
+    let client = reqwest::Client::builder()
+        .add_root_certificate(reqwest::Certificate::from_pem(cert.cert_pem.as_bytes())?)
+        .no_proxy()
+        .build()?;
+    let response = client
+        .get(format!("https://localhost:{}/health", port))
+        .send()
+        .await?;
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.text().await?, "OK");
+    Ok(())
+}
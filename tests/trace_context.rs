@@ -0,0 +1,128 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+const TRACESTATE: &str = "rojo=00f067aa0ba902b7,congo=t61rcWkgMzE";
+const BAGGAGE: &str = "userId=alice,serverNode=DF%2028;ttl=60, isProduction=false";
+
+/// Send a GET with trace context headers through a route with
+/// `route_config`, returning the trace headers the upstream received
+async fn send(route_config: &str) -> TestResult<Vec<(String, Option<String>)>> {
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::method("GET"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let config = Config::from_str(&format!(
+        r#"
+routes:
+  - path: /api/users
+    method: GET
+    upstream: {}
+{}
+"#,
+        mock_server.uri(),
+        route_config
+    ))?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri("/api/users")
+        .header("traceparent", TRACEPARENT)
+        .header("tracestate", TRACESTATE)
+        .header("baggage", BAGGAGE)
+        .body(Body::empty())?;
+    let (parts, body) = request.into_parts();
+    let response = handle_request(State(state), parts.method, parts.uri, parts.headers, body).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let received = mock_server.received_requests().await.unwrap_or_default();
+    let headers = &received.first().ok_or("upstream not called")?.headers;
+    Ok(["traceparent", "tracestate", "baggage"]
+        .into_iter()
+        .map(|name| {
+            let value = headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string);
+            (name.to_string(), value)
+        })
+        .collect())
+}
+
+#[tokio::test]
+async fn test_trace_context_is_forwarded_verbatim() -> TestResult {
+    let received = send("").await?;
+    assert_eq!(
+        received,
+        vec![
+            ("traceparent".to_string(), Some(TRACEPARENT.to_string())),
+            ("tracestate".to_string(), Some(TRACESTATE.to_string())),
+            ("baggage".to_string(), Some(BAGGAGE.to_string())),
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_trace_context_can_be_dropped_per_route() -> TestResult {
+    let received = send("    config:\n      propagate_trace_context: false").await?;
+    assert!(
+        received.iter().all(|(_, value)| value.is_none()),
+        "{:?}",
+        received
+    );
+    Ok(())
+}
+
+#[test]
+fn test_propagate_trace_context_config() -> TestResult {
+    let config = Config::from_str(
+        r#"
+global:
+  propagate_trace_context: false
+
+routes:
+  - path: /api/users
+    method: GET
+    upstream: http://backend:3000
+    config:
+      propagate_trace_context: true
+  - path: /api/posts
+    method: GET
+    upstream: http://backend:3000
+"#,
+    )?;
+    assert!(
+        config
+            .get_effective_config(&config.routes[0])
+            .propagate_trace_context
+    );
+    assert!(
+        !config
+            .get_effective_config(&config.routes[1])
+            .propagate_trace_context
+    );
+
+    let config = Config::from_str("routes: []\n")?;
+    assert!(config.global.propagate_trace_context);
+    Ok(())
+}
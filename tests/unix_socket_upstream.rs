@@ -0,0 +1,214 @@
+#![cfg(unix)]
+
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use axum::response::Response;
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::RwLock;
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn temp_dir() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().to_path_buf();
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// Answer one HTTP/1.1 request with JSON describing it
+async fn echo_request(mut stream: UnixStream) -> TestResult {
+    let mut head = Vec::new();
+    while !head.ends_with(b"\r\n\r\n") {
+        let mut byte = [0u8; 1];
+        if stream.read(&mut byte).await? == 0 {
+            return Ok(());
+        }
+        head.push(byte[0]);
+    }
+    let head = String::from_utf8(head)?;
+    let mut lines = head.split("\r\n");
+    let request_line = lines.next().unwrap_or_default().to_string();
+    let header = |name: &str| {
+        head.split("\r\n").find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.eq_ignore_ascii_case(name)
+                .then(|| value.trim().to_string())
+        })
+    };
+    let length: usize = header("content-length")
+        .and_then(|length| length.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0u8; length];
+    stream.read_exact(&mut body).await?;
+
+    let reply = json!({
+        "request_line": request_line,
+        "host": header("host"),
+        "x_client": header("x-client"),
+        "body": String::from_utf8(body)?,
+    })
+    .to_string();
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nX-Upstream: unix\r\nConnection: close\r\n\r\n{}",
+        reply.len(),
+        reply
+    );
+    stream.write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+/// An HTTP upstream listening on a socket in a temp directory
+fn unix_upstream() -> TestResult<PathBuf> {
+    let socket = temp_dir().join("backend.sock");
+    let listener = UnixListener::bind(&socket)?;
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(echo_request(stream));
+        }
+    });
+    Ok(socket)
+}
+
+fn write_schema() -> PathBuf {
+    let path = temp_dir().join("item.json");
+    fs::write(&path, r#"{"type": "object", "required": ["id"]}"#).expect("write schema");
+    path
+}
+
+/// Gateway for `GET /api/items` and a validated `POST /api/items` on
+/// `upstream`
+fn gateway(upstream: &str) -> TestResult<Arc<RwLock<AppState>>> {
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: false
+
+routes:
+  - path: /api/items
+    method: GET
+    upstream: {upstream}
+  - path: /api/items
+    method: POST
+    schema: {schema}
+    upstream: {upstream}
+"#,
+        upstream = upstream,
+        schema = write_schema().display()
+    ))?;
+    config.validate()?;
+    let http_client = build_http_client(&config.global.http_client);
+    Ok(Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    ))))
+}
+
+async fn send(
+    state: &Arc<RwLock<AppState>>,
+    method: Method,
+    uri: &str,
+    body: &str,
+) -> TestResult<Response> {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .header("x-client", "tests")
+        .body(Body::from(body.to_string()))?;
+    let (parts, body) = request.into_parts();
+    Ok(handle_request(
+        State(state.clone()),
+        parts.method,
+        parts.uri,
+        parts.headers,
+        body,
+    )
+    .await)
+}
+
+async fn json_body(response: Response) -> TestResult<Value> {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+fn unix_url(host: &str, socket: &Path) -> String {
+    format!("unix://{}{}", host, socket.display())
+}
+
+#[tokio::test]
+async fn test_requests_are_forwarded_over_the_socket() -> TestResult {
+    let socket = unix_upstream()?;
+    let state = gateway(&unix_url("", &socket))?;
+
+    let response = send(&state, Method::GET, "/api/items?page=2", "").await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response
+            .headers()
+            .get("x-upstream")
+            .and_then(|v| v.to_str().ok()),
+        Some("unix")
+    );
+    let seen = json_body(response).await?;
+    assert_eq!(seen["request_line"], "GET /api/items?page=2 HTTP/1.1");
+    assert_eq!(seen["host"], "localhost");
+    assert_eq!(seen["x_client"], "tests");
+
+    // Validated bodies go through too, and invalid ones are still rejected
+    let response = send(&state, Method::POST, "/api/items", r#"{"id": 7}"#).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(json_body(response).await?["body"], r#"{"id": 7}"#);
+    let response = send(&state, Method::POST, "/api/items", "{}").await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_url_host_is_sent_as_host_header() -> TestResult {
+    let socket = unix_upstream()?;
+    let state = gateway(&unix_url("backend.local", &socket))?;
+
+    let response = send(&state, Method::GET, "/api/items", "").await?;
+    assert_eq!(json_body(response).await?["host"], "backend.local");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_missing_socket_is_a_bad_gateway() -> TestResult {
+    let socket = temp_dir().join("nothing-here.sock");
+    let state = gateway(&unix_url("", &socket))?;
+
+    let response = send(&state, Method::GET, "/api/items", "").await?;
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    Ok(())
+}
+
+#[test]
+fn test_unix_upstream_validation() -> TestResult {
+    let upstream_error = |upstream: &str| {
+        Config::from_str(&format!(
+            "routes:\n  - path: /api\n    method: GET\n    upstream: {}\n",
+            upstream
+        ))
+        .map_err(|e| e.to_string())
+        .and_then(|config| config.validate())
+        .err()
+    };
+    assert_eq!(upstream_error("unix:///var/run/backend.sock"), None);
+    assert_eq!(upstream_error("unix://backend/var/run/backend.sock"), None);
+
+    let error = upstream_error("unix://").unwrap_or_default();
+    assert!(error.contains("missing socket path"), "{}", error);
+    Ok(())
+}
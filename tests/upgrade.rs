@@ -0,0 +1,266 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use schema_gateway::router::build_router;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{oneshot, RwLock};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// Read an HTTP message head, up to and including the blank line
+async fn read_head(stream: &mut TcpStream) -> TestResult<String> {
+    let mut head = Vec::new();
+    while !head.ends_with(b"\r\n\r\n") {
+        let mut byte = [0u8; 1];
+        if stream.read(&mut byte).await? == 0 {
+            return Err("connection closed before the end of the head".into());
+        }
+        head.push(byte[0]);
+    }
+    Ok(String::from_utf8(head)?)
+}
+
+/// An upstream that accepts one `Upgrade: echo` handshake, greets the
+/// client, then echoes whatever it receives. The handshake's head is sent
+/// on the returned channel.
+async fn echo_upstream() -> TestResult<(SocketAddr, oneshot::Receiver<String>)> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let (head_tx, head_rx) = oneshot::channel();
+    tokio::spawn(async move {
+        let Ok((mut stream, _)) = listener.accept().await else {
+            return;
+        };
+        let Ok(head) = read_head(&mut stream).await else {
+            return;
+        };
+        let _ = head_tx.send(head);
+        let handshake =
+            "HTTP/1.1 101 Switching Protocols\r\nConnection: Upgrade\r\nUpgrade: echo\r\n\r\nhello";
+        if stream.write_all(handshake.as_bytes()).await.is_err() {
+            return;
+        }
+        let mut buf = [0u8; 1024];
+        while let Ok(n) = stream.read(&mut buf).await {
+            if n == 0 || stream.write_all(&buf[..n]).await.is_err() {
+                break;
+            }
+        }
+    });
+    Ok((addr, head_rx))
+}
+
+/// Gateway for `/ws` on `upstream`, rejecting invalid bodies, served on a
+/// local port
+async fn gateway(upstream: &str, allow_upgrade: bool) -> TestResult<SocketAddr> {
+    gateway_with(upstream, allow_upgrade, "").await
+}
+
+/// `gateway` with `global` added to its global settings
+async fn gateway_with(upstream: &str, allow_upgrade: bool, global: &str) -> TestResult<SocketAddr> {
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: false
+{}
+
+routes:
+  - path: /ws
+    method: GET
+    upstream: {}
+    allow_upgrade: {}
+"#,
+        global, upstream, allow_upgrade
+    ))?;
+    config.validate()?;
+    let global = config.global.clone();
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+    let app = build_router(state, &global);
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.expect("serve");
+    });
+    Ok(addr)
+}
+
+/// Open a connection to `addr` and send an upgrade handshake for `/ws`
+async fn handshake(addr: SocketAddr) -> TestResult<(TcpStream, String)> {
+    let mut stream = TcpStream::connect(addr).await?;
+    let request = format!(
+        "GET /ws?room=1 HTTP/1.1\r\nHost: {}\r\nConnection: Upgrade\r\nUpgrade: echo\r\nX-Client: tests\r\n\r\n",
+        addr
+    );
+    stream.write_all(request.as_bytes()).await?;
+    let head = read_head(&mut stream).await?;
+    Ok((stream, head))
+}
+
+#[tokio::test]
+async fn test_upgrade_is_tunnelled_both_ways() -> TestResult {
+    let (upstream, upstream_head) = echo_upstream().await?;
+    let addr = gateway(&format!("http://{}", upstream), true).await?;
+
+    let (mut stream, head) = handshake(addr).await?;
+    assert!(head.starts_with("HTTP/1.1 101"), "{}", head);
+    assert!(
+        head.to_ascii_lowercase().contains("upgrade: echo"),
+        "{}",
+        head
+    );
+
+    // The upstream saw the handshake, upgrade headers included
+    let upstream_head = upstream_head.await?.to_ascii_lowercase();
+    assert!(
+        upstream_head.starts_with("get /ws?room=1 http/1.1"),
+        "{}",
+        upstream_head
+    );
+    assert!(
+        upstream_head.contains("connection: upgrade"),
+        "{}",
+        upstream_head
+    );
+    assert!(upstream_head.contains("upgrade: echo"), "{}", upstream_head);
+    assert!(
+        upstream_head.contains("x-client: tests"),
+        "{}",
+        upstream_head
+    );
+
+    // Upstream to client
+    let mut greeting = [0u8; 5];
+    stream.read_exact(&mut greeting).await?;
+    assert_eq!(&greeting, b"hello");
+
+    // Client to upstream and back
+    for message in [&b"ping"[..], &b"a longer second message"[..]] {
+        stream.write_all(message).await?;
+        let mut echoed = vec![0u8; message.len()];
+        stream.read_exact(&mut echoed).await?;
+        assert_eq!(echoed, message);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upgrade_refused_without_allow_upgrade() -> TestResult {
+    let (upstream, _) = echo_upstream().await?;
+    let addr = gateway(&format!("http://{}", upstream), false).await?;
+
+    let (_stream, head) = handshake(addr).await?;
+    assert!(head.starts_with("HTTP/1.1 501"), "{}", head);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upstream_refusing_the_upgrade_is_passed_on() -> TestResult {
+    // An ordinary HTTP server answers the handshake like any request
+    let upstream = wiremock::MockServer::start().await;
+    wiremock::Mock::given(wiremock::matchers::any())
+        .respond_with(wiremock::ResponseTemplate::new(426).set_body_string("upgrade elsewhere"))
+        .mount(&upstream)
+        .await;
+    let addr = gateway(&upstream.uri(), true).await?;
+
+    let (_stream, head) = handshake(addr).await?;
+    assert!(head.starts_with("HTTP/1.1 426"), "{}", head);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upgrade_connect_honours_connect_timeout() -> TestResult {
+    // A listener whose backlog is full: further connection attempts stall
+    let socket = tokio::net::TcpSocket::new_v4()?;
+    socket.bind("127.0.0.1:0".parse()?)?;
+    let upstream = socket.listen(0)?;
+    let upstream_addr = upstream.local_addr()?;
+    let _queued = TcpStream::connect(upstream_addr).await?;
+
+    let addr = gateway_with(
+        &format!("http://{}", upstream_addr),
+        true,
+        "  http_client:\n    connect_timeout_ms: 200",
+    )
+    .await?;
+    let (_stream, head) =
+        tokio::time::timeout(std::time::Duration::from_secs(5), handshake(addr)).await??;
+    assert!(head.starts_with("HTTP/1.1 504"), "{}", head);
+    drop(upstream);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upgrade_without_a_connection_to_take_over() -> TestResult {
+    let config = Config::from_str(
+        "routes:\n  - path: /ws\n    method: GET\n    upstream: http://127.0.0.1:9\n    allow_upgrade: true\n  - path: /plain\n    method: GET\n    upstream: http://127.0.0.1:9\n",
+    )?;
+    config.validate()?;
+    let http_client = build_http_client(&config.global.http_client);
+    let state = Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    )));
+
+    for (path, message) in [
+        ("/ws", "Upgrade requests need an HTTP/1.1 connection"),
+        ("/plain", "Upgrade requests are not enabled for this route"),
+    ] {
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(path)
+            .header("connection", "keep-alive, Upgrade")
+            .header("upgrade", "websocket")
+            .body(Body::empty())?;
+        let (parts, body) = request.into_parts();
+        let response = handle_request(
+            State(state.clone()),
+            parts.method,
+            parts.uri,
+            parts.headers,
+            body,
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::NOT_IMPLEMENTED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+        let body: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(body["error"], message);
+    }
+
+    let metrics = state.read().await.metrics.gather()?;
+    assert!(
+        metrics.contains(
+            r#"http_requests_total{method="GET",route="/plain",route_tags="",status="501"} 1"#
+        ),
+        "{}",
+        metrics
+    );
+    Ok(())
+}
+
+#[test]
+fn test_allow_upgrade_needs_a_cleartext_upstream() -> TestResult {
+    let config = Config::from_str(
+        "routes:\n  - path: /ws\n    method: GET\n    upstream: https://backend:3000\n    allow_upgrade: true\n",
+    )?;
+    let error = config.validate().expect_err("https upstream");
+    assert!(
+        error.contains("'allow_upgrade' requires an http:// upstream"),
+        "{}",
+        error
+    );
+    Ok(())
+}
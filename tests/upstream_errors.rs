@@ -0,0 +1,194 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{HeaderMap, Method, Request, StatusCode};
+use axum::response::{IntoResponse, Response};
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use schema_gateway::proxy::{try_forward_request, ForwardOptions, UpstreamErrorKind};
+use serde_json::Value;
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// Log output collected in memory
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().expect("log buffer").extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// `GET /api/items` on `upstream`, with `global` settings added
+fn gateway(upstream: &str, global: &str) -> TestResult<Arc<RwLock<AppState>>> {
+    let config = Config::from_str(&format!(
+        "global:\n  forward_on_error: false\n{}\nroutes:\n  - path: /api/items\n    method: GET\n    upstream: {}\n",
+        global, upstream
+    ))?;
+    config.validate()?;
+    let http_client = build_http_client(&config.global.http_client);
+    Ok(Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    ))))
+}
+
+async fn send(state: &Arc<RwLock<AppState>>) -> TestResult<Response> {
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri("/api/items")
+        .body(Body::empty())?;
+    let (parts, body) = request.into_parts();
+    Ok(handle_request(
+        State(state.clone()),
+        parts.method,
+        parts.uri,
+        parts.headers,
+        body,
+    )
+    .await)
+}
+
+async fn error_body(response: Response) -> TestResult<Value> {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+/// An address nothing listens on
+async fn closed_port() -> TestResult<String> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    drop(listener);
+    Ok(format!("http://{}", addr))
+}
+
+#[tokio::test]
+async fn test_refused_connection_is_reported_and_logged() -> TestResult {
+    let upstream = closed_port().await?;
+    let state = gateway(&upstream, "")?;
+    let captured = Captured::default();
+    let writer = captured.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::ERROR)
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let response = send(&state).await?;
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    assert_eq!(
+        error_body(response).await?,
+        serde_json::json!({
+            "error": "Failed to connect to upstream",
+            "kind": "connect",
+            "upstream": "127.0.0.1",
+        })
+    );
+
+    let logs = String::from_utf8_lossy(&captured.0.lock().expect("log buffer")).into_owned();
+    assert!(logs.contains("ERROR"), "{}", logs);
+    assert!(
+        logs.contains(&format!("upstream_url={}/api/items", upstream)),
+        "{}",
+        logs
+    );
+    assert!(logs.contains("error trying to connect"), "{}", logs);
+
+    let metrics = state.read().await.metrics.gather()?;
+    assert!(
+        metrics.contains(r#"upstream_errors_total{error_type="connect"} 1"#),
+        "{}",
+        metrics
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_failure_kinds() -> TestResult {
+    let response = send(&gateway("http://upstream.invalid", "")?).await?;
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    let body = error_body(response).await?;
+    assert_eq!(body["kind"], "dns");
+    assert_eq!(body["upstream"], "upstream.invalid");
+
+    // A plain HTTP server can't complete a TLS handshake
+    let mock_server = MockServer::start().await;
+    let upstream = mock_server.uri().replace("http://", "https://");
+    let state = gateway(&upstream, "")?;
+    let response = send(&state).await?;
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    assert_eq!(error_body(response).await?["kind"], "tls");
+    let metrics = state.read().await.metrics.gather()?;
+    assert!(
+        metrics.contains(r#"upstream_errors_total{error_type="tls"} 1"#),
+        "{}",
+        metrics
+    );
+
+    // Routes with `upstream_tls` use rustls rather than the default client
+    let state = gateway(
+        &upstream,
+        "  upstream_tls:\n    verify: false\n    insecure: true",
+    )?;
+    let response = send(&state).await?;
+    assert_eq!(error_body(response).await?["kind"], "tls");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_timeout_is_a_504() -> TestResult {
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::any())
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+        .mount(&mock_server)
+        .await;
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(50))
+        .build()?;
+
+    let failure = try_forward_request(
+        &client,
+        Method::GET,
+        &mock_server.uri(),
+        "/slow",
+        HeaderMap::new(),
+        Vec::new(),
+        &ForwardOptions::default(),
+    )
+    .await
+    .expect_err("timed out");
+    assert_eq!(failure.kind, UpstreamErrorKind::Timeout);
+    assert_eq!(failure.url, format!("{}/slow", mock_server.uri()));
+
+    let response = failure.into_response();
+    assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+    assert_eq!(error_body(response).await?["kind"], "timeout");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upstream_host_can_be_redacted() -> TestResult {
+    let state = gateway(&closed_port().await?, "  redact_upstream_in_errors: true")?;
+    let response = send(&state).await?;
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    assert_eq!(
+        error_body(response).await?,
+        serde_json::json!({"error": "Failed to connect to upstream", "kind": "connect"})
+    );
+    Ok(())
+}
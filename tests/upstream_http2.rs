@@ -0,0 +1,104 @@
+use axum::body::Body;
+use axum::extract::{Request, State};
+use axum::http::{Method, StatusCode};
+use axum::routing::get;
+use schema_gateway::config::{Config, HttpClientConfig};
+use schema_gateway::handler::{build_http_client, handle_request, AppState, UpstreamClientKey};
+use schema_gateway::metrics::Metrics;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// Start a cleartext upstream that answers with the HTTP version each
+/// request arrived over
+fn start_version_upstream() -> TestResult<String> {
+    let app = axum::Router::new().route(
+        "/api/version",
+        get(|request: Request| async move { format!("{:?}", request.version()) }),
+    );
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let upstream = format!("http://{}", listener.local_addr()?);
+    tokio::spawn(axum_server::from_tcp(listener).serve(app.into_make_service()));
+    Ok(upstream)
+}
+
+fn gateway(upstream: &str, upstream_http2: bool) -> TestResult<Arc<RwLock<AppState>>> {
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  upstream_http2: {}
+
+routes:
+  - path: /api/version
+    method: GET
+    upstream: {}
+"#,
+        upstream_http2, upstream
+    ))?;
+    config.validate()?;
+    let http_client = build_http_client(&config.global.http_client);
+    Ok(Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    ))))
+}
+
+/// The HTTP version the upstream saw for a request through `state`
+async fn upstream_version(state: &Arc<RwLock<AppState>>) -> TestResult<String> {
+    let response = handle_request(
+        State(state.clone()),
+        Method::GET,
+        "/api/version".parse()?,
+        Default::default(),
+        Body::empty(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    Ok(String::from_utf8(body.to_vec())?)
+}
+
+#[tokio::test]
+async fn test_h2c_client_uses_http2_prior_knowledge() -> TestResult {
+    let upstream = start_version_upstream()?;
+    let url = format!("{}/api/version", upstream);
+
+    let defaults = HttpClientConfig::default();
+    let h2c = UpstreamClientKey {
+        h2c: true,
+        ..Default::default()
+    };
+    let version = h2c.build(&defaults)?.get(&url).send().await?.version();
+    assert_eq!(version, reqwest::Version::HTTP_2);
+    let version = build_http_client(&defaults)
+        .get(&url)
+        .send()
+        .await?
+        .version();
+    assert_eq!(version, reqwest::Version::HTTP_11);
+
+    // `http_client.http2_prior_knowledge` makes the default client do the same
+    let http2 = HttpClientConfig {
+        http2_prior_knowledge: true,
+        ..Default::default()
+    };
+    let version = build_http_client(&http2).get(&url).send().await?.version();
+    assert_eq!(version, reqwest::Version::HTTP_2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upstream_http2_speaks_h2c_to_cleartext_upstreams() -> TestResult {
+    let upstream = start_version_upstream()?;
+
+    let state = gateway(&upstream, true)?;
+    assert_eq!(upstream_version(&state).await?, "HTTP/2.0");
+
+    // Off by default
+    let state = gateway(&upstream, false)?;
+    assert_eq!(upstream_version(&state).await?, "HTTP/1.1");
+    Ok(())
+}
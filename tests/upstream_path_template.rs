@@ -0,0 +1,116 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// A route on `upstream` with the given `path` (or `path_regex`) line and
+/// `upstream_path_template`
+fn gateway(upstream: &MockServer, path: &str, template: &str) -> TestResult<Arc<RwLock<AppState>>> {
+    let config = Config::from_str(&format!(
+        "routes:\n  - {}\n    method: GET\n    upstream: {}\n    upstream_path_template: {}\n",
+        path,
+        upstream.uri(),
+        template
+    ))?;
+    config.validate()?;
+    let http_client = build_http_client(&config.global.http_client);
+    Ok(Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    ))))
+}
+
+async fn get(state: &Arc<RwLock<AppState>>, uri: &str) -> TestResult<StatusCode> {
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(uri)
+        .body(Body::empty())?;
+    let (parts, body) = request.into_parts();
+    let response = handle_request(
+        State(state.clone()),
+        parts.method,
+        parts.uri,
+        parts.headers,
+        body,
+    )
+    .await;
+    Ok(response.status())
+}
+
+async fn upstream_expecting(path: &str) -> MockServer {
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::path(path))
+        .and(matchers::query_param("page", "2"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    mock_server
+}
+
+#[tokio::test]
+async fn test_path_parameters_fill_the_template() -> TestResult {
+    let upstream = upstream_expecting("/v2/accounts/42/purchases").await;
+    let state = gateway(
+        &upstream,
+        "path: /users/:id/orders",
+        "/v2/accounts/{id}/purchases",
+    )?;
+    assert_eq!(
+        get(&state, "/users/42/orders?page=2").await?,
+        StatusCode::OK
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_regex_captures_fill_the_template() -> TestResult {
+    let upstream = upstream_expecting("/v2/accounts/7/purchases/99").await;
+    let state = gateway(
+        &upstream,
+        r"path_regex: '^/users/(?P<id>\d+)/orders/(?P<order>\d+)$'",
+        "/v2/accounts/{id}/purchases/{order}",
+    )?;
+    assert_eq!(
+        get(&state, "/users/7/orders/99?page=2").await?,
+        StatusCode::OK
+    );
+    Ok(())
+}
+
+#[test]
+fn test_template_parameters_must_exist() -> TestResult {
+    let validate = |path: &str, template: &str| -> TestResult<Result<(), String>> {
+        let config = Config::from_str(&format!(
+            "routes:\n  - {}\n    method: GET\n    upstream: http://backend:3000\n    upstream_path_template: '{}'\n",
+            path, template
+        ))?;
+        Ok(config.validate())
+    };
+
+    validate("path: /users/:id/orders", "/accounts/{id}")??;
+    let error = validate("path: /users/:id/orders", "/accounts/{user_id}")?.unwrap_err();
+    assert!(
+        error.contains("uses '{user_id}', but '/users/:id/orders' has no ':user_id' parameter"),
+        "{}",
+        error
+    );
+
+    let error = validate("path: /users/:id", "/accounts/{id")?.unwrap_err();
+    assert!(error.contains("unmatched brace"), "{}", error);
+    let error = validate("path: /users/:id", "accounts/{id}")?.unwrap_err();
+    assert!(error.contains("must start with '/'"), "{}", error);
+
+    let error = validate(r"path_regex: '^/users/(?P<id>\d+)$'", "/accounts/{name}")?.unwrap_err();
+    assert!(error.contains("'{name}'"), "{}", error);
+    Ok(())
+}
@@ -0,0 +1,243 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use schema_gateway::config::{Config, RetryConfig, RetryOn};
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn write_schema() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("item.json");
+    fs::write(&path, r#"{"type": "object"}"#).expect("write schema");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// Gateway state for `upstream` with a validated `/api/items` route (GET
+/// and POST) and a passthrough `/api/upload`, all with `retry`
+fn gateway(upstream: &str, retry: &str) -> TestResult<Arc<RwLock<AppState>>> {
+    let schema = write_schema();
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  retry:
+{retry}
+
+routes:
+  - path: /api/items
+    method: GET
+    upstream: {upstream}
+  - path: /api/items
+    method: POST
+    schema: {schema}
+    upstream: {upstream}
+  - path: /api/upload
+    method: POST
+    upstream: {upstream}
+"#,
+        retry = retry,
+        upstream = upstream,
+        schema = schema.display()
+    ))?;
+    config.validate()?;
+    let http_client = build_http_client(&config.global.http_client);
+    Ok(Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    ))))
+}
+
+async fn send(
+    state: &Arc<RwLock<AppState>>,
+    method: Method,
+    uri: &str,
+    body: &str,
+) -> TestResult<StatusCode> {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))?;
+    let (parts, body) = request.into_parts();
+    let response = handle_request(
+        State(state.clone()),
+        parts.method,
+        parts.uri,
+        parts.headers,
+        body,
+    )
+    .await;
+    Ok(response.status())
+}
+
+/// An upstream answering 503 `failures` times, then 200
+async fn flaky_upstream(failures: u64) -> MockServer {
+    let mock_server = MockServer::start().await;
+    Mock::given(matchers::any())
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(failures)
+        .mount(&mock_server)
+        .await;
+    Mock::given(matchers::any())
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&mock_server)
+        .await;
+    mock_server
+}
+
+async fn received(mock_server: &MockServer) -> usize {
+    mock_server
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .len()
+}
+
+const RETRY_5XX: &str = "    max_attempts: 3\n    backoff_ms: 1\n    retry_on: [5xx]";
+
+#[tokio::test]
+async fn test_server_errors_are_retried_until_success() -> TestResult {
+    let upstream = flaky_upstream(2).await;
+    let state = gateway(&upstream.uri(), RETRY_5XX)?;
+
+    assert_eq!(
+        send(&state, Method::GET, "/api/items", "").await?,
+        StatusCode::OK
+    );
+    assert_eq!(received(&upstream).await, 3);
+
+    let metrics = state.read().await.metrics.gather()?;
+    assert!(
+        metrics
+            .contains(r#"upstream_retries_total{reason="5xx",route="/api/items",route_tags=""} 2"#),
+        "{}",
+        metrics
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_last_attempt_response_is_returned() -> TestResult {
+    let upstream = flaky_upstream(5).await;
+    let state = gateway(&upstream.uri(), RETRY_5XX)?;
+
+    assert_eq!(
+        send(&state, Method::GET, "/api/items", "").await?,
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+    assert_eq!(received(&upstream).await, 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_post_is_only_retried_when_opted_in() -> TestResult {
+    let upstream = flaky_upstream(1).await;
+    let state = gateway(&upstream.uri(), RETRY_5XX)?;
+    assert_eq!(
+        send(&state, Method::POST, "/api/items", "{}").await?,
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+    assert_eq!(received(&upstream).await, 1);
+
+    let upstream = flaky_upstream(1).await;
+    let state = gateway(
+        &upstream.uri(),
+        &format!("{}\n    retry_non_idempotent: true", RETRY_5XX),
+    )?;
+    assert_eq!(
+        send(&state, Method::POST, "/api/items", r#"{"id": 1}"#).await?,
+        StatusCode::OK
+    );
+    let requests = upstream.received_requests().await.unwrap_or_default();
+    assert_eq!(requests.len(), 2);
+    // The buffered body is sent again as it was
+    assert!(requests.iter().all(|r| r.body == br#"{"id": 1}"#));
+
+    // Streamed passthrough bodies can't be replayed
+    let upstream = flaky_upstream(1).await;
+    let state = gateway(
+        &upstream.uri(),
+        &format!("{}\n    retry_non_idempotent: true", RETRY_5XX),
+    )?;
+    assert_eq!(
+        send(&state, Method::POST, "/api/upload", "data").await?,
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+    assert_eq!(received(&upstream).await, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_connect_failures_are_retried_by_default() -> TestResult {
+    // A port nothing listens on
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let upstream = format!("http://{}", listener.local_addr()?);
+    drop(listener);
+
+    let state = gateway(&upstream, "    max_attempts: 2\n    backoff_ms: 1")?;
+    assert_eq!(
+        send(&state, Method::GET, "/api/items", "").await?,
+        StatusCode::BAD_GATEWAY
+    );
+    let metrics = state.read().await.metrics.gather()?;
+    assert!(
+        metrics.contains(
+            r#"upstream_retries_total{reason="connect",route="/api/items",route_tags=""} 1"#
+        ),
+        "{}",
+        metrics
+    );
+
+    // 5xx isn't retried unless listed
+    let upstream = flaky_upstream(1).await;
+    let state = gateway(&upstream.uri(), "    max_attempts: 2\n    backoff_ms: 1")?;
+    assert_eq!(
+        send(&state, Method::GET, "/api/items", "").await?,
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+    Ok(())
+}
+
+#[test]
+fn test_retry_backoff_doubles() {
+    let retry = RetryConfig {
+        max_attempts: 4,
+        backoff_ms: 50,
+        retry_on: vec![RetryOn::Connect],
+        retry_non_idempotent: false,
+    };
+    assert_eq!(retry.backoff(1), Duration::from_millis(50));
+    assert_eq!(retry.backoff(2), Duration::from_millis(100));
+    assert_eq!(retry.backoff(3), Duration::from_millis(200));
+    assert!(retry.allows(&Method::PUT));
+    assert!(!retry.allows(&Method::POST));
+}
+
+#[test]
+fn test_retry_config_validation() -> TestResult {
+    let config = Config::from_str(
+        "routes:\n  - path: /api\n    method: GET\n    upstream: http://backend:3000\n    config:\n      retry: { max_attempts: 0 }\n",
+    )?;
+    let error = config.validate().expect_err("zero attempts");
+    assert!(
+        error.contains("retry.max_attempts must be greater than 0"),
+        "{}",
+        error
+    );
+
+    let unknown = Config::from_str(
+        "routes:\n  - path: /api\n    method: GET\n    upstream: http://backend:3000\n    config:\n      retry: { max_attempts: 2, retry_on: [4xx] }\n",
+    );
+    assert!(unknown.is_err());
+    Ok(())
+}
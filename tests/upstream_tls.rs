@@ -0,0 +1,305 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use axum::routing::get;
+use axum_server::tls_rustls::RustlsConfig;
+use rcgen::{BasicConstraints, CertificateParams, CertifiedIssuer, IsCa, KeyPair};
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState, UpstreamClients};
+use schema_gateway::metrics::Metrics;
+use schema_gateway::tls::load_server_config;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// A CA plus server and client certificates it signed, written to a temp dir
+struct TestPki {
+    dir: PathBuf,
+    server_der: Vec<u8>,
+}
+
+impl TestPki {
+    fn generate() -> TestResult<Self> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().to_path_buf();
+        let _ = Box::leak(Box::new(dir));
+
+        let mut ca_params = CertificateParams::new(Vec::<String>::new())?;
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = CertifiedIssuer::self_signed(ca_params, KeyPair::generate()?)?;
+        fs::write(path.join("ca.pem"), ca.pem())?;
+
+        let server_key = KeyPair::generate()?;
+        let server =
+            CertificateParams::new(vec!["localhost".to_string()])?.signed_by(&server_key, &ca)?;
+        fs::write(path.join("server.pem"), server.pem())?;
+        fs::write(path.join("server-key.pem"), server_key.serialize_pem())?;
+
+        let client_key = KeyPair::generate()?;
+        let client =
+            CertificateParams::new(vec!["gateway".to_string()])?.signed_by(&client_key, &ca)?;
+        fs::write(path.join("client.pem"), client.pem())?;
+        fs::write(path.join("client-key.pem"), client_key.serialize_pem())?;
+
+        Ok(Self {
+            dir: path,
+            server_der: server.der().to_vec(),
+        })
+    }
+
+    fn file(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+}
+
+/// Start an HTTPS upstream that requires client certificates signed by the test CA
+async fn start_mtls_upstream(pki: &TestPki) -> TestResult<u16> {
+    let tls_config = load_server_config(
+        &pki.file("server.pem"),
+        &pki.file("server-key.pem"),
+        Some(&pki.file("ca.pem")),
+    )?;
+    let app = axum::Router::new().route("/api/users", get(|| async { "hello over mtls" }));
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let port = listener.local_addr()?.port();
+    tokio::spawn(
+        axum_server::from_tcp_rustls(listener, RustlsConfig::from_config(Arc::new(tls_config)))
+            .serve(app.into_make_service()),
+    );
+    Ok(port)
+}
+
+fn build_state(port: u16, upstream_tls: &str) -> TestResult<Arc<RwLock<AppState>>> {
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: false
+
+routes:
+  - path: /api/users
+    method: GET
+    upstream: https://localhost:{}
+    config:
+      upstream_tls:
+{}
+"#,
+        port, upstream_tls
+    ))?;
+    config.validate()?;
+    let http_client = build_http_client(&config.global.http_client);
+    Ok(Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    ))))
+}
+
+async fn get_users(state: Arc<RwLock<AppState>>) -> TestResult<(StatusCode, String)> {
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri("/api/users")
+        .body(Body::empty())?;
+    let (parts, body) = request.into_parts();
+    let response = handle_request(State(state), parts.method, parts.uri, parts.headers, body).await;
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    Ok((status, String::from_utf8(body.to_vec())?))
+}
+
+fn tls_block(lines: &[(&str, &Path)]) -> String {
+    lines
+        .iter()
+        .map(|(key, path)| format!("        {}: {}", key, path.display()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[tokio::test]
+async fn test_upstream_mtls_handshake() -> TestResult {
+    let pki = TestPki::generate()?;
+    let port = start_mtls_upstream(&pki).await?;
+
+    let state = build_state(
+        port,
+        &tls_block(&[
+            ("ca_bundle", &pki.file("ca.pem")),
+            ("client_cert", &pki.file("client.pem")),
+            ("client_key", &pki.file("client-key.pem")),
+        ]),
+    )?;
+
+    let (status, body) = get_users(state).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, "hello over mtls");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upstream_mtls_rejected_without_client_cert() -> TestResult {
+    let pki = TestPki::generate()?;
+    let port = start_mtls_upstream(&pki).await?;
+
+    let state = build_state(port, &tls_block(&[("ca_bundle", &pki.file("ca.pem"))]))?;
+
+    let (status, _) = get_users(state).await?;
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upstream_cert_pinning() -> TestResult {
+    let pki = TestPki::generate()?;
+    let port = start_mtls_upstream(&pki).await?;
+    let client_files = [
+        ("ca_bundle", pki.file("ca.pem")),
+        ("client_cert", pki.file("client.pem")),
+        ("client_key", pki.file("client-key.pem")),
+    ];
+    let files: Vec<(&str, &Path)> = client_files
+        .iter()
+        .map(|(key, path)| (*key, path.as_path()))
+        .collect();
+
+    let pin = hex::encode(Sha256::digest(&pki.server_der));
+    let pinned = format!("{}\n        pin_cert_hash: \"{}\"", tls_block(&files), pin);
+    let (status, _) = get_users(build_state(port, &pinned)?).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let wrong_pin = format!(
+        "{}\n        pin_cert_hash: \"{}\"",
+        tls_block(&files),
+        "00".repeat(32)
+    );
+    let (status, _) = get_users(build_state(port, &wrong_pin)?).await?;
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+    Ok(())
+}
+
+#[test]
+fn test_upstream_tls_validation() -> TestResult {
+    let parse = |tls: &str| -> TestResult<Config> {
+        Ok(Config::from_str(&format!(
+            r#"
+global:
+  upstream_tls:
+{}
+routes:
+  - path: /api/users
+    method: GET
+    upstream: https://backend:3000
+"#,
+            tls
+        ))?)
+    };
+
+    let err = parse("    verify: false")?.validate().unwrap_err();
+    assert!(err.contains("insecure: true"), "unexpected error: {}", err);
+    assert!(parse("    verify: false\n    insecure: true")?
+        .validate()
+        .is_ok());
+
+    // danger_accept_invalid_certs stands for verify: false, insecure: true
+    let config = parse("    danger_accept_invalid_certs: true")?;
+    config.validate()?;
+    let tls = config.global.upstream_tls.as_ref().expect("upstream_tls");
+    assert!(!tls.verify && tls.insecure);
+    let config = parse("    danger_accept_invalid_certs: false")?;
+    let tls = config.global.upstream_tls.as_ref().expect("upstream_tls");
+    assert!(tls.verify && !tls.insecure);
+    let err = parse("    danger_accept_invalid_certs: true\n    verify: true")
+        .expect_err("contradicts verify")
+        .to_string();
+    assert!(
+        err.contains("danger_accept_invalid_certs"),
+        "unexpected error: {}",
+        err
+    );
+
+    let err = parse("    pin_cert_hash: not-hex")?.validate().unwrap_err();
+    assert!(err.contains("pin_cert_hash"), "unexpected error: {}", err);
+    let colon_pin = vec!["AB"; 32].join(":");
+    assert!(parse(&format!("    pin_cert_hash: \"{}\"", colon_pin))?
+        .validate()
+        .is_ok());
+
+    let pki = TestPki::generate()?;
+    let err = parse(&format!(
+        "    client_cert: {}",
+        pki.file("client.pem").display()
+    ))?
+    .validate()
+    .unwrap_err();
+    assert!(err.contains("client_key"), "unexpected error: {}", err);
+
+    let err = parse("    ca_bundle: /definitely/does/not/exist.pem")?
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("does not exist"), "unexpected error: {}", err);
+
+    // Files must parse as PEM, not just exist
+    let not_pem = pki.file("not-pem.txt");
+    fs::write(&not_pem, "garbage\n")?;
+    let err = parse(&format!("    ca_cert: {}", not_pem.display()))?
+        .validate()
+        .unwrap_err();
+    assert!(
+        err.contains("No certificates found"),
+        "unexpected error: {}",
+        err
+    );
+    let err = parse(&format!(
+        "    client_cert: {}\n    client_key: {}",
+        pki.file("client.pem").display(),
+        pki.file("client.pem").display()
+    ))?
+    .validate()
+    .unwrap_err();
+    assert!(
+        err.contains("No private key found"),
+        "unexpected error: {}",
+        err
+    );
+    assert!(parse(&format!(
+        "    ca_cert: {}\n    client_cert: {}\n    client_key: {}",
+        pki.file("ca.pem").display(),
+        pki.file("client.pem").display(),
+        pki.file("client-key.pem").display()
+    ))?
+    .validate()
+    .is_ok());
+    Ok(())
+}
+
+#[test]
+fn test_upstream_clients_report_unusable_certificates() -> TestResult {
+    let pki = TestPki::generate()?;
+    let not_pem = pki.file("not-a-cert.pem");
+    fs::write(&not_pem, "garbage\n")?;
+
+    let config = Config::from_str(&format!(
+        r#"
+routes:
+  - path: /api/users
+    method: GET
+    upstream: https://backend:3000
+    config:
+      upstream_tls:
+        ca_bundle: {}
+"#,
+        not_pem.display()
+    ))?;
+    let clients = UpstreamClients::from_config(&config);
+    let err = clients.first_error().expect("expected a client error");
+    assert!(
+        err.contains("No certificates found"),
+        "unexpected error: {}",
+        err
+    );
+    Ok(())
+}
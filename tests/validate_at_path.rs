@@ -0,0 +1,69 @@
+use schema_gateway::validation::validate_value_at_path;
+use serde_json::{json, Value};
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn account_schema() -> Value {
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "type": "object",
+        "required": ["id", "user"],
+        "properties": {
+            "id": { "type": "integer" },
+            "user": { "$ref": "#/definitions/user" },
+            "display name": { "type": "string", "maxLength": 5 }
+        },
+        "definitions": {
+            "user": {
+                "type": "object",
+                "required": ["email"],
+                "properties": {
+                    "email": { "type": "string", "format": "email" },
+                    "age": { "type": "integer", "minimum": 0 }
+                }
+            }
+        }
+    })
+}
+
+#[test]
+fn test_only_the_sub_schema_applies() -> TestResult {
+    let schema = account_schema();
+
+    // The document's own required fields don't apply to the sub-value
+    let result = validate_value_at_path(
+        &schema,
+        &json!({"email": "a@example.com"}),
+        "/properties/user",
+    )?;
+    assert!(result.valid, "{:?}", result.messages());
+
+    let result = validate_value_at_path(&schema, &json!({"age": -1}), "/properties/user")?;
+    assert!(!result.valid);
+    assert_eq!(result.error_count, 2, "{:?}", result.messages());
+
+    let result = validate_value_at_path(&schema, &json!(7), "/properties/id")?;
+    assert!(result.valid);
+    let result = validate_value_at_path(&schema, &json!("7"), "/properties/id")?;
+    assert!(!result.valid);
+    Ok(())
+}
+
+#[test]
+fn test_paths_are_json_pointers() -> TestResult {
+    let schema = account_schema();
+    let result =
+        validate_value_at_path(&schema, &json!("far too long"), "/properties/display name")?;
+    assert!(!result.valid);
+
+    let result = validate_value_at_path(&schema, &json!({"email": 1}), "/definitions/user")?;
+    assert_eq!(result.errors[0].instance_path, "/email");
+    Ok(())
+}
+
+#[test]
+fn test_unknown_path_is_an_error() {
+    let error = validate_value_at_path(&account_schema(), &json!({}), "/properties/missing")
+        .expect_err("no schema there");
+    assert_eq!(error, "No schema at '/properties/missing'");
+}
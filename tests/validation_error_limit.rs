@@ -0,0 +1,149 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request, StatusCode};
+use jsonschema::JSONSchema;
+use schema_gateway::config::Config;
+use schema_gateway::handler::{build_http_client, handle_request, AppState};
+use schema_gateway::metrics::Metrics;
+use schema_gateway::validation::{validate, validate_with_limit};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const SCHEMA: &str = r#"{"type": "array", "items": {"type": "integer"}}"#;
+
+fn write_schema() -> PathBuf {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("numbers.json");
+    fs::write(&path, SCHEMA).expect("write schema");
+    let _ = Box::leak(Box::new(dir));
+    path
+}
+
+/// `count` strings where the schema wants integers: one error each
+fn invalid_items(count: usize) -> Value {
+    Value::Array((0..count).map(|i| json!(format!("item-{}", i))).collect())
+}
+
+/// Reject-mode gateway validating `POST /api/numbers`, with `global` added
+/// to its global settings
+fn gateway(global: &str) -> TestResult<Arc<RwLock<AppState>>> {
+    let config = Config::from_str(&format!(
+        r#"
+global:
+  forward_on_error: false
+{}
+
+routes:
+  - path: /api/numbers
+    method: POST
+    schema: {}
+    upstream: http://127.0.0.1:9
+"#,
+        global,
+        write_schema().display()
+    ))?;
+    config.validate()?;
+    let http_client = build_http_client(&config.global.http_client);
+    Ok(Arc::new(RwLock::new(AppState::new(
+        config,
+        http_client,
+        Arc::new(Metrics::new()?),
+    ))))
+}
+
+async fn post(state: &Arc<RwLock<AppState>>, body: &Value) -> TestResult<(StatusCode, Value)> {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/numbers")
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))?;
+    let (parts, body) = request.into_parts();
+    let response = handle_request(
+        State(state.clone()),
+        parts.method,
+        parts.uri,
+        parts.headers,
+        body,
+    )
+    .await;
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    Ok((status, serde_json::from_slice(&body)?))
+}
+
+#[test]
+fn test_validate_with_limit_counts_what_it_drops() -> TestResult {
+    let schema_value: Value = serde_json::from_str(SCHEMA)?;
+    let schema = JSONSchema::compile(&schema_value).map_err(|e| e.to_string())?;
+    let document = invalid_items(50);
+
+    let result = validate_with_limit(&schema, &document, Some(5));
+    assert!(!result.valid);
+    assert_eq!(result.errors.len(), 5);
+    assert_eq!(result.error_count, 50);
+    let messages = result.messages();
+    assert_eq!(messages.len(), 6);
+    assert_eq!(messages[5], "… and 45 more errors");
+    assert_eq!(
+        result.details.last().map(String::as_str),
+        Some("… and 45 more errors")
+    );
+
+    // No limit, nothing dropped
+    let result = validate(&schema, &document);
+    assert_eq!(result.errors.len(), 50);
+    assert_eq!(result.error_count, 50);
+    assert_eq!(result.messages().len(), 50);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_error_body_is_capped_at_twenty_by_default() -> TestResult {
+    let state = gateway("")?;
+
+    let (status, body) = post(&state, &invalid_items(1000)).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error_count"], 1000);
+    let errors = body["errors"].as_array().ok_or("errors is not a list")?;
+    assert_eq!(errors.len(), 21);
+    assert_eq!(errors[20], "… and 980 more errors");
+    let error = body["error"].as_str().ok_or("error is not a string")?;
+    assert!(error.starts_with("Validation failed: "), "{}", error);
+    assert!(error.ends_with("… and 980 more errors"), "{}", error);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_error_limit_is_configurable() -> TestResult {
+    let state = gateway("  max_validation_errors: 2")?;
+
+    let (_, body) = post(&state, &invalid_items(3)).await?;
+    assert_eq!(body["error_count"], 3);
+    assert_eq!(body["errors"].as_array().map(Vec::len), Some(3));
+
+    // Under the limit, nothing is cut
+    let (_, body) = post(&state, &invalid_items(2)).await?;
+    assert_eq!(body["error_count"], 2);
+    assert_eq!(body["errors"].as_array().map(Vec::len), Some(2));
+    Ok(())
+}
+
+#[test]
+fn test_max_validation_errors_must_be_positive() -> TestResult {
+    let config = Config::from_str(
+        "global:\n  max_validation_errors: 0\nroutes:\n  - path: /api\n    method: GET\n    upstream: http://backend:3000\n",
+    )?;
+    let error = config.validate().expect_err("zero limit");
+    assert!(
+        error.contains("max_validation_errors must be greater than 0"),
+        "{}",
+        error
+    );
+    Ok(())
+}